@@ -0,0 +1,212 @@
+//! Разбор командной строки и запуск команд CLI `mft_shadow_forge` (src/main.rs - только вызов [`main`]).
+
+use clap::{CommandFactory, FromArgMatches};
+use tracing::error;
+
+use crate::cli::{Cli, Commands};
+use crate::error::exit_code;
+use crate::{commands, i18n, logging, models, Outcome, Result};
+
+fn tags(tag: &[(String, String)]) -> models::Tags {
+    tag.iter().cloned().collect()
+}
+
+pub fn main() -> ! {
+    // Ошибка командной строки - код INVALID, а не 2 clap: 2 занят частичным успехом
+    let matches = Cli::command().try_get_matches().unwrap_or_else(exit_on_usage);
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(exit_on_usage);
+    logging::init(logging::level(cli.verbose, cli.quiet), cli.log_json);
+    i18n::set_lang(cli.lang);
+
+    let command = matches.subcommand_name().unwrap_or_default();
+    let result = tracing::info_span!("run", command).in_scope(|| run(&cli));
+    let code = match result {
+        Ok(outcome) => outcome.exit_code(),
+        Err(e) => {
+            error!("{}", i18n::common::critical_error(&e));
+            e.exit_code()
+        }
+    };
+    std::process::exit(code);
+}
+
+/// `--help` и `--version` - успех, остальное - ошибка аргументов.
+fn exit_on_usage<T>(e: clap::Error) -> T {
+    let _ = e.print();
+    std::process::exit(if e.use_stderr() { exit_code::INVALID } else { exit_code::OK });
+}
+
+fn run(cli: &Cli) -> Result<Outcome> {
+    match &cli.command {
+        Commands::Extract { image, out, partition, list_partitions, shadow, bitlocker_key, listen, connect, cert, key, ca, io_chunk_size, threads, no_progress, tag, case } => {
+            let image = image.as_deref().unwrap_or_default();
+            let out = out.as_deref().unwrap_or_default();
+            // --listen/--connect требуют все три файла mTLS (проверяет clap)
+            let (cert, key, ca) = (cert.as_deref().unwrap_or_default(), key.as_deref().unwrap_or_default(), ca.as_deref().unwrap_or_default());
+            let opts = commands::extract::ExtractOptions {
+                partition: *partition,
+                io_chunk_size: *io_chunk_size,
+                threads: *threads as usize,
+                bitlocker_key: bitlocker_key.clone(),
+                progress: !*no_progress,
+                audit: true,
+                remote: connect.as_ref().map(|addr| commands::extract::RemoteEndpoint {
+                    addr: addr.clone(), cert: cert.to_string(), key: key.to_string(), ca: ca.to_string(),
+                }),
+            };
+            if let Some(listen) = listen {
+                commands::extract::run_listen(image, listen, cert, key, ca)?;
+            } else if *list_partitions {
+                commands::extract::run_list_partitions(image, &opts)?;
+            } else if let Some(select) = shadow {
+                commands::extract::run_shadows(image, out, *select, &case.to_case_info(), &tags(tag))?;
+            } else {
+                commands::extract::run(image, out, &opts, &case.to_case_info(), &tags(tag))?;
+            }
+        }
+        Commands::Info { image, mft, partition, out_json } => {
+            commands::info::run(image.as_deref(), mft.as_deref(), *partition, out_json.as_deref())?;
+        }
+        Commands::Carve { image, out, data } => {
+            commands::carve::run(image, out, *data)?;
+        }
+        Commands::Usnjrnl { image, out, partition } => {
+            commands::usnjrnl::run(image, out, *partition)?;
+        }
+        Commands::Bitmap { image, out, partition } => {
+            commands::bitmap::run(image, out, *partition)?;
+        }
+        Commands::I30 { image, out, partition, active } => {
+            commands::i30::run(image, out, *partition, *active)?;
+        }
+        Commands::Dirindex { image, out, partition, mismatched_only } => {
+            commands::dirindex::run(image, out, *partition, *mismatched_only)?;
+        }
+        Commands::Recover { image, entry, path, report, stream, out, partition } => {
+            commands::recover::run(image, *entry, path.as_deref(), report.as_deref(), stream.as_deref(), out, *partition)?;
+        }
+        Commands::Schema { out_json } => {
+            commands::schema::run(out_json.as_deref())?;
+        }
+        Commands::Whohas { image, mft, lcn, offset, out_json } => {
+            commands::whohas::run(image.as_deref(), mft, lcn, offset, out_json.as_deref())?;
+        }
+        Commands::Verify { path, out_json, record_size, sector_size, max_issues } => {
+            commands::verify::run(path, out_json.as_deref(), *record_size, *sector_size, *max_issues)?;
+        }
+        Commands::Bench { records, iterations, out_json } => {
+            commands::bench::run(*records, *iterations as usize, out_json.as_deref())?;
+        }
+        Commands::Parse { path, out_json, data, hash, hashset, skip_known, image, partition, max_data_size, profile, rules, sigma, exclude_rules, max_path_depth, max_path_length, normalize_paths, since, tag, case, plugin, runs, runs_out, record_slack, raw_timestamps, no_progress, low_memory, preserve_flagged, out_parquet, bodyfile, alerts, tln, hostname, user, es_url, es_index, splunk_hec_url, splunk_token, compress, format, i30_image, i30_partition, i30_entries, sds } => {
+            let opts = commands::parse::ParseOptions {
+                data: *data,
+                hash: *hash,
+                hashset: hashset.clone(),
+                skip_known: *skip_known,
+                profiles: profile.clone(),
+                rules: rules.clone(),
+                sigma: sigma.clone(),
+                exclude_rules: exclude_rules.clone(),
+                max_path_depth: *max_path_depth,
+                max_path_length: *max_path_length,
+                normalize_paths: *normalize_paths,
+                since: since.clone(),
+                case: case.to_case_info(),
+                tags: tags(tag),
+                plugins: plugin.clone(),
+                runs: *runs || runs_out.is_some(),
+                runs_out: runs_out.clone(),
+                record_slack: *record_slack,
+                raw_timestamps: *raw_timestamps,
+                progress: !*no_progress,
+                low_memory: *low_memory,
+                preserve_flagged: *preserve_flagged,
+                out_parquet: out_parquet.clone(),
+                bodyfile: bodyfile.clone(),
+                alerts: alerts.clone(),
+                tln: tln.clone(),
+                tln_host: hostname.clone().unwrap_or_default(),
+                tln_user: user.clone().unwrap_or_default(),
+                es_url: es_url.clone(),
+                es_index: es_index.clone(),
+                splunk_hec_url: splunk_hec_url.clone(),
+                splunk_token: splunk_token.clone().unwrap_or_default(),
+                compress: *compress,
+                format: *format,
+                i30_image: i30_image.clone(),
+                i30_partition: *i30_partition,
+                i30_entries: i30_entries.clone(),
+                sds: sds.clone(),
+                image: image.clone(),
+                partition: *partition,
+                max_data_size: *max_data_size,
+            };
+            return commands::parse::run(path, out_json.as_deref(), &opts).map(|summary| summary.outcome());
+        }
+        Commands::Play { image, out, data, stream, keep_mft, interval, keep, tag, case } => {
+            let opts = commands::play::PlayOptions { data: *data, stream: *stream, keep_mft: *keep_mft };
+            return commands::play::run(image, out, &opts, *interval, *keep, &case.to_case_info(), &tags(tag));
+        }
+        Commands::Activity { input, out, bucket, by_dir, format, html } => {
+            commands::activity::run(input, out, *bucket, *by_dir, *format, html.as_deref())?;
+        }
+        Commands::Diff { baseline, current, old, new, out, system_only, top } => {
+            match (old, new, baseline, current) {
+                (Some(old), Some(new), _, _) => commands::diff::run_raw(old, new, out, *system_only, *top),
+                (_, _, Some(baseline), Some(current)) => commands::diff::run(baseline, current, out, *system_only, *top),
+                _ => unreachable!("clap требует --baseline/--current или --old/--new"),
+            }?;
+        }
+        Commands::Merge { input, out, max_hosts, top } => {
+            commands::merge::run(input, out, *max_hosts, *top)?;
+        }
+        Commands::Stats { input, out_json, top } => {
+            commands::stats::run(input, out_json.as_deref(), *top)?;
+        }
+        Commands::Query { input, filter, path, after, before, time_field, out, format, compress, count, no_index } => {
+            let opts = commands::query::QueryOptions {
+                filter: filter.clone(),
+                path: path.clone(),
+                after: after.clone(),
+                before: before.clone(),
+                time_field: time_field.clone(),
+                format: *format,
+                compress: *compress,
+                count: *count,
+                no_index: *no_index,
+            };
+            commands::query::run(input, out, &opts)?;
+        }
+        #[cfg(feature = "agent")]
+        Commands::Agent { listen, cert, key, ca } => {
+            commands::agent::run(listen, cert, key, ca)?;
+        }
+        #[cfg(feature = "agent")]
+        Commands::Collect { agent, cert, key, ca, image, out, data, tag, case } => {
+            let task = commands::agent::Task {
+                image: image.clone(),
+                data: *data,
+                case: case.to_case_info(),
+                case_in_rows: case.case_in_rows,
+                tags: tags(tag),
+            };
+            return commands::collect::run(agent, cert, key, ca, out, &task);
+        }
+        Commands::Watch { image, out, data, interval, alerts_only, profile, rules, sigma, exclude_rules, tag, case } => {
+            let opts = commands::parse::ParseOptions {
+                data: *data,
+                profiles: profile.clone(),
+                rules: rules.clone(),
+                sigma: sigma.clone(),
+                exclude_rules: exclude_rules.clone(),
+                case: case.to_case_info(),
+                ..Default::default()
+            };
+            commands::watch::run(image, out, *interval, *alerts_only, &opts, &tags(tag))?;
+        }
+        Commands::Supertimeline { mft, usn, out, format } => {
+            commands::supertimeline::run(mft, usn, out, *format)?;
+        }
+    }
+    Ok(Outcome::Complete)
+}
\ No newline at end of file
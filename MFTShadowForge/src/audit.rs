@@ -0,0 +1,57 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+
+use serde::Serialize;
+use tracing::warn;
+
+use crate::i18n::common;
+use crate::models::{CaseInfo, Tags};
+
+/// Имя журнала действий в папке результатов.
+const AUDIT_FILE: &str = "audit.log";
+
+/// Одна строка журнала действий (JSONL): кто, когда и что получил из какого источника.
+#[derive(Debug, Serialize)]
+struct AuditRecord<'a> {
+    time: String,
+    action: &'a str,
+    source: &'a str,
+    output: &'a str,
+    host: String,
+    user: String,
+    tool_version: &'a str,
+    #[serde(flatten)]
+    case: &'a CaseInfo,
+    #[serde(skip_serializing_if = "Tags::is_empty")]
+    tags: &'a Tags,
+}
+
+/// Дописывает запись в `audit.log` рядом с результатом `output`.
+/// Ошибка записи журнала не прерывает сбор, только выводится предупреждение.
+pub fn append(action: &str, source: &str, output: &str, case: &CaseInfo, tags: &Tags) {
+    // Для вывода по URL (Elasticsearch) журнал ведется в текущей папке
+    let dir = Some(Path::new(output)).filter(|_| !output.contains("://"))
+        .and_then(Path::parent).filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let path = dir.join(AUDIT_FILE);
+
+    let record = AuditRecord {
+        time: chrono::Utc::now().to_rfc3339(),
+        action,
+        source,
+        output,
+        host: crate::commands::merge::default_host(),
+        user: std::env::var("USERNAME").or_else(|_| std::env::var("USER")).unwrap_or_default(),
+        tool_version: env!("CARGO_PKG_VERSION"),
+        case,
+        tags,
+    };
+
+    let written = OpenOptions::new().create(true).append(true).open(&path).and_then(|mut f| {
+        let line = serde_json::to_string(&record).map_err(std::io::Error::from)?;
+        writeln!(f, "{}", line)
+    });
+    if let Err(e) = written {
+        warn!("{}", common::audit_failed(path.display(), e));
+    }
+}
\ No newline at end of file
@@ -1,4 +1,16 @@
-use clap::{Parser, Subcommand};
+use std::time::Duration;
+
+use clap::{Args, Parser, Subcommand};
+
+use crate::commands::activity::{ActivityBucket, ActivityFormat};
+use crate::commands::extract::{PartitionSelect, ShadowSelect, DEFAULT_READ_THREADS};
+use crate::image::bitlocker::BitLockerKey;
+use crate::commands::supertimeline::TimelineFormat;
+use crate::i18n::Lang;
+use crate::mft::utils::NameNormalization;
+use crate::output::{Compression, ReportFormat};
+use crate::models::CaseInfo;
+use crate::rules::profiles::PROFILE_NAMES;
 
 const ASCII_LOGO: &str = r#"
                                 ___  _________ _____ _____ _               _              ______                   
@@ -21,6 +33,17 @@ const EXAMPLES: &str = r#"
      Или коротко:
      MFTShadowForge.exe extract -i C: -o C:\MftDump\mft.raw
 
+     Диск с несколькими томами: посмотреть разделы и снять $MFT со второго:
+     MFTShadowForge.exe extract -i disk.E01 --list-partitions
+     MFTShadowForge.exe extract -i disk.E01 -o C:\MftDump\mft.raw --partition 2
+
+     Удаленно по mTLS: на сервере раздать диск, у аналитика снять $MFT со второго раздела:
+     MFTShadowForge.exe extract -i \\.\PhysicalDrive0 --listen 0.0.0.0:7444 --cert agent.pem --key agent.key --ca ca.pem
+     MFTShadowForge.exe extract --connect srv01:7444 --cert ctl.pem --key ctl.key --ca ca.pem -o mft.raw --partition 2
+
+     Из всех теневых копий тома C: (mft.vss1.raw, mft.vss4.raw, ...):
+     MFTShadowForge.exe extract -i C: -o C:\MftDump\mft.raw --shadow all
+
   2. АНАЛИЗ (Parse)
      Распарсить дамп MFT в формат JSONL с извлечением бинарных данных ($DATA):
      MFTShadowForge.exe parse --path C:\MftDump\mft.raw --out-json C:\MftDump\report.jsonl --data
@@ -28,12 +51,134 @@ const EXAMPLES: &str = r#"
      Или коротко:
      MFTShadowForge.exe parse -p mft.raw -j report.jsonl -d
 
+     С хешами резидентного $DATA (поля Md5/Sha1/Sha256) для сверки с индикаторами:
+     MFTShadowForge.exe parse -p mft.raw -j report.jsonl --hash
+     С содержимым и небольших нерезидентных файлов (скрипты, конфиги) с исходного образа:
+     MFTShadowForge.exe parse -p mft.raw -j report.jsonl -d --image disk.E01 --partition 2 --max-data-size 262144
+
+     Вшитые наборы правил (default, persistence, webshell, ransomware, lolbin):
+     MFTShadowForge.exe parse -p mft.raw -j report.jsonl --profile persistence,webshell
+     С исключениями известного шума (совпавшие записи теряют FitsRules):
+     MFTShadowForge.exe parse -p mft.raw -j report.jsonl --profile lolbin --exclude-rules C:\Cases\allowlist.yaml
+
+     Свои правила для дела вместо встроенных (TOML или YAML; совпавшие - в RuleMatches):
+     MFTShadowForge.exe parse -p mft.raw -j report.jsonl --rules C:\Cases\case42.toml
+     Плюс Sigma-правила file_event из каталога:
+     MFTShadowForge.exe parse -p mft.raw -j report.jsonl --sigma C:\sigma\rules\windows\file
+     Отдельный короткий JSONL только со срабатываниями и аномалиями:
+     MFTShadowForge.exe parse -p mft.raw -j report.jsonl --profile persistence --alerts alerts.jsonl
+
+     Из pipe в pipe (ход разбора выводится в stderr):
+     cat mft.raw | MFTShadowForge parse -p - -j - | jq 'select(.Timestomped)'
+
   3. ПОЛНЫЙ ЦИКЛ (Play)
      Автоматически извлечь MFT с диска C: и сразу запустить анализ в указанную папку:
      MFTShadowForge.exe play --image C: --out C:\MftDump --data
      
      Или коротко:
      MFTShadowForge.exe play -i C: -o C:\MftDump -d
+
+     Без промежуточного дампа на диске (весь $MFT в памяти):
+     MFTShadowForge.exe play -i C: -o C:\MftDump --stream
+
+     Снимки каждые 6 часов, хранить 10 последних:
+     MFTShadowForge.exe play -i C: -o C:\MftDump --interval 6h --keep 10
+
+     Несколько томов сразу (подпапки C и D + общий отчет MERGED):
+     MFTShadowForge.exe play -i C: -i D: -o C:\MftDump
+
+  4. СТАТИСТИКА (Stats)
+     Обзор тома по готовому JSONL-отчету или прямо по дампу (детекты, расширения, крупнейшие и редкие файлы):
+     MFTShadowForge.exe stats --input C:\MftDump\report.jsonl --out-json C:\MftDump\stats.json
+     MFTShadowForge.exe stats --input C:\MftDump\mft.raw
+
+  5. ОТБОР СТРОК (Query)
+     Исполняемые файлы с подменой меток в профилях пользователей, созданные в марте 2024:
+     MFTShadowForge.exe query -i C:\MftDump\REPORT.gz -w "Extension = 'exe' and Timestomped" --path C:\Users\* --after 2024-03-01 --before 2024-04-01 -o C:\MftDump\hits.jsonl
+     Сколько удаленных файлов больше 100 МБ:
+     MFTShadowForge.exe query -i C:\MftDump\REPORT -w "not InUse and FileSize > 104857600" --count
+
+  6. СРАВНЕНИЕ С ЭТАЛОНОМ (Diff)
+     Что добавилось, удалилось и изменилось с прошлого снимка (системные каталоги - первыми):
+     MFTShadowForge.exe diff --baseline C:\MftDump\old.jsonl --current C:\MftDump\new.jsonl -o C:\MftDump\diff.jsonl
+
+  7. ОБЪЕДИНЕНИЕ (Merge)
+     Общий отчет по нескольким хостам с частотой имен файлов между хостами:
+     MFTShadowForge.exe merge -i SRV01=C:\Dumps\srv01.jsonl -i SRV02=C:\Dumps\srv02.jsonl -o C:\Dumps\merged.jsonl
+
+  8. АГЕНТ И СБОР (Agent / Collect)
+     На хосте - агент, принимающий задания только от контроллеров с сертификатом от ca.pem:
+     MFTShadowForge.exe agent --listen 0.0.0.0:7443 --cert agent.pem --key agent.key --ca ca.pem
+     На рабочем месте аналитика - снять диск C: агента и получить MFT и отчет:
+     MFTShadowForge.exe collect --agent srv01.corp.local:7443 --cert ctl.pem --key ctl.key --ca ca.pem -i C: -o C:\Dumps\srv01
+
+  9. МОНИТОРИНГ (Watch)
+     Первичный разбор диска C:, затем дозапись изменений из USN-журнала в C:\MftDump\WATCH:
+     MFTShadowForge.exe watch -i C: -o C:\MftDump --interval 5
+     Режим сенсора: в WATCH только измененные файлы, сработавшие на правила и детекты:
+     MFTShadowForge.exe watch -i C: -o C:\MftDump --alerts-only
+     Сенсор со своими правилами: правка rules.toml подхватывается на лету, без перезапуска:
+     MFTShadowForge.exe watch -i C: -o C:\MftDump --alerts-only --rules C:\Cases\rules.toml --exclude-rules C:\Cases\allowlist.yaml
+
+  10. ЕДИНАЯ ВРЕМЕННАЯ ШКАЛА (Supertimeline)
+     События MFT ($SI/$FN MACB) и USN-журнала в одном хронологическом CSV:
+     MFTShadowForge.exe supertimeline --mft C:\MftDump\REPORT --usn C:\MftDump\USN -o C:\MftDump\timeline.csv --format csv
+
+  11. СВЕДЕНИЯ О ТОМЕ (Info)
+     Разделы, геометрия, фрагментация $MFT и проверки ($MFTMirr, резервный VBR) на диске C: без извлечения:
+     MFTShadowForge.exe info --image C:
+     То же для готового дампа (из mft.raw.meta.json):
+     MFTShadowForge.exe info --mft C:\MftDump\mft.raw
+
+  12. ПРОВЕРКА ДАМПА (Verify)
+     Сигнатуры, fixups, границы атрибутов и runlist $MFT в дампе стороннего инструмента - до полного разбора:
+     MFTShadowForge.exe verify -p C:\Cases\other_tool\$MFT -j C:\Cases\mft-health.json
+
+  13. ВЛАДЕЛЕЦ КЛАСТЕРА (Whohas)
+     Какой файл (в т.ч. удаленный) занимает кластер или байтовое смещение из поиска по сырому диску:
+     MFTShadowForge.exe whohas --image disk.raw --mft C:\MftDump\mft.raw --offset 1234567890
+     MFTShadowForge.exe whohas --mft C:\MftDump\mft.raw --lcn 786432
+
+  14. АКТИВНОСТЬ СОЗДАНИЯ ФАЙЛОВ (Activity)
+     Гистограмма создания файлов по часам с разбивкой по каталогам верхнего уровня и HTML-диаграммой:
+     MFTShadowForge.exe activity --input C:\MftDump\report.jsonl -o C:\MftDump\activity.csv --format csv --bucket hour --by-dir --html C:\MftDump\activity.html
+
+  15. ПОИСК ЗАПИСЕЙ ПО ОБРАЗУ (Carve)
+     Записи FILE со всего диска, в том числе вне $MFT (затертый или обрезанный $MFT):
+     MFTShadowForge.exe carve -i disk.E01 -o C:\MftDump\carved.jsonl
+
+  16. USN-ЖУРНАЛ ТОМА (UsnJrnl)
+     Разбор $Extend\$UsnJrnl:$J прямо с тома или образа в JSONL (вход для supertimeline --usn):
+     MFTShadowForge.exe usnjrnl -i disk.E01 -o C:\MftDump\usn.jsonl --partition 2
+
+  17. SLACK ИНДЕКСОВ КАТАЛОГОВ (I30)
+     Имена и метки удаленных файлов из slack $I30 всех каталогов (в т.ч. с переиспользованными записями MFT):
+     MFTShadowForge.exe i30 -i disk.E01 --partition 2 -o C:\MftDump\i30.jsonl
+     То же полем I30Slack в строках каталогов при разборе дампа:
+     MFTShadowForge.exe parse -p C:\MftDump\MFT -j C:\MftDump\REPORT --i30-image disk.E01 --i30-partition 2
+     Старые имена переиспользованных каталогов в путях удаленных файлов (PathConfidence=Carved):
+     MFTShadowForge.exe parse -p C:\MftDump\MFT -j C:\MftDump\REPORT --i30-entries C:\MftDump\i30.jsonl
+
+  18. ИЗВЛЕЧЕНИЕ ФАЙЛА (Recover)
+     Содержимое файла с тома или образа по пути или номеру записи (с SHA-256):
+     MFTShadowForge.exe recover -i disk.E01 --partition 2 --path C:\Users\Public\evil.exe -o evil.bin
+     MFTShadowForge.exe recover -i disk.E01 --partition 2 --entry 12345 --stream Zone.Identifier -o zone.txt
+     $Secure:$SDS, чтобы parse --sds добавил владельца и DACL каждого файла:
+     MFTShadowForge.exe recover -i disk.E01 --partition 2 --path \$Secure --stream \$SDS -o C:\MftDump\sds.bin
+     MFTShadowForge.exe parse -p C:\MftDump\MFT -j C:\MftDump\REPORT --sds C:\MftDump\sds.bin
+
+  19. КЛАСТЕРЫ УДАЛЕННЫХ ФАЙЛОВ (Bitmap)
+     Можно ли вернуть удаленные файлы: свободны ли их кластеры по $Bitmap (Recoverable/Partial/Reused):
+     MFTShadowForge.exe bitmap -i disk.E01 --partition 2 -o C:\MftDump\bitmap.jsonl
+     MFTShadowForge.exe query -i C:\MftDump\bitmap.jsonl --where "Status = 'Recoverable'"
+
+  20. СХЕМА ОТЧЕТА (Schema)
+     JSON Schema строки отчета для валидации на стороне потребителя:
+     MFTShadowForge.exe schema -j C:\MftDump\mft-entry.schema.json
+
+  21. СОДЕРЖИМОЕ КАТАЛОГОВ (Dirindex)
+     Индексы $I30 против MFT: элементы без записей (удаленные, переиспользованные) и имена вне индекса:
+     MFTShadowForge.exe dirindex -i disk.E01 --partition 2 -o C:\MftDump\dirindex.jsonl --mismatched-only
 "#;
 
 #[derive(Parser, Debug)]
@@ -45,41 +190,717 @@ const EXAMPLES: &str = r#"
 pub struct Cli {
     #[command(subcommand)]
     pub command: Commands,
+    /// Подробнее журнал: -v - отладочные события, -vv - трассировка
+    #[arg(short, long, action = clap::ArgAction::Count, global = true, conflicts_with = "quiet")]
+    pub verbose: u8,
+    /// Только предупреждения и ошибки
+    #[arg(short, long, global = true)]
+    pub quiet: bool,
+    /// Журнал в stderr в JSON, одно событие на строку (с длительностью этапов и команды)
+    #[arg(long, global = true)]
+    pub log_json: bool,
+    /// Язык сообщений extract, parse и play
+    #[arg(long, global = true, value_enum, default_value_t = Lang::En)]
+    pub lang: Lang,
 }
 
+// Разбирается один раз при запуске - размер варианта parse не важен
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand, Debug)]
 pub enum Commands {
     /// Извлекает MFT в raw-формат из образа диска
     Extract {
         /// Образ диска (E01/raw) или условный C:\
+        #[arg(short, long, required_unless_present = "connect", conflicts_with = "connect")]
+        image: Option<String>,
+        /// Путь к raw MFT
+        #[arg(short, long, required_unless_present_any = ["list_partitions", "listen"])]
+        out: Option<String>,
+        /// Раздел с NTFS: номер из --list-partitions или смещение в байтах (0x100000);
+        /// по умолчанию - первый найденный NTFS
+        #[arg(long, value_name = "INDEX|OFFSET", value_parser = parse_partition, conflicts_with = "shadow")]
+        partition: Option<PartitionSelect>,
+        /// Показать все записи MBR/EBR/GPT со смещениями и файловой системой и выйти
+        #[arg(long)]
+        list_partitions: bool,
+        /// Снять $MFT из теневых копий тома (только живая Windows): номер снимка, all или list;
+        /// для снимка N пишется <out>.vssN (mft.raw -> mft.vssN.raw) и его meta.json
+        #[arg(long, value_name = "N|all|list", value_parser = parse_shadow)]
+        shadow: Option<ShadowSelect>,
+        /// Снять BitLocker: ключ восстановления (48 цифр), пароль или путь к файлу .BEK
+        #[arg(long, value_name = "KEY", value_parser = parse_bitlocker_key, conflicts_with = "shadow")]
+        bitlocker_key: Option<BitLockerKey>,
+        /// Режим агента на исследуемой машине: раздавать чтение --image по mTLS на этом адресе
+        #[arg(long, value_name = "HOST:PORT", requires = "cert", requires = "key", requires = "ca",
+            conflicts_with_all = ["out", "connect", "partition", "list_partitions", "shadow", "bitlocker_key"])]
+        listen: Option<String>,
+        /// Читать устройство агента extract --listen (host:port, host должен совпадать с сертификатом агента)
+        #[arg(long, value_name = "HOST:PORT", requires = "cert", requires = "key", requires = "ca", conflicts_with = "shadow")]
+        connect: Option<String>,
+        /// Сертификат для --listen/--connect (PEM)
+        #[arg(long)]
+        cert: Option<String>,
+        /// Закрытый ключ для --listen/--connect (PEM)
+        #[arg(long)]
+        key: Option<String>,
+        /// CA, которым подписан сертификат другой стороны (PEM)
+        #[arg(long)]
+        ca: Option<String>,
+        /// Размер блока чтения $MFT (512K, 4M, 16M; по умолчанию 1M): крупнее - для быстрых
+        /// NVMe, округляется вниз до кратного кластеру
+        #[arg(long, value_name = "SIZE", value_parser = parse_chunk_size)]
+        io_chunk_size: Option<usize>,
+        /// Потоков чтения сильно фрагментированного $MFT (у каждого свой дескриптор; 1 - подряд)
+        #[arg(long, value_name = "N", default_value_t = DEFAULT_READ_THREADS as u64, value_parser = clap::value_parser!(u64).range(1..=64))]
+        threads: u64,
+        /// Не показывать ход извлечения в stderr (для скриптов)
+        #[arg(long)]
+        no_progress: bool,
+        /// Метка источника KEY=VALUE в каждой строке отчета и в meta/summary (можно несколько раз)
+        #[arg(long = "tag", value_name = "KEY=VALUE", value_parser = parse_tag)]
+        tag: Vec<(String, String)>,
+        #[command(flatten)]
+        case: CaseArgs,
+    },
+    /// Сведения о томе и раскладке $MFT (разделы, геометрия, фрагментация, проверки целостности)
+    Info {
+        /// Том или образ (анализ без извлечения)
+        #[arg(short, long, required_unless_present = "mft", conflicts_with = "mft")]
+        image: Option<String>,
+        /// Дамп MFT, рядом с которым лежит <mft>.meta.json
+        #[arg(long)]
+        mft: Option<String>,
+        /// Раздел с NTFS (номер или смещение, как у extract); по умолчанию - первый найденный
+        #[arg(long, value_name = "INDEX|OFFSET", value_parser = parse_partition, conflicts_with = "mft")]
+        partition: Option<PartitionSelect>,
+        /// Сохранить сведения в JSON
+        #[arg(short = 'j', long)]
+        out_json: Option<String>,
+    },
+    /// Поиск записей FILE по всему образу (неразмеченное пространство, затертый или обрезанный $MFT)
+    Carve {
+        /// Том, диск или образ (raw, E01, VHD, VHDX, VMDK)
         #[arg(short, long)]
         image: String,
-        /// Путь к raw MFT
+        /// Куда сохранить найденные записи (JSONL, поля Carved и ImageOffset)
         #[arg(short, long)]
         out: String,
+        /// Включать ли содержимое $DATA для резидентных файлов
+        #[arg(short, long)]
+        data: bool,
     },
-    /// Конвертирует raw MFT в JSONL (JSON Lines) с анализом и правилами
-    Parse {
+    /// Разбор USN-журнала тома ($Extend\$UsnJrnl:$J) в JSONL
+    Usnjrnl {
+        /// Том, диск или образ (\\.\C:, raw, E01, VHD, VHDX, VMDK)
+        #[arg(short, long)]
+        image: String,
+        /// Куда сохранить записи журнала (JSONL)
+        #[arg(short, long)]
+        out: String,
+        /// Раздел с NTFS: номер из extract --list-partitions или смещение в байтах (0x100000)
+        #[arg(long, value_name = "INDEX|OFFSET", value_parser = parse_partition)]
+        partition: Option<PartitionSelect>,
+    },
+    /// Удаленные файлы и занятость их кластеров по $Bitmap: можно ли вернуть содержимое
+    Bitmap {
+        /// Том, диск или образ (\\.\C:, raw, E01, VHD, VHDX, VMDK)
+        #[arg(short, long)]
+        image: String,
+        /// Куда сохранить отчет (JSONL)
+        #[arg(short, long)]
+        out: String,
+        /// Раздел с NTFS: номер из extract --list-partitions или смещение в байтах (0x100000)
+        #[arg(long, value_name = "INDEX|OFFSET", value_parser = parse_partition)]
+        partition: Option<PartitionSelect>,
+    },
+    /// Имена удаленных файлов из slack индексов $I30 каталогов
+    I30 {
+        /// Том, диск или образ (\\.\C:, raw, E01, VHD, VHDX, VMDK)
+        #[arg(short, long)]
+        image: String,
+        /// Куда сохранить элементы индексов (JSONL)
+        #[arg(short, long)]
+        out: String,
+        /// Раздел с NTFS: номер из extract --list-partitions или смещение в байтах (0x100000)
+        #[arg(long, value_name = "INDEX|OFFSET", value_parser = parse_partition)]
+        partition: Option<PartitionSelect>,
+        /// Выводить и живые элементы индексов (Slack: false)
+        #[arg(long)]
+        active: bool,
+    },
+    /// Содержимое каталогов по индексам $I30 и сверка с MFT: элементы без записей и имена вне индекса
+    Dirindex {
+        /// Том, диск или образ (\\.\C:, raw, E01, VHD, VHDX, VMDK)
+        #[arg(short, long)]
+        image: String,
+        /// Куда сохранить отчет по каталогам (JSONL)
+        #[arg(short, long)]
+        out: String,
+        /// Раздел с NTFS: номер из extract --list-partitions или смещение в байтах (0x100000)
+        #[arg(long, value_name = "INDEX|OFFSET", value_parser = parse_partition)]
+        partition: Option<PartitionSelect>,
+        /// Только каталоги с расхождениями (или ошибкой чтения индекса)
+        #[arg(long)]
+        mismatched_only: bool,
+    },
+    /// Извлечение содержимого файла с тома или образа
+    Recover {
+        /// Том, диск или образ (\\.\C:, raw, E01, VHD, VHDX, VMDK)
+        #[arg(short, long)]
+        image: String,
+        /// Номер записи MFT файла
+        #[arg(short, long, required_unless_present = "path", conflicts_with = "path")]
+        entry: Option<u64>,
+        /// Путь к файлу внутри тома (C:\Windows\notepad.exe или \Windows\notepad.exe)
+        #[arg(short, long)]
+        path: Option<String>,
+        /// Отчет parse этого тома: --path ищется по его индексу (<report>.idx), без обхода каталогов
+        #[arg(long, requires = "path")]
+        report: Option<String>,
+        /// Именованный поток (ADS), например Zone.Identifier; по умолчанию - основной $DATA
+        #[arg(short, long)]
+        stream: Option<String>,
+        /// Куда сохранить содержимое
+        #[arg(short, long)]
+        out: String,
+        /// Раздел с NTFS: номер из extract --list-partitions или смещение в байтах (0x100000)
+        #[arg(long, value_name = "INDEX|OFFSET", value_parser = parse_partition)]
+        partition: Option<PartitionSelect>,
+    },
+    /// JSON Schema строки отчета parse (версия формата - поле SchemaVersion)
+    Schema {
+        /// Сохранить схему в файл (по умолчанию - stdout)
+        #[arg(short = 'j', long)]
+        out_json: Option<String>,
+    },
+    /// Какие файлы занимают кластер: обратный поиск по data runs всех записей
+    Whohas {
+        /// Том или образ (геометрия тома); без него - из <mft>.meta.json
+        #[arg(short, long)]
+        image: Option<String>,
+        /// Дамп MFT
+        #[arg(long)]
+        mft: String,
+        /// Номер кластера (LCN) от начала тома (можно несколько раз)
+        #[arg(long, required_unless_present = "offset")]
+        lcn: Vec<u64>,
+        /// Байтовое смещение от начала образа/устройства (можно несколько раз)
+        #[arg(long)]
+        offset: Vec<u64>,
+        /// Сохранить найденных владельцев в JSONL
+        #[arg(short = 'j', long)]
+        out_json: Option<String>,
+    },
+    /// Проверка готового дампа MFT (в т.ч. снятого другим инструментом) до полного разбора
+    Verify {
         /// Путь к raw MFT
         #[arg(short, long)]
         path: String,
-        /// Путь к итоговому JSONL (1 строка - 1 объект)
+        /// Сохранить отчет о состоянии дампа в JSON
+        #[arg(short = 'j', long)]
+        out_json: Option<String>,
+        /// Размер записи; по умолчанию - из <mft>.meta.json или заголовка записи 0
+        #[arg(long)]
+        record_size: Option<usize>,
+        /// Размер сектора для fixups; по умолчанию - из <mft>.meta.json или массива USA записи 0
+        #[arg(long)]
+        sector_size: Option<u16>,
+        /// Сколько проблем перечислить в отчете (счетчики ведутся по всем)
+        #[arg(long, default_value_t = 1000)]
+        max_issues: usize,
+    },
+    /// Замер скорости разбора на синтетическом MFT: сравнение машин и проверка регрессий между версиями
+    Bench {
+        /// Число записей синтетического MFT (по 1 КБ)
+        #[arg(long, default_value_t = 200_000, value_parser = clap::value_parser!(u64).range(16..))]
+        records: u64,
+        /// Сколько раз повторить разбор (итог - по лучшему прогону)
+        #[arg(long, default_value_t = 3, value_parser = clap::value_parser!(u64).range(1..=100))]
+        iterations: u64,
+        /// Сохранить результат в JSON
         #[arg(short = 'j', long)]
-        out_json: String,
+        out_json: Option<String>,
+    },
+    /// Конвертирует raw MFT в JSONL (JSON Lines) с анализом и правилами
+    Parse {
+        /// Путь к raw MFT (`-` - читать из stdin)
+        #[arg(short, long)]
+        path: String,
+        /// Путь к итоговому JSONL (1 строка - 1 объект; `-` - писать в stdout)
+        #[arg(short = 'j', long, required_unless_present_any = ["es_url", "splunk_hec_url"])]
+        out_json: Option<String>,
+        /// Формат JSONL: строка на запись или событие на метку времени для Timesketch
+        #[arg(long, value_enum, default_value_t = ReportFormat::Jsonl, requires = "out_json")]
+        format: ReportFormat,
+        /// Сжимать JSONL на лету (к имени дописывается .gz/.zst)
+        #[arg(long, value_enum, requires = "out_json")]
+        compress: Option<Compression>,
         /// Включать ли содержимое $DATA для резидентных файлов
         #[arg(short, long)]
         data: bool,
+        /// Том или образ, с которого снят дамп: экстенты из нерезидентных $ATTRIBUTE_LIST, с --data - содержимое нерезидентных файлов
+        #[arg(long, value_name = "IMAGE")]
+        image: Option<String>,
+        /// Раздел для --image: номер из extract --list-partitions или смещение в байтах
+        #[arg(long, value_name = "INDEX|OFFSET", value_parser = parse_partition, requires = "image")]
+        partition: Option<PartitionSelect>,
+        /// Предел размера нерезидентного $DATA, читаемого с --image, байт
+        #[arg(long, value_name = "BYTES", default_value_t = 1024 * 1024)]
+        max_data_size: u64,
+        /// MD5/SHA1/SHA256 резидентного $DATA в полях Md5/Sha1/Sha256 (без вывода самих данных)
+        #[arg(long)]
+        hash: bool,
+        /// Хешсет известных файлов (NSRL в CSV или SQLite, свой список) для флага KnownGood
+        #[arg(long)]
+        hashset: Option<String>,
+        /// Не выводить записи, найденные в хешсете
+        #[arg(long, requires = "hashset")]
+        skip_known: bool,
+        /// Вшитые наборы правил через запятую вместо встроенных: default, persistence, webshell, ransomware, lolbin
+        #[arg(long = "profile", value_name = "NAME,...", value_delimiter = ',', value_parser = PROFILE_NAMES)]
+        profile: Vec<String>,
+        /// Файл правил (.toml, .yaml) для FitsRules/RuleMatches: вместо встроенных, а с --profile - дополняет их и заменяет одноименные
+        #[arg(long, value_name = "FILE")]
+        rules: Option<String>,
+        /// Sigma-правила file_event/file_create (файл или каталог, можно несколько раз) в дополнение к правилам
+        #[arg(long, value_name = "PATH")]
+        sigma: Vec<String>,
+        /// Файл исключений (.toml, .yaml, формат --rules): совпавшие с ним записи не получают FitsRules/RuleMatches
+        #[arg(long, value_name = "FILE")]
+        exclude_rules: Option<String>,
+        /// Порог глубины пути для флага PathTooDeep (0 - отключить)
+        #[arg(long, default_value_t = 32)]
+        max_path_depth: usize,
+        /// Порог длины пути для флага PathTooLong (0 - отключить)
+        #[arg(long, default_value_t = 260)]
+        max_path_length: usize,
+        /// Юникод-нормализация имен в выводе (после декодирования UTF-16)
+        #[arg(long, value_enum, default_value_t = NameNormalization::None)]
+        normalize_paths: NameNormalization,
+        /// Только записи, измененные после прошлого прогона: его summary.json или USN
+        #[arg(long, value_name = "SUMMARY|USN")]
+        since: Option<String>,
+        /// Плагин-детектор (.dll/.so), добавляющий поля в строки отчета (можно несколько раз)
+        #[arg(long, value_name = "PATH")]
+        plugin: Vec<String>,
+        /// Выгружать карту VCN -> LCN non-resident $DATA (поле DataRuns)
+        #[arg(long)]
+        runs: bool,
+        /// Дополнительно записать карту VCN -> LCN всех записей в CSV (включает --runs)
+        #[arg(long, value_name = "PATH")]
+        runs_out: Option<String>,
+        /// Разбирать slack записей MFT: строки и остатки прежних имен и меток (поле RecordSlack)
+        #[arg(long)]
+        record_slack: bool,
+        /// Добавлять исходные FILETIME меток $SI и $FN как u64 (поле RawTimestamps)
+        #[arg(long)]
+        raw_timestamps: bool,
+        /// Не показывать ход проходов в stderr (для скриптов)
+        #[arg(long)]
+        no_progress: bool,
+        /// Ограничить память на томах в десятки миллионов записей: имена дерева путей -
+        /// во временном файле, одинаковые хранятся один раз; индекс отчета не пишется
+        #[arg(long)]
+        low_memory: bool,
+        /// Сохранять сырую запись MFT (hex, до fixups) в строках с детектами (поле RawRecord)
+        #[arg(long)]
+        preserve_flagged: bool,
+        /// Дополнительно записать отчет в Apache Parquet (типизированные колонки для pandas/DuckDB/Spark)
+        #[arg(long, value_name = "PATH")]
+        out_parquet: Option<String>,
+        /// Дополнительно записать bodyfile Sleuth Kit (строки $SI и $FN) для mactime/log2timeline
+        #[arg(long, value_name = "PATH")]
+        bodyfile: Option<String>,
+        /// Дополнительно записать JSONL только со строками с детектами (правила, Timestomped, TornWrite, ...)
+        #[arg(long, value_name = "PATH")]
+        alerts: Option<String>,
+        /// Дополнительно записать временную шкалу TLN (time|source|host|user|desc)
+        #[arg(long, value_name = "PATH")]
+        tln: Option<String>,
+        /// Поле host в строках TLN
+        #[arg(long, requires = "tln")]
+        hostname: Option<String>,
+        /// Поле user в строках TLN
+        #[arg(long, requires = "tln")]
+        user: Option<String>,
+        /// Индексировать строки отчета в Elasticsearch через _bulk (http(s)://[user:pass@]host:9200); без -j файл не пишется
+        #[arg(long, value_name = "URL")]
+        es_url: Option<String>,
+        /// Индекс Elasticsearch
+        #[arg(long, value_name = "INDEX", default_value = "mft", requires = "es_url")]
+        es_index: String,
+        /// Отправлять строки отчета в Splunk HTTP Event Collector (https://host:8088); без -j файл не пишется
+        #[arg(long, value_name = "URL", requires = "splunk_token")]
+        splunk_hec_url: Option<String>,
+        /// Токен HEC
+        #[arg(long, value_name = "TOKEN", requires = "splunk_hec_url")]
+        splunk_token: Option<String>,
+        /// Том или образ, с которого снят дамп: удаленные имена из slack индексов $I30 в поле I30Slack каталогов
+        #[arg(long, value_name = "IMAGE")]
+        i30_image: Option<String>,
+        /// Раздел для --i30-image: номер из extract --list-partitions или смещение в байтах
+        #[arg(long, value_name = "INDEX|OFFSET", value_parser = parse_partition, requires = "i30_image")]
+        i30_partition: Option<PartitionSelect>,
+        /// Отчет команды i30 (JSONL): старые имена переиспользованных каталогов в путях удаленных файлов (PathConfidence=Carved)
+        #[arg(long, value_name = "FILE")]
+        i30_entries: Option<String>,
+        /// Поток $Secure:$SDS тома (recover -p \$Secure -s \$SDS): поля OwnerSid, GroupSid и Dacl по SecurityId
+        #[arg(long, value_name = "FILE")]
+        sds: Option<String>,
+        /// Метка источника KEY=VALUE в каждой строке отчета и в meta/summary (можно несколько раз)
+        #[arg(long = "tag", value_name = "KEY=VALUE", value_parser = parse_tag)]
+        tag: Vec<(String, String)>,
+        #[command(flatten)]
+        case: CaseArgs,
     },
     /// Полный пайплайн (extract + parse)
     Play {
-        /// Образ диска (E01/raw) или условный C:\
+        /// Образ диска (E01/raw) или условный C:\ (можно указать несколько раз)
+        #[arg(short, long, required = true)]
+        image: Vec<String>,
+        /// Папка для raw MFT и JSONL
+        #[arg(short, long)]
+        out: String,
+        /// Включать ли содержимое $DATA для резидентных файлов
+        #[arg(short, long)]
+        data: bool,
+        /// Передавать $MFT в разбор через память, без записи и повторного чтения дампа
+        /// (весь $MFT тома держится в памяти)
+        #[arg(long)]
+        stream: bool,
+        /// С --stream все равно сохранить дамп MFT и meta.json в папке результата
+        #[arg(long, requires = "stream")]
+        keep_mft: bool,
+        /// Повторять снятие по расписанию (30m, 6h, 1d); каждый снимок - в своей подпапке
+        #[arg(long, value_parser = parse_interval)]
+        interval: Option<Duration>,
+        /// Сколько последних снимков хранить (0 - все)
+        #[arg(long, default_value_t = 0, requires = "interval")]
+        keep: usize,
+        /// Метка источника KEY=VALUE в каждой строке отчета и в meta/summary (можно несколько раз)
+        #[arg(long = "tag", value_name = "KEY=VALUE", value_parser = parse_tag)]
+        tag: Vec<(String, String)>,
+        #[command(flatten)]
+        case: CaseArgs,
+    },
+    /// Обзор тома по JSONL-отчету или raw MFT (счетчики, детекты, расширения, крупнейшие файлы, редкость)
+    Stats {
+        /// Путь к JSONL-отчету (результат parse) или к raw MFT
+        #[arg(short, long)]
+        input: String,
+        /// Сохранить статистику в JSON
+        #[arg(short = 'j', long)]
+        out_json: Option<String>,
+        /// Сколько самых редких, крупнейших файлов и расширений вывести
+        #[arg(long, default_value_t = 20)]
+        top: usize,
+    },
+    /// Отбор строк готового JSONL-отчета по выражению (без jq/duckdb), потоково
+    Query {
+        /// JSONL-отчет (parse, carve, i30, usnjrnl; .gz/.zst распознаются сами; `-` - stdin)
+        #[arg(short, long)]
+        input: String,
+        /// Условие: поля, = != < <= > >= glob contains, and/or/not и скобки,
+        /// например "Extension = 'exe' and Timestomped"
+        #[arg(short = 'w', long = "where", value_name = "EXPR")]
+        filter: Option<String>,
+        /// Glob по Full_Path без учета регистра (C:\Users\*\AppData\*)
+        #[arg(long, value_name = "GLOB")]
+        path: Option<String>,
+        /// Нижняя граница --time-field включительно (2024-03-01 или RFC 3339)
+        #[arg(long, value_name = "TIME")]
+        after: Option<String>,
+        /// Верхняя граница --time-field, не включая ее
+        #[arg(long, value_name = "TIME")]
+        before: Option<String>,
+        /// Поле времени для --after/--before
+        #[arg(long, default_value = "Created0x10")]
+        time_field: String,
+        /// Куда писать совпавшие строки (`-` - stdout)
+        #[arg(short, long, default_value = "-")]
+        out: String,
+        /// Формат вывода, как у parse: строки как есть или события Timesketch
+        #[arg(long, value_enum, default_value_t = ReportFormat::Jsonl)]
+        format: ReportFormat,
+        /// Сжимать вывод на лету (к имени дописывается .gz/.zst)
+        #[arg(long, value_enum)]
+        compress: Option<Compression>,
+        /// Вывести только число совпавших строк
+        #[arg(long, conflicts_with_all = ["format", "compress"])]
+        count: bool,
+        /// Не использовать индекс отчета (<input>.idx от parse), читать отчет целиком
+        #[arg(long)]
+        no_index: bool,
+    },
+    /// Гистограмма создания файлов ($SI Created) по часам/дням, с HTML-диаграммой
+    Activity {
+        /// Путь к JSONL-отчету (результат parse)
+        #[arg(short, long)]
+        input: String,
+        /// Куда сохранить гистограмму
+        #[arg(short, long)]
+        out: String,
+        /// Размер корзины
+        #[arg(long, value_enum, default_value_t = ActivityBucket::Day)]
+        bucket: ActivityBucket,
+        /// Разбивка по каталогам верхнего уровня
+        #[arg(long)]
+        by_dir: bool,
+        /// Формат гистограммы
+        #[arg(long, value_enum, default_value_t = ActivityFormat::Json)]
+        format: ActivityFormat,
+        /// Сохранить HTML-страницу с диаграммой
+        #[arg(long)]
+        html: Option<String>,
+    },
+    /// Сравнение двух JSONL-отчетов: добавленные, удаленные и измененные записи
+    Diff {
+        /// Базовый (эталонный) отчет
+        #[arg(long, required_unless_present = "old", conflicts_with = "old")]
+        baseline: Option<String>,
+        /// Текущий отчет
+        #[arg(long, required_unless_present = "new", conflicts_with = "new")]
+        current: Option<String>,
+        /// Базовый сырой дамп $MFT (сравнение записей по номеру и sequence вместо отчетов)
+        #[arg(long, requires = "new")]
+        old: Option<String>,
+        /// Текущий сырой дамп $MFT
+        #[arg(long, requires = "old")]
+        new: Option<String>,
+        /// Куда сохранить изменения (JSONL)
+        #[arg(short, long)]
+        out: String,
+        /// Выводить только изменения в системных каталогах
+        #[arg(long)]
+        system_only: bool,
+        /// Сколько изменений в системных каталогах показать в консоли
+        #[arg(long, default_value_t = 20)]
+        top: usize,
+    },
+    /// Объединение отчетов нескольких томов/хостов со сводкой детектов и частотой имен
+    Merge {
+        /// Отчет JSONL, можно с именем хоста: SRV01=report.jsonl (указывается несколько раз)
+        #[arg(short, long, required = true)]
+        input: Vec<String>,
+        /// Итоговый JSONL (сводка - в <out>.summary.json)
+        #[arg(short, long)]
+        out: String,
+        /// Считать имя редким, если оно встречается не более чем на N хостах
+        #[arg(long, default_value_t = 1)]
+        max_hosts: usize,
+        /// Сколько редких имен вывести
+        #[arg(long, default_value_t = 50)]
+        top: usize,
+    },
+    /// Агент: принимает задания на сбор по mTLS и возвращает MFT и отчет контроллеру
+    #[cfg(feature = "agent")]
+    Agent {
+        /// Адрес для входящих подключений
+        #[arg(long, default_value = "0.0.0.0:7443")]
+        listen: String,
+        /// Сертификат агента (PEM)
+        #[arg(long)]
+        cert: String,
+        /// Закрытый ключ агента (PEM)
+        #[arg(long)]
+        key: String,
+        /// CA, которым подписаны сертификаты контроллеров (PEM)
+        #[arg(long)]
+        ca: String,
+    },
+    /// Контроллер: отправляет задание агенту и сохраняет полученные MFT и отчет
+    #[cfg(feature = "agent")]
+    Collect {
+        /// Адрес агента (host:port), host должен совпадать с сертификатом агента
+        #[arg(long)]
+        agent: String,
+        /// Сертификат контроллера (PEM)
+        #[arg(long)]
+        cert: String,
+        /// Закрытый ключ контроллера (PEM)
+        #[arg(long)]
+        key: String,
+        /// CA, которым подписан сертификат агента (PEM)
+        #[arg(long)]
+        ca: String,
+        /// Том или образ на стороне агента
         #[arg(short, long)]
         image: String,
-        /// Папка для raw MFT и JSONL
+        /// Локальная папка для результатов
         #[arg(short, long)]
         out: String,
         /// Включать ли содержимое $DATA для резидентных файлов
         #[arg(short, long)]
         data: bool,
+        /// Метка источника KEY=VALUE в каждой строке отчета и в meta/summary (можно несколько раз)
+        #[arg(long = "tag", value_name = "KEY=VALUE", value_parser = parse_tag)]
+        tag: Vec<(String, String)>,
+        #[command(flatten)]
+        case: CaseArgs,
     },
-}
\ No newline at end of file
+    /// Мониторинг живого тома: первичный разбор + поток изменений по USN-журналу
+    Watch {
+        /// Живой том (C:)
+        #[arg(short, long)]
+        image: String,
+        /// Папка для raw MFT, первичного отчета и потока изменений
+        #[arg(short, long)]
+        out: String,
+        /// Включать ли содержимое $DATA для резидентных файлов
+        #[arg(short, long)]
+        data: bool,
+        /// Интервал опроса USN-журнала в секундах
+        #[arg(long, default_value_t = 2)]
+        interval: u64,
+        /// Писать в WATCH только измененные записи с детектами (правила, Timestomped, ADS, ...)
+        #[arg(long)]
+        alerts_only: bool,
+        /// Вшитые наборы правил через запятую вместо встроенных (как в parse)
+        #[arg(long = "profile", value_name = "NAME,...", value_delimiter = ',', value_parser = PROFILE_NAMES)]
+        profile: Vec<String>,
+        /// Файл правил (.toml, .yaml); при изменении перезагружается без перезапуска
+        #[arg(long, value_name = "FILE")]
+        rules: Option<String>,
+        /// Sigma-правила file_event (файл или каталог, можно несколько раз); отслеживаются как --rules
+        #[arg(long, value_name = "PATH")]
+        sigma: Vec<String>,
+        /// Файл исключений (формат --rules); отслеживается как --rules
+        #[arg(long, value_name = "FILE")]
+        exclude_rules: Option<String>,
+        /// Метка источника KEY=VALUE в каждой строке отчета и в meta/summary (можно несколько раз)
+        #[arg(long = "tag", value_name = "KEY=VALUE", value_parser = parse_tag)]
+        tag: Vec<(String, String)>,
+        #[command(flatten)]
+        case: CaseArgs,
+    },
+    /// Единая временная шкала: метки MFT и события USN-журнала в хронологическом порядке
+    #[command(visible_alias = "timeline")]
+    Supertimeline {
+        /// JSONL-отчеты (результат parse); можно несколько - совпадающие события не дублируются
+        #[arg(long, required = true, num_args = 1..)]
+        mft: Vec<String>,
+        /// USN-записи: поток USN из watch (JSONL), результат usnjrnl или выгруженный $UsnJrnl:$J; можно несколько
+        #[arg(long, num_args = 1..)]
+        usn: Vec<String>,
+        /// Итоговый файл
+        #[arg(short, long)]
+        out: String,
+        /// Формат вывода
+        #[arg(long, value_enum, default_value_t = TimelineFormat::Jsonl)]
+        format: TimelineFormat,
+    },
+}
+
+/// Сведения о деле: сохраняются в meta.json, summary.json и audit.log.
+#[derive(Args, Debug, Clone)]
+pub struct CaseArgs {
+    /// Номер дела
+    #[arg(long)]
+    pub case_id: Option<String>,
+    /// Идентификатор носителя/улики
+    #[arg(long)]
+    pub evidence_id: Option<String>,
+    /// Исследователь
+    #[arg(long)]
+    pub examiner: Option<String>,
+    /// Дописывать CaseId/EvidenceId/Examiner в каждую строку отчета
+    #[arg(long)]
+    pub case_in_rows: bool,
+}
+
+impl CaseArgs {
+    pub fn to_case_info(&self) -> CaseInfo {
+        CaseInfo {
+            case_id: self.case_id.clone(),
+            evidence_id: self.evidence_id.clone(),
+            examiner: self.examiner.clone(),
+            in_rows: self.case_in_rows,
+        }
+    }
+}
+
+/// Интервал вида `90`, `45s`, `30m`, `6h`, `1d` (без суффикса - секунды).
+fn parse_interval(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let (num, mult) = match s.char_indices().last() {
+        Some((i, 's')) => (&s[..i], 1),
+        Some((i, 'm')) => (&s[..i], 60),
+        Some((i, 'h')) => (&s[..i], 3600),
+        Some((i, 'd')) => (&s[..i], 86400),
+        _ => (s, 1),
+    };
+    let value: u64 = num.parse().map_err(|_| format!("некорректный интервал: {}", s))?;
+    if value == 0 { return Err("интервал должен быть больше нуля".to_string()); }
+    let secs = value.checked_mul(mult).ok_or_else(|| format!("слишком большой интервал: {}", s))?;
+    Ok(Duration::from_secs(secs))
+}
+
+/// Размер блока чтения: байты или с суффиксом `K`/`M` (`512K`, `4M`), от 64 КБ до 256 МБ.
+fn parse_chunk_size(s: &str) -> Result<usize, String> {
+    let upper = s.trim().to_ascii_uppercase();
+    let (num, mult) = match upper.strip_suffix('K').or_else(|| upper.strip_suffix("KB")) {
+        Some(num) => (num, 1024),
+        None => match upper.strip_suffix('M').or_else(|| upper.strip_suffix("MB")) {
+            Some(num) => (num, 1024 * 1024),
+            None => (upper.as_str(), 1),
+        },
+    };
+    let size = num.parse::<usize>().ok().and_then(|n| n.checked_mul(mult)).ok_or_else(|| format!("некорректный размер блока: {}", s))?;
+    if !(64 * 1024..=256 * 1024 * 1024).contains(&size) {
+        return Err(format!("размер блока должен быть от 64K до 256M: {}", s));
+    }
+    Ok(size)
+}
+
+/// Выбор теневой копии: номер снимка, `all` или `list`.
+fn parse_shadow(s: &str) -> Result<ShadowSelect, String> {
+    match s.to_ascii_lowercase().as_str() {
+        "all" => Ok(ShadowSelect::All),
+        "list" => Ok(ShadowSelect::List),
+        n => n.parse().map(ShadowSelect::Index).map_err(|_| format!("ожидался номер снимка, all или list: {}", s)),
+    }
+}
+
+/// Номер раздела (`2`) или смещение в байтах (`0x100000`).
+fn parse_partition(s: &str) -> Result<PartitionSelect, String> {
+    let err = || format!("ожидался номер раздела или смещение 0x...: {}", s);
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16).map(PartitionSelect::Offset).map_err(|_| err()),
+        None => match s.parse() {
+            Ok(0) | Err(_) => Err(err()),
+            Ok(index) => Ok(PartitionSelect::Index(index)),
+        },
+    }
+}
+
+fn parse_bitlocker_key(s: &str) -> Result<BitLockerKey, String> {
+    BitLockerKey::parse(s)
+}
+
+/// Метка вида `KEY=VALUE` (ключ не пустой, значение может быть пустым).
+fn parse_tag(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => Ok((key.trim().to_string(), value.to_string())),
+        _ => Err(format!("метка должна иметь вид KEY=VALUE: {}", s)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interval_suffixes_and_overflow() {
+        assert_eq!(parse_interval("90").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_interval("30m").unwrap(), Duration::from_secs(1800));
+        assert_eq!(parse_interval("1d").unwrap(), Duration::from_secs(86400));
+        assert!(parse_interval("0h").is_err());
+        assert!(parse_interval("99999999999999999d").is_err());
+    }
+
+    #[test]
+    fn interval_overflow_is_a_clap_error() {
+        let err = Cli::try_parse_from(["mft_shadow_forge", "play", "--interval", "99999999999999999d"]).err().unwrap();
+        assert_eq!(err.kind(), clap::error::ErrorKind::ValueValidation);
+    }
+}
@@ -0,0 +1,303 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Timelike, Utc};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use super::stats::top_level_dir;
+use crate::error::{Error, Result};
+use crate::i18n::common;
+
+/// Размер корзины гистограммы.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ActivityBucket {
+    Hour,
+    Day,
+}
+
+/// Формат выгрузки гистограммы.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ActivityFormat {
+    Json,
+    Csv,
+}
+
+/// Метки раньше этой даты - нулевой или битый FILETIME, в гистограмму не попадают.
+const MIN_VALID_YEAR: i32 = 1980;
+
+/// Сколько каталогов верхнего уровня показывать отдельными цветами в HTML.
+const CHART_DIRS: usize = 8;
+
+const CHART_COLORS: [&str; CHART_DIRS + 1] = [
+    "#4e79a7", "#f28e2b", "#e15759", "#76b7b2", "#59a14f", "#edc948", "#b07aa1", "#ff9da7", "#9c9c9c",
+];
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ReportRow {
+    #[serde(rename = "Full_Path", default)]
+    full_path: String,
+    #[serde(default)]
+    is_directory: bool,
+    #[serde(rename = "Created0x10", default)]
+    created: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct BucketCount {
+    start: String,
+    total: u64,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    by_top_dir: BTreeMap<String, u64>,
+}
+
+#[derive(Debug, Serialize)]
+struct ActivityReport {
+    source: String,
+    bucket: ActivityBucket,
+    files_total: u64,
+    /// Файлы без $SI или с меткой раньше 1980 года
+    skipped: u64,
+    buckets: Vec<BucketCount>,
+}
+
+fn bucket_start(time: DateTime<Utc>, bucket: ActivityBucket) -> DateTime<Utc> {
+    let date = time.date_naive();
+    let naive = match bucket {
+        ActivityBucket::Day => date.and_hms_opt(0, 0, 0),
+        ActivityBucket::Hour => date.and_hms_opt(time.hour(), 0, 0),
+    };
+    Utc.from_utc_datetime(&naive.unwrap_or(NaiveDateTime::MIN))
+}
+
+fn bucket_label(start: DateTime<Utc>, bucket: ActivityBucket) -> String {
+    match bucket {
+        ActivityBucket::Day => start.format("%Y-%m-%d").to_string(),
+        ActivityBucket::Hour => start.format("%Y-%m-%d %H:00").to_string(),
+    }
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// Самостоятельная HTML-страница со столбчатой диаграммой (SVG). Столбцы стоят по оси времени,
+/// поэтому пустые периоды видны как разрывы; при разбивке по каталогам столбцы составные.
+fn render_html(report: &ActivityReport, starts: &[DateTime<Utc>], bucket: ActivityBucket) -> String {
+    const WIDTH: f64 = 1200.0;
+    const HEIGHT: f64 = 360.0;
+    const MARGIN: f64 = 40.0;
+
+    let mut dir_totals: BTreeMap<&str, u64> = BTreeMap::new();
+    for b in &report.buckets {
+        for (dir, count) in &b.by_top_dir {
+            *dir_totals.entry(dir.as_str()).or_insert(0) += count;
+        }
+    }
+    let mut top_dirs: Vec<(&str, u64)> = dir_totals.into_iter().collect();
+    top_dirs.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    let top_dirs: Vec<&str> = top_dirs.iter().take(CHART_DIRS).map(|(d, _)| *d).collect();
+
+    let step = match bucket {
+        ActivityBucket::Hour => 3600,
+        ActivityBucket::Day => 86400,
+    };
+    let first = starts.first().map(|t| t.timestamp()).unwrap_or(0);
+    let last = starts.last().map(|t| t.timestamp()).unwrap_or(0) + step;
+    let span = (last - first).max(step) as f64;
+    let bar_width = (WIDTH * step as f64 / span).max(1.0);
+    let max_total = report.buckets.iter().map(|b| b.total).max().unwrap_or(0).max(1) as f64;
+
+    let mut svg = String::new();
+    for (b, start) in report.buckets.iter().zip(starts) {
+        let x = MARGIN + WIDTH * (start.timestamp() - first) as f64 / span;
+        let mut y = MARGIN + HEIGHT;
+        // Без разбивки по каталогам - один сегмент на весь столбец
+        let mut segments: Vec<(usize, u64)> = Vec::new();
+        if b.by_top_dir.is_empty() {
+            segments.push((0, b.total));
+        } else {
+            let mut other = b.total;
+            for (i, dir) in top_dirs.iter().enumerate() {
+                if let Some(count) = b.by_top_dir.get(*dir) {
+                    segments.push((i, *count));
+                    other -= count;
+                }
+            }
+            if other > 0 { segments.push((CHART_DIRS, other)); }
+        }
+        for (color, count) in segments {
+            let h = HEIGHT * count as f64 / max_total;
+            y -= h;
+            svg.push_str(&format!(
+                "<rect x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\" fill=\"{}\"><title>{}: {}</title></rect>\n",
+                x, y, bar_width, h, CHART_COLORS[color], html_escape(&b.start), count));
+        }
+    }
+
+    let mut legend = String::new();
+    if report.buckets.iter().any(|b| !b.by_top_dir.is_empty()) {
+        for (i, dir) in top_dirs.iter().enumerate() {
+            legend.push_str(&format!("<span><i style=\"background:{}\"></i>{}</span>\n", CHART_COLORS[i], html_escape(dir)));
+        }
+        legend.push_str(&format!("<span><i style=\"background:{}\"></i>прочие</span>\n", CHART_COLORS[CHART_DIRS]));
+    }
+
+    let mut peaks: Vec<&BucketCount> = report.buckets.iter().collect();
+    peaks.sort_by(|a, b| b.total.cmp(&a.total).then(a.start.cmp(&b.start)));
+    let rows: String = peaks.iter().take(20)
+        .map(|b| format!("<tr><td>{}</td><td>{}</td></tr>\n", html_escape(&b.start), b.total))
+        .collect();
+
+    format!(r##"<!DOCTYPE html>
+<html lang="ru">
+<head>
+<meta charset="utf-8">
+<title>Активность создания файлов - {source}</title>
+<style>
+body {{ font-family: sans-serif; margin: 20px; }}
+.legend span {{ margin-right: 14px; white-space: nowrap; }}
+.legend i {{ display: inline-block; width: 12px; height: 12px; margin-right: 4px; vertical-align: middle; }}
+table {{ border-collapse: collapse; }}
+td, th {{ border: 1px solid #ccc; padding: 2px 8px; }}
+</style>
+</head>
+<body>
+<h2>Активность создания файлов ($SI Created)</h2>
+<p>Источник: {source}. Корзина: {bucket}. Файлов: {files}, пропущено: {skipped}. Период: {from} - {to}.</p>
+<div class="legend">
+{legend}</div>
+<svg width="{svg_w}" height="{svg_h}" xmlns="http://www.w3.org/2000/svg">
+<line x1="{m}" y1="{base}" x2="{right}" y2="{base}" stroke="#333"/>
+<text x="{m}" y="{label_y}" font-size="12">{from}</text>
+<text x="{right}" y="{label_y}" font-size="12" text-anchor="end">{to}</text>
+<text x="{m}" y="{top_y}" font-size="12">{max}</text>
+{svg}</svg>
+<h3>Пиковые периоды</h3>
+<table>
+<tr><th>Начало</th><th>Файлов</th></tr>
+{rows}</table>
+</body>
+</html>
+"##,
+        source = html_escape(&report.source),
+        bucket = match bucket { ActivityBucket::Hour => "час", ActivityBucket::Day => "день" },
+        files = report.files_total,
+        skipped = report.skipped,
+        from = report.buckets.first().map(|b| html_escape(&b.start)).unwrap_or_default(),
+        to = report.buckets.last().map(|b| html_escape(&b.start)).unwrap_or_default(),
+        legend = legend,
+        svg_w = WIDTH + 2.0 * MARGIN,
+        svg_h = HEIGHT + 2.0 * MARGIN,
+        m = MARGIN,
+        base = MARGIN + HEIGHT,
+        right = MARGIN + WIDTH,
+        label_y = MARGIN + HEIGHT + 16.0,
+        top_y = MARGIN - 6.0,
+        max = max_total,
+        svg = svg,
+        rows = rows,
+    )
+}
+
+fn write_report<W: Write>(writer: &mut W, report: &ActivityReport, format: ActivityFormat, by_dir: bool) -> io::Result<()> {
+    match format {
+        ActivityFormat::Json => {
+            serde_json::to_writer_pretty(&mut *writer, report)?;
+            writer.write_all(b"\n")
+        }
+        ActivityFormat::Csv if by_dir => {
+            writeln!(writer, "Bucket,TopDir,Count")?;
+            for b in &report.buckets {
+                for (dir, count) in &b.by_top_dir {
+                    writeln!(writer, "{},{},{}", b.start, csv_field(dir), count)?;
+                }
+            }
+            Ok(())
+        }
+        ActivityFormat::Csv => {
+            writeln!(writer, "Bucket,Count")?;
+            for b in &report.buckets {
+                writeln!(writer, "{},{}", b.start, b.total)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+pub fn run(input: &str, out: &str, bucket: ActivityBucket, by_dir: bool, format: ActivityFormat, html: Option<&str>) -> Result<()> {
+    info!("[*] Запуск Activity");
+
+    let reader = BufReader::new(File::open(input).map_err(Error::io(format!("Не удалось открыть {}", input)))?);
+    let min_valid = NaiveDate::from_ymd_opt(MIN_VALID_YEAR, 1, 1)
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .map(|d| Utc.from_utc_datetime(&d))
+        .unwrap_or_default();
+
+    let mut histogram: BTreeMap<DateTime<Utc>, (u64, BTreeMap<String, u64>)> = BTreeMap::new();
+    let mut files_total = 0u64;
+    let mut skipped = 0u64;
+    for line in reader.lines().map_while(Result::ok) {
+        if line.trim().is_empty() { continue; }
+        let Ok(row) = serde_json::from_str::<ReportRow>(&line) else { continue };
+        if row.is_directory { continue; }
+        files_total += 1;
+
+        let created = row.created.as_deref()
+            .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+            .map(|t| t.with_timezone(&Utc))
+            .filter(|t| *t >= min_valid);
+        let Some(created) = created else {
+            skipped += 1;
+            continue;
+        };
+        let slot = histogram.entry(bucket_start(created, bucket)).or_default();
+        slot.0 += 1;
+        if by_dir {
+            *slot.1.entry(top_level_dir(&row.full_path)).or_insert(0) += 1;
+        }
+    }
+
+    let starts: Vec<DateTime<Utc>> = histogram.keys().copied().collect();
+    let report = ActivityReport {
+        source: input.to_string(),
+        bucket,
+        files_total,
+        skipped,
+        buckets: histogram.into_iter()
+            .map(|(start, (total, by_top_dir))| BucketCount { start: bucket_label(start, bucket), total, by_top_dir })
+            .collect(),
+    };
+
+    let mut writer = BufWriter::new(File::create(out).map_err(Error::io(format!("Не удалось создать {}", out)))?);
+    write_report(&mut writer, &report, format, by_dir)
+        .and_then(|_| writer.flush())
+        .map_err(Error::io(common::write_failed(out)))?;
+
+    let mut peaks: Vec<&BucketCount> = report.buckets.iter().collect();
+    peaks.sort_by(|a, b| b.total.cmp(&a.total).then(a.start.cmp(&b.start)));
+    info!("\n[+] Файлов: {} (без метки или раньше {} года: {}), корзин: {}", files_total, MIN_VALID_YEAR, skipped, report.buckets.len());
+    info!("[+] Пиковые периоды:");
+    for b in peaks.iter().take(10) {
+        info!("    {:<18} {}", b.start, b.total);
+    }
+    info!("\n[+] Результат сохранен: {}", out);
+
+    if let Some(html) = html {
+        std::fs::write(html, render_html(&report, &starts, bucket)).map_err(Error::io(common::write_failed(html)))?;
+        info!("[+] Диаграмма сохранена: {}", html);
+    }
+    Ok(())
+}
\ No newline at end of file
@@ -0,0 +1,270 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::Path;
+use std::process::Command;
+use std::sync::Arc;
+
+use rustls::crypto::ring::default_provider;
+use rustls::crypto::CryptoProvider;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName};
+use rustls::server::WebPkiClientVerifier;
+use rustls::{ClientConfig, ClientConnection, RootCertStore, ServerConfig, ServerConnection, StreamOwned};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::error::{exit_code, Error, Outcome, Result};
+use crate::i18n::common;
+use crate::mft::utils::create_temp_dir;
+use crate::models::{CaseInfo, Tags};
+
+/// Предел длины строки задания: ее присылает сетевой клиент, читать без ограничения нельзя.
+const MAX_TASK_LEN: u64 = 64 * 1024;
+
+/// Задание на сбор от контроллера (одна строка JSON).
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Task {
+    pub image: String,
+    #[serde(default)]
+    pub data: bool,
+    #[serde(default)]
+    pub case: CaseInfo,
+    #[serde(default)]
+    pub case_in_rows: bool,
+    #[serde(default)]
+    pub tags: Tags,
+}
+
+/// Заголовок кадра ответа агента (одна строка JSON).
+/// За `File` следуют ровно `size` байт содержимого; поток завершает `Done`, `Partial`
+/// (результаты отправлены, но play завершился частичным успехом) или `Error`.
+#[derive(Debug, Serialize, Deserialize)]
+pub enum Frame {
+    File { name: String, size: u64 },
+    Done,
+    Partial(String),
+    Error(String),
+}
+
+pub fn write_frame<W: Write>(w: &mut W, frame: &Frame) -> io::Result<()> {
+    serde_json::to_writer(&mut *w, frame)?;
+    w.write_all(b"\n")
+}
+
+pub fn read_frame<R: BufRead>(r: &mut R) -> io::Result<Frame> {
+    let mut line = String::new();
+    if r.read_line(&mut line)? == 0 {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, common::connection_closed()));
+    }
+    serde_json::from_str(&line).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+pub fn provider() -> Arc<CryptoProvider> {
+    Arc::new(default_provider())
+}
+
+pub fn load_certs(path: &str) -> Result<Vec<CertificateDer<'static>>> {
+    let certs: Vec<_> = CertificateDer::pem_file_iter(path)
+        .and_then(|it| it.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| Error::Invalid(common::certs_unreadable(path, e)))?;
+    if certs.is_empty() { return Err(Error::Invalid(common::no_certs(path))); }
+    Ok(certs)
+}
+
+pub fn load_key(path: &str) -> Result<PrivateKeyDer<'static>> {
+    PrivateKeyDer::from_pem_file(path)
+        .map_err(|e| Error::Invalid(common::key_unreadable(path, e)))
+}
+
+/// Хранилище доверенных CA для проверки другой стороны.
+pub fn load_roots(path: &str) -> Result<Arc<RootCertStore>> {
+    let mut roots = RootCertStore::empty();
+    for cert in load_certs(path)? {
+        roots.add(cert).map_err(|e| Error::Invalid(common::bad_ca(path, e)))?;
+    }
+    Ok(Arc::new(roots))
+}
+
+/// Серверная сторона mTLS: принимаются только клиенты с сертификатом, подписанным `ca`.
+pub fn server_config(cert: &str, key: &str, ca: &str) -> Result<Arc<ServerConfig>> {
+    let provider = provider();
+    let verifier = WebPkiClientVerifier::builder_with_provider(load_roots(ca)?, provider.clone())
+        .build()
+        .map_err(|e| Error::Invalid(common::client_verifier(e)))?;
+    let (certs, key) = (load_certs(cert)?, load_key(key)?);
+    let config = ServerConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .and_then(|b| b.with_client_cert_verifier(verifier).with_single_cert(certs, key))
+        .map_err(|e| Error::Invalid(common::tls_config(e)))?;
+    Ok(Arc::new(config))
+}
+
+/// Подключение клиента mTLS к `addr` (host:port); сертификат сервера проверяется по хосту из адреса.
+pub fn connect(addr: &str, cert: &str, key: &str, ca: &str) -> Result<StreamOwned<ClientConnection, TcpStream>> {
+    let (roots, certs, key) = (load_roots(ca)?, load_certs(cert)?, load_key(key)?);
+    let config = ClientConfig::builder_with_provider(provider())
+        .with_safe_default_protocol_versions()
+        .map(|b| b.with_root_certificates(roots))
+        .and_then(|b| b.with_client_auth_cert(certs, key))
+        .map_err(|e| Error::Invalid(common::tls_config(e)))?;
+
+    // Имя для проверки сертификата - хост из адреса (без порта)
+    let host = addr.rsplit_once(':').map(|(h, _)| h).unwrap_or(addr);
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let server_name = ServerName::try_from(host.to_string())
+        .map_err(|e| Error::Invalid(common::bad_host(host, e)))?;
+
+    let tcp = TcpStream::connect(addr)
+        .map_err(Error::io(common::connect_failed(addr)))?;
+    let conn = ClientConnection::new(Arc::new(config), server_name)
+        .map_err(io::Error::other)
+        .map_err(Error::io(common::tls_failed()))?;
+    Ok(StreamOwned::new(conn, tcp))
+}
+
+/// Выполняет задание в дочернем процессе (`play`), чтобы фатальная ошибка
+/// извлечения не останавливала агента, и отправляет результаты контроллеру.
+fn handle_task<S: Read + Write>(stream: &mut S, task: &Task) -> io::Result<()> {
+    // Каталог создается заранее со случайным именем и правами 0700: play пишет в уже существующий
+    let work_dir = create_temp_dir("mftsf-agent")?;
+    let result = run_task(stream, task, &work_dir);
+    let _ = std::fs::remove_dir_all(&work_dir);
+    result
+}
+
+fn run_task<S: Read + Write>(stream: &mut S, task: &Task, work_dir: &Path) -> io::Result<()> {
+    let exe = std::env::current_exe()?;
+
+    let mut cmd = Command::new(exe);
+    cmd.arg("play").arg("-i").arg(&task.image).arg("-o").arg(work_dir);
+    if task.data { cmd.arg("--data"); }
+    let case_args = [("--case-id", &task.case.case_id), ("--evidence-id", &task.case.evidence_id), ("--examiner", &task.case.examiner)];
+    for (flag, value) in case_args {
+        if let Some(value) = value { cmd.arg(flag).arg(value); }
+    }
+    if task.case_in_rows { cmd.arg("--case-in-rows"); }
+    for (key, value) in &task.tags {
+        cmd.arg("--tag").arg(format!("{}={}", key, value));
+    }
+    let status = cmd.status()?;
+
+    // Код 2 - отчет есть, но часть записей пропущена: обычное дело на живых томах
+    match status.code() {
+        Some(exit_code::OK) => send_dir(stream, work_dir, Outcome::Complete),
+        Some(exit_code::PARTIAL) => {
+            warn!("[!] Сбор {} завершен частично (пропущены записи или вывод)", task.image);
+            send_dir(stream, work_dir, Outcome::Partial)
+        }
+        _ => write_frame(stream, &Frame::Error(format!("сбор {} завершился с кодом {}", task.image, status))),
+    }
+}
+
+fn send_dir<W: Write>(stream: &mut W, dir: &Path, outcome: Outcome) -> io::Result<()> {
+    let mut files: Vec<_> = std::fs::read_dir(dir)?
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().is_file())
+        .collect();
+    files.sort_by_key(|e| e.file_name());
+
+    for entry in files {
+        let mut file = File::open(entry.path())?;
+        let size = file.metadata()?.len();
+        let name = entry.file_name().to_string_lossy().into_owned();
+        info!(" -> Отправка {} ({} байт)", name, size);
+        write_frame(stream, &Frame::File { name, size })?;
+        let copied = io::copy(&mut file, stream)?;
+        if copied != size {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "файл изменился во время отправки"));
+        }
+    }
+    let last = match outcome {
+        Outcome::Complete => Frame::Done,
+        Outcome::Partial => Frame::Partial("пропущены поврежденные записи или часть вывода, см. REPORT.summary.json".into()),
+    };
+    write_frame(stream, &last)?;
+    stream.flush()
+}
+
+/// Читает строку задания не длиннее `MAX_TASK_LEN` байт.
+fn read_task<R: BufRead>(r: &mut R) -> io::Result<Task> {
+    let mut line = String::new();
+    r.take(MAX_TASK_LEN).read_line(&mut line)?;
+    if !line.ends_with('\n') && line.len() as u64 >= MAX_TASK_LEN {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("задание длиннее {} байт", MAX_TASK_LEN)));
+    }
+    serde_json::from_str(&line).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn serve(config: Arc<ServerConfig>, tcp: TcpStream) -> io::Result<()> {
+    let conn = ServerConnection::new(config).map_err(io::Error::other)?;
+    let mut tls = StreamOwned::new(conn, tcp);
+
+    let task = match read_task(&mut BufReader::new(&mut tls)) {
+        Ok(task) => task,
+        Err(e) => {
+            // Контроллер узнает причину отказа, а не просто обрыв соединения
+            write_frame(&mut tls, &Frame::Error(e.to_string()))?;
+            tls.conn.send_close_notify();
+            tls.flush()?;
+            return Err(e);
+        }
+    };
+    info!("[*] Задание: {} (data: {})", task.image, task.data);
+
+    handle_task(&mut tls, &task)?;
+    tls.conn.send_close_notify();
+    tls.flush()
+}
+
+pub fn run(listen: &str, cert: &str, key: &str, ca: &str) -> Result<()> {
+    info!("[*] Запуск Agent (mTLS)");
+
+    let config = server_config(cert, key, ca)?;
+
+    let listener = TcpListener::bind(listen)
+        .map_err(Error::io(format!("Не удалось слушать {}", listen)))?;
+    info!("[*] Ожидание заданий на {}", listen);
+
+    // Задания выполняются по одному: снятие MFT само по себе нагружает диск
+    for tcp in listener.incoming() {
+        let tcp = match tcp {
+            Ok(t) => t,
+            Err(e) => { warn!("[!] Ошибка входящего соединения: {}", e); continue; }
+        };
+        let peer = tcp.peer_addr().map(|a| a.to_string()).unwrap_or_default();
+        info!("[*] Подключение контроллера: {}", peer);
+        if let Err(e) = serve(config.clone(), tcp) {
+            warn!("[!] Задание от {} прервано: {}", peer, e);
+        }
+    }
+    Ok(())
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn task_line_is_bounded() {
+        let task = read_task(&mut &b"{\"image\":\"C:\",\"data\":true}\n"[..]).unwrap();
+        assert_eq!((task.image.as_str(), task.data), ("C:", true));
+
+        let huge = format!("{{\"image\":\"{}\"}}\n", "A".repeat(MAX_TASK_LEN as usize));
+        let err = read_task(&mut huge.as_bytes()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("65536"));
+    }
+
+    #[test]
+    fn work_dir_is_private_and_unique() {
+        let (a, b) = (create_temp_dir("mftsf-agent").unwrap(), create_temp_dir("mftsf-agent").unwrap());
+        assert_ne!(a, b);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(std::fs::metadata(&a).unwrap().permissions().mode() & 0o777, 0o700);
+        }
+        std::fs::remove_dir(a).unwrap();
+        std::fs::remove_dir(b).unwrap();
+    }
+}
@@ -0,0 +1,263 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::time::Instant;
+
+use byteorder::{ByteOrder, LittleEndian};
+use serde::Serialize;
+use tracing::info;
+
+use super::parse::{self, ParseOptions};
+use crate::error::{Error, Result};
+use crate::i18n::common;
+use crate::mft::utils::create_temp_file;
+use crate::output;
+
+const RECORD_SIZE: usize = 1024;
+const SECTOR_SIZE: usize = 512;
+/// Смещение массива USA и первого атрибута (раскладка NTFS 3.1).
+const USA_OFFSET: usize = 0x30;
+const FIRST_ATTRIBUTE: usize = 0x38;
+/// Файлов в одном синтетическом каталоге.
+const FILES_PER_DIR: u64 = 1000;
+/// 2024-01-01T00:00:00Z в FILETIME: от нее отсчитываются метки синтетических записей.
+const BASE_FILETIME: u64 = 133_485_408_000_000_000;
+const EXTENSIONS: [&str; 6] = ["txt", "exe", "dll", "log", "docx", "ps1"];
+
+/// Один прогон разбора.
+#[derive(Serialize)]
+struct BenchRun {
+    seconds: f64,
+    records_per_sec: f64,
+    mb_per_sec: f64,
+}
+
+/// Итог bench: окружение, размер синтетического MFT и скорость прогонов.
+#[derive(Serialize)]
+struct BenchReport {
+    version: &'static str,
+    os: &'static str,
+    arch: &'static str,
+    cpus: usize,
+    records: u64,
+    mft_bytes: u64,
+    entries: u64,
+    runs: Vec<BenchRun>,
+    best_records_per_sec: f64,
+    best_mb_per_sec: f64,
+}
+
+/// Резидентный атрибут: заголовок 24 байта и значение, выровненные на 8.
+fn push_resident(record: &mut Vec<u8>, attr_type: u32, attr_id: u16, indexed: bool, value: &[u8]) {
+    let len = (24 + value.len()).next_multiple_of(8);
+    let start = record.len();
+    record.resize(start + len, 0);
+    let attr = &mut record[start..];
+    LittleEndian::write_u32(&mut attr[0..4], attr_type);
+    LittleEndian::write_u32(&mut attr[4..8], len as u32);
+    LittleEndian::write_u16(&mut attr[14..16], attr_id);
+    LittleEndian::write_u32(&mut attr[16..20], value.len() as u32);
+    LittleEndian::write_u16(&mut attr[20..22], 24);
+    attr[22] = indexed as u8;
+    attr[24..24 + value.len()].copy_from_slice(value);
+}
+
+/// Синтетическая запись: $STANDARD_INFORMATION, $FILE_NAME (Win32) и у файлов резидентный $DATA.
+fn synthetic_record(entry: u64, parent: u64, name: &str, dir: bool, in_use: bool) -> Vec<u8> {
+    let mut record = vec![0u8; FIRST_ATTRIBUTE];
+    record[0..4].copy_from_slice(b"FILE");
+    LittleEndian::write_u16(&mut record[4..6], USA_OFFSET as u16);
+    LittleEndian::write_u16(&mut record[6..8], (RECORD_SIZE / SECTOR_SIZE + 1) as u16);
+    LittleEndian::write_u64(&mut record[8..16], 0x1000 + entry);
+    LittleEndian::write_u16(&mut record[16..18], 1);
+    LittleEndian::write_u16(&mut record[18..20], 1);
+    LittleEndian::write_u16(&mut record[20..22], FIRST_ATTRIBUTE as u16);
+    LittleEndian::write_u16(&mut record[22..24], in_use as u16 | (dir as u16) << 1);
+    LittleEndian::write_u32(&mut record[28..32], RECORD_SIZE as u32);
+    LittleEndian::write_u16(&mut record[40..42], 3);
+    LittleEndian::write_u32(&mut record[44..48], entry as u32);
+
+    let time = BASE_FILETIME + entry * 10_000_000;
+    let file_attributes: u32 = if dir { 0x10 } else { 0x20 };
+    let mut si = [0u8; 72];
+    for i in 0..4 {
+        LittleEndian::write_u64(&mut si[i * 8..i * 8 + 8], time);
+    }
+    LittleEndian::write_u32(&mut si[32..36], file_attributes);
+    LittleEndian::write_u32(&mut si[52..56], 0x100);
+    push_resident(&mut record, 0x10, 0, false, &si);
+
+    let data = format!("synthetic content of record {}", entry).into_bytes();
+    let size = if dir { 0 } else { data.len() as u64 };
+    let name_utf16: Vec<u16> = name.encode_utf16().collect();
+    let mut fn_value = vec![0u8; 66 + name_utf16.len() * 2];
+    LittleEndian::write_u64(&mut fn_value[0..8], parent | 1 << 48);
+    for i in 0..4 {
+        LittleEndian::write_u64(&mut fn_value[8 + i * 8..16 + i * 8], time);
+    }
+    LittleEndian::write_u64(&mut fn_value[40..48], size.next_multiple_of(8));
+    LittleEndian::write_u64(&mut fn_value[48..56], size);
+    LittleEndian::write_u32(&mut fn_value[56..60], file_attributes | if dir { 0x1000_0000 } else { 0 });
+    fn_value[64] = name_utf16.len() as u8;
+    fn_value[65] = 1;
+    for (i, c) in name_utf16.iter().enumerate() {
+        LittleEndian::write_u16(&mut fn_value[66 + i * 2..68 + i * 2], *c);
+    }
+    push_resident(&mut record, 0x30, 1, true, &fn_value);
+    if !dir {
+        push_resident(&mut record, 0x80, 2, false, &data);
+    }
+    record.extend_from_slice(&[0xFF, 0xFF, 0xFF, 0xFF, 0, 0, 0, 0]);
+    let real_size = record.len() as u32;
+    LittleEndian::write_u32(&mut record[24..28], real_size);
+    record.resize(RECORD_SIZE, 0);
+
+    // Fixups: хвост каждого сектора уходит в USA, на его место - USN
+    let usn = [0x01, 0x00];
+    record[USA_OFFSET..USA_OFFSET + 2].copy_from_slice(&usn);
+    for i in 1..=RECORD_SIZE / SECTOR_SIZE {
+        let tail = i * SECTOR_SIZE - 2;
+        record.copy_within(tail..tail + 2, USA_OFFSET + i * 2);
+        record[tail..tail + 2].copy_from_slice(&usn);
+    }
+    record
+}
+
+/// Синтетический MFT для тестов: записи 0..16 пусты, кроме корня (5), дальше - `files`
+/// по порядку как (родитель, имя, каталог, используется).
+#[cfg(test)]
+pub(crate) fn synthetic_mft(files: &[(u64, &str, bool, bool)]) -> Vec<u8> {
+    let mut mft = Vec::new();
+    for entry in 0..16 {
+        match entry {
+            5 => mft.extend(synthetic_record(5, 5, ".", true, true)),
+            _ => mft.resize(mft.len() + RECORD_SIZE, 0),
+        }
+    }
+    for (i, &(parent, name, dir, in_use)) in files.iter().enumerate() {
+        mft.extend(synthetic_record(16 + i as u64, parent, name, dir, in_use));
+    }
+    mft
+}
+
+/// Пишет синтетический MFT из `records` записей: корень (5), каталоги по `FILES_PER_DIR`
+/// файлов с разными расширениями; каждая 20-я запись файла помечена удаленной.
+fn generate(file: File, records: u64) -> io::Result<()> {
+    let mut out = BufWriter::new(file);
+    let mut current_dir = 5;
+    for entry in 0..records {
+        let record = match entry {
+            5 => synthetic_record(5, 5, ".", true, true),
+            0..16 => vec![0u8; RECORD_SIZE],
+            _ if (entry - 16).is_multiple_of(FILES_PER_DIR + 1) => {
+                current_dir = entry;
+                synthetic_record(entry, 5, &format!("dir{:06}", entry), true, true)
+            }
+            _ => {
+                let name = format!("file{:08}.{}", entry, EXTENSIONS[(entry % EXTENSIONS.len() as u64) as usize]);
+                synthetic_record(entry, current_dir, &name, false, !entry.is_multiple_of(20))
+            }
+        };
+        out.write_all(&record)?;
+    }
+    out.flush()
+}
+
+/// Генерирует синтетический MFT в `file` и разбирает его `iterations` раз; возвращает
+/// прогоны и число строк отчета.
+fn measure(path: &str, file: File, records: u64, iterations: usize) -> Result<(Vec<BenchRun>, u64)> {
+    let started = Instant::now();
+    generate(file, records).map_err(Error::io(common::write_failed(path)))?;
+    let mft_bytes = records * RECORD_SIZE as u64;
+    info!("[*] Синтетический MFT: {} МБ за {:.2} с", mft_bytes / 1024 / 1024, started.elapsed().as_secs_f64());
+
+    let mut runs = Vec::new();
+    let mut entries = 0;
+    for i in 1..=iterations {
+        let started = Instant::now();
+        let mut count = 0u64;
+        parse::scan_entries(path, &ParseOptions::default(), |entry| {
+            let _ = serde_json::to_writer(io::sink(), &entry);
+            count += 1;
+        }).map_err(Error::io(format!("Ошибка разбора {}", path)))?;
+        let seconds = started.elapsed().as_secs_f64().max(f64::EPSILON);
+        let run = BenchRun {
+            seconds,
+            records_per_sec: records as f64 / seconds,
+            mb_per_sec: mft_bytes as f64 / 1024.0 / 1024.0 / seconds,
+        };
+        info!("[*] Прогон {}: {:.2} с, {:.0} записей/с, {:.1} МБ/с", i, run.seconds, run.records_per_sec, run.mb_per_sec);
+        runs.push(run);
+        entries = count;
+    }
+    Ok((runs, entries))
+}
+
+/// Замер скорости разбора: синтетический MFT во временном файле разбирается `iterations` раз
+/// (оба прохода, сборка строк и сериализация в JSON без записи на диск).
+pub fn run(records: u64, iterations: usize, out_json: Option<&str>) -> Result<()> {
+    info!("[*] Запуск Bench: {} записей, прогонов: {}", records, iterations);
+    // Случайное имя и create_new: в общем /tmp по предсказуемому имени могли подложить ссылку
+    let (path, file) = create_temp_file("mft_shadow_forge_bench", "raw")
+        .map_err(Error::io(common::create_failed(std::env::temp_dir().display())))?;
+    let path = path.to_string_lossy().into_owned();
+    let result = measure(&path, file, records, iterations);
+    let _ = std::fs::remove_file(&path);
+    let (runs, entries) = result?;
+    let mft_bytes = records * RECORD_SIZE as u64;
+
+    let (best_records_per_sec, best_mb_per_sec) = runs.iter()
+        .min_by(|a, b| a.seconds.total_cmp(&b.seconds))
+        .map(|best| (best.records_per_sec, best.mb_per_sec))
+        .unwrap_or_default();
+    let report = BenchReport {
+        version: env!("CARGO_PKG_VERSION"),
+        os: std::env::consts::OS,
+        arch: std::env::consts::ARCH,
+        cpus: std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+        records,
+        mft_bytes,
+        entries,
+        runs,
+        best_records_per_sec,
+        best_mb_per_sec,
+    };
+    info!("[+] Лучший прогон: {:.0} записей/с, {:.1} МБ/с (строк отчета: {}, версия {}, {} {}, CPU: {})",
+        report.best_records_per_sec, report.best_mb_per_sec, report.entries, report.version, report.os, report.arch, report.cpus);
+
+    if let Some(out_json) = out_json {
+        output::write_json_file(out_json, &report).map_err(Error::io(common::write_failed(out_json)))?;
+        info!("[+] Результат сохранен: {}", out_json);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn synthetic_mft_parses_and_temp_file_is_removed() {
+        let (path, file) = create_temp_file("mft_shadow_forge_test", "raw").unwrap();
+        let path = path.to_string_lossy().into_owned();
+        let (runs, entries) = measure(&path, file, 2100, 2).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(runs.len(), 2);
+        // Корень, два каталога и файлы; записи 0..16 кроме корня пусты
+        assert_eq!(entries, 2100 - 15);
+    }
+
+    #[test]
+    fn report_is_written_and_scratch_file_removed() {
+        let (out, _) = create_temp_file("mft_shadow_forge_test", "json").unwrap();
+        let out = out.to_string_lossy().into_owned();
+        run(200, 1, Some(&out)).unwrap();
+        let report: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&out).unwrap()).unwrap();
+        std::fs::remove_file(&out).unwrap();
+        assert_eq!(report["records"], 200);
+        let leftovers = std::fs::read_dir(std::env::temp_dir()).unwrap()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_name().to_string_lossy().starts_with(&format!("mft_shadow_forge_bench_{}_", std::process::id())))
+            .count();
+        assert_eq!(leftovers, 0);
+    }
+}
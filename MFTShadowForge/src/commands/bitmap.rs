@@ -0,0 +1,153 @@
+use std::fs::File;
+use std::io::{BufWriter, Read, Seek};
+
+use serde::Serialize;
+use tracing::info;
+
+use super::extract::{self, DataRun, PartitionSelect};
+use super::volume::{self, StreamReader, VolumeRecords};
+use crate::audit;
+use crate::error::{Error, Result};
+use crate::i18n::common;
+use crate::image;
+use crate::models::{CaseInfo, Tags};
+use crate::output::JsonlWriter;
+
+/// Запись $Bitmap - карта занятости кластеров тома.
+const BITMAP_RECORD: u64 = 6;
+
+/// Можно ли вернуть содержимое удаленного файла.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum RecoveryStatus {
+    /// Все кластеры свободны - данные на месте, если их не затерли без выделения
+    Recoverable,
+    /// Часть кластеров уже выделена другим файлам
+    Partial,
+    /// Все кластеры выделены заново
+    Reused,
+    /// Данные внутри записи MFT - целы, пока цела запись
+    Resident,
+    /// Нет $DATA или пустой поток
+    NoData,
+    /// Запись или runlist не читается
+    Unknown,
+}
+
+/// Строка отчета bitmap: удаленный файл и занятость его кластеров.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct BitmapEntry {
+    pub entry_number: u64,
+    pub sequence_number: u16,
+    #[serde(rename = "Full_Path")]
+    pub full_path: String,
+    pub file_size: u64,
+    pub clusters: u64,
+    pub free_clusters: u64,
+    pub allocated_clusters: u64,
+    pub status: RecoveryStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Карта $Bitmap: бит на кластер, младший бит байта - младший кластер.
+pub struct ClusterBitmap {
+    bits: Vec<u8>,
+}
+
+impl ClusterBitmap {
+    /// Кластер занят. Кластеры за концом карты считаются занятыми - вернуть их нельзя.
+    pub fn is_allocated(&self, lcn: u64) -> bool {
+        self.bits.get((lcn / 8) as usize).is_none_or(|b| b & (1 << (lcn % 8)) != 0)
+    }
+
+    /// Свободные и занятые кластеры нерезидентных участков runlist (разреженные не считаются).
+    pub fn count(&self, runs: &[DataRun]) -> (u64, u64) {
+        let (mut free, mut allocated) = (0u64, 0u64);
+        for run in runs.iter().filter(|r| !r.is_sparse) {
+            for lcn in run.lcn..run.lcn + run.length {
+                if self.is_allocated(lcn) { allocated += 1 } else { free += 1 }
+            }
+        }
+        (free, allocated)
+    }
+}
+
+/// Читает $Bitmap тома целиком.
+fn read_bitmap<R: Read + Seek>(src: &mut VolumeRecords<R>) -> Result<ClusterBitmap, String> {
+    let buffers = volume::read_record(src, BITMAP_RECORD)?;
+    let stream = volume::data_stream(&buffers, src.layout.record_size, "")?;
+    if let Some(bits) = stream.resident {
+        return Ok(ClusterBitmap { bits });
+    }
+    let mut bits = Vec::with_capacity(stream.size as usize);
+    StreamReader::new(src.vol, src.layout, &stream, false).read_to_end(&mut bits).map_err(|e| e.to_string())?;
+    Ok(ClusterBitmap { bits })
+}
+
+/// Сверка удаленных файлов тома с $Bitmap: для каждой удаленной записи MFT - сколько
+/// кластеров ее $DATA сейчас свободно (файл можно вернуть) и сколько уже занято.
+pub fn run(image: &str, out_jsonl: &str, partition: Option<PartitionSelect>) -> Result<()> {
+    info!("[*] Запуск Bitmap");
+    let volume_path = extract::volume_path(image);
+    let mut vol = image::open(&volume_path).map_err(Error::io(format!("Ошибка открытия {}", volume_path)))?;
+    let layout = extract::locate_mft_at(&mut vol, partition)?;
+
+    let bitmap = read_bitmap(&mut VolumeRecords { vol: &mut vol, layout: &layout })
+        .map_err(|e| Error::Corrupt(format!("$Bitmap: {}", e)))?;
+    let free: u64 = bitmap.bits.iter().map(|b| b.count_zeros() as u64).sum();
+    info!("[*] $Bitmap: свободно {} кластеров из {}", free, bitmap.bits.len() * 8);
+
+    info!("[*] Проход 1: дерево путей и удаленные записи...");
+    let mut deleted = Vec::new();
+    let path_builder = volume::index_volume(&mut vol, &layout, |entry_num, header, _| {
+        if !header.is_in_use() && !header.is_directory() {
+            deleted.push((entry_num, header.sequence_number));
+        }
+    });
+    info!("[*] Удаленных файлов: {}", deleted.len());
+
+    info!("[*] Проход 2: сверка кластеров с $Bitmap...");
+    let out = File::create(out_jsonl).map_err(Error::io(format!("Не удалось создать {}", out_jsonl)))?;
+    let mut writer = JsonlWriter::new(BufWriter::new(out));
+    let mut src = VolumeRecords { vol: &mut vol, layout: &layout };
+    let mut totals: Vec<(RecoveryStatus, u64)> = Vec::new();
+    for (entry_num, seq) in deleted {
+        let mut row = BitmapEntry {
+            entry_number: entry_num, sequence_number: seq, full_path: path_builder.get_full_path(entry_num, seq),
+            file_size: 0, clusters: 0, free_clusters: 0, allocated_clusters: 0, status: RecoveryStatus::Unknown, error: None,
+        };
+        match volume::read_record(&mut src, entry_num).and_then(|b| volume::data_stream(&b, layout.record_size, "")) {
+            Ok(stream) if stream.resident.is_some() => {
+                row.file_size = stream.size;
+                row.status = RecoveryStatus::Resident;
+            }
+            Ok(stream) => {
+                let (free, allocated) = bitmap.count(&stream.runs);
+                row.file_size = stream.size;
+                row.clusters = free + allocated;
+                row.free_clusters = free;
+                row.allocated_clusters = allocated;
+                row.status = match (free, allocated) {
+                    (0, 0) => RecoveryStatus::NoData,
+                    (_, 0) => RecoveryStatus::Recoverable,
+                    (0, _) => RecoveryStatus::Reused,
+                    _ => RecoveryStatus::Partial,
+                };
+            }
+            Err(e) if e.starts_with("нет потока") => row.status = RecoveryStatus::NoData,
+            Err(e) => row.error = Some(e),
+        }
+        match totals.iter_mut().find(|(s, _)| *s == row.status) {
+            Some((_, n)) => *n += 1,
+            None => totals.push((row.status, 1)),
+        }
+        writer.write(&row).map_err(Error::io(common::write_failed(out_jsonl)))?;
+    }
+    writer.flush().map_err(Error::io(common::write_failed(out_jsonl)))?;
+    totals.sort_by_key(|(_, n)| std::cmp::Reverse(*n));
+    let summary: Vec<String> = totals.iter().map(|(s, n)| format!("{:?}: {}", s, n)).collect();
+    info!("[+] {} -> {}", if summary.is_empty() { "удаленных файлов нет".to_string() } else { summary.join(", ") }, out_jsonl);
+    audit::append("bitmap", &volume_path, out_jsonl, &CaseInfo::default(), &Tags::new());
+    Ok(())
+}
\ No newline at end of file
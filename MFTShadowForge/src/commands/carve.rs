@@ -0,0 +1,175 @@
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Seek, SeekFrom};
+
+use byteorder::{ByteOrder, LittleEndian};
+use serde_json::Value;
+use tracing::{info, warn};
+
+use super::extract::{self, validate_record_boundaries};
+use super::parse::{build_entry, index_record, ParseContext, ParseOptions};
+use crate::audit;
+use crate::error::{Error, Result};
+use crate::image;
+use crate::mft::parser::{apply_fixups, FixupResult};
+use crate::mft::path_builder::PathBuilder;
+use crate::mft::record::MftRecordHeader;
+use crate::models::{CaseInfo, Tags};
+use crate::output::JsonlWriter;
+
+/// Шаг поиска сигнатуры: записи MFT всегда начинаются на границе сектора.
+const SCAN_STEP: usize = 512;
+/// Объем одного чтения образа при сканировании.
+const SCAN_CHUNK: usize = 4 * 1024 * 1024;
+/// Наибольший размер записи MFT; на столько чтения перекрываются, чтобы не терять записи на стыке.
+const MAX_RECORD_SIZE: usize = 4096;
+/// Чтение устройств выравнивается по границе, кратной любому размеру сектора.
+const DEVICE_ALIGN: u64 = 4096;
+/// Как часто сообщать о ходе сканирования.
+const PROGRESS_EVERY: u64 = 1024 * 1024 * 1024;
+
+/// Найденная запись: где лежит и как ее перечитать.
+struct Carved {
+    offset: u64,
+    entry: u64,
+    record_size: usize,
+}
+
+/// Размер записи и сектора по заголовку: allocated_size - размер записи,
+/// массив USA содержит по слову на сектор плюс номер последовательности.
+fn record_geometry(header: &MftRecordHeader) -> Option<(usize, u16)> {
+    let record_size = header.allocated_size as usize;
+    if !(1024..=MAX_RECORD_SIZE).contains(&record_size) || !record_size.is_power_of_two() { return None; }
+    let sectors = header.update_sequence_size.checked_sub(1)? as usize;
+    if sectors == 0 || !record_size.is_multiple_of(sectors) { return None; }
+    let bytes_per_sector = record_size / sectors;
+    if !(256..=4096).contains(&bytes_per_sector) || !bytes_per_sector.is_power_of_two() { return None; }
+    Some((record_size, bytes_per_sector as u16))
+}
+
+/// Проверяет кандидата с сигнатурой FILE: границы заголовка, fixups и номер записи
+/// (поле 0x2C, есть только у записей NTFS 3.1 с массивом USA по смещению 0x30).
+fn check_candidate(data: &[u8]) -> Option<(Vec<u8>, MftRecordHeader, Carved, bool)> {
+    let header = MftRecordHeader::parse(data)?;
+    if header.signature != "FILE" { return None; }
+    let (record_size, bytes_per_sector) = record_geometry(&header)?;
+    if data.len() < record_size || validate_record_boundaries(&header, record_size, false).is_err() { return None; }
+    if header.update_sequence_offset < 0x30 { return None; }
+
+    let mut record = data[..record_size].to_vec();
+    let fixup = apply_fixups(&mut record, &header, bytes_per_sector);
+    if fixup == FixupResult::Failed { return None; }
+    let entry = LittleEndian::read_u32(&record[0x2C..0x30]) as u64;
+    Some((record, header, Carved { offset: 0, entry, record_size }, fixup == FixupResult::TornWrite))
+}
+
+/// Дочитывает буфер до конца или до конца образа; возвращает число прочитанных байт.
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            // Устройства Windows отвечают ошибкой на чтение за концом тома
+            Err(_) if filled > 0 => break,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+/// Читает `len` байт по смещению `offset` блоками, выровненными по DEVICE_ALIGN.
+fn read_at<R: Read + Seek>(reader: &mut R, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+    let start = offset - offset % DEVICE_ALIGN;
+    let end = (offset + len as u64).next_multiple_of(DEVICE_ALIGN);
+    let mut buf = vec![0u8; (end - start) as usize];
+    reader.seek(SeekFrom::Start(start))?;
+    let filled = read_full(reader, &mut buf)?;
+    let skip = (offset - start) as usize;
+    if filled < skip + len {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "запись за концом образа"));
+    }
+    Ok(buf[skip..skip + len].to_vec())
+}
+
+/// Проход 1: все кандидаты FILE по образу; имена базовых записей - в дерево путей.
+fn scan_image<R: Read + Seek>(img: &mut R, ctx: &mut ParseContext, path_builder: &mut PathBuilder) -> Result<Vec<Carved>> {
+    let mut found = Vec::new();
+    let mut buf = vec![0u8; SCAN_CHUNK + MAX_RECORD_SIZE];
+    let mut base = 0u64;
+    let mut filled = read_full(img, &mut buf).map_err(Error::io("Ошибка чтения образа"))?;
+    let mut next_progress = PROGRESS_EVERY;
+    loop {
+        let scan_end = if filled == buf.len() { SCAN_CHUNK } else { filled };
+        for pos in (0..scan_end).step_by(SCAN_STEP) {
+            if pos + 4 > filled || &buf[pos..pos + 4] != b"FILE" { continue; }
+            let Some((record, header, mut carved, _)) = check_candidate(&buf[pos..filled]) else { continue };
+            carved.offset = base + pos as u64;
+            if header.base_record_reference == 0 {
+                ctx.record_size = carved.record_size;
+                index_record(ctx, path_builder, carved.entry, &header, &[Cow::from(record)]);
+            }
+            found.push(carved);
+        }
+        if filled < buf.len() { break; }
+
+        buf.copy_within(SCAN_CHUNK.., 0);
+        base += SCAN_CHUNK as u64;
+        match read_full(img, &mut buf[MAX_RECORD_SIZE..]) {
+            Ok(n) => filled = MAX_RECORD_SIZE + n,
+            Err(e) => {
+                warn!("[!] Ошибка чтения по смещению {:#X}: {} - сканирование остановлено", base + MAX_RECORD_SIZE as u64, e);
+                filled = MAX_RECORD_SIZE;
+            }
+        }
+        if base >= next_progress {
+            info!("[*] Просканировано {} ГБ, найдено записей: {}", base / (1024 * 1024 * 1024), found.len());
+            next_progress += PROGRESS_EVERY;
+        }
+    }
+    Ok(found)
+}
+
+/// Поиск записей FILE по всему образу (в том числе вне runlist $MFT: неразмеченное
+/// пространство, старые копии $MFT, файлы подкачки и гибернации). Запись проходит
+/// проверку границ заголовка и fixups; строки отчета помечаются `Carved` и `ImageOffset`.
+pub fn run(image: &str, out_jsonl: &str, data: bool) -> Result<()> {
+    info!("[*] Запуск Carve");
+    let image_path = extract::volume_path(image);
+    let mut img = image::open(&image_path).map_err(Error::io(format!("Ошибка открытия {}", image_path)))?;
+
+    let opts = ParseOptions { data, ..Default::default() };
+    // Размер записи задается заголовком каждой найденной записи и меняется в контексте по ходу
+    let mut ctx = ParseContext::standalone(&opts, &image_path, String::new(), MAX_RECORD_SIZE);
+    let mut path_builder = PathBuilder::new();
+
+    info!("[*] Проход 1: поиск сигнатур FILE в {}...", image_path);
+    let found = scan_image(&mut img, &mut ctx, &mut path_builder)?;
+    info!("[*] Найдено записей: {}", found.len());
+
+    info!("[*] Проход 2: разбор найденных записей...");
+    let out = File::create(out_jsonl).map_err(Error::io(format!("Не удалось создать {}", out_jsonl)))?;
+    let mut writer = JsonlWriter::new(BufWriter::new(out));
+    let mut written = 0u64;
+    for carved in &found {
+        let raw = match read_at(&mut img, carved.offset, carved.record_size) {
+            Ok(raw) => raw,
+            Err(e) => { warn!("[!] Ошибка чтения записи по смещению {:#X}: {}", carved.offset, e); continue; }
+        };
+        let Some((record, header, _, torn_write)) = check_candidate(&raw) else { continue };
+        if header.base_record_reference != 0 { continue; }
+
+        ctx.record_size = carved.record_size;
+        let mut entry = build_entry(&ctx, &path_builder, carved.entry, &header, &[Cow::from(record)], torn_write, false);
+        entry.plugin_fields.insert("Carved".into(), Value::Bool(true));
+        entry.plugin_fields.insert("ImageOffset".into(), Value::from(carved.offset));
+        if writer.write(&entry).is_ok() {
+            written += 1;
+        }
+    }
+    writer.flush().map_err(Error::io(format!("Ошибка записи {}", out_jsonl)))?;
+    info!("[+] Записано {} записей (расширения без базовой записи не выводятся): {}", written, out_jsonl);
+    audit::append("carve", &image_path, out_jsonl, &CaseInfo::default(), &Tags::new());
+    Ok(())
+}
\ No newline at end of file
@@ -0,0 +1,56 @@
+use std::fs::File;
+use std::io::{self, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+
+use rustls::{ClientConnection, StreamOwned};
+use tracing::{info, warn};
+
+use super::agent::{self, read_frame, Frame, Task};
+use crate::error::{Error, Outcome, Result};
+
+fn receive(tls: &mut StreamOwned<ClientConnection, TcpStream>, out_dir: &Path) -> io::Result<(Vec<PathBuf>, Outcome)> {
+    let mut reader = BufReader::new(tls);
+    let mut received = Vec::new();
+    loop {
+        match read_frame(&mut reader)? {
+            Frame::File { name, size } => {
+                // Агенту не даем писать за пределы папки результатов
+                let file_name = Path::new(&name).file_name()
+                    .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("некорректное имя файла: {}", name)))?;
+                let path = out_dir.join(file_name);
+                let mut out = File::create(&path)?;
+                let copied = io::copy(&mut (&mut reader).take(size), &mut out)?;
+                if copied != size {
+                    return Err(io::Error::new(io::ErrorKind::UnexpectedEof, format!("{}: получено {} из {} байт", name, copied, size)));
+                }
+                info!(" -> Получен {} ({} байт)", name, size);
+                received.push(path);
+            }
+            Frame::Done => return Ok((received, Outcome::Complete)),
+            Frame::Partial(reason) => {
+                warn!("[!] Агент: сбор завершен частично: {}", reason);
+                return Ok((received, Outcome::Partial));
+            }
+            Frame::Error(e) => return Err(io::Error::other(format!("агент: {}", e))),
+        }
+    }
+}
+
+pub fn run(agent_addr: &str, cert: &str, key: &str, ca: &str, out_dir: &str, task: &Task) -> Result<Outcome> {
+    info!("[*] Запуск Collect: {} -> {}", agent_addr, task.image);
+
+    let mut tls = agent::connect(agent_addr, cert, key, ca)?;
+
+    std::fs::create_dir_all(out_dir)
+        .map_err(Error::io(format!("Не удалось создать {}", out_dir)))?;
+
+    serde_json::to_writer(&mut tls, task).map_err(io::Error::from)
+        .and_then(|_| tls.write_all(b"\n"))
+        .and_then(|_| tls.flush())
+        .map_err(Error::io("Не удалось отправить задание"))?;
+
+    let (files, outcome) = receive(&mut tls, Path::new(out_dir)).map_err(Error::io("Сбор прерван"))?;
+    info!("\n[+] Сбор завершен: {} файлов в {}", files.len(), out_dir);
+    Ok(outcome)
+}
\ No newline at end of file
@@ -0,0 +1,360 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+use serde::Serialize;
+use serde_json::Value;
+use tracing::info;
+
+use super::parse::{self, ParseOptions};
+use crate::error::{Error, Result};
+use crate::i18n::common;
+use crate::models::MftEntry;
+use crate::output;
+use crate::rules::reparse::is_system_path;
+
+/// Поля отчета, изменение которых считается изменением метаданных.
+/// LastAccess не сравнивается: он меняется при любом чтении и дает только шум.
+const COMPARED_FIELDS: [&str; 15] = [
+    "SequenceNumber", "InUse", "IsDirectory", "FileSize",
+    "Created0x10", "LastModified0x10", "LastRecordChange0x10",
+    "Created0x30", "LastModified0x30", "LastRecordChange0x30",
+    "SiFlags", "SecurityId", "HasAds", "ZoneIdContents", "ReparseTarget",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+enum Change {
+    Added,
+    Removed,
+    Modified,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct FieldChange {
+    old: Value,
+    new: Value,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct DiffRecord {
+    change: Change,
+    system_path: bool,
+    entry_number: u64,
+    #[serde(rename = "Full_Path")]
+    full_path: String,
+    changed_fields: BTreeMap<String, FieldChange>,
+}
+
+/// Сравниваемая часть строки отчета.
+struct Snapshot {
+    full_path: String,
+    fields: Vec<Value>,
+}
+
+/// Ключ записи: номер записи + путь без учета регистра.
+/// Переиспользованная под другой файл запись дает пару Removed/Added, а не Modified.
+fn read_rows<F: FnMut((u64, String), Snapshot)>(input: &str, mut f: F) -> Result<()> {
+    let reader = BufReader::new(File::open(input).map_err(Error::io(format!("Не удалось открыть {}", input)))?);
+    for line in reader.lines().map_while(Result::ok) {
+        if line.trim().is_empty() { continue; }
+        let Ok(row) = serde_json::from_str::<Value>(&line) else { continue };
+        let Some(entry) = row.get("EntryNumber").and_then(Value::as_u64) else { continue };
+        let full_path = row.get("Full_Path").and_then(Value::as_str).unwrap_or("").to_string();
+        let fields = COMPARED_FIELDS.iter().map(|k| row.get(*k).cloned().unwrap_or(Value::Null)).collect();
+        f((entry, full_path.to_lowercase()), Snapshot { full_path, fields });
+    }
+    Ok(())
+}
+
+pub fn run(baseline: &str, current: &str, out_jsonl: &str, system_only: bool, top: usize) -> Result<()> {
+    info!("[*] Запуск Diff");
+    info!(" -> Базовый отчет: {}", baseline);
+    info!(" -> Текущий отчет: {}", current);
+
+    let mut base: HashMap<(u64, String), Snapshot> = HashMap::new();
+    read_rows(baseline, |key, snap| { base.insert(key, snap); })?;
+
+    let mut changes: Vec<DiffRecord> = Vec::new();
+    read_rows(current, |key, snap| {
+        let entry_number = key.0;
+        match base.remove(&key) {
+            None => changes.push(DiffRecord {
+                change: Change::Added, system_path: is_system_path(&snap.full_path),
+                entry_number, full_path: snap.full_path, changed_fields: BTreeMap::new(),
+            }),
+            Some(old) => {
+                let changed_fields: BTreeMap<String, FieldChange> = COMPARED_FIELDS.iter()
+                    .zip(old.fields.into_iter().zip(snap.fields))
+                    .filter(|(_, (o, n))| o != n)
+                    .map(|(name, (old, new))| (name.to_string(), FieldChange { old, new }))
+                    .collect();
+                if !changed_fields.is_empty() {
+                    changes.push(DiffRecord {
+                        change: Change::Modified, system_path: is_system_path(&snap.full_path),
+                        entry_number, full_path: snap.full_path, changed_fields,
+                    });
+                }
+            }
+        }
+    })?;
+    changes.extend(base.into_iter().map(|((entry_number, _), old)| DiffRecord {
+        change: Change::Removed, system_path: is_system_path(&old.full_path),
+        entry_number, full_path: old.full_path, changed_fields: BTreeMap::new(),
+    }));
+
+    if system_only {
+        changes.retain(|c| c.system_path);
+    }
+    // Системные пути - в начало: ради них обычно и запускается контроль целостности
+    changes.sort_by(|a, b| b.system_path.cmp(&a.system_path)
+        .then(a.change.cmp(&b.change))
+        .then(a.full_path.cmp(&b.full_path)));
+
+    output::write_jsonl_file(out_jsonl, &changes).map_err(Error::io(common::write_failed(out_jsonl)))?;
+
+    let count = |kind: Change, system: bool| changes.iter().filter(|c| c.change == kind && (!system || c.system_path)).count();
+    info!("\n[+] Изменения (всего / в системных каталогах):");
+    info!("    Добавлено:  {} / {}", count(Change::Added, false), count(Change::Added, true));
+    info!("    Удалено:    {} / {}", count(Change::Removed, false), count(Change::Removed, true));
+    info!("    Изменено:   {} / {}", count(Change::Modified, false), count(Change::Modified, true));
+
+    let system: Vec<&DiffRecord> = changes.iter().filter(|c| c.system_path).take(top).collect();
+    if !system.is_empty() {
+        info!("\n[+] Изменения в системных каталогах:");
+        for c in system {
+            let fields: Vec<&str> = c.changed_fields.keys().map(String::as_str).collect();
+            info!("    {:<9} {} {}", format!("{:?}", c.change), c.full_path, fields.join(","));
+        }
+    }
+    info!("\n[+] Результат сохранен: {}", out_jsonl);
+    Ok(())
+}
+
+/// Изменение записи между двумя дампами MFT.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+enum RecordChange {
+    Created,
+    Deleted,
+    Renamed,
+    Resized,
+    /// Метки $SI сдвинуты назад или появился флаг Timestomped
+    Timestomp,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct RecordDiff {
+    changes: Vec<RecordChange>,
+    system_path: bool,
+    entry_number: u64,
+    sequence_number: u16,
+    #[serde(rename = "Full_Path")]
+    full_path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    old_path: Option<String>,
+    changed_fields: BTreeMap<String, FieldChange>,
+}
+
+/// Метки $SI в порядке Created, LastModified, LastRecordChange, LastAccess.
+const SI_FIELDS: [&str; 4] = ["Created0x10", "LastModified0x10", "LastRecordChange0x10", "LastAccess0x10"];
+
+/// Сравниваемая часть записи из старого дампа.
+struct RecordSnapshot {
+    full_path: String,
+    file_size: u64,
+    si_times: [Option<String>; 4],
+    timestomped: bool,
+}
+
+impl RecordSnapshot {
+    fn new(entry: MftEntry) -> Self {
+        Self {
+            si_times: [entry.created0x10, entry.last_modified0x10, entry.last_record_change0x10, entry.last_access0x10],
+            full_path: entry.full_path,
+            file_size: entry.file_size,
+            timestomped: entry.timestomped,
+        }
+    }
+}
+
+fn record_diff(changes: Vec<RecordChange>, entry_number: u64, sequence_number: u16, full_path: String) -> RecordDiff {
+    RecordDiff {
+        changes, system_path: is_system_path(&full_path), entry_number, sequence_number, full_path,
+        old_path: None, changed_fields: BTreeMap::new(),
+    }
+}
+
+/// Сравнение записи, живой в обоих дампах под тем же номером и sequence.
+fn compare_records(old: RecordSnapshot, new: RecordSnapshot, entry_number: u64, sequence_number: u16) -> Option<RecordDiff> {
+    let mut diff = record_diff(Vec::new(), entry_number, sequence_number, new.full_path);
+    if old.full_path != diff.full_path {
+        diff.changes.push(RecordChange::Renamed);
+        diff.old_path = Some(old.full_path);
+    }
+    if old.file_size != new.file_size {
+        diff.changes.push(RecordChange::Resized);
+        diff.changed_fields.insert("FileSize".into(), FieldChange { old: old.file_size.into(), new: new.file_size.into() });
+    }
+    // RFC 3339 с одинаковым смещением сравнивается как строка
+    let backdated = old.si_times.iter().zip(&new.si_times).any(|(o, n)| matches!((o, n), (Some(o), Some(n)) if n < o));
+    if backdated || (new.timestomped && !old.timestomped) {
+        diff.changes.push(RecordChange::Timestomp);
+        for ((name, o), n) in SI_FIELDS.iter().zip(old.si_times).zip(new.si_times) {
+            if o != n {
+                diff.changed_fields.insert(name.to_string(), FieldChange { old: o.into(), new: n.into() });
+            }
+        }
+        if old.timestomped != new.timestomped {
+            diff.changed_fields.insert("Timestomped".into(), FieldChange { old: old.timestomped.into(), new: new.timestomped.into() });
+        }
+    }
+    (!diff.changes.is_empty()).then_some(diff)
+}
+
+/// Сравнение двух сырых дампов $MFT (`diff --old a.raw --new b.raw`).
+/// Записи сопоставляются по номеру и sequence: переиспользованная запись
+/// (sequence вырос) дает пару Deleted/Created, а не изменение.
+pub fn run_raw(old_mft: &str, new_mft: &str, out_jsonl: &str, system_only: bool, top: usize) -> Result<()> {
+    info!("[*] Запуск Diff (дампы MFT)");
+    info!(" -> Базовый дамп: {}", old_mft);
+    info!(" -> Текущий дамп: {}", new_mft);
+
+    let opts = ParseOptions::default();
+    let mut base: HashMap<(u64, u16), RecordSnapshot> = HashMap::new();
+    let scanned = parse::scan_entries(old_mft, &opts, |entry| {
+        if entry.in_use {
+            base.insert((entry.entry_number, entry.sequence_number), RecordSnapshot::new(entry));
+        }
+    });
+    scanned.map_err(Error::io(format!("Не удалось разобрать {}", old_mft)))?;
+    info!("[*] Базовый дамп: {} занятых записей", base.len());
+
+    let mut changes: Vec<RecordDiff> = Vec::new();
+    let scanned = parse::scan_entries(new_mft, &opts, |entry| {
+        let key = (entry.entry_number, entry.sequence_number);
+        if !entry.in_use {
+            // Удаление без смены sequence (запись еще не переиспользована) - Deleted ниже
+            return;
+        }
+        match base.remove(&key) {
+            Some(old) => changes.extend(compare_records(old, RecordSnapshot::new(entry), key.0, key.1)),
+            None => changes.push(record_diff(vec![RecordChange::Created], key.0, key.1, entry.full_path)),
+        }
+    });
+    scanned.map_err(Error::io(format!("Не удалось разобрать {}", new_mft)))?;
+    changes.extend(base.into_iter().map(|((entry_number, sequence_number), old)| {
+        record_diff(vec![RecordChange::Deleted], entry_number, sequence_number, old.full_path)
+    }));
+
+    if system_only {
+        changes.retain(|c| c.system_path);
+    }
+    changes.sort_by(|a, b| b.system_path.cmp(&a.system_path)
+        .then(a.changes.cmp(&b.changes))
+        .then(a.full_path.cmp(&b.full_path)));
+
+    output::write_jsonl_file(out_jsonl, &changes).map_err(Error::io(common::write_failed(out_jsonl)))?;
+
+    let count = |kind: RecordChange, system: bool| changes.iter().filter(|c| c.changes.contains(&kind) && (!system || c.system_path)).count();
+    info!("\n[+] Изменения (всего / в системных каталогах):");
+    info!("    Создано:        {} / {}", count(RecordChange::Created, false), count(RecordChange::Created, true));
+    info!("    Удалено:        {} / {}", count(RecordChange::Deleted, false), count(RecordChange::Deleted, true));
+    info!("    Переименовано:  {} / {}", count(RecordChange::Renamed, false), count(RecordChange::Renamed, true));
+    info!("    Изменен размер: {} / {}", count(RecordChange::Resized, false), count(RecordChange::Resized, true));
+    info!("    Timestomp:      {} / {}", count(RecordChange::Timestomp, false), count(RecordChange::Timestomp, true));
+
+    let system: Vec<&RecordDiff> = changes.iter().filter(|c| c.system_path).take(top).collect();
+    if !system.is_empty() {
+        info!("\n[+] Изменения в системных каталогах:");
+        for c in system {
+            let kinds: Vec<String> = c.changes.iter().map(|k| format!("{:?}", k)).collect();
+            match &c.old_path {
+                Some(old) => info!("    {:<16} {} <- {}", kinds.join(","), c.full_path, old),
+                None => info!("    {:<16} {}", kinds.join(","), c.full_path),
+            }
+        }
+    }
+    info!("\n[+] Результат сохранен: {}", out_jsonl);
+    Ok(())
+}
+#[cfg(test)]
+mod tests {
+    use byteorder::{ByteOrder, LittleEndian};
+
+    use super::*;
+    use crate::commands::bench::synthetic_mft;
+    use crate::image::Fixture;
+
+    const RECORD: usize = 1024;
+
+    /// Смещение атрибута `kind` в записи `entry` синтетического MFT.
+    fn attribute(mft: &[u8], entry: usize, kind: u32) -> usize {
+        let record = &mft[entry * RECORD..(entry + 1) * RECORD];
+        let mut offset = LittleEndian::read_u16(&record[20..22]) as usize;
+        while LittleEndian::read_u32(&record[offset..]) != kind {
+            offset += LittleEndian::read_u32(&record[offset + 4..]) as usize;
+        }
+        entry * RECORD + offset
+    }
+
+    #[test]
+    fn raw_diff_matches_records_by_sequence() {
+        let old = synthetic_mft(&[
+            (5, "docs", true, true), (5, "Windows", true, true),
+            (16, "a.txt", false, true), (16, "old.txt", false, true),
+            (16, "keep.txt", false, true), (16, "gone.txt", false, true),
+        ]);
+        let mut new = synthetic_mft(&[
+            (5, "docs", true, true), (5, "Windows", true, true),
+            (16, "b.txt", false, true), (16, "new.txt", false, true),
+            (16, "keep.txt", false, true), (16, "gone.txt", false, false),
+            (17, "evil.dll", false, true),
+        ]);
+        // Запись 19 занята другим файлом (sequence 2), у 20 $SI Created сдвинут на день назад
+        // и резидентные данные укорочены
+        LittleEndian::write_u16(&mut new[19 * RECORD + 16..], 2);
+        let created = attribute(&new, 20, 0x10) + 24;
+        let backdated = LittleEndian::read_u64(&new[created..]) - 864_000_000_000;
+        LittleEndian::write_u64(&mut new[created..], backdated);
+        let data = attribute(&new, 20, 0x80);
+        LittleEndian::write_u32(&mut new[data + 16..], 10);
+
+        let fixture = Fixture::new();
+        let (old, new) = (fixture.write("old.raw", &old), fixture.write("new.raw", &new));
+        let out = fixture.write("diff.jsonl", b"");
+
+        let rows = diff_rows(&old, &new, &out, false);
+        assert_eq!(summary(&rows), [
+            "Created \\Windows\\evil.dll",
+            "Created \\docs\\new.txt",
+            "Deleted \\docs\\gone.txt",
+            "Deleted \\docs\\old.txt",
+            "Renamed \\docs\\b.txt",
+            "Resized,Timestomp \\docs\\keep.txt",
+        ]);
+        assert_eq!(rows[0]["SystemPath"], true);
+        assert_eq!((rows[1]["EntryNumber"].as_u64(), rows[1]["SequenceNumber"].as_u64()), (Some(19), Some(2)));
+        assert_eq!(rows[4]["OldPath"], "\\docs\\a.txt");
+        let fields = &rows[5]["ChangedFields"];
+        assert_eq!(fields["FileSize"], serde_json::json!({"Old": 30, "New": 10}));
+        assert!(fields["Created0x10"]["New"].as_str() < fields["Created0x10"]["Old"].as_str());
+
+        let rows = diff_rows(&old, &new, &out, true);
+        assert_eq!(summary(&rows), ["Created \\Windows\\evil.dll"]);
+    }
+
+    fn diff_rows(old: &str, new: &str, out: &str, system_only: bool) -> Vec<Value> {
+        run_raw(old, new, out, system_only, 10).unwrap();
+        std::fs::read_to_string(out).unwrap().lines().map(|line| serde_json::from_str(line).unwrap()).collect()
+    }
+
+    /// Виды изменений через запятую и путь каждой строки отчета.
+    fn summary(rows: &[Value]) -> Vec<String> {
+        rows.iter().map(|row| {
+            let changes: Vec<&str> = row["Changes"].as_array().unwrap().iter().filter_map(Value::as_str).collect();
+            format!("{} {}", changes.join(","), row["Full_Path"].as_str().unwrap())
+        }).collect()
+    }
+}
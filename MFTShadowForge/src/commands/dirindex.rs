@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufWriter;
+
+use serde::Serialize;
+use tracing::info;
+
+use super::extract::{self, PartitionSelect};
+use super::i30;
+use super::volume::{self, VolumeRecords, ENTRY_MASK};
+use crate::audit;
+use crate::error::{Error, Result};
+use crate::i18n::common;
+use crate::image;
+use crate::mft::attributes::FileNameAttribute;
+use crate::models::{CaseInfo, Tags};
+use crate::output::JsonlWriter;
+
+/// Почему элемент индекса не подтверждается записью MFT.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum OrphanReason {
+    /// Запись свободна - файл удален, а индекс не перестроен
+    RecordFree,
+    /// Запись занята другим файлом (sequence не совпадает)
+    SequenceMismatch,
+    /// Запись та же, но $FILE_NAME с этим именем в этом каталоге в ней нет
+    NameNotInRecord,
+}
+
+/// Элемент индекса $I30 без подтверждения в MFT.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct OrphanedEntry {
+    pub entry_number: u64,
+    pub sequence_number: u16,
+    pub file_name: String,
+    pub reason: OrphanReason,
+}
+
+/// Имя из $FILE_NAME записи MFT, которого нет в индексе каталога.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct MissingEntry {
+    pub entry_number: u64,
+    pub sequence_number: u16,
+    pub file_name: String,
+}
+
+/// Строка отчета dirindex: содержимое каталога по индексу и по MFT.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct DirListing {
+    pub directory_entry: u64,
+    pub sequence_number: u16,
+    pub directory_path: String,
+    /// Живые элементы $INDEX_ROOT/$INDEX_ALLOCATION (DOS-имена - отдельными элементами)
+    pub index_entries: u64,
+    /// Имена занятых записей MFT, у которых родитель - этот каталог
+    pub mft_children: u64,
+    pub orphaned: Vec<OrphanedEntry>,
+    pub missing_from_index: Vec<MissingEntry>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Имя в каталоге по $FILE_NAME записи.
+struct ChildName {
+    entry: u64,
+    seq: u16,
+    name: String,
+}
+
+/// Восстановление содержимого каталогов по $INDEX_ROOT (0x90) и $INDEX_ALLOCATION (0xA0)
+/// и сверка с MFT: сколько элементов в индексе и сколько записей ссылаются на каталог,
+/// какие элементы индекса не подтверждаются записями и каких имен в индексе нет.
+/// Блоки INDX читаются с тома, поэтому нужен образ, а не дамп $MFT.
+pub fn run(image: &str, out_jsonl: &str, partition: Option<PartitionSelect>, mismatched_only: bool) -> Result<()> {
+    info!("[*] Запуск DirIndex");
+    let volume_path = extract::volume_path(image);
+    let mut vol = image::open(&volume_path).map_err(Error::io(format!("Ошибка открытия {}", volume_path)))?;
+    let layout = extract::locate_mft_at(&mut vol, partition)?;
+
+    info!("[*] Проход 1: дерево путей и имена $FILE_NAME...");
+    let mut dirs = Vec::new();
+    let mut records: HashMap<u64, (u16, bool)> = HashMap::new();
+    let mut children: HashMap<u64, Vec<ChildName>> = HashMap::new();
+    let path_builder = volume::index_volume(&mut vol, &layout, |entry_num, header, buffers| {
+        records.insert(entry_num, (header.sequence_number, header.is_in_use()));
+        if !header.is_in_use() { return; }
+        if header.is_directory() {
+            dirs.push((entry_num, header.sequence_number));
+        }
+        for attr in volume::attributes(buffers, layout.record_size).iter().filter(|a| a.attr_type == 0x30) {
+            let Some(fn_attr) = attr.resident_value().and_then(FileNameAttribute::parse) else { continue };
+            let parent = fn_attr.parent_directory_reference;
+            // Корень ссылается сам на себя - это не элемент его индекса
+            if parent & ENTRY_MASK == entry_num { continue; }
+            children.entry(parent).or_default().push(ChildName { entry: entry_num, seq: header.sequence_number, name: fn_attr.name });
+        }
+    });
+    info!("[*] Каталогов: {}", dirs.len());
+
+    info!("[*] Проход 2: разбор индексов и сверка с MFT...");
+    let out = File::create(out_jsonl).map_err(Error::io(format!("Не удалось создать {}", out_jsonl)))?;
+    let mut writer = JsonlWriter::new(BufWriter::new(out));
+    let mut src = VolumeRecords { vol: &mut vol, layout: &layout };
+    let (mut written, mut orphaned_total, mut missing_total) = (0u64, 0u64, 0u64);
+    for (dir, seq) in dirs {
+        let mft_names = children.get(&(dir | (seq as u64) << 48)).map(Vec::as_slice).unwrap_or_default();
+        let mut row = DirListing {
+            directory_entry: dir, sequence_number: seq, directory_path: path_builder.get_full_path(dir, seq),
+            index_entries: 0, mft_children: mft_names.len() as u64, orphaned: Vec::new(), missing_from_index: Vec::new(), error: None,
+        };
+        match i30::scan_directory(&mut src, dir, true) {
+            Ok(entries) => {
+                let live: Vec<i30::I30Entry> = entries.into_iter().filter(|e| !e.slack).collect();
+                row.index_entries = live.len() as u64;
+                for entry in &live {
+                    let (Some(entry_number), Some(sequence_number)) = (entry.entry_number, entry.sequence_number) else { continue };
+                    if mft_names.iter().any(|c| c.entry == entry_number && c.seq == sequence_number && c.name == entry.file_name) {
+                        continue;
+                    }
+                    let reason = match records.get(&entry_number) {
+                        Some((_, false)) | None => OrphanReason::RecordFree,
+                        Some((current, true)) if *current != sequence_number => OrphanReason::SequenceMismatch,
+                        Some(_) => OrphanReason::NameNotInRecord,
+                    };
+                    row.orphaned.push(OrphanedEntry { entry_number, sequence_number, file_name: entry.file_name.clone(), reason });
+                }
+                row.missing_from_index = mft_names.iter()
+                    .filter(|c| !live.iter().any(|e| e.entry_number == Some(c.entry) && e.file_name == c.name))
+                    .map(|c| MissingEntry { entry_number: c.entry, sequence_number: c.seq, file_name: c.name.clone() })
+                    .collect();
+            }
+            Err(e) => row.error = Some(e),
+        }
+        orphaned_total += row.orphaned.len() as u64;
+        missing_total += row.missing_from_index.len() as u64;
+        if mismatched_only && row.orphaned.is_empty() && row.missing_from_index.is_empty() && row.error.is_none() {
+            continue;
+        }
+        written += 1;
+        writer.write(&row).map_err(Error::io(common::write_failed(out_jsonl)))?;
+    }
+    writer.flush().map_err(Error::io(common::write_failed(out_jsonl)))?;
+    info!("[+] Каталогов в отчете: {}, элементов индекса без записи: {}, имен вне индекса: {} -> {}",
+        written, orphaned_total, missing_total, out_jsonl);
+    audit::append("dirindex", &volume_path, out_jsonl, &CaseInfo::default(), &Tags::new());
+    Ok(())
+}
\ No newline at end of file
@@ -1,46 +1,53 @@
 use std::fs::File;
 use std::io::{Read, Seek, SeekFrom, Write};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::SyncSender;
+use std::sync::{Condvar, Mutex};
 
 use byteorder::{ByteOrder, LittleEndian};
+use serde::Serialize;
+use tracing::{debug, info, warn};
 
+use super::volume::{self, VolumeRecords};
 use crate::mft::boot::NtfsBootSector;
 use crate::mft::parser::{apply_fixups, FixupResult};
 use crate::mft::record::MftRecordHeader;
-use crate::models::MftMeta;
+use crate::audit;
+use crate::error::{Error, Result};
+use crate::i18n::{common, extract as msg};
+use crate::image;
+use crate::image::bitlocker::{BitLockerKey, BitLockerVolume, BDE_SIGNATURE};
+use crate::models::{CaseInfo, MftFragment, MftLayoutReport, MftMeta, Tags};
+use crate::output;
+use crate::progress::{Progress, ProgressUnit};
 
 #[derive(Debug, Clone)]
-struct DataRun {
-    vcn_start: u64,
-    length: u64,
-    lcn: u64,
-    is_sparse: bool,
-}
-
-// Вспомогательная функция для фатальных ошибок
-fn fatal(msg: &str) -> ! {
-    eprintln!("[!] КРИТИЧЕСКАЯ ОШИБКА: {}", msg);
-    std::process::exit(1);
+pub struct DataRun {
+    pub vcn_start: u64,
+    pub length: u64,
+    pub lcn: u64,
+    pub is_sparse: bool,
 }
 
 // 1. Ультра-строгие проверки границ заголовка записи
-fn validate_record_boundaries(header: &MftRecordHeader, record_size: usize, is_record_0: bool) -> Result<(), String> {
+pub fn validate_record_boundaries(header: &MftRecordHeader, record_size: usize, is_record_0: bool) -> Result<(), String> {
     if is_record_0 && header.signature != "FILE" {
-        return Err(format!("Record 0 обязан иметь сигнатуру FILE, найдено: {}", header.signature));
+        return Err(msg::record0_signature(&header.signature));
     }
     if !is_record_0 && header.signature != "FILE" {
-        return Err(format!("Экстент обязан иметь сигнатуру FILE, найдено: {}", header.signature));
+        return Err(msg::extent_signature(&header.signature));
     }
     if header.real_size < 48 {
-        return Err("real_size меньше минимального размера заголовка MFT (48 байт)".to_string());
+        return Err(msg::real_size_below_header());
     }
     if header.first_attribute_offset as usize >= record_size {
-        return Err("first_attribute_offset выходит за пределы (или равен) record_size".to_string());
+        return Err(msg::first_attribute_out_of_record());
     }
     if header.real_size as usize > record_size {
-        return Err("real_size выходит за пределы record_size".to_string());
+        return Err(msg::real_size_out_of_record());
     }
     if (header.first_attribute_offset as usize) + 8 > header.real_size as usize {
-        return Err("real_size слишком мал для хранения атрибутов".to_string());
+        return Err(msg::real_size_no_attributes());
     }
     Ok(())
 }
@@ -49,28 +56,28 @@ fn validate_record_boundaries(header: &MftRecordHeader, record_size: usize, is_r
 fn validate_vbr(boot: &NtfsBootSector) -> Result<usize, String> {
     let bps = boot.bytes_per_sector;
     if bps != 512 && bps != 1024 && bps != 2048 && bps != 4096 {
-        return Err(format!("Некорректный bytes_per_sector: {}", bps));
+        return Err(msg::bad_bytes_per_sector(bps));
     }
     if boot.sectors_per_cluster == 0 || !boot.sectors_per_cluster.is_power_of_two() {
-        return Err(format!("Некорректный sectors_per_cluster: {}", boot.sectors_per_cluster));
+        return Err(msg::bad_sectors_per_cluster(boot.sectors_per_cluster));
     }
     if boot.bytes_per_cluster() == 0 {
-        return Err("bytes_per_cluster равен 0".to_string());
+        return Err(msg::zero_cluster_size());
     }
     if boot.mft_lcn == 0 {
-        return Err("mft_lcn равен 0".to_string());
+        return Err(msg::zero_mft_lcn());
     }
-    let rs = boot.file_record_size_bytes().ok_or_else(|| "Не удалось определить file_record_size".to_string())? as usize;
+    let rs = boot.file_record_size_bytes().ok_or_else(msg::unknown_record_size)? as usize;
     if rs < 1024 || !rs.is_power_of_two() {
-        return Err(format!("Некорректный record_size: {}", rs));
+        return Err(msg::bad_record_size(rs));
     }
     Ok(rs)
 }
 
 // Жесткая проверка VBR с учетом логического сектора (размер передается явно)
-fn check_vbr_strict(vol: &mut File, offset: u64, sector_size: u64) -> bool {
+fn check_vbr_strict<R: Read + Seek>(vol: &mut R, offset: u64, sector_size: u64) -> bool {
     let sz = sector_size as usize;
-    if sz < 512 || sz > 4096 { return false; }
+    if !(512..=4096).contains(&sz) { return false; }
 
     let mut vbr = vec![0u8; sz];
     if vol.seek(SeekFrom::Start(offset)).is_err() || vol.read_exact(&mut vbr).is_err() {
@@ -82,10 +89,8 @@ fn check_vbr_strict(vol: &mut File, offset: u64, sector_size: u64) -> bool {
     }
 
     let mut valid_sig = vbr[sz - 2] == 0x55 && vbr[sz - 1] == 0xAA;
-    if !valid_sig && sz > 512 {
-        if vbr[510] == 0x55 && vbr[511] == 0xAA {
-            valid_sig = true;
-        }
+    if !valid_sig && sz > 512 && vbr[510] == 0x55 && vbr[511] == 0xAA {
+        valid_sig = true;
     }
     if !valid_sig { return false; }
 
@@ -103,117 +108,165 @@ fn check_vbr_strict(vol: &mut File, offset: u64, sector_size: u64) -> bool {
 }
 
 // Поиск NTFS партиции с поддержкой 4Kn, MBR (в т.ч. Extended) и GPT
-fn find_ntfs_partition(vol: &mut File) -> Result<u64, String> {
-    for &sector_size in &[512u64, 1024u64, 2048u64, 4096u64] {
-        if check_vbr_strict(vol, 0, sector_size) {
+fn find_ntfs_partition<R: Read + Seek>(vol: &mut R) -> Result<u64, String> {
+    find_partition(vol, check_vbr_strict)
+}
+
+// Сигнатура тома BitLocker вместо NTFS (сам VBR зашифрован и перенесен)
+fn check_bde_header<R: Read + Seek>(vol: &mut R, offset: u64, sector_size: u64) -> bool {
+    let mut vbr = vec![0u8; sector_size as usize];
+    vol.seek(SeekFrom::Start(offset)).is_ok() && vol.read_exact(&mut vbr).is_ok()
+        && &vbr[3..11] == BDE_SIGNATURE && vbr[510] == 0x55 && vbr[511] == 0xAA
+}
+
+// Первый раздел (или том без таблицы разделов), на котором срабатывает проверка VBR
+fn find_partition<R: Read + Seek>(vol: &mut R, is_match: fn(&mut R, u64, u64) -> bool) -> Result<u64, String> {
+    for &sector_size in &SECTOR_SIZES {
+        if is_match(vol, 0, sector_size) {
             return Ok(0);
         }
-
-        let mut sector0 = vec![0u8; sector_size as usize];
-        if vol.seek(SeekFrom::Start(0)).is_err() || vol.read_exact(&mut sector0).is_err() {
-            continue;
+        for entry in partition_table(vol, sector_size) {
+            if is_match(vol, entry.offset, sector_size) {
+                return Ok(entry.offset);
+            }
         }
+    }
 
-        // MBR/EBR подпись всегда на 510-511
-        if sector0[510] != 0x55 || sector0[511] != 0xAA {
-            continue;
-        }
+    Err(msg::ntfs_scan_failed())
+}
 
-        let mut has_gpt = false;
+const SECTOR_SIZES: [u64; 4] = [512, 1024, 2048, 4096];
+
+/// Запись таблицы разделов (MBR, EBR или GPT).
+#[derive(Debug, Clone, Serialize)]
+pub struct PartitionEntry {
+    /// Номер по порядку обхода (с 1) - его принимает `--partition`
+    pub index: usize,
+    pub scheme: &'static str,
+    pub offset: u64,
+    pub size: u64,
+    /// Тип раздела: байт MBR (0x07) или GUID типа GPT
+    pub type_id: String,
+    /// Имя раздела GPT
+    pub name: String,
+    /// Файловая система по сигнатуре в начале раздела
+    pub filesystem: &'static str,
+}
 
-        // Перебор записей MBR и EBR
-        for i in 0..4 {
-            let offset = 446 + i * 16;
-            let part_type = sector0[offset + 4];
-            if part_type == 0 { continue; }
-            
-            if part_type == 0xEE { 
-                has_gpt = true;
-                break; 
-            }
+// Записи MBR (с цепочкой EBR) и GPT в порядке обхода; смещения - для заданного размера сектора
+fn partition_table<R: Read + Seek>(vol: &mut R, sector_size: u64) -> Vec<PartitionEntry> {
+    let mut entries: Vec<PartitionEntry> = Vec::new();
+    let mut push = |scheme: &'static str, offset: u64, size: u64, type_id: String, name: String| {
+        entries.push(PartitionEntry { index: entries.len() + 1, scheme, offset, size, type_id, name, filesystem: "" });
+    };
 
-            let lba_start = LittleEndian::read_u32(&sector0[offset + 8 .. offset + 12]) as u64;
-            let part_offset = match lba_start.checked_mul(sector_size) {
-                Some(v) if v != 0 => v,
-                _ => continue,
-            };
+    let mut sector0 = vec![0u8; sector_size as usize];
+    if vol.seek(SeekFrom::Start(0)).is_err() || vol.read_exact(&mut sector0).is_err() {
+        return entries;
+    }
 
-            if check_vbr_strict(vol, part_offset, sector_size) { 
-                return Ok(part_offset); 
-            }
+    // MBR/EBR подпись всегда на 510-511
+    if sector0[510] != 0x55 || sector0[511] != 0xAA {
+        return entries;
+    }
 
-            // Extended Partition (цепочка EBR, включая Linux Extended 0x85)
-            if part_type == 0x05 || part_type == 0x0F || part_type == 0x85 {
-                let ext_base_lba = lba_start;
-                let mut current_ebr_lba = ext_base_lba;
-                let mut ebr_depth = 0;
+    let mut has_gpt = false;
 
-                while ebr_depth < 128 { 
-                    let ebr_offset = match current_ebr_lba.checked_mul(sector_size) {
-                        Some(v) if v != 0 => v,
-                        _ => break,
-                    };
-                    
-                    let mut ebr_sector = vec![0u8; sector_size as usize];
-                    if vol.seek(SeekFrom::Start(ebr_offset)).is_err() || vol.read_exact(&mut ebr_sector).is_err() { break; }
-                    
-                    // Подпись EBR всегда на 510-511
-                    if ebr_sector[510] != 0x55 || ebr_sector[511] != 0xAA { break; }
-
-                    let p1 = 446;
-                    let log_type = ebr_sector[p1 + 4];
-                    if log_type != 0 {
-                        let log_lba_offset = LittleEndian::read_u32(&ebr_sector[p1 + 8 .. p1 + 12]) as u64;
-                        let log_lba = match current_ebr_lba.checked_add(log_lba_offset) {
-                            Some(v) => v,
-                            None => break,
-                        };
-                        let log_offset = match log_lba.checked_mul(sector_size) {
-                            Some(v) if v != 0 => v,
-                            _ => break,
-                        };
-                        if check_vbr_strict(vol, log_offset, sector_size) { return Ok(log_offset); }
-                    }
+    // Перебор записей MBR и EBR
+    for i in 0..4 {
+        let offset = 446 + i * 16;
+        let part_type = sector0[offset + 4];
+        if part_type == 0 { continue; }
+        
+        if part_type == 0xEE { 
+            has_gpt = true;
+            break; 
+        }
 
-                    let p2 = 446 + 16;
-                    let next_ebr_type = ebr_sector[p2 + 4];
-                    if next_ebr_type == 0 { break; } 
-                    
-                    let next_ebr_lba_offset = LittleEndian::read_u32(&ebr_sector[p2 + 8 .. p2 + 12]) as u64;
-                    current_ebr_lba = match ext_base_lba.checked_add(next_ebr_lba_offset) {
+        let lba_start = LittleEndian::read_u32(&sector0[offset + 8 .. offset + 12]) as u64;
+        let sectors = LittleEndian::read_u32(&sector0[offset + 12 .. offset + 16]) as u64;
+        let part_offset = match lba_start.checked_mul(sector_size) {
+            Some(v) if v != 0 => v,
+            _ => continue,
+        };
+
+        push("MBR", part_offset, sectors.saturating_mul(sector_size), format!("{:#04X}", part_type), String::new());
+
+        // Extended Partition (цепочка EBR, включая Linux Extended 0x85)
+        if part_type == 0x05 || part_type == 0x0F || part_type == 0x85 {
+            let ext_base_lba = lba_start;
+            let mut current_ebr_lba = ext_base_lba;
+            let mut ebr_depth = 0;
+
+            while ebr_depth < 128 { 
+                let ebr_offset = match current_ebr_lba.checked_mul(sector_size) {
+                    Some(v) if v != 0 => v,
+                    _ => break,
+                };
+                
+                let mut ebr_sector = vec![0u8; sector_size as usize];
+                if vol.seek(SeekFrom::Start(ebr_offset)).is_err() || vol.read_exact(&mut ebr_sector).is_err() { break; }
+                
+                // Подпись EBR всегда на 510-511
+                if ebr_sector[510] != 0x55 || ebr_sector[511] != 0xAA { break; }
+
+                let p1 = 446;
+                let log_type = ebr_sector[p1 + 4];
+                if log_type != 0 {
+                    let log_lba_offset = LittleEndian::read_u32(&ebr_sector[p1 + 8 .. p1 + 12]) as u64;
+                    let log_sectors = LittleEndian::read_u32(&ebr_sector[p1 + 12 .. p1 + 16]) as u64;
+                    let log_lba = match current_ebr_lba.checked_add(log_lba_offset) {
+                        Some(v) => v,
+                        None => break,
+                    };
+                    let log_offset = match log_lba.checked_mul(sector_size) {
                         Some(v) if v != 0 => v,
                         _ => break,
                     };
-                    ebr_depth += 1;
+                    push("EBR", log_offset, log_sectors.saturating_mul(sector_size), format!("{:#04X}", log_type), String::new());
                 }
+
+                let p2 = 446 + 16;
+                let next_ebr_type = ebr_sector[p2 + 4];
+                if next_ebr_type == 0 { break; } 
+                
+                let next_ebr_lba_offset = LittleEndian::read_u32(&ebr_sector[p2 + 8 .. p2 + 12]) as u64;
+                current_ebr_lba = match ext_base_lba.checked_add(next_ebr_lba_offset) {
+                    Some(v) if v != 0 => v,
+                    _ => break,
+                };
+                ebr_depth += 1;
             }
         }
+    }
 
-        // Парсинг GPT
-        if has_gpt {
-            let gpt_header_offset = sector_size;
-            let mut gpt_header = vec![0u8; sector_size as usize];
-            if vol.seek(SeekFrom::Start(gpt_header_offset)).is_ok() && vol.read_exact(&mut gpt_header).is_ok() {
-                if &gpt_header[0..8] == b"EFI PART" {
-                    let part_entry_lba = LittleEndian::read_u64(&gpt_header[0x48..0x50]);
-                    let num_entries = LittleEndian::read_u32(&gpt_header[0x50..0x54]);
-                    let entry_size = LittleEndian::read_u32(&gpt_header[0x54..0x58]);
-
-                    if entry_size >= 128 && entry_size <= 4096 && num_entries > 0 && num_entries <= 4096 {
-                        if let Some(table_offset) = part_entry_lba.checked_mul(sector_size) {
-                            if vol.seek(SeekFrom::Start(table_offset)).is_ok() {
-                                let mut entry = vec![0u8; entry_size as usize];
-                                for _ in 0..num_entries {
-                                    if vol.read_exact(&mut entry).is_err() { break; }
-                                    if entry[0..16].iter().all(|&b| b == 0) { continue; }
-
-                                    let first_lba = LittleEndian::read_u64(&entry[0x20..0x28]);
-                                    if let Some(part_offset) = first_lba.checked_mul(sector_size) {
-                                        let cur_pos = vol.stream_position().unwrap_or(0);
-                                        if check_vbr_strict(vol, part_offset, sector_size) { return Ok(part_offset); }
-                                        let _ = vol.seek(SeekFrom::Start(cur_pos));
-                                    }
-                                }
+    // Парсинг GPT
+    if has_gpt {
+        let gpt_header_offset = sector_size;
+        let mut gpt_header = vec![0u8; sector_size as usize];
+        if vol.seek(SeekFrom::Start(gpt_header_offset)).is_ok() && vol.read_exact(&mut gpt_header).is_ok()
+            && &gpt_header[0..8] == b"EFI PART" {
+            let part_entry_lba = LittleEndian::read_u64(&gpt_header[0x48..0x50]);
+            let num_entries = LittleEndian::read_u32(&gpt_header[0x50..0x54]);
+            let entry_size = LittleEndian::read_u32(&gpt_header[0x54..0x58]);
+
+            if (128..=4096).contains(&entry_size) && num_entries > 0 && num_entries <= 4096 {
+                if let Some(table_offset) = part_entry_lba.checked_mul(sector_size) {
+                    if vol.seek(SeekFrom::Start(table_offset)).is_ok() {
+                        let mut entry = vec![0u8; entry_size as usize];
+                        for _ in 0..num_entries {
+                            if vol.read_exact(&mut entry).is_err() { break; }
+                            if entry[0..16].iter().all(|&b| b == 0) { continue; }
+
+                            let first_lba = LittleEndian::read_u64(&entry[0x20..0x28]);
+                            let last_lba = LittleEndian::read_u64(&entry[0x28..0x30]);
+                            if let Some(part_offset) = first_lba.checked_mul(sector_size) {
+                                let size = last_lba.saturating_sub(first_lba).saturating_add(1).saturating_mul(sector_size);
+                                let name_units: Vec<u16> = entry[0x38..0x80].chunks_exact(2)
+                                    .map(LittleEndian::read_u16)
+                                    .take_while(|&c| c != 0)
+                                    .collect();
+                                push("GPT", part_offset, size, format_guid(&entry[0..16]), String::from_utf16_lossy(&name_units));
                             }
                         }
                     }
@@ -222,25 +275,75 @@ fn find_ntfs_partition(vol: &mut File) -> Result<u64, String> {
         }
     }
 
-    Err("Подходящий NTFS раздел не найден (сканирование MBR/EBR/GPT завершено)".to_string())
+    entries
+}
+
+/// Все записи таблицы разделов с определенной файловой системой. Размер сектора (512 или 4Kn)
+/// подбирается так же, как при поиске NTFS: первый, при котором на разделах узнается ФС.
+pub fn partitions<R: Read + Seek>(vol: &mut R) -> Vec<PartitionEntry> {
+    let mut fallback: Option<Vec<PartitionEntry>> = None;
+    for &sector_size in &SECTOR_SIZES {
+        let mut entries = partition_table(vol, sector_size);
+        for entry in &mut entries {
+            entry.filesystem = match entry.type_id.as_str() {
+                "0x05" | "0x0F" | "0x85" => "Extended",
+                _ => guess_filesystem(vol, entry.offset),
+            };
+        }
+        if entries.iter().any(|e| e.filesystem != FS_UNKNOWN) { return entries; }
+        if fallback.is_none() && !entries.is_empty() { fallback = Some(entries); }
+    }
+    fallback.unwrap_or_default()
+}
+
+const FS_UNKNOWN: &str = "?";
+
+// Файловая система по сигнатурам загрузочного сектора и суперблока
+fn guess_filesystem<R: Read + Seek>(vol: &mut R, offset: u64) -> &'static str {
+    let mut head = [0u8; 2048];
+    if vol.seek(SeekFrom::Start(offset)).is_err() || vol.read_exact(&mut head).is_err() {
+        return FS_UNKNOWN;
+    }
+    match &head[3..11] {
+        b"NTFS    " => return "NTFS",
+        b"-FVE-FS-" => return "BitLocker",
+        b"EXFAT   " => return "exFAT",
+        _ => {}
+    }
+    if &head[3..7] == b"ReFS" { return "ReFS"; }
+    if &head[0x52..0x5A] == b"FAT32   " { return "FAT32"; }
+    if &head[0x36..0x39] == b"FAT" { return "FAT12/16"; }
+    if &head[0..4] == b"XFSB" { return "XFS"; }
+    if &head[0..6] == b"LUKS\xBA\xBE" { return "LUKS"; }
+    if &head[32..36] == b"NXSB" { return "APFS"; }
+    if LittleEndian::read_u16(&head[1024 + 56..1024 + 58]) == 0xEF53 { return "ext2/3/4"; }
+    if &head[1024..1026] == b"H+" || &head[1024..1026] == b"HX" { return "HFS+"; }
+    FS_UNKNOWN
+}
+
+// GUID в каноническом виде (первые три поля на диске - little-endian)
+fn format_guid(b: &[u8]) -> String {
+    format!("{:08X}-{:04X}-{:04X}-{:02X}{:02X}-{}",
+        LittleEndian::read_u32(&b[0..4]), LittleEndian::read_u16(&b[4..6]), LittleEndian::read_u16(&b[6..8]),
+        b[8], b[9], b[10..16].iter().map(|x| format!("{:02X}", x)).collect::<String>())
 }
 
-// 3. Безопасное чтение логических байтов MFT
-fn read_logical_mft(vol: &mut File, runs: &[DataRun], bpc: u64, partition_offset: u64, mut logical_offset: u64, mut buf: &mut [u8]) -> Result<(), String> {
+// 3. Безопасное чтение логических байтов атрибута по runlist ($MFT, индексы каталогов)
+pub fn read_logical<R: Read + Seek>(vol: &mut R, runs: &[DataRun], bpc: u64, partition_offset: u64, mut logical_offset: u64, mut buf: &mut [u8]) -> Result<(), String> {
     while !buf.is_empty() {
         let target_vcn = logical_offset / bpc;
         let offset_in_cluster = logical_offset % bpc;
 
         let mut found_run = None;
         for r in runs {
-            let run_end = r.vcn_start.checked_add(r.length).ok_or("Переполнение при вычислении конца run")?;
+            let run_end = r.vcn_start.checked_add(r.length).ok_or_else(|| msg::overflow("vcn_start + length"))?;
             if target_vcn >= r.vcn_start && target_vcn < run_end {
                 found_run = Some(r);
                 break;
             }
         }
         
-        let run = found_run.ok_or_else(|| format!("VCN {} не найден в базовых runs при чтении экстента", target_vcn))?;
+        let run = found_run.ok_or_else(|| msg::vcn_not_mapped(target_vcn))?;
 
         let to_read = std::cmp::min(buf.len() as u64, bpc - offset_in_cluster) as usize;
 
@@ -248,25 +351,25 @@ fn read_logical_mft(vol: &mut File, runs: &[DataRun], bpc: u64, partition_offset
             buf[..to_read].fill(0);
         } else {
             let physical_cluster = run.lcn.checked_add(target_vcn - run.vcn_start)
-                .ok_or("Переполнение physical_cluster")?;
+                .ok_or_else(|| msg::overflow("physical_cluster"))?;
             let physical_offset = physical_cluster.checked_mul(bpc)
                 .and_then(|po| po.checked_add(offset_in_cluster))
                 .and_then(|po| po.checked_add(partition_offset))
-                .ok_or("Переполнение физического смещения при чтении экстента")?;
+                .ok_or_else(|| msg::overflow("physical_offset"))?;
 
-            vol.seek(SeekFrom::Start(physical_offset)).map_err(|e| format!("Ошибка seek: {}", e))?;
-            vol.read_exact(&mut buf[..to_read]).map_err(|e| format!("Ошибка read_exact: {}", e))?;
+            vol.seek(SeekFrom::Start(physical_offset)).map_err(msg::seek_failed)?;
+            vol.read_exact(&mut buf[..to_read]).map_err(msg::read_failed)?;
         }
 
         let tmp = buf;
         buf = &mut tmp[to_read..];
-        logical_offset = logical_offset.checked_add(to_read as u64).ok_or("Переполнение logical_offset")?;
+        logical_offset = logical_offset.checked_add(to_read as u64).ok_or_else(|| msg::overflow("logical_offset"))?;
     }
     Ok(())
 }
 
 // 4. Строгий парсинг Data Runs
-fn parse_data_runs(record: &[u8], mut run_off: usize, attr_end: usize, start_vcn: u64) -> Result<Vec<DataRun>, String> {
+pub fn parse_data_runs(record: &[u8], mut run_off: usize, attr_end: usize, start_vcn: u64) -> Result<Vec<DataRun>, String> {
     let mut runs = Vec::new();
     let mut current_vcn = start_vcn;
     let mut current_lcn: i64 = 0;
@@ -281,11 +384,11 @@ fn parse_data_runs(record: &[u8], mut run_off: usize, attr_end: usize, start_vcn
         run_off += 1;
 
         if len_bytes == 0 || len_bytes > 8 || off_bytes > 8 {
-            return Err(format!("Некорректные размеры нибблов: len={}, off={}", len_bytes, off_bytes));
+            return Err(msg::bad_run_header(len_bytes, off_bytes));
         }
 
-        if run_off.checked_add(len_bytes).unwrap_or(usize::MAX).checked_add(off_bytes).unwrap_or(usize::MAX) > attr_end {
-            return Err("Data runs выходят за границы атрибута".to_string());
+        if run_off.saturating_add(len_bytes).saturating_add(off_bytes) > attr_end {
+            return Err(msg::runs_out_of_attribute());
         }
 
         let mut run_length: u64 = 0;
@@ -295,7 +398,7 @@ fn parse_data_runs(record: &[u8], mut run_off: usize, attr_end: usize, start_vcn
         run_off += len_bytes;
 
         if run_length == 0 {
-            return Err("Длина Data Run равна 0".to_string());
+            return Err(msg::zero_run_length());
         }
 
         let mut run_delta: i64 = 0;
@@ -311,10 +414,10 @@ fn parse_data_runs(record: &[u8], mut run_off: usize, attr_end: usize, start_vcn
         }
         run_off += off_bytes;
 
-        current_lcn = current_lcn.checked_add(run_delta).ok_or("Переполнение current_lcn")?;
+        current_lcn = current_lcn.checked_add(run_delta).ok_or_else(|| msg::overflow("current_lcn"))?;
         
         if off_bytes > 0 && current_lcn < 0 {
-            return Err(format!("Отрицательный LCN вычислен в runlist: {}", current_lcn));
+            return Err(msg::negative_lcn(current_lcn));
         }
 
         let is_sparse = off_bytes == 0;
@@ -326,67 +429,189 @@ fn parse_data_runs(record: &[u8], mut run_off: usize, attr_end: usize, start_vcn
             lcn,
             is_sparse,
         });
-        current_vcn = current_vcn.checked_add(run_length).ok_or("Переполнение current_vcn")?;
+        current_vcn = current_vcn.checked_add(run_length).ok_or_else(|| msg::overflow("current_vcn"))?;
     }
     Ok(runs)
 }
 
-pub fn run(image: &str, out: &str) {
-    println!("[*] Запуск Extract (Strict DFIR Mode)");
-    println!(" -> Источник: {}", image);
-    println!(" -> Выходной файл: {}", out);
+/// Число фрагментов $MFT, начиная с которого раскладка считается аномальной.
+const FRAGMENTS_WARN_THRESHOLD: usize = 32;
+
+/// Расположение $MFT на томе: смещение партиции, параметры VBR и полный runlist.
+pub struct MftLayout {
+    pub partition_offset: u64,
+    pub boot: NtfsBootSector,
+    pub record_size: usize,
+    pub bytes_per_cluster: u64,
+    pub runs: Vec<DataRun>,
+    pub total_bytes: u64,
+}
 
-    let volume_path = if image.len() <= 3 && image.starts_with(|c: char| c.is_ascii_alphabetic()) {
+impl MftLayout {
+    pub fn total_records(&self) -> u64 {
+        self.total_bytes / self.record_size as u64
+    }
+
+    /// Читает сырую запись MFT с тома по номеру (fixups не применяются).
+    pub fn read_record<R: Read + Seek>(&self, vol: &mut R, entry: u64) -> Result<Vec<u8>, String> {
+        if entry >= self.total_records() {
+            return Err(msg::record_out_of_mft(entry));
+        }
+        let mut buf = vec![0u8; self.record_size];
+        read_logical(vol, &self.runs, self.bytes_per_cluster, self.partition_offset, entry * self.record_size as u64, &mut buf)?;
+        Ok(buf)
+    }
+
+    /// Метаданные дампа (`<out>.meta.json`) без сведений о деле и меток.
+    pub fn to_meta(&self, source: &str) -> MftMeta {
+        MftMeta {
+            bytes_per_sector: self.boot.bytes_per_sector, sectors_per_cluster: self.boot.sectors_per_cluster,
+            bytes_per_cluster: self.bytes_per_cluster, mft_lcn: self.boot.mft_lcn, mft_mirror_lcn: self.boot.mft_mirror_lcn,
+            clusters_per_index_buffer: self.boot.clusters_per_index_buffer, mft_record_size: self.record_size as u32,
+            volume_serial_number: self.boot.volume_serial_number, partition_offset: self.partition_offset,
+            source: source.to_string(), layout: Some(self.layout_report()),
+            volume: None, shadow_copy: None, case: CaseInfo::default(), tags: Tags::new(),
+        }
+    }
+
+    /// Отчет о раскладке $MFT: смежные runs склеиваются в фрагменты.
+    pub fn layout_report(&self) -> MftLayoutReport {
+        let mut ranges: Vec<MftFragment> = Vec::new();
+        for run in &self.runs {
+            let lcn = if run.is_sparse { None } else { Some(run.lcn) };
+            if let Some(last) = ranges.last_mut() {
+                let contiguous = match (last.lcn, lcn) {
+                    (Some(prev), Some(next)) => prev + last.clusters == next,
+                    (None, None) => true,
+                    _ => false,
+                };
+                if contiguous {
+                    last.clusters += run.length;
+                    continue;
+                }
+            }
+            ranges.push(MftFragment { vcn: run.vcn_start, lcn, clusters: run.length });
+        }
+
+        let total_clusters: u64 = ranges.iter().map(|r| r.clusters).sum();
+        let sparse_clusters: u64 = ranges.iter().filter(|r| r.lcn.is_none()).map(|r| r.clusters).sum();
+        let fragments = ranges.iter().filter(|r| r.lcn.is_some()).count();
+        let volume_clusters = match self.boot.sectors_per_cluster {
+            0 => 0,
+            spc => self.boot.total_sectors / spc as u64,
+        };
+        let percent = |part: u64, whole: u64| if whole == 0 { 0.0 } else { part as f64 * 100.0 / whole as f64 };
+        let first_lcn = ranges.iter().find_map(|r| r.lcn).unwrap_or(0);
+        let start_percent = percent(first_lcn, volume_clusters);
+
+        let mut warnings = Vec::new();
+        if sparse_clusters > 0 {
+            warnings.push(msg::layout_sparse(sparse_clusters));
+        }
+        if fragments > FRAGMENTS_WARN_THRESHOLD {
+            warnings.push(msg::layout_fragmented(fragments));
+        }
+        if start_percent > 50.0 {
+            warnings.push(msg::layout_late_start(start_percent));
+        }
+        let mirror = self.boot.mft_mirror_lcn;
+        if ranges.iter().any(|r| r.lcn.is_some_and(|lcn| mirror >= lcn && mirror < lcn + r.clusters)) {
+            warnings.push(msg::layout_mirror_inside(mirror));
+        }
+
+        MftLayoutReport {
+            fragments,
+            total_clusters,
+            sparse_clusters,
+            sparse_percent: percent(sparse_clusters, total_clusters),
+            volume_clusters,
+            start_percent,
+            ranges,
+            warnings,
+        }
+    }
+}
+
+/// `C:` -> `\\.\C:`, образы и пути к устройствам возвращаются как есть.
+pub fn volume_path(image: &str) -> String {
+    if image.len() <= 3 && image.starts_with(|c: char| c.is_ascii_alphabetic()) {
         format!("\\\\.\\{}", &image[0..2])
     } else {
         image.to_string()
-    };
+    }
+}
 
-    let mut vol = match File::open(&volume_path) {
-        Ok(f) => f,
-        Err(e) => fatal(&format!("Ошибка открытия {}. {}", volume_path, e)),
-    };
+/// Смещение раздела, выбранного через `--partition`.
+fn resolve_partition<R: Read + Seek>(vol: &mut R, select: PartitionSelect) -> Result<u64> {
+    match select {
+        PartitionSelect::Offset(offset) => Ok(offset),
+        PartitionSelect::Index(index) => partitions(vol).into_iter()
+            .find(|p| p.index == index)
+            .map(|p| p.offset)
+            .ok_or_else(|| Error::Invalid(msg::partition_not_found(index))),
+    }
+}
 
-    let partition_offset = match find_ntfs_partition(&mut vol) {
-        Ok(offset) => offset,
-        Err(e) => fatal(&format!("Не удалось найти NTFS партицию: {}", e)),
+/// Находит NTFS-партицию, валидирует VBR и собирает runlist $MFT (включая экстенты).
+pub fn locate_mft<R: Read + Seek>(vol: &mut R) -> Result<MftLayout> {
+    locate_mft_at(vol, None)
+}
+
+/// То же, но на выбранном разделе (None - первый найденный NTFS).
+pub fn locate_mft_at<R: Read + Seek>(vol: &mut R, partition: Option<PartitionSelect>) -> Result<MftLayout> {
+    let partition_offset = match partition {
+        Some(select) => {
+            let offset = resolve_partition(vol, select)?;
+            if !SECTOR_SIZES.iter().any(|&ss| check_vbr_strict(vol, offset, ss)) {
+                return Err(Error::NoNtfs(msg::no_ntfs_at(offset, guess_filesystem(vol, offset))));
+            }
+            offset
+        }
+        None => match find_ntfs_partition(vol) {
+            Ok(offset) => offset,
+            Err(_) if find_partition(vol, check_bde_header).is_ok() => {
+                return Err(Error::NoNtfs(msg::bitlocker_locked()))
+            }
+            Err(e) => return Err(Error::NoNtfs(msg::ntfs_not_found(e))),
+        },
     };
 
     let mut boot_sector = [0u8; 512];
-    vol.seek(SeekFrom::Start(partition_offset)).unwrap_or_else(|e| fatal(&format!("Ошибка seek к VBR: {}", e)));
-    vol.read_exact(&mut boot_sector).unwrap_or_else(|e| fatal(&format!("Ошибка чтения VBR: {}", e)));
+    vol.seek(SeekFrom::Start(partition_offset)).map_err(Error::io(msg::vbr_read_failed()))?;
+    vol.read_exact(&mut boot_sector).map_err(Error::io(msg::vbr_read_failed()))?;
 
-    let boot = NtfsBootSector::parse(&boot_sector).unwrap_or_else(|| fatal("Не удалось распарсить VBR"));
+    let boot = NtfsBootSector::parse(&boot_sector).ok_or_else(|| Error::Corrupt(msg::vbr_unparsable()))?;
     let record_size = match validate_vbr(&boot) {
         Ok(sz) => sz,
-        Err(e) => fatal(&format!("Валидация VBR не пройдена: {}", e)),
+        Err(e) => return Err(Error::Corrupt(msg::vbr_invalid(e))),
     };
 
     let bytes_per_cluster = boot.bytes_per_cluster();
     let mft_physical_offset = partition_offset.checked_add(
-        boot.mft_lcn.checked_mul(bytes_per_cluster).unwrap_or_else(|| fatal("Переполнение при расчете LCN MFT"))
-    ).unwrap_or_else(|| fatal("Переполнение при добавлении partition offset"));
+        boot.mft_lcn.checked_mul(bytes_per_cluster).ok_or_else(|| Error::Corrupt(msg::overflow("mft_lcn * bytes_per_cluster")))?
+    ).ok_or_else(|| Error::Corrupt(msg::overflow("partition_offset + mft_offset")))?;
 
-    println!("[+] Метаданные (смещение {:#X}):", partition_offset);
-    println!("    bytes_per_sector: {}", boot.bytes_per_sector);
-    println!("    sectors_per_cluster: {}", boot.sectors_per_cluster);
-    println!("    mft_record_size: {}", record_size);
+    info!("{}", msg::metadata(partition_offset));
+    info!("    bytes_per_sector: {}", boot.bytes_per_sector);
+    info!("    sectors_per_cluster: {}", boot.sectors_per_cluster);
+    info!("    mft_record_size: {}", record_size);
+    debug!("{}", msg::mft_location(boot.mft_lcn, mft_physical_offset, bytes_per_cluster));
 
-    vol.seek(SeekFrom::Start(mft_physical_offset)).unwrap_or_else(|e| fatal(&format!("Ошибка seek к $MFT: {}", e)));
+    vol.seek(SeekFrom::Start(mft_physical_offset)).map_err(Error::io(msg::record0_read_failed()))?;
     let mut mft_record0 = vec![0u8; record_size];
-    vol.read_exact(&mut mft_record0).unwrap_or_else(|e| fatal(&format!("Ошибка чтения MFT record 0: {}", e)));
+    vol.read_exact(&mut mft_record0).map_err(Error::io(msg::record0_read_failed()))?;
 
     let header0 = match MftRecordHeader::parse(&mft_record0) {
         Some(h) => h,
-        None => fatal("MFT record 0 поврежден (заголовок не распознан)"),
+        None => return Err(Error::Corrupt(msg::record0_unparsable())),
     };
 
     if let Err(e) = validate_record_boundaries(&header0, record_size, true) {
-        fatal(&format!("Отбраковка MFT record 0: {}", e));
+        return Err(Error::Corrupt(msg::record0_rejected(e)));
     }
 
     if apply_fixups(&mut mft_record0, &header0, boot.bytes_per_sector) == FixupResult::Failed {
-        fatal("Fixups MFT record 0 не применились (повреждение массива USA).");
+        return Err(Error::Corrupt(msg::record0_fixups()));
     }
 
     struct ExtentTarget { start_vcn: u64, entry: u64, seq: u16 }
@@ -401,7 +626,7 @@ pub fn run(image: &str, out: &str) {
     // Парсинг Record 0
     while attr_offset + 8 <= used_end {
         if attr_offset <= previous_offset && previous_offset != 0 {
-            fatal("Зацикленный атрибут (смещение перестало расти).");
+            return Err(Error::Corrupt(msg::attribute_loop()));
         }
         previous_offset = attr_offset;
 
@@ -409,8 +634,8 @@ pub fn run(image: &str, out: &str) {
         if attr_type == 0xFFFFFFFF || attr_type == 0 { break; }
 
         let attr_len = LittleEndian::read_u32(&mft_record0[attr_offset + 4..attr_offset + 8]) as usize;
-        if attr_len == 0 || attr_offset.checked_add(attr_len).unwrap_or(usize::MAX) > used_end {
-            fatal("Выход размера атрибута за границы используемой части записи.");
+        if attr_len == 0 || attr_offset.saturating_add(attr_len) > used_end {
+            return Err(Error::Corrupt(msg::attribute_out_of_record()));
         }
         
         let attr_end = attr_offset + attr_len;
@@ -422,11 +647,11 @@ pub fn run(image: &str, out: &str) {
                 let value_len = LittleEndian::read_u32(&mft_record0[attr_offset + 16..attr_offset + 20]) as usize;
                 let value_off = LittleEndian::read_u16(&mft_record0[attr_offset + 20..attr_offset + 22]) as usize;
                 
-                let list_start = attr_offset.checked_add(value_off).unwrap_or(usize::MAX);
-                let list_end = list_start.checked_add(value_len).unwrap_or(usize::MAX);
+                let list_start = attr_offset.saturating_add(value_off);
+                let list_end = list_start.saturating_add(value_len);
                 
                 if list_start < attr_offset || list_end > attr_end {
-                    fatal("$ATTRIBUTE_LIST выходит за границы атрибута.");
+                    return Err(Error::Corrupt(msg::attribute_list_out_of_attribute()));
                 }
                 
                 let mut curr = list_start;
@@ -434,13 +659,13 @@ pub fn run(image: &str, out: &str) {
                     let entry_type = LittleEndian::read_u32(&mft_record0[curr..curr + 4]);
                     if entry_type == 0 { break; }
                     let entry_len = LittleEndian::read_u16(&mft_record0[curr + 4..curr + 6]) as usize;
-                    if entry_len < 26 || curr.checked_add(entry_len).unwrap_or(usize::MAX) > list_end { break; }
+                    if entry_len < 26 || curr.saturating_add(entry_len) > list_end { break; }
                     
                     let name_len = mft_record0[curr + 6] as usize; 
                     let name_off = mft_record0[curr + 7] as usize; 
                     
-                    if name_off.checked_add(name_len * 2).unwrap_or(usize::MAX) > entry_len {
-                        fatal("Длина имени UTF-16 в $ATTRIBUTE_LIST выходит за пределы записи.");
+                    if name_off.saturating_add(name_len * 2) > entry_len {
+                        return Err(Error::Corrupt(msg::attribute_list_name("$ATTRIBUTE_LIST")));
                     }
                     
                     if entry_type == 0x80 && name_len == 0 {
@@ -459,35 +684,35 @@ pub fn run(image: &str, out: &str) {
                 let dr_off = LittleEndian::read_u16(&mft_record0[attr_offset + 0x20..attr_offset + 0x22]) as usize;
                 let actual_size = LittleEndian::read_u64(&mft_record0[attr_offset + 0x30..attr_offset + 0x38]) as usize;
 
-                if dr_off < 0x40 || attr_offset.checked_add(dr_off).unwrap_or(usize::MAX) >= attr_end {
-                    fatal("Некорректное смещение Data Runs (dr_off) в non-resident $ATTRIBUTE_LIST.");
+                if dr_off < 0x40 || attr_offset.saturating_add(dr_off) >= attr_end {
+                    return Err(Error::Corrupt(msg::bad_runs_offset("non-resident $ATTRIBUTE_LIST")));
                 }
 
                 let al_runs = match parse_data_runs(&mft_record0, attr_offset + dr_off, attr_end, al_svcn) {
                     Ok(runs) => runs,
-                    Err(e) => fatal(&format!("Ошибка runlist в non-resident $ATTRIBUTE_LIST: {}", e)),
+                    Err(e) => return Err(Error::Corrupt(msg::runlist_error("non-resident $ATTRIBUTE_LIST", e))),
                 };
 
                 let mut covered_clusters: u64 = 0;
                 for r in &al_runs {
                     covered_clusters = covered_clusters.checked_add(r.length)
-                        .unwrap_or_else(|| fatal("Переполнение при подсчете al_runs"));
+                        .ok_or_else(|| Error::Corrupt(msg::overflow("al_runs")))?;
                 }
                 let covered_bytes = covered_clusters.checked_mul(bytes_per_cluster)
-                    .unwrap_or_else(|| fatal("Переполнение covered_bytes"));
+                    .ok_or_else(|| Error::Corrupt(msg::overflow("covered_bytes")))?;
                 if covered_bytes < actual_size as u64 {
-                    fatal("Runlist non-resident $ATTRIBUTE_LIST короче actual_size");
+                    return Err(Error::Corrupt(msg::attribute_list_short()));
                 }
 
                 if actual_size == 0 || actual_size > 1024 * 1024 {
-                    fatal(&format!("Недопустимый размер non-resident $ATTRIBUTE_LIST: {} байт", actual_size));
+                    return Err(Error::Corrupt(msg::attribute_list_size(actual_size)));
                 }
 
-                let al_logical_offset = al_svcn.checked_mul(bytes_per_cluster).unwrap_or_else(|| fatal("Переполнение смещения al_svcn"));
+                let al_logical_offset = al_svcn.checked_mul(bytes_per_cluster).ok_or_else(|| Error::Corrupt(msg::overflow("al_svcn * bytes_per_cluster")))?;
                 let mut attr_list_buf = vec![0u8; actual_size];
                 
-                if let Err(e) = read_logical_mft(&mut vol, &al_runs, bytes_per_cluster, partition_offset, al_logical_offset, &mut attr_list_buf) {
-                    fatal(&format!("Ошибка чтения non-resident $ATTRIBUTE_LIST: {}", e));
+                if let Err(e) = read_logical(vol, &al_runs, bytes_per_cluster, partition_offset, al_logical_offset, &mut attr_list_buf) {
+                    return Err(Error::Corrupt(msg::attribute_list_read(e)));
                 }
 
                 let mut curr = 0;
@@ -495,13 +720,13 @@ pub fn run(image: &str, out: &str) {
                     let entry_type = LittleEndian::read_u32(&attr_list_buf[curr..curr + 4]);
                     if entry_type == 0 { break; }
                     let entry_len = LittleEndian::read_u16(&attr_list_buf[curr + 4..curr + 6]) as usize;
-                    if entry_len < 26 || curr.checked_add(entry_len).unwrap_or(usize::MAX) > actual_size { break; }
+                    if entry_len < 26 || curr.saturating_add(entry_len) > actual_size { break; }
 
                     let name_len = attr_list_buf[curr + 6] as usize;
                     let name_off = attr_list_buf[curr + 7] as usize;
 
-                    if name_off.checked_add(name_len * 2).unwrap_or(usize::MAX) > entry_len {
-                        fatal("Длина имени UTF-16 в non-resident $ATTRIBUTE_LIST выходит за пределы записи.");
+                    if name_off.saturating_add(name_len * 2) > entry_len {
+                        return Err(Error::Corrupt(msg::attribute_list_name("non-resident $ATTRIBUTE_LIST")));
                     }
 
                     if entry_type == 0x80 && name_len == 0 {
@@ -516,30 +741,28 @@ pub fn run(image: &str, out: &str) {
                     curr += entry_len;
                 }
             }
-        } else if attr_type == 0x80 && main_name_len == 0 { 
-            if non_resident {
-                let start_vcn = LittleEndian::read_u64(&mft_record0[attr_offset + 16..attr_offset + 24]);
-                let dr_off = LittleEndian::read_u16(&mft_record0[attr_offset + 32..attr_offset + 34]) as usize;
-                
-                if attr_offset + 0x30 <= attr_end {
-                    expected_allocated_size = LittleEndian::read_u64(&mft_record0[attr_offset + 0x28..attr_offset + 0x30]);
-                }
-                
-                if dr_off < 0x40 || attr_offset.checked_add(dr_off).unwrap_or(usize::MAX) >= attr_end {
-                    fatal("Некорректное смещение Data Runs (dr_off).");
-                }
-                
-                match parse_data_runs(&mft_record0, attr_offset + dr_off, attr_end, start_vcn) {
-                    Ok(runs) => base_runs.extend(runs),
-                    Err(e) => fatal(&format!("Ошибка runlist в Record 0: {}", e)),
-                }
+        } else if attr_type == 0x80 && main_name_len == 0 && non_resident {
+            let start_vcn = LittleEndian::read_u64(&mft_record0[attr_offset + 16..attr_offset + 24]);
+            let dr_off = LittleEndian::read_u16(&mft_record0[attr_offset + 32..attr_offset + 34]) as usize;
+            
+            if attr_offset + 0x30 <= attr_end {
+                expected_allocated_size = LittleEndian::read_u64(&mft_record0[attr_offset + 0x28..attr_offset + 0x30]);
+            }
+            
+            if dr_off < 0x40 || attr_offset.saturating_add(dr_off) >= attr_end {
+                return Err(Error::Corrupt(msg::bad_runs_offset("Record 0")));
+            }
+            
+            match parse_data_runs(&mft_record0, attr_offset + dr_off, attr_end, start_vcn) {
+                Ok(runs) => base_runs.extend(runs),
+                Err(e) => return Err(Error::Corrupt(msg::runlist_error("Record 0", e))),
             }
         }
         attr_offset = attr_end;
     }
 
     if base_runs.is_empty() {
-        fatal("Базовые Data Runs для $MFT не найдены.");
+        return Err(Error::Corrupt(msg::no_base_runs()));
     }
 
     let mut all_runs = base_runs.clone();
@@ -547,29 +770,29 @@ pub fn run(image: &str, out: &str) {
     // Сбор экстентов
     for target in attr_list_entries {
         let record_byte_offset = target.entry.checked_mul(record_size as u64)
-            .unwrap_or_else(|| fatal("Переполнение при вычислении логического смещения экстента"));
+            .ok_or_else(|| Error::Corrupt(msg::overflow("entry * record_size")))?;
             
         let mut ext_record = vec![0u8; record_size];
         
-        if let Err(e) = read_logical_mft(&mut vol, &base_runs, bytes_per_cluster, partition_offset, record_byte_offset, &mut ext_record) {
-            fatal(&format!("Ошибка чтения ext_record ({}): {}", target.entry, e));
+        if let Err(e) = read_logical(vol, &base_runs, bytes_per_cluster, partition_offset, record_byte_offset, &mut ext_record) {
+            return Err(Error::Corrupt(msg::extent_read_failed(target.entry, e)));
         }
         
         let eh = match MftRecordHeader::parse(&ext_record) {
             Some(h) => h,
-            None => fatal(&format!("ext_record поврежден ({})", target.entry)),
+            None => return Err(Error::Corrupt(msg::extent_unparsable(target.entry))),
         };
         
         if let Err(e) = validate_record_boundaries(&eh, record_size, false) {
-            fatal(&format!("ext_record ({}) отбракован: {}", target.entry, e));
+            return Err(Error::Corrupt(msg::extent_rejected(target.entry, e)));
         }
 
         if eh.sequence_number != target.seq {
-            fatal(&format!("Sequence mismatch в ext_record {}. Ожидался {}, найден {}.", target.entry, target.seq, eh.sequence_number));
+            return Err(Error::Corrupt(msg::extent_sequence(target.entry, target.seq, eh.sequence_number)));
         }
         
         if apply_fixups(&mut ext_record, &eh, boot.bytes_per_sector) == FixupResult::Failed {
-            fatal(&format!("Ошибка fixups в ext_record ({})", target.entry));
+            return Err(Error::Corrupt(msg::extent_fixups(target.entry)));
         }
         
         let mut e_off = eh.first_attribute_offset as usize;
@@ -583,7 +806,7 @@ pub fn run(image: &str, out: &str) {
             let e_type = LittleEndian::read_u32(&ext_record[e_off..e_off + 4]);
             if e_type == 0xFFFFFFFF || e_type == 0 { break; }
             let e_len = LittleEndian::read_u32(&ext_record[e_off + 4..e_off + 8]) as usize;
-            if e_len == 0 || e_off.checked_add(e_len).unwrap_or(usize::MAX) > e_used { break; }
+            if e_len == 0 || e_off.saturating_add(e_len) > e_used { break; }
             
             let e_attr_end = e_off + e_len;
             let non_resident = ext_record[e_off + 8] != 0;
@@ -593,13 +816,13 @@ pub fn run(image: &str, out: &str) {
                 let svcn = LittleEndian::read_u64(&ext_record[e_off + 16..e_off + 24]);
                 if svcn == target.start_vcn {
                     let dr_off = LittleEndian::read_u16(&ext_record[e_off + 32..e_off + 34]) as usize;
-                    if dr_off < 0x40 || e_off.checked_add(dr_off).unwrap_or(usize::MAX) >= e_attr_end {
-                        fatal(&format!("Некорректное смещение Data Runs (dr_off) в экстенте {}.", target.entry));
+                    if dr_off < 0x40 || e_off.saturating_add(dr_off) >= e_attr_end {
+                        return Err(Error::Corrupt(msg::bad_runs_offset(format!("ext_record {}", target.entry))));
                     }
                     
                     match parse_data_runs(&ext_record, e_off + dr_off, e_attr_end, target.start_vcn) {
                         Ok(runs) => all_runs.extend(runs),
-                        Err(e) => fatal(&format!("Ошибка runlist в ext_record ({}): {}", target.entry, e)),
+                        Err(e) => return Err(Error::Corrupt(msg::runlist_error(format!("ext_record ({})", target.entry), e))),
                     }
                 }
             }
@@ -609,74 +832,678 @@ pub fn run(image: &str, out: &str) {
 
     all_runs.sort_by_key(|r| r.vcn_start);
 
-    if all_runs.is_empty() { fatal("Итоговый Runlist пуст."); }
-    if all_runs[0].vcn_start != 0 { fatal(&format!("Дыра в VCN с самого начала. Ожидался 0, найден {}.", all_runs[0].vcn_start)); }
+    if all_runs.is_empty() { return Err(Error::Corrupt(msg::runlist_empty())); }
+    if all_runs[0].vcn_start != 0 { return Err(Error::Corrupt(msg::vcn_gap(0, all_runs[0].vcn_start))); }
 
     let mut expected_vcn = 0;
     for run in &all_runs {
-        if run.vcn_start > expected_vcn { fatal(&format!("Дыра в VCN. Ожидался {}, найден {}.", expected_vcn, run.vcn_start)); } 
-        else if run.vcn_start < expected_vcn { fatal(&format!("Перекрытие VCN. Ожидался {}, найден {}.", expected_vcn, run.vcn_start)); }
-        expected_vcn = expected_vcn.checked_add(run.length).unwrap_or_else(|| fatal("Переполнение суммы VCN."));
+        if run.vcn_start > expected_vcn { return Err(Error::Corrupt(msg::vcn_gap(expected_vcn, run.vcn_start))); } 
+        else if run.vcn_start < expected_vcn { return Err(Error::Corrupt(msg::vcn_overlap(expected_vcn, run.vcn_start))); }
+        expected_vcn = expected_vcn.checked_add(run.length).ok_or_else(|| Error::Corrupt(msg::overflow("expected_vcn + length")))?;
     }
     
-    let expected_total_bytes = expected_vcn.checked_mul(bytes_per_cluster).unwrap_or_else(|| fatal("Переполнение при вычислении итогового размера MFT."));
+    let expected_total_bytes = expected_vcn.checked_mul(bytes_per_cluster).ok_or_else(|| Error::Corrupt(msg::overflow("expected_vcn * bytes_per_cluster")))?;
 
     if expected_allocated_size > 0 && expected_total_bytes < expected_allocated_size {
-        fatal(&format!("Собранный по кластерам размер MFT ({} байт) меньше заявленного Allocated Size ({} байт). Runlist поврежден.", expected_total_bytes, expected_allocated_size));
+        return Err(Error::Corrupt(msg::mft_size_short(expected_total_bytes, expected_allocated_size)));
+    }
+
+    Ok(MftLayout {
+        partition_offset,
+        boot,
+        record_size,
+        bytes_per_cluster,
+        runs: all_runs,
+        total_bytes: expected_total_bytes,
+    })
+}
+
+/// Какие теневые копии снимать (`--shadow`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShadowSelect {
+    /// Только показать снимки тома
+    List,
+    All,
+    Index(u32),
+}
+
+/// Выбор раздела (`--partition`): номер из `--list-partitions` или смещение в байтах.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PartitionSelect {
+    Index(usize),
+    Offset(u64),
+}
+
+/// Размер блока чтения $MFT по умолчанию.
+pub const DEFAULT_IO_CHUNK: usize = 1024 * 1024;
+/// Сколько прочитанных байт может ждать записи: чтение тома идет впереди записи дампа.
+const READ_AHEAD_BYTES: usize = 64 * 1024 * 1024;
+/// Потоков чтения фрагментированного $MFT по умолчанию.
+pub const DEFAULT_READ_THREADS: usize = 4;
+/// С какого числа фрагментов $MFT читается параллельно: смежный $MFT быстрее читать подряд.
+const PARALLEL_MIN_FRAGMENTS: usize = 32;
+
+/// Параметры команды extract.
+#[derive(Debug, Clone, Default)]
+pub struct ExtractOptions {
+    /// Раздел с NTFS; по умолчанию - первый найденный
+    pub partition: Option<PartitionSelect>,
+    /// Размер блока чтения $MFT; None - `DEFAULT_IO_CHUNK`
+    pub io_chunk_size: Option<usize>,
+    /// Потоков чтения фрагментированного $MFT; 0 и 1 - последовательное чтение
+    pub threads: usize,
+    /// Ключ для раздела BitLocker
+    pub bitlocker_key: Option<BitLockerKey>,
+    /// Читать устройство агента по сети вместо локального образа
+    pub remote: Option<RemoteEndpoint>,
+    /// Показывать ход извлечения в stderr
+    pub progress: bool,
+    /// Дописывать операцию в audit.log рядом с дампом (так делает CLI; библиотека - только по запросу)
+    pub audit: bool,
+}
+
+/// Агент `extract --listen` и файлы mTLS контроллера.
+#[derive(Debug, Clone)]
+#[cfg_attr(not(feature = "agent"), allow(dead_code))]
+pub struct RemoteEndpoint {
+    pub addr: String,
+    pub cert: String,
+    pub key: String,
+    pub ca: String,
+}
+
+/// Открывает источник: локальный том/образ или устройство агента (`--connect`).
+/// Возвращает и имя источника для meta.json: у удаленного - `<устройство>@<агент>`.
+#[cfg_attr(not(feature = "agent"), allow(unused_variables))]
+fn open_source(image: &str, opts: &ExtractOptions) -> Result<(Box<dyn image::ImageReader>, String)> {
+    if let Some(remote) = &opts.remote {
+        #[cfg(feature = "agent")]
+        {
+            let reader = image::remote::RemoteReader::connect(&remote.addr, &remote.cert, &remote.key, &remote.ca)
+                .map_err(Error::io(msg::connect_failed(&remote.addr)))?;
+            let size = reader.size().map(msg::remote_size).unwrap_or_else(msg::remote_size_unknown);
+            info!("{}", msg::remote_device(reader.device(), &remote.addr, size));
+            let source = format!("{}@{}", reader.device(), remote.addr);
+            return Ok((Box::new(reader), source));
+        }
+        #[cfg(not(feature = "agent"))]
+        return Err(Error::Unsupported(msg::connect_unsupported()));
     }
+    let volume_path = volume_path(image);
+    let vol = image::open(&volume_path).map_err(Error::io(common::open_failed(&volume_path)))?;
+    Ok((vol, volume_path))
+}
+
+/// Возвращает геометрию тома и расположение $MFT (то же, что пишется в meta.json).
+pub fn run(image: &str, out: &str, opts: &ExtractOptions, case: &CaseInfo, tags: &Tags) -> Result<MftMeta> {
+    info!("{}", msg::started());
+    let (vol, source) = open_source(image, opts)?;
+    info!("{}", msg::source(&source));
+    info!("{}", msg::output(out));
+
+    extract_volume(vol, &source, out, None, opts, case, tags)
+}
+
+/// Режим агента для `extract --connect`: раздает чтение тома или образа по mTLS.
+#[cfg_attr(not(feature = "agent"), allow(unused_variables))]
+pub fn run_listen(image: &str, listen: &str, cert: &str, key: &str, ca: &str) -> Result<()> {
+    info!("{}", msg::started_listen());
+    #[cfg(feature = "agent")]
+    return image::remote::serve(listen, &volume_path(image), cert, key, ca)
+        .map_err(Error::io(msg::listen_failed(listen)));
+    #[cfg(not(feature = "agent"))]
+    Err(Error::Unsupported(msg::listen_unsupported()))
+}
+
+/// Таблица разделов образа или диска: схема, смещение, размер, тип и файловая система.
+pub fn run_list_partitions(image: &str, opts: &ExtractOptions) -> Result<()> {
+    let (mut vol, volume_path) = open_source(image, opts)?;
 
-    let mut extracted_bytes: u64 = 0;
-    println!("[*] Извлечение: Строгий режим, размер {} байт", expected_total_bytes);
-    let mut out_file = match File::create(out) {
-        Ok(f) => f,
-        Err(e) => fatal(&format!("Не удалось создать {}: {}", out, e)),
+    let entries = partitions(&mut vol);
+    if entries.is_empty() {
+        let fs = guess_filesystem(&mut vol, 0);
+        info!("{}", msg::no_partition_table(&volume_path, fs));
+        return Ok(());
+    }
+
+    info!("{}", msg::partitions(&volume_path));
+    info!("{}", msg::partitions_header());
+    for p in &entries {
+        info!("{:>3}  {:<5} {:>16} {:>16}  {:<38} {:<10} {}",
+            p.index, p.scheme, format!("{:#X}", p.offset), p.size, p.type_id, p.filesystem, p.name);
+    }
+    Ok(())
+}
+
+/// Если NTFS не найден (или выбранный раздел - BitLocker) - читать образ через расшифровывающий слой.
+/// Без ключа снимается только приостановленная защита (clear key). Второе в ответе -
+/// был ли снят BitLocker.
+fn unlock_bitlocker(mut vol: Box<dyn image::ImageReader>, opts: &ExtractOptions) -> Result<(Box<dyn image::ImageReader>, bool)> {
+    let offset = match opts.partition {
+        Some(select) => {
+            let offset = resolve_partition(&mut vol, select)?;
+            if !SECTOR_SIZES.iter().any(|&ss| check_bde_header(&mut vol, offset, ss)) { return Ok((vol, false)); }
+            offset
+        }
+        None => {
+            if find_ntfs_partition(&mut vol).is_ok() { return Ok((vol, false)); }
+            let Ok(offset) = find_partition(&mut vol, check_bde_header) else { return Ok((vol, false)) };
+            offset
+        }
     };
+    let unlocked = BitLockerVolume::open(vol, offset, opts.bitlocker_key.as_ref())
+        .map_err(Error::io(msg::bitlocker_partition(offset)))?;
+    info!("{}", msg::bitlocker_unlocked(offset, unlocked.method_name()));
+    Ok((Box::new(unlocked), true))
+}
 
-    for run in all_runs {
-        let bytes_to_read = run.length.checked_mul(bytes_per_cluster).unwrap_or_else(|| fatal("Переполнение bytes_to_read."));
+/// Серийный номер NTFS-тома по VBR в начале устройства (теневая копия - это том без таблицы разделов).
+fn volume_serial<R: Read + Seek>(vol: &mut R) -> Option<u64> {
+    let mut boot_sector = [0u8; 512];
+    vol.seek(SeekFrom::Start(0)).ok()?;
+    vol.read_exact(&mut boot_sector).ok()?;
+    NtfsBootSector::parse(&boot_sector).map(|b| b.volume_serial_number)
+}
 
-        if run.is_sparse {
-            let chunk = vec![0u8; 1024 * 1024];
-            let mut remaining = bytes_to_read;
-            while remaining > 0 {
-                let to_write = std::cmp::min(remaining, chunk.len() as u64) as usize;
-                out_file.write_all(&chunk[..to_write]).unwrap_or_else(|e| fatal(&format!("Ошибка записи разреженных нулей: {}", e)));
-                remaining -= to_write as u64;
-                extracted_bytes += to_write as u64;
+/// `<out>` для снимка N: `mft.raw` -> `mft.vssN.raw`.
+fn shadow_out_path(out: &str, index: u32) -> String {
+    let path = std::path::Path::new(out);
+    match (path.file_stem(), path.extension()) {
+        (Some(stem), Some(ext)) => path.with_file_name(format!("{}.vss{}.{}", stem.to_string_lossy(), index, ext.to_string_lossy()))
+            .to_string_lossy().into_owned(),
+        _ => format!("{}.vss{}", out, index),
+    }
+}
+
+/// Извлечение $MFT из теневых копий тома: снимки сопоставляются с томом по серийному номеру NTFS.
+pub fn run_shadows(image: &str, out: &str, select: ShadowSelect, case: &CaseInfo, tags: &Tags) -> Result<()> {
+    info!("{}", msg::started_shadows());
+    info!("{}", msg::source(image));
+
+    let all = image::vss::list().map_err(Error::io(msg::shadows_list_failed()))?;
+
+    let volume_path = volume_path(image);
+    let mut vol = image::open(&volume_path).map_err(Error::io(common::open_failed(&volume_path)))?;
+    let serial = locate_mft(&mut vol)?.boot.volume_serial_number;
+    drop(vol);
+
+    let shadows: Vec<_> = all.into_iter()
+        .filter(|s| image::open(&s.device).ok().and_then(|mut v| volume_serial(&mut v)) == Some(serial))
+        .collect();
+
+    info!("{}", msg::shadows(&volume_path, serial, shadows.len()));
+    for shadow in &shadows {
+        info!("    {:>5}  {}", shadow.index, shadow.device);
+    }
+
+    let selected: Vec<_> = match select {
+        ShadowSelect::List => return Ok(()),
+        ShadowSelect::All => shadows,
+        ShadowSelect::Index(n) => match shadows.into_iter().find(|s| s.index == n) {
+            Some(s) => vec![s],
+            None => return Err(Error::Invalid(msg::shadow_not_found(n, &volume_path))),
+        },
+    };
+    if selected.is_empty() { return Err(Error::Invalid(msg::no_shadows())); }
+
+    for shadow in selected {
+        let shadow_out = shadow_out_path(out, shadow.index);
+        info!("{}", msg::shadow(shadow.index, &shadow.device, &shadow_out));
+        let vol = image::open(&shadow.device).map_err(Error::io(common::open_failed(&shadow.device)))?;
+        let shadow_opts = ExtractOptions { audit: true, ..Default::default() };
+        extract_volume(vol, &shadow.device, &shadow_out, Some(shadow.index), &shadow_opts, case, tags)?;
+    }
+    Ok(())
+}
+
+/// Источник, раскладка $MFT, meta.json и путь для повторного открытия источника.
+type OpenedMft = (Box<dyn image::ImageReader>, MftLayout, MftMeta, Option<String>);
+
+/// Открывает $MFT тома: снимает BitLocker, находит раздел и раскладку и собирает meta.json.
+/// Последнее в ответе - путь для повторного открытия источника потоками чтения; у устройства
+/// агента и расшифрованного BitLocker его нет.
+fn open_mft(vol: Box<dyn image::ImageReader>, volume_path: &str, shadow_copy: Option<u32>, opts: &ExtractOptions, case: &CaseInfo, tags: &Tags) -> Result<OpenedMft> {
+    let (mut vol, unlocked) = unlock_bitlocker(vol, opts)?;
+    let reopen = (opts.remote.is_none() && !unlocked).then(|| volume_path.to_string());
+
+    let layout = locate_mft_at(&mut vol, opts.partition)?;
+    let volume = volume::volume_info(&mut VolumeRecords { vol: &mut vol, layout: &layout });
+    if let Some(info) = &volume {
+        info!("{}", common::volume(info.describe()));
+    }
+    let meta = MftMeta { case: case.clone(), tags: tags.clone(), shadow_copy, volume, ..layout.to_meta(volume_path) };
+    Ok((vol, layout, meta, reopen))
+}
+
+/// Смежные runs (продолжающие друг друга на диске или подряд разреженные) склеиваются:
+/// фрагментированный $MFT читается меньшим числом seek и более крупными блоками.
+fn coalesce_runs(runs: &[DataRun]) -> Vec<DataRun> {
+    let mut merged: Vec<DataRun> = Vec::with_capacity(runs.len());
+    for run in runs {
+        if let Some(last) = merged.last_mut() {
+            let adjacent = match (last.is_sparse, run.is_sparse) {
+                (true, true) => true,
+                (false, false) => last.lcn.checked_add(last.length) == Some(run.lcn),
+                _ => false,
+            };
+            if adjacent {
+                last.length += run.length;
+                continue;
             }
-            continue;
         }
+        merged.push(run.clone());
+    }
+    merged
+}
 
-        let physical_offset = partition_offset.checked_add(run.lcn.checked_mul(bytes_per_cluster).unwrap_or_else(|| fatal("Переполнение lcn * bpc"))).unwrap_or_else(|| fatal("Переполнение partition_offset + LCN offset"));
-        vol.seek(SeekFrom::Start(physical_offset)).unwrap_or_else(|e| fatal(&format!("Ошибка seek на физический offset {}: {}", physical_offset, e)));
+/// Размер блока чтения: запрошенный (по умолчанию 1 МБ), кратный кластеру тома,
+/// чтобы чтения внутри run шли по границам кластеров.
+fn io_chunk(requested: Option<usize>, bytes_per_cluster: u64) -> usize {
+    let cluster = bytes_per_cluster.max(512) as usize;
+    (requested.unwrap_or(DEFAULT_IO_CHUNK) / cluster).max(1) * cluster
+}
 
-        let mut chunk = vec![0u8; 1024 * 1024];
-        let mut remaining = bytes_to_read;
-        while remaining > 0 {
-            let to_read = std::cmp::min(remaining, chunk.len() as u64) as usize;
-            let buffer_slice = &mut chunk[..to_read];
-            
-            vol.read_exact(buffer_slice).unwrap_or_else(|e| fatal(&format!("Недочитка байтов с диска. Осталось прочитать: {}. Ошибка: {}", remaining, e)));
-            out_file.write_all(buffer_slice).unwrap_or_else(|e| fatal(&format!("Ошибка записи в файл дампа: {}", e)));
-            
-            remaining -= to_read as u64;
-            extracted_bytes += to_read as u64;
+/// Один блок чтения $MFT: смещение на устройстве (None - разреженный, нули) и длина.
+struct ReadItem {
+    offset: Option<u64>,
+    len: usize,
+}
+
+/// Склеенные runs, нарезанные на блоки по `chunk_size`, в порядке VCN.
+fn read_items(layout: &MftLayout, chunk_size: usize) -> Result<Vec<ReadItem>> {
+    let mut items = Vec::new();
+    for run in coalesce_runs(&layout.runs) {
+        let bytes = run.length.checked_mul(layout.bytes_per_cluster).ok_or_else(|| Error::Corrupt(msg::overflow("length * bytes_per_cluster")))?;
+        let start = if run.is_sparse { None } else {
+            let lcn_offset = run.lcn.checked_mul(layout.bytes_per_cluster).ok_or_else(|| Error::Corrupt(msg::overflow("lcn * bytes_per_cluster")))?;
+            Some(layout.partition_offset.checked_add(lcn_offset).ok_or_else(|| Error::Corrupt(msg::overflow("partition_offset + lcn_offset")))?)
+        };
+        let mut done = 0u64;
+        while done < bytes {
+            let len = std::cmp::min(bytes - done, chunk_size as u64) as usize;
+            items.push(ReadItem { offset: start.map(|s| s + done), len });
+            done += len as u64;
         }
     }
+    Ok(items)
+}
+
+/// Читает блок в `chunk` (разреженный - заполняет нулями).
+fn read_item<R: Read + Seek>(vol: &mut R, item: &ReadItem, chunk: &mut Vec<u8>) -> Result<()> {
+    chunk.resize(item.len, 0);
+    let Some(offset) = item.offset else {
+        chunk.fill(0);
+        return Ok(());
+    };
+    vol.seek(SeekFrom::Start(offset)).map_err(Error::io(msg::disk_seek_failed(offset)))?;
+    vol.read_exact(chunk).map_err(Error::io(msg::disk_read_short(offset)))
+}
+
+/// Помечает файл дампа разреженным: на NTFS без этого пропущенные seek участки
+/// все равно заполняются нулями на диске. Ошибка (FAT, exFAT) не мешает записи.
+#[cfg(windows)]
+fn mark_sparse(file: &File) {
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::System::IO::DeviceIoControl;
+
+    const FSCTL_SET_SPARSE: u32 = 0x000900C4;
+    let mut returned = 0u32;
+    unsafe {
+        DeviceIoControl(file.as_raw_handle() as _, FSCTL_SET_SPARSE, std::ptr::null(), 0, std::ptr::null_mut(), 0, &mut returned, std::ptr::null_mut());
+    }
+}
 
-    if extracted_bytes != expected_total_bytes { fatal(&format!("Извлечено {} байт, ожидалось {}.", extracted_bytes, expected_total_bytes)); }
+/// На Unix-ФС дыры образуются сами при записи за seek.
+#[cfg(not(windows))]
+fn mark_sparse(_file: &File) {}
+
+/// Пишет блок в дамп; нулевой блок (разреженный run или нулевые кластеры) пропускается seek,
+/// и на ФС с поддержкой разреженных файлов на месте него остается дыра. Возвращает, был ли пропуск.
+fn write_chunk(file: &mut File, chunk: &[u8]) -> std::io::Result<bool> {
+    if chunk.iter().all(|&b| b == 0) {
+        file.seek(SeekFrom::Current(chunk.len() as i64))?;
+        return Ok(true);
+    }
+    file.write_all(chunk)?;
+    Ok(false)
+}
 
-    println!("[+] Успешно извлечено: {} МБ.", extracted_bytes / 1024 / 1024);
+/// Читает runs $MFT блоками `--io-chunk-size` и по порядку передает их по каналу потоку
+/// записи: он пишет их в `out` (нулевые блоки - дырами разреженного файла) и, если `collect`,
+/// собирает в буфер.
+/// Пока поток пишет, чтение идет дальше - до `READ_AHEAD_BYTES` впереди записи.
+/// Фрагментированный $MFT (от `PARALLEL_MIN_FRAGMENTS` фрагментов) с источника, который можно
+/// открыть повторно (`reopen`), читается в `opts.threads` потоков, каждый со своим дескриптором.
+/// Возвращает число прочитанных байт и собранный буфер.
+fn copy_mft<R: Read + Seek>(vol: &mut R, reopen: Option<&str>, layout: &MftLayout, opts: &ExtractOptions, mut out: Option<File>, collect: bool) -> Result<(u64, Vec<u8>)> {
+    let chunk_size = io_chunk(opts.io_chunk_size, layout.bytes_per_cluster);
+    let window = (READ_AHEAD_BYTES / chunk_size).max(2);
+    let (tx, rx) = std::sync::mpsc::sync_channel::<Vec<u8>>(window);
+    // Записанные блоки возвращаются читателю, чтобы не выделять память под каждый
+    let (free_tx, free_rx) = std::sync::mpsc::channel::<Vec<u8>>();
+    let capacity = if collect { layout.total_bytes as usize } else { 0 };
+    if let Some(file) = out.as_ref() {
+        mark_sparse(file);
+    }
+    let writer = std::thread::spawn(move || -> std::io::Result<(Vec<u8>, u64)> {
+        let mut data = Vec::with_capacity(capacity);
+        let mut holes = 0u64;
+        for chunk in rx {
+            if let Some(file) = out.as_mut() {
+                if write_chunk(file, &chunk)? {
+                    holes += chunk.len() as u64;
+                }
+            }
+            if collect {
+                data.extend_from_slice(&chunk);
+            }
+            let _ = free_tx.send(chunk);
+        }
+        // seek за конец файла его не удлиняет: хвостовая дыра доводится до размера $MFT
+        if let Some(file) = out.as_mut() {
+            let end = file.stream_position()?;
+            file.set_len(end)?;
+        }
+        Ok((data, holes))
+    });
+
+    let items = read_items(layout, chunk_size)?;
+    let mut progress = Progress::new("extract", msg::stage(), layout.total_bytes, ProgressUnit::Bytes, opts.progress);
+    let fragments = coalesce_runs(&layout.runs).iter().filter(|r| !r.is_sparse).count();
+    let parallel = reopen.filter(|_| opts.threads > 1 && fragments >= PARALLEL_MIN_FRAGMENTS);
+    let extracted_bytes = match parallel {
+        Some(path) => {
+            info!("{}", msg::threads(fragments, opts.threads));
+            let open = || image::open(path).map_err(Error::io(common::open_failed(path)));
+            read_parallel(&open, &items, opts.threads, window, &tx, &mut progress)?
+        }
+        None => {
+            let mut extracted_bytes = 0u64;
+            for item in &items {
+                let mut chunk = free_rx.try_recv().unwrap_or_default();
+                read_item(vol, item, &mut chunk)?;
+                // Поток записи закрывает канал только при ошибке - она будет получена из join
+                if tx.send(chunk).is_err() { break; }
+                extracted_bytes += item.len as u64;
+                progress.set(extracted_bytes);
+            }
+            extracted_bytes
+        }
+    };
+    progress.finish();
+    drop(tx);
 
-    let meta = MftMeta {
-        bytes_per_sector: boot.bytes_per_sector, sectors_per_cluster: boot.sectors_per_cluster,
-        bytes_per_cluster, mft_lcn: boot.mft_lcn, mft_mirror_lcn: boot.mft_mirror_lcn,
-        clusters_per_index_buffer: boot.clusters_per_index_buffer, mft_record_size: record_size as u32,
-        volume_serial_number: boot.volume_serial_number, source: volume_path,
+    let (data, holes) = match writer.join() {
+        Ok(Ok(written)) => written,
+        Ok(Err(e)) => return Err(Error::io(msg::dump_write_failed())(e)),
+        Err(panic) => std::panic::resume_unwind(panic),
     };
+    if extracted_bytes != layout.total_bytes { return Err(Error::Corrupt(msg::extracted_mismatch(extracted_bytes, layout.total_bytes))); }
+    if holes > 0 {
+        info!("{}", msg::holes(holes / 1024 / 1024));
+    }
+    Ok((extracted_bytes, data))
+}
+
+/// Параллельное чтение блоков: рабочие потоки открывают источник каждый сам (`open`) и берут
+/// блоки по очереди, а вызывающий поток восстанавливает порядок и передает их в `tx`.
+/// Поток не берет блок дальше `window` от последнего переданного - память ограничена.
+/// Ошибка чтения в любом потоке останавливает остальные.
+fn read_parallel<R: Read + Seek>(open: &(dyn Fn() -> Result<R> + Sync), items: &[ReadItem], threads: usize, window: usize, tx: &SyncSender<Vec<u8>>, progress: &mut Progress) -> Result<u64> {
+    let next = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+    let gate = (Mutex::new(0usize), Condvar::new());
+    let mut extracted_bytes = 0u64;
+    std::thread::scope(|scope| {
+        let (done_tx, done_rx) = std::sync::mpsc::channel::<(usize, Vec<u8>)>();
+        let workers: Vec<_> = (0..threads).map(|_| {
+            let (next, stop, gate, done_tx) = (&next, &stop, &gate, done_tx.clone());
+            scope.spawn(move || -> Result<()> {
+                let read = || -> Result<()> {
+                    let mut vol = open()?;
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(item) = items.get(index) else { break };
+                        let mut emitted = gate.0.lock().unwrap_or_else(|e| e.into_inner());
+                        while index >= *emitted + window && !stop.load(Ordering::Relaxed) {
+                            emitted = gate.1.wait(emitted).unwrap_or_else(|e| e.into_inner());
+                        }
+                        drop(emitted);
+                        if stop.load(Ordering::Relaxed) { break; }
+                        let mut chunk = Vec::new();
+                        read_item(&mut vol, item, &mut chunk)?;
+                        if done_tx.send((index, chunk)).is_err() { break; }
+                    }
+                    Ok(())
+                };
+                let result = read();
+                if result.is_err() {
+                    // Блок этого потока уже не придет: ждущие его потоки отпускаются
+                    stop.store(true, Ordering::Relaxed);
+                    gate.1.notify_all();
+                }
+                result
+            })
+        }).collect();
+        drop(done_tx);
+
+        let mut pending = std::collections::BTreeMap::new();
+        let mut emitted = 0usize;
+        'receive: for (index, chunk) in &done_rx {
+            pending.insert(index, chunk);
+            while let Some(chunk) = pending.remove(&emitted) {
+                let len = chunk.len() as u64;
+                if tx.send(chunk).is_err() { break 'receive; }
+                emitted += 1;
+                extracted_bytes += len;
+                progress.set(extracted_bytes);
+            }
+            *gate.0.lock().unwrap_or_else(|e| e.into_inner()) = emitted;
+            gate.1.notify_all();
+        }
+        // Запись прервана: ждущие потоки отпускаются, их блоки уже не нужны
+        stop.store(true, Ordering::Relaxed);
+        gate.1.notify_all();
+        drop(done_rx);
+        workers.into_iter().try_for_each(|worker| worker.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic)))
+    })?;
+    Ok(extracted_bytes)
+}
+
+/// Итог извлечения: объем и предупреждения о раскладке $MFT.
+fn report_extracted(extracted_bytes: u64, meta: &MftMeta) {
+    info!("{}", msg::extracted(extracted_bytes / 1024 / 1024));
+    if let Some(report) = &meta.layout {
+        info!("{}", msg::layout(report.fragments, report.start_percent));
+        for warning in &report.warnings {
+            warn!("[!] {}", warning);
+        }
+    }
+}
+
+fn write_meta(out: &str, meta: &MftMeta) -> Result<()> {
+    let path = format!("{}.meta.json", out);
+    output::write_json_file(&path, meta).map_err(Error::io(common::write_failed(&path)))
+}
+
+fn extract_volume(vol: Box<dyn image::ImageReader>, volume_path: &str, out: &str, shadow_copy: Option<u32>, opts: &ExtractOptions, case: &CaseInfo, tags: &Tags) -> Result<MftMeta> {
+    let (mut vol, layout, meta, reopen) = open_mft(vol, volume_path, shadow_copy, opts, case, tags)?;
+
+    info!("{}", msg::strict(layout.total_bytes));
+    let out_file = File::create(out).map_err(Error::io(common::create_failed(out)))?;
+    let (extracted_bytes, _) = copy_mft(&mut vol, reopen.as_deref(), &layout, opts, Some(out_file), false)?;
+
+    report_extracted(extracted_bytes, &meta);
+    write_meta(out, &meta)?;
+    if opts.audit {
+        audit::append("extract", volume_path, out, case, tags);
+    }
+    Ok(meta)
+}
+
+/// Извлечение $MFT сразу в память для разбора без промежуточного дампа (`play --stream`).
+/// Блоки тома идут по каналу в поток-сборщик, который складывает их в буфер и, если задан
+/// `tee`, параллельно пишет обычный дамп с meta.json. Буфер занимает в памяти весь $MFT.
+pub fn stream(image: &str, tee: Option<&str>, opts: &ExtractOptions, case: &CaseInfo, tags: &Tags) -> Result<(Vec<u8>, MftMeta)> {
+    info!("{}", msg::started_memory());
+    let (vol, source) = open_source(image, opts)?;
+    info!("{}", msg::source(&source));
+    if let Some(tee) = tee {
+        info!("{}", msg::tee(tee));
+    }
+    let (mut vol, layout, meta, reopen) = open_mft(vol, &source, None, opts, case, tags)?;
+    let tee_file = tee.map(|path| File::create(path).map_err(Error::io(common::create_failed(path)))).transpose()?;
+
+    info!("{}", msg::strict(layout.total_bytes));
+    let (extracted_bytes, data) = copy_mft(&mut vol, reopen.as_deref(), &layout, opts, tee_file, true)?;
+
+    report_extracted(extracted_bytes, &meta);
+    if let Some(tee) = tee {
+        write_meta(tee, &meta)?;
+        if opts.audit {
+            audit::append("extract", &source, tee, case, tags);
+        }
+    }
+    Ok((data, meta))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Cursor};
+    use std::sync::mpsc::sync_channel;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use super::*;
+
+    const BLOCK: usize = 64;
+    const BLOCKS: usize = 64;
+
+    /// Источник для read_parallel: общий образ (блок i заполнен байтом i), самый дальний
+    /// прочитанный блок и блок, чтение которого завершается ошибкой.
+    struct Probe {
+        data: Cursor<Arc<[u8]>>,
+        furthest: Arc<AtomicUsize>,
+        fail_at: Option<usize>,
+    }
+
+    impl Read for Probe {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let block = self.data.position() as usize / BLOCK;
+            if Some(block) == self.fail_at {
+                return Err(io::Error::other("bad sector"));
+            }
+            self.furthest.fetch_max(block, Ordering::SeqCst);
+            self.data.read(buf)
+        }
+    }
+
+    impl Seek for Probe {
+        fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+            self.data.seek(pos)
+        }
+    }
+
+    fn image() -> Arc<[u8]> {
+        (0..BLOCKS).flat_map(|i| [i as u8; BLOCK]).collect()
+    }
+
+    fn opener(image: &Arc<[u8]>, furthest: &Arc<AtomicUsize>, fail_at: Option<usize>) -> impl Fn() -> Result<Probe> + Sync {
+        let (image, furthest) = (image.clone(), furthest.clone());
+        move || Ok(Probe { data: Cursor::new(image.clone()), furthest: furthest.clone(), fail_at })
+    }
+
+    /// Блоки в порядке `blocks`; None - разреженный блок.
+    fn items(blocks: impl IntoIterator<Item = Option<usize>>) -> Vec<ReadItem> {
+        blocks.into_iter().map(|b| ReadItem { offset: b.map(|b| (b * BLOCK) as u64), len: BLOCK }).collect()
+    }
+
+    fn progress() -> Progress {
+        Progress::new("extract", String::new(), (BLOCKS * BLOCK) as u64, ProgressUnit::Bytes, false)
+    }
+
+    /// read_parallel с потоком записи, который забирает блоки по мере поступления.
+    fn run(open: &(dyn Fn() -> Result<Probe> + Sync), items: &[ReadItem], threads: usize, window: usize) -> (Result<u64>, Vec<Vec<u8>>) {
+        let (tx, rx) = sync_channel(window);
+        let writer = std::thread::spawn(move || rx.into_iter().collect::<Vec<Vec<u8>>>());
+        let result = read_parallel(open, items, threads, window, &tx, &mut progress());
+        drop(tx);
+        (result, writer.join().unwrap())
+    }
+
+    #[test]
+    fn read_parallel_keeps_item_order() {
+        let image = image();
+        let furthest = Arc::new(AtomicUsize::new(0));
+        let open = opener(&image, &furthest, None);
+        // Фрагменты в обратном порядке, каждый десятый блок разреженный
+        let items = items((0..BLOCKS).map(|k| (k % 10 != 9).then_some(BLOCKS - 1 - k)));
+        let expected: Vec<Vec<u8>> = items.iter().map(|item| match item.offset {
+            Some(offset) => image[offset as usize..offset as usize + BLOCK].to_vec(),
+            None => vec![0; BLOCK],
+        }).collect();
+        for threads in [1, 2, 4, 8] {
+            for window in [1, 2, 5] {
+                let (result, chunks) = run(&open, &items, threads, window);
+                assert_eq!(result.unwrap(), (BLOCKS * BLOCK) as u64);
+                assert!(chunks == expected, "{} потоков, окно {}", threads, window);
+            }
+        }
+    }
+
+    #[test]
+    fn read_parallel_window_limits_read_ahead() {
+        let image = image();
+        let furthest = Arc::new(AtomicUsize::new(0));
+        let open = opener(&image, &furthest, None);
+        let items = items((0..BLOCKS).map(Some));
+        let window = 4;
+        // Поток записи стоит: блок 0 не передан, и читатели не уходят дальше окна
+        let (tx, rx) = sync_channel(0);
+        std::thread::scope(|scope| {
+            let reader = scope.spawn(|| read_parallel(&open, &items, 4, window, &tx, &mut progress()));
+            std::thread::sleep(Duration::from_millis(200));
+            assert!(furthest.load(Ordering::SeqCst) < window);
+            let chunks: Vec<Vec<u8>> = rx.iter().take(BLOCKS).collect();
+            assert_eq!(reader.join().unwrap().unwrap(), (BLOCKS * BLOCK) as u64);
+            assert_eq!(chunks.concat(), image[..]);
+        });
+    }
+
+    #[test]
+    fn read_parallel_stops_on_error() {
+        let image = image();
+        let furthest = Arc::new(AtomicUsize::new(0));
+        let items = items((0..BLOCKS).map(Some));
+
+        let open = opener(&image, &furthest, Some(40));
+        let (result, chunks) = run(&open, &items, 4, 4);
+        assert!(matches!(result, Err(Error::Io { .. })));
+        assert!(chunks.len() <= 40);
+        assert!(chunks.iter().enumerate().all(|(i, chunk)| chunk[0] == i as u8));
+
+        // Источник не открылся в одном из потоков - остальные тоже останавливаются
+        let opened = AtomicUsize::new(0);
+        let working = opener(&image, &furthest, None);
+        let open = || match opened.fetch_add(1, Ordering::SeqCst) {
+            1 => Err(Error::Corrupt("no source".into())),
+            _ => working(),
+        };
+        let (result, _) = run(&open, &items, 4, 2);
+        assert!(matches!(result, Err(Error::Corrupt(_))));
+    }
 
-    if let Ok(mut f) = File::create(format!("{}.meta.json", out)) {
-        let _ = serde_json::to_writer_pretty(&mut f, &meta);
-        let _ = f.write_all(b"\n");
+    #[test]
+    fn read_parallel_stops_when_writer_fails() {
+        let image = image();
+        let furthest = Arc::new(AtomicUsize::new(0));
+        let open = opener(&image, &furthest, None);
+        let items = items((0..BLOCKS).map(Some));
+        // Поток записи принимает пять блоков и закрывает канал, как при ошибке записи
+        let (tx, rx) = sync_channel(0);
+        let writer = std::thread::spawn(move || rx.iter().take(5).count());
+        let extracted = read_parallel(&open, &items, 4, 4, &tx, &mut progress()).unwrap();
+        assert_eq!(writer.join().unwrap(), 5);
+        assert_eq!(extracted, (5 * BLOCK) as u64);
     }
 }
\ No newline at end of file
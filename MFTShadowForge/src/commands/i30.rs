@@ -0,0 +1,234 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Seek};
+
+use byteorder::{ByteOrder, LittleEndian};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use super::extract::{self, MftLayout, PartitionSelect};
+use super::volume::{self, VolumeRecords, ENTRY_MASK};
+use crate::audit;
+use crate::error::{Error, Result};
+use crate::i18n::common;
+use crate::i18n::parse as parse_msg;
+use crate::image::{self, ImageReader};
+use crate::mft::attributes::FileNameAttribute;
+use crate::mft::parser::RecordSource;
+use crate::mft::record::MftRecordHeader;
+use crate::models::{CaseInfo, MftEntry, Tags};
+use crate::output::JsonlWriter;
+
+/// Правдоподобные метки $FN в slack: 1980-01-01 .. 2100-01-01 (FILETIME).
+const FILETIME_MIN: u64 = 119_600_064_000_000_000;
+const FILETIME_MAX: u64 = 157_469_184_000_000_000;
+/// Длина ключа $FILE_NAME без имени.
+const FILE_NAME_HEADER: usize = 66;
+
+/// Элемент индекса $I30 каталога: живой или найденный в slack узла.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct I30Entry {
+    pub directory_entry: u64,
+    pub directory_path: String,
+    /// Ссылка на запись MFT; у элементов slack заголовок может быть затерт
+    pub entry_number: Option<u64>,
+    pub sequence_number: Option<u16>,
+    pub file_name: String,
+    /// Пространство имен $FN: 0 - POSIX, 1 - Win32, 2 - DOS, 3 - Win32&DOS
+    pub namespace: u8,
+    pub created: String,
+    pub modified: String,
+    pub mft_modified: String,
+    pub accessed: String,
+    pub logical_size: u64,
+    pub allocated_size: u64,
+    pub file_attributes: u32,
+    pub slack: bool,
+    /// VCN блока INDX; None - $INDEX_ROOT
+    pub node_vcn: Option<u64>,
+    pub node_offset: usize,
+}
+
+impl I30Entry {
+    fn from_key(key: &[u8], reference: Option<u64>, slack: bool, node_vcn: Option<u64>, node_offset: usize) -> Option<Self> {
+        let fn_attr = FileNameAttribute::parse(key)?;
+        Some(I30Entry {
+            directory_entry: fn_attr.parent_directory_reference & ENTRY_MASK,
+            directory_path: String::new(),
+            entry_number: reference.map(|r| r & ENTRY_MASK),
+            sequence_number: reference.map(|r| (r >> 48) as u16),
+            file_name: fn_attr.name,
+            namespace: fn_attr.name_type,
+            created: fn_attr.creation_time.to_rfc3339(),
+            modified: fn_attr.modified_time.to_rfc3339(),
+            mft_modified: fn_attr.mft_modified_time.to_rfc3339(),
+            accessed: fn_attr.accessed_time.to_rfc3339(),
+            logical_size: fn_attr.logical_size,
+            allocated_size: LittleEndian::read_u64(&key[40..48]),
+            file_attributes: LittleEndian::read_u32(&key[56..60]),
+            slack,
+            node_vcn,
+            node_offset,
+        })
+    }
+
+    /// Одна и та же ссылка с тем же именем (копии, сдвинутые при перестройке узла).
+    fn same_file(&self, other: &I30Entry) -> bool {
+        self.entry_number == other.entry_number && self.sequence_number == other.sequence_number && self.file_name == other.file_name
+    }
+}
+
+/// Проверяет, похож ли `data[pos..]` на ключ $FILE_NAME элемента каталога `dir`;
+/// возвращает длину ключа.
+fn slack_key_len(data: &[u8], pos: usize, end: usize, dir: u64) -> Option<usize> {
+    if pos + FILE_NAME_HEADER > end { return None; }
+    let key = &data[pos..end];
+    if LittleEndian::read_u64(&key[0..8]) & ENTRY_MASK != dir { return None; }
+    let name_len = key[64] as usize;
+    if name_len == 0 || key[65] > 3 { return None; }
+    let key_len = FILE_NAME_HEADER + name_len * 2;
+    if key_len > key.len() { return None; }
+    if !(0..4).all(|i| (FILETIME_MIN..FILETIME_MAX).contains(&LittleEndian::read_u64(&key[8 + i * 8..16 + i * 8]))) { return None; }
+    let name: Vec<u16> = key[FILE_NAME_HEADER..key_len].chunks_exact(2).map(LittleEndian::read_u16).collect();
+    let valid = char::decode_utf16(name.iter().copied())
+        .all(|c| c.is_ok_and(|c| !c.is_control() && !matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|')));
+    valid.then_some(key_len)
+}
+
+/// Элементы одного узла: живые (`active`) и найденные в slack - от конца занятой части
+/// до конца выделенной с шагом 8 байт. Заголовок элемента перед ключом в slack часто
+/// затерт, поэтому ссылка на запись берется из него, только если длина ключа в нем сходится.
+fn scan_node(node: &volume::IndexNode, dir: u64, active: bool, out: &mut Vec<I30Entry>) {
+    let data = node.data;
+    let used = node.used.min(node.allocated);
+    if active {
+        let mut pos = node.entries;
+        while pos + 16 <= used {
+            let entry_len = LittleEndian::read_u16(&data[pos + 8..pos + 10]) as usize;
+            let key_len = LittleEndian::read_u16(&data[pos + 10..pos + 12]) as usize;
+            let flags = LittleEndian::read_u32(&data[pos + 12..pos + 16]);
+            if flags & 0x02 != 0 || entry_len < 16 { break; }
+            if let Some(key) = data.get(pos + 16..(pos + 16 + key_len).min(used)) {
+                let reference = LittleEndian::read_u64(&data[pos..pos + 8]);
+                out.extend(I30Entry::from_key(key, Some(reference), false, node.vcn, pos));
+            }
+            pos += entry_len;
+        }
+    }
+
+    let mut pos = used.next_multiple_of(8);
+    while pos + FILE_NAME_HEADER <= node.allocated {
+        let Some(key_len) = slack_key_len(data, pos, node.allocated, dir) else {
+            pos += 8;
+            continue;
+        };
+        let reference = (pos >= 16 && LittleEndian::read_u16(&data[pos - 6..pos - 4]) as usize == key_len)
+            .then(|| LittleEndian::read_u64(&data[pos - 16..pos - 8]));
+        out.extend(I30Entry::from_key(&data[pos..pos + key_len], reference, true, node.vcn, pos));
+        pos += key_len.next_multiple_of(8);
+    }
+}
+
+/// Элементы $I30 каталога `dir`. Из slack отбрасываются копии живых элементов
+/// и повторы: остаются только имена, которых в индексе уже нет.
+pub fn scan_directory<R: Read + Seek>(src: &mut VolumeRecords<R>, dir: u64, include_active: bool) -> Result<Vec<I30Entry>, String> {
+    let mut found = Vec::new();
+    volume::for_each_index_node(src, dir, |node| {
+        scan_node(node, dir, true, &mut found);
+        false
+    })?;
+    let (live, slack): (Vec<I30Entry>, Vec<I30Entry>) = found.into_iter().partition(|e| !e.slack);
+    let mut recovered: Vec<I30Entry> = Vec::new();
+    for entry in slack {
+        if live.iter().any(|l| l.same_file(&entry)) || recovered.iter().any(|r| r.same_file(&entry) && r.modified == entry.modified) {
+            continue;
+        }
+        recovered.push(entry);
+    }
+    Ok(if include_active { live.into_iter().chain(recovered).collect() } else { recovered })
+}
+
+/// Том для `parse --i30-image`: тот же, с которого снят разбираемый дамп.
+pub struct I30Source {
+    vol: Box<dyn ImageReader>,
+    layout: MftLayout,
+}
+
+impl I30Source {
+    pub fn open(image: &str, partition: Option<PartitionSelect>) -> Result<Self> {
+        let volume_path = extract::volume_path(image);
+        let mut vol = image::open(&volume_path).map_err(Error::io(parse_msg::i30_image_open_failed(&volume_path)))?;
+        let layout = extract::locate_mft_at(&mut vol, partition)?;
+        Ok(I30Source { vol, layout })
+    }
+
+    /// Slack-элементы индекса каталога строки отчета. Пусто, если на томе по этому номеру
+    /// уже другая запись (sequence не совпадает - образ не от того дампа или каталог удален).
+    pub fn slack_for(&mut self, entry: &MftEntry) -> Vec<I30Entry> {
+        let mut src = VolumeRecords { vol: &mut self.vol, layout: &self.layout };
+        let current = src.fetch_record(entry.entry_number).as_deref().and_then(MftRecordHeader::parse);
+        if current.is_none_or(|h| h.sequence_number != entry.sequence_number) {
+            return Vec::new();
+        }
+        let mut found = scan_directory(&mut src, entry.entry_number, false).unwrap_or_default();
+        for item in &mut found {
+            item.directory_path = entry.full_path.clone();
+        }
+        found
+    }
+}
+
+/// Элементы из отчета команды `i30` (JSONL); строки другого формата пропускаются.
+pub fn read_report(path: &str) -> io::Result<Vec<I30Entry>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut entries = Vec::new();
+    for line in reader.lines() {
+        if let Ok(entry) = serde_json::from_str::<I30Entry>(&line?) {
+            entries.push(entry);
+        }
+    }
+    Ok(entries)
+}
+
+/// Разбор индексов $I30 всех каталогов тома: имена и метки $FN удаленных файлов
+/// из slack узлов, в том числе тех, чьи записи MFT уже заняты другими файлами.
+pub fn run(image: &str, out_jsonl: &str, partition: Option<PartitionSelect>, active: bool) -> Result<()> {
+    info!("[*] Запуск I30");
+    let volume_path = extract::volume_path(image);
+    let mut vol = image::open(&volume_path).map_err(Error::io(format!("Ошибка открытия {}", volume_path)))?;
+    let layout = extract::locate_mft_at(&mut vol, partition)?;
+
+    info!("[*] Проход 1: дерево путей и список каталогов...");
+    let mut dirs = Vec::new();
+    let path_builder = volume::index_volume(&mut vol, &layout, |entry_num, header, _| {
+        if header.is_directory() {
+            dirs.push((entry_num, header.sequence_number, header.is_in_use()));
+        }
+    });
+    info!("[*] Каталогов: {}", dirs.len());
+
+    info!("[*] Проход 2: разбор индексов $I30...");
+    let out = File::create(out_jsonl).map_err(Error::io(format!("Не удалось создать {}", out_jsonl)))?;
+    let mut writer = JsonlWriter::new(BufWriter::new(out));
+    let mut src = VolumeRecords { vol: &mut vol, layout: &layout };
+    let (mut slack, mut live, mut failed) = (0u64, 0u64, 0u64);
+    for (dir, seq, in_use) in dirs {
+        // Индекс удаленного каталога читается тоже: его блоки могли еще не переиспользоваться
+        let entries = match scan_directory(&mut src, dir, active) {
+            Ok(entries) => entries,
+            Err(_) if !in_use => continue,
+            Err(e) => { failed += 1; warn!("[!] Каталог {}: {}", dir, e); continue; }
+        };
+        let path = path_builder.get_full_path(dir, seq);
+        for mut entry in entries {
+            entry.directory_path = path.clone();
+            if entry.slack { slack += 1 } else { live += 1 }
+            writer.write(&entry).map_err(Error::io(common::write_failed(out_jsonl)))?;
+        }
+    }
+    writer.flush().map_err(Error::io(common::write_failed(out_jsonl)))?;
+    info!("[+] Элементов из slack: {}{}, каталогов с ошибками: {} -> {}", slack,
+        if active { format!(", живых: {}", live) } else { String::new() }, failed, out_jsonl);
+    audit::append("i30", &volume_path, out_jsonl, &CaseInfo::default(), &Tags::new());
+    Ok(())
+}
\ No newline at end of file
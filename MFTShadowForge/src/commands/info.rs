@@ -0,0 +1,260 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+
+use serde::Serialize;
+use tracing::{info, warn};
+
+use super::extract::{self, MftLayout, PartitionEntry, PartitionSelect};
+use super::volume::{self, VolumeRecords};
+use crate::error::{Error, Result};
+use crate::i18n::common;
+use crate::image;
+use crate::mft::parser::{apply_fixups, FixupResult};
+use crate::mft::record::MftRecordHeader;
+use crate::models::MftMeta;
+use crate::output;
+
+/// Записи $MFT, которые Windows дублирует в $MFTMirr ($MFT, $MFTMirr, $LogFile, $Volume).
+const MIRROR_RECORDS: u64 = 4;
+/// Системные записи 0-15, без которых том не монтируется.
+const SYSTEM_RECORDS: u64 = 16;
+
+/// Сведения `info`: метаданные дампа и, при анализе тома, геометрия, разделы и проверки.
+#[derive(Serialize)]
+struct VolumeInfo {
+    #[serde(flatten)]
+    meta: MftMeta,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    geometry: Option<Geometry>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    partitions: Vec<PartitionEntry>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    checks: Vec<HealthCheck>,
+}
+
+/// Геометрия тома по VBR и положение $MFT/$MFTMirr в байтах от начала образа.
+#[derive(Serialize)]
+struct Geometry {
+    total_sectors: u64,
+    volume_size: u64,
+    clusters_per_file_record_segment: i8,
+    mft_offset: u64,
+    mft_mirror_offset: u64,
+    mft_size: u64,
+    mft_records: u64,
+}
+
+/// Результат одной проверки целостности тома.
+#[derive(Serialize)]
+struct HealthCheck {
+    name: &'static str,
+    passed: bool,
+    detail: String,
+}
+
+impl HealthCheck {
+    fn new(name: &'static str, result: Result<String, String>) -> Self {
+        match result {
+            Ok(detail) => HealthCheck { name, passed: true, detail },
+            Err(detail) => HealthCheck { name, passed: false, detail },
+        }
+    }
+}
+
+fn geometry(layout: &MftLayout) -> Geometry {
+    let boot = &layout.boot;
+    Geometry {
+        total_sectors: boot.total_sectors,
+        volume_size: boot.total_sectors * boot.bytes_per_sector as u64,
+        clusters_per_file_record_segment: boot.clusters_per_file_record_segment,
+        mft_offset: layout.partition_offset + boot.mft_lcn * layout.bytes_per_cluster,
+        mft_mirror_offset: layout.partition_offset + boot.mft_mirror_lcn * layout.bytes_per_cluster,
+        mft_size: layout.total_bytes,
+        mft_records: layout.total_records(),
+    }
+}
+
+/// Резервная копия VBR лежит в последнем секторе тома (за total_sectors) и должна совпадать с основной.
+fn check_backup_boot<R: Read + Seek>(vol: &mut R, layout: &MftLayout) -> Result<String, String> {
+    let bps = layout.boot.bytes_per_sector as usize;
+    let mut read_sector = |offset: u64| -> Result<Vec<u8>, String> {
+        let mut buf = vec![0u8; bps];
+        vol.seek(SeekFrom::Start(offset)).and_then(|_| vol.read_exact(&mut buf)).map_err(|e| format!("смещение {:#X}: {}", offset, e))?;
+        Ok(buf)
+    };
+    let primary = read_sector(layout.partition_offset)?;
+    let backup_offset = layout.partition_offset + layout.boot.total_sectors * bps as u64;
+    let backup = read_sector(backup_offset).map_err(|e| format!("резервный VBR не прочитан ({}) - образ тома обрезан?", e))?;
+    if primary == backup {
+        Ok(format!("резервный VBR ({:#X}) совпадает с основным", backup_offset))
+    } else {
+        Err(format!("резервный VBR ({:#X}) отличается от основного", backup_offset))
+    }
+}
+
+/// Все кластеры $MFT и $MFTMirr должны лежать в пределах тома.
+fn check_bounds(layout: &MftLayout) -> Result<String, String> {
+    let volume_clusters = match layout.boot.sectors_per_cluster {
+        0 => 0,
+        spc => layout.boot.total_sectors / spc as u64,
+    };
+    let outside: Vec<String> = layout.runs.iter()
+        .filter(|r| !r.is_sparse && r.lcn + r.length > volume_clusters)
+        .map(|r| format!("VCN {} (LCN {}+{})", r.vcn_start, r.lcn, r.length))
+        .collect();
+    if !outside.is_empty() {
+        return Err(format!("runs $MFT за пределами тома ({} кластеров): {}", volume_clusters, outside.join(", ")));
+    }
+    if layout.boot.mft_mirror_lcn >= volume_clusters {
+        return Err(format!("$MFTMirr (LCN {}) за пределами тома ({} кластеров)", layout.boot.mft_mirror_lcn, volume_clusters));
+    }
+    Ok(format!("$MFT и $MFTMirr в пределах тома ({} кластеров)", volume_clusters))
+}
+
+/// Системные записи 0-15: сигнатура FILE и fixups.
+fn check_system_records<R: Read + Seek>(vol: &mut R, layout: &MftLayout) -> Result<String, String> {
+    let mut src = VolumeRecords { vol, layout };
+    let count = SYSTEM_RECORDS.min(layout.total_records());
+    let failed: Vec<String> = (0..count).filter_map(|entry| volume::read_record(&mut src, entry).err()).collect();
+    if failed.is_empty() {
+        Ok(format!("записи 0-{} читаются", count - 1))
+    } else {
+        Err(failed.join("; "))
+    }
+}
+
+/// Первые записи $MFTMirr совпадают с $MFT (после fixups), а $DATA записи 1 указывает на LCN из VBR.
+fn check_mirror<R: Read + Seek>(vol: &mut R, layout: &MftLayout) -> Result<String, String> {
+    let record_size = layout.record_size;
+    let bps = layout.boot.bytes_per_sector;
+    let mirror_offset = layout.partition_offset + layout.boot.mft_mirror_lcn * layout.bytes_per_cluster;
+    let mut mismatched = Vec::new();
+    for entry in 0..MIRROR_RECORDS.min(layout.total_records()) {
+        let mut mft = layout.read_record(vol, entry)?;
+        let mut mirror = vec![0u8; record_size];
+        let offset = mirror_offset + entry * record_size as u64;
+        vol.seek(SeekFrom::Start(offset)).and_then(|_| vol.read_exact(&mut mirror)).map_err(|e| format!("$MFTMirr {}: {}", entry, e))?;
+        let fixed = |buf: &mut Vec<u8>| MftRecordHeader::parse(buf)
+            .filter(|h| h.signature == "FILE")
+            .is_some_and(|h| apply_fixups(buf, &h, bps) != FixupResult::Failed);
+        if !fixed(&mut mft) || !fixed(&mut mirror) || mft != mirror {
+            mismatched.push(entry.to_string());
+        }
+    }
+    if !mismatched.is_empty() {
+        return Err(format!("записи $MFTMirr не совпадают с $MFT: {}", mismatched.join(", ")));
+    }
+
+    let mut src = VolumeRecords { vol, layout };
+    let buffers = volume::read_record(&mut src, 1)?;
+    let stream = volume::data_stream(&buffers, record_size, "").map_err(|e| format!("$MFTMirr: {}", e))?;
+    match stream.runs.first() {
+        Some(run) if !run.is_sparse && run.lcn == layout.boot.mft_mirror_lcn => {
+            Ok(format!("записи 0-{} совпадают, $DATA $MFTMirr на LCN {}", MIRROR_RECORDS - 1, run.lcn))
+        }
+        Some(run) => Err(format!("$DATA $MFTMirr начинается с LCN {}, а VBR указывает {}", run.lcn, layout.boot.mft_mirror_lcn)),
+        None => Err("у $MFTMirr нет нерезидентного $DATA".to_string()),
+    }
+}
+
+fn health_checks<R: Read + Seek>(vol: &mut R, layout: &MftLayout) -> Vec<HealthCheck> {
+    vec![
+        HealthCheck::new("BackupBootSector", check_backup_boot(vol, layout)),
+        HealthCheck::new("VolumeBounds", check_bounds(layout)),
+        HealthCheck::new("SystemRecords", check_system_records(vol, layout)),
+        HealthCheck::new("MftMirror", check_mirror(vol, layout)),
+    ]
+}
+
+fn print_info(info: &VolumeInfo) {
+    let meta = &info.meta;
+    if !info.partitions.is_empty() {
+        info!("\n[+] Разделы:");
+        info!("    {:>3}  {:<5} {:>16} {:>16}  {:<38} {:<10} Имя", "№", "Схема", "Смещение", "Размер", "Тип", "ФС");
+        for p in &info.partitions {
+            let selected = if p.offset == meta.partition_offset { "*" } else { " " };
+            info!("  {} {:>3}  {:<5} {:>16} {:>16}  {:<38} {:<10} {}",
+                selected, p.index, p.scheme, format!("{:#X}", p.offset), p.size, p.type_id, p.filesystem, p.name);
+        }
+    }
+
+    info!("\n[+] Том: {}", meta.source);
+    info!("    partition_offset: {:#X}", meta.partition_offset);
+    info!("    volume_serial_number: {:#018X}", meta.volume_serial_number);
+    info!("    bytes_per_sector: {}", meta.bytes_per_sector);
+    info!("    bytes_per_cluster: {}", meta.bytes_per_cluster);
+    info!("    mft_record_size: {}", meta.mft_record_size);
+    info!("    mft_lcn: {}, mft_mirror_lcn: {}", meta.mft_lcn, meta.mft_mirror_lcn);
+    if let Some(g) = &info.geometry {
+        info!("    total_sectors: {} ({} байт)", g.total_sectors, g.volume_size);
+        info!("    clusters_per_file_record_segment: {}, clusters_per_index_buffer: {}", g.clusters_per_file_record_segment, meta.clusters_per_index_buffer);
+        info!("    $MFT: смещение {:#X}, {} байт ({} записей)", g.mft_offset, g.mft_size, g.mft_records);
+        info!("    $MFTMirr: смещение {:#X}", g.mft_mirror_offset);
+    }
+
+    if let Some(layout) = &meta.layout {
+        info!("\n[+] Раскладка $MFT:");
+        info!("    Фрагментов: {}", layout.fragments);
+        info!("    Кластеров: {} (разреженных: {}, {:.2}%)", layout.total_clusters, layout.sparse_clusters, layout.sparse_percent);
+        info!("    Кластеров тома: {}, начало $MFT на {:.1}% тома", layout.volume_clusters, layout.start_percent);
+        info!("    {:>12} {:>14} {:>14} {:>12}", "VCN", "LCN начала", "LCN конца", "Кластеров");
+        for range in &layout.ranges {
+            match range.lcn {
+                Some(lcn) => info!("    {:>12} {:>14} {:>14} {:>12}", range.vcn, lcn, lcn + range.clusters - 1, range.clusters),
+                None => info!("    {:>12} {:>14} {:>14} {:>12}", range.vcn, "sparse", "-", range.clusters),
+            }
+        }
+        for warning in &layout.warnings {
+            warn!("[!] {}", warning);
+        }
+    } else {
+        warn!("\n[!] Раскладка $MFT в метаданных отсутствует (дамп снят старой версией)");
+    }
+
+    if !info.checks.is_empty() {
+        info!("\n[+] Проверки:");
+        for check in &info.checks {
+            info!("    [{}] {}: {}", if check.passed { "OK" } else { "!!" }, check.name, check.detail);
+        }
+    }
+}
+
+/// Сведения о томе и раскладке $MFT: с тома/образа (без извлечения) или из meta.json дампа.
+/// По тому дополнительно выводятся таблица разделов, геометрия и проверки целостности
+/// (резервный VBR, границы $MFT, системные записи, $MFTMirr).
+pub fn run(image: Option<&str>, mft: Option<&str>, partition: Option<PartitionSelect>, out_json: Option<&str>) -> Result<()> {
+    info!("[*] Запуск Info");
+
+    let info = match (image, mft) {
+        (Some(image), _) => {
+            let volume_path = extract::volume_path(image);
+            let mut vol = image::open(&volume_path)
+                .map_err(Error::io(format!("Ошибка открытия {}", volume_path)))?;
+            let partitions = extract::partitions(&mut vol);
+            let layout = extract::locate_mft_at(&mut vol, partition)?;
+            VolumeInfo {
+                meta: layout.to_meta(&volume_path),
+                geometry: Some(geometry(&layout)),
+                partitions,
+                checks: health_checks(&mut vol, &layout),
+            }
+        }
+        (None, Some(mft)) => {
+            let meta_path = format!("{}.meta.json", mft);
+            let file = File::open(&meta_path)
+                .map_err(Error::io(format!("Не удалось открыть {}", meta_path)))?;
+            let meta = serde_json::from_reader(file)
+                .map_err(|e| Error::Invalid(format!("Некорректный {}: {}", meta_path, e)))?;
+            VolumeInfo { meta, geometry: None, partitions: Vec::new(), checks: Vec::new() }
+        }
+        (None, None) => return Err(Error::Invalid("Укажите --image или --mft".into())),
+    };
+
+    print_info(&info);
+
+    if let Some(out_json) = out_json {
+        output::write_json_file(out_json, &info).map_err(Error::io(common::write_failed(out_json)))?;
+        info!("\n[+] Результат сохранен: {}", out_json);
+    }
+    Ok(())
+}
\ No newline at end of file
@@ -0,0 +1,189 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use serde::Serialize;
+use serde_json::Value;
+use tracing::info;
+
+use crate::error::{Error, Result};
+use crate::i18n::common;
+use crate::output;
+
+/// Булевы признаки отчета, которые считаются детектами при агрегации.
+const DETECTION_FLAGS: [&str; 16] = [
+    "Timestomped", "FitsRules", "Copied", "uSecZeros", "TornWrite", "HasAds",
+    "PathTooDeep", "PathTooLong", "RepeatedNesting", "DotLikeName",
+    "CaseCollision", "SuspiciousReparse", "PosixNameAnomaly", "CompressedExecInTemp",
+    "LinkCountMismatch", "ShortNameMismatch",
+];
+
+/// Входной отчет: `HOST=report.jsonl` или просто `report.jsonl`.
+#[derive(Debug, Clone)]
+pub struct ReportInput {
+    pub host: String,
+    pub path: String,
+}
+
+impl ReportInput {
+    pub fn parse(spec: &str) -> Self {
+        match spec.split_once('=') {
+            Some((host, path)) if !host.is_empty() => Self { host: host.to_string(), path: path.to_string() },
+            _ => Self { host: default_host(), path: spec.to_string() },
+        }
+    }
+}
+
+/// Имя текущей машины (для отчетов без явного HOST=).
+pub fn default_host() -> String {
+    std::env::var("COMPUTERNAME")
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .unwrap_or_else(|_| String::from("localhost"))
+}
+
+#[derive(Debug, Default, Serialize)]
+struct VolumeSummary {
+    host: String,
+    volume: String,
+    source: String,
+    entries: u64,
+    detections: BTreeMap<String, u64>,
+}
+
+#[derive(Debug, Serialize)]
+struct RareAcrossHosts {
+    file_name: String,
+    host_count: usize,
+    total_hosts: usize,
+    hosts: Vec<String>,
+    example_path: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct MergeSummary {
+    total_hosts: usize,
+    total_entries: u64,
+    detections_total: BTreeMap<String, u64>,
+    volumes: Vec<VolumeSummary>,
+    rare_across_hosts: Vec<RareAcrossHosts>,
+}
+
+/// Том строки: буква диска из Full_Path, иначе имя исходного дампа.
+fn volume_of(row: &Value, fallback: &str) -> String {
+    let full_path = row.get("Full_Path").and_then(Value::as_str).unwrap_or("");
+    let bytes = full_path.as_bytes();
+    if bytes.len() >= 2 && bytes[1] == b':' && bytes[0].is_ascii_alphabetic() {
+        return full_path[..2].to_ascii_uppercase();
+    }
+    fallback.to_string()
+}
+
+/// Сливает отчеты в один JSONL с полями Host/Volume и пишет сводку `<out>.summary.json`.
+pub fn merge_reports(inputs: &[ReportInput], out_jsonl: &str, max_hosts: usize, top: usize) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(out_jsonl).map_err(Error::io(format!("Не удалось создать {}", out_jsonl)))?);
+
+    let mut summary = MergeSummary::default();
+    let mut volumes: BTreeMap<(String, String), VolumeSummary> = BTreeMap::new();
+    let mut host_ids: BTreeMap<String, usize> = BTreeMap::new();
+    // имя файла (lowercase) -> (хосты, пример пути)
+    let mut names: HashMap<String, (BTreeSet<usize>, String)> = HashMap::new();
+
+    for input in inputs {
+        let host_id = host_ids.len();
+        let host_id = *host_ids.entry(input.host.clone()).or_insert(host_id);
+        // Без буквы диска томом считается папка отчета (play) или имя самого отчета
+        let report_path = Path::new(&input.path);
+        let fallback_volume = report_path.parent()
+            .and_then(|p| p.file_name())
+            .or_else(|| report_path.file_stem())
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| input.path.clone());
+
+        let reader = BufReader::new(File::open(&input.path).map_err(Error::io(format!("Не удалось открыть {}", input.path)))?);
+        info!(" -> {} ({})", input.path, input.host);
+
+        for line in reader.lines().map_while(Result::ok) {
+            let line = line.trim();
+            if !line.starts_with('{') { continue; }
+            let Ok(row) = serde_json::from_str::<Value>(line) else { continue };
+            let volume = volume_of(&row, &fallback_volume);
+
+            // Host/Volume дописываются в начало объекта, порядок остальных полей не меняется
+            let rest = line[1..].trim_start();
+            let sep = if rest.starts_with('}') { "" } else { "," };
+            writeln!(writer, "{{\"Host\":{},\"Volume\":{}{}{}",
+                Value::from(input.host.as_str()), Value::from(volume.as_str()), sep, rest)
+                .map_err(Error::io(common::write_failed(out_jsonl)))?;
+
+            let vs = volumes.entry((input.host.clone(), volume.clone())).or_insert_with(|| VolumeSummary {
+                host: input.host.clone(), volume: volume.clone(), source: input.path.clone(), ..Default::default()
+            });
+            vs.entries += 1;
+            summary.total_entries += 1;
+            for flag in DETECTION_FLAGS {
+                if row.get(flag).and_then(Value::as_bool) == Some(true) {
+                    *vs.detections.entry(flag.to_string()).or_insert(0) += 1;
+                    *summary.detections_total.entry(flag.to_string()).or_insert(0) += 1;
+                }
+            }
+
+            if row.get("IsDirectory").and_then(Value::as_bool) == Some(true) { continue; }
+            let Some(file_name) = row.get("FileName").and_then(Value::as_str) else { continue };
+            if file_name.is_empty() { continue; }
+            let slot = names.entry(file_name.to_lowercase()).or_insert_with(|| {
+                (BTreeSet::new(), row.get("Full_Path").and_then(Value::as_str).unwrap_or("").to_string())
+            });
+            slot.0.insert(host_id);
+        }
+    }
+    writer.flush().map_err(Error::io(common::write_failed(out_jsonl)))?;
+
+    let host_names: Vec<String> = {
+        let mut v = vec![String::new(); host_ids.len()];
+        for (name, id) in &host_ids { v[*id] = name.clone(); }
+        v
+    };
+    summary.total_hosts = host_names.len();
+    summary.volumes = volumes.into_values().collect();
+
+    // Кросс-хостовая частота имеет смысл, только если хостов больше порога
+    if summary.total_hosts > max_hosts {
+        summary.rare_across_hosts = names.into_iter()
+            .filter(|(_, (hosts, _))| hosts.len() <= max_hosts)
+            .map(|(file_name, (hosts, example_path))| RareAcrossHosts {
+                file_name,
+                host_count: hosts.len(),
+                total_hosts: summary.total_hosts,
+                hosts: hosts.iter().map(|id| host_names[*id].clone()).collect(),
+                example_path,
+            })
+            .collect();
+        summary.rare_across_hosts.sort_by(|a, b| a.host_count.cmp(&b.host_count).then(a.file_name.cmp(&b.file_name)));
+        summary.rare_across_hosts.truncate(top);
+    }
+
+    info!("\n[+] Объединено записей: {} (хостов: {}, томов: {})", summary.total_entries, summary.total_hosts, summary.volumes.len());
+    for vs in &summary.volumes {
+        let preview: Vec<String> = vs.detections.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+        info!("    {:<20} {:<6} записей: {:<10} {}", vs.host, vs.volume, vs.entries, preview.join(", "));
+    }
+    if !summary.rare_across_hosts.is_empty() {
+        info!("\n[+] Имена, встречающиеся не более чем на {} хостах:", max_hosts);
+        for r in &summary.rare_across_hosts {
+            info!("    {} из {}: {} ({})", r.host_count, r.total_hosts, r.file_name, r.hosts.join(", "));
+        }
+    }
+
+    let summary_path = format!("{}.summary.json", out_jsonl);
+    output::write_json_file(&summary_path, &summary).map_err(Error::io(common::write_failed(&summary_path)))?;
+    info!("\n[+] Сводка сохранена: {}", summary_path);
+    Ok(())
+}
+
+pub fn run(inputs: &[String], out_jsonl: &str, max_hosts: usize, top: usize) -> Result<()> {
+    info!("[*] Запуск Merge");
+    let inputs: Vec<ReportInput> = inputs.iter().map(|s| ReportInput::parse(s)).collect();
+    merge_reports(&inputs, out_jsonl, max_hosts, top)?;
+    Ok(())
+}
\ No newline at end of file
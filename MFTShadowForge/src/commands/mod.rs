@@ -1,3 +1,26 @@
+#[cfg(feature = "agent")]
+pub mod agent;
+#[cfg(feature = "agent")]
+pub mod collect;
+pub mod activity;
+pub mod bench;
+pub mod bitmap;
+pub mod carve;
+pub mod diff;
+pub mod dirindex;
 pub mod extract;
+pub mod i30;
+pub mod info;
+pub mod merge;
 pub mod parse;
-pub mod play;
\ No newline at end of file
+pub mod play;
+pub mod query;
+pub mod recover;
+pub mod schema;
+pub mod stats;
+pub mod supertimeline;
+pub mod usnjrnl;
+pub mod verify;
+pub mod volume;
+pub mod watch;
+pub mod whohas;
\ No newline at end of file
@@ -1,22 +1,243 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::fs::File;
-use std::io::{BufWriter, Read, Seek, SeekFrom};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
 use byteorder::{ByteOrder, LittleEndian};
+use chrono::{DateTime, Utc};
+use tracing::{debug, info, trace, warn};
 
-use crate::mft::attributes::{FileNameAttribute, StandardInformation};
-use crate::mft::parser::{apply_fixups, FixupResult, MftParser};
+use crate::mft::attributes::{
+    reparse_tag_name, wof_algorithm_name, FileFlags, FileNameAttribute, ReparsePoint, StandardInformation,
+    EFS_STREAM, IO_REPARSE_TAG_WOF, TXF_DATA_STREAM, WOF_COMPRESSED_DATA_STREAM, WOF_PROVIDER_WIM,
+};
+use crate::mft::parser::{apply_fixups, FixupResult, MftParser, RecordSource};
+use crate::mft::path_builder::{PathBuilder, PathConfidence};
 use crate::mft::record::MftRecordHeader;
-use crate::models::{MftEntry, MftMeta};
-use crate::output::JsonlWriter;
-use crate::rules::rules::Rule;
-use crate::rules::timestamp::TimestampData;
+use crate::mft::efs::{EfsKeyEntry, EfsMetadata};
+use crate::mft::recycle::RecycleInfo;
+use crate::mft::slack::scan_record_slack;
+use crate::mft::secure::{scan_sds, SecurityDescriptor};
+use crate::mft::utils::{create_temp_file, NameNormalization};
+use crate::audit;
+use crate::error::{Error, Result};
+use crate::hashing::{to_hex, HashAlgo};
+use crate::i18n::{common, parse as msg};
+use crate::logging;
+use crate::commands::extract::{self, parse_data_runs, DataRun, PartitionSelect};
+use crate::commands::i30::{self, I30Source};
+use crate::commands::volume::{self, SourceVolume};
+use crate::image::{self, ImageReader};
+use crate::models::{
+    AdsStream, CaseInfo, DataRunEntry, EfsInfo, EfsKey, HardLink, MftEntry, MftMeta, MftUtilization, RawTimestamps, RecordSlack, RuleMatch, RunSummary,
+    SlackRemnant, Tags, SCHEMA_VERSION,
+};
+use crate::plugins::PluginSet;
+use crate::progress::{Progress, ProgressUnit};
+use crate::report_index::{index_path_for_report, ReportIndex};
+use crate::output::{self, timesketch_events, AlertsWriter, BodyfileWriter, CompressedWriter, Compression, EntrySink, JsonlWriter, ReportFormat, RunsWriter, TlnWriter};
+#[cfg(feature = "parquet")]
+use crate::output::ParquetWriter;
+#[cfg(feature = "siem")]
+use crate::output::{redact_url, ElasticSink, SplunkSink};
+use crate::rules::flags::is_compressed_exec_in_temp;
+use crate::rules::hashset::KnownHashSet;
+use crate::rules::path::{is_win32_invalid_name, PathShape};
+use crate::rules::reparse::{is_suspicious_reparse, resolve_relative};
+use crate::rules::profiles;
+use crate::rules::rules::Subject;
+use crate::rules::ruleset::{NamedRule, RuleSet, RuleSources};
+use crate::rules::siblings::CaseCollisionIndex;
+use crate::rules::timestamp::{short_name_diverges, TimestampData};
+
+/// Параметры команды parse.
+#[derive(Debug, Clone)]
+pub struct ParseOptions {
+    /// Извлекать содержимое резидентного $DATA
+    pub data: bool,
+    /// MD5/SHA1/SHA256 резидентного $DATA
+    pub hash: bool,
+    /// Файл хешсета (NSRL/свой список) для отметки известных файлов
+    pub hashset: Option<String>,
+    /// Не выводить записи, найденные в хешсете
+    pub skip_known: bool,
+    /// Вшитые наборы правил (--profile) вместо встроенных
+    pub profiles: Vec<String>,
+    /// Файл правил (TOML/YAML): без профилей - вместо встроенных, с ними - дополняет
+    /// и переопределяет одноименные
+    pub rules: Option<String>,
+    /// Sigma-правила file_event (файлы или каталоги) в дополнение к правилам
+    pub sigma: Vec<String>,
+    /// Файл правил-исключений (TOML/YAML): совпавшая с ним запись не считается срабатыванием
+    pub exclude_rules: Option<String>,
+    /// Порог глубины пути для PathTooDeep (0 - отключено)
+    pub max_path_depth: usize,
+    /// Порог длины пути для PathTooLong (0 - отключено)
+    pub max_path_length: usize,
+    /// Юникод-нормализация имен в выводе
+    pub normalize_paths: NameNormalization,
+    /// Выводить только записи, измененные после прошлого прогона (summary.json или USN)
+    pub since: Option<String>,
+    /// Сведения о деле (дополняют сохраненные в meta.json)
+    pub case: CaseInfo,
+    /// Метки источника для каждой строки отчета
+    pub tags: Tags,
+    /// Плагины-детекторы (динамические библиотеки)
+    pub plugins: Vec<String>,
+    /// Выгружать VCN -> LCN non-resident $DATA
+    pub runs: bool,
+    /// Дополнительно писать карту размещения потоков в CSV
+    pub runs_out: Option<String>,
+    /// Разбирать slack записей: строки и остатки прежних $SI/$FN
+    pub record_slack: bool,
+    /// Добавлять исходные FILETIME меток $SI/$FN
+    pub raw_timestamps: bool,
+    /// Показывать ход проходов в stderr
+    pub progress: bool,
+    /// Дерево путей с именами во временном файле (PathBuilder::low_memory), без индекса отчета
+    pub low_memory: bool,
+    /// Сохранять сырую запись (до fixups) в строках с детектами
+    pub preserve_flagged: bool,
+    /// Дополнительно писать отчет в Apache Parquet
+    pub out_parquet: Option<String>,
+    /// Дополнительно писать bodyfile для mactime
+    pub bodyfile: Option<String>,
+    /// Дополнительно писать JSONL только со строками с детектами
+    pub alerts: Option<String>,
+    /// Дополнительно писать временную шкалу TLN
+    pub tln: Option<String>,
+    /// Поля host и user строк TLN
+    pub tln_host: String,
+    pub tln_user: String,
+    /// Индексировать строки отчета в Elasticsearch (_bulk)
+    pub es_url: Option<String>,
+    pub es_index: String,
+    /// Отправлять строки отчета в Splunk HTTP Event Collector
+    pub splunk_hec_url: Option<String>,
+    pub splunk_token: String,
+    /// Сжатие основного JSONL
+    pub compress: Option<Compression>,
+    /// Формат основного JSONL
+    pub format: ReportFormat,
+    /// Том или образ, с которого снят дамп: slack индексов $I30 каталогов в поле I30Slack
+    pub i30_image: Option<String>,
+    pub i30_partition: Option<PartitionSelect>,
+    /// Отчет команды i30: старые имена переиспользованных каталогов для путей (PathConfidence=Carved)
+    pub i30_entries: Option<String>,
+    /// Поток $Secure:$SDS (recover -p \$Secure -s \$SDS): владелец и DACL по SecurityId
+    pub sds: Option<String>,
+    /// Том или образ, с которого снят дамп: нерезидентные $ATTRIBUTE_LIST,
+    /// с `data` - и содержимое нерезидентных $DATA
+    pub image: Option<String>,
+    pub partition: Option<PartitionSelect>,
+    /// Предел размера нерезидентного $DATA, читаемого с `image`
+    pub max_data_size: u64,
+}
+
+impl ParseOptions {
+    pub fn rule_sources(&self) -> RuleSources {
+        RuleSources { profiles: self.profiles.clone(), rules: self.rules.clone(), sigma: self.sigma.clone(), exclude: self.exclude_rules.clone() }
+    }
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            data: false,
+            hash: false,
+            hashset: None,
+            skip_known: false,
+            profiles: Vec::new(),
+            rules: None,
+            sigma: Vec::new(),
+            exclude_rules: None,
+            max_path_depth: 32,
+            max_path_length: 260,
+            normalize_paths: NameNormalization::None,
+            since: None,
+            case: CaseInfo::default(),
+            tags: Tags::new(),
+            plugins: Vec::new(),
+            runs: false,
+            runs_out: None,
+            record_slack: false,
+            raw_timestamps: false,
+            progress: false,
+            low_memory: false,
+            preserve_flagged: false,
+            out_parquet: None,
+            bodyfile: None,
+            alerts: None,
+            tln: None,
+            tln_host: String::new(),
+            tln_user: String::new(),
+            es_url: None,
+            es_index: String::new(),
+            splunk_hec_url: None,
+            splunk_token: String::new(),
+            compress: None,
+            format: ReportFormat::Jsonl,
+            i30_image: None,
+            i30_partition: None,
+            i30_entries: None,
+            sds: None,
+            image: None,
+            partition: None,
+            max_data_size: 1024 * 1024,
+        }
+    }
+}
 
 fn meta_path_for_mft(mft_path: &str) -> String { format!("{}.meta.json", mft_path) }
 
-fn load_mft_meta(mft_path: &str) -> Option<MftMeta> {
-    serde_json::from_reader(File::open(&meta_path_for_mft(mft_path)).ok()?).ok()
+/// Фактический путь отчета: при сжатии к имени дописывается расширение архива, если его нет.
+fn compressed_report_path(out_jsonl: &str, compress: Option<Compression>) -> String {
+    match compress {
+        Some(c) if out_jsonl != STDIO_PATH && !out_jsonl.ends_with(c.extension()) => format!("{}{}", out_jsonl, c.extension()),
+        _ => out_jsonl.to_string(),
+    }
+}
+
+pub fn summary_path_for_report(out_jsonl: &str) -> String { format!("{}.summary.json", out_jsonl) }
+
+pub fn load_mft_meta(mft_path: &str) -> Option<MftMeta> {
+    serde_json::from_reader(File::open(meta_path_for_mft(mft_path)).ok()?).ok()
+}
+
+/// Порог инкрементального разбора (`--since`).
+#[derive(Clone, Copy)]
+enum SinceThreshold {
+    /// Итоги прошлого прогона: LSN записи и USN из $SI
+    Summary { max_lsn: u64, max_usn: u64 },
+    /// Явный USN: записи без USN в $SI считаются неизмененными
+    Usn(u64),
+}
+
+impl SinceThreshold {
+    fn load(spec: &str, meta: Option<&MftMeta>) -> Result<Self> {
+        if let Ok(usn) = spec.parse::<u64>() {
+            return Ok(SinceThreshold::Usn(usn));
+        }
+        let summary: RunSummary = File::open(spec).ok()
+            .and_then(|f| serde_json::from_reader(f).ok())
+            .ok_or_else(|| Error::Invalid(msg::since_unreadable(spec)))?;
+        if let (Some(prev), Some(meta)) = (summary.volume_serial_number, meta) {
+            if prev != meta.volume_serial_number {
+                return Err(Error::Invalid(msg::since_other_volume(spec, prev, meta.volume_serial_number)));
+            }
+        }
+        Ok(SinceThreshold::Summary { max_lsn: summary.max_lsn, max_usn: summary.max_usn })
+    }
+
+    fn is_unchanged(&self, entry: &MftEntry) -> bool {
+        match *self {
+            SinceThreshold::Summary { max_lsn, max_usn } => entry.logfile_sequence_number <= max_lsn && entry.journal_usn <= max_usn,
+            SinceThreshold::Usn(usn) => entry.journal_usn <= usn,
+        }
+    }
 }
 
-fn read_attr_name(record: &[u8], attr_offset: usize, attr_end: usize) -> String {
+pub fn read_attr_name(record: &[u8], attr_offset: usize, attr_end: usize) -> String {
     if attr_offset + 12 > attr_end { return String::new(); }
     let name_len = record[attr_offset + 9] as usize;
     let name_off = LittleEndian::read_u16(&record[attr_offset + 10..attr_offset + 12]) as usize;
@@ -31,9 +252,68 @@ fn read_attr_name(record: &[u8], attr_offset: usize, attr_end: usize) -> String
     String::from_utf16_lossy(&u16s)
 }
 
-fn read_nonresident_data_size(record: &[u8], attr_offset: usize, attr_end: usize) -> Option<u64> {
-    if attr_offset + 0x38 > attr_end { return None; }
-    Some(LittleEndian::read_u64(&record[attr_offset + 0x30..attr_offset + 0x38]))
+/// Runlist non-resident атрибута (стартовый VCN берется из заголовка атрибута).
+pub fn read_nonresident_runs(record: &[u8], attr_offset: usize, attr_end: usize) -> Option<Vec<DataRun>> {
+    if attr_offset + 0x40 > attr_end { return None; }
+    let start_vcn = LittleEndian::read_u64(&record[attr_offset + 16..attr_offset + 24]);
+    let dr_off = LittleEndian::read_u16(&record[attr_offset + 32..attr_offset + 34]) as usize;
+    if dr_off < 0x40 || attr_offset + dr_off >= attr_end { return None; }
+    parse_data_runs(record, attr_offset + dr_off, attr_end, start_vcn).ok()
+}
+
+/// Размеры из заголовка non-resident атрибута: выделенный (0x28), реальный (0x30), инициализированный (0x38).
+pub struct NonResidentSizes {
+    pub allocated: u64,
+    pub real: u64,
+    pub initialized: u64,
+}
+
+/// Slack базовой записи и ее расширений в поле отчета; None - slack везде нулевой.
+fn record_slack(buffers: &[Cow<[u8]>]) -> Option<RecordSlack> {
+    let mut size = 0;
+    let mut found: Option<RecordSlack> = None;
+    for buf in buffers {
+        let Some(header) = MftRecordHeader::parse(buf) else { continue };
+        size += (header.allocated_size as usize).min(buf.len()).saturating_sub(header.real_size as usize);
+        let Some(scan) = scan_record_slack(buf, header.real_size as usize, header.allocated_size as usize) else { continue };
+        let slack = found.get_or_insert_with(|| RecordSlack { size: 0, non_zero_bytes: 0, strings: Vec::new(), remnants: Vec::new() });
+        slack.non_zero_bytes += scan.non_zero;
+        slack.strings.extend(scan.strings);
+        slack.remnants.extend(scan.attributes.into_iter().map(|attr| {
+            let [created, modified, mft_modified, accessed] = attr.times.map(|t| t.to_rfc3339());
+            SlackRemnant {
+                attribute: if attr.attr_type == 0x10 { "$STANDARD_INFORMATION" } else { "$FILE_NAME" }.to_string(),
+                offset: attr.offset,
+                name: attr.name,
+                parent_entry_number: attr.parent_reference.map(|r| r & 0xFFFFFFFFFFFF),
+                parent_sequence_number: attr.parent_reference.map(|r| (r >> 48) as u16),
+                created, modified, mft_modified, accessed,
+            }
+        }));
+    }
+    found.map(|slack| RecordSlack { size, ..slack })
+}
+
+/// Резидентный поток $EFS в поле отчета; нераспознанный заголовок - поток без ключей.
+fn efs_info(metadata: Option<EfsMetadata>) -> EfsInfo {
+    let keys = |list: Vec<EfsKeyEntry>| list.into_iter()
+        .map(|k| EfsKey { sid: k.sid, thumbprint: k.thumbprint, container: k.container, provider: k.provider, user_name: k.user_name })
+        .collect();
+    match metadata {
+        Some(m) => EfsInfo { resident: true, version: Some(m.version), users: keys(m.users), recovery_agents: keys(m.recovery_agents) },
+        None => EfsInfo { resident: true, version: None, users: Vec::new(), recovery_agents: Vec::new() },
+    }
+}
+
+/// Размеры достоверны только в экстенте с начальным VCN 0, в остальных они нулевые.
+pub fn read_nonresident_sizes(record: &[u8], attr_offset: usize, attr_end: usize) -> Option<NonResidentSizes> {
+    if attr_offset + 0x40 > attr_end { return None; }
+    if LittleEndian::read_u64(&record[attr_offset + 16..attr_offset + 24]) != 0 { return None; }
+    Some(NonResidentSizes {
+        allocated: LittleEndian::read_u64(&record[attr_offset + 0x28..attr_offset + 0x30]),
+        real: LittleEndian::read_u64(&record[attr_offset + 0x30..attr_offset + 0x38]),
+        initialized: LittleEndian::read_u64(&record[attr_offset + 0x38..attr_offset + 0x40]),
+    })
 }
 
 fn extract_human_readable(data: &[u8]) -> String {
@@ -43,9 +323,29 @@ fn extract_human_readable(data: &[u8]) -> String {
         .collect()
 }
 
-// возвращаем не только буферы, но и флаг наличия non-resident $ATTRIBUTE_LIST
-fn gather_record_buffers(parser: &mut MftParser, entry_num: u64, base_buffer: Vec<u8>) -> (Vec<Vec<u8>>, bool) {
-    let mut buffers = vec![base_buffer];
+/// Записи-расширения (номер, sequence) из значения $ATTRIBUTE_LIST (кроме самой базовой записи).
+fn collect_extents(list: &[u8], entry_num: u64, total_records: u64, extents: &mut std::collections::HashSet<(u64, u16)>) {
+    let mut list_off = 0;
+    while list_off + 26 <= list.len() {
+        let ext_type = LittleEndian::read_u32(&list[list_off..list_off + 4]);
+        if ext_type == 0 { break; }
+        let ext_len = LittleEndian::read_u16(&list[list_off + 4..list_off + 6]) as usize;
+        if ext_len == 0 || list_off.saturating_add(ext_len) > list.len() { break; }
+
+        let base_ref = LittleEndian::read_u64(&list[list_off + 16..list_off + 24]);
+        let extent_entry = base_ref & 0xFFFFFFFFFFFF;
+
+        if extent_entry != entry_num && extent_entry > 0 && extent_entry < total_records {
+            extents.insert((extent_entry, (base_ref >> 48) as u16));
+        }
+        list_off += ext_len;
+    }
+}
+
+// возвращаем не только буферы, но и флаг наличия non-resident $ATTRIBUTE_LIST;
+// базовая запись заимствуется у вызывающего, память выделяется только под расширения
+pub fn gather_record_buffers<'a, S: RecordSource>(parser: &mut S, entry_num: u64, base_buffer: impl Into<Cow<'a, [u8]>>) -> (Vec<Cow<'a, [u8]>>, bool) {
+    let mut buffers = vec![base_buffer.into()];
     let mut extents_to_fetch = std::collections::HashSet::new();
     let mut complex_extents = false;
 
@@ -57,8 +357,8 @@ fn gather_record_buffers(parser: &mut MftParser, entry_num: u64, base_buffer: Ve
     let mut attr_offset = header.first_attribute_offset as usize;
     
     // ИЗМЕНЕНИЕ 1: Строгое ограничение по real_size (защита от мусора в slack-пространстве)
-    let mut used_end = std::cmp::min(header.real_size as usize, parser.record_size);
-    if used_end < attr_offset { used_end = parser.record_size; } // Защита от битого real_size
+    let mut used_end = std::cmp::min(header.real_size as usize, parser.record_size());
+    if used_end < attr_offset { used_end = parser.record_size(); } // Защита от битого real_size
 
     while attr_offset + 8 <= used_end {
         let attr_type = LittleEndian::read_u32(&buffers[0][attr_offset..attr_offset + 4]);
@@ -72,294 +372,1092 @@ fn gather_record_buffers(parser: &mut MftParser, entry_num: u64, base_buffer: Ve
         if attr_type == 0x20 {
             if non_resident {
                 complex_extents = true; // Фиксируем, что список атрибутов на диске
+                // С томом (--image) список читается по runlist, без него экстенты теряются
+                if let Some(list) = parser.read_nonresident(&buffers[0], attr_offset, attr_end) {
+                    collect_extents(&list, entry_num, parser.total_records(), &mut extents_to_fetch);
+                }
             } else if attr_offset + 22 <= used_end {
                 let value_len = LittleEndian::read_u32(&buffers[0][attr_offset + 16..attr_offset + 20]) as usize;
                 let value_off = LittleEndian::read_u16(&buffers[0][attr_offset + 20..attr_offset + 22]) as usize;
                 let content_offset = attr_offset.saturating_add(value_off);
                 let content_end = std::cmp::min(content_offset.saturating_add(value_len), attr_end);
+                if let Some(list) = buffers[0].get(content_offset..content_end) {
+                    collect_extents(list, entry_num, parser.total_records(), &mut extents_to_fetch);
+                }
+            }
+        }
+        attr_offset = attr_end;
+    }
+
+    for (extent_entry, extent_seq) in extents_to_fetch {
+        buffers.extend(parser.fetch_extent(extent_entry, extent_seq).map(Cow::Owned));
+    }
+    (buffers, complex_extents)
+}
+
+/// Учет записи (после fixups) в статистике заполненности $MFT: число атрибутов
+/// и объем резидентного $DATA.
+fn account_record(util: &mut MftUtilization, record: &[u8], header: &MftRecordHeader) {
+    if header.is_in_use() { util.in_use += 1; } else { util.free += 1; }
+    if header.base_record_reference != 0 { util.extension_records += 1; }
+
+    let mut attr_offset = header.first_attribute_offset as usize;
+    let mut used_end = std::cmp::min(header.real_size as usize, record.len());
+    if used_end < attr_offset { used_end = record.len(); }
+    while attr_offset + 8 <= used_end {
+        let attr_type = LittleEndian::read_u32(&record[attr_offset..attr_offset + 4]);
+        if attr_type == 0xFFFFFFFF || attr_type == 0 { break; }
+        let attr_len = LittleEndian::read_u32(&record[attr_offset + 4..attr_offset + 8]) as usize;
+        if attr_len == 0 || attr_offset.saturating_add(attr_len) > used_end { break; }
 
-                let mut list_off = content_offset;
-                while list_off + 26 <= content_end {
-                    let ext_type = LittleEndian::read_u32(&buffers[0][list_off..list_off + 4]);
-                    if ext_type == 0 { break; }
-                    let ext_len = LittleEndian::read_u16(&buffers[0][list_off + 4..list_off + 6]) as usize;
-                    if ext_len == 0 || list_off.saturating_add(ext_len) > content_end { break; }
+        util.attributes_total += 1;
+        if attr_type == 0x80 && header.is_in_use() && record[attr_offset + 8] == 0 && attr_offset + 20 <= used_end {
+            util.resident_data_bytes += LittleEndian::read_u32(&record[attr_offset + 16..attr_offset + 20]) as u64;
+        }
+        attr_offset += attr_len;
+    }
+}
+
+/// Состояние разбора, накопленное в проходе 1 и нужное для сборки записей.
+pub struct ParseContext {
+    pub opts: ParseOptions,
+    pub source_file: String,
+    pub drive_prefix: String,
+    pub record_size: usize,
+    pub volume_birth: Option<chrono::DateTime<chrono::Utc>>,
+    pub case_index: CaseCollisionIndex,
+    pub known_hashes: Option<KnownHashSet>,
+    /// Дескрипторы безопасности из $SDS по security_id
+    pub security: Option<HashMap<u32, SecurityDescriptor>>,
+    pub rules_list: RuleSet,
+    /// Исключения (--exclude-rules): снимают совпадения rules_list с известного шума
+    pub exclude_list: RuleSet,
+    /// Метки строк: из meta.json, метки запуска и (с --case-in-rows) поля дела
+    pub tags: Tags,
+    pub plugins: PluginSet,
+}
 
-                    let base_ref = LittleEndian::read_u64(&buffers[0][list_off + 16..list_off + 24]);
-                    let extent_entry = base_ref & 0xFFFFFFFFFFFF;
+impl ParseContext {
+    /// Контекст без хешсета, плагинов и меток - для разбора записей вне parse (diff дампов, carve).
+    pub fn standalone(opts: &ParseOptions, source_file: &str, drive_prefix: String, record_size: usize) -> Self {
+        Self {
+            opts: opts.clone(),
+            source_file: source_file.to_string(),
+            drive_prefix,
+            record_size,
+            volume_birth: None,
+            case_index: CaseCollisionIndex::new(),
+            known_hashes: None,
+            security: None,
+            rules_list: RuleSet::new(profiles::default_rules()),
+            exclude_list: RuleSet::default(),
+            tags: Tags::new(),
+            plugins: PluginSet::default(),
+        }
+    }
+
+    /// Контекст по опциям parse: правила (--rules, профили, sigma, --exclude-rules),
+    /// хешсет, $SDS и плагины загружаются так же, как в самой команде.
+    pub fn from_options(opts: &ParseOptions, source_file: &str, drive_prefix: String, record_size: usize, tags: Tags) -> Result<Self> {
+        let loaded = opts.rule_sources().load().map_err(|e| Error::Invalid(e.to_string()))?;
+        for line in &loaded.log {
+            info!("{}", line);
+        }
+        for warning in &loaded.warnings {
+            warn!("{}", warning);
+        }
 
-                    if extent_entry != entry_num && extent_entry > 0 && extent_entry < parser.total_records() {
-                        extents_to_fetch.insert(extent_entry);
+        let known_hashes = opts.hashset.as_ref().map(|hs_path| -> Result<_> {
+            // Поврежденная или неподдерживаемая база - неверный входной файл, а не ошибка ввода-вывода
+            let set = KnownHashSet::load(hs_path).map_err(|e| match e.kind() {
+                io::ErrorKind::InvalidData => Error::Invalid(format!("{}: {}", msg::hashset_failed(hs_path), e)),
+                _ => Error::io(msg::hashset_failed(hs_path))(e),
+            })?;
+            info!("{}", msg::hashset(set.len()));
+            Ok(set)
+        }).transpose()?;
+
+        let security = opts.sds.as_ref().map(|sds_path| -> Result<_> {
+            let descriptors = File::open(sds_path).and_then(|f| scan_sds(BufReader::new(f)))
+                .map_err(Error::io(msg::sds_failed(sds_path)))?;
+            info!("{}", msg::sds(descriptors.len()));
+            Ok(descriptors)
+        }).transpose()?;
+
+        let plugins = PluginSet::load(&opts.plugins)
+            .map_err(|e| Error::Invalid(msg::plugin_failed(e)))?;
+
+        Ok(Self {
+            opts: opts.clone(),
+            source_file: source_file.to_string(),
+            drive_prefix,
+            record_size,
+            volume_birth: None,
+            case_index: CaseCollisionIndex::new(),
+            known_hashes,
+            security,
+            rules_list: RuleSet::new(loaded.rules),
+            exclude_list: RuleSet::new(loaded.excludes),
+            tags,
+            plugins,
+        })
+    }
+}
+
+/// Дерево путей по опциям: --low-memory (имена во временном файле) и имена из --i30-entries.
+fn prepare_path_builder(parser: &mut MftParser, opts: &ParseOptions) -> Result<()> {
+    if opts.low_memory {
+        parser.path_builder = PathBuilder::low_memory()
+            .map_err(Error::io(msg::low_memory_failed()))?;
+        info!("{}", msg::low_memory(std::env::temp_dir().display()));
+    }
+    parser.path_builder.reserve(parser.total_records() as usize);
+    if let Some(report) = &opts.i30_entries {
+        let entries = i30::read_report(report)
+            .map_err(|e| Error::Invalid(format!("--i30-entries {}: {}", report, e)))?;
+        info!("{}", msg::i30_entries(entries.len()));
+        // DOS-имена 8.3 дублируют Win32-имена тех же файлов
+        for entry in entries.into_iter().filter(|e| e.namespace != 2) {
+            if let (Some(num), Some(seq)) = (entry.entry_number, entry.sequence_number) {
+                parser.path_builder.add_carved(num, seq, entry.directory_entry, entry.file_name);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Проход 1 для одной базовой записи (после fixups): имя в дерево путей,
+/// индекс соседей по регистру и время рождения тома.
+pub fn index_record(ctx: &mut ParseContext, path_builder: &mut PathBuilder, entry_num: u64, header: &MftRecordHeader, buffers: &[Cow<[u8]>]) {
+    let mut best_fn: Option<FileNameAttribute> = None;
+
+    for buf in buffers {
+        let buf_header = MftRecordHeader::parse(buf).unwrap();
+        let mut attr_offset = buf_header.first_attribute_offset as usize;
+        
+        let mut used_end = std::cmp::min(buf_header.real_size as usize, ctx.record_size);
+        if used_end < attr_offset { used_end = ctx.record_size; }
+
+        while attr_offset + 8 <= used_end {
+            let attr_type = LittleEndian::read_u32(&buf[attr_offset..attr_offset + 4]);
+            if attr_type == 0xFFFFFFFF || attr_type == 0 { break; }
+            let attr_len = LittleEndian::read_u32(&buf[attr_offset + 4..attr_offset + 8]) as usize;
+            if attr_len == 0 || attr_offset.saturating_add(attr_len) > used_end { break; }
+
+            let attr_end = attr_offset.saturating_add(attr_len);
+            let non_resident = buf[attr_offset + 8] != 0;
+
+            if attr_type == 0x10 && entry_num <= 11 && !non_resident && attr_offset + 22 <= attr_end {
+                let value_len = LittleEndian::read_u32(&buf[attr_offset + 16..attr_offset + 20]) as usize;
+                let value_off = LittleEndian::read_u16(&buf[attr_offset + 20..attr_offset + 22]) as usize;
+                let content_end = std::cmp::min(attr_offset.saturating_add(value_off).saturating_add(value_len), attr_end);
+                if let Some(slice) = buf.get(attr_offset.saturating_add(value_off)..content_end) {
+                    if let Some(si) = StandardInformation::parse(slice) {
+                        ctx.volume_birth = Some(ctx.volume_birth.unwrap_or(si.creation_time).min(si.creation_time));
                     }
-                    list_off += ext_len;
                 }
             }
+
+            if attr_type == 0x30 && !non_resident && attr_offset + 22 <= attr_end {
+                let value_len = LittleEndian::read_u32(&buf[attr_offset + 16..attr_offset + 20]) as usize;
+                let value_off = LittleEndian::read_u16(&buf[attr_offset + 20..attr_offset + 22]) as usize;
+                let content_end = std::cmp::min(attr_offset.saturating_add(value_off).saturating_add(value_len), attr_end);
+                if let Some(slice) = buf.get(attr_offset.saturating_add(value_off)..content_end) {
+                    if let Some(fn_attr) = FileNameAttribute::parse(slice) {
+                        let current_prio = match best_fn.as_ref() {
+                            Some(f) if f.name_type == 1 || f.name_type == 3 => 2,
+                            Some(_) => 1, None => 0,
+                        };
+                        if (fn_attr.name_type == 1 || fn_attr.name_type == 3) || current_prio == 0 {
+                            best_fn = Some(fn_attr);
+                        }
+                    }
+                }
+            }
+            attr_offset = attr_end;
         }
-        attr_offset = attr_end;
     }
 
-    for extent_entry in extents_to_fetch {
-        if let Some(mut ext_buf) = parser.fetch_record(extent_entry) {
-            if let Some(eh) = MftRecordHeader::parse(&ext_buf) {
-                if apply_fixups(&mut ext_buf, &eh, parser.bytes_per_sector) != FixupResult::Failed {
-                    buffers.push(ext_buf);
+    if let Some(fn_attr) = best_fn {
+        let parent_entry = fn_attr.parent_directory_reference & 0xFFFFFFFFFFFF;
+        let parent_seq = (fn_attr.parent_directory_reference >> 48) as u16;
+        let name = ctx.opts.normalize_paths.apply(fn_attr.name);
+        if header.is_in_use() { ctx.case_index.add(parent_entry, &name); }
+        path_builder.add_entry(entry_num, header.sequence_number, header.is_in_use(), parent_entry, parent_seq, name);
+    }
+}
+
+/// Проход 2 для одной базовой записи (после fixups): разбор атрибутов и эвристики.
+pub fn build_entry(ctx: &ParseContext, path_builder: &PathBuilder, entry_num: u64, header: &MftRecordHeader, buffers: &[Cow<[u8]>], is_torn_write: bool, complex_extents: bool) -> MftEntry {
+    let mut file_name = String::new();
+    let mut si_attr: Option<StandardInformation> = None;
+    let mut fn_attr_data: Option<FileNameAttribute> = None;
+    let mut content_data: Option<String> = None;
+    let mut zone_id_contents: Option<String> = None;
+    let mut has_ads = false;
+    let mut has_wof_stream = false;
+    let mut resident_data_loc: Option<(usize, std::ops::Range<usize>)> = None;
+    let mut reparse: Option<ReparsePoint> = None;
+    let mut has_txf_data = false;
+    let mut efs: Option<EfsInfo> = None;
+    let mut resident_descriptor: Option<SecurityDescriptor> = None;
+    let mut data_unnamed_size: Option<u64> = None;
+    let mut data_flags: u16 = 0;
+    let mut allocated_size_data: Option<u64> = None;
+    let mut initialized_size: Option<u64> = None;
+    let mut data_runs: Option<Vec<DataRunEntry>> = None;
+    let mut fn_logical_size: Option<u64> = None;
+    let mut fn_names: Vec<(u8, String, u64)> = Vec::new();
+    let mut short_fn: Option<(String, [DateTime<Utc>; 4])> = None;
+    let (mut fn_attribute_id, mut other_attribute_id) = (0u16, 0u16);
+    let mut ads_streams: Vec<AdsStream> = Vec::new();
+
+    for (buf_idx, buf) in buffers.iter().enumerate() {
+        let buf_header = MftRecordHeader::parse(buf).unwrap();
+        let mut attr_offset = buf_header.first_attribute_offset as usize;
+        
+        let mut used_end = std::cmp::min(buf_header.real_size as usize, ctx.record_size);
+        if used_end < attr_offset { used_end = ctx.record_size; }
+
+        while attr_offset + 8 <= used_end {
+            let attr_type = LittleEndian::read_u32(&buf[attr_offset..attr_offset + 4]);
+            if attr_type == 0xFFFFFFFF || attr_type == 0 { break; }
+
+            let attr_len = LittleEndian::read_u32(&buf[attr_offset + 4..attr_offset + 8]) as usize;
+            if attr_len == 0 || attr_offset.saturating_add(attr_len) > used_end { break; }
+
+            let attr_end = attr_offset.saturating_add(attr_len);
+            let non_resident = buf[attr_offset + 8] != 0;
+            let attr_name = read_attr_name(buf, attr_offset, attr_end);
+            let attr_id = if attr_offset + 16 <= attr_end { LittleEndian::read_u16(&buf[attr_offset + 14..attr_offset + 16]) } else { 0 };
+            
+            if attr_type == 0x80 && !attr_name.is_empty() { has_ads = true; }
+            if attr_type == 0x80 && attr_name == WOF_COMPRESSED_DATA_STREAM { has_wof_stream = true; }
+            if attr_type == 0x100 && attr_name == TXF_DATA_STREAM { has_txf_data = true; }
+            if attr_type == 0x100 && attr_name == EFS_STREAM && non_resident {
+                efs.get_or_insert(EfsInfo { resident: false, version: None, users: Vec::new(), recovery_agents: Vec::new() });
+            }
+            if attr_type == 0x80 && attr_name.is_empty() && attr_offset + 14 <= attr_end {
+                data_flags |= LittleEndian::read_u16(&buf[attr_offset + 12..attr_offset + 14]);
+            }
+
+            if !non_resident && attr_offset + 22 <= attr_end {
+                let value_len = LittleEndian::read_u32(&buf[attr_offset + 16..attr_offset + 20]) as usize;
+                let value_off = LittleEndian::read_u16(&buf[attr_offset + 20..attr_offset + 22]) as usize;
+                let content_end = std::cmp::min(attr_offset.saturating_add(value_off).saturating_add(value_len), attr_end);
+
+                match attr_type {
+                    0x10 => {
+                        if let Some(slice) = buf.get(attr_offset.saturating_add(value_off)..content_end) {
+                            si_attr = StandardInformation::parse(slice);
+                            other_attribute_id = attr_id;
+                        }
+                    }
+                    0x30 => {
+                        if let Some(slice) = buf.get(attr_offset.saturating_add(value_off)..content_end) {
+                            if let Some(fn_a) = FileNameAttribute::parse(slice) {
+                                fn_names.push((fn_a.name_type, fn_a.name.clone(), fn_a.parent_directory_reference));
+                                if fn_a.name_type == 2 {
+                                    short_fn = Some((fn_a.name.clone(), [fn_a.creation_time, fn_a.modified_time, fn_a.mft_modified_time, fn_a.accessed_time]));
+                                }
+                                let current_prio = match fn_attr_data.as_ref() {
+                                    Some(f) if f.name_type == 1 || f.name_type == 3 => 2,
+                                    Some(_) => 1, None => 0,
+                                };
+                                if (fn_a.name_type == 1 || fn_a.name_type == 3) || current_prio == 0 {
+                                    fn_logical_size = Some(fn_a.logical_size);
+                                    file_name = fn_a.name.clone();
+                                    fn_attr_data = Some(fn_a);
+                                    fn_attribute_id = attr_id;
+                                }
+                            }
+                        }
+                    }
+                    // Собственный дескриптор записи (тома NTFS 1.x и отдельные записи NTFS 3.x)
+                    0x50 => {
+                        if let Some(slice) = buf.get(attr_offset.saturating_add(value_off)..content_end) {
+                            resident_descriptor = SecurityDescriptor::parse(slice);
+                        }
+                    }
+                    0xC0 => {
+                        if let Some(slice) = buf.get(attr_offset.saturating_add(value_off)..content_end) {
+                            reparse = ReparsePoint::parse(slice);
+                        }
+                    }
+                    0x100 if attr_name == EFS_STREAM => {
+                        let metadata = buf.get(attr_offset.saturating_add(value_off)..content_end).and_then(EfsMetadata::parse);
+                        efs = Some(efs_info(metadata));
+                    }
+                    0x80 => {
+                        if attr_name.is_empty() { data_unnamed_size = Some(value_len as u64); }
+                        else { ads_streams.push(AdsStream { name: attr_name.clone(), size: value_len as u64, resident: true }); }
+                        if let Some(raw_data) = buf.get(attr_offset.saturating_add(value_off)..content_end) {
+                            if attr_name.is_empty() {
+                                resident_data_loc = Some((buf_idx, attr_offset.saturating_add(value_off)..content_end));
+                            }
+                            if attr_name == "Zone.Identifier" {
+                                zone_id_contents = Some(extract_human_readable(raw_data));
+                            } else if attr_name.is_empty() && ctx.opts.data {
+                                content_data = Some(extract_human_readable(raw_data));
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            } else if non_resident && attr_type == 0x80 {
+                // Размеры есть только у первого экстента потока (VCN 0)
+                if let Some(sizes) = read_nonresident_sizes(buf, attr_offset, attr_end) {
+                    if attr_name.is_empty() {
+                        data_unnamed_size = Some(sizes.real);
+                        allocated_size_data = Some(sizes.allocated);
+                        initialized_size = Some(sizes.initialized);
+                    } else {
+                        ads_streams.push(AdsStream { name: attr_name.clone(), size: sizes.real, resident: false });
+                    }
+                }
+                if ctx.opts.runs {
+                    if let Some(runs) = read_nonresident_runs(buf, attr_offset, attr_end) {
+                        data_runs.get_or_insert_with(Vec::new).extend(runs.into_iter().map(|r| DataRunEntry {
+                            stream: attr_name.clone(),
+                            vcn: r.vcn_start,
+                            lcn: if r.is_sparse { None } else { Some(r.lcn) },
+                            clusters: r.length,
+                            sparse: r.is_sparse,
+                        }));
+                    }
                 }
             }
+            attr_offset = attr_end;
         }
     }
-    (buffers, complex_extents)
+
+    let normalization_changed = ctx.opts.normalize_paths.changes(&file_name);
+    let file_name = ctx.opts.normalize_paths.apply(file_name);
+
+    let parent_entry = fn_attr_data.as_ref().map(|f| f.parent_directory_reference & 0xFFFFFFFFFFFF).unwrap_or(0);
+    let parent_seq = fn_attr_data.as_ref().map(|f| (f.parent_directory_reference >> 48) as u16).unwrap_or(0);
+    
+    let (mut parent_path, mut path_confidence) = path_builder.resolve(parent_entry, parent_seq);
+    
+    let join_path = |parent_path: &str, name: &str| if parent_path == "\\" || parent_path.is_empty() {
+        format!("{}\\{}", ctx.drive_prefix, name)
+    } else {
+        let sep = if parent_path.starts_with('\\') { "" } else { "\\" };
+        format!("{}{}{}\\{}", ctx.drive_prefix, sep, parent_path, name)
+    };
+    let mut full_path = join_path(&parent_path, &file_name);
+    // Все $FILE_NAME записи (и расширений): жесткие ссылки из других каталогов и DOS-имена
+    let mut link_paths = Vec::new();
+    let hard_links: Vec<HardLink> = fn_names.iter().map(|(name_type, name, parent_ref)| {
+        let (entry, seq) = (parent_ref & 0xFFFFFFFFFFFF, (parent_ref >> 48) as u16);
+        let (link_parent, confidence) = path_builder.resolve(entry, seq);
+        let link = HardLink {
+            parent_entry_number: entry,
+            parent_sequence_number: seq,
+            name: name.clone(),
+            name_type: *name_type,
+            full_path: join_path(&link_parent, &ctx.opts.normalize_paths.apply(name.clone())),
+        };
+        link_paths.push((link_parent, confidence));
+        link
+    }).collect();
+    // Основная цепочка оборвана - путь по другой ссылке записи, чей каталог еще на месте
+    if path_confidence == PathConfidence::Orphan {
+        let alternate = hard_links.iter().zip(link_paths).find(|(_, (_, c))| *c != PathConfidence::Orphan);
+        if let Some((link, (link_parent, _))) = alternate {
+            full_path = link.full_path.clone();
+            parent_path = link_parent;
+            path_confidence = PathConfidence::HardLink;
+        }
+    }
+    
+    let mut timestomped = false;
+    let mut usec_zeros = false;
+    let mut copied = false;
+    let mut c_0x10 = None; let mut m_0x10 = None; let mut a_0x10 = None; let mut r_0x10 = None;
+    let mut c_0x30 = None; let mut m_0x30 = None; let mut a_0x30 = None; let mut r_0x30 = None;
+
+    if let (Some(si), Some(fn_a)) = (&si_attr, &fn_attr_data) {
+        let ts = TimestampData {
+            si_c: si.creation_time, si_m: si.modified_time, si_e: si.mft_modified_time, si_a: si.accessed_time,
+            fn_c: fn_a.creation_time, fn_m: fn_a.modified_time, fn_e: fn_a.mft_modified_time, fn_a: fn_a.accessed_time,
+        };
+        timestomped = ts.is_timestomped() || ts.is_before_volume_birth(ctx.volume_birth);
+        usec_zeros = ts.has_usec_zeros(); copied = ts.is_copied();
+        c_0x10 = Some(si.creation_time.to_rfc3339()); m_0x10 = Some(si.modified_time.to_rfc3339());
+        a_0x10 = Some(si.accessed_time.to_rfc3339()); r_0x10 = Some(si.mft_modified_time.to_rfc3339());
+        c_0x30 = Some(fn_a.creation_time.to_rfc3339()); m_0x30 = Some(fn_a.modified_time.to_rfc3339());
+        a_0x30 = Some(fn_a.accessed_time.to_rfc3339()); r_0x30 = Some(fn_a.mft_modified_time.to_rfc3339());
+    }
+
+    let raw_timestamps = ctx.opts.raw_timestamps.then(|| {
+        let (si, fn_a) = (si_attr.as_ref().map(|s| s.raw_times), fn_attr_data.as_ref().map(|f| f.raw_times));
+        RawTimestamps {
+            created0x10: si.map(|t| t[0]), created0x30: fn_a.map(|t| t[0]),
+            last_modified0x10: si.map(|t| t[1]), last_modified0x30: fn_a.map(|t| t[1]),
+            last_record_change0x10: si.map(|t| t[2]), last_record_change0x30: fn_a.map(|t| t[2]),
+            last_access0x10: si.map(|t| t[3]), last_access0x30: fn_a.map(|t| t[3]),
+        }
+    });
+
+    let case_collision = header.is_in_use() && ctx.case_index.collides(parent_entry, &file_name);
+
+    let resident_data = resident_data_loc.map(|(idx, range)| &buffers[idx][range]);
+    let known_good = match (&ctx.known_hashes, resident_data) {
+        (Some(set), Some(data)) => set.contains_data(data),
+        _ => false,
+    };
+    let digest = |algo: HashAlgo| resident_data.filter(|_| ctx.opts.hash).map(|data| algo.digest_hex(data));
+
+    let recycle_info = resident_data
+        .filter(|_| RecycleInfo::is_candidate(&full_path.to_lowercase(), &file_name))
+        .and_then(RecycleInfo::parse);
+    // Каталог файла с завершающим разделителем: после замены пути жесткой ссылкой
+    // `full_path` оканчивается уже другим именем, резать его по длине `file_name` нельзя
+    let parent_dir = join_path(&parent_path, "");
+    let recycle_data_path = recycle_info.as_ref()
+        .map(|_| format!("{}{}", parent_dir, RecycleInfo::data_file_name(&file_name)));
+
+    let suspicious_reparse = reparse.as_ref().filter(|rp| rp.is_link()).and_then(|rp| {
+        let target = rp.target()?;
+        let target = if rp.is_relative { resolve_relative(&parent_dir, &target) } else { target };
+        Some(is_suspicious_reparse(&full_path, &target))
+    }).unwrap_or(false);
+
+    // Каждая жесткая ссылка - один $FILE_NAME; DOS-имя (8.3) - вторая запись той же ссылки
+    let link_names = fn_names.iter().filter(|(nt, _, _)| *nt != 2).count();
+    let link_count_mismatch = header.is_in_use() && !fn_names.is_empty() && link_names != header.hard_link_count as usize;
+
+    let posix_name_anomaly = (!fn_names.is_empty() && fn_names.iter().all(|(nt, _, _)| *nt == 0))
+        || fn_names.iter().any(|(_, name, _)| is_win32_invalid_name(name));
+
+    let shape = PathShape::new(&full_path);
+    let path_too_deep = shape.is_too_deep(ctx.opts.max_path_depth);
+    let path_too_long = shape.is_too_long(ctx.opts.max_path_length);
+    let repeated_nesting = shape.has_repeated_nesting();
+    let dot_like_name = shape.has_dot_like_name();
+
+    let usn = MftParser::get_update_sequence_number(&buffers[0], header).unwrap_or(0) as u64;
+
+    // Экстенты из $ATTRIBUTE_LIST идут в порядке записей - упорядочиваем по потоку и VCN
+    if let Some(runs) = data_runs.as_mut() {
+        runs.sort_by(|a, b| a.stream.cmp(&b.stream).then(a.vcn.cmp(&b.vcn)));
+    }
+
+    let file_size = data_unnamed_size.or(fn_logical_size).unwrap_or(0);
+    let is_dir = header.is_directory();
+
+    let path_lc = full_path.to_ascii_lowercase();
+    let subject = Subject {
+        path: &full_path, path_lc: &path_lc, size: file_size,
+        created: si_attr.as_ref().map(|s| s.creation_time), modified: si_attr.as_ref().map(|s| s.modified_time),
+        mft_modified: si_attr.as_ref().map(|s| s.mft_modified_time), accessed: si_attr.as_ref().map(|s| s.accessed_time),
+        has_ads, deleted: !header.is_in_use(), directory: is_dir, timestomped,
+    };
+    let mut rule_matches: Vec<RuleMatch> = ctx.rules_list.matching(&subject).into_iter().map(NamedRule::to_match).collect();
+    if !rule_matches.is_empty() && ctx.exclude_list.any_match(&subject) {
+        rule_matches.clear();
+    }
+    rule_matches.sort_by_key(|m| std::cmp::Reverse(m.severity));
+    let fits_rules = !rule_matches.is_empty();
+    let extension = if is_dir || !file_name.contains('.') { None } else { file_name.rsplit('.').next().map(|ext| ext.to_string()) };
+
+    let si_flags = si_attr.as_ref().map(|s| s.file_attributes).unwrap_or(0);
+    let flags = FileFlags::decode(si_flags, data_flags, reparse.is_some());
+    // Тег WOF без сжатых данных (потока или WIM) - остаток после распаковки, не сжатие
+    let wof = reparse.as_ref().filter(|rp| rp.tag == IO_REPARSE_TAG_WOF)
+        .filter(|rp| has_wof_stream || rp.wof_provider == Some(WOF_PROVIDER_WIM));
+    let wof_compressed = wof.is_some();
+    let wof_algorithm = wof.map(|rp| match rp.wof_algorithm {
+        Some(alg) => wof_algorithm_name(alg),
+        None if rp.wof_provider == Some(WOF_PROVIDER_WIM) => "WIM".to_string(),
+        None => "UNKNOWN".to_string(),
+    });
+    // Размер на диске - поток WofCompressedData; у файлов во внешнем WIM его нет
+    let wof_compressed_size = ads_streams.iter().find(|s| wof_compressed && s.name == WOF_COMPRESSED_DATA_STREAM).map(|s| s.size);
+    // Метки DOS-имени сравниваются с отдельным Win32-именем (у Win32&DOS атрибут один)
+    let short_name_mismatch = match (&short_fn, fn_attr_data.as_ref().filter(|f| f.name_type == 1)) {
+        (Some((_, dos)), Some(f)) => short_name_diverges(dos, &[f.creation_time, f.modified_time, f.mft_modified_time, f.accessed_time]),
+        _ => false,
+    };
+    let short_name = short_fn.map(|(name, _)| name);
+    let compressed_exec_in_temp = !is_dir && is_compressed_exec_in_temp(&full_path, extension.as_deref(), &flags);
+    let security_id = si_attr.as_ref().map(|s| s.security_id).unwrap_or(0);
+    // Атрибут $SECURITY_DESCRIPTOR в записи важнее общего $SDS: по нему ОС и проверяет доступ
+    let descriptor = resident_descriptor.as_ref().or_else(|| ctx.security.as_ref().and_then(|s| s.get(&security_id)));
+
+    let mut entry = MftEntry {
+        schema_version: SCHEMA_VERSION,
+        entry_number: entry_num, signature: header.signature.clone(), base_record_reference: header.base_record_reference,
+        real_size: header.real_size, allocated_size: header.allocated_size, sequence_number: header.sequence_number,
+        parent_entry_number: parent_entry, parent_sequence_number: parent_seq,
+        in_use: header.is_in_use(), is_directory: is_dir, parent_path, file_name, extension, full_path, path_confidence: path_confidence.as_str().to_string(),
+        has_ads, is_ads: has_ads, ads_streams, file_size, allocated_size_data, initialized_size,
+        created0x10: c_0x10, created0x30: c_0x30, last_modified0x10: m_0x10, last_modified0x30: m_0x30,
+        last_record_change0x10: r_0x10, last_record_change0x30: r_0x30, last_access0x10: a_0x10, last_access0x30: a_0x30, raw_timestamps,
+        update_sequence_number: usn, logfile_sequence_number: header.logfile_sequence_number,
+        journal_usn: si_attr.as_ref().map(|s| s.usn).unwrap_or(0),
+        security_id, owner_sid: descriptor.and_then(|d| d.owner.clone()), group_sid: descriptor.and_then(|d| d.group.clone()),
+        dacl: descriptor.and_then(|d| d.dacl.clone()), resident_security_descriptor: resident_descriptor.is_some(), si_flags,
+        is_sparse: flags.is_sparse, is_compressed: flags.is_compressed, is_encrypted: flags.is_encrypted, is_offline: flags.is_offline,
+        is_reparse: flags.is_reparse, is_temporary: flags.is_temporary,
+        reparse_tag: reparse.as_ref().map(|rp| reparse_tag_name(rp.tag)), reparse_target: reparse.as_ref().and_then(ReparsePoint::target),
+        wof_compressed, wof_algorithm, wof_compressed_size, has_txf_data, efs,
+        reference_count: header.hard_link_count, hard_links, name_type: fn_attr_data.as_ref().map(|f| f.name_type).unwrap_or(0),
+        recycle_original_path: recycle_info.as_ref().map(|r| r.original_path.clone()),
+        recycle_deleted_at: recycle_info.as_ref().map(|r| r.deleted_at.to_rfc3339()),
+        recycle_original_size: recycle_info.as_ref().map(|r| r.original_size),
+        recycle_data_path,
+        timestomped, fits_rules, rule_matches: fits_rules.then_some(rule_matches), known_good, zone_id_contents, content_data,
+        md5: digest(HashAlgo::Md5), sha1: digest(HashAlgo::Sha1), sha256: digest(HashAlgo::Sha256), data_runs, u_sec_zeros: usec_zeros, copied,
+        torn_write: is_torn_write, path_too_deep, path_too_long, repeated_nesting, dot_like_name, normalization_changed, case_collision, suspicious_reparse, posix_name_anomaly, compressed_exec_in_temp, link_count_mismatch, short_name, short_name_mismatch, complex_extents, fn_attribute_id, other_attribute_id, source_file: ctx.source_file.clone(),
+        raw_record: None, record_slack: ctx.opts.record_slack.then(|| record_slack(buffers)).flatten(), plugin_fields: serde_json::Map::new(), tags: ctx.tags.clone(),
+    };
+
+    if !ctx.plugins.is_empty() {
+        if let Ok(value) = serde_json::to_value(&entry) {
+            entry.plugin_fields = ctx.plugins.analyze(&value, buffers);
+        }
+    }
+    entry
 }
 
-pub fn run(path: &str, out_jsonl: &str, data_flag: bool) {
-    println!("[*] Запуск Parse");
+/// Путь `-`: дамп читается из stdin, JSONL пишется в stdout.
+pub const STDIO_PATH: &str = "-";
 
-    let meta_opt = load_mft_meta(path);
-    let (record_size, bytes_per_sector) = meta_opt.as_ref()
-        .map(|meta| (meta.mft_record_size as usize, meta.bytes_per_sector))
-        .unwrap_or((1024, 512));
+/// Сохраняет stdin во временный файл: для ссылок на записи-расширения и второго прохода
+/// нужен произвольный доступ, а поток из pipe не перематывается.
+fn spill_stdin() -> io::Result<PathBuf> {
+    let (path, file) = create_temp_file("mft_shadow_forge_stdin", "raw")?;
+    let mut file = BufWriter::new(file);
+    let copied = io::copy(&mut io::stdin().lock(), &mut file).and_then(|n| file.flush().map(|_| n));
+    match copied {
+        Ok(_) => Ok(path),
+        Err(e) => {
+            let _ = std::fs::remove_file(&path);
+            Err(e)
+        }
+    }
+}
+
+/// `out_jsonl` - None, если отчет уходит только в приемники (Elasticsearch).
+/// Итоги прогона - те же, что в `<report>.summary.json`.
+pub fn run(path: &str, out_jsonl: Option<&str>, opts: &ParseOptions) -> Result<RunSummary> {
+    if path != STDIO_PATH {
+        return run_source(path, path, out_jsonl, opts).map(|(_, _, summary)| summary);
+    }
+    let spill = spill_stdin().map_err(Error::io(msg::stdin_failed()))?;
+    // Парсер закрывает файл до удаления (на Windows открытый файл не удалить)
+    let result = run_source(&spill.to_string_lossy(), STDIO_PATH, out_jsonl, opts);
+    let _ = std::fs::remove_file(&spill);
+    result.map(|(_, _, summary)| summary)
+}
 
-    let drive_prefix = meta_opt.as_ref().and_then(|m| {
+/// Полный разбор дампа; возвращает парсер с деревом путей и контекст
+/// для последующего инкрементального разбора (watch).
+pub fn run_with_context(path: &str, out_jsonl: &str, opts: &ParseOptions) -> Result<(MftParser, ParseContext)> {
+    run_source(path, path, Some(out_jsonl), opts).map(|(parser, ctx, _)| (parser, ctx))
+}
+
+/// Диск из meta.json дампа снятого с `\\.\C:` - префикс путей; иначе пути начинаются с "\".
+fn drive_prefix(meta: Option<&MftMeta>) -> String {
+    meta.and_then(|m| {
         if m.source.starts_with("\\\\.\\") && m.source.len() >= 6 {
             let maybe_drive = &m.source[4..6];
             if maybe_drive.ends_with(':') { Some(maybe_drive.to_string()) } else { None }
         } else { None }
-    }).unwrap_or_default(); // Если не нашли диск - будет пустая строка, пути начнутся с "\"
+    }).unwrap_or_default()
+}
 
-    let mut parser = MftParser::new(path, record_size, bytes_per_sector).unwrap();
+/// Проход 1: дерево путей и индексы по всем базовым записям, попутно - заполненность $MFT.
+fn index_pass(parser: &mut MftParser, mut volume: Option<&mut ImageVolume>, ctx: &mut ParseContext) -> MftUtilization {
     let total_records = parser.total_records();
-    parser.path_builder.reserve(total_records as usize);
-
-    println!("[*] Проход 1: построение дерева путей и baseline...");
     let mut record_buffer = vec![0u8; parser.record_size];
-    let mut volume_birth: Option<chrono::DateTime<chrono::Utc>> = None;
-
+    let mut utilization = MftUtilization { records_total: total_records, ..Default::default() };
+    let mut progress = Progress::new("pass1", msg::pass1_stage(), total_records, ProgressUnit::Records, ctx.opts.progress);
     for entry_num in 0..total_records {
+        progress.set(entry_num);
         if parser.reader.read_exact(&mut record_buffer).is_err() { break; }
 
         let header = match MftRecordHeader::parse(&record_buffer) {
-            Some(h) => h, None => continue,
+            Some(h) => h, None => { utilization.empty += 1; continue; }
         };
 
-        if header.signature == "BAAD" || header.base_record_reference != 0 { continue; } 
-        if apply_fixups(&mut record_buffer, &header, parser.bytes_per_sector) == FixupResult::Failed { continue; }
+        if header.signature == "BAAD" {
+            trace!("{}", msg::bad_signature(entry_num));
+            utilization.baad += 1;
+            continue;
+        }
+        if apply_fixups(&mut record_buffer, &header, parser.bytes_per_sector) == FixupResult::Failed {
+            trace!("{}", msg::bad_fixup(entry_num));
+            utilization.fixup_failed += 1;
+            continue;
+        }
+        account_record(&mut utilization, &record_buffer, &header);
+        if header.base_record_reference != 0 { continue; }
 
-        let (buffers, _) = gather_record_buffers(&mut parser, entry_num, record_buffer.clone());
-        let mut best_fn: Option<FileNameAttribute> = None;
+        let (buffers, _) = gather_record_buffers(&mut DumpOnVolume { dump: parser, volume: volume.as_deref_mut() }, entry_num, &record_buffer[..]);
+        index_record(ctx, &mut parser.path_builder, entry_num, &header, &buffers);
+    }
+    progress.finish();
+    let parsed_records = utilization.in_use + utilization.free;
+    if parsed_records > 0 {
+        let avg = utilization.attributes_total as f64 / parsed_records as f64;
+        utilization.avg_attributes_per_record = (avg * 100.0).round() / 100.0;
+    }
+    utilization
+}
 
-        for buf in &buffers {
-            let buf_header = MftRecordHeader::parse(buf).unwrap();
-            let mut attr_offset = buf_header.first_attribute_offset as usize;
-            
-            let mut used_end = std::cmp::min(buf_header.real_size as usize, parser.record_size);
-            if used_end < attr_offset { used_end = parser.record_size; }
-
-            while attr_offset + 8 <= used_end {
-                let attr_type = LittleEndian::read_u32(&buf[attr_offset..attr_offset + 4]);
-                if attr_type == 0xFFFFFFFF || attr_type == 0 { break; }
-                let attr_len = LittleEndian::read_u32(&buf[attr_offset + 4..attr_offset + 8]) as usize;
-                if attr_len == 0 || attr_offset.saturating_add(attr_len) > used_end { break; }
-
-                let attr_end = attr_offset.saturating_add(attr_len);
-                let non_resident = buf[attr_offset + 8] != 0;
-
-                if attr_type == 0x10 && entry_num <= 11 && !non_resident && attr_offset + 22 <= attr_end {
-                    let value_len = LittleEndian::read_u32(&buf[attr_offset + 16..attr_offset + 20]) as usize;
-                    let value_off = LittleEndian::read_u16(&buf[attr_offset + 20..attr_offset + 22]) as usize;
-                    let content_end = std::cmp::min(attr_offset.saturating_add(value_off).saturating_add(value_len), attr_end);
-                    if let Some(slice) = buf.get(attr_offset.saturating_add(value_off)..content_end) {
-                        if let Some(si) = StandardInformation::parse(slice) {
-                            volume_birth = Some(volume_birth.unwrap_or(si.creation_time).min(si.creation_time));
-                        }
-                    }
-                }
+/// Проход 2 для одной записи, прочитанной в `record_buffer`: строка отчета
+/// или None для пустых, BAAD, расширений и записей с ошибкой fixup.
+fn decode_record(parser: &mut MftParser, volume: Option<&mut ImageVolume>, ctx: &ParseContext, entry_num: u64, record_buffer: &mut [u8]) -> Option<MftEntry> {
+    let header = MftRecordHeader::parse(record_buffer)?;
+    if header.signature == "BAAD" || header.base_record_reference != 0 { return None; }
 
-                if attr_type == 0x30 && !non_resident && attr_offset + 22 <= attr_end {
-                    let value_len = LittleEndian::read_u32(&buf[attr_offset + 16..attr_offset + 20]) as usize;
-                    let value_off = LittleEndian::read_u16(&buf[attr_offset + 20..attr_offset + 22]) as usize;
-                    let content_end = std::cmp::min(attr_offset.saturating_add(value_off).saturating_add(value_len), attr_end);
-                    if let Some(slice) = buf.get(attr_offset.saturating_add(value_off)..content_end) {
-                        if let Some(fn_attr) = FileNameAttribute::parse(slice) {
-                            let current_prio = match best_fn.as_ref() {
-                                Some(f) if f.name_type == 1 || f.name_type == 3 => 2,
-                                Some(_) => 1, None => 0,
-                            };
-                            if (fn_attr.name_type == 1 || fn_attr.name_type == 3) || current_prio == 0 {
-                                best_fn = Some(fn_attr);
-                            }
-                        }
-                    }
+    // Байты как на диске: после fixups последние слова секторов уже заменены
+    let raw_record = ctx.opts.preserve_flagged.then(|| record_buffer.to_vec());
+    let fixup_res = apply_fixups(record_buffer, &header, parser.bytes_per_sector);
+    if fixup_res == FixupResult::Failed { return None; }
+
+    let is_torn_write = fixup_res == FixupResult::TornWrite;
+    let (buffers, complex_extents) = gather_record_buffers(&mut DumpOnVolume { dump: parser, volume }, entry_num, &*record_buffer);
+    let mut entry = build_entry(ctx, &parser.path_builder, entry_num, &header, &buffers, is_torn_write, complex_extents);
+    if let Some(raw) = raw_record.filter(|_| entry.has_detections()) {
+        entry.raw_record = Some(to_hex(&raw));
+    }
+    Some(entry)
+}
+
+/// Строки отчета по дампу без вывода, в порядке записей: проход 1 выполняется при создании,
+/// проход 2 - по мере итерации.
+pub struct Records {
+    parser: MftParser,
+    ctx: ParseContext,
+    /// Том-источник (--image): нерезидентные данные и содержимое файлов
+    source_volume: Option<ImageVolume>,
+    next_entry: u64,
+    record_buffer: Vec<u8>,
+}
+
+impl Records {
+    fn new(mut parser: MftParser, mut ctx: ParseContext, mut source_volume: Option<ImageVolume>) -> io::Result<Self> {
+        parser.path_builder.reserve(parser.total_records() as usize);
+        index_pass(&mut parser, source_volume.as_mut(), &mut ctx);
+        parser.reader.seek(SeekFrom::Start(0))?;
+        let record_buffer = vec![0u8; parser.record_size];
+        Ok(Self { parser, ctx, source_volume, next_entry: 0, record_buffer })
+    }
+}
+
+impl Iterator for Records {
+    type Item = MftEntry;
+
+    fn next(&mut self) -> Option<MftEntry> {
+        while self.next_entry < self.parser.total_records() {
+            let entry_num = self.next_entry;
+            self.next_entry += 1;
+            if self.parser.reader.read_exact(&mut self.record_buffer).is_err() {
+                self.next_entry = u64::MAX;
+                return None;
+            }
+            if let Some(mut entry) = decode_record(&mut self.parser, self.source_volume.as_mut(), &self.ctx, entry_num, &mut self.record_buffer) {
+                let opts = &self.ctx.opts;
+                let wants_content = opts.data && entry.in_use && !entry.is_directory && entry.content_data.is_none() && (1..=opts.max_data_size).contains(&entry.file_size);
+                if let Some(volume) = self.source_volume.as_mut().filter(|_| wants_content) {
+                    entry.content_data = volume.read(entry.entry_number, entry.sequence_number, opts.max_data_size).map(|data| extract_human_readable(&data));
                 }
-                attr_offset = attr_end;
+                return Some(entry);
             }
         }
+        None
+    }
+}
+
+/// Разбирает дамп `path` без вывода и передает строки отчета в `f` по порядку записей.
+/// Для команд, которым нужны записи в памяти, а не JSONL (diff дампов).
+pub fn scan_entries<F: FnMut(MftEntry)>(path: &str, opts: &ParseOptions, f: F) -> io::Result<()> {
+    let meta = load_mft_meta(path);
+    let (record_size, bytes_per_sector) = meta.as_ref()
+        .map(|meta| (meta.mft_record_size as usize, meta.bytes_per_sector))
+        .unwrap_or((1024, 512));
+    let parser = MftParser::new(path, record_size, bytes_per_sector)?;
+    let ctx = ParseContext::standalone(opts, path, drive_prefix(meta.as_ref()), parser.record_size);
+    Records::new(parser, ctx, None)?.for_each(f);
+    Ok(())
+}
+
+/// Разбор дампа из произвольного `reader` (API библиотеки): записи читаются по мере обхода,
+/// в памяти - только дерево путей. Геометрия берется из записи 0 (1024/512, если она
+/// не распознана), пути - от корня тома. Правила, хешсет, $SDS, плагины, --image,
+/// --low-memory и --i30-entries берутся из `opts`, как у parse; опции вывода не применяются.
+pub fn parse_records<R: Read + Seek + 'static>(mut reader: R, opts: &ParseOptions) -> Result<Records> {
+    let read = |reader: &mut R| -> io::Result<(u64, Vec<u8>)> {
+        let file_size = reader.seek(SeekFrom::End(0))?;
+        reader.seek(SeekFrom::Start(0))?;
+        let mut first = Vec::new();
+        reader.by_ref().take(4096).read_to_end(&mut first)?;
+        reader.seek(SeekFrom::Start(0))?;
+        Ok((file_size, first))
+    };
+    let (file_size, first) = read(&mut reader).map_err(Error::io(msg::mft_read_failed()))?;
+    let (record_size, bytes_per_sector) = MftRecordHeader::parse(&first)
+        .and_then(|h| h.geometry())
+        .unwrap_or((1024, 512));
+    let mut parser = MftParser::with_reader(Box::new(reader), file_size, record_size, bytes_per_sector);
+    let mut row_tags = opts.tags.clone();
+    if opts.case.in_rows {
+        row_tags.extend(opts.case.row_tags());
+    }
+    let ctx = ParseContext::from_options(opts, STDIO_PATH, String::new(), parser.record_size, row_tags)?;
+    prepare_path_builder(&mut parser, opts)?;
+    let source_volume = opts.image.as_deref().map(|image| open_source_volume(image, opts.partition)).transpose()?;
+    Records::new(parser, ctx, source_volume).map_err(Error::io(msg::mft_read_failed()))
+}
+
+/// Дополнительный приемник отчета: вид, путь или URL (для сообщений и audit.log) и писатель.
+type Sink = (&'static str, String, Box<dyn EntrySink>);
 
-        if let Some(fn_attr) = best_fn {
-            let parent_entry = fn_attr.parent_directory_reference & 0xFFFFFFFFFFFF;
-            let parent_seq = (fn_attr.parent_directory_reference >> 48) as u16;
-            parser.path_builder.add_entry(entry_num, header.sequence_number, parent_entry, parent_seq, fn_attr.name);
+/// Создает дополнительные приемники отчета из опций.
+fn open_sinks(opts: &ParseOptions, row_tags: &Tags) -> Result<Vec<Sink>> {
+    let create = |path: &str| File::create(path).map(BufWriter::new)
+        .map_err(Error::io(common::create_failed(path)));
+    let mut sinks: Vec<Sink> = Vec::new();
+    if let Some(path) = &opts.bodyfile {
+        sinks.push(("Bodyfile", path.clone(), Box::new(BodyfileWriter::new(create(path)?))));
+    }
+    if let Some(path) = &opts.alerts {
+        sinks.push(("Alerts", path.clone(), Box::new(AlertsWriter::new(create(path)?))));
+    }
+    if let Some(path) = &opts.runs_out {
+        let writer = RunsWriter::new(create(path)?)
+            .map_err(Error::io(common::write_failed(path)))?;
+        sinks.push(("Runs", path.clone(), Box::new(writer)));
+    }
+    if let Some(path) = &opts.tln {
+        sinks.push(("TLN", path.clone(), Box::new(TlnWriter::new(create(path)?, &opts.tln_host, &opts.tln_user))));
+    }
+    if let Some(path) = &opts.out_parquet {
+        #[cfg(feature = "parquet")]
+        {
+            let tag_columns: Vec<String> = row_tags.keys().cloned().collect();
+            let writer = ParquetWriter::create(path, &tag_columns)
+                .map_err(Error::io(common::create_failed(path)))?;
+            sinks.push(("Parquet", path.clone(), Box::new(writer)));
+        }
+        #[cfg(not(feature = "parquet"))]
+        {
+            let _ = (path, row_tags);
+            return Err(Error::Unsupported(msg::parquet_unsupported()));
+        }
+    }
+    if let Some(url) = &opts.es_url {
+        #[cfg(feature = "siem")]
+        {
+            let target = format!("{}/{}", redact_url(url).trim_end_matches('/'), opts.es_index);
+            sinks.push(("Elasticsearch", target, Box::new(ElasticSink::new(url, &opts.es_index))));
+        }
+        #[cfg(not(feature = "siem"))]
+        {
+            let _ = (url, &opts.es_index);
+            return Err(Error::Unsupported(msg::es_unsupported()));
         }
     }
+    if let Some(url) = &opts.splunk_hec_url {
+        #[cfg(feature = "siem")]
+        {
+            let sink = SplunkSink::new(url, &opts.splunk_token);
+            sinks.push(("Splunk HEC", sink.event_url(), Box::new(sink)));
+        }
+        #[cfg(not(feature = "siem"))]
+        {
+            let _ = (url, &opts.splunk_token);
+            return Err(Error::Unsupported(msg::splunk_unsupported()));
+        }
+    }
+    Ok(sinks)
+}
 
-    println!("[*] Проход 2: парсинг атрибутов и экспорт в JSONL...");
-    parser.reader.seek(SeekFrom::Start(0)).unwrap();
-    let mut writer = JsonlWriter::new(BufWriter::new(File::create(out_jsonl).unwrap()));
+type ImageVolume = SourceVolume<Box<dyn ImageReader>>;
 
-    let rules_list: Vec<Rule> = vec![
-        Rule::glob(r"*\Windows\System32\AppLocker\*.txt").unwrap().and(Rule::ends_with("123.txt").not()),
-        Rule::glob(r"*\Windows\IME\*.ps1").unwrap(),
-        Rule::glob(r"*\$Recycle.Bin\*.exe").unwrap(),
-        Rule::starts_with("C:\\Users\\Public\\").and(Rule::ends_with(".exe")),
-        Rule::contains("\\system32\\").and(Rule::ends_with(".dll")),
-    ];
+/// Дамп MFT вместе с томом, с которого он снят (--image): записи читаются из дампа,
+/// нерезидентные атрибуты - с тома.
+struct DumpOnVolume<'a> {
+    dump: &'a mut MftParser,
+    volume: Option<&'a mut ImageVolume>,
+}
 
+impl RecordSource for DumpOnVolume<'_> {
+    fn record_size(&self) -> usize { self.dump.record_size }
+    fn bytes_per_sector(&self) -> u16 { self.dump.bytes_per_sector }
+    fn total_records(&self) -> u64 { self.dump.total_records() }
+    fn fetch_record(&mut self, entry_num: u64) -> Option<Vec<u8>> { self.dump.fetch_record(entry_num) }
+    fn fetch_extent(&mut self, entry_num: u64, sequence: u16) -> Option<Vec<u8>> { self.dump.fetch_extent(entry_num, sequence) }
+    fn read_nonresident(&mut self, record: &[u8], attr_offset: usize, attr_end: usize) -> Option<Vec<u8>> {
+        self.volume.as_mut()?.read_attribute(record, attr_offset, attr_end)
+    }
+}
+
+/// Том для --image: нерезидентные атрибуты записей дампа читаются по runlist с этого тома.
+fn open_source_volume(image: &str, partition: Option<PartitionSelect>) -> Result<ImageVolume> {
+    let volume_path = extract::volume_path(image);
+    let mut vol = image::open(&volume_path)
+        .map_err(Error::io(msg::image_open_failed(&volume_path)))?;
+    let layout = extract::locate_mft_at(&mut vol, partition)?;
+    Ok(SourceVolume::new(vol, layout))
+}
+
+/// Разбор файла `path`; в отчете, summary и audit.log источник записывается как `source`.
+fn run_source(path: &str, source: &str, out_jsonl: Option<&str>, opts: &ParseOptions) -> Result<(MftParser, ParseContext, RunSummary)> {
+    let meta_opt = load_mft_meta(path);
+    let (record_size, bytes_per_sector) = meta_opt.as_ref()
+        .map(|meta| (meta.mft_record_size as usize, meta.bytes_per_sector))
+        .unwrap_or((1024, 512));
+    debug!("{}", msg::geometry(record_size, bytes_per_sector,
+        if meta_opt.is_some() { "meta.json".to_string() } else { msg::geometry_default() }));
+    let parser = MftParser::new(path, record_size, bytes_per_sector)
+        .map_err(Error::io(common::open_failed(path)))?;
+    run_parser(parser, meta_opt, source, out_jsonl, opts)
+}
+
+/// Разбор $MFT, уже прочитанного в память (`play --stream`): метаданные тома
+/// приходят от extract, а не из meta.json рядом с дампом.
+pub fn run_in_memory(data: Vec<u8>, meta: MftMeta, out_jsonl: &str, opts: &ParseOptions) -> Result<RunSummary> {
+    let parser = MftParser::from_memory(data, meta.mft_record_size as usize, meta.bytes_per_sector);
+    let source = meta.source.clone();
+    run_parser(parser, Some(meta), &source, Some(out_jsonl), opts).map(|(_, _, summary)| summary)
+}
+
+fn run_parser(mut parser: MftParser, meta_opt: Option<MftMeta>, source: &str, out_jsonl: Option<&str>, opts: &ParseOptions) -> Result<(MftParser, ParseContext, RunSummary)> {
+    let to_stdout = out_jsonl == Some(STDIO_PATH);
+    if to_stdout {
+        logging::reserve_stdout();
+    }
+    info!("{}", msg::started());
+
+    let drive_prefix = drive_prefix(meta_opt.as_ref());
+
+    let since = opts.since.as_deref().map(|spec| SinceThreshold::load(spec, meta_opt.as_ref())).transpose()?;
+    match since {
+        Some(SinceThreshold::Summary { max_lsn, max_usn }) => info!("{}", msg::since_summary(max_lsn, max_usn)),
+        Some(SinceThreshold::Usn(usn)) => info!("{}", msg::since_usn(usn)),
+        None => {}
+    }
+
+    let case = match meta_opt.as_ref() {
+        Some(meta) => opts.case.clone().or(&meta.case),
+        None => opts.case.clone(),
+    };
+    let mut tags = meta_opt.as_ref().map(|m| m.tags.clone()).unwrap_or_default();
+    tags.extend(opts.tags.clone());
+    // В summary и audit.log дело пишется отдельным полем, в строки - метками
+    let mut row_tags = tags.clone();
+    if case.in_rows {
+        row_tags.extend(case.row_tags());
+    }
+
+    let mut ctx = ParseContext::from_options(opts, source, drive_prefix, parser.record_size, row_tags)?;
+
+    let total_records = parser.total_records();
+    prepare_path_builder(&mut parser, opts)?;
+
+    // Запись $Volume есть в самом дампе; meta.json - запасной источник
+    let volume = volume::volume_info(&mut parser).or_else(|| meta_opt.as_ref().and_then(|m| m.volume.clone()));
+    if let Some(info) = &volume {
+        info!("{}", common::volume(info.describe()));
+        if info.dirty {
+            info!("{}", msg::dirty_volume());
+        }
+    }
+
+    info!("{}", msg::pass1());
+    let mut source_volume = opts.image.as_deref().map(|image| open_source_volume(image, opts.partition)).transpose()?;
+    let utilization = index_pass(&mut parser, source_volume.as_mut(), &mut ctx);
+    info!("{}", msg::records(utilization.records_total, utilization.in_use, utilization.free, utilization.empty, utilization.baad, utilization.extension_records));
+
+    info!("{}", msg::pass2());
+    parser.reader.seek(SeekFrom::Start(0)).map_err(Error::io(msg::mft_read_failed()))?;
+    let out_jsonl = out_jsonl.map(|out_jsonl| compressed_report_path(out_jsonl, opts.compress));
+    let out_jsonl = out_jsonl.as_deref();
+    let mut writer = out_jsonl.map(|out_jsonl| -> Result<_> {
+        let out: Box<dyn Write> = if to_stdout {
+            Box::new(io::stdout().lock())
+        } else {
+            Box::new(File::create(out_jsonl).map_err(Error::io(common::create_failed(out_jsonl)))?)
+        };
+        let out = CompressedWriter::new(out, opts.compress).map_err(Error::io("--compress"))?;
+        Ok(JsonlWriter::new(BufWriter::new(out)))
+    }).transpose()?;
+    // Индекс строк для query/recover: смещения имеют смысл только в несжатом JSONL на диске;
+    // с --low-memory он не строится - пути всех строк держались бы в памяти
+    let mut index = (writer.is_some() && !to_stdout && !opts.low_memory && opts.compress.is_none() && opts.format == ReportFormat::Jsonl)
+        .then(ReportIndex::new);
+    let mut sinks = open_sinks(opts, &ctx.tags)?;
+    let mut i30 = opts.i30_image.as_deref().map(|image| I30Source::open(image, opts.i30_partition)).transpose()?;
+    let mut summary = RunSummary {
+        source: source.to_string(),
+        volume_serial_number: meta_opt.as_ref().map(|m| m.volume_serial_number),
+        records_total: total_records,
+        entries_written: 0,
+        max_lsn: 0,
+        max_usn: 0,
+        skipped_records: utilization.baad + utilization.fixup_failed,
+        failed_outputs: 0,
+        utilization: Some(utilization),
+        volume,
+        case: case.clone(),
+        tags: tags.clone(),
+    };
+
+    let mut record_buffer = vec![0u8; parser.record_size];
+    let mut progress = Progress::new("pass2", msg::pass2_stage(), total_records, ProgressUnit::Records, opts.progress);
     for entry_num in 0..total_records {
+        progress.set(entry_num);
         if parser.reader.read_exact(&mut record_buffer).is_err() { break; }
+        let Some(mut entry) = decode_record(&mut parser, source_volume.as_mut(), &ctx, entry_num, &mut record_buffer) else { continue };
+        if let Some(i30) = i30.as_mut().filter(|_| entry.is_directory) {
+            let slack = i30.slack_for(&entry);
+            if !slack.is_empty() {
+                entry.plugin_fields.insert("I30Slack".into(), serde_json::to_value(slack).unwrap_or_default());
+            }
+        }
+        let wants_content = opts.data && entry.in_use && !entry.is_directory && entry.content_data.is_none() && (1..=opts.max_data_size).contains(&entry.file_size);
+        if let Some(volume) = source_volume.as_mut().filter(|_| wants_content) {
+            entry.content_data = volume.read(entry.entry_number, entry.sequence_number, opts.max_data_size).map(|data| extract_human_readable(&data));
+        }
 
-        let header = match MftRecordHeader::parse(&record_buffer) {
-            Some(h) => h, None => continue,
-        };
+        summary.max_lsn = summary.max_lsn.max(entry.logfile_sequence_number);
+        summary.max_usn = summary.max_usn.max(entry.journal_usn);
 
-        if header.signature == "BAAD" || header.base_record_reference != 0 { continue; } 
+        if opts.skip_known && entry.known_good { continue; }
+        if since.is_some_and(|s| s.is_unchanged(&entry)) { continue; }
 
-        let fixup_res = apply_fixups(&mut record_buffer, &header, parser.bytes_per_sector);
-        if fixup_res == FixupResult::Failed { continue; }
-        
-        let is_torn_write = fixup_res == FixupResult::TornWrite;
-        let (buffers, complex_extents) = gather_record_buffers(&mut parser, entry_num, record_buffer.clone());
-
-        let mut file_name = String::new();
-        let mut si_attr: Option<StandardInformation> = None;
-        let mut fn_attr_data: Option<FileNameAttribute> = None;
-        let mut content_data: Option<String> = None;
-        let mut zone_id_contents: Option<String> = None;
-        let mut has_ads = false;
-        let mut data_unnamed_size: Option<u64> = None;
-        let mut fn_logical_size: Option<u64> = None;
-
-        for buf in &buffers {
-            let buf_header = MftRecordHeader::parse(buf).unwrap();
-            let mut attr_offset = buf_header.first_attribute_offset as usize;
-            
-            let mut used_end = std::cmp::min(buf_header.real_size as usize, parser.record_size);
-            if used_end < attr_offset { used_end = parser.record_size; }
-
-            while attr_offset + 8 <= used_end {
-                let attr_type = LittleEndian::read_u32(&buf[attr_offset..attr_offset + 4]);
-                if attr_type == 0xFFFFFFFF || attr_type == 0 { break; }
-
-                let attr_len = LittleEndian::read_u32(&buf[attr_offset + 4..attr_offset + 8]) as usize;
-                if attr_len == 0 || attr_offset.saturating_add(attr_len) > used_end { break; }
-
-                let attr_end = attr_offset.saturating_add(attr_len);
-                let non_resident = buf[attr_offset + 8] != 0;
-                let attr_name = read_attr_name(&buf, attr_offset, attr_end);
-                
-                if attr_type == 0x80 && !attr_name.is_empty() { has_ads = true; }
-
-                if !non_resident && attr_offset + 22 <= attr_end {
-                    let value_len = LittleEndian::read_u32(&buf[attr_offset + 16..attr_offset + 20]) as usize;
-                    let value_off = LittleEndian::read_u16(&buf[attr_offset + 20..attr_offset + 22]) as usize;
-                    let content_end = std::cmp::min(attr_offset.saturating_add(value_off).saturating_add(value_len), attr_end);
-
-                    match attr_type {
-                        0x10 => {
-                            if let Some(slice) = buf.get(attr_offset.saturating_add(value_off)..content_end) {
-                                si_attr = StandardInformation::parse(slice);
-                            }
-                        }
-                        0x30 => {
-                            if let Some(slice) = buf.get(attr_offset.saturating_add(value_off)..content_end) {
-                                if let Some(fn_a) = FileNameAttribute::parse(slice) {
-                                    let current_prio = match fn_attr_data.as_ref() {
-                                        Some(f) if f.name_type == 1 || f.name_type == 3 => 2,
-                                        Some(_) => 1, None => 0,
-                                    };
-                                    if (fn_a.name_type == 1 || fn_a.name_type == 3) || current_prio == 0 {
-                                        fn_logical_size = Some(fn_a.logical_size);
-                                        file_name = fn_a.name.clone();
-                                        fn_attr_data = Some(fn_a);
-                                    }
-                                }
-                            }
-                        }
-                        0x80 => {
-                            if attr_name.is_empty() { data_unnamed_size = Some(value_len as u64); }
-                            if let Some(raw_data) = buf.get(attr_offset.saturating_add(value_off)..content_end) {
-                                if attr_name == "Zone.Identifier" {
-                                    zone_id_contents = Some(extract_human_readable(raw_data));
-                                } else if attr_name.is_empty() && data_flag {
-                                    content_data = Some(extract_human_readable(raw_data));
-                                }
-                            }
-                        }
-                        _ => {}
-                    }
-                } else if non_resident && attr_type == 0x80 {
-                    if let Some(sz) = read_nonresident_data_size(&buf, attr_offset, attr_end) {
-                        if attr_name.is_empty() { data_unnamed_size = Some(sz); }
+        let mut reported = true;
+        if let Some(writer) = writer.as_mut() {
+            let offset = writer.position();
+            let written = match opts.format {
+                ReportFormat::Jsonl => writer.write(&entry),
+                ReportFormat::Timesketch => timesketch_events(&entry)
+                    .and_then(|events| events.iter().try_for_each(|event| writer.write(event))),
+            };
+            match written {
+                Ok(()) if opts.format == ReportFormat::Jsonl => {
+                    if let Some(index) = index.as_mut() {
+                        index.push(offset, &entry);
                     }
                 }
-                attr_offset = attr_end;
+                Ok(()) => {}
+                // Строку не удалось сериализовать - она пропускается, отчет продолжается
+                Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+                    warn!("{}", msg::entry_write_failed(entry.entry_number, e));
+                    summary.failed_outputs += 1;
+                    reported = false;
+                }
+                // Отчет больше не пишется (диск заполнен, поток закрыт) - продолжать разбор незачем
+                Err(e) => return Err(Error::io(common::write_failed(out_jsonl.unwrap_or_default()))(e)),
             }
         }
+        // Сбойный приемник (диск заполнен, SIEM недоступен после повторов) отключается, разбор продолжается
+        sinks.retain_mut(|(kind, target, sink)| match sink.write(&entry) {
+            Ok(()) => true,
+            Err(e) => {
+                warn!("{}", msg::sink_stopped(kind, target, e));
+                summary.failed_outputs += 1;
+                false
+            }
+        });
+        if reported {
+            summary.entries_written += 1;
+        }
+    }
+    progress.finish();
+    if let Some(writer) = writer {
+        let report_size = writer.position();
+        let finished = writer.into_inner().into_inner().map_err(|e| e.into_error()).and_then(CompressedWriter::finish);
+        if let Err(e) = finished {
+            warn!("{}", msg::report_write_failed(out_jsonl.unwrap_or_default(), e));
+            summary.failed_outputs += 1;
+            index = None;
+        }
+        if let Some(out_jsonl) = out_jsonl.filter(|_| !to_stdout) {
+            match index.as_mut() {
+                Some(index) => match index.save(out_jsonl, report_size) {
+                    Ok(()) => info!("{}", msg::index_written(index_path_for_report(out_jsonl), index.rows())),
+                    Err(e) => warn!("{}", msg::index_failed(index_path_for_report(out_jsonl), e)),
+                },
+                // Индекс прошлого отчета с тем же именем к новому не относится
+                None => { let _ = std::fs::remove_file(index_path_for_report(out_jsonl)); }
+            }
+        }
+    }
+    for (kind, target, sink) in &mut sinks {
+        match sink.finish() {
+            Ok(()) => info!("[+] {}: {}", kind, target),
+            Err(e) => {
+                warn!("[!] {} {}: {}", kind, target, e);
+                summary.failed_outputs += 1;
+            }
+        }
+    }
 
-        let parent_entry = fn_attr_data.as_ref().map(|f| f.parent_directory_reference & 0xFFFFFFFFFFFF).unwrap_or(0);
-        let parent_seq = fn_attr_data.as_ref().map(|f| (f.parent_directory_reference >> 48) as u16).unwrap_or(0);
-        
-        let parent_path = parser.path_builder.get_parent_path(parent_entry, parent_seq);
-        
-        let full_path = if parent_path == "\\" || parent_path.is_empty() {
-            format!("{}\\{}", drive_prefix, file_name)
-        } else {
-            let sep = if parent_path.starts_with('\\') { "" } else { "\\" };
-            format!("{}{}{}\\{}", drive_prefix, sep, parent_path, file_name)
-        };
-        
-        let mut timestomped = false;
-        let mut usec_zeros = false;
-        let mut copied = false;
-        let mut c_0x10 = None; let mut m_0x10 = None; let mut a_0x10 = None; let mut r_0x10 = None;
-        let mut c_0x30 = None; let mut m_0x30 = None; let mut a_0x30 = None; let mut r_0x30 = None;
-
-        if let (Some(si), Some(fn_a)) = (&si_attr, &fn_attr_data) {
-            let ts = TimestampData {
-                si_c: si.creation_time, si_m: si.modified_time, si_e: si.mft_modified_time, si_a: si.accessed_time,
-                fn_c: fn_a.creation_time, fn_m: fn_a.modified_time, fn_e: fn_a.mft_modified_time, fn_a: fn_a.accessed_time,
-            };
-            timestomped = ts.is_timestomped() || ts.is_before_volume_birth(volume_birth);
-            usec_zeros = ts.has_usec_zeros(); copied = ts.is_copied();
-            c_0x10 = Some(si.creation_time.to_rfc3339()); m_0x10 = Some(si.modified_time.to_rfc3339());
-            a_0x10 = Some(si.accessed_time.to_rfc3339()); r_0x10 = Some(si.mft_modified_time.to_rfc3339());
-            c_0x30 = Some(fn_a.creation_time.to_rfc3339()); m_0x30 = Some(fn_a.modified_time.to_rfc3339());
-            a_0x30 = Some(fn_a.accessed_time.to_rfc3339()); r_0x30 = Some(fn_a.mft_modified_time.to_rfc3339());
-        }
-
-        let usn = MftParser::get_update_sequence_number(&record_buffer, &header).unwrap_or(0) as u64;
-        let fits_rules = if !full_path.is_empty() {
-            let fp_lc = full_path.to_ascii_lowercase();
-            rules_list.iter().any(|r| r.check_lowered(&fp_lc))
-        } else { false };
-
-        let file_size = data_unnamed_size.or(fn_logical_size).unwrap_or(0);
-        let is_dir = header.is_directory();
-        let extension = if is_dir || !file_name.contains('.') { None } else { file_name.rsplit('.').next().map(|ext| ext.to_string()) };
-
-        let entry = MftEntry {
-            entry_number: entry_num, signature: header.signature.clone(), base_record_reference: header.base_record_reference,
-            real_size: header.real_size, allocated_size: header.allocated_size, sequence_number: header.sequence_number,
-            parent_entry_number: parent_entry, parent_sequence_number: parent_seq,
-            in_use: header.is_in_use(), is_directory: is_dir, parent_path, file_name, extension, full_path,
-            has_ads, is_ads: has_ads, file_size,
-            created0x10: c_0x10, created0x30: c_0x30, last_modified0x10: m_0x10, last_modified0x30: m_0x30,
-            last_record_change0x10: r_0x10, last_record_change0x30: r_0x30, last_access0x10: a_0x10, last_access0x30: a_0x30,
-            update_sequence_number: usn, logfile_sequence_number: header.logfile_sequence_number,
-            security_id: si_attr.as_ref().map(|s| s.security_id).unwrap_or(0), si_flags: si_attr.as_ref().map(|s| s.file_attributes).unwrap_or(0),
-            reference_count: header.hard_link_count, name_type: fn_attr_data.as_ref().map(|f| f.name_type).unwrap_or(0),
-            timestomped, fits_rules, zone_id_contents, content_data, u_sec_zeros: usec_zeros, copied,
-            torn_write: is_torn_write, complex_extents, fn_attribute_id: 0, other_attribute_id: 0, source_file: path.to_string(),
-        };
+    // При выводе в stdout итоги некуда положить рядом с отчетом
+    if let Some(out_jsonl) = out_jsonl.filter(|_| !to_stdout) {
+        let summary_path = summary_path_for_report(out_jsonl);
+        output::write_json_file(&summary_path, &summary).map_err(Error::io(common::write_failed(&summary_path)))?;
+    }
+    info!("{}", msg::written(summary.entries_written, total_records, summary.max_lsn, summary.max_usn));
+    if summary.skipped_records > 0 {
+        warn!("{}", msg::skipped(summary.skipped_records));
+    }
+    // Без JSONL в журнал пишется первый приемник (он и определяет, куда ушел отчет)
+    let output = out_jsonl.map(str::to_string)
+        .or_else(|| sinks.first().map(|(_, target, _)| target.clone()))
+        .unwrap_or_default();
+    audit::append("parse", source, &output, &case, &tags);
 
-        let _ = writer.write(&entry);
+    Ok((parser, ctx, summary))
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::bench::synthetic_mft;
+    use crate::image::Fixture;
+
+    fn names(entry: &MftEntry) -> Vec<&str> {
+        entry.rule_matches.iter().flatten().map(|m| m.name.as_str()).collect()
+    }
+
+    #[test]
+    fn parse_records_loads_rules_from_options() {
+        let fixture = Fixture::new();
+        let rules = fixture.write("rules.toml", b"[[rules]]\nname = \"drop\"\nends_with = \".exe\"\n\n[[rules]]\nname = \"notes\"\nends_with = \".txt\"\n");
+        let exclude = fixture.write("exclude.toml", b"[[rules]]\nname = \"quiet\"\ncontains = \"\\\\tools\\\\\"\n");
+        let mft = synthetic_mft(&[
+            (5, "tools", true, true),
+            (5, "drop.exe", false, true),
+            (16, "known.exe", false, true),
+            (5, "notes.txt", false, true),
+        ]);
+        let opts = ParseOptions { rules: Some(rules), exclude_rules: Some(exclude), ..Default::default() };
+        let entries: Vec<MftEntry> = parse_records(io::Cursor::new(mft), &opts).unwrap().collect();
+
+        let by_name = |name: &str| entries.iter().find(|e| e.file_name == name).unwrap();
+        assert_eq!(names(by_name("drop.exe")), ["drop"]);
+        assert_eq!(by_name("known.exe").full_path, "\\tools\\known.exe");
+        assert!(names(by_name("known.exe")).is_empty());
+        assert_eq!(names(by_name("notes.txt")), ["notes"]);
     }
-    let _ = writer.flush();
-}
\ No newline at end of file
+
+    #[test]
+    fn parse_records_reports_bad_rule_files() {
+        let fixture = Fixture::new();
+        let rules = fixture.write("rules.toml", b"[[rules]]\nname = \"a\"\nsize = \"huge\"\n");
+        let opts = ParseOptions { rules: Some(rules), ..Default::default() };
+        let mft = synthetic_mft(&[(5, "a.exe", false, true)]);
+        assert!(matches!(parse_records(io::Cursor::new(mft.clone()), &opts), Err(Error::Invalid(_))));
+
+        // Недоступная база - ошибка ввода-вывода, а не молча пустой хешсет
+        let missing = format!("{}.missing", fixture.write("hashes", b""));
+        let opts = ParseOptions { hashset: Some(missing), ..Default::default() };
+        assert!(matches!(parse_records(io::Cursor::new(mft), &opts), Err(Error::Io { .. })));
+    }
+}
@@ -1,29 +1,131 @@
 use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use tracing::{info, warn};
 
 use super::extract;
-use super::parse;
+use super::merge::{self, ReportInput};
+use super::parse::{self, ParseOptions};
+use crate::error::{Error, Outcome, Result};
+use crate::i18n::{common, play as msg};
+use crate::models::{CaseInfo, Tags};
+
+/// Формат имени папки снимка в режиме `--interval` (UTC, сортируется по времени).
+const SNAPSHOT_FORMAT: &str = "%Y%m%d_%H%M%S";
+
+/// Параметры пайплайна play.
+#[derive(Debug, Clone, Default)]
+pub struct PlayOptions {
+    /// Включать содержимое резидентного $DATA
+    pub data: bool,
+    /// Передавать $MFT из extract в parse через память, без чтения дампа с диска
+    pub stream: bool,
+    /// В потоковом режиме все равно сохранить дамп MFT (и meta.json)
+    pub keep_mft: bool,
+}
+
+/// Имя подпапки тома: `C:` -> `C`, образ -> имя файла без расширения.
+fn volume_label(image: &str) -> String {
+    if image.len() <= 3 && image.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return image[..1].to_ascii_uppercase();
+    }
+    Path::new(image).file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| image.to_string())
+}
 
-pub fn run(image: &str, out_dir: &str, data_flag: bool) {
-    println!("[*] Запуск полного пайплайна (Play)");
+/// Один том - сразу в `out_dir`; несколько - в подпапки томов и общий отчет MERGED.
+/// Частичный успех на любом томе - частичный успех всего прогона.
+fn run_all(images: &[String], out_dir: &Path, opts: &PlayOptions, case: &CaseInfo, tags: &Tags) -> Result<Outcome> {
+    if let [image] = images {
+        return run_once(image, out_dir, opts, case, tags);
+    }
 
-    if !Path::new(out_dir).exists() {
-        std::fs::create_dir_all(out_dir).unwrap();
+    let host = merge::default_host();
+    let mut reports = Vec::new();
+    let mut outcome = Outcome::Complete;
+    for image in images {
+        let volume_dir = out_dir.join(volume_label(image));
+        outcome = outcome.max(run_once(image, &volume_dir, opts, case, tags)?);
+        reports.push(ReportInput { host: host.clone(), path: volume_dir.join("REPORT").to_string_lossy().into_owned() });
+    }
+
+    info!("{}", msg::merging());
+    merge::merge_reports(&reports, out_dir.join("MERGED").to_string_lossy().as_ref(), 1, 50)?;
+    Ok(outcome)
+}
+
+fn run_once(image: &str, out_dir: &Path, opts: &PlayOptions, case: &CaseInfo, tags: &Tags) -> Result<Outcome> {
+    if !out_dir.exists() {
+        std::fs::create_dir_all(out_dir)
+            .map_err(Error::io(common::create_failed(out_dir.display())))?;
     }
 
-    let out_dir = PathBuf::from(out_dir);
     let mft_path = out_dir.join("MFT");
     let jsonl_path = out_dir.join("REPORT");
 
-    extract::run(image, mft_path.to_string_lossy().as_ref());
+    let extract_opts = extract::ExtractOptions { threads: extract::DEFAULT_READ_THREADS, progress: true, audit: true, ..Default::default() };
+    let parse_opts = ParseOptions { data: opts.data, case: case.clone(), progress: true, ..Default::default() };
+
+    if opts.stream {
+        // Дамп не перечитывается с диска: оба прохода parse идут по копии в памяти
+        let tee = opts.keep_mft.then(|| mft_path.to_string_lossy().into_owned());
+        let (data, meta) = extract::stream(image, tee.as_deref(), &extract_opts, case, tags)?;
+        return parse::run_in_memory(data, meta, jsonl_path.to_string_lossy().as_ref(), &parse_opts).map(|s| s.outcome());
+    }
+
+    // Метки попадают в meta.json, откуда parse переносит их в каждую строку отчета
+    extract::run(image, mft_path.to_string_lossy().as_ref(), &extract_opts, case, tags)?;
 
     parse::run(
         mft_path.to_string_lossy().as_ref(),
-        jsonl_path.to_string_lossy().as_ref(),
-        data_flag,
-    );
-
-    println!(
-        "\n[+] Пайплайн успешно завершен! Результаты в папке: {}",
-        out_dir.display()
-    );
+        Some(jsonl_path.to_string_lossy().as_ref()),
+        &parse_opts,
+    ).map(|s| s.outcome())
+}
+
+/// Удаляет самые старые папки снимков, оставляя `keep` последних.
+fn rotate_snapshots(out_dir: &Path, keep: usize) {
+    let Ok(read_dir) = std::fs::read_dir(out_dir) else { return };
+    let mut snapshots: Vec<PathBuf> = read_dir
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().is_dir())
+        .filter(|e| {
+            let name = e.file_name();
+            chrono::NaiveDateTime::parse_from_str(&name.to_string_lossy(), SNAPSHOT_FORMAT).is_ok()
+        })
+        .map(|e| e.path())
+        .collect();
+    snapshots.sort();
+
+    let excess = snapshots.len().saturating_sub(keep);
+    for old in &snapshots[..excess] {
+        match std::fs::remove_dir_all(old) {
+            Ok(_) => info!("{}", msg::rotated(old.display())),
+            Err(e) => warn!("{}", msg::rotate_failed(old.display(), e)),
+        }
+    }
+}
+
+/// По расписанию (`interval`) работает до первой ошибки.
+pub fn run(images: &[String], out_dir: &str, opts: &PlayOptions, interval: Option<Duration>, keep: usize, case: &CaseInfo, tags: &Tags) -> Result<Outcome> {
+    info!("{}", msg::started());
+
+    let Some(interval) = interval else {
+        let outcome = run_all(images, Path::new(out_dir), opts, case, tags)?;
+        info!("{}", msg::finished(out_dir));
+        return Ok(outcome);
+    };
+
+    info!("{}", msg::schedule(interval.as_secs(), if keep == 0 { msg::keep_all() } else { keep.to_string() }));
+    loop {
+        let snapshot_dir = Path::new(out_dir).join(chrono::Utc::now().format(SNAPSHOT_FORMAT).to_string());
+        run_all(images, &snapshot_dir, opts, case, tags)?;
+        info!("{}", msg::snapshot_ready(snapshot_dir.display()));
+
+        if keep > 0 {
+            rotate_snapshots(Path::new(out_dir), keep);
+        }
+        std::thread::sleep(interval);
+    }
 }
\ No newline at end of file
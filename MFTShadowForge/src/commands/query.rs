@@ -0,0 +1,163 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Seek, SeekFrom, Write};
+
+use serde_json::Value;
+use tracing::{debug, info, warn};
+
+use super::parse::STDIO_PATH;
+use crate::error::{Error, Result};
+use crate::logging;
+use crate::output::{open_report, timesketch_row_events, CompressedWriter, Compression, ReportFormat};
+use crate::report_index::ReportIndex;
+use crate::rules::query::{IndexKey, Query};
+
+/// Условия отбора строк: выражение `--where` и короткие флаги, которые к нему дописываются через and.
+pub struct QueryOptions {
+    pub filter: Option<String>,
+    pub path: Option<String>,
+    pub after: Option<String>,
+    pub before: Option<String>,
+    pub time_field: String,
+    pub format: ReportFormat,
+    pub compress: Option<Compression>,
+    pub count: bool,
+    /// Читать отчет целиком, даже если рядом есть индекс
+    pub no_index: bool,
+}
+
+/// Строковый литерал языка запросов: кавычка внутри удваивается.
+fn quoted(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
+
+/// Итоговое условие: `--where and Full_Path glob --path and <поле> >= --after and <поле> < --before`.
+fn build_query(opts: &QueryOptions) -> Result<Option<Query>> {
+    let parse = |flag: &str, expr: &str| Query::parse(expr).map_err(|e| Error::Invalid(format!("{}: {}", flag, e)));
+    let mut parts = Vec::new();
+    if let Some(filter) = &opts.filter {
+        parts.push(parse("--where", filter)?);
+    }
+    if let Some(path) = &opts.path {
+        parts.push(parse("--path", &format!("Full_Path glob {}", quoted(path)))?);
+    }
+    if let Some(after) = &opts.after {
+        parts.push(parse("--after", &format!("{} >= {}", opts.time_field, quoted(after)))?);
+    }
+    if let Some(before) = &opts.before {
+        parts.push(parse("--before", &format!("{} < {}", opts.time_field, quoted(before)))?);
+    }
+    Ok(parts.into_iter().reduce(Query::and))
+}
+
+/// Строки отчета, которые могут совпасть с `query`, по индексу: пересечение выборок
+/// по номеру записи, расширению и пути. None - в условии нет ничего, что есть в индексе.
+fn indexed_rows(index: &ReportIndex, query: &Query) -> Option<Vec<usize>> {
+    query.index_keys().into_iter().map(|key| match key {
+        IndexKey::Entry(entry) => index.row_by_entry(entry).into_iter().collect(),
+        IndexKey::Extension(ext) => index.rows_by_extension(ext),
+        IndexKey::PathEq(path) => {
+            let path = path.to_lowercase();
+            index.rows_by_path(|p| p == path)
+        }
+        IndexKey::PathGlob(glob) => index.rows_by_path(|p| glob.regex.is_match(p)),
+    }).reduce(|acc: Vec<usize>, rows| acc.into_iter().filter(|row| rows.binary_search(row).is_ok()).collect())
+}
+
+/// Строки отчета `input` по смещениям из индекса, без конца строки.
+fn read_rows(input: &str, index: ReportIndex, rows: Vec<usize>) -> io::Result<impl Iterator<Item = io::Result<String>>> {
+    let mut reader = BufReader::new(File::open(input)?);
+    Ok(rows.into_iter().map(move |row| {
+        reader.seek(SeekFrom::Start(index.offset(row)))?;
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        line.truncate(line.trim_end_matches(['\r', '\n']).len());
+        Ok(line)
+    }))
+}
+
+/// Потоковый отбор строк готового отчета (JSONL parse/carve/i30/usnjrnl, в т.ч. .gz/.zst)
+/// без jq и duckdb. Подходящие строки пишутся как есть (JSONL) или событиями Timesketch;
+/// `--count` выводит только число совпадений.
+pub fn run(input: &str, out: &str, opts: &QueryOptions) -> Result<()> {
+    let to_stdout = out == STDIO_PATH;
+    if to_stdout {
+        logging::reserve_stdout();
+    }
+    let query = build_query(opts)?;
+    if let Some(query) = &query {
+        debug!("[*] Условие: {}", query);
+    }
+    let index = query.as_ref()
+        .filter(|_| !opts.no_index && input != STDIO_PATH)
+        .and_then(|query| ReportIndex::load(input).map(|index| (indexed_rows(&index, query), index)));
+    let lines: Box<dyn Iterator<Item = io::Result<String>>> = match index {
+        Some((Some(rows), index)) => {
+            info!("[*] Индекс отчета: строк к чтению {} из {}", rows.len(), index.rows());
+            Box::new(read_rows(input, index, rows).map_err(Error::io(format!("Ошибка открытия {}", input)))?)
+        }
+        _ => Box::new(open_report(input).map_err(Error::io(format!("Ошибка открытия {}", input)))?.lines()),
+    };
+
+    let out_path = match opts.compress {
+        Some(c) if !to_stdout && !out.ends_with(c.extension()) => format!("{}{}", out, c.extension()),
+        _ => out.to_string(),
+    };
+    let mut writer = (!opts.count).then(|| -> Result<_> {
+        let sink: Box<dyn Write> = if to_stdout {
+            Box::new(io::stdout().lock())
+        } else {
+            Box::new(File::create(&out_path).map_err(Error::io(format!("Не удалось создать {}", out_path)))?)
+        };
+        let sink = CompressedWriter::new(sink, opts.compress).map_err(Error::io("--compress"))?;
+        Ok(BufWriter::new(sink))
+    }).transpose()?;
+
+    let (mut rows, mut matched, mut malformed) = (0u64, 0u64, 0u64);
+    for (line_no, line) in lines.enumerate() {
+        let line = line.map_err(Error::io(format!("Ошибка чтения {} (строка {})", input, line_no + 1)))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        rows += 1;
+        let Ok(row) = serde_json::from_str::<Value>(&line) else {
+            malformed += 1;
+            continue;
+        };
+        if !query.as_ref().is_none_or(|q| q.matches(&row)) {
+            continue;
+        }
+        matched += 1;
+        let Some(writer) = writer.as_mut() else { continue };
+        let written = match (opts.format, &row) {
+            (ReportFormat::Timesketch, Value::Object(fields)) => timesketch_row_events(fields).iter().try_for_each(|event| {
+                serde_json::to_writer(&mut *writer, event).map_err(io::Error::from)?;
+                writer.write_all(b"\n")
+            }),
+            _ => writer.write_all(line.as_bytes()).and_then(|_| writer.write_all(b"\n")),
+        };
+        // Закрытый pipe (| head) - не ошибка, просто конец вывода
+        if let Err(e) = written {
+            if e.kind() == io::ErrorKind::BrokenPipe { break; }
+            return Err(Error::io(format!("Ошибка записи {}", out_path))(e));
+        }
+    }
+    if let Some(writer) = writer {
+        let finished = writer.into_inner().map_err(|e| e.into_error()).and_then(CompressedWriter::finish);
+        match finished {
+            Err(e) if e.kind() != io::ErrorKind::BrokenPipe => return Err(Error::io(format!("Ошибка записи {}", out_path))(e)),
+            _ => {}
+        }
+    }
+
+    if malformed > 0 {
+        warn!("[!] Строк, не являющихся JSON: {} - пропущены", malformed);
+    }
+    if opts.count {
+        println!("{}", matched);
+    } else if to_stdout {
+        info!("[+] Совпало строк: {} из {}", matched, rows);
+    } else {
+        info!("[+] Совпало строк: {} из {} -> {}", matched, rows, out_path);
+    }
+    Ok(())
+}
\ No newline at end of file
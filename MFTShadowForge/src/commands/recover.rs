@@ -0,0 +1,103 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+
+use super::extract::{self, PartitionSelect};
+use super::volume::{self, StreamReader, VolumeRecords, ATTR_COMPRESSED, ATTR_ENCRYPTED, ENTRY_MASK};
+use crate::audit;
+use crate::error::{Error, Result};
+use crate::hashing::to_hex;
+use crate::image;
+use crate::mft::record::MftRecordHeader;
+use crate::models::{CaseInfo, Tags};
+use crate::report_index::{index_path_for_report, ReportIndex};
+
+/// Копирует поток в файл, попутно считая SHA-256; возвращает число байт.
+fn copy_hashed<R: Read, W: Write>(mut reader: R, writer: &mut W, hasher: &mut Sha256) -> io::Result<u64> {
+    let mut buf = vec![0u8; 1024 * 1024];
+    let mut total = 0u64;
+    loop {
+        let n = match reader.read(&mut buf) {
+            Ok(0) => return Ok(total),
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        hasher.update(&buf[..n]);
+        writer.write_all(&buf[..n])?;
+        total += n as u64;
+    }
+}
+
+/// Извлечение содержимого файла с тома или образа по номеру записи или пути:
+/// $DATA (или именованный поток) читается по runlist со всех экстентов $ATTRIBUTE_LIST,
+/// разреженные участки и хвост за valid data length записываются нулями.
+/// С `report` путь ищется по индексу отчета parse этого тома, а не обходом каталогов.
+pub fn run(image: &str, entry: Option<u64>, path: Option<&str>, report: Option<&str>, stream: Option<&str>, out: &str, partition: Option<PartitionSelect>) -> Result<()> {
+    info!("[*] Запуск Recover");
+    let volume_path = extract::volume_path(image);
+    let mut vol = image::open(&volume_path).map_err(Error::io(format!("Ошибка открытия {}", volume_path)))?;
+    let layout = extract::locate_mft_at(&mut vol, partition)?;
+
+    let mut src = VolumeRecords { vol: &mut vol, layout: &layout };
+    let mut expected_sequence = None;
+    let entry = match (entry, path, report) {
+        (Some(entry), _, _) => entry,
+        (None, Some(path), Some(report)) => {
+            let index = ReportIndex::load(report)
+                .ok_or_else(|| Error::Invalid(format!("{}: нет индекса {} или он построен по другой версии отчета", report, index_path_for_report(report))))?;
+            let (entry, sequence) = index.entry_by_path(path).ok_or_else(|| Error::Invalid(format!("{}: нет в индексе {}", path, report)))?;
+            info!("[*] {} -> запись {} (по индексу {})", path, entry, report);
+            expected_sequence = Some(sequence);
+            entry
+        }
+        (None, Some(path), None) => {
+            let reference = volume::resolve_path(&mut src, path).map_err(|e| Error::Invalid(format!("{}: {}", path, e)))?;
+            info!("[*] {} -> запись {}", path, reference & ENTRY_MASK);
+            reference & ENTRY_MASK
+        }
+        (None, None, _) => return Err(Error::Invalid("нужен --entry или --path".into())),
+    };
+
+    let buffers = volume::read_record(&mut src, entry).map_err(Error::Corrupt)?;
+    let header = MftRecordHeader::parse(&buffers[0]).ok_or_else(|| Error::Corrupt(format!("запись {} повреждена", entry)))?;
+    if let Some(sequence) = expected_sequence.filter(|&s| s != header.sequence_number) {
+        return Err(Error::Invalid(format!("запись {}: sequence {} вместо {} из отчета - запись переиспользована после parse", entry, header.sequence_number, sequence)));
+    }
+    if header.base_record_reference & ENTRY_MASK != 0 {
+        return Err(Error::Invalid(format!("запись {} - расширение записи {}, укажите базовую", entry, header.base_record_reference & ENTRY_MASK)));
+    }
+    if header.is_directory() {
+        return Err(Error::Invalid(format!("запись {} - каталог", entry)));
+    }
+    if !header.is_in_use() {
+        warn!("[!] Запись {} удалена: кластеры могли быть заняты другими файлами, содержимое не гарантировано", entry);
+    }
+
+    let data = volume::data_stream(&buffers, layout.record_size, stream.unwrap_or("")).map_err(|e| Error::Invalid(format!("запись {}: {}", entry, e)))?;
+    if data.flags & ATTR_COMPRESSED != 0 {
+        return Err(Error::Invalid(format!("запись {}: поток сжат NTFS (LZNT1), распаковка не поддерживается", entry)));
+    }
+    if data.flags & ATTR_ENCRYPTED != 0 {
+        warn!("[!] Поток зашифрован EFS - записывается шифротекст");
+    }
+
+    let file = File::create(out).map_err(Error::io(format!("Не удалось создать {}", out)))?;
+    let mut writer = BufWriter::new(file);
+    let mut hasher = Sha256::new();
+    let copied = match &data.resident {
+        Some(value) => copy_hashed(value.as_slice(), &mut writer, &mut hasher),
+        None => copy_hashed(StreamReader::new(&mut vol, &layout, &data, false), &mut writer, &mut hasher),
+    };
+    let written = copied.and_then(|n| writer.flush().map(|_| n)).map_err(Error::io(format!("Ошибка копирования в {}", out)))?;
+    if written != data.size {
+        warn!("[!] Записано {} байт из {}", written, data.size);
+    }
+
+    info!("[+] Запись {}{}: {} байт -> {}", entry, stream.map(|s| format!(":{}", s)).unwrap_or_default(), written, out);
+    info!("    SHA-256: {}", to_hex(&hasher.finalize()));
+    audit::append("recover", &volume_path, out, &CaseInfo::default(), &Tags::new());
+    Ok(())
+}
\ No newline at end of file
@@ -0,0 +1,86 @@
+use std::fs::File;
+use std::io::{self, Write};
+
+use serde_json::Value;
+use tracing::info;
+
+use crate::error::{Error, Result};
+use crate::models::{MftEntry, SCHEMA_VERSION};
+
+/// JSON Schema строки отчета parse текущей версии формата.
+/// Поле SchemaVersion зафиксировано константой: валидатор сразу отвергает строки другой версии.
+pub fn mft_entry_schema() -> Value {
+    let mut schema = schemars::schema_for!(MftEntry).to_value();
+    schema["$id"] = Value::from(format!("urn:mftshadowforge:mft-entry:v{}", SCHEMA_VERSION));
+    schema["properties"]["SchemaVersion"]["const"] = Value::from(SCHEMA_VERSION);
+    schema
+}
+
+pub fn run(out_json: Option<&str>) -> Result<()> {
+    let schema = mft_entry_schema();
+    let result = match out_json {
+        Some(path) => File::create(path).and_then(|mut f| {
+            serde_json::to_writer_pretty(&mut f, &schema)?;
+            f.write_all(b"\n")
+        }),
+        None => {
+            let mut stdout = io::stdout().lock();
+            serde_json::to_writer_pretty(&mut stdout, &schema).map_err(io::Error::from)
+                .and_then(|_| stdout.write_all(b"\n"))
+        }
+    };
+    result.map_err(Error::io("Не удалось записать схему"))?;
+    if let Some(path) = out_json {
+        info!("[+] Схема формата v{}: {}", SCHEMA_VERSION, path);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::bench::synthetic_mft;
+    use crate::commands::parse::{parse_records, ParseOptions};
+
+    /// Подходит ли значение под `type` свойства схемы (строка или список типов).
+    fn type_matches(schema_type: &Value, value: &Value) -> bool {
+        let kind = match value {
+            Value::Null => "null",
+            Value::Bool(_) => "boolean",
+            Value::Number(n) if n.is_u64() || n.is_i64() => "integer",
+            Value::Number(_) => "number",
+            Value::String(_) => "string",
+            Value::Array(_) => "array",
+            Value::Object(_) => "object",
+        };
+        match schema_type {
+            Value::String(t) => t == kind || (t == "number" && kind == "integer"),
+            Value::Array(types) => types.iter().any(|t| type_matches(t, value)),
+            _ => true,
+        }
+    }
+
+    #[test]
+    fn report_rows_match_schema() {
+        let schema = mft_entry_schema();
+        assert_eq!(schema["$id"], format!("urn:mftshadowforge:mft-entry:v{}", SCHEMA_VERSION));
+        let properties = schema["properties"].as_object().unwrap();
+        let required: Vec<&str> = schema["required"].as_array().unwrap().iter().filter_map(Value::as_str).collect();
+
+        let mft = synthetic_mft(&[(5, "docs", true, true), (16, "a.txt", false, true), (16, "gone.txt", false, false)]);
+        let mut rows = 0;
+        for entry in parse_records(io::Cursor::new(mft), &ParseOptions::default()).unwrap() {
+            let Value::Object(row) = serde_json::to_value(&entry).unwrap() else { panic!("строка не объект") };
+            assert_eq!(row["SchemaVersion"], properties["SchemaVersion"]["const"]);
+            for name in &required {
+                assert!(row.contains_key(*name), "нет обязательного поля {}", name);
+            }
+            for (name, value) in &row {
+                let property = properties.get(name).unwrap_or_else(|| panic!("поля {} нет в схеме", name));
+                assert!(type_matches(&property["type"], value), "{}: {} не подходит под {}", name, value, property);
+            }
+            rows += 1;
+        }
+        assert_eq!(rows, 4);
+    }
+}
@@ -0,0 +1,297 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+use serde::Serialize;
+use serde_json::Value;
+use tracing::info;
+
+use super::parse::{scan_entries, summary_path_for_report, ParseOptions};
+use crate::error::{Error, Result};
+use crate::i18n::common;
+use crate::models::{MftUtilization, RunSummary};
+use crate::output;
+
+/// Флаги детектов строки отчета, которые считаются в сводке.
+const DETECTION_FLAGS: [&str; 17] = [
+    "Timestomped", "FitsRules", "uSecZeros", "Copied", "TornWrite", "HasAds", "PathTooDeep", "PathTooLong",
+    "RepeatedNesting", "DotLikeName", "CaseCollision", "SuspiciousReparse", "PosixNameAnomaly",
+    "CompressedExecInTemp", "LinkCountMismatch", "ShortNameMismatch", "KnownGood",
+];
+
+/// Минимальный набор полей строки отчета, нужный для статистики.
+struct ReportRow {
+    full_path: String,
+    extension: Option<String>,
+    is_directory: bool,
+    in_use: bool,
+    has_ads: bool,
+    file_size: u64,
+    created: Option<String>,
+    flags: Vec<&'static str>,
+}
+
+impl ReportRow {
+    fn from_value(row: &Value) -> Self {
+        let flag = |name: &str| row.get(name).and_then(Value::as_bool) == Some(true);
+        ReportRow {
+            full_path: row.get("Full_Path").and_then(Value::as_str).unwrap_or_default().to_string(),
+            extension: row.get("Extension").and_then(Value::as_str).map(str::to_string),
+            is_directory: flag("IsDirectory"),
+            in_use: flag("InUse"),
+            has_ads: flag("HasAds"),
+            file_size: row.get("FileSize").and_then(Value::as_u64).unwrap_or(0),
+            created: row.get("Created0x10").and_then(Value::as_str).map(str::to_string),
+            flags: DETECTION_FLAGS.iter().copied().filter(|f| flag(f)).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+struct DirExtensions {
+    total_files: u64,
+    extensions: BTreeMap<String, u64>,
+}
+
+#[derive(Debug, Serialize)]
+struct RareEntry {
+    full_path: String,
+    top_dir: String,
+    extension: String,
+    ext_count: u64,
+    tree_files: u64,
+    rarity: f64,
+}
+
+/// Общие счетчики: записи, файлы, каталоги, удаленные, файлы с ADS.
+#[derive(Debug, Default, Serialize)]
+struct Overview {
+    records: u64,
+    files: u64,
+    directories: u64,
+    in_use: u64,
+    deleted: u64,
+    files_with_ads: u64,
+    /// Суммарный размер занятых файлов, байт
+    total_file_size: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+struct LargeFile {
+    file_size: u64,
+    full_path: String,
+    in_use: bool,
+}
+
+/// Диапазон и помесячная гистограмма $SI Created файлов.
+#[derive(Debug, Default, Serialize)]
+struct CreatedRange {
+    first: Option<String>,
+    last: Option<String>,
+    by_month: BTreeMap<String, u64>,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct StatsReport {
+    /// Заполненность $MFT из summary.json отчета (если он есть рядом)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    utilization: Option<MftUtilization>,
+    overview: Overview,
+    extensions: BTreeMap<String, u64>,
+    /// Число записей по глубине пути (число компонентов без буквы диска)
+    depth: BTreeMap<usize, u64>,
+    detections: BTreeMap<String, u64>,
+    largest_files: Vec<LargeFile>,
+    created: CreatedRange,
+    extensions_by_top_dir: BTreeMap<String, DirExtensions>,
+    rare_entries: Vec<RareEntry>,
+}
+
+/// Каталог верхнего уровня: первый компонент пути без буквы диска.
+pub fn top_level_dir(full_path: &str) -> String {
+    let trimmed = strip_drive(full_path);
+    let mut parts = trimmed.split('\\').filter(|p| !p.is_empty());
+    match (parts.next(), parts.next()) {
+        (Some(first), Some(_)) => first.to_string(),
+        _ => String::from("\\"),
+    }
+}
+
+fn strip_drive(full_path: &str) -> &str {
+    match full_path.find(":\\") {
+        Some(pos) if pos <= 2 => &full_path[pos + 1..],
+        _ => full_path,
+    }
+}
+
+/// Строки отчета: JSONL или (если файл не начинается с `{`) raw MFT, разбираемый на лету.
+fn for_each_row<F: FnMut(ReportRow)>(input: &str, mut f: F) -> Result<()> {
+    let mut reader = BufReader::new(File::open(input).map_err(Error::io(format!("Не удалось открыть {}", input)))?);
+    let is_jsonl = reader.fill_buf().map(|b| b.iter().find(|c| !c.is_ascii_whitespace()) == Some(&b'{')).unwrap_or(false);
+    if is_jsonl {
+        for line in reader.lines().map_while(Result::ok) {
+            if line.trim().is_empty() { continue; }
+            if let Ok(row) = serde_json::from_str::<Value>(&line) {
+                f(ReportRow::from_value(&row));
+            }
+        }
+        return Ok(());
+    }
+    info!("[*] {} - не JSONL, разбор как raw MFT", input);
+    let scanned = scan_entries(input, &ParseOptions::default(), |entry| {
+        if let Ok(row) = serde_json::to_value(&entry) {
+            f(ReportRow::from_value(&row));
+        }
+    });
+    scanned.map_err(Error::io(format!("Ошибка разбора {}", input)))?;
+    Ok(())
+}
+
+/// Сводка по тому за один проход: общие счетчики, расширения, глубина путей, детекты,
+/// крупнейшие файлы, гистограмма создания и редкие расширения по деревьям.
+pub fn run(input: &str, out_json: Option<&str>, top: usize) -> Result<()> {
+    info!("[*] Запуск Stats");
+
+    let mut report = StatsReport {
+        utilization: File::open(summary_path_for_report(input)).ok()
+            .and_then(|f| serde_json::from_reader::<_, RunSummary>(f).ok())
+            .and_then(|s| s.utilization),
+        ..Default::default()
+    };
+    let mut largest: BinaryHeap<Reverse<LargeFile>> = BinaryHeap::new();
+    // Кандидаты в редкие: для каждой пары (дерево, расширение) - `top` первых по алфавиту путей.
+    // Внутри пары порядок итогового списка - по пути, поэтому остальные в него не попадут
+    let mut candidates: BTreeMap<(String, String), BTreeSet<String>> = BTreeMap::new();
+
+    for_each_row(input, |row| {
+        let overview = &mut report.overview;
+        overview.records += 1;
+        if row.is_directory { overview.directories += 1 } else { overview.files += 1 }
+        if row.in_use { overview.in_use += 1 } else { overview.deleted += 1 }
+        if row.has_ads { overview.files_with_ads += 1 }
+        let depth = strip_drive(&row.full_path).split('\\').filter(|p| !p.is_empty()).count();
+        *report.depth.entry(depth).or_insert(0) += 1;
+        for flag in &row.flags {
+            *report.detections.entry(flag.to_string()).or_insert(0) += 1;
+        }
+        if row.is_directory { return; }
+
+        if row.in_use { overview.total_file_size += row.file_size; }
+        largest.push(Reverse(LargeFile { file_size: row.file_size, full_path: row.full_path.clone(), in_use: row.in_use }));
+        if largest.len() > top { largest.pop(); }
+
+        // Нулевой или битый FILETIME (раньше 1980) в диапазон не попадает, как и в activity
+        if let Some(created) = row.created.filter(|c| c.as_str() >= "1980") {
+            if let Some(month) = created.get(..7) {
+                *report.created.by_month.entry(month.to_string()).or_insert(0) += 1;
+            }
+            if report.created.first.as_ref().is_none_or(|f| &created < f) { report.created.first = Some(created.clone()); }
+            if report.created.last.as_ref().is_none_or(|l| &created > l) { report.created.last = Some(created); }
+        }
+
+        let ext = row.extension.map(|e| e.to_lowercase()).unwrap_or_default();
+        *report.extensions.entry(ext.clone()).or_insert(0) += 1;
+        let top_dir = top_level_dir(&row.full_path);
+        let dir = report.extensions_by_top_dir.entry(top_dir.clone()).or_default();
+        dir.total_files += 1;
+        *dir.extensions.entry(ext.clone()).or_insert(0) += 1;
+        if !ext.is_empty() && top > 0 {
+            let paths = candidates.entry((top_dir, ext)).or_default();
+            if paths.len() < top || paths.last().is_some_and(|last| &row.full_path < last) {
+                paths.insert(row.full_path);
+                if paths.len() > top { paths.pop_last(); }
+            }
+        }
+    })?;
+
+    report.largest_files = largest.into_sorted_vec().into_iter().map(|Reverse(f)| f).collect();
+
+    // Редкость расширения внутри своего дерева
+    for ((top_dir, ext), paths) in candidates {
+        let dir = &report.extensions_by_top_dir[&top_dir];
+        let ext_count = dir.extensions.get(&ext).copied().unwrap_or(0);
+        if ext_count == 0 || (ext_count > 1 && ext_count * 100 > dir.total_files) { continue; }
+
+        let rarity = 1.0 - ext_count as f64 / dir.total_files as f64;
+        for full_path in paths {
+            report.rare_entries.push(RareEntry {
+                full_path, top_dir: top_dir.clone(), extension: ext.clone(), ext_count,
+                tree_files: dir.total_files, rarity: (rarity * 10_000.0).round() / 10_000.0,
+            });
+        }
+    }
+    report.rare_entries.sort_by(|a, b| a.ext_count.cmp(&b.ext_count)
+        .then(b.tree_files.cmp(&a.tree_files))
+        .then(a.full_path.cmp(&b.full_path)));
+    report.rare_entries.truncate(top);
+
+    if let Some(u) = &report.utilization {
+        info!("\n[+] Заполненность $MFT:");
+        info!("    Записей: {} (занято {}, {:.1}%; свободно {})", u.records_total, u.in_use, u.in_use_percent(), u.free);
+        info!("    Пустых слотов: {}, BAAD: {}, ошибок fixup: {}", u.empty, u.baad, u.fixup_failed);
+        info!("    Записей-расширений: {}, атрибутов на запись: {:.2}", u.extension_records, u.avg_attributes_per_record);
+        info!("    Резидентные данные: {} байт", u.resident_data_bytes);
+    }
+
+    let o = &report.overview;
+    info!("\n[+] Обзор:");
+    info!("    Записей: {} (файлов {}, каталогов {}); занято {}, удалено {}", o.records, o.files, o.directories, o.in_use, o.deleted);
+    info!("    Файлов с ADS: {}; объем занятых файлов: {} байт", o.files_with_ads, o.total_file_size);
+    if let (Some(first), Some(last)) = (&report.created.first, &report.created.last) {
+        info!("    Создание файлов ($SI): {} .. {}", first, last);
+    }
+
+    info!("\n[+] Детекты:");
+    if report.detections.is_empty() {
+        info!("    нет");
+    }
+    for (flag, count) in &report.detections {
+        info!("    {:<24} {}", flag, count);
+    }
+
+    let mut exts: Vec<_> = report.extensions.iter().collect();
+    exts.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+    info!("\n[+] Расширения (топ-{}):", top.min(exts.len()));
+    for (ext, count) in exts.iter().take(top) {
+        info!("    {:<24} {}", if ext.is_empty() { "<нет>" } else { ext.as_str() }, count);
+    }
+
+    info!("\n[+] Глубина путей:");
+    for (depth, count) in &report.depth {
+        info!("    {:>3}: {}", depth, count);
+    }
+
+    info!("\n[+] Крупнейшие файлы:");
+    for f in &report.largest_files {
+        info!("    {:>16} {} {}", f.file_size, if f.in_use { "   " } else { "DEL" }, f.full_path);
+    }
+
+    let mut months: Vec<_> = report.created.by_month.iter().collect();
+    months.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+    info!("\n[+] Месяцы с наибольшим числом созданных файлов:");
+    for (month, count) in months.iter().take(10) {
+        info!("    {} {}", month, count);
+    }
+
+    info!("\n[+] Расширения по каталогам верхнего уровня:");
+    for (dir, stats) in &report.extensions_by_top_dir {
+        let mut exts: Vec<_> = stats.extensions.iter().collect();
+        exts.sort_by(|a, b| b.1.cmp(a.1));
+        let preview: Vec<String> = exts.iter().take(5)
+            .map(|(e, c)| format!("{}={}", if e.is_empty() { "<нет>" } else { e.as_str() }, c))
+            .collect();
+        info!("    {:<30} файлов: {:<8} {}", dir, stats.total_files, preview.join(", "));
+    }
+
+    info!("\n[+] Самые редкие расширения в своем дереве:");
+    for e in &report.rare_entries {
+        info!("    [{:.4}] .{} ({} из {} в {}) {}", e.rarity, e.extension, e.ext_count, e.tree_files, e.top_dir, e.full_path);
+    }
+
+    if let Some(out) = out_json {
+        output::write_json_file(out, &report).map_err(Error::io(common::write_failed(out)))?;
+        info!("\n[+] Статистика сохранена: {}", out);
+    }
+    Ok(())
+}
\ No newline at end of file
@@ -0,0 +1,347 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Lines, Write};
+use std::path::PathBuf;
+
+use chrono::{DateTime, FixedOffset};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::{info, warn};
+
+use crate::error::{Error, Result};
+use crate::i18n::common;
+use crate::mft::usn::{self, UsnRecord};
+
+/// Формат итоговой временной шкалы.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TimelineFormat {
+    Jsonl,
+    Csv,
+}
+
+/// Временные метки атрибута в порядке M, A, C, B.
+const MACB_FIELDS: [(&str, [&str; 4]); 2] = [
+    ("$SI", ["LastModified0x10", "LastAccess0x10", "LastRecordChange0x10", "Created0x10"]),
+    ("$FN", ["LastModified0x30", "LastAccess0x30", "LastRecordChange0x30", "Created0x30"]),
+];
+
+/// Сколько событий сортируется в памяти; сверх этого отсортированные порции
+/// сбрасываются во временные файлы и затем сливаются.
+const SPILL_EVENTS: usize = 2_000_000;
+
+/// Признаки строки отчета, которые переносятся в Details событий MFT.
+const DETAIL_FLAGS: [&str; 4] = ["Timestomped", "uSecZeros", "Copied", "HasAds"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+enum Source {
+    #[serde(rename = "MFT")]
+    Mft,
+    #[serde(rename = "USN")]
+    Usn,
+}
+
+/// Строка временной шкалы; колонки одинаковы для событий MFT и USN.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct TimelineEvent {
+    // Во временных файлах не хранится: восстанавливается из timestamp
+    #[serde(skip)]
+    time: DateTime<FixedOffset>,
+    timestamp: String,
+    source: Source,
+    event: String,
+    entry_number: u64,
+    sequence_number: u64,
+    path: String,
+    details: String,
+}
+
+impl TimelineEvent {
+    fn source_name(&self) -> &'static str {
+        match self.source {
+            Source::Mft => "MFT",
+            Source::Usn => "USN",
+        }
+    }
+
+    /// Порядок шкалы: время, источник, запись. Сортировка устойчивая - события одной
+    /// записи с одинаковым временем идут в порядке появления ($SI раньше $FN).
+    fn sort_key(&self) -> SortKey {
+        (self.time, self.source, self.entry_number, self.sequence_number)
+    }
+}
+
+type SortKey = (DateTime<FixedOffset>, Source, u64, u64);
+
+/// Событие из временного файла с номером файла: при равном ключе порядок
+/// частей сохраняет исходный порядок событий.
+struct PartHead {
+    ev: TimelineEvent,
+    part: usize,
+}
+
+impl PartHead {
+    fn key(&self) -> (SortKey, usize) { (self.ev.sort_key(), self.part) }
+}
+
+impl PartialEq for PartHead {
+    fn eq(&self, other: &Self) -> bool { self.key() == other.key() }
+}
+
+impl Eq for PartHead {}
+
+impl Ord for PartHead {
+    fn cmp(&self, other: &Self) -> Ordering { self.key().cmp(&other.key()) }
+}
+
+impl PartialOrd for PartHead {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) }
+}
+
+const CSV_HEADER: &str = "Timestamp,Source,Event,EntryNumber,SequenceNumber,Path,Details";
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+fn open(path: &str) -> Result<File> {
+    File::open(path).map_err(Error::io(format!("Не удалось открыть {}", path)))
+}
+
+/// События MFT: одинаковые метки одного атрибута сворачиваются в одно событие с маской MACB.
+fn mft_events(row: &Value, events: &mut Vec<TimelineEvent>) {
+    let entry_number = row.get("EntryNumber").and_then(Value::as_u64).unwrap_or(0);
+    let sequence_number = row.get("SequenceNumber").and_then(Value::as_u64).unwrap_or(0);
+    let path = row.get("Full_Path").and_then(Value::as_str).unwrap_or("");
+    let details: Vec<&str> = DETAIL_FLAGS.iter().copied()
+        .filter(|f| row.get(*f).and_then(Value::as_bool) == Some(true))
+        .collect();
+    let details = details.join("|");
+
+    for (attr, fields) in MACB_FIELDS {
+        let mut groups: Vec<(&str, DateTime<FixedOffset>, [bool; 4])> = Vec::new();
+        for (i, field) in fields.iter().enumerate() {
+            let Some(ts) = row.get(*field).and_then(Value::as_str) else { continue };
+            let Ok(time) = DateTime::parse_from_rfc3339(ts) else { continue };
+            match groups.iter_mut().find(|g| g.1 == time) {
+                Some(g) => g.2[i] = true,
+                None => {
+                    let mut mask = [false; 4];
+                    mask[i] = true;
+                    groups.push((ts, time, mask));
+                }
+            }
+        }
+        for (ts, time, mask) in groups {
+            let macb: String = "MACB".chars().zip(mask).map(|(c, set)| if set { c } else { '.' }).collect();
+            events.push(TimelineEvent {
+                time,
+                timestamp: ts.to_string(),
+                source: Source::Mft,
+                event: format!("{} {}", attr, macb),
+                entry_number,
+                sequence_number,
+                path: path.to_string(),
+                details: details.clone(),
+            });
+        }
+    }
+}
+
+fn usn_event(rec: UsnRecord, dirs: &HashMap<u64, String>) -> Option<TimelineEvent> {
+    let time = DateTime::parse_from_rfc3339(&rec.timestamp).ok()?;
+    // Путь восстанавливается по родительскому каталогу из отчета: сам файл мог быть уже удален
+    let path = match dirs.get(&rec.parent_entry) {
+        Some(parent) if parent.ends_with('\\') => format!("{}{}", parent, rec.file_name),
+        Some(parent) => format!("{}\\{}", parent, rec.file_name),
+        None => rec.file_name.clone(),
+    };
+    Some(TimelineEvent {
+        time,
+        timestamp: rec.timestamp,
+        source: Source::Usn,
+        event: rec.reason_names,
+        entry_number: rec.entry,
+        sequence_number: rec.sequence as u64,
+        path,
+        details: format!("Usn={} Attributes=0x{:X}", rec.usn, rec.file_attributes),
+    })
+}
+
+/// Читает USN-записи: JSONL (поток USN из watch) или сырой выгруженный `$J`.
+fn read_usn<F: FnMut(UsnRecord)>(path: &str, mut f: F) -> Result<()> {
+    let mut reader = BufReader::new(open(path)?);
+    let is_jsonl = reader.fill_buf().map(|b| b.first() == Some(&b'{')).unwrap_or(false);
+    if is_jsonl {
+        for line in reader.lines().map_while(Result::ok) {
+            if let Ok(rec) = serde_json::from_str::<UsnRecord>(&line) {
+                f(rec);
+            }
+        }
+    } else if let Err(e) = usn::scan_journal(reader, f) {
+        warn!("[!] Ошибка чтения {}: {}", path, e);
+    }
+    Ok(())
+}
+
+/// Накопитель событий: при переполнении сортирует порцию и сбрасывает во временный файл.
+struct Spill {
+    events: Vec<TimelineEvent>,
+    parts: Vec<PathBuf>,
+}
+
+impl Spill {
+    fn push(&mut self, ev: TimelineEvent) -> Result<()> {
+        self.events.push(ev);
+        if self.events.len() >= SPILL_EVENTS {
+            self.flush_part()?;
+        }
+        Ok(())
+    }
+
+    fn flush_part(&mut self) -> Result<()> {
+        self.events.sort_by_key(TimelineEvent::sort_key);
+        let path = std::env::temp_dir().join(format!("mft_shadow_forge_timeline_{}_{}.jsonl", std::process::id(), self.parts.len()));
+        let written = File::create(&path).and_then(|f| {
+            let mut w = BufWriter::new(f);
+            for ev in self.events.drain(..) {
+                serde_json::to_writer(&mut w, &ev)?;
+                w.write_all(b"\n")?;
+            }
+            w.flush()
+        });
+        written.map_err(Error::io(format!("Не удалось записать временный файл {}", path.display())))?;
+        self.parts.push(path);
+        Ok(())
+    }
+}
+
+/// Следующее событие из отсортированного временного файла.
+fn next_part_event(lines: &mut Lines<BufReader<File>>) -> Option<TimelineEvent> {
+    let line = lines.next()?.ok()?;
+    let mut ev: TimelineEvent = serde_json::from_str(&line).ok()?;
+    ev.time = DateTime::parse_from_rfc3339(&ev.timestamp).ok()?;
+    Some(ev)
+}
+
+fn write_event<W: Write>(writer: &mut W, ev: &TimelineEvent, format: TimelineFormat) -> io::Result<()> {
+    match format {
+        TimelineFormat::Jsonl => {
+            serde_json::to_writer(&mut *writer, ev)?;
+            writer.write_all(b"\n")
+        }
+        TimelineFormat::Csv => writeln!(writer, "{},{},{},{},{},{},{}",
+            ev.timestamp, ev.source_name(), csv_field(&ev.event), ev.entry_number,
+            ev.sequence_number, csv_field(&ev.path), csv_field(&ev.details)),
+    }
+}
+
+/// Пишет события по порядку, пропуская полные дубли (одна и та же запись из
+/// пересекающихся отчетов или USN-потоков): дубли имеют одинаковый ключ сортировки,
+/// поэтому сравнивать достаточно внутри группы с равным ключом. Возвращает (записано, дублей).
+fn write_sorted<W: Write, I: Iterator<Item = TimelineEvent>>(writer: &mut W, events: I, format: TimelineFormat) -> io::Result<(u64, u64)> {
+    let (mut written, mut duplicates) = (0u64, 0u64);
+    let mut group: Vec<TimelineEvent> = Vec::new();
+    for ev in events {
+        if group.first().is_some_and(|g| g.sort_key() != ev.sort_key()) {
+            group.clear();
+        }
+        if group.contains(&ev) {
+            duplicates += 1;
+            continue;
+        }
+        write_event(writer, &ev, format)?;
+        written += 1;
+        group.push(ev);
+    }
+    Ok((written, duplicates))
+}
+
+pub fn run(mft_reports: &[String], usn_paths: &[String], out: &str, format: TimelineFormat) -> Result<()> {
+    info!("[*] Запуск Supertimeline");
+
+    let mut spill = Spill { events: Vec::new(), parts: Vec::new() };
+    let mut dirs: HashMap<u64, String> = HashMap::new();
+    let (mut mft_count, mut usn_count) = (0u64, 0u64);
+    let mut row_events = Vec::new();
+
+    for mft_report in mft_reports {
+        info!(" -> Отчет MFT: {}", mft_report);
+        for line in BufReader::new(open(mft_report)?).lines().map_while(Result::ok) {
+            if line.trim().is_empty() { continue; }
+            let Ok(row) = serde_json::from_str::<Value>(&line) else { continue };
+            mft_events(&row, &mut row_events);
+            mft_count += row_events.len() as u64;
+            row_events.drain(..).try_for_each(|ev| spill.push(ev))?;
+            // Пути каталогов: из самих каталогов и из ParentPath их содержимого
+            let is_dir = row.get("IsDirectory").and_then(Value::as_bool) == Some(true);
+            if let (true, Some(entry), Some(path)) = (is_dir, row.get("EntryNumber").and_then(Value::as_u64), row.get("Full_Path").and_then(Value::as_str)) {
+                dirs.insert(entry, path.to_string());
+            }
+            if let (Some(parent), Some(path)) = (row.get("ParentEntryNumber").and_then(Value::as_u64), row.get("ParentPath").and_then(Value::as_str)) {
+                dirs.entry(parent).or_insert_with(|| path.to_string());
+            }
+        }
+    }
+
+    for usn_path in usn_paths {
+        info!(" -> USN-журнал: {}", usn_path);
+        // Ошибка сброса порции на диск прерывает чтение журнала: остальные записи не сохранить
+        let mut spilled = Ok(());
+        read_usn(usn_path, |rec| {
+            if let Some(ev) = usn_event(rec, &dirs).filter(|_| spilled.is_ok()) {
+                usn_count += 1;
+                spilled = spill.push(ev);
+            }
+        })?;
+        spilled?;
+    }
+
+    let mut writer = BufWriter::new(File::create(out).map_err(Error::io(format!("Не удалось создать {}", out)))?);
+    if format == TimelineFormat::Csv {
+        writeln!(writer, "{}", CSV_HEADER).map_err(Error::io(common::write_failed(out)))?;
+    }
+
+    let counts = if spill.parts.is_empty() {
+        spill.events.sort_by_key(TimelineEvent::sort_key);
+        write_sorted(&mut writer, spill.events.drain(..), format)
+    } else {
+        if !spill.events.is_empty() {
+            spill.flush_part()?;
+        }
+        info!(" -> Слияние {} отсортированных частей...", spill.parts.len());
+        let mut readers: Vec<Lines<BufReader<File>>> = spill.parts.iter()
+            .map(|p| open(&p.to_string_lossy()).map(|f| BufReader::new(f).lines()))
+            .collect::<Result<_>>()?;
+        let mut heap: BinaryHeap<Reverse<PartHead>> = BinaryHeap::new();
+        for (part, r) in readers.iter_mut().enumerate() {
+            if let Some(ev) = next_part_event(r) {
+                heap.push(Reverse(PartHead { ev, part }));
+            }
+        }
+        let merged = std::iter::from_fn(|| {
+            let Reverse(head) = heap.pop()?;
+            if let Some(ev) = next_part_event(&mut readers[head.part]) {
+                heap.push(Reverse(PartHead { ev, part: head.part }));
+            }
+            Some(head.ev)
+        });
+        let result = write_sorted(&mut writer, merged, format);
+        for part in &spill.parts {
+            let _ = std::fs::remove_file(part);
+        }
+        result
+    };
+    let (written, duplicates) = counts.and_then(|counts| writer.flush().map(|_| counts))
+        .map_err(Error::io(common::write_failed(out)))?;
+
+    info!("\n[+] Событий: {} (MFT: {}, USN: {}, дублей отброшено: {})", written, mft_count, usn_count, duplicates);
+    info!("[+] Результат сохранен: {}", out);
+    Ok(())
+}
\ No newline at end of file
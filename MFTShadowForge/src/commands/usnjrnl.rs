@@ -0,0 +1,51 @@
+use std::fs::File;
+use std::io::BufWriter;
+
+use tracing::{info, warn};
+
+use super::extract::{self, PartitionSelect};
+use super::volume::{self, StreamReader, VolumeRecords, ENTRY_MASK, ROOT_RECORD};
+use crate::audit;
+use crate::error::{Error, Result};
+use crate::image;
+use crate::mft::usn;
+use crate::models::{CaseInfo, Tags};
+use crate::output::JsonlWriter;
+
+/// Выгрузка и разбор USN-журнала тома: `\$Extend\$UsnJrnl` ищется по индексам каталогов
+/// от корня, поток `$J` читается по runlist, записи V2/V3 пишутся в JSONL
+/// (тот же формат, что USN-поток watch, - годится для supertimeline --usn).
+pub fn run(image: &str, out_jsonl: &str, partition: Option<PartitionSelect>) -> Result<()> {
+    info!("[*] Запуск UsnJrnl");
+    let volume_path = extract::volume_path(image);
+    let mut vol = image::open(&volume_path).map_err(Error::io(format!("Ошибка открытия {}", volume_path)))?;
+    let layout = extract::locate_mft_at(&mut vol, partition)?;
+
+    let mut src = VolumeRecords { vol: &mut vol, layout: &layout };
+    let extend = volume::lookup(&mut src, ROOT_RECORD, "$Extend").map_err(|e| Error::Corrupt(format!("$Extend: {}", e)))?;
+    let journal = volume::lookup(&mut src, extend & ENTRY_MASK, "$UsnJrnl").map_err(|e| Error::Corrupt(format!("$UsnJrnl: {}", e)))?;
+    let journal_entry = journal & ENTRY_MASK;
+    info!("[*] $Extend: запись {}, $UsnJrnl: запись {}", extend & ENTRY_MASK, journal_entry);
+
+    let buffers = volume::read_record(&mut src, journal_entry).map_err(|e| Error::Corrupt(format!("$UsnJrnl: {}", e)))?;
+    let stream = volume::data_stream(&buffers, layout.record_size, "$J").map_err(|e| Error::Corrupt(format!("$UsnJrnl: {}", e)))?;
+    if stream.resident.is_some() { return Err(Error::Corrupt("$UsnJrnl: резидентный поток $J - журнал пуст".into())); }
+    let allocated: u64 = stream.runs.iter().filter(|r| !r.is_sparse).map(|r| r.length).sum::<u64>() * layout.bytes_per_cluster;
+    info!("[*] $J: логический размер {} байт, выделено {} байт", stream.size, allocated.min(stream.size));
+
+    let out = File::create(out_jsonl).map_err(Error::io(format!("Не удалось создать {}", out_jsonl)))?;
+    let mut writer = JsonlWriter::new(BufWriter::new(out));
+    let mut records = 0u64;
+    let scanned = usn::scan_journal(StreamReader::new(&mut vol, &layout, &stream, true), |rec| {
+        if writer.write(&rec).is_ok() {
+            records += 1;
+        }
+    });
+    if let Err(e) = scanned {
+        warn!("[!] Ошибка чтения $J: {} - записано то, что прочитано", e);
+    }
+    writer.flush().map_err(Error::io(format!("Ошибка записи {}", out_jsonl)))?;
+    info!("[+] Записей USN: {} -> {}", records, out_jsonl);
+    audit::append("usnjrnl", &volume_path, out_jsonl, &CaseInfo::default(), &Tags::new());
+    Ok(())
+}
\ No newline at end of file
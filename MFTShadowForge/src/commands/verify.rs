@@ -0,0 +1,311 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufReader, Read};
+
+use byteorder::{ByteOrder, LittleEndian};
+use serde::Serialize;
+use tracing::{info, warn};
+
+use super::extract::{parse_data_runs, validate_record_boundaries};
+use super::parse::{gather_record_buffers, load_mft_meta};
+use super::volume;
+use crate::error::{Error, Result};
+use crate::i18n::common;
+use crate::mft::parser::{apply_fixups, FixupResult, MftParser};
+use crate::mft::record::MftRecordHeader;
+use crate::output;
+
+/// Смещение номера записи в заголовке NTFS 3.1 (при usa_offset 0x30).
+const RECORD_NUMBER_OFFSET: usize = 0x2C;
+
+/// Одна найденная проблема: запись (None - дамп в целом), вид и подробности.
+#[derive(Serialize)]
+struct Issue {
+    entry: Option<u64>,
+    kind: &'static str,
+    detail: String,
+}
+
+/// Проверка runlist $DATA записи 0 ($MFT) против самого дампа.
+#[derive(Serialize, Default)]
+struct RunlistCheck {
+    runs: usize,
+    clusters: u64,
+    sparse_clusters: u64,
+    data_size: u64,
+    initialized_size: u64,
+    vcn_continuous: bool,
+}
+
+/// Отчет verify: геометрия, счетчики по видам проблем и сами проблемы (до --max-issues).
+#[derive(Serialize)]
+struct VerifyReport {
+    source: String,
+    /// Откуда взята геометрия: meta (meta.json), record0 (заголовок записи 0) или args
+    geometry_source: &'static str,
+    record_size: usize,
+    bytes_per_sector: u16,
+    file_size: u64,
+    records: u64,
+    valid: u64,
+    empty: u64,
+    in_use: u64,
+    problems: BTreeMap<&'static str, u64>,
+    runlist: Option<RunlistCheck>,
+    healthy: bool,
+    issues_total: u64,
+    issues: Vec<Issue>,
+}
+
+impl VerifyReport {
+    fn issue(&mut self, max_issues: usize, entry: Option<u64>, kind: &'static str, detail: String) {
+        *self.problems.entry(kind).or_default() += 1;
+        self.issues_total += 1;
+        if self.issues.len() < max_issues {
+            self.issues.push(Issue { entry, kind, detail });
+        }
+    }
+}
+
+/// Размер записи и сектора дампа `path` по заголовку записи 0.
+/// Для дампов других инструментов без meta.json.
+fn detect_geometry(path: &str) -> Option<(usize, u16)> {
+    let mut head = [0u8; 48];
+    File::open(path).ok()?.read_exact(&mut head).ok()?;
+    MftRecordHeader::parse(&head)?.geometry()
+}
+
+/// Атрибуты записи (после fixups): длины, порядок типов, имена, значения и runlist в пределах
+/// real_size, завершающий маркер 0xFFFFFFFF.
+fn check_attributes(record: &[u8], header: &MftRecordHeader) -> Result<(), String> {
+    let used = header.real_size as usize;
+    let mut pos = header.first_attribute_offset as usize;
+    let mut prev_type = 0u32;
+    loop {
+        if pos + 4 > used {
+            return Err(format!("нет маркера конца атрибутов до real_size ({})", used));
+        }
+        let attr_type = LittleEndian::read_u32(&record[pos..pos + 4]);
+        if attr_type == 0xFFFFFFFF {
+            return Ok(());
+        }
+        if pos + 16 > used {
+            return Err(format!("заголовок атрибута 0x{:X} по смещению {} выходит за real_size", attr_type, pos));
+        }
+        let len = LittleEndian::read_u32(&record[pos + 4..pos + 8]) as usize;
+        if len < 16 || !len.is_multiple_of(8) || pos + len > used {
+            return Err(format!("атрибут 0x{:X} по смещению {}: некорректная длина {}", attr_type, pos, len));
+        }
+        if attr_type < prev_type {
+            return Err(format!("атрибут 0x{:X} по смещению {} нарушает порядок типов (после 0x{:X})", attr_type, pos, prev_type));
+        }
+        let attr = &record[pos..pos + len];
+        let name_len = attr[9] as usize;
+        let name_off = LittleEndian::read_u16(&attr[10..12]) as usize;
+        if name_len > 0 && name_off + name_len * 2 > len {
+            return Err(format!("имя атрибута 0x{:X} по смещению {} выходит за атрибут", attr_type, pos));
+        }
+        if attr[8] == 0 {
+            let value_len = if len >= 24 { LittleEndian::read_u32(&attr[16..20]) as usize } else { usize::MAX };
+            let value_off = if len >= 24 { LittleEndian::read_u16(&attr[20..22]) as usize } else { 0 };
+            if value_off.checked_add(value_len).is_none_or(|end| end > len) {
+                return Err(format!("значение резидентного атрибута 0x{:X} по смещению {} выходит за атрибут", attr_type, pos));
+            }
+        } else {
+            if len < 0x40 {
+                return Err(format!("нерезидентный атрибут 0x{:X} по смещению {} короче заголовка", attr_type, pos));
+            }
+            let start_vcn = LittleEndian::read_u64(&attr[16..24]);
+            let runs_off = LittleEndian::read_u16(&attr[32..34]) as usize;
+            if runs_off < 0x40 || runs_off >= len {
+                return Err(format!("runlist атрибута 0x{:X} по смещению {} вне атрибута", attr_type, pos));
+            }
+            parse_data_runs(record, pos + runs_off, pos + len, start_vcn)
+                .map_err(|e| format!("runlist атрибута 0x{:X} по смещению {}: {}", attr_type, pos, e))?;
+        }
+        prev_type = attr_type;
+        pos += len;
+    }
+}
+
+/// Runlist $DATA записи 0 (с экстентами): VCN идут подряд с 0, кластеров хватает на data_size,
+/// а сам дамп не короче data_size.
+fn check_runlist(parser: &mut MftParser, report: &mut VerifyReport, max_issues: usize) {
+    let Some(mut record0) = parser.fetch_record(0) else {
+        report.issue(max_issues, Some(0), "runlist", "запись 0 не прочитана".to_string());
+        return;
+    };
+    let Some(header) = MftRecordHeader::parse(&record0).filter(|h| h.signature == "FILE") else {
+        report.issue(max_issues, Some(0), "runlist", "запись 0 без сигнатуры FILE - runlist $MFT не проверен".to_string());
+        return;
+    };
+    if apply_fixups(&mut record0, &header, parser.bytes_per_sector) == FixupResult::Failed {
+        report.issue(max_issues, Some(0), "runlist", "fixups записи 0 не применились - runlist $MFT не проверен".to_string());
+        return;
+    }
+    let (buffers, _) = gather_record_buffers(parser, 0, record0);
+    let stream = match volume::data_stream(&buffers, parser.record_size, "") {
+        Ok(stream) if stream.resident.is_none() => stream,
+        Ok(_) => return report.issue(max_issues, Some(0), "runlist", "$DATA записи 0 резидентный".to_string()),
+        Err(e) => return report.issue(max_issues, Some(0), "runlist", format!("$DATA записи 0: {}", e)),
+    };
+
+    let mut check = RunlistCheck {
+        runs: stream.runs.len(),
+        data_size: stream.size,
+        initialized_size: stream.initialized,
+        vcn_continuous: true,
+        ..Default::default()
+    };
+    let mut next_vcn = 0u64;
+    for run in &stream.runs {
+        if run.vcn_start != next_vcn {
+            check.vcn_continuous = false;
+            let what = if run.vcn_start > next_vcn { "пропуск" } else { "перекрытие" };
+            report.issue(max_issues, Some(0), "vcn_gap", format!("{} VCN: ожидался {}, run начинается с {}", what, next_vcn, run.vcn_start));
+        }
+        next_vcn = run.vcn_start + run.length;
+        check.clusters += run.length;
+        if run.is_sparse { check.sparse_clusters += run.length; }
+    }
+    if check.sparse_clusters > 0 {
+        report.issue(max_issues, Some(0), "sparse_mft", format!("$MFT содержит {} разреженных кластеров", check.sparse_clusters));
+    }
+    if report.file_size < stream.size {
+        report.issue(max_issues, None, "truncated", format!("дамп {} байт короче $DATA записи 0 ({} байт)", report.file_size, stream.size));
+    }
+    if let Some(bpc) = load_mft_meta(&report.source).map(|m| m.bytes_per_cluster).filter(|&b| b > 0) {
+        if check.clusters * bpc < stream.size {
+            report.issue(max_issues, Some(0), "runlist", format!("кластеров runlist ({} x {}) меньше data_size {}", check.clusters, bpc, stream.size));
+        }
+    }
+    report.runlist = Some(check);
+}
+
+/// Проверка готового дампа $MFT (в том числе снятого другим инструментом) без разбора:
+/// сигнатуры, границы заголовков, fixups, номера записей, границы атрибутов и непрерывность
+/// VCN в runlist записи 0. Итог - JSON-отчет о состоянии дампа.
+pub fn run(path: &str, out_json: Option<&str>, record_size: Option<usize>, sector_size: Option<u16>, max_issues: usize) -> Result<()> {
+    info!("[*] Запуск Verify: {}", path);
+    let (geometry_source, (detected_record, detected_sector)) = match (load_mft_meta(path), detect_geometry(path)) {
+        (Some(meta), _) => ("meta", (meta.mft_record_size as usize, meta.bytes_per_sector)),
+        (None, Some(detected)) => ("record0", detected),
+        (None, None) => ("args", (1024, 512)),
+    };
+    let geometry_source = if record_size.is_some() || sector_size.is_some() { "args" } else { geometry_source };
+    let record_size = record_size.unwrap_or(detected_record);
+    let bytes_per_sector = sector_size.unwrap_or(detected_sector);
+    if record_size == 0 || bytes_per_sector == 0 || !record_size.is_multiple_of(bytes_per_sector as usize) {
+        return Err(Error::Invalid(format!("Некорректная геометрия: запись {} байт, сектор {} байт", record_size, bytes_per_sector)));
+    }
+    info!("[*] Запись {} байт, сектор {} байт (источник: {})", record_size, bytes_per_sector, geometry_source);
+
+    let mut parser = MftParser::new(path, record_size, bytes_per_sector)
+        .map_err(Error::io(format!("Не удалось открыть {}", path)))?;
+    let mut report = VerifyReport {
+        source: path.to_string(),
+        geometry_source,
+        record_size,
+        bytes_per_sector,
+        file_size: parser.file_size,
+        records: parser.total_records(),
+        valid: 0,
+        empty: 0,
+        in_use: 0,
+        problems: BTreeMap::new(),
+        runlist: None,
+        healthy: false,
+        issues_total: 0,
+        issues: Vec::new(),
+    };
+    if !parser.file_size.is_multiple_of(record_size as u64) {
+        report.issue(max_issues, None, "partial_record", format!("размер дампа {} не кратен размеру записи {}", parser.file_size, record_size));
+    }
+
+    let mut reader = BufReader::new(File::open(path).map_err(Error::io(format!("Не удалось открыть {}", path)))?);
+    let mut record = vec![0u8; record_size];
+    for entry in 0..report.records {
+        if let Err(e) = reader.read_exact(&mut record) {
+            report.issue(max_issues, Some(entry), "read_error", e.to_string());
+            break;
+        }
+        let Some(header) = MftRecordHeader::parse(&record) else {
+            if record.iter().all(|&b| b == 0) {
+                report.empty += 1;
+            } else {
+                let signature = String::from_utf8_lossy(&record[..4]).escape_debug().to_string();
+                report.issue(max_issues, Some(entry), "bad_signature", format!("сигнатура \"{}\"", signature));
+            }
+            continue;
+        };
+        if header.signature == "BAAD" {
+            report.issue(max_issues, Some(entry), "baad", "запись помечена BAAD (ошибка fixups при записи)".to_string());
+            continue;
+        }
+        if header.is_in_use() { report.in_use += 1; }
+        let mut problems = 0;
+        if let Err(e) = validate_record_boundaries(&header, record_size, entry == 0) {
+            report.issue(max_issues, Some(entry), "bad_header", e);
+            continue;
+        }
+        if header.allocated_size as usize != record_size {
+            report.issue(max_issues, Some(entry), "bad_header", format!("allocated_size {} не равен размеру записи {}", header.allocated_size, record_size));
+            problems += 1;
+        }
+        match apply_fixups(&mut record, &header, bytes_per_sector) {
+            FixupResult::Failed => {
+                report.issue(max_issues, Some(entry), "fixup_failed", "массив USA не соответствует записи".to_string());
+                continue;
+            }
+            FixupResult::TornWrite => {
+                report.issue(max_issues, Some(entry), "torn_write", "хвосты секторов не совпадают с USN".to_string());
+                problems += 1;
+            }
+            FixupResult::Ok => {}
+        }
+        if header.update_sequence_offset as usize >= RECORD_NUMBER_OFFSET + 4 {
+            let number = LittleEndian::read_u32(&record[RECORD_NUMBER_OFFSET..RECORD_NUMBER_OFFSET + 4]) as u64;
+            if number != entry & 0xFFFF_FFFF {
+                report.issue(max_issues, Some(entry), "record_number_mismatch", format!("в заголовке номер {}", number));
+                problems += 1;
+            }
+        }
+        if let Err(e) = check_attributes(&record, &header) {
+            report.issue(max_issues, Some(entry), "bad_attributes", e);
+            problems += 1;
+        }
+        if problems == 0 { report.valid += 1; }
+    }
+
+    check_runlist(&mut parser, &mut report, max_issues);
+    report.healthy = report.issues_total == 0;
+
+    info!("[+] Записей: {} (корректных {}, пустых {}, занятых {})", report.records, report.valid, report.empty, report.in_use);
+    if let Some(runlist) = &report.runlist {
+        info!("[+] Runlist $MFT: {} runs, {} кластеров, data_size {} байт, VCN {}", runlist.runs, runlist.clusters, runlist.data_size,
+            if runlist.vcn_continuous { "подряд" } else { "с разрывами" });
+    }
+    for (kind, count) in &report.problems {
+        warn!("[!] {}: {}", kind, count);
+    }
+    for issue in report.issues.iter().take(10) {
+        match issue.entry {
+            Some(entry) => info!("    запись {}: {} - {}", entry, issue.kind, issue.detail),
+            None => info!("    дамп: {} - {}", issue.kind, issue.detail),
+        }
+    }
+    if report.issues_total > 10 {
+        info!("    ... и еще {} (полный список - в --out-json)", report.issues_total - 10);
+    }
+    if report.healthy {
+        info!("[+] Дамп исправен");
+    } else {
+        warn!("[!] Дамп содержит ошибки");
+    }
+
+    if let Some(out_json) = out_json {
+        output::write_json_file(out_json, &report).map_err(Error::io(common::write_failed(out_json)))?;
+        info!("[+] Отчет сохранен: {}", out_json);
+    }
+    Ok(())
+}
\ No newline at end of file
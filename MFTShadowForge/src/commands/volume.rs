@@ -0,0 +1,423 @@
+use std::borrow::Cow;
+use std::io::{self, Read, Seek, SeekFrom};
+
+use byteorder::{ByteOrder, LittleEndian};
+
+use super::extract::{self, DataRun, MftLayout};
+use super::parse::{gather_record_buffers, index_record, read_attr_name, read_nonresident_runs, read_nonresident_sizes, ParseContext, ParseOptions};
+use crate::i18n::common;
+use crate::mft::attributes::FileNameAttribute;
+use crate::mft::parser::{apply_block_fixups, apply_fixups, FixupResult, RecordSource};
+use crate::mft::path_builder::PathBuilder;
+use crate::mft::record::MftRecordHeader;
+use crate::models::VolumeInfo;
+
+/// Корневой каталог тома - начало разрешения путей.
+pub const ROOT_RECORD: u64 = 5;
+/// Запись $Volume: метка и версия NTFS.
+const VOLUME_RECORD: u64 = 3;
+/// Флаг $VOLUME_INFORMATION: том не размонтирован корректно.
+const VOLUME_IS_DIRTY: u16 = 0x0001;
+/// Номер записи в ссылке на файл (старшие 16 бит - sequence).
+pub const ENTRY_MASK: u64 = 0xFFFFFFFFFFFF;
+/// Флаг последнего элемента узла индекса.
+const INDEX_ENTRY_LAST: u32 = 0x02;
+/// Объем одного чтения потока (кратен любому размеру кластера).
+const READ_CHUNK: u64 = 1024 * 1024;
+/// Предел служебного нерезидентного атрибута, читаемого в память ($ATTRIBUTE_LIST - до 256 КиБ).
+const MAX_NONRESIDENT_ATTR: u64 = 1024 * 1024;
+/// Атрибут сжат (LZNT1) - на диске не содержимое файла, а сжатые блоки.
+pub const ATTR_COMPRESSED: u16 = 0x0001;
+/// Атрибут зашифрован EFS - на диске шифротекст.
+pub const ATTR_ENCRYPTED: u16 = 0x4000;
+
+/// Записи MFT тома, читаемые по runlist $MFT.
+pub struct VolumeRecords<'a, R: Read + Seek> {
+    pub vol: &'a mut R,
+    pub layout: &'a MftLayout,
+}
+
+impl<R: Read + Seek> RecordSource for VolumeRecords<'_, R> {
+    fn record_size(&self) -> usize { self.layout.record_size }
+    fn bytes_per_sector(&self) -> u16 { self.layout.boot.bytes_per_sector }
+    fn total_records(&self) -> u64 { self.layout.total_records() }
+    fn fetch_record(&mut self, entry_num: u64) -> Option<Vec<u8>> { self.layout.read_record(self.vol, entry_num).ok() }
+    fn read_nonresident(&mut self, record: &[u8], attr_offset: usize, attr_end: usize) -> Option<Vec<u8>> {
+        read_attribute(self.vol, self.layout, record, attr_offset, attr_end)
+    }
+}
+
+/// Базовая запись с fixups и ее экстенты из $ATTRIBUTE_LIST.
+pub fn read_record<S: RecordSource>(src: &mut S, entry: u64) -> Result<Vec<Cow<'static, [u8]>>, String> {
+    let mut record = src.fetch_record(entry).ok_or_else(|| common::record_unreadable(entry))?;
+    let header = MftRecordHeader::parse(&record).ok_or_else(|| common::record_damaged(entry))?;
+    if header.signature != "FILE" {
+        return Err(common::record_signature(entry, &header.signature));
+    }
+    if apply_fixups(&mut record, &header, src.bytes_per_sector()) == FixupResult::Failed {
+        return Err(common::record_fixups(entry));
+    }
+    Ok(gather_record_buffers(src, entry, record).0)
+}
+
+/// Метка и $VOLUME_INFORMATION из записи $Volume (3); None - запись не читается.
+pub fn volume_info<S: RecordSource>(src: &mut S) -> Option<VolumeInfo> {
+    let buffers = read_record(src, VOLUME_RECORD).ok()?;
+    let mut info = VolumeInfo::default();
+    for attr in attributes(&buffers, src.record_size()) {
+        let Some(value) = attr.resident_value() else { continue };
+        match attr.attr_type {
+            0x60 => {
+                let name: Vec<u16> = value.chunks_exact(2).map(LittleEndian::read_u16).collect();
+                info.label = Some(String::from_utf16_lossy(&name));
+            }
+            // 8 байт резерва, MajorVersion, MinorVersion, Flags
+            0x70 if value.len() >= 12 => {
+                info.ntfs_version = Some(format!("{}.{}", value[8], value[9]));
+                info.volume_flags = LittleEndian::read_u16(&value[10..12]);
+                info.dirty = info.volume_flags & VOLUME_IS_DIRTY != 0;
+            }
+            _ => {}
+        }
+    }
+    Some(info)
+}
+
+/// Атрибут записи: буфер, в котором он лежит, границы и тип.
+pub struct Attribute<'a> {
+    pub buf: &'a [u8],
+    pub offset: usize,
+    pub end: usize,
+    pub attr_type: u32,
+    pub non_resident: bool,
+}
+
+impl Attribute<'_> {
+    pub fn name(&self) -> String { read_attr_name(self.buf, self.offset, self.end) }
+
+    /// Флаги атрибута: 0x0001 - сжат, 0x4000 - зашифрован, 0x8000 - разрежен.
+    pub fn flags(&self) -> u16 { LittleEndian::read_u16(&self.buf[self.offset + 12..self.offset + 14]) }
+
+    pub fn resident_value(&self) -> Option<&[u8]> {
+        if self.non_resident || self.offset + 22 > self.end { return None; }
+        let value_len = LittleEndian::read_u32(&self.buf[self.offset + 16..self.offset + 20]) as usize;
+        let value_off = LittleEndian::read_u16(&self.buf[self.offset + 20..self.offset + 22]) as usize;
+        let start = self.offset.checked_add(value_off)?;
+        self.buf.get(start..start.checked_add(value_len)?.min(self.end))
+    }
+
+    /// Начальный VCN нерезидентного экстента.
+    fn start_vcn(&self) -> Option<u64> {
+        (self.non_resident && self.offset + 0x40 <= self.end)
+            .then(|| LittleEndian::read_u64(&self.buf[self.offset + 16..self.offset + 24]))
+    }
+}
+
+/// Все атрибуты записи по порядку, включая экстенты.
+pub fn attributes<'a>(buffers: &'a [Cow<[u8]>], record_size: usize) -> Vec<Attribute<'a>> {
+    let mut attrs = Vec::new();
+    for buf in buffers {
+        let Some(header) = MftRecordHeader::parse(buf) else { continue };
+        let mut attr_offset = header.first_attribute_offset as usize;
+        let mut used_end = std::cmp::min(header.real_size as usize, record_size);
+        if used_end < attr_offset { used_end = record_size; }
+
+        while attr_offset + 16 <= used_end {
+            let attr_type = LittleEndian::read_u32(&buf[attr_offset..attr_offset + 4]);
+            if attr_type == 0xFFFFFFFF || attr_type == 0 { break; }
+            let attr_len = LittleEndian::read_u32(&buf[attr_offset + 4..attr_offset + 8]) as usize;
+            if attr_len < 16 || attr_offset.saturating_add(attr_len) > used_end { break; }
+            attrs.push(Attribute { buf, offset: attr_offset, end: attr_offset + attr_len, attr_type, non_resident: buf[attr_offset + 8] != 0 });
+            attr_offset += attr_len;
+        }
+    }
+    attrs
+}
+
+/// Ищет имя среди элементов одного узла индекса $I30 (`data[pos..end]`).
+/// Возвращает ссылку на файл (номер записи и sequence).
+fn find_in_node(data: &[u8], mut pos: usize, end: usize, name: &str) -> Option<u64> {
+    let end = end.min(data.len());
+    while pos + 16 <= end {
+        let entry_len = LittleEndian::read_u16(&data[pos + 8..pos + 10]) as usize;
+        let key_len = LittleEndian::read_u16(&data[pos + 10..pos + 12]) as usize;
+        let flags = LittleEndian::read_u32(&data[pos + 12..pos + 16]);
+        if flags & INDEX_ENTRY_LAST != 0 || entry_len < 16 { break; }
+        let key = data.get(pos + 16..(pos + 16 + key_len).min(end))?;
+        if FileNameAttribute::parse(key).is_some_and(|f| f.name.to_lowercase() == name.to_lowercase()) {
+            return Some(LittleEndian::read_u64(&data[pos..pos + 8]));
+        }
+        pos += entry_len;
+    }
+    None
+}
+
+/// Узел индекса $I30: $INDEX_ROOT или блок INDX после fixups. Смещения - от начала `data`:
+/// первый элемент, конец занятой части и конец выделенной (между ними - slack).
+pub struct IndexNode<'a> {
+    pub data: &'a [u8],
+    pub entries: usize,
+    pub used: usize,
+    pub allocated: usize,
+    /// VCN блока в $INDEX_ALLOCATION; None - $INDEX_ROOT
+    pub vcn: Option<u64>,
+}
+
+/// Обходит узлы индекса $I30 каталога `dir`: $INDEX_ROOT, затем все блоки $INDEX_ALLOCATION
+/// по порядку. `f` возвращает true, чтобы прекратить обход.
+pub fn for_each_index_node<R: Read + Seek, F: FnMut(&IndexNode) -> bool>(src: &mut VolumeRecords<R>, dir: u64, mut f: F) -> Result<(), String> {
+    let buffers = read_record(src, dir)?;
+    let attrs = attributes(&buffers, src.layout.record_size);
+    let index = |a: &&Attribute| a.name() == "$I30";
+
+    let root = attrs.iter().filter(index).find(|a| a.attr_type == 0x90).and_then(Attribute::resident_value)
+        .ok_or_else(|| common::not_a_directory(dir))?;
+    if root.len() < 32 { return Err(common::index_root_damaged(dir)); }
+    let block_size = LittleEndian::read_u32(&root[8..12]) as usize;
+    let node = IndexNode {
+        data: root,
+        entries: 16 + LittleEndian::read_u32(&root[16..20]) as usize,
+        used: 16 + LittleEndian::read_u32(&root[20..24]) as usize,
+        allocated: (16 + LittleEndian::read_u32(&root[24..28]) as usize).min(root.len()),
+        vcn: None,
+    };
+    if f(&node) { return Ok(()); }
+
+    let mut runs: Vec<DataRun> = attrs.iter().filter(index).filter(|a| a.attr_type == 0xA0 && a.non_resident)
+        .filter_map(|a| read_nonresident_runs(a.buf, a.offset, a.end))
+        .flatten()
+        .collect();
+    if runs.is_empty() { return Ok(()); }
+    runs.sort_by_key(|r| r.vcn_start);
+    let bpc = src.layout.bytes_per_cluster;
+    let total = runs.iter().map(|r| r.length).sum::<u64>() * bpc;
+    if block_size == 0 || block_size > 64 * 1024 || !block_size.is_power_of_two() {
+        return Err(common::index_block_size(dir, block_size));
+    }
+    let mut block = vec![0u8; block_size];
+    for offset in (0..total).step_by(block_size) {
+        if extract::read_logical(src.vol, &runs, bpc, src.layout.partition_offset, offset, &mut block).is_err() { continue; }
+        if &block[0..4] != b"INDX" { continue; }
+        let usa_offset = LittleEndian::read_u16(&block[4..6]) as usize;
+        let usa_count = LittleEndian::read_u16(&block[6..8]) as usize;
+        if apply_block_fixups(&mut block, usa_offset, usa_count, src.layout.boot.bytes_per_sector) == FixupResult::Failed { continue; }
+        let node = IndexNode {
+            data: &block,
+            entries: 0x18 + LittleEndian::read_u32(&block[0x18..0x1C]) as usize,
+            used: 0x18 + LittleEndian::read_u32(&block[0x1C..0x20]) as usize,
+            allocated: (0x18 + LittleEndian::read_u32(&block[0x20..0x24]) as usize).min(block_size),
+            vcn: Some(LittleEndian::read_u64(&block[0x10..0x18])),
+        };
+        if f(&node) { return Ok(()); }
+    }
+    Ok(())
+}
+
+/// Находит файл `name` в каталоге `dir` перебором всех узлов индекса, без спуска
+/// по B-дереву: порядок сортировки NTFS задается таблицей $UpCase, которой у нас нет.
+pub fn lookup<R: Read + Seek>(src: &mut VolumeRecords<R>, dir: u64, name: &str) -> Result<u64, String> {
+    let mut found = None;
+    for_each_index_node(src, dir, |node| {
+        found = find_in_node(node.data, node.entries, node.used, name);
+        found.is_some()
+    })?;
+    found.ok_or_else(|| common::not_in_directory(name, dir))
+}
+
+/// Разрешает путь внутри тома (`C:\Windows\notepad.exe`, `\Windows\notepad.exe` или
+/// `Windows/notepad.exe`) в ссылку на файл, проходя индексы каталогов от корня.
+pub fn resolve_path<R: Read + Seek>(src: &mut VolumeRecords<R>, path: &str) -> Result<u64, String> {
+    let path = match path.as_bytes() {
+        [letter, b':', ..] if letter.is_ascii_alphabetic() => &path[2..],
+        _ => path,
+    };
+    let mut current = ROOT_RECORD | (ROOT_RECORD << 48);
+    for part in path.split(['\\', '/']).filter(|p| !p.is_empty()) {
+        current = lookup(src, current & ENTRY_MASK, part)?;
+    }
+    Ok(current)
+}
+
+/// Поток $DATA файла: резидентное содержимое или runlist со всех экстентов.
+pub struct DataStream {
+    pub resident: Option<Vec<u8>>,
+    pub runs: Vec<DataRun>,
+    /// Логический размер (real size).
+    pub size: u64,
+    /// Граница записанных данных (valid data length); дальше - нули.
+    pub initialized: u64,
+    pub flags: u16,
+}
+
+/// Поток $DATA с именем `name` ("" - основной поток) из буферов записи.
+pub fn data_stream(buffers: &[Cow<[u8]>], record_size: usize, name: &str) -> Result<DataStream, String> {
+    let label = if name.is_empty() { "$DATA".to_string() } else { format!("$DATA:{}", name) };
+    let parts: Vec<Attribute> = attributes(buffers, record_size).into_iter()
+        .filter(|a| a.attr_type == 0x80 && a.name() == name)
+        .collect();
+    let first = parts.first().ok_or_else(|| common::no_stream(&label))?;
+    if let Some(value) = parts.iter().find_map(Attribute::resident_value) {
+        return Ok(DataStream { resident: Some(value.to_vec()), runs: Vec::new(), size: value.len() as u64, initialized: value.len() as u64, flags: first.flags() });
+    }
+
+    let mut runs: Vec<DataRun> = parts.iter().filter_map(|a| read_nonresident_runs(a.buf, a.offset, a.end)).flatten().collect();
+    runs.sort_by_key(|r| r.vcn_start);
+    // Размеры хранятся только в экстенте с начальным VCN 0
+    let header = parts.iter().find(|a| a.start_vcn() == Some(0))
+        .ok_or_else(|| common::no_stream_header(&label))?;
+    let size = LittleEndian::read_u64(&header.buf[header.offset + 0x30..header.offset + 0x38]);
+    let initialized = LittleEndian::read_u64(&header.buf[header.offset + 0x38..header.offset + 0x40]);
+    Ok(DataStream { resident: None, runs, size, initialized: initialized.min(size), flags: header.flags() })
+}
+
+/// Последовательное чтение нерезидентного потока блоками до 1 МБ. Разреженные участки
+/// либо отдаются нулями, либо (`skip_sparse`) пропускаются целиком - так читается $J,
+/// у которого вытесненное начало занимает гигабайты нулей.
+pub struct StreamReader<'a, R: Read + Seek> {
+    vol: &'a mut R,
+    runs: Vec<DataRun>,
+    bytes_per_cluster: u64,
+    partition_offset: u64,
+    size: u64,
+    initialized: u64,
+    skip_sparse: bool,
+    run_index: usize,
+    pos: u64,
+    chunk: Vec<u8>,
+    chunk_pos: usize,
+}
+
+impl<'a, R: Read + Seek> StreamReader<'a, R> {
+    pub fn new(vol: &'a mut R, layout: &MftLayout, stream: &DataStream, skip_sparse: bool) -> Self {
+        StreamReader {
+            vol, runs: stream.runs.clone(), bytes_per_cluster: layout.bytes_per_cluster, partition_offset: layout.partition_offset,
+            size: stream.size, initialized: stream.initialized, skip_sparse, run_index: 0, pos: 0, chunk: Vec::new(), chunk_pos: 0,
+        }
+    }
+
+    /// Дочитывает следующий блок; false - поток закончился.
+    fn fill(&mut self) -> io::Result<bool> {
+        loop {
+            let Some(run) = self.runs.get(self.run_index) else {
+                if self.pos < self.size && !self.skip_sparse {
+                    return Err(io::Error::new(io::ErrorKind::UnexpectedEof, common::runlist_truncated(self.pos, self.size)));
+                }
+                return Ok(false);
+            };
+            let run_start = run.vcn_start * self.bytes_per_cluster;
+            let run_end = ((run.vcn_start + run.length) * self.bytes_per_cluster).min(self.size);
+            if self.skip_sparse || run.is_sparse {
+                self.pos = self.pos.max(run_start);
+            }
+            if self.pos >= run_end || (run.is_sparse && self.skip_sparse) {
+                self.run_index += 1;
+                continue;
+            }
+            if self.pos < run_start {
+                // Дыра в runlist (экстент не найден) - как разреженный участок
+                return Err(io::Error::new(io::ErrorKind::InvalidData, common::runlist_hole(self.pos)));
+            }
+
+            let len = READ_CHUNK.min(run_end - self.pos);
+            self.chunk.clear();
+            if run.is_sparse {
+                self.chunk.resize(len as usize, 0);
+            } else {
+                // Читаем целыми кластерами (устройства требуют выровненного чтения), лишнее за size отрезаем
+                let physical = self.partition_offset + run.lcn * self.bytes_per_cluster + (self.pos - run_start);
+                self.chunk.resize(len.next_multiple_of(self.bytes_per_cluster) as usize, 0);
+                self.vol.seek(SeekFrom::Start(physical))?;
+                self.vol.read_exact(&mut self.chunk)?;
+                self.chunk.truncate(len as usize);
+            }
+            // За valid data length на диске мусор, Windows отдает там нули
+            if self.pos + len > self.initialized {
+                let from = self.initialized.saturating_sub(self.pos) as usize;
+                self.chunk[from..].fill(0);
+            }
+            self.pos += len;
+            self.chunk_pos = 0;
+            return Ok(true);
+        }
+    }
+}
+
+impl<R: Read + Seek> Read for StreamReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.chunk_pos >= self.chunk.len() && !self.fill()? {
+            return Ok(0);
+        }
+        let n = buf.len().min(self.chunk.len() - self.chunk_pos);
+        buf[..n].copy_from_slice(&self.chunk[self.chunk_pos..self.chunk_pos + n]);
+        self.chunk_pos += n;
+        Ok(n)
+    }
+}
+
+/// Значение нерезидентного атрибута целиком по runlist; атрибуты больше предела не читаются.
+fn read_attribute<R: Read + Seek>(vol: &mut R, layout: &MftLayout, record: &[u8], attr_offset: usize, attr_end: usize) -> Option<Vec<u8>> {
+    let sizes = read_nonresident_sizes(record, attr_offset, attr_end).filter(|s| s.real <= MAX_NONRESIDENT_ATTR)?;
+    let runs = read_nonresident_runs(record, attr_offset, attr_end)?;
+    let stream = DataStream { resident: None, runs, size: sizes.real, initialized: sizes.initialized, flags: 0 };
+    let mut value = Vec::with_capacity(sizes.real as usize);
+    StreamReader::new(vol, layout, &stream, false).read_to_end(&mut value).ok()?;
+    Some(value)
+}
+
+/// Том, с которого снят дамп (parse --image): содержимое нерезидентных $DATA
+/// и нерезидентные $ATTRIBUTE_LIST записей по runlist.
+pub struct SourceVolume<R: Read + Seek> {
+    vol: R,
+    layout: MftLayout,
+}
+
+impl<R: Read + Seek> SourceVolume<R> {
+    pub fn new(vol: R, layout: MftLayout) -> Self {
+        SourceVolume { vol, layout }
+    }
+
+    /// Значение нерезидентного атрибута записи из дампа.
+    pub fn read_attribute(&mut self, record: &[u8], attr_offset: usize, attr_end: usize) -> Option<Vec<u8>> {
+        read_attribute(&mut self.vol, &self.layout, record, attr_offset, attr_end)
+    }
+
+    /// Основной $DATA записи не длиннее `max` байт. None - поток резидентный, больше лимита,
+    /// сжат или зашифрован, либо на томе по этому номеру уже другая запись (не совпал sequence).
+    pub fn read(&mut self, entry: u64, sequence: u16, max: u64) -> Option<Vec<u8>> {
+        let mut src = VolumeRecords { vol: &mut self.vol, layout: &self.layout };
+        let buffers = read_record(&mut src, entry).ok()?;
+        if MftRecordHeader::parse(&buffers[0])?.sequence_number != sequence {
+            return None;
+        }
+        let stream = data_stream(&buffers, self.layout.record_size, "").ok()?;
+        if stream.resident.is_some() || stream.size > max || stream.flags & (ATTR_COMPRESSED | ATTR_ENCRYPTED) != 0 {
+            return None;
+        }
+        let mut content = Vec::with_capacity(stream.size as usize);
+        StreamReader::new(&mut self.vol, &self.layout, &stream, false).read_to_end(&mut content).ok()?;
+        Some(content)
+    }
+}
+
+/// Проход по $MFT тома потоком: дерево путей по всем базовым записям FILE;
+/// `f` получает номер, заголовок и буфер (после fixups, без записей-расширений) каждой из них.
+pub fn index_volume<R: Read + Seek, F: FnMut(u64, &MftRecordHeader, &[Cow<[u8]>])>(vol: &mut R, layout: &MftLayout, mut f: F) -> PathBuilder {
+    let opts = ParseOptions::default();
+    let mut ctx = ParseContext::standalone(&opts, "", String::new(), layout.record_size);
+    let mut path_builder = PathBuilder::new();
+    path_builder.reserve(layout.total_records() as usize);
+
+    let mft = DataStream { resident: None, runs: layout.runs.clone(), size: layout.total_bytes, initialized: layout.total_bytes, flags: 0 };
+    let mut reader = StreamReader::new(vol, layout, &mft, false);
+    let mut record = vec![0u8; layout.record_size];
+    for entry_num in 0..layout.total_records() {
+        if reader.read_exact(&mut record).is_err() { break; }
+        let Some(header) = MftRecordHeader::parse(&record) else { continue };
+        if header.signature != "FILE" || header.base_record_reference != 0 { continue; }
+        if apply_fixups(&mut record, &header, layout.boot.bytes_per_sector) == FixupResult::Failed { continue; }
+        let buffers = [Cow::Borrowed(&record[..])];
+        index_record(&mut ctx, &mut path_builder, entry_num, &header, &buffers);
+        f(entry_num, &header, &buffers);
+    }
+    path_builder
+}
\ No newline at end of file
@@ -0,0 +1,248 @@
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use tracing::{info, warn};
+
+use super::extract::{self, MftLayout};
+use super::parse::{self, build_entry, gather_record_buffers, index_record, ParseContext, ParseOptions};
+use crate::error::{Error, Result};
+use crate::i18n::common;
+use crate::mft::parser::{apply_fixups, FixupResult, RecordSource};
+use crate::mft::path_builder::PathBuilder;
+use crate::mft::record::MftRecordHeader;
+use crate::mft::usn::{parse_records, reason_names};
+use crate::models::{MftEntry, Tags};
+use crate::output::JsonlWriter;
+use crate::rules::ruleset::{RuleSet, RuleSources};
+
+/// Записи MFT, читаемые напрямую с живого тома по runlist $MFT.
+struct LiveVolume {
+    vol: File,
+    layout: MftLayout,
+}
+
+impl RecordSource for LiveVolume {
+    fn record_size(&self) -> usize { self.layout.record_size }
+    fn bytes_per_sector(&self) -> u16 { self.layout.boot.bytes_per_sector }
+    fn total_records(&self) -> u64 { self.layout.total_records() }
+
+    fn fetch_record(&mut self, entry_num: u64) -> Option<Vec<u8>> {
+        // $MFT мог вырасти после запуска - перечитываем runlist
+        if entry_num >= self.layout.total_records() {
+            self.layout = extract::locate_mft(&mut self.vol).ok()?;
+        }
+        self.layout.read_record(&mut self.vol, entry_num).ok()
+    }
+}
+
+/// Перечитывает одну запись с тома, обновляет дерево путей и собирает MftEntry.
+fn reparse_record(live: &mut LiveVolume, ctx: &mut ParseContext, path_builder: &mut PathBuilder, entry_num: u64) -> Option<MftEntry> {
+    let mut record = live.fetch_record(entry_num)?;
+    let header = MftRecordHeader::parse(&record)?;
+    if header.signature == "BAAD" || header.base_record_reference != 0 { return None; }
+
+    let fixup_res = apply_fixups(&mut record, &header, live.bytes_per_sector());
+    if fixup_res == FixupResult::Failed { return None; }
+
+    let (buffers, complex_extents) = gather_record_buffers(live, entry_num, record);
+    index_record(ctx, path_builder, entry_num, &header, &buffers);
+    Some(build_entry(ctx, path_builder, entry_num, &header, &buffers, fixup_res == FixupResult::TornWrite, complex_extents))
+}
+
+/// Первичный extract + parse, затем перечитывание записей, измененных по USN-журналу.
+/// Строки WATCH несут причины изменения в поле UsnReason; с `alerts_only` пишутся
+/// только записи с детектами (правила, timestomp, ADS и т.д.) - режим сенсора.
+/// Файлы правил из `opts` отслеживаются: при их изменении набор перезагружается
+/// между пачками без перезапуска, при ошибке остается прежний.
+pub fn run(image: &str, out_dir: &str, interval: u64, alerts_only: bool, opts: &ParseOptions, tags: &Tags) -> Result<()> {
+    info!("[*] Запуск Watch (мониторинг по USN-журналу)");
+
+    let volume_path = extract::volume_path(image);
+    // Журнал открываем до извлечения, чтобы не потерять изменения, сделанные во время первого разбора
+    let mut journal = journal::UsnJournal::open(&volume_path)
+        .map_err(Error::io(format!("USN-журнал {} недоступен", volume_path)))?;
+
+    if !Path::new(out_dir).exists() {
+        std::fs::create_dir_all(out_dir)
+            .map_err(Error::io(format!("Не удалось создать {}", out_dir)))?;
+    }
+
+    let out_dir = PathBuf::from(out_dir);
+    let mft_path = out_dir.join("MFT");
+    let jsonl_path = out_dir.join("REPORT");
+    let watch_path = out_dir.join("WATCH");
+    let usn_path = out_dir.join("USN");
+
+    let rule_sources = opts.rule_sources();
+    let mut rules_stamp = rule_sources.fingerprint();
+
+    extract::run(image, mft_path.to_string_lossy().as_ref(), &extract::ExtractOptions { audit: true, ..Default::default() }, &opts.case, tags)?;
+    let (mut parser, mut ctx) = parse::run_with_context(
+        mft_path.to_string_lossy().as_ref(),
+        jsonl_path.to_string_lossy().as_ref(),
+        opts,
+    )?;
+    ctx.source_file = volume_path.clone();
+
+    let mut vol = File::open(&volume_path)
+        .map_err(Error::io(format!("Ошибка открытия {}", volume_path)))?;
+    let layout = extract::locate_mft(&mut vol)?;
+    let mut live = LiveVolume { vol, layout };
+
+    let open_append = |path: &Path| OpenOptions::new().create(true).append(true).open(path)
+        .map_err(Error::io(format!("Не удалось открыть {}", path.display())));
+    let mut writer = JsonlWriter::new(BufWriter::new(open_append(&watch_path)?));
+    let mut usn_writer = JsonlWriter::new(BufWriter::new(open_append(&usn_path)?));
+    let (watch_name, usn_name) = (watch_path.display().to_string(), usn_path.display().to_string());
+
+    info!("[*] Ожидание изменений (Ctrl+C для выхода). Поток: {}", watch_path.display());
+    loop {
+        if rule_sources.has_files() {
+            let stamp = rule_sources.fingerprint();
+            if stamp != rules_stamp {
+                rules_stamp = stamp;
+                reload_rules(&rule_sources, &mut ctx);
+            }
+        }
+
+        let raw = journal.read_new()
+            .map_err(Error::io("Ошибка чтения USN-журнала"))?;
+        let records = parse_records(&raw);
+        if records.is_empty() {
+            std::thread::sleep(Duration::from_secs(interval));
+            continue;
+        }
+
+        // Одна запись MFT может встретиться в пачке много раз - разбираем ее однократно
+        let mut changed: BTreeMap<u64, u32> = BTreeMap::new();
+        for rec in &records {
+            *changed.entry(rec.entry).or_default() |= rec.reason;
+            usn_writer.write(rec).map_err(Error::io(common::write_failed(&usn_name)))?;
+        }
+        usn_writer.flush().map_err(Error::io(common::write_failed(&usn_name)))?;
+
+        for (entry_num, reason) in changed {
+            let Some(mut entry) = reparse_record(&mut live, &mut ctx, &mut parser.path_builder, entry_num) else { continue };
+            if ctx.opts.skip_known && entry.known_good { continue; }
+            if alerts_only && !entry.has_detections() { continue; }
+            let reasons = reason_names(reason);
+            info!(" -> [{}] {}", reasons, entry.full_path);
+            entry.plugin_fields.insert("UsnReason".into(), reasons.into());
+            writer.write(&entry).map_err(Error::io(common::write_failed(&watch_name)))?;
+        }
+        writer.flush().map_err(Error::io(common::write_failed(&watch_name)))?;
+    }
+}
+
+/// Собирает набор заново и подменяет правила и исключения разом; записи пачки
+/// проверяются либо старым набором, либо новым целиком.
+fn reload_rules(sources: &RuleSources, ctx: &mut ParseContext) {
+    match sources.load() {
+        Ok(loaded) => {
+            for warning in &loaded.warnings {
+                warn!("{}", warning);
+            }
+            info!("[*] Правила перезагружены: {} правил, {} исключений", loaded.rules.len(), loaded.excludes.len());
+            ctx.rules_list = RuleSet::new(loaded.rules);
+            ctx.exclude_list = RuleSet::new(loaded.excludes);
+        }
+        Err(e) => warn!("[!] Правила не перезагружены, действует прежний набор: {}", e),
+    }
+}
+
+#[cfg(windows)]
+mod journal {
+    use std::fs::File;
+    use std::io;
+    use std::os::windows::io::AsRawHandle;
+
+    use byteorder::{ByteOrder, LittleEndian};
+    use windows_sys::Win32::System::IO::DeviceIoControl;
+
+    const FSCTL_QUERY_USN_JOURNAL: u32 = 0x000900F4;
+    const FSCTL_READ_USN_JOURNAL: u32 = 0x000900BB;
+
+    /// Курсор по живому USN-журналу тома.
+    pub struct UsnJournal {
+        vol: File,
+        journal_id: u64,
+        next_usn: i64,
+    }
+
+    impl UsnJournal {
+        /// Открывает журнал и встает на его текущий конец (NextUsn).
+        pub fn open(volume_path: &str) -> io::Result<Self> {
+            let vol = File::open(volume_path)?;
+            // USN_JOURNAL_DATA_V0: UsnJournalID, FirstUsn, NextUsn, ...
+            let mut data = [0u8; 56];
+            ioctl(&vol, FSCTL_QUERY_USN_JOURNAL, &[], &mut data)?;
+            Ok(Self {
+                journal_id: LittleEndian::read_u64(&data[0..8]),
+                next_usn: LittleEndian::read_i64(&data[16..24]),
+                vol,
+            })
+        }
+
+        /// Сырые записи USN_RECORD, появившиеся с прошлого вызова (без ожидания).
+        pub fn read_new(&mut self) -> io::Result<Vec<u8>> {
+            let mut records = Vec::new();
+            let mut out = vec![0u8; 64 * 1024];
+            loop {
+                // READ_USN_JOURNAL_DATA_V0: StartUsn, ReasonMask, ReturnOnlyOnClose, Timeout, BytesToWaitFor, UsnJournalID
+                let mut input = [0u8; 40];
+                LittleEndian::write_i64(&mut input[0..8], self.next_usn);
+                LittleEndian::write_u32(&mut input[8..12], 0xFFFF_FFFF);
+                LittleEndian::write_u64(&mut input[32..40], self.journal_id);
+
+                let returned = ioctl(&self.vol, FSCTL_READ_USN_JOURNAL, &input, &mut out)?;
+                if returned < 8 { break; }
+
+                records.extend_from_slice(&out[8..returned]);
+
+                let next_usn = LittleEndian::read_i64(&out[0..8]);
+                let advanced = next_usn != self.next_usn;
+                self.next_usn = next_usn;
+                if !advanced || returned == 8 { break; }
+            }
+            Ok(records)
+        }
+    }
+
+    fn ioctl(vol: &File, code: u32, input: &[u8], output: &mut [u8]) -> io::Result<usize> {
+        let mut returned = 0u32;
+        let ok = unsafe {
+            DeviceIoControl(
+                vol.as_raw_handle() as _,
+                code,
+                input.as_ptr() as _,
+                input.len() as u32,
+                output.as_mut_ptr() as _,
+                output.len() as u32,
+                &mut returned,
+                std::ptr::null_mut(),
+            )
+        };
+        if ok == 0 { Err(io::Error::last_os_error()) } else { Ok(returned as usize) }
+    }
+}
+
+#[cfg(not(windows))]
+mod journal {
+    use std::io;
+
+    /// Живой USN-журнал доступен только через FSCTL на Windows.
+    pub struct UsnJournal;
+
+    impl UsnJournal {
+        pub fn open(_volume_path: &str) -> io::Result<Self> {
+            Err(io::Error::new(io::ErrorKind::Unsupported, "watch работает только на живом томе Windows"))
+        }
+
+        pub fn read_new(&mut self) -> io::Result<Vec<u8>> {
+            Ok(Vec::new())
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::Read;
+
+use byteorder::{ByteOrder, LittleEndian};
+use serde::Serialize;
+use tracing::{info, warn};
+
+use super::extract;
+use super::parse::{gather_record_buffers, read_attr_name, read_nonresident_runs};
+use crate::error::{Error, Result};
+use crate::i18n::common;
+use crate::image;
+use crate::mft::attributes::FileNameAttribute;
+use crate::mft::parser::{apply_fixups, FixupResult, MftParser};
+use crate::mft::path_builder::PathBuilder;
+use crate::mft::record::MftRecordHeader;
+use crate::models::MftMeta;
+use crate::output;
+
+/// Участок кластеров, занятый non-resident атрибутом записи.
+struct ClusterExtent {
+    lcn_start: u64,
+    clusters: u64,
+    vcn_start: u64,
+    entry: u64,
+    sequence: u16,
+    in_use: bool,
+    attr_type: u32,
+    attr_name: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct Owner {
+    lcn: u64,
+    entry_number: u64,
+    sequence_number: u16,
+    in_use: bool,
+    attribute: String,
+    /// Смещение кластера внутри потока атрибута, байт
+    stream_offset: u64,
+    #[serde(rename = "Full_Path")]
+    full_path: String,
+}
+
+fn attr_label(attr_type: u32, name: &str) -> String {
+    let base = match attr_type {
+        0x20 => "$ATTRIBUTE_LIST",
+        0x80 => "$DATA",
+        0xA0 => "$INDEX_ALLOCATION",
+        0xB0 => "$BITMAP",
+        0xC0 => "$REPARSE_POINT",
+        0x100 => "$LOGGED_UTILITY_STREAM",
+        _ => return format!("0x{:X}:{}", attr_type, name),
+    };
+    if name.is_empty() { base.to_string() } else { format!("{}:{}", base, name) }
+}
+
+/// Имя записи ($FN, Win32 приоритетнее DOS) и non-resident участки всех ее атрибутов.
+fn scan_record(buffers: &[Cow<[u8]>], record_size: usize, mut on_extent: impl FnMut(u64, u64, u64, u32, String)) -> Option<FileNameAttribute> {
+    let mut best_fn: Option<FileNameAttribute> = None;
+    for buf in buffers {
+        let Some(header) = MftRecordHeader::parse(buf) else { continue };
+        let mut attr_offset = header.first_attribute_offset as usize;
+        let mut used_end = std::cmp::min(header.real_size as usize, record_size);
+        if used_end < attr_offset { used_end = record_size; }
+
+        while attr_offset + 8 <= used_end {
+            let attr_type = LittleEndian::read_u32(&buf[attr_offset..attr_offset + 4]);
+            if attr_type == 0xFFFFFFFF || attr_type == 0 { break; }
+            let attr_len = LittleEndian::read_u32(&buf[attr_offset + 4..attr_offset + 8]) as usize;
+            if attr_len == 0 || attr_offset.saturating_add(attr_len) > used_end { break; }
+            let attr_end = attr_offset + attr_len;
+            let non_resident = buf[attr_offset + 8] != 0;
+
+            if non_resident {
+                if let Some(runs) = read_nonresident_runs(buf, attr_offset, attr_end) {
+                    let name = read_attr_name(buf, attr_offset, attr_end);
+                    for run in runs.iter().filter(|r| !r.is_sparse) {
+                        on_extent(run.lcn, run.length, run.vcn_start, attr_type, name.clone());
+                    }
+                }
+            } else if attr_type == 0x30 && !non_resident && attr_offset + 22 <= attr_end {
+                let value_len = LittleEndian::read_u32(&buf[attr_offset + 16..attr_offset + 20]) as usize;
+                let value_off = LittleEndian::read_u16(&buf[attr_offset + 20..attr_offset + 22]) as usize;
+                let content_end = std::cmp::min(attr_offset.saturating_add(value_off).saturating_add(value_len), attr_end);
+                if let Some(fn_attr) = buf.get(attr_offset.saturating_add(value_off)..content_end).and_then(FileNameAttribute::parse) {
+                    let replace = match best_fn.as_ref() {
+                        None => true,
+                        Some(f) => f.name_type == 2 && fn_attr.name_type != 2,
+                    };
+                    if replace { best_fn = Some(fn_attr); }
+                }
+            }
+            attr_offset = attr_end;
+        }
+    }
+    best_fn
+}
+
+/// Кластеры для поиска: LCN как есть, байтовые смещения - с учетом партиции.
+fn target_clusters(lcns: &[u64], offsets: &[u64], meta: &MftMeta) -> Vec<(u64, u64)> {
+    let bpc = meta.bytes_per_cluster.max(1);
+    let mut targets: Vec<(u64, u64)> = lcns.iter().map(|lcn| (*lcn, 0)).collect();
+    for offset in offsets {
+        let Some(rel) = offset.checked_sub(meta.partition_offset) else {
+            warn!("[!] Смещение {} лежит до начала NTFS-партиции ({})", offset, meta.partition_offset);
+            continue;
+        };
+        targets.push((rel / bpc, rel % bpc));
+    }
+    targets
+}
+
+pub fn run(image: Option<&str>, mft: &str, lcns: &[u64], offsets: &[u64], out_json: Option<&str>) -> Result<()> {
+    info!("[*] Запуск WhoHas");
+
+    // Геометрия тома: с образа, если он указан, иначе из meta.json дампа
+    let meta: MftMeta = match image {
+        Some(image) => {
+            let volume_path = extract::volume_path(image);
+            let mut vol = image::open(&volume_path)
+                .map_err(Error::io(format!("Ошибка открытия {}", volume_path)))?;
+            extract::locate_mft(&mut vol)?.to_meta(&volume_path)
+        }
+        None => {
+            let meta_path = format!("{}.meta.json", mft);
+            File::open(&meta_path).ok()
+                .and_then(|f| serde_json::from_reader(f).ok())
+                .ok_or_else(|| Error::Invalid(format!("Нет {}: укажите --image для определения геометрии тома", meta_path)))?
+        }
+    };
+    let targets = target_clusters(lcns, offsets, &meta);
+    if targets.is_empty() { return Err(Error::Invalid("Не задано ни одного кластера (--lcn или --offset)".into())); }
+
+    let mut parser = MftParser::new(mft, meta.mft_record_size as usize, meta.bytes_per_sector)
+        .map_err(Error::io(format!("Ошибка открытия {}", mft)))?;
+    let total_records = parser.total_records();
+    let record_size = parser.record_size;
+
+    info!("[*] Построение индекса кластеров ({} записей)...", total_records);
+    let mut path_builder = PathBuilder::new();
+    let mut extents: Vec<ClusterExtent> = Vec::new();
+    let mut record = vec![0u8; record_size];
+    for entry_num in 0..total_records {
+        if parser.reader.read_exact(&mut record).is_err() { break; }
+        let Some(header) = MftRecordHeader::parse(&record) else { continue };
+        if header.signature == "BAAD" || header.base_record_reference != 0 { continue; }
+        if apply_fixups(&mut record, &header, parser.bytes_per_sector) == FixupResult::Failed { continue; }
+
+        let (buffers, _) = gather_record_buffers(&mut parser, entry_num, &record[..]);
+        let best_fn = scan_record(&buffers, record_size, |lcn_start, clusters, vcn_start, attr_type, attr_name| {
+            extents.push(ClusterExtent {
+                lcn_start, clusters, vcn_start, entry: entry_num, sequence: header.sequence_number,
+                in_use: header.is_in_use(), attr_type, attr_name,
+            });
+        });
+        if let Some(fn_attr) = best_fn {
+            let parent = fn_attr.parent_directory_reference;
+            path_builder.add_entry(entry_num, header.sequence_number, header.is_in_use(), parent & 0xFFFFFFFFFFFF, (parent >> 48) as u16, fn_attr.name);
+        }
+    }
+
+    // Интервальный индекс: участки по началу + максимальная длина для ограничения поиска назад.
+    // Кластеры удаленных файлов могут быть уже заняты новыми - возвращаются все владельцы.
+    extents.sort_by_key(|e| e.lcn_start);
+    let max_len = extents.iter().map(|e| e.clusters).max().unwrap_or(0);
+    info!("[*] Участков в индексе: {}", extents.len());
+
+    let mut owners: Vec<Owner> = Vec::new();
+    for (lcn, in_cluster) in targets {
+        let end = extents.partition_point(|e| e.lcn_start <= lcn);
+        let lower = lcn.saturating_sub(max_len);
+        let before = owners.len();
+        for ext in extents[..end].iter().rev().take_while(|e| e.lcn_start >= lower) {
+            if lcn >= ext.lcn_start + ext.clusters { continue; }
+            let vcn = ext.vcn_start + (lcn - ext.lcn_start);
+            owners.push(Owner {
+                lcn,
+                entry_number: ext.entry,
+                sequence_number: ext.sequence,
+                in_use: ext.in_use,
+                attribute: attr_label(ext.attr_type, &ext.attr_name),
+                stream_offset: vcn * meta.bytes_per_cluster + in_cluster,
+                full_path: path_builder.get_full_path(ext.entry, ext.sequence),
+            });
+        }
+        if owners.len() == before {
+            info!("\n[-] LCN {}: владелец не найден (свободный кластер или резидентные данные)", lcn);
+            continue;
+        }
+        info!("\n[+] LCN {}:", lcn);
+        for o in &owners[before..] {
+            info!("    {} #{}-{} {} +{} {}", if o.in_use { "   " } else { "DEL" },
+                o.entry_number, o.sequence_number, o.attribute, o.stream_offset, o.full_path);
+        }
+    }
+
+    if let Some(out_json) = out_json {
+        output::write_jsonl_file(out_json, &owners).map_err(Error::io(common::write_failed(out_json)))?;
+        info!("\n[+] Результат сохранен: {}", out_json);
+    }
+    Ok(())
+}
\ No newline at end of file
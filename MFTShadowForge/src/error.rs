@@ -0,0 +1,87 @@
+use std::io;
+
+/// Ошибка команды. Команды и API библиотеки возвращают ее вместо завершения процесса;
+/// сообщение и код выхода ([`Error::exit_code`]) выбирает main.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// Чтение или запись файла, тома, сетевого источника
+    #[error("{context}: {source}")]
+    Io {
+        context: String,
+        #[source]
+        source: io::Error,
+    },
+    /// На томе или выбранном разделе нет NTFS (в том числе раздел BitLocker без ключа)
+    #[error("{0}")]
+    NoNtfs(String),
+    /// Структуры NTFS (VBR, записи MFT, runlist) не прошли проверку
+    #[error("{0}")]
+    Corrupt(String),
+    /// Недопустимые аргументы или входные данные (отчеты, правила, метки)
+    #[error("{0}")]
+    Invalid(String),
+    /// Функция не включена в сборку или недоступна на этой платформе
+    #[error("{0}")]
+    Unsupported(String),
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Коды выхода CLI - контракт для скриптов и оркестрации (см. README, «Коды выхода»).
+pub mod exit_code {
+    /// Команда выполнена полностью
+    pub const OK: i32 = 0;
+    /// Отчет записан, но часть записей пропущена (BAAD, ошибка fixup) или приемник отчета отключен
+    pub const PARTIAL: i32 = 2;
+    /// [`super::Error::NoNtfs`]
+    pub const NO_NTFS: i32 = 3;
+    /// [`super::Error::Io`]
+    pub const IO: i32 = 4;
+    /// [`super::Error::Corrupt`]
+    pub const CORRUPT: i32 = 5;
+    /// [`super::Error::Invalid`] и ошибки разбора командной строки
+    pub const INVALID: i32 = 6;
+    /// [`super::Error::Unsupported`]
+    pub const UNSUPPORTED: i32 = 7;
+}
+
+/// Итог успешно завершенной команды.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Outcome {
+    #[default]
+    Complete,
+    /// Часть записей пропущена или часть вывода потеряна
+    Partial,
+}
+
+impl Outcome {
+    pub fn exit_code(self) -> i32 {
+        match self {
+            Outcome::Complete => exit_code::OK,
+            Outcome::Partial => exit_code::PARTIAL,
+        }
+    }
+}
+
+impl Error {
+    /// Для `map_err`: ошибка ввода-вывода с описанием действия (`"Не удалось создать out.raw"`).
+    /// `io::ErrorKind::Unsupported` (нет функции сборки, не та платформа - например, USN-журнал
+    /// не на Windows) становится [`Error::Unsupported`], чтобы код выхода был 7, а не 4.
+    pub fn io(context: impl Into<String>) -> impl FnOnce(io::Error) -> Self {
+        let context = context.into();
+        move |source| match source.kind() {
+            io::ErrorKind::Unsupported => Self::Unsupported(format!("{}: {}", context, source)),
+            _ => Self::Io { context, source },
+        }
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::Io { .. } => exit_code::IO,
+            Error::NoNtfs(_) => exit_code::NO_NTFS,
+            Error::Corrupt(_) => exit_code::CORRUPT,
+            Error::Invalid(_) => exit_code::INVALID,
+            Error::Unsupported(_) => exit_code::UNSUPPORTED,
+        }
+    }
+}
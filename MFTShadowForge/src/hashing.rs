@@ -0,0 +1,40 @@
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+/// Алгоритмы хеширования, поддерживаемые для резидентного $DATA.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HashAlgo {
+    Md5,
+    Sha1,
+    Sha256,
+}
+
+impl HashAlgo {
+    /// Определяет алгоритм по длине hex-строки (32/40/64 символа).
+    pub fn from_hex_len(len: usize) -> Option<Self> {
+        match len {
+            32 => Some(HashAlgo::Md5),
+            40 => Some(HashAlgo::Sha1),
+            64 => Some(HashAlgo::Sha256),
+            _ => None,
+        }
+    }
+
+    /// Хеш в нижнем регистре (hex).
+    pub fn digest_hex(&self, data: &[u8]) -> String {
+        match self {
+            HashAlgo::Md5 => to_hex(&Md5::digest(data)),
+            HashAlgo::Sha1 => to_hex(&Sha1::digest(data)),
+            HashAlgo::Sha256 => to_hex(&Sha256::digest(data)),
+        }
+    }
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    let mut s = String::with_capacity(bytes.len() * 2);
+    for b in bytes {
+        s.push_str(&format!("{:02x}", b));
+    }
+    s
+}
@@ -0,0 +1,194 @@
+//! Общие сообщения: ошибка верхнего уровня, строка хода, описание тома, ошибки образов
+//! дисков, чтения тома и mTLS - их видят и extract, и parse.
+
+use std::fmt::Display;
+
+messages! {
+    critical_error(e: impl Display) => "[!] FATAL ERROR: {e}", "[!] КРИТИЧЕСКАЯ ОШИБКА: {e}";
+    open_failed(path: impl Display) => "Failed to open {path}", "Ошибка открытия {path}";
+    create_failed(path: impl Display) => "Failed to create {path}", "Не удалось создать {path}";
+    write_failed(path: impl Display) => "Failed to write {path}", "Не удалось записать {path}";
+
+    volume(info: impl Display) => "[*] Volume: {info}", "[*] Том: {info}";
+    volume_describe(label: &str, version: &str, flags: u16, dirty: &str) =>
+        "label \"{label}\", NTFS {version}, flags 0x{flags:04X}{dirty}",
+        "метка \"{label}\", NTFS {version}, флаги 0x{flags:04X}{dirty}";
+
+    progress_line(label: &str, percent: f64, done: &str, total: &str, rate: &str, eta: &str) =>
+        "[*] {label}: {percent:5.1}% {done}/{total}, {rate}, {eta} left",
+        "[*] {label}: {percent:5.1}% {done}/{total}, {rate}, осталось {eta}";
+    progress_records(count: u64) => "{count} records", "{count} записей";
+    progress_records_rate(rate: f64) => "{rate:.0} records/s", "{rate:.0} записей/с";
+    progress_mib(mib: &str) => "{mib} MB", "{mib} МБ";
+    progress_mib_rate(mib: &str) => "{mib} MB/s", "{mib} МБ/с";
+    stage_finished(label: &str, secs: f64) => "[*] {label} finished in {secs:.2} s", "[*] {label} завершен за {secs:.2} с";
+
+    audit_failed(path: impl Display, e: impl Display) =>
+        "[!] Failed to write the audit log {path}: {e}", "[!] Не удалось записать журнал действий {path}: {e}";
+    temp_name_exhausted() =>
+        "no free name left in the temporary directory", "не удалось подобрать свободное имя во временном каталоге";
+
+    // Образы дисков
+    seek_before_start() => "seek before the start of the image", "seek за начало образа";
+    ewf_image(segments: usize, size: u64) =>
+        "[*] EWF image: {segments} segments, media size {size} bytes", "[*] Образ EWF: сегментов {segments}, размер носителя {size} байт";
+    vhdx_image(size: u64, blocks: usize) =>
+        "[*] VHDX image: disk size {size} bytes, {blocks} blocks allocated", "[*] Образ VHDX: размер диска {size} байт, выделено блоков {blocks}";
+    vmdk_image(kind: impl Display, extents: usize, size: u64) =>
+        "[*] VMDK image ({kind}): {extents} extents, disk size {size} bytes", "[*] Образ VMDK ({kind}): экстентов {extents}, размер диска {size} байт";
+    vhd_image(kind: impl Display, size: u64) =>
+        "[*] VHD image ({kind}): disk size {size} bytes", "[*] Образ VHD ({kind}): размер диска {size} байт";
+    differencing_unsupported(format: &str) =>
+        "Differencing {format} is not supported: merge it with its parent disk",
+        "Разностный (differencing) {format} не поддерживается: объедините его с родительским диском";
+
+    ewf_segment(path: impl Display, e: impl Display) => "segment {path}: {e}", "сегмент {path}: {e}";
+    ewf_segment_name(index: usize, path: impl Display) =>
+        "Cannot derive the name of segment {index} for {path}", "Не удается вывести имя сегмента {index} для {path}";
+    ewf_no_volume() => "The image has no volume/disk section", "В образе нет секции volume/disk";
+    ewf_no_chunks() => "The image has no chunk tables", "В образе нет таблиц чанков";
+    ewf_not_e01(segment: usize) => "Segment {segment} is not EWF-E01", "Сегмент {segment} не является EWF-E01";
+    ewf_chunk_inflate(index: usize, e: impl Display) => "Chunk {index} does not decompress: {e}", "Чанк {index} не распаковывается: {e}";
+    ewf_beyond_chunks(offset: u64) => "Offset {offset} is beyond the chunk tables", "Смещение {offset} за пределами таблиц чанков";
+    ewf_chunk_short(index: usize) => "Chunk {index} is shorter than expected", "Чанк {index} короче ожидаемого";
+
+    vhd_no_footer() => "VHD footer not found", "Футер VHD не найден";
+    vhd_no_dynamic_header() => "Dynamic VHD header not found", "Заголовок динамического VHD не найден";
+    vhd_bad_block_size(size: u64) => "Invalid VHD block size: {size}", "Некорректный размер блока VHD: {size}";
+    vhd_unknown_type(kind: u32) => "Unknown VHD type: {kind}", "Неизвестный тип VHD: {kind}";
+
+    vhdx_no_headers() => "VHDX headers not found", "Заголовки VHDX не найдены";
+    vhdx_log_not_empty() =>
+        "[!] VHDX log is not empty (the disk was not closed cleanly): changes from the log are ignored",
+        "[!] Журнал VHDX не пуст (диск не был корректно закрыт): изменения из журнала не учитываются";
+    vhdx_no_region_table() => "VHDX region table not found", "Таблица регионов VHDX не найдена";
+    vhdx_no_bat() => "VHDX has no BAT region", "В VHDX нет региона BAT";
+    vhdx_no_metadata_region() => "VHDX has no metadata region", "В VHDX нет региона метаданных";
+    vhdx_no_metadata_header() => "VHDX metadata header not found", "Заголовок метаданных VHDX не найден";
+    vhdx_missing_item(item: &str) => "VHDX has no {item}", "В VHDX нет {item}";
+    vhdx_bad_parameters(block: u64, sector: u64) =>
+        "Invalid VHDX parameters: block {block}, sector {sector}", "Некорректные параметры VHDX: блок {block}, сектор {sector}";
+
+    vmdk_no_sparse_header() => "VMDK sparse extent header not found", "Заголовок sparse-экстента VMDK не найден";
+    vmdk_no_footer() => "streamOptimized VMDK footer not found", "Футер streamOptimized VMDK не найден";
+    vmdk_no_directory() => "VMDK footer has no grain directory offset", "В футере VMDK нет смещения каталога грейнов";
+    vmdk_differencing() =>
+        "Differencing VMDK (snapshot with parentCID) is not supported: merge it with its parent disk",
+        "Разностный VMDK (снимок с parentCID) не поддерживается: объедините его с родительским диском";
+    vmdk_descriptor_too_large() => "File is too large for a VMDK descriptor", "Файл слишком велик для дескриптора VMDK";
+    vmdk_descriptor_not_text() => "VMDK descriptor is not text", "Дескриптор VMDK не является текстом";
+    vmdk_no_extents() => "No extents found in the VMDK", "В VMDK не найдено ни одного экстента";
+    vmdk_bad_extent_line(line: &str) => "Invalid extent line: {line}", "Некорректная строка экстента: {line}";
+    vmdk_extent(path: impl Display, e: impl Display) => "extent {path}: {e}", "экстент {path}: {e}";
+    vmdk_bad_extent_offset(line: &str) => "Invalid extent offset: {line}", "Некорректное смещение экстента: {line}";
+    vmdk_extent_type(kind: &str) => "VMDK extent type {kind} is not supported", "Тип экстента VMDK {kind} не поддерживается";
+    vmdk_bad_sparse(grain: u64, gtes: u64) =>
+        "Invalid sparse extent parameters: grain {grain}, GTE {gtes}", "Некорректные параметры sparse-экстента: грейн {grain}, GTE {gtes}";
+    vmdk_grain_inflate(sector: u64, e: impl Display) =>
+        "Grain at sector {sector} does not decompress: {e}", "Грейн в секторе {sector} не распаковывается: {e}";
+
+    recovery_group_invalid(group: &str) =>
+        "invalid recovery key group: {group}", "некорректная группа ключа восстановления: {group}";
+    recovery_group_check(index: usize, group: &str) =>
+        "recovery key group {index} fails the check: {group}", "группа {index} ключа восстановления не проходит проверку: {group}";
+    bitlocker_empty_key() => "empty BitLocker key", "пустой ключ BitLocker";
+    bek_no_key(path: impl Display) => "{path} has no BitLocker external key", "В {path} нет внешнего ключа BitLocker";
+    fvek_short(method: u32) =>
+        "FVEK is shorter than expected for method {method:#06X}", "Ключ FVEK короче ожидаемого для метода {method:#06X}";
+    aes_key_length() => "Invalid AES key length", "Некорректная длина ключа AES";
+    bitlocker_method(method: u32) =>
+        "BitLocker encryption method {method:#06X} is not supported", "Метод шифрования BitLocker {method:#06X} не поддерживается";
+    bitlocker_no_header() =>
+        "BitLocker volume header not found (Windows 7 and later volumes are supported)",
+        "Заголовок тома BitLocker не найден (поддерживаются тома Windows 7 и новее)";
+    bitlocker_sector_size(size: u64) => "Invalid BitLocker sector size: {size}", "Некорректный размер сектора BitLocker: {size}";
+    fve_unreadable() => "BitLocker FVE metadata is unreadable", "Метаданные FVE BitLocker не читаются";
+    bitlocker_wrong_key() =>
+        "The BitLocker key matches none of the volume protectors", "Ключ BitLocker не подходит ни к одному предохранителю тома";
+    bitlocker_key_required() =>
+        "The volume is BitLocker-encrypted: pass --bitlocker-key (recovery key, password or .BEK file)",
+        "Том зашифрован BitLocker: укажите --bitlocker-key (ключ восстановления, пароль или файл .BEK)";
+    fvek_missing() => "BitLocker metadata has no FVEK", "В метаданных BitLocker нет FVEK";
+    fvek_undecryptable() => "FVEK does not decrypt with the VMK", "FVEK не расшифровывается ключом VMK";
+    bitlocker_not_ntfs() =>
+        "The decrypted boot sector is not NTFS: wrong key or encryption method",
+        "После расшифровки загрузочный сектор не NTFS: ключ или метод шифрования не подходят";
+    fve_block_missing() => "FVE metadata block not found", "Блок метаданных FVE не найден";
+    fve_bad_size(size: usize) => "Invalid FVE metadata size: {size}", "Некорректный размер метаданных FVE: {size}";
+
+    #[cfg(windows)]
+    vss_access(device: impl Display, e: impl Display) =>
+        "{device}: {e} (administrator rights required)", "{device}: {e} (нужны права администратора)";
+    #[cfg(not(windows))]
+    vss_unsupported() =>
+        "shadow copies (VSS) are only available on live Windows", "теневые копии (VSS) доступны только на живой Windows";
+
+    // Чтение записей и потоков тома
+    record_unreadable(entry: u64) => "record {entry} could not be read", "запись {entry} не прочитана";
+    record_damaged(entry: u64) => "record {entry} is damaged", "запись {entry} повреждена";
+    record_signature(entry: u64, signature: impl Display) => "record {entry}: signature {signature}", "запись {entry}: сигнатура {signature}";
+    record_fixups(entry: u64) => "record {entry} fixups failed", "fixups записи {entry} не применились";
+    not_a_directory(entry: u64) => "record {entry} is not a directory (no $I30 index)", "запись {entry} - не каталог (нет индекса $I30)";
+    index_root_damaged(entry: u64) => "$INDEX_ROOT of record {entry} is damaged", "$INDEX_ROOT записи {entry} поврежден";
+    index_block_size(entry: u64, size: usize) =>
+        "invalid index block size of record {entry}: {size}", "некорректный размер блока индекса записи {entry}: {size}";
+    not_in_directory(name: impl Display, dir: u64) =>
+        "{name} not found in the directory (record {dir})", "{name} не найден в каталоге (запись {dir})";
+    no_stream(label: impl Display) => "no stream {label}", "нет потока {label}";
+    no_stream_header(label: impl Display) => "no {label} header with VCN 0", "не найден заголовок {label} с VCN 0";
+    runlist_truncated(pos: u64, size: u64) => "runlist ends at byte {pos} of {size}", "runlist обрывается на {pos} байте из {size}";
+    runlist_hole(pos: u64) => "runlist has no data for offset {pos}", "в runlist нет данных для смещения {pos}";
+
+    // Агент и mTLS (extract --connect / --listen, collect)
+    #[cfg(feature = "agent")]
+    agent_error(e: impl Display) => "agent: {e}", "агент: {e}";
+    #[cfg(feature = "agent")]
+    agent_no_hello() => "the agent sent no greeting", "агент не прислал приветствие";
+    #[cfg(feature = "agent")]
+    agent_bad_reply() => "invalid agent reply", "некорректный ответ агента";
+    #[cfg(feature = "agent")]
+    agent_closed() => "the agent closed the connection", "агент закрыл соединение";
+    #[cfg(feature = "agent")]
+    agent_no_size() => "the agent did not report the device size", "агент не сообщил размер устройства";
+    #[cfg(feature = "agent")]
+    beyond_device() => "offset is beyond the device", "смещение за пределами устройства";
+    #[cfg(feature = "agent")]
+    request_too_large(len: u64, max: u64) =>
+        "request of {len} bytes exceeds the limit ({max})", "запрос {len} байт больше допустимого ({max})";
+    #[cfg(feature = "agent")]
+    device_read_failed(offset: u64, e: impl Display) => "read {offset:#X}: {e}", "чтение {offset:#X}: {e}";
+    #[cfg(feature = "agent")]
+    serving(device: impl Display, addr: impl Display) =>
+        "[*] Serving {device} on {addr} (mTLS, read-only)", "[*] Раздача {device} на {addr} (mTLS, только чтение)";
+    #[cfg(feature = "agent")]
+    incoming_failed(e: impl Display) => "[!] Incoming connection failed: {e}", "[!] Ошибка входящего соединения: {e}";
+    #[cfg(feature = "agent")]
+    controller_connected(peer: impl Display) => "[*] Controller connected: {peer}", "[*] Подключение контроллера: {peer}";
+    #[cfg(feature = "agent")]
+    session_finished(peer: impl Display, bytes: u64) =>
+        "[*] Session {peer} finished: {bytes} bytes sent", "[*] Сеанс {peer} завершен: передано {bytes} байт";
+    #[cfg(feature = "agent")]
+    session_aborted(peer: impl Display, e: impl Display) => "[!] Session {peer} aborted: {e}", "[!] Сеанс {peer} прерван: {e}";
+    #[cfg(feature = "agent")]
+    connection_closed() => "connection closed", "соединение закрыто";
+    #[cfg(feature = "agent")]
+    certs_unreadable(path: impl Display, e: impl Display) =>
+        "Failed to read certificates {path}: {e}", "Не удалось прочитать сертификаты {path}: {e}";
+    #[cfg(feature = "agent")]
+    no_certs(path: impl Display) => "{path} contains no certificates", "В {path} нет сертификатов";
+    #[cfg(feature = "agent")]
+    key_unreadable(path: impl Display, e: impl Display) => "Failed to read key {path}: {e}", "Не удалось прочитать ключ {path}: {e}";
+    #[cfg(feature = "agent")]
+    bad_ca(path: impl Display, e: impl Display) => "Invalid CA certificate in {path}: {e}", "Некорректный CA-сертификат в {path}: {e}";
+    #[cfg(feature = "agent")]
+    client_verifier(e: impl Display) =>
+        "Failed to set up client certificate verification: {e}", "Не удалось настроить проверку клиентских сертификатов: {e}";
+    #[cfg(feature = "agent")]
+    tls_config(e: impl Display) => "Invalid TLS configuration: {e}", "Некорректная TLS-конфигурация: {e}";
+    #[cfg(feature = "agent")]
+    bad_host(host: impl Display, e: impl Display) => "Invalid host name {host}: {e}", "Некорректное имя хоста {host}: {e}";
+    #[cfg(feature = "agent")]
+    connect_failed(addr: impl Display) => "Failed to connect to {addr}", "Не удалось подключиться к {addr}";
+    #[cfg(feature = "agent")]
+    tls_failed() => "TLS error", "Ошибка TLS";
+}
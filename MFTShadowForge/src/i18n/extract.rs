@@ -0,0 +1,168 @@
+//! Сообщения extract.
+
+use std::fmt::Display;
+
+messages! {
+    started() => "[*] Starting Extract (strict DFIR mode)", "[*] Запуск Extract (Strict DFIR Mode)";
+    started_listen() => "[*] Starting Extract (serving the device)", "[*] Запуск Extract (раздача устройства)";
+    started_shadows() => "[*] Starting Extract from shadow copies (VSS)", "[*] Запуск Extract из теневых копий (VSS)";
+    started_memory() => "[*] Starting Extract (in memory)", "[*] Запуск Extract (в память)";
+    source(source: impl Display) => " -> Source: {source}", " -> Источник: {source}";
+    output(out: impl Display) => " -> Output file: {out}", " -> Выходной файл: {out}";
+    tee(path: impl Display) => " -> Dump copy: {path}", " -> Копия дампа: {path}";
+
+    #[cfg(feature = "agent")]
+    remote_device(device: impl Display, addr: impl Display, size: impl Display) =>
+        "[*] Remote device {device} on {addr}: size {size}",
+        "[*] Удаленное устройство {device} на {addr}: размер {size}";
+    #[cfg(feature = "agent")]
+    remote_size(bytes: u64) => "{bytes} bytes", "{bytes} байт";
+    #[cfg(feature = "agent")]
+    remote_size_unknown() => "unknown", "неизвестен";
+
+    no_partition_table(path: impl Display, fs: impl Display) =>
+        "[*] No partition table: {path} is an unpartitioned volume (file system: {fs})",
+        "[*] Таблица разделов не найдена: {path} - том без разметки (файловая система: {fs})";
+    partitions(path: impl Display) => "[*] Partitions of {path}:", "[*] Разделы {path}:";
+    partitions_header() =>
+        "  #  Table           Offset             Size  Type                                   FS         Name",
+        "  №  Схема         Смещение           Размер  Тип                                    ФС         Имя";
+    bitlocker_unlocked(offset: u64, method: impl Display) =>
+        "[*] BitLocker partition (offset {offset:#X}, {method}) decrypted",
+        "[*] Раздел BitLocker (смещение {offset:#X}, {method}) расшифрован";
+    shadows(volume: impl Display, serial: u64, count: usize) =>
+        "[*] Shadow copies of volume {volume} (serial number {serial:016X}): {count}",
+        "[*] Теневые копии тома {volume} (серийный номер {serial:016X}): {count}";
+    shadow(index: u32, device: impl Display, out: impl Display) =>
+        "\n[*] Snapshot {index}: {device} -> {out}", "\n[*] Снимок {index}: {device} -> {out}";
+
+    metadata(offset: u64) => "[+] Metadata (offset {offset:#X}):", "[+] Метаданные (смещение {offset:#X}):";
+    mft_location(lcn: u64, offset: u64, cluster: u64) =>
+        "[*] $MFT: LCN {lcn}, offset {offset:#X}, cluster {cluster} bytes",
+        "[*] $MFT: LCN {lcn}, смещение {offset:#X}, кластер {cluster} байт";
+    strict(bytes: u64) => "[*] Extraction: strict mode, size {bytes} bytes", "[*] Извлечение: Строгий режим, размер {bytes} байт";
+    threads(fragments: usize, threads: usize) =>
+        "[*] $MFT in {fragments} fragments: reading with {threads} threads",
+        "[*] $MFT из {fragments} фрагментов: чтение в {threads} потоков";
+    holes(mib: u64) =>
+        "[*] Zero ranges were skipped instead of written (sparse file): {mib} MB",
+        "[*] Нулевые участки не записаны, а пропущены (разреженный файл): {mib} МБ";
+    extracted(mib: u64) => "[+] Successfully extracted: {mib} MB.", "[+] Успешно извлечено: {mib} МБ.";
+    layout(fragments: usize, start_percent: f64) =>
+        "[+] $MFT layout: {fragments} fragments, starts at {start_percent:.1}% of the volume",
+        "[+] Раскладка $MFT: фрагментов {fragments}, начало на {start_percent:.1}% тома";
+    stage() => "Extraction", "Извлечение";
+
+    layout_sparse(clusters: u64) =>
+        "$MFT has sparse ranges ({clusters} clusters)", "$MFT содержит разреженные участки ({clusters} кластеров)";
+    layout_fragmented(fragments: usize) =>
+        "Heavy $MFT fragmentation: {fragments} fragments", "Сильная фрагментация $MFT: {fragments} фрагментов";
+    layout_late_start(percent: f64) =>
+        "$MFT starts in the second half of the volume ({percent:.1}%)", "$MFT начинается во второй половине тома ({percent:.1}%)";
+    layout_mirror_inside(lcn: u64) =>
+        "$MFTMirr (LCN {lcn}) lies inside $MFT", "$MFTMirr (LCN {lcn}) лежит внутри $MFT";
+
+    partition_not_found(index: usize) =>
+        "Partition {index} is not in the partition table (see --list-partitions)",
+        "Раздел {index} не найден в таблице разделов (см. --list-partitions)";
+    no_ntfs_at(offset: u64, fs: impl Display) =>
+        "No NTFS on the partition at offset {offset:#X} (file system: {fs})",
+        "На разделе по смещению {offset:#X} нет NTFS (файловая система: {fs})";
+    bitlocker_locked() =>
+        "The partition is BitLocker-encrypted: extract $MFT with extract --bitlocker-key",
+        "Раздел зашифрован BitLocker: снимите $MFT через extract --bitlocker-key";
+    ntfs_not_found(e: impl Display) => "No NTFS partition found: {e}", "Не удалось найти NTFS партицию: {e}";
+    shadow_not_found(index: u32, volume: impl Display) =>
+        "Shadow copy {index} is not among the snapshots of volume {volume}",
+        "Теневая копия {index} не найдена среди снимков тома {volume}";
+    no_shadows() => "The volume has no shadow copies", "У тома нет теневых копий";
+    #[cfg(not(feature = "agent"))]
+    connect_unsupported() =>
+        "Remote reading (--connect) is unavailable: built without the agent feature",
+        "Удаленное чтение (--connect) недоступно: сборка без функции agent";
+    #[cfg(not(feature = "agent"))]
+    listen_unsupported() =>
+        "Serving a device (--listen) is unavailable: built without the agent feature",
+        "Раздача устройства (--listen) недоступна: сборка без функции agent";
+    #[cfg(feature = "agent")]
+    connect_failed(addr: impl Display) => "Failed to connect to agent {addr}", "Ошибка подключения к агенту {addr}";
+    #[cfg(feature = "agent")]
+    listen_failed(addr: impl Display) => "Failed to listen on {addr}", "Не удалось слушать {addr}";
+    bitlocker_partition(offset: u64) => "BitLocker partition (offset {offset:#X})", "Раздел BitLocker (смещение {offset:#X})";
+    shadows_list_failed() => "Failed to enumerate shadow copies", "Не удалось перечислить теневые копии";
+    disk_seek_failed(offset: u64) => "Failed to seek to physical offset {offset}", "Ошибка seek на физический offset {offset}";
+    disk_read_short(offset: u64) => "Short read from disk at offset {offset}", "Недочитка байтов с диска по смещению {offset}";
+    dump_write_failed() => "Failed to write the dump file", "Ошибка записи в файл дампа";
+    extracted_mismatch(extracted: u64, expected: u64) =>
+        "Extracted {extracted} bytes, expected {expected}.", "Извлечено {extracted} байт, ожидалось {expected}.";
+
+    // Проверка VBR и сборка runlist $MFT. Имена полей и выражения в `what`/`location`
+    // (real_size, ext_record 42, non-resident $ATTRIBUTE_LIST) не переводятся.
+    ntfs_scan_failed() =>
+        "no NTFS boot sector on the volume or in its MBR/EBR/GPT partitions",
+        "NTFS не найден ни на томе, ни в разделах MBR/EBR/GPT";
+    bad_bytes_per_sector(value: impl Display) => "Invalid bytes_per_sector: {value}", "Некорректный bytes_per_sector: {value}";
+    bad_sectors_per_cluster(value: impl Display) => "Invalid sectors_per_cluster: {value}", "Некорректный sectors_per_cluster: {value}";
+    zero_cluster_size() => "bytes_per_cluster is 0", "bytes_per_cluster равен 0";
+    zero_mft_lcn() => "mft_lcn is 0", "mft_lcn равен 0";
+    unknown_record_size() => "Cannot determine file_record_size", "Не удалось определить file_record_size";
+    bad_record_size(value: usize) => "Invalid record_size: {value}", "Некорректный record_size: {value}";
+    record0_signature(found: impl Display) =>
+        "Record 0 must have the FILE signature, found: {found}", "Record 0 обязан иметь сигнатуру FILE, найдено: {found}";
+    extent_signature(found: impl Display) =>
+        "An extent must have the FILE signature, found: {found}", "Экстент обязан иметь сигнатуру FILE, найдено: {found}";
+    real_size_below_header() =>
+        "real_size is below the minimum MFT header size (48 bytes)", "real_size меньше минимального размера заголовка MFT (48 байт)";
+    first_attribute_out_of_record() =>
+        "first_attribute_offset is at or beyond record_size", "first_attribute_offset выходит за пределы (или равен) record_size";
+    real_size_out_of_record() => "real_size exceeds record_size", "real_size выходит за пределы record_size";
+    real_size_no_attributes() => "real_size is too small to hold attributes", "real_size слишком мал для хранения атрибутов";
+    overflow(what: &str) => "Arithmetic overflow: {what}", "Переполнение: {what}";
+    vcn_not_mapped(vcn: u64) => "VCN {vcn} is not in the base runs", "VCN {vcn} не найден в базовых runs при чтении экстента";
+    seek_failed(e: impl Display) => "seek failed: {e}", "Ошибка seek: {e}";
+    read_failed(e: impl Display) => "read failed: {e}", "Ошибка read_exact: {e}";
+    bad_run_header(len: usize, off: usize) => "Invalid nibble sizes: len={len}, off={off}", "Некорректные размеры нибблов: len={len}, off={off}";
+    runs_out_of_attribute() => "Data runs extend past the attribute", "Data runs выходят за границы атрибута";
+    zero_run_length() => "Data run length is 0", "Длина Data Run равна 0";
+    negative_lcn(lcn: i64) => "Negative LCN in the runlist: {lcn}", "Отрицательный LCN вычислен в runlist: {lcn}";
+    record_out_of_mft(entry: u64) => "Record {entry} is beyond $MFT", "Запись {entry} за пределами $MFT";
+
+    vbr_read_failed() => "Failed to read the VBR", "Ошибка чтения VBR";
+    vbr_unparsable() => "Failed to parse the VBR", "Не удалось распарсить VBR";
+    vbr_invalid(e: impl Display) => "VBR validation failed: {e}", "Валидация VBR не пройдена: {e}";
+    record0_read_failed() => "Failed to read MFT record 0", "Ошибка чтения MFT record 0";
+    record0_unparsable() => "MFT record 0 is damaged (header not recognized)", "MFT record 0 поврежден (заголовок не распознан)";
+    record0_rejected(e: impl Display) => "MFT record 0 rejected: {e}", "Отбраковка MFT record 0: {e}";
+    record0_fixups() =>
+        "MFT record 0 fixups failed (damaged USA array).", "Fixups MFT record 0 не применились (повреждение массива USA).";
+    attribute_loop() => "Attribute loop (offset stopped growing).", "Зацикленный атрибут (смещение перестало расти).";
+    attribute_out_of_record() =>
+        "Attribute size extends past the used part of the record.", "Выход размера атрибута за границы используемой части записи.";
+    attribute_list_out_of_attribute() => "$ATTRIBUTE_LIST extends past the attribute.", "$ATTRIBUTE_LIST выходит за границы атрибута.";
+    attribute_list_name(location: &str) =>
+        "UTF-16 name length in {location} extends past the entry.", "Длина имени UTF-16 в {location} выходит за пределы записи.";
+    bad_runs_offset(location: impl Display) =>
+        "Invalid Data Runs offset (dr_off) in {location}.", "Некорректное смещение Data Runs (dr_off) в {location}.";
+    runlist_error(location: impl Display, e: impl Display) => "Runlist error in {location}: {e}", "Ошибка runlist в {location}: {e}";
+    attribute_list_short() =>
+        "non-resident $ATTRIBUTE_LIST runlist is shorter than actual_size", "Runlist non-resident $ATTRIBUTE_LIST короче actual_size";
+    attribute_list_size(size: usize) =>
+        "Invalid non-resident $ATTRIBUTE_LIST size: {size} bytes", "Недопустимый размер non-resident $ATTRIBUTE_LIST: {size} байт";
+    attribute_list_read(e: impl Display) =>
+        "Failed to read non-resident $ATTRIBUTE_LIST: {e}", "Ошибка чтения non-resident $ATTRIBUTE_LIST: {e}";
+    no_base_runs() => "No base Data Runs found for $MFT.", "Базовые Data Runs для $MFT не найдены.";
+    extent_read_failed(entry: u64, e: impl Display) => "Failed to read ext_record ({entry}): {e}", "Ошибка чтения ext_record ({entry}): {e}";
+    extent_unparsable(entry: u64) => "ext_record is damaged ({entry})", "ext_record поврежден ({entry})";
+    extent_rejected(entry: u64, e: impl Display) => "ext_record ({entry}) rejected: {e}", "ext_record ({entry}) отбракован: {e}";
+    extent_sequence(entry: u64, expected: u16, found: u16) =>
+        "Sequence mismatch in ext_record {entry}: expected {expected}, found {found}.",
+        "Sequence mismatch в ext_record {entry}. Ожидался {expected}, найден {found}.";
+    extent_fixups(entry: u64) => "Fixups failed in ext_record ({entry})", "Ошибка fixups в ext_record ({entry})";
+    runlist_empty() => "The final runlist is empty.", "Итоговый Runlist пуст.";
+    vcn_gap(expected: u64, found: u64) => "VCN gap: expected {expected}, found {found}.", "Дыра в VCN. Ожидался {expected}, найден {found}.";
+    vcn_overlap(expected: u64, found: u64) =>
+        "VCN overlap: expected {expected}, found {found}.", "Перекрытие VCN. Ожидался {expected}, найден {found}.";
+    mft_size_short(collected: u64, allocated: u64) =>
+        "$MFT size assembled from clusters ({collected} bytes) is below the declared Allocated Size ({allocated} bytes). The runlist is damaged.",
+        "Собранный по кластерам размер MFT ({collected} байт) меньше заявленного Allocated Size ({allocated} байт). Runlist поврежден.";
+}
@@ -0,0 +1,47 @@
+//! Каталог сообщений extract, parse и play: английский по умолчанию, `--lang ru` -
+//! русские строки. Каждое сообщение - функция, которая собирает строку на текущем языке.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use clap::ValueEnum;
+
+/// Язык сообщений.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Lang {
+    #[default]
+    En,
+    Ru,
+}
+
+static LANG: AtomicU8 = AtomicU8::new(Lang::En as u8);
+
+/// Выбирает язык сообщений до конца процесса.
+pub fn set_lang(lang: Lang) {
+    LANG.store(lang as u8, Ordering::Relaxed);
+}
+
+pub fn lang() -> Lang {
+    if LANG.load(Ordering::Relaxed) == Lang::Ru as u8 { Lang::Ru } else { Lang::En }
+}
+
+/// Сообщения каталога: `имя(аргументы) => "english", "русский";`. Аргументы подставляются
+/// в строки по имени (`{path}`, `{offset:#X}`), поэтому форматы проверяет компилятор.
+macro_rules! messages {
+    ($($(#[$attr:meta])* $name:ident($($arg:ident: $ty:ty),*) => $en:literal, $ru:literal;)*) => {
+        $(
+            $(#[$attr])*
+            pub fn $name($($arg: $ty),*) -> String {
+                match $crate::i18n::lang() {
+                    $crate::i18n::Lang::En => format!($en),
+                    $crate::i18n::Lang::Ru => format!($ru),
+                }
+            }
+        )*
+    };
+}
+
+pub mod common;
+pub mod extract;
+pub mod parse;
+pub mod play;
+pub mod rules;
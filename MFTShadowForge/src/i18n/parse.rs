@@ -0,0 +1,93 @@
+//! Сообщения parse.
+
+use std::fmt::Display;
+
+messages! {
+    started() => "[*] Starting Parse", "[*] Запуск Parse";
+    geometry(record_size: usize, sector_size: impl Display, from: impl Display) =>
+        "[*] Record {record_size} bytes, sector {sector_size} bytes ({from})",
+        "[*] Запись {record_size} байт, сектор {sector_size} байт ({from})";
+    geometry_default() => "default", "по умолчанию";
+    hashset(count: usize) => "[*] Hash set loaded: {count} hashes", "[*] Хешсет загружен: {count} хешей";
+    sds(count: usize) => "[*] $SDS: {count} security descriptors", "[*] $SDS: {count} дескрипторов безопасности";
+    since_summary(max_lsn: u64, max_usn: u64) =>
+        "[*] Incremental mode: LSN > {max_lsn} or USN > {max_usn}",
+        "[*] Инкрементальный режим: LSN > {max_lsn} или USN > {max_usn}";
+    since_usn(usn: u64) => "[*] Incremental mode: USN > {usn}", "[*] Инкрементальный режим: USN > {usn}";
+    low_memory(dir: impl Display) =>
+        "[*] Low-memory mode: path names in temporary directory {dir}",
+        "[*] Режим ограниченной памяти: имена путей во временном каталоге {dir}";
+    i30_entries(count: usize) =>
+        "[*] $I30 entries for path recovery: {count}", "[*] Элементов $I30 для восстановления путей: {count}";
+    dirty_volume() =>
+        "[!] Volume is marked dirty (not cleanly unmounted): TornWrite on unfinished records is expected",
+        "[!] Том помечен dirty (не размонтирован корректно): TornWrite у недописанных записей ожидаем";
+    pass1() => "[*] Pass 1: building the path tree and baseline...", "[*] Проход 1: построение дерева путей и baseline...";
+    pass2() => "[*] Pass 2: parsing attributes and exporting to JSONL...", "[*] Проход 2: парсинг атрибутов и экспорт в JSONL...";
+    pass1_stage() => "Pass 1", "Проход 1";
+    pass2_stage() => "Pass 2", "Проход 2";
+    records(total: u64, in_use: u64, free: u64, empty: u64, baad: u64, extensions: u64) =>
+        "[*] Records: {total} (in use {in_use}, free {free}, empty {empty}, BAAD {baad}, extensions {extensions})",
+        "[*] Записей: {total} (занято {in_use}, свободно {free}, пустых {empty}, BAAD {baad}, расширений {extensions})";
+    bad_signature(entry: u64) => "[!] Record {entry}: BAAD signature", "[!] Запись {entry}: сигнатура BAAD";
+    bad_fixup(entry: u64) => "[!] Record {entry}: fixup mismatch", "[!] Запись {entry}: fixup не сошелся";
+    sink_stopped(kind: impl Display, target: impl Display, e: impl Display) =>
+        "[!] {kind} {target}: {e} - output stopped", "[!] {kind} {target}: {e} - вывод прекращен";
+    entry_write_failed(entry: u64, e: impl Display) =>
+        "[!] Record {entry} skipped in the report: {e}", "[!] Запись {entry} пропущена в отчете: {e}";
+    report_write_failed(path: impl Display, e: impl Display) => "[!] Failed to write {path}: {e}", "[!] Ошибка записи {path}: {e}";
+    index_written(path: impl Display, rows: usize) =>
+        "[+] Report index: {path} ({rows} rows)", "[+] Индекс отчета: {path} ({rows} строк)";
+    index_failed(path: impl Display, e: impl Display) =>
+        "[!] Failed to write index {path}: {e}", "[!] Не удалось записать индекс {path}: {e}";
+    written(written: u64, total: u64, max_lsn: u64, max_usn: u64) =>
+        "[+] Wrote {written} of {total} records (max LSN {max_lsn}, max USN {max_usn})",
+        "[+] Записано {written} из {total} записей (max LSN {max_lsn}, max USN {max_usn})";
+    skipped(count: u64) =>
+        "[!] Skipped {count} damaged records (BAAD, fixup errors)",
+        "[!] Пропущено поврежденных записей (BAAD, ошибки fixup): {count}";
+
+    since_unreadable(spec: impl Display) =>
+        "--since: cannot read run summary {spec}", "--since: не удалось прочитать итоги прогона {spec}";
+    since_other_volume(spec: impl Display, prev: u64, current: u64) =>
+        "--since: summary {spec} comes from another volume (serial {prev:#X}, current {current:#X})",
+        "--since: итоги {spec} получены с другого тома (serial {prev:#X}, текущий {current:#X})";
+    image_open_failed(path: impl Display) => "--image: failed to open {path}", "--image: ошибка открытия {path}";
+    i30_image_open_failed(path: impl Display) => "--i30-image: failed to open {path}", "--i30-image: ошибка открытия {path}";
+    low_memory_failed() =>
+        "--low-memory: failed to create the temporary names file", "--low-memory: не удалось создать временный файл имен";
+    stdin_failed() => "Failed to read MFT from stdin", "Не удалось прочитать MFT из stdin";
+    mft_read_failed() => "Failed to read MFT", "Ошибка чтения MFT";
+    plugin_loaded(name: impl Display, path: impl Display) => "[*] Plugin loaded: {name} ({path})", "[*] Плагин загружен: {name} ({path})";
+    plugin_abi(version: u32, expected: u32) =>
+        "interface version {version} is not supported (expected {expected})",
+        "версия интерфейса {version} не поддерживается (ожидается {expected})";
+    #[cfg(not(feature = "zstd"))]
+    zstd_unsupported() => "built without the zstd feature", "сборка без функции zstd";
+    #[cfg(feature = "parquet")]
+    parquet_schema_short() => "the Parquet schema is shorter than the column set", "схема Parquet короче набора колонок";
+    #[cfg(feature = "parquet")]
+    row_not_object() => "a report row must be an object", "строка отчета должна быть объектом";
+    #[cfg(feature = "siem")]
+    http_retry(url: impl Display, e: impl Display, secs: u64) =>
+        "[!] {url}: {e} - retrying in {secs} s", "[!] {url}: {e} - повтор через {secs} с";
+    #[cfg(feature = "siem")]
+    http_no_response(attempts: u32, e: impl Display) =>
+        "no response after {attempts} attempts: {e}", "нет ответа после {attempts} попыток: {e}";
+    #[cfg(feature = "siem")]
+    es_not_indexed(failed: u64, indexed: u64, reason: impl Display) =>
+        "{failed} documents not indexed ({indexed} indexed), first error: {reason}",
+        "не проиндексировано {failed} документов (проиндексировано {indexed}), первая ошибка: {reason}";
+    hashset_failed(path: impl Display) => "Failed to load hash set {path}", "Не удалось загрузить хешсет {path}";
+    sds_failed(path: impl Display) => "Failed to read $SDS {path}", "Не удалось прочитать $SDS {path}";
+    plugin_failed(e: impl Display) => "Failed to load plugin {e}", "Не удалось загрузить плагин {e}";
+    #[cfg(not(feature = "parquet"))]
+    parquet_unsupported() =>
+        "--out-parquet is unavailable: built without the parquet feature", "--out-parquet недоступен: сборка без функции parquet";
+    #[cfg(not(feature = "siem"))]
+    es_unsupported() =>
+        "--es-url is unavailable: built without the siem feature", "--es-url недоступен: сборка без функции siem";
+    #[cfg(not(feature = "siem"))]
+    splunk_unsupported() =>
+        "--splunk-hec-url is unavailable: built without the siem feature", "--splunk-hec-url недоступен: сборка без функции siem";
+}
@@ -0,0 +1,19 @@
+//! Сообщения play.
+
+use std::fmt::Display;
+
+messages! {
+    started() => "[*] Starting the full pipeline (Play)", "[*] Запуск полного пайплайна (Play)";
+    finished(out: impl Display) =>
+        "\n[+] Pipeline finished successfully! Results in: {out}",
+        "\n[+] Пайплайн успешно завершен! Результаты в папке: {out}";
+    schedule(secs: u64, keep: impl Display) =>
+        " -> Scheduled mode: every {secs} s, snapshots to keep: {keep}",
+        " -> Режим по расписанию: каждые {secs} с, хранить снимков: {keep}";
+    keep_all() => "all", "все";
+    snapshot_ready(dir: impl Display) => "\n[+] Snapshot ready: {dir}", "\n[+] Снимок готов: {dir}";
+    merging() => "\n[*] Merging per-volume reports", "\n[*] Объединение отчетов по томам";
+    rotated(dir: impl Display) => "[*] Rotation: removed snapshot {dir}", "[*] Ротация: удален снимок {dir}";
+    rotate_failed(dir: impl Display, e: impl Display) =>
+        "[!] Failed to remove snapshot {dir}: {e}", "[!] Не удалось удалить снимок {dir}: {e}";
+}
@@ -0,0 +1,18 @@
+//! Сообщения загрузки правил (parse, watch).
+
+use std::fmt::Display;
+
+messages! {
+    profiles(names: impl Display, count: usize) => "[*] Profiles {names}: {count} rules", "[*] Профили {names}: {count} правил";
+    rules_from(path: impl Display, count: usize) => "[*] Rules from {path}: {count}", "[*] Правила из {path}: {count}";
+    excludes_from(path: impl Display, count: usize) => "[*] Exclusions from {path}: {count}", "[*] Исключения из {path}: {count}";
+    sigma_from(path: impl Display, count: usize, skipped: usize, other: usize) =>
+        "[*] Sigma from {path}: {count} file_event rules (skipped {skipped}, other categories {other})",
+        "[*] Sigma из {path}: {count} правил file_event (пропущено {skipped}, других категорий {other})";
+    sigma_skipped(reason: impl Display) => "[!] Sigma skipped: {reason}", "[!] Sigma пропущено: {reason}";
+    rules_file_error(path: impl Display, e: impl Display) =>
+        "Error in rules file {path}:\n{e}", "Ошибка в файле правил {path}:\n{e}";
+    excludes_file_error(path: impl Display, e: impl Display) =>
+        "Error in exclusions file {path}:\n{e}", "Ошибка в файле исключений {path}:\n{e}";
+    sigma_failed(e: impl Display) => "Failed to load Sigma rules {e}", "Не удалось загрузить Sigma-правила {e}";
+}
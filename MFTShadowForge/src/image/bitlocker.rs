@@ -0,0 +1,617 @@
+use std::fs;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::PathBuf;
+
+use aes::cipher::{BlockDecrypt, BlockEncrypt, KeyInit};
+use aes::{Aes128, Aes256, Block};
+use byteorder::{ByteOrder, LittleEndian};
+use ccm::aead::generic_array::GenericArray;
+use ccm::aead::AeadInPlace;
+use ccm::consts::{U12, U16};
+use ccm::Ccm;
+use sha2::{Digest, Sha256};
+
+use crate::i18n::common;
+
+/// Сигнатура тома BitLocker (Windows 7 и новее) вместо "NTFS    ".
+pub const BDE_SIGNATURE: &[u8; 8] = b"-FVE-FS-";
+
+const VOLUME_HEADER_SIZE: usize = 512;
+const BLOCK_HEADER_SIZE: usize = 64;
+const METADATA_HEADER_SIZE: usize = 48;
+const ENTRY_HEADER_SIZE: usize = 8;
+const STRETCH_ITERATIONS: u64 = 0x10_0000;
+/// Сколько секторов расшифровывается за одно чтение.
+const SECTORS_PER_READ: u64 = 128;
+
+// Типы записей метаданных FVE
+const ENTRY_VMK: u16 = 0x0002;
+const ENTRY_FVEK: u16 = 0x0003;
+
+// Типы значений
+const VALUE_KEY: u16 = 0x0001;
+const VALUE_STRETCH_KEY: u16 = 0x0003;
+const VALUE_AES_CCM_KEY: u16 = 0x0005;
+const VALUE_VMK: u16 = 0x0008;
+const VALUE_EXTERNAL_KEY: u16 = 0x0009;
+
+// Способы защиты VMK
+const PROTECTION_CLEAR_KEY: u16 = 0x0000;
+const PROTECTION_STARTUP_KEY: u16 = 0x0200;
+const PROTECTION_RECOVERY_PASSWORD: u16 = 0x0800;
+const PROTECTION_PASSWORD: u16 = 0x2000;
+
+type BdeCcm = Ccm<Aes256, U16, U12>;
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Ключ для снятия BitLocker (`--bitlocker-key`).
+#[derive(Debug, Clone)]
+pub enum BitLockerKey {
+    /// 48 цифр ключа восстановления (8 групп по 6), уже преобразованные в 128-битный ключ
+    RecoveryPassword([u8; 16]),
+    Password(String),
+    /// Файл .BEK (ключ запуска с USB)
+    StartupKey(PathBuf),
+}
+
+impl BitLockerKey {
+    /// Ключ восстановления (`111111-222222-...`), путь к существующему файлу .BEK или пароль.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let groups: Vec<&str> = s.trim().split('-').collect();
+        if groups.len() == 8 && groups.iter().all(|g| g.len() == 6 && g.bytes().all(|b| b.is_ascii_digit())) {
+            let mut key = [0u8; 16];
+            for (i, group) in groups.iter().enumerate() {
+                let value: u32 = group.parse().map_err(|_| common::recovery_group_invalid(group))?;
+                if !value.is_multiple_of(11) || value / 11 > u16::MAX as u32 {
+                    return Err(common::recovery_group_check(i + 1, group));
+                }
+                LittleEndian::write_u16(&mut key[i * 2..i * 2 + 2], (value / 11) as u16);
+            }
+            return Ok(Self::RecoveryPassword(key));
+        }
+        let path = PathBuf::from(s);
+        if path.is_file() {
+            return Ok(Self::StartupKey(path));
+        }
+        if s.is_empty() {
+            return Err(common::bitlocker_empty_key());
+        }
+        Ok(Self::Password(s.to_string()))
+    }
+}
+
+/// Запись метаданных FVE: тип, тип значения и данные (без заголовка).
+struct Entry<'a> {
+    entry_type: u16,
+    value_type: u16,
+    data: &'a [u8],
+}
+
+fn entries(mut buf: &[u8]) -> Vec<Entry<'_>> {
+    let mut out = Vec::new();
+    while buf.len() >= ENTRY_HEADER_SIZE {
+        let size = LittleEndian::read_u16(&buf[0..2]) as usize;
+        if size < ENTRY_HEADER_SIZE || size > buf.len() { break; }
+        out.push(Entry {
+            entry_type: LittleEndian::read_u16(&buf[2..4]),
+            value_type: LittleEndian::read_u16(&buf[4..6]),
+            data: &buf[ENTRY_HEADER_SIZE..size],
+        });
+        buf = &buf[size..];
+    }
+    out
+}
+
+/// Ключ из записи типа "key": метод шифрования (4 байта) и сами байты ключа.
+fn key_value(data: &[u8]) -> Option<&[u8]> {
+    data.get(4..)
+}
+
+/// Растягивание пароля/ключа восстановления: 2^20 итераций SHA-256 с солью из VMK.
+fn stretch_key(password_hash: &[u8; 32], salt: &[u8]) -> [u8; 32] {
+    let mut state = [0u8; 88];
+    state[32..64].copy_from_slice(password_hash);
+    state[64..80].copy_from_slice(&salt[..16]);
+    for count in 0..STRETCH_ITERATIONS {
+        LittleEndian::write_u64(&mut state[80..88], count);
+        let hash = Sha256::digest(&state[..]);
+        state[..32].copy_from_slice(&hash);
+    }
+    let mut key = [0u8; 32];
+    key.copy_from_slice(&state[..32]);
+    key
+}
+
+/// Расшифровка значения AES-CCM (nonce 12 байт, MAC 16 байт, далее данные). Внутри - запись key:
+/// возвращаются ее данные (метод шифрования и ключ). None - ключ не подошел (не сошелся MAC).
+fn decrypt_ccm(key: &[u8], data: &[u8]) -> Option<Vec<u8>> {
+    if key.len() != 32 || data.len() <= 28 { return None; }
+    let cipher = BdeCcm::new(GenericArray::from_slice(key));
+    let mut plain = data[28..].to_vec();
+    cipher.decrypt_in_place_detached(GenericArray::from_slice(&data[..12]), b"", &mut plain, GenericArray::from_slice(&data[12..28])).ok()?;
+    let inner = entries(&plain).into_iter().next().filter(|e| e.value_type == VALUE_KEY && e.data.len() > 4)?;
+    Some(inner.data.to_vec())
+}
+
+/// Первая вложенная запись VMK с данным типом значения.
+fn property(vmk: &Entry, value_type: u16) -> Option<Vec<u8>> {
+    entries(vmk.data.get(28..)?).into_iter().find(|e| e.value_type == value_type).map(|e| e.data.to_vec())
+}
+
+/// Ключ из файла .BEK (запись external key с вложенной записью key).
+fn startup_key(path: &PathBuf) -> io::Result<Vec<u8>> {
+    let data = fs::read(path)?;
+    let header_size = data.get(8..12).map(LittleEndian::read_u32).unwrap_or(0) as usize;
+    let size = data.get(0..4).map(LittleEndian::read_u32).unwrap_or(0) as usize;
+    let body = data.get(header_size..size.min(data.len())).unwrap_or_default();
+    entries(body).into_iter()
+        .filter(|e| e.value_type == VALUE_EXTERNAL_KEY)
+        .find_map(|e| entries(e.data.get(24..)?).into_iter().find(|p| p.value_type == VALUE_KEY).and_then(|p| key_value(p.data).map(|k| k.to_vec())))
+        .ok_or_else(|| invalid(common::bek_no_key(path.display())))
+}
+
+/// Пытается получить VMK из записи VMK подходящим ключом.
+fn unlock_vmk(vmk: &Entry, key: Option<&BitLockerKey>, bek: Option<&[u8]>) -> Option<Vec<u8>> {
+    let protection = LittleEndian::read_u16(vmk.data.get(26..28)?);
+    let encrypted = property(vmk, VALUE_AES_CCM_KEY)?;
+    let stretched = |hash: [u8; 32]| -> Option<[u8; 32]> {
+        let stretch = property(vmk, VALUE_STRETCH_KEY)?;
+        Some(stretch_key(&hash, stretch.get(4..20)?))
+    };
+
+    let unlock_key: Vec<u8> = match (protection, key) {
+        (PROTECTION_CLEAR_KEY, _) => key_value(&property(vmk, VALUE_KEY)?)?.to_vec(),
+        (PROTECTION_RECOVERY_PASSWORD, Some(BitLockerKey::RecoveryPassword(recovery))) => {
+            stretched(Sha256::digest(recovery).into())?.to_vec()
+        }
+        (PROTECTION_PASSWORD, Some(BitLockerKey::Password(password))) => {
+            let utf16: Vec<u8> = password.encode_utf16().flat_map(|c| c.to_le_bytes()).collect();
+            stretched(Sha256::digest(Sha256::digest(&utf16)).into())?.to_vec()
+        }
+        (PROTECTION_STARTUP_KEY, Some(BitLockerKey::StartupKey(_))) => bek?.to_vec(),
+        _ => return None,
+    };
+    decrypt_ccm(&unlock_key, &encrypted).and_then(|k| key_value(&k).map(|v| v.to_vec()))
+}
+
+enum AesKey {
+    Aes128(Box<Aes128>),
+    Aes256(Box<Aes256>),
+}
+
+impl AesKey {
+    fn new(key: &[u8]) -> Option<Self> {
+        match key.len() {
+            16 => Some(Self::Aes128(Box::new(Aes128::new(GenericArray::from_slice(key))))),
+            32 => Some(Self::Aes256(Box::new(Aes256::new(GenericArray::from_slice(key))))),
+            _ => None,
+        }
+    }
+
+    fn encrypt(&self, block: &mut Block) {
+        match self {
+            Self::Aes128(c) => c.encrypt_block(block),
+            Self::Aes256(c) => c.encrypt_block(block),
+        }
+    }
+
+    fn decrypt(&self, block: &mut Block) {
+        match self {
+            Self::Aes128(c) => c.decrypt_block(block),
+            Self::Aes256(c) => c.decrypt_block(block),
+        }
+    }
+
+    /// Блок из смещения сектора (LE u64, дополненный нулями), зашифрованный ключом.
+    fn encrypt_offset(&self, offset: u64, last_byte: u8) -> Block {
+        let mut block = Block::default();
+        LittleEndian::write_u64(&mut block[..8], offset);
+        block[15] = last_byte;
+        self.encrypt(&mut block);
+        block
+    }
+}
+
+/// Шифр секторов тома по методу из FVEK.
+enum SectorCipher {
+    /// AES-XTS (Windows 10+): ключ данных и ключ tweak, tweak - номер сектора
+    Xts { data: AesKey, tweak: AesKey },
+    /// AES-CBC (Vista/7: с диффузором Elephant, если есть ключ tweak)
+    Cbc { fvek: AesKey, tweak: Option<AesKey> },
+}
+
+impl SectorCipher {
+    fn new(method: u32, key: &[u8]) -> io::Result<Self> {
+        let slice = |range: std::ops::Range<usize>| {
+            key.get(range).ok_or_else(|| invalid(common::fvek_short(method)))
+        };
+        let aes = |k: &[u8]| AesKey::new(k).ok_or_else(|| invalid(common::aes_key_length()));
+        Ok(match method {
+            0x8000 => Self::Cbc { fvek: aes(slice(0..16)?)?, tweak: Some(aes(slice(32..48)?)?) },
+            0x8001 => Self::Cbc { fvek: aes(slice(0..32)?)?, tweak: Some(aes(slice(32..64)?)?) },
+            0x8002 => Self::Cbc { fvek: aes(slice(0..16)?)?, tweak: None },
+            0x8003 => Self::Cbc { fvek: aes(slice(0..32)?)?, tweak: None },
+            0x8004 => Self::Xts { data: aes(slice(0..16)?)?, tweak: aes(slice(16..32)?)? },
+            0x8005 => Self::Xts { data: aes(slice(0..32)?)?, tweak: aes(slice(32..64)?)? },
+            other => return Err(invalid(common::bitlocker_method(other))),
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Xts { data: AesKey::Aes128(_), .. } => "AES-XTS-128",
+            Self::Xts { data: AesKey::Aes256(_), .. } => "AES-XTS-256",
+            Self::Cbc { fvek: AesKey::Aes128(_), tweak: Some(_) } => "AES-CBC-128 + Elephant",
+            Self::Cbc { fvek: AesKey::Aes256(_), tweak: Some(_) } => "AES-CBC-256 + Elephant",
+            Self::Cbc { fvek: AesKey::Aes128(_), tweak: None } => "AES-CBC-128",
+            Self::Cbc { fvek: AesKey::Aes256(_), tweak: None } => "AES-CBC-256",
+        }
+    }
+
+    /// Расшифровывает сектора, лежащие на томе по смещению `offset`.
+    fn decrypt(&self, buf: &mut [u8], sector_size: usize, offset: u64) {
+        match self {
+            Self::Xts { data, tweak } => {
+                for (i, sector) in buf.chunks_exact_mut(sector_size).enumerate() {
+                    let sector_no = offset / sector_size as u64 + i as u64;
+                    let mut t = Block::clone_from_slice(&(sector_no as u128).to_le_bytes());
+                    tweak.encrypt(&mut t);
+                    for block in sector.chunks_exact_mut(16) {
+                        let block = Block::from_mut_slice(block);
+                        block.iter_mut().zip(t.iter()).for_each(|(b, t)| *b ^= t);
+                        data.decrypt(block);
+                        block.iter_mut().zip(t.iter()).for_each(|(b, t)| *b ^= t);
+                        xts_next_tweak(&mut t);
+                    }
+                }
+            }
+            Self::Cbc { fvek, tweak } => {
+                for (i, sector) in buf.chunks_exact_mut(sector_size).enumerate() {
+                    let sector_offset = offset + (i * sector_size) as u64;
+                    let mut prev = fvek.encrypt_offset(sector_offset, 0);
+                    for block in sector.chunks_exact_mut(16) {
+                        let cipher_block = Block::clone_from_slice(block);
+                        let block = Block::from_mut_slice(block);
+                        fvek.decrypt(block);
+                        block.iter_mut().zip(prev.iter()).for_each(|(b, p)| *b ^= p);
+                        prev = cipher_block;
+                    }
+                    if let Some(tweak) = tweak {
+                        let mut sector_key = [0u8; 32];
+                        sector_key[..16].copy_from_slice(&tweak.encrypt_offset(sector_offset, 0));
+                        sector_key[16..].copy_from_slice(&tweak.encrypt_offset(sector_offset, 0x80));
+                        elephant_decrypt(sector);
+                        sector.iter_mut().enumerate().for_each(|(j, b)| *b ^= sector_key[j % 32]);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Tweak следующего блока XTS: умножение на x в GF(2^128) (little-endian, полином 0x87).
+fn xts_next_tweak(t: &mut Block) {
+    let carry = t[15] >> 7;
+    for i in (1..16).rev() {
+        t[i] = (t[i] << 1) | (t[i - 1] >> 7);
+    }
+    t[0] = (t[0] << 1) ^ (0x87 * carry);
+}
+
+/// Обратные диффузоры Elephant: сначала B (3 цикла), затем A (5 циклов).
+fn elephant_decrypt(sector: &mut [u8]) {
+    const RA: [u32; 4] = [9, 0, 13, 0];
+    const RB: [u32; 4] = [0, 10, 0, 25];
+    let mut d: Vec<u32> = sector.chunks_exact(4).map(LittleEndian::read_u32).collect();
+    let n = d.len();
+    for _ in 0..3 {
+        for i in 0..n {
+            d[i] = d[i].wrapping_add(d[(i + 2) % n] ^ d[(i + 5) % n].rotate_left(RB[i % 4]));
+        }
+    }
+    for _ in 0..5 {
+        for i in 0..n {
+            d[i] = d[i].wrapping_add(d[(i + n - 2) % n] ^ d[(i + n - 5) % n].rotate_left(RA[i % 4]));
+        }
+    }
+    for (chunk, value) in sector.chunks_exact_mut(4).zip(d) {
+        LittleEndian::write_u32(chunk, value);
+    }
+}
+
+/// Образ, в котором раздел BitLocker читается расшифрованным: остальной диск отдается как есть,
+/// поэтому поиск раздела и смещения в meta.json остаются прежними.
+pub struct BitLockerVolume<R> {
+    inner: R,
+    volume_offset: u64,
+    volume_size: u64,
+    sector_size: u64,
+    /// Граница зашифрованной части (при незавершенном шифровании дальше - открытые данные)
+    encrypted_size: u64,
+    /// Настоящий загрузочный сектор NTFS перенесен: (смещение копии на томе, размер)
+    header_backup: (u64, u64),
+    cipher: SectorCipher,
+    position: u64,
+}
+
+impl<R: Read + Seek> BitLockerVolume<R> {
+    /// Открывает раздел BitLocker по смещению `volume_offset`. Без ключа снимается только
+    /// приостановленная защита (clear key).
+    pub fn open(mut inner: R, volume_offset: u64, key: Option<&BitLockerKey>) -> io::Result<Self> {
+        let mut header = [0u8; VOLUME_HEADER_SIZE];
+        inner.seek(SeekFrom::Start(volume_offset))?;
+        inner.read_exact(&mut header)?;
+        if &header[3..11] != BDE_SIGNATURE {
+            return Err(invalid(common::bitlocker_no_header()));
+        }
+        let sector_size = LittleEndian::read_u16(&header[11..13]) as u64;
+        if !(512..=4096).contains(&sector_size) || !sector_size.is_power_of_two() {
+            return Err(invalid(common::bitlocker_sector_size(sector_size)));
+        }
+
+        // Три копии метаданных FVE: берется первая читаемая
+        let block_offsets = [0xB0, 0xB8, 0xC0].map(|o| LittleEndian::read_u64(&header[o..o + 8]));
+        let mut block = None;
+        for offset in block_offsets {
+            if let Ok(data) = read_metadata_block(&mut inner, volume_offset + offset) {
+                block = Some(data);
+                break;
+            }
+        }
+        let block = block.ok_or_else(|| invalid(common::fve_unreadable()))?;
+
+        let encrypted_size = LittleEndian::read_u64(&block[16..24]);
+        let header_sectors = LittleEndian::read_u32(&block[28..32]) as u64;
+        let header_offset = LittleEndian::read_u64(&block[56..64]);
+        let metadata_entries = entries(&block[BLOCK_HEADER_SIZE + METADATA_HEADER_SIZE..]);
+
+        let bek = match key {
+            Some(BitLockerKey::StartupKey(path)) => Some(startup_key(path)?),
+            _ => None,
+        };
+        let vmk = metadata_entries.iter()
+            .filter(|e| e.entry_type == ENTRY_VMK && e.value_type == VALUE_VMK)
+            .find_map(|e| unlock_vmk(e, key, bek.as_deref()))
+            .ok_or_else(|| invalid(match key {
+                Some(_) => common::bitlocker_wrong_key(),
+                None => common::bitlocker_key_required(),
+            }))?;
+
+        let fvek_entry = metadata_entries.iter()
+            .find(|e| e.entry_type == ENTRY_FVEK && e.value_type == VALUE_AES_CCM_KEY)
+            .ok_or_else(|| invalid(common::fvek_missing()))?;
+        let fvek = decrypt_ccm(&vmk, fvek_entry.data).ok_or_else(|| invalid(common::fvek_undecryptable()))?;
+        let cipher = SectorCipher::new(LittleEndian::read_u32(&fvek[..4]), &fvek[4..])?;
+
+        let mut volume = Self {
+            inner,
+            volume_offset,
+            volume_size: u64::MAX - volume_offset,
+            sector_size,
+            encrypted_size,
+            header_backup: (header_offset, header_sectors * sector_size),
+            cipher,
+            position: 0,
+        };
+
+        // Размер тома - из расшифрованного загрузочного сектора NTFS (+ его резервная копия в конце)
+        let mut boot = vec![0u8; sector_size as usize];
+        volume.seek(SeekFrom::Start(volume_offset))?;
+        volume.read_exact(&mut boot)?;
+        if &boot[3..11] != b"NTFS    " {
+            return Err(invalid(common::bitlocker_not_ntfs()));
+        }
+        volume.volume_size = (LittleEndian::read_u64(&boot[0x28..0x30]) + 1) * sector_size;
+        volume.position = 0;
+        Ok(volume)
+    }
+
+    pub fn method_name(&self) -> &'static str {
+        self.cipher.name()
+    }
+
+    /// Читает и расшифровывает сектора тома, начиная с `rel` (выровнено по сектору).
+    fn read_volume_sectors(&mut self, rel: u64, max_len: u64) -> io::Result<Vec<u8>> {
+        let (backup_offset, backup_size) = self.header_backup;
+        // Граница, которую нельзя пересекать одним чтением
+        let limit = if rel < backup_size { backup_size }
+            else if rel < self.encrypted_size { self.encrypted_size }
+            else { self.volume_size };
+        let len = max_len.min(limit - rel).min(SECTORS_PER_READ * self.sector_size);
+        let len = len.div_ceil(self.sector_size) * self.sector_size;
+
+        let location = if rel < backup_size { backup_offset + rel } else { rel };
+        let mut buf = vec![0u8; len as usize];
+        self.inner.seek(SeekFrom::Start(self.volume_offset + location))?;
+        self.inner.read_exact(&mut buf)?;
+        if location < self.encrypted_size {
+            self.cipher.decrypt(&mut buf, self.sector_size as usize, location);
+        }
+        Ok(buf)
+    }
+}
+
+/// Блок метаданных FVE целиком (заголовок блока, заголовок метаданных и записи).
+fn read_metadata_block<R: Read + Seek>(inner: &mut R, offset: u64) -> io::Result<Vec<u8>> {
+    let mut head = [0u8; BLOCK_HEADER_SIZE + METADATA_HEADER_SIZE];
+    inner.seek(SeekFrom::Start(offset))?;
+    inner.read_exact(&mut head)?;
+    if &head[..8] != BDE_SIGNATURE || LittleEndian::read_u16(&head[10..12]) < 2 {
+        return Err(invalid(common::fve_block_missing()));
+    }
+    let metadata_size = LittleEndian::read_u32(&head[BLOCK_HEADER_SIZE..BLOCK_HEADER_SIZE + 4]) as usize;
+    if !(METADATA_HEADER_SIZE..=1024 * 1024).contains(&metadata_size) {
+        return Err(invalid(common::fve_bad_size(metadata_size)));
+    }
+    let mut block = vec![0u8; BLOCK_HEADER_SIZE + metadata_size];
+    inner.seek(SeekFrom::Start(offset))?;
+    inner.read_exact(&mut block)?;
+    Ok(block)
+}
+
+impl<R: Read + Seek> Read for BitLockerVolume<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() { return Ok(0); }
+        let volume_end = self.volume_offset.saturating_add(self.volume_size);
+        if self.position < self.volume_offset || self.position >= volume_end {
+            // Вне раздела BitLocker - данные диска как есть
+            let max = if self.position < self.volume_offset { self.volume_offset - self.position } else { u64::MAX };
+            let n = (buf.len() as u64).min(max) as usize;
+            self.inner.seek(SeekFrom::Start(self.position))?;
+            let read = self.inner.read(&mut buf[..n])?;
+            self.position += read as u64;
+            return Ok(read);
+        }
+
+        let rel = self.position - self.volume_offset;
+        let in_sector = rel % self.sector_size;
+        let wanted = (buf.len() as u64).min(volume_end - self.position);
+        let data = self.read_volume_sectors(rel - in_sector, in_sector + wanted)?;
+        let n = (data.len() as u64 - in_sector).min(wanted) as usize;
+        buf[..n].copy_from_slice(&data[in_sector as usize..in_sector as usize + n]);
+        self.position += n as u64;
+        Ok(n)
+    }
+}
+
+impl<R: Read + Seek> Seek for BitLockerVolume<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(p) => Some(p),
+            SeekFrom::End(d) => self.inner.seek(SeekFrom::End(0))?.checked_add_signed(d),
+            SeekFrom::Current(d) => self.position.checked_add_signed(d),
+        };
+        self.position = target.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, common::seek_before_start()))?;
+        Ok(self.position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+    }
+
+    fn plaintext(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 7 + i / 256) as u8).collect()
+    }
+
+    /// AES-CBC как у BitLocker: IV - зашифрованное смещение сектора.
+    fn cbc_encrypt(fvek: &AesKey, sector: &mut [u8], offset: u64) {
+        let mut prev = fvek.encrypt_offset(offset, 0);
+        for block in sector.chunks_exact_mut(16) {
+            let block = Block::from_mut_slice(block);
+            block.iter_mut().zip(prev.iter()).for_each(|(b, p)| *b ^= p);
+            fvek.encrypt(block);
+            prev = *block;
+        }
+    }
+
+    /// Прямые диффузоры Elephant (A, затем B) - обратные к `elephant_decrypt`.
+    fn elephant_encrypt(sector: &mut [u8]) {
+        const RA: [u32; 4] = [9, 0, 13, 0];
+        const RB: [u32; 4] = [0, 10, 0, 25];
+        let mut d: Vec<u32> = sector.chunks_exact(4).map(LittleEndian::read_u32).collect();
+        let n = d.len();
+        for _ in 0..5 {
+            for i in (0..n).rev() {
+                d[i] = d[i].wrapping_sub(d[(i + n - 2) % n] ^ d[(i + n - 5) % n].rotate_left(RA[i % 4]));
+            }
+        }
+        for _ in 0..3 {
+            for i in (0..n).rev() {
+                d[i] = d[i].wrapping_sub(d[(i + 2) % n] ^ d[(i + 5) % n].rotate_left(RB[i % 4]));
+            }
+        }
+        for (chunk, value) in sector.chunks_exact_mut(4).zip(d) {
+            LittleEndian::write_u32(chunk, value);
+        }
+    }
+
+    #[test]
+    fn xts_matches_ieee_1619_vectors() {
+        // Векторы 1 и 2 из IEEE P1619 (единица данных 32 байта)
+        let vectors = [
+            ([0u8; 32].to_vec(), 0u64, "917cf69ebd68b2ec9b9fe9a3eadda692cd43d2f59598ed858c02c2652fbf922e", [0u8; 32]),
+            ([[0x11; 16], [0x22; 16]].concat(), 0x33_3333_3333, "c454185e6a16936e39334038acef838bfb186fff7480adc4289382ecd6d394f0", [0x44; 32]),
+        ];
+        for (key, sector, ciphertext, expected) in vectors {
+            let cipher = SectorCipher::new(0x8004, &key).unwrap();
+            assert_eq!(cipher.name(), "AES-XTS-128");
+            let mut buf = hex(ciphertext);
+            cipher.decrypt(&mut buf, 32, sector * 32);
+            assert_eq!(buf, expected);
+        }
+    }
+
+    #[test]
+    fn xts_numbers_sectors_from_offset() {
+        let cipher = SectorCipher::new(0x8005, &plaintext(64)).unwrap();
+        let mut whole = plaintext(1024);
+        cipher.decrypt(&mut whole, 512, 0x2_0000);
+        for (i, expected) in whole.chunks(512).enumerate() {
+            let mut sector = plaintext(1024)[i * 512..(i + 1) * 512].to_vec();
+            cipher.decrypt(&mut sector, 512, 0x2_0000 + i as u64 * 512);
+            assert_eq!(sector, expected);
+        }
+    }
+
+    #[test]
+    fn cbc_uses_encrypted_offset_as_iv() {
+        let key = [0x5Au8; 32];
+        let cipher = SectorCipher::new(0x8003, &key).unwrap();
+        let fvek = AesKey::new(&key).unwrap();
+        let mut buf = plaintext(1024);
+        for (i, sector) in buf.chunks_exact_mut(512).enumerate() {
+            cbc_encrypt(&fvek, sector, 0x10_0000 + i as u64 * 512);
+        }
+        cipher.decrypt(&mut buf, 512, 0x10_0000);
+        assert_eq!(buf, plaintext(1024));
+    }
+
+    #[test]
+    fn cbc_with_elephant_diffuser_round_trips() {
+        let key: Vec<u8> = (0..64).collect();
+        let cipher = SectorCipher::new(0x8000, &key).unwrap();
+        assert_eq!(cipher.name(), "AES-CBC-128 + Elephant");
+        let fvek = AesKey::new(&key[..16]).unwrap();
+        let tweak = AesKey::new(&key[32..48]).unwrap();
+
+        let offset = 0x4_0000;
+        let mut sector = plaintext(512);
+        let mut sector_key = [0u8; 32];
+        sector_key[..16].copy_from_slice(&tweak.encrypt_offset(offset, 0));
+        sector_key[16..].copy_from_slice(&tweak.encrypt_offset(offset, 0x80));
+        sector.iter_mut().enumerate().for_each(|(j, b)| *b ^= sector_key[j % 32]);
+        elephant_encrypt(&mut sector);
+        cbc_encrypt(&fvek, &mut sector, offset);
+
+        cipher.decrypt(&mut sector, 512, offset);
+        assert_eq!(sector, plaintext(512));
+    }
+
+    #[test]
+    fn elephant_diffuses_single_bit() {
+        let mut a = plaintext(512);
+        let mut b = a.clone();
+        b[100] ^= 1;
+        elephant_encrypt(&mut a);
+        elephant_encrypt(&mut b);
+        let changed = a.iter().zip(&b).filter(|(x, y)| x != y).count();
+        assert!(changed > 256, "изменилось только {} байт", changed);
+
+        elephant_decrypt(&mut a);
+        assert_eq!(a, plaintext(512));
+    }
+
+    #[test]
+    fn unknown_method_is_rejected() {
+        assert!(SectorCipher::new(0x8006, &[0u8; 64]).is_err());
+        assert!(SectorCipher::new(0x8001, &[0u8; 32]).is_err());
+    }
+}
@@ -0,0 +1,63 @@
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+
+use crate::i18n::common;
+
+/// Виртуальный диск из блоков одного размера, разложенных по файлам контейнера
+/// (VHD, VHDX, ...). Невыделенные блоки читаются как нули.
+pub struct BlockMapReader {
+    files: Vec<File>,
+    block_size: u64,
+    size: u64,
+    /// Для каждого блока: индекс файла и смещение данных блока в нем
+    blocks: Vec<Option<(usize, u64)>>,
+    position: u64,
+}
+
+impl BlockMapReader {
+    pub fn new(files: Vec<File>, block_size: u64, size: u64, blocks: Vec<Option<(usize, u64)>>) -> Self {
+        Self { files, block_size, size, blocks, position: 0 }
+    }
+
+    /// Размер виртуального диска, байт.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Сколько блоков реально выделено в контейнере.
+    pub fn allocated_blocks(&self) -> usize {
+        self.blocks.iter().filter(|b| b.is_some()).count()
+    }
+}
+
+impl Read for BlockMapReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.position >= self.size || buf.is_empty() { return Ok(0); }
+        let block = (self.position / self.block_size) as usize;
+        let in_block = self.position % self.block_size;
+        let n = (buf.len() as u64).min(self.block_size - in_block).min(self.size - self.position) as usize;
+
+        match self.blocks.get(block).copied().flatten() {
+            Some((file, offset)) => {
+                let file = &mut self.files[file];
+                file.seek(SeekFrom::Start(offset + in_block))?;
+                file.read_exact(&mut buf[..n])?;
+            }
+            None => buf[..n].fill(0),
+        }
+        self.position += n as u64;
+        Ok(n)
+    }
+}
+
+impl Seek for BlockMapReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(p) => Some(p),
+            SeekFrom::End(d) => self.size.checked_add_signed(d),
+            SeekFrom::Current(d) => self.position.checked_add_signed(d),
+        };
+        self.position = target.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, common::seek_before_start()))?;
+        Ok(self.position)
+    }
+}
\ No newline at end of file
@@ -0,0 +1,321 @@
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use byteorder::{ByteOrder, LittleEndian};
+use flate2::read::ZlibDecoder;
+
+use crate::i18n::common;
+
+/// Сигнатура сегмента EWF-E01 (EnCase).
+pub const EVF_SIGNATURE: [u8; 8] = *b"EVF\x09\x0d\x0a\xff\x00";
+
+const FILE_HEADER_SIZE: u64 = 13;
+const SECTION_DESCRIPTOR_SIZE: usize = 76;
+/// Заголовок секции table: число записей, base offset, контрольная сумма.
+const TABLE_HEADER_SIZE: usize = 24;
+const CHUNK_COMPRESSED: u32 = 0x8000_0000;
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Место чанка в сегменте: смещение и длина (для несжатого - с adler32 в конце).
+#[derive(Debug, Clone, Copy)]
+struct ChunkLocation {
+    segment: usize,
+    offset: u64,
+    size: u64,
+    compressed: bool,
+}
+
+/// Чтение образа EWF-E01 (в том числе разбитого на сегменты E01, E02, ...)
+/// как непрерывного диска: чанки распаковываются по требованию, последний кешируется.
+pub struct EwfReader {
+    segments: Vec<File>,
+    chunks: Vec<ChunkLocation>,
+    chunk_size: u64,
+    media_size: u64,
+    position: u64,
+    cached: Option<(usize, Vec<u8>)>,
+}
+
+/// Имена сегментов по порядку: E01..E99, затем EAA..EZZ, FAA.. (регистр - как у первого).
+fn segment_path(first: &Path, index: usize) -> Option<PathBuf> {
+    let ext = first.extension()?.to_str()?;
+    if ext.len() != 3 { return None; }
+    let lower = ext.starts_with(|c: char| c.is_ascii_lowercase());
+    let first_letter = ext.as_bytes()[0];
+    let new_ext = if index < 100 {
+        format!("{}{:02}", first_letter as char, index)
+    } else {
+        let n = index - 100;
+        let letters = [first_letter + (n / 676) as u8, b'A' + (n / 26 % 26) as u8, b'A' + (n % 26) as u8];
+        String::from_utf8_lossy(&letters).into_owned()
+    };
+    let new_ext = if lower { new_ext.to_ascii_lowercase() } else { new_ext.to_ascii_uppercase() };
+    Some(first.with_extension(new_ext))
+}
+
+impl EwfReader {
+    pub fn open(path: &str) -> io::Result<Self> {
+        let first = Path::new(path);
+        let mut reader = Self { segments: Vec::new(), chunks: Vec::new(), chunk_size: 0, media_size: 0, position: 0, cached: None };
+
+        let mut index = 1;
+        let mut next_path = Some(first.to_path_buf());
+        while let Some(segment_path_buf) = next_path.take() {
+            let file = File::open(&segment_path_buf)
+                .map_err(|e| io::Error::new(e.kind(), common::ewf_segment(segment_path_buf.display(), &e)))?;
+            reader.segments.push(file);
+            let has_next = reader.read_segment(reader.segments.len() - 1)?;
+            if has_next {
+                index += 1;
+                next_path = Some(segment_path(first, index)
+                    .ok_or_else(|| invalid(common::ewf_segment_name(index, path)))?);
+            }
+        }
+
+        if reader.chunk_size == 0 { return Err(invalid(common::ewf_no_volume())); }
+        if reader.chunks.is_empty() { return Err(invalid(common::ewf_no_chunks())); }
+        Ok(reader)
+    }
+
+    pub fn segment_count(&self) -> usize {
+        self.segments.len()
+    }
+
+    pub fn media_size(&self) -> u64 {
+        self.media_size
+    }
+
+    /// Разбирает секции сегмента; true - образ продолжается в следующем сегменте ("next").
+    fn read_segment(&mut self, segment: usize) -> io::Result<bool> {
+        let file = &mut self.segments[segment];
+        let mut header = [0u8; FILE_HEADER_SIZE as usize];
+        file.seek(SeekFrom::Start(0))?;
+        file.read_exact(&mut header)?;
+        if header[..8] != EVF_SIGNATURE {
+            return Err(invalid(common::ewf_not_e01(segment + 1)));
+        }
+
+        let mut offset = FILE_HEADER_SIZE;
+        let mut sectors_end: Option<u64> = None;
+        loop {
+            let mut desc = [0u8; SECTION_DESCRIPTOR_SIZE];
+            file.seek(SeekFrom::Start(offset))?;
+            file.read_exact(&mut desc)?;
+            let kind = String::from_utf8_lossy(&desc[..16]).trim_end_matches('\0').to_string();
+            let next = LittleEndian::read_u64(&desc[16..24]);
+            let size = LittleEndian::read_u64(&desc[24..32]);
+            let data_offset = offset + SECTION_DESCRIPTOR_SIZE as u64;
+
+            match kind.as_str() {
+                "volume" | "disk" => {
+                    let mut data = [0u8; 24];
+                    file.read_exact(&mut data)?;
+                    let sectors_per_chunk = LittleEndian::read_u32(&data[8..12]) as u64;
+                    let bytes_per_sector = LittleEndian::read_u32(&data[12..16]) as u64;
+                    self.chunk_size = sectors_per_chunk * bytes_per_sector;
+                    self.media_size = LittleEndian::read_u64(&data[16..24]) * bytes_per_sector;
+                }
+                "sectors" => sectors_end = Some(offset + size),
+                "table" => {
+                    let mut table_header = [0u8; TABLE_HEADER_SIZE];
+                    file.read_exact(&mut table_header)?;
+                    let count = LittleEndian::read_u32(&table_header[0..4]) as usize;
+                    let base = LittleEndian::read_u64(&table_header[8..16]);
+                    let mut entries = vec![0u8; count * 4];
+                    file.seek(SeekFrom::Start(data_offset + TABLE_HEADER_SIZE as u64))?;
+                    file.read_exact(&mut entries)?;
+
+                    // Длина чанка - до начала следующего; у последнего - до конца секции sectors
+                    let data_end = sectors_end.unwrap_or(offset);
+                    let locations: Vec<(u64, bool)> = entries.chunks_exact(4)
+                        .map(LittleEndian::read_u32)
+                        .map(|e| (base + (e & !CHUNK_COMPRESSED) as u64, e & CHUNK_COMPRESSED != 0))
+                        .collect();
+                    for (i, &(chunk_offset, compressed)) in locations.iter().enumerate() {
+                        let end = locations.get(i + 1).map(|l| l.0).unwrap_or(data_end);
+                        self.chunks.push(ChunkLocation { segment, offset: chunk_offset, size: end.saturating_sub(chunk_offset), compressed });
+                    }
+                }
+                "next" => return Ok(true),
+                "done" => return Ok(false),
+                _ => {}
+            }
+
+            // У последней секции next указывает на нее саму
+            if next <= offset { return Ok(false); }
+            offset = next;
+        }
+    }
+
+    fn load_chunk(&mut self, index: usize) -> io::Result<&[u8]> {
+        if self.cached.as_ref().map(|c| c.0) != Some(index) {
+            let loc = self.chunks[index];
+            let file = &mut self.segments[loc.segment];
+            file.seek(SeekFrom::Start(loc.offset))?;
+            let mut raw = vec![0u8; loc.size as usize];
+            file.read_exact(&mut raw)?;
+
+            let data = if loc.compressed {
+                let mut out = Vec::with_capacity(self.chunk_size as usize);
+                ZlibDecoder::new(&raw[..]).take(self.chunk_size).read_to_end(&mut out)
+                    .map_err(|e| invalid(common::ewf_chunk_inflate(index, e)))?;
+                out
+            } else {
+                raw.truncate(self.chunk_size as usize);
+                raw
+            };
+            self.cached = Some((index, data));
+        }
+        Ok(self.cached.as_ref().map(|c| &c.1[..]).unwrap_or_default())
+    }
+}
+
+impl Read for EwfReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.position >= self.media_size || buf.is_empty() { return Ok(0); }
+        let index = (self.position / self.chunk_size) as usize;
+        if index >= self.chunks.len() {
+            return Err(invalid(common::ewf_beyond_chunks(self.position)));
+        }
+        let in_chunk = (self.position % self.chunk_size) as usize;
+        let remaining_media = self.media_size - self.position;
+
+        let chunk = self.load_chunk(index)?;
+        if in_chunk >= chunk.len() {
+            return Err(invalid(common::ewf_chunk_short(index)));
+        }
+        let n = buf.len().min(chunk.len() - in_chunk).min(remaining_media as usize);
+        buf[..n].copy_from_slice(&chunk[in_chunk..in_chunk + n]);
+        self.position += n as u64;
+        Ok(n)
+    }
+}
+
+impl Seek for EwfReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(p) => Some(p),
+            SeekFrom::End(d) => self.media_size.checked_add_signed(d),
+            SeekFrom::Current(d) => self.position.checked_add_signed(d),
+        };
+        self.position = target.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, common::seek_before_start()))?;
+        Ok(self.position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::write::ZlibEncoder;
+
+    use super::*;
+    use crate::image::Fixture;
+
+    const SECTOR: usize = 512;
+
+    /// Сегмент EWF, собираемый секция за секцией.
+    struct Segment(Vec<u8>);
+
+    impl Segment {
+        fn new(number: u16) -> Self {
+            let mut data = EVF_SIGNATURE.to_vec();
+            data.push(1);
+            data.extend(number.to_le_bytes());
+            data.extend([0, 0]);
+            Self(data)
+        }
+
+        /// Добавляет секцию и возвращает смещение ее данных; next - на следующую секцию.
+        fn section(&mut self, kind: &str, payload: &[u8]) -> u64 {
+            let offset = self.0.len() as u64;
+            let size = (SECTION_DESCRIPTOR_SIZE + payload.len()) as u64;
+            self.descriptor(kind, offset + size, size);
+            self.0.extend(payload);
+            offset + SECTION_DESCRIPTOR_SIZE as u64
+        }
+
+        /// Завершающая секция (next/done): next указывает на нее саму.
+        fn finish(mut self, kind: &str) -> Vec<u8> {
+            let offset = self.0.len() as u64;
+            self.descriptor(kind, offset, SECTION_DESCRIPTOR_SIZE as u64);
+            self.0
+        }
+
+        fn descriptor(&mut self, kind: &str, next: u64, size: u64) {
+            let mut desc = [0u8; SECTION_DESCRIPTOR_SIZE];
+            desc[..kind.len()].copy_from_slice(kind.as_bytes());
+            LittleEndian::write_u64(&mut desc[16..24], next);
+            LittleEndian::write_u64(&mut desc[24..32], size);
+            self.0.extend(desc);
+        }
+
+        fn table(&mut self, base: u64, entries: &[u32]) {
+            let mut payload = vec![0u8; TABLE_HEADER_SIZE];
+            LittleEndian::write_u32(&mut payload[0..4], entries.len() as u32);
+            LittleEndian::write_u64(&mut payload[8..16], base);
+            for entry in entries {
+                payload.extend(entry.to_le_bytes());
+            }
+            self.section("table", &payload);
+        }
+    }
+
+    fn chunk(fill: u8) -> Vec<u8> {
+        (0..SECTOR).map(|i| fill.wrapping_add((i / 32) as u8)).collect()
+    }
+
+    #[test]
+    fn segments_join_compressed_and_raw_chunks() {
+        let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&chunk(0x10)).unwrap();
+        let compressed = encoder.finish().unwrap();
+        // У несжатого чанка в конце adler32 - он отбрасывается
+        let raw = |fill| [chunk(fill), vec![0xCC; 4]].concat();
+
+        let mut first = Segment::new(1);
+        let mut volume = [0u8; 24];
+        LittleEndian::write_u32(&mut volume[8..12], 1);
+        LittleEndian::write_u32(&mut volume[12..16], SECTOR as u32);
+        LittleEndian::write_u64(&mut volume[16..24], 3);
+        first.section("volume", &volume);
+        let data = first.section("sectors", &[compressed.clone(), raw(0x40)].concat());
+        first.table(data, &[CHUNK_COMPRESSED, compressed.len() as u32]);
+
+        let mut second = Segment::new(2);
+        let data = second.section("sectors", &raw(0x80));
+        second.table(data, &[0]);
+
+        let fixture = Fixture::new();
+        let path = fixture.write("image.E01", &first.finish("next"));
+        fixture.write("image.E02", &second.finish("done"));
+
+        let reader = EwfReader::open(&path).unwrap();
+        assert_eq!((reader.segment_count(), reader.media_size()), (2, 3 * SECTOR as u64));
+        assert_eq!(Fixture::read_image(&path), [chunk(0x10), chunk(0x40), chunk(0x80)].concat());
+    }
+
+    #[test]
+    fn missing_segment_is_reported() {
+        let mut first = Segment::new(1);
+        let mut volume = [0u8; 24];
+        LittleEndian::write_u32(&mut volume[8..12], 1);
+        LittleEndian::write_u32(&mut volume[12..16], SECTOR as u32);
+        first.section("volume", &volume);
+        let fixture = Fixture::new();
+        let path = fixture.write("image.E01", &first.finish("next"));
+        assert_eq!(EwfReader::open(&path).err().unwrap().kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn segment_names_continue_after_e99() {
+        let first = Path::new("case/disk.E01");
+        assert_eq!(segment_path(first, 2).unwrap(), Path::new("case/disk.E02"));
+        assert_eq!(segment_path(first, 100).unwrap(), Path::new("case/disk.EAA"));
+        assert_eq!(segment_path(Path::new("disk.e01"), 126).unwrap(), Path::new("disk.eba"));
+    }
+}
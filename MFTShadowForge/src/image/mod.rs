@@ -0,0 +1,99 @@
+pub mod bitlocker;
+pub mod block;
+pub mod ewf;
+#[cfg(feature = "agent")]
+pub mod remote;
+pub mod vhd;
+pub mod vhdx;
+pub mod vmdk;
+pub mod vss;
+
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+
+use tracing::info;
+
+use crate::i18n::common;
+
+/// Источник данных для extract: устройство, raw-образ или контейнер (E01, VHD, VHDX, VMDK),
+/// читаемый как непрерывный диск.
+pub trait ImageReader: Read + Seek {}
+
+impl<T: Read + Seek> ImageReader for T {}
+
+/// Открывает том или образ; формат контейнера определяется по сигнатуре, а не по расширению.
+pub fn open(path: &str) -> io::Result<Box<dyn ImageReader>> {
+    let mut file = File::open(path)?;
+    // Устройства (\\.\C:, \\.\PhysicalDrive0) читаются только целыми секторами - их не проверяем
+    if path.starts_with("\\\\.\\") || path.starts_with("\\\\?\\") {
+        return Ok(Box::new(file));
+    }
+
+    let mut magic = [0u8; 8];
+    if file.read_exact(&mut magic).is_err() {
+        magic = [0u8; 8];
+    }
+    file.seek(SeekFrom::Start(0))?;
+
+    if magic == ewf::EVF_SIGNATURE {
+        let reader = ewf::EwfReader::open(path)?;
+        info!("{}", common::ewf_image(reader.segment_count(), reader.media_size()));
+        return Ok(Box::new(reader));
+    }
+    if magic == vhdx::SIGNATURE {
+        let reader = vhdx::open(path)?;
+        info!("{}", common::vhdx_image(reader.size(), reader.allocated_blocks()));
+        return Ok(Box::new(reader));
+    }
+    if magic[..4] == vmdk::SPARSE_MAGIC || magic == vmdk::DESCRIPTOR_PREFIX {
+        let reader = vmdk::VmdkReader::open(path)?;
+        info!("{}", common::vmdk_image(reader.create_type(), reader.extent_count(), reader.size()));
+        return Ok(Box::new(reader));
+    }
+    if magic == vhd::COOKIE || vhd::has_footer(&mut file)? {
+        let (reader, kind) = vhd::open(path)?;
+        info!("{}", common::vhd_image(kind, reader.size()));
+        return Ok(Box::new(reader));
+    }
+    Ok(Box::new(file))
+}
+
+/// Каталог с файлами образов для тестов; удаляется вместе с содержимым.
+#[cfg(test)]
+pub(crate) struct Fixture {
+    marker: std::path::PathBuf,
+    dir: std::path::PathBuf,
+}
+
+#[cfg(test)]
+impl Fixture {
+    pub fn new() -> Self {
+        // Уникальное имя дает временный файл-маркер, каталог создается рядом с ним
+        let (marker, _) = crate::mft::utils::create_temp_file("mft_shadow_forge_test", "tmp").unwrap();
+        let dir = marker.with_extension("d");
+        std::fs::create_dir(&dir).unwrap();
+        Self { marker, dir }
+    }
+
+    /// Записывает файл в каталог и возвращает его путь.
+    pub fn write(&self, name: &str, bytes: &[u8]) -> String {
+        let path = self.dir.join(name);
+        std::fs::write(&path, bytes).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    /// Открывает образ через [`open`] и читает его целиком.
+    pub fn read_image(path: &str) -> Vec<u8> {
+        let mut data = Vec::new();
+        open(path).unwrap().read_to_end(&mut data).unwrap();
+        data
+    }
+}
+
+#[cfg(test)]
+impl Drop for Fixture {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+        let _ = std::fs::remove_file(&self.marker);
+    }
+}
@@ -0,0 +1,214 @@
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+
+use rustls::{ClientConnection, ServerConfig, ServerConnection, StreamOwned};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::commands::agent;
+use crate::i18n::common;
+
+/// Объем одного запроса чтения: сетевые задержки окупаются только крупными блоками.
+const CHUNK_SIZE: u64 = 1024 * 1024;
+/// Чтение устройства выравнивается по границе, кратной любому размеру сектора.
+const DEVICE_ALIGN: u64 = 4096;
+/// Больше агент не читает за один запрос, чтобы контроллер не мог занять всю память.
+const MAX_REQUEST: u64 = 16 * 1024 * 1024;
+
+/// Запрос контроллера (одна строка JSON).
+#[derive(Debug, Serialize, Deserialize)]
+struct ReadRequest {
+    offset: u64,
+    len: u64,
+}
+
+/// Ответ агента (одна строка JSON). За `Data` следуют ровно `size` байт;
+/// меньше запрошенного - только в конце устройства.
+#[derive(Debug, Serialize, Deserialize)]
+enum Reply {
+    Hello { device: String, size: Option<u64> },
+    Data { size: u64 },
+    Error(String),
+}
+
+fn send<W: Write, T: Serialize>(w: &mut W, value: &T) -> io::Result<()> {
+    serde_json::to_writer(&mut *w, value)?;
+    w.write_all(b"\n")?;
+    w.flush()
+}
+
+fn recv<R: BufRead, T: DeserializeOwned>(r: &mut R) -> io::Result<Option<T>> {
+    let mut line = String::new();
+    if r.read_line(&mut line)? == 0 { return Ok(None); }
+    serde_json::from_str(&line).map(Some).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Устройство на стороне агента, читаемое по сети как обычный образ.
+/// Последний полученный блок кешируется: разбор таблиц разделов и VBR читает мелко и рядом.
+pub struct RemoteReader {
+    stream: BufReader<StreamOwned<ClientConnection, TcpStream>>,
+    device: String,
+    size: Option<u64>,
+    pos: u64,
+    cache_offset: u64,
+    cache: Vec<u8>,
+}
+
+impl RemoteReader {
+    pub fn connect(addr: &str, cert: &str, key: &str, ca: &str) -> io::Result<Self> {
+        let tls = agent::connect(addr, cert, key, ca).map_err(io::Error::other)?;
+        let mut stream = BufReader::new(tls);
+        match recv(&mut stream)? {
+            Some(Reply::Hello { device, size }) => Ok(Self { stream, device, size, pos: 0, cache_offset: 0, cache: Vec::new() }),
+            Some(Reply::Error(e)) => Err(io::Error::other(common::agent_error(e))),
+            _ => Err(io::Error::new(io::ErrorKind::InvalidData, common::agent_no_hello())),
+        }
+    }
+
+    /// Устройство, которое раздает агент
+    pub fn device(&self) -> &str { &self.device }
+
+    /// Размер устройства, если агент смог его определить
+    pub fn size(&self) -> Option<u64> { self.size }
+
+    fn fetch(&mut self, offset: u64) -> io::Result<()> {
+        send(self.stream.get_mut(), &ReadRequest { offset, len: CHUNK_SIZE })?;
+        match recv(&mut self.stream)? {
+            Some(Reply::Data { size }) if size <= CHUNK_SIZE => {
+                self.cache.resize(size as usize, 0);
+                if let Err(e) = self.stream.read_exact(&mut self.cache) {
+                    self.cache.clear();
+                    return Err(e);
+                }
+                self.cache_offset = offset;
+                Ok(())
+            }
+            Some(Reply::Error(e)) => Err(io::Error::other(common::agent_error(e))),
+            Some(_) => Err(io::Error::new(io::ErrorKind::InvalidData, common::agent_bad_reply())),
+            None => Err(io::Error::new(io::ErrorKind::UnexpectedEof, common::agent_closed())),
+        }
+    }
+}
+
+impl Drop for RemoteReader {
+    fn drop(&mut self) {
+        let tls = self.stream.get_mut();
+        tls.conn.send_close_notify();
+        let _ = tls.flush();
+    }
+}
+
+impl Read for RemoteReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() { return Ok(0); }
+        let cached = self.pos >= self.cache_offset && self.pos < self.cache_offset + self.cache.len() as u64;
+        if !cached {
+            self.fetch(self.pos - self.pos % CHUNK_SIZE)?;
+        }
+        let start = (self.pos - self.cache_offset) as usize;
+        if start >= self.cache.len() { return Ok(0); }
+        let n = buf.len().min(self.cache.len() - start);
+        buf[..n].copy_from_slice(&self.cache[start..start + n]);
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl Seek for RemoteReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let new_pos = match pos {
+            SeekFrom::Start(p) => Some(p),
+            SeekFrom::Current(d) => self.pos.checked_add_signed(d),
+            SeekFrom::End(d) => {
+                let size = self.size.ok_or_else(|| io::Error::new(io::ErrorKind::Unsupported, common::agent_no_size()))?;
+                size.checked_add_signed(d)
+            }
+        };
+        self.pos = new_pos.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, common::beyond_device()))?;
+        Ok(self.pos)
+    }
+}
+
+/// Читает с устройства блок, выровненный по DEVICE_ALIGN, и возвращает запрошенную часть.
+/// Короткий результат - конец устройства.
+fn read_device<R: Read + Seek>(device: &mut R, offset: u64, len: u64) -> io::Result<Vec<u8>> {
+    let start = offset - offset % DEVICE_ALIGN;
+    let end = offset.checked_add(len).and_then(|e| e.checked_next_multiple_of(DEVICE_ALIGN))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, common::beyond_device()))?;
+    let mut buf = vec![0u8; (end - start) as usize];
+    device.seek(SeekFrom::Start(start))?;
+    let mut filled = 0;
+    while filled < buf.len() {
+        match device.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            // Устройства Windows отвечают ошибкой на чтение за концом тома
+            Err(_) if filled > 0 => break,
+            Err(e) => return Err(e),
+        }
+    }
+    let skip = ((offset - start) as usize).min(filled);
+    let take = (len as usize).min(filled - skip);
+    Ok(buf[skip..skip + take].to_vec())
+}
+
+fn serve_session(config: Arc<ServerConfig>, tcp: TcpStream, device_path: &str) -> io::Result<u64> {
+    let conn = ServerConnection::new(config).map_err(io::Error::other)?;
+    let mut stream = BufReader::new(StreamOwned::new(conn, tcp));
+
+    let mut device = match super::open(device_path) {
+        Ok(d) => d,
+        Err(e) => {
+            send(stream.get_mut(), &Reply::Error(format!("{}: {}", device_path, e)))?;
+            return Err(e);
+        }
+    };
+    let size = device.seek(SeekFrom::End(0)).ok().filter(|s| *s > 0);
+    send(stream.get_mut(), &Reply::Hello { device: device_path.to_string(), size })?;
+
+    let mut sent = 0;
+    while let Some(request) = recv::<_, ReadRequest>(&mut stream)? {
+        if request.len > MAX_REQUEST {
+            send(stream.get_mut(), &Reply::Error(common::request_too_large(request.len, MAX_REQUEST)))?;
+            continue;
+        }
+        match read_device(&mut device, request.offset, request.len) {
+            Ok(data) => {
+                send(stream.get_mut(), &Reply::Data { size: data.len() as u64 })?;
+                stream.get_mut().write_all(&data)?;
+                stream.get_mut().flush()?;
+                sent += data.len() as u64;
+            }
+            Err(e) => send(stream.get_mut(), &Reply::Error(common::device_read_failed(request.offset, e)))?,
+        }
+    }
+    let tls = stream.get_mut();
+    tls.conn.send_close_notify();
+    tls.flush()?;
+    Ok(sent)
+}
+
+/// Режим `extract --listen`: раздает контроллерам только чтение одного устройства.
+/// Сеансы обслуживаются по одному, устройство открывается заново для каждого.
+pub fn serve(listen: &str, device_path: &str, cert: &str, key: &str, ca: &str) -> io::Result<()> {
+    let config = agent::server_config(cert, key, ca).map_err(io::Error::other)?;
+    let listener = TcpListener::bind(listen)?;
+    info!("{}", common::serving(device_path, listen));
+
+    for tcp in listener.incoming() {
+        let tcp = match tcp {
+            Ok(t) => t,
+            Err(e) => { warn!("{}", common::incoming_failed(e)); continue; }
+        };
+        let peer = tcp.peer_addr().map(|a| a.to_string()).unwrap_or_default();
+        info!("{}", common::controller_connected(&peer));
+        match serve_session(config.clone(), tcp, device_path) {
+            Ok(sent) => info!("{}", common::session_finished(&peer, sent)),
+            Err(e) => warn!("{}", common::session_aborted(&peer, e)),
+        }
+    }
+    Ok(())
+}
\ No newline at end of file
@@ -0,0 +1,159 @@
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+
+use byteorder::{BigEndian, ByteOrder};
+
+use super::block::BlockMapReader;
+use crate::i18n::common;
+
+/// Сигнатура футера VHD (в конце файла; у динамического - копия в начале).
+pub const COOKIE: [u8; 8] = *b"conectix";
+const DYNAMIC_COOKIE: &[u8] = b"cxsparse";
+const FOOTER_SIZE: usize = 512;
+const DYNAMIC_HEADER_SIZE: usize = 1024;
+const SECTOR: u64 = 512;
+
+const DISK_TYPE_FIXED: u32 = 2;
+const DISK_TYPE_DYNAMIC: u32 = 3;
+const DISK_TYPE_DIFFERENCING: u32 = 4;
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Есть ли в конце файла футер VHD (фиксированный VHD в начале ничем не отличается от raw).
+pub fn has_footer(file: &mut File) -> io::Result<bool> {
+    let len = file.seek(SeekFrom::End(0))?;
+    if len < FOOTER_SIZE as u64 { return Ok(false); }
+    let mut cookie = [0u8; 8];
+    file.seek(SeekFrom::Start(len - FOOTER_SIZE as u64))?;
+    file.read_exact(&mut cookie)?;
+    file.seek(SeekFrom::Start(0))?;
+    Ok(cookie == COOKIE)
+}
+
+/// Открывает VHD (фиксированный или динамический); тип диска - для вывода.
+pub fn open(path: &str) -> io::Result<(BlockMapReader, &'static str)> {
+    let mut file = File::open(path)?;
+    let len = file.seek(SeekFrom::End(0))?;
+
+    // Копия футера в начале есть только у динамических дисков и надежнее хвоста (его могли обрезать)
+    let mut footer = [0u8; FOOTER_SIZE];
+    file.seek(SeekFrom::Start(0))?;
+    file.read_exact(&mut footer)?;
+    if footer[..8] != COOKIE {
+        file.seek(SeekFrom::Start(len.saturating_sub(FOOTER_SIZE as u64)))?;
+        file.read_exact(&mut footer)?;
+    }
+    if footer[..8] != COOKIE { return Err(invalid(common::vhd_no_footer())); }
+
+    let data_offset = BigEndian::read_u64(&footer[16..24]);
+    let size = BigEndian::read_u64(&footer[48..56]);
+    let disk_type = BigEndian::read_u32(&footer[60..64]);
+
+    match disk_type {
+        DISK_TYPE_FIXED => {
+            // Данные лежат подряд с начала файла, футер - после них
+            let reader = BlockMapReader::new(vec![file], size.max(1), size, vec![Some((0, 0))]);
+            Ok((reader, "fixed"))
+        }
+        DISK_TYPE_DYNAMIC => {
+            let mut header = [0u8; DYNAMIC_HEADER_SIZE];
+            file.seek(SeekFrom::Start(data_offset))?;
+            file.read_exact(&mut header)?;
+            if &header[..8] != DYNAMIC_COOKIE { return Err(invalid(common::vhd_no_dynamic_header())); }
+
+            let table_offset = BigEndian::read_u64(&header[16..24]);
+            let entries = BigEndian::read_u32(&header[28..32]) as usize;
+            let block_size = BigEndian::read_u32(&header[32..36]) as u64;
+            if block_size == 0 || !block_size.is_multiple_of(SECTOR) {
+                return Err(invalid(common::vhd_bad_block_size(block_size)));
+            }
+
+            let mut bat = vec![0u8; entries * 4];
+            file.seek(SeekFrom::Start(table_offset))?;
+            file.read_exact(&mut bat)?;
+
+            // Перед данными блока - битовая карта секторов, выровненная по сектору
+            let bitmap_size = (block_size / SECTOR).div_ceil(8).div_ceil(SECTOR) * SECTOR;
+            let blocks = bat.chunks_exact(4)
+                .map(BigEndian::read_u32)
+                .map(|sector| (sector != u32::MAX).then(|| (0, sector as u64 * SECTOR + bitmap_size)))
+                .collect();
+            Ok((BlockMapReader::new(vec![file], block_size, size, blocks), "dynamic"))
+        }
+        DISK_TYPE_DIFFERENCING => Err(invalid(common::differencing_unsupported("VHD"))),
+        other => Err(invalid(common::vhd_unknown_type(other))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image::Fixture;
+
+    fn footer(data_offset: u64, size: u64, disk_type: u32) -> Vec<u8> {
+        let mut footer = vec![0u8; FOOTER_SIZE];
+        footer[..8].copy_from_slice(&COOKIE);
+        BigEndian::write_u64(&mut footer[16..24], data_offset);
+        BigEndian::write_u64(&mut footer[48..56], size);
+        BigEndian::write_u32(&mut footer[60..64], disk_type);
+        footer
+    }
+
+    #[test]
+    fn fixed_disk_is_data_before_footer() {
+        let data: Vec<u8> = (0..2048u32).map(|i| i as u8).collect();
+        let mut image = data.clone();
+        image.extend(footer(u64::MAX, data.len() as u64, DISK_TYPE_FIXED));
+
+        let fixture = Fixture::new();
+        let path = fixture.write("fixed.vhd", &image);
+        assert!(has_footer(&mut File::open(&path).unwrap()).unwrap());
+        assert_eq!(open(&path).unwrap().1, "fixed");
+        assert_eq!(Fixture::read_image(&path), data);
+    }
+
+    #[test]
+    fn dynamic_disk_skips_sector_bitmaps() {
+        // Блок 4 КБ (битовая карта - один сектор), три блока: выделен, пуст, выделен
+        let block = 4096usize;
+        let mut image = footer(512, 3 * block as u64, DISK_TYPE_DYNAMIC);
+        let mut header = vec![0u8; DYNAMIC_HEADER_SIZE];
+        header[..8].copy_from_slice(DYNAMIC_COOKIE);
+        BigEndian::write_u64(&mut header[16..24], 1536);
+        BigEndian::write_u32(&mut header[28..32], 3);
+        BigEndian::write_u32(&mut header[32..36], block as u32);
+        image.extend(header);
+        let mut bat = vec![0u8; 512];
+        for (i, sector) in [4u32, u32::MAX, 13].into_iter().enumerate() {
+            BigEndian::write_u32(&mut bat[i * 4..i * 4 + 4], sector);
+        }
+        image.extend(bat);
+        let second: Vec<u8> = (0..block).map(|i| (i % 251) as u8).collect();
+        for data in [vec![0xAA; block], second.clone()] {
+            image.extend([0xFF; 512]);
+            image.extend(data);
+        }
+        image.extend(footer(512, 3 * block as u64, DISK_TYPE_DYNAMIC));
+
+        let fixture = Fixture::new();
+        let path = fixture.write("dynamic.vhd", &image);
+        let (reader, kind) = open(&path).unwrap();
+        assert_eq!((kind, reader.size(), reader.allocated_blocks()), ("dynamic", 3 * block as u64, 2));
+
+        let mut expected = vec![0xAA; block];
+        expected.extend(vec![0; block]);
+        expected.extend(second);
+        assert_eq!(Fixture::read_image(&path), expected);
+    }
+
+    #[test]
+    fn differencing_disk_is_rejected() {
+        let mut image = footer(512, 4096, DISK_TYPE_DIFFERENCING);
+        image.extend(vec![0u8; 1024]);
+        let fixture = Fixture::new();
+        let err = open(&fixture.write("child.vhd", &image)).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,222 @@
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+
+use byteorder::{ByteOrder, LittleEndian};
+use tracing::warn;
+
+use super::block::BlockMapReader;
+use crate::i18n::common;
+
+/// Идентификатор файла VHDX (смещение 0).
+pub const SIGNATURE: [u8; 8] = *b"vhdxfile";
+
+const KB: u64 = 1024;
+const MB: u64 = 1024 * KB;
+const HEADER_OFFSETS: [u64; 2] = [64 * KB, 128 * KB];
+const REGION_TABLE_OFFSET: u64 = 192 * KB;
+
+// GUID в порядке байт на диске (первые три поля - little-endian)
+const BAT_REGION: [u8; 16] = [0x66, 0x77, 0xC2, 0x2D, 0x23, 0xF6, 0x00, 0x42, 0x9D, 0x64, 0x11, 0x5E, 0x9B, 0xFD, 0x4A, 0x08];
+const METADATA_REGION: [u8; 16] = [0x06, 0xA2, 0x7C, 0x8B, 0x90, 0x47, 0x9A, 0x4B, 0xB8, 0xFE, 0x57, 0x5F, 0x05, 0x0F, 0x88, 0x6E];
+const FILE_PARAMETERS: [u8; 16] = [0x37, 0x67, 0xA1, 0xCA, 0x36, 0xFA, 0x43, 0x4D, 0xB3, 0xB6, 0x33, 0xF0, 0xAA, 0x44, 0xE7, 0x6B];
+const VIRTUAL_DISK_SIZE: [u8; 16] = [0x24, 0x42, 0xA5, 0x2F, 0x1B, 0xCD, 0x76, 0x48, 0xB2, 0x11, 0x5D, 0xBE, 0xD8, 0x3B, 0xF4, 0xB8];
+const LOGICAL_SECTOR_SIZE: [u8; 16] = [0x1D, 0xBF, 0x41, 0x81, 0x6F, 0xA9, 0x09, 0x47, 0xBA, 0x47, 0xF2, 0x33, 0xA8, 0xFA, 0xAB, 0x5F];
+
+const FILE_PARAMETERS_HAS_PARENT: u32 = 0x2;
+
+// Состояния записи BAT для блока данных
+const PAYLOAD_BLOCK_FULLY_PRESENT: u64 = 6;
+const PAYLOAD_BLOCK_PARTIALLY_PRESENT: u64 = 7;
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn read_at(file: &mut File, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+    let mut buf = vec![0u8; len];
+    file.seek(SeekFrom::Start(offset))?;
+    file.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+/// Открывает VHDX (фиксированный или динамический). Журнал не проигрывается:
+/// если он не пуст, выводится предупреждение - последние записи могут быть не видны.
+pub fn open(path: &str) -> io::Result<BlockMapReader> {
+    let mut file = File::open(path)?;
+
+    // Действующий заголовок - с большим sequence number
+    let mut current: Option<(u64, Vec<u8>)> = None;
+    for offset in HEADER_OFFSETS {
+        let header = read_at(&mut file, offset, 80)?;
+        if &header[..4] != b"head" { continue; }
+        let sequence = LittleEndian::read_u64(&header[8..16]);
+        if current.as_ref().is_none_or(|c| sequence > c.0) {
+            current = Some((sequence, header));
+        }
+    }
+    let (_, header) = current.ok_or_else(|| invalid(common::vhdx_no_headers()))?;
+    if header[48..64].iter().any(|b| *b != 0) {
+        warn!("{}", common::vhdx_log_not_empty());
+    }
+
+    let regions = read_at(&mut file, REGION_TABLE_OFFSET, 64 * KB as usize)?;
+    if &regions[..4] != b"regi" { return Err(invalid(common::vhdx_no_region_table())); }
+    let region_count = LittleEndian::read_u32(&regions[8..12]) as usize;
+    let mut bat_region: Option<(u64, u32)> = None;
+    let mut metadata_region: Option<(u64, u32)> = None;
+    for entry in regions[16..].chunks_exact(32).take(region_count) {
+        let location = (LittleEndian::read_u64(&entry[16..24]), LittleEndian::read_u32(&entry[24..28]));
+        if entry[..16] == BAT_REGION { bat_region = Some(location); }
+        if entry[..16] == METADATA_REGION { metadata_region = Some(location); }
+    }
+    let (bat_offset, bat_len) = bat_region.ok_or_else(|| invalid(common::vhdx_no_bat()))?;
+    let (meta_offset, meta_len) = metadata_region.ok_or_else(|| invalid(common::vhdx_no_metadata_region()))?;
+
+    let metadata = read_at(&mut file, meta_offset, meta_len as usize)?;
+    if &metadata[..8] != b"metadata" { return Err(invalid(common::vhdx_no_metadata_header())); }
+    let item_count = LittleEndian::read_u16(&metadata[10..12]) as usize;
+    let item = |guid: &[u8; 16]| -> Option<&[u8]> {
+        metadata[32..].chunks_exact(32).take(item_count).find(|e| e[..16] == *guid).and_then(|e| {
+            let offset = LittleEndian::read_u32(&e[16..20]) as usize;
+            let len = LittleEndian::read_u32(&e[20..24]) as usize;
+            metadata.get(offset..offset + len)
+        })
+    };
+
+    let params = item(&FILE_PARAMETERS).filter(|p| p.len() >= 8)
+        .ok_or_else(|| invalid(common::vhdx_missing_item("File Parameters")))?;
+    let block_size = LittleEndian::read_u32(&params[0..4]) as u64;
+    if LittleEndian::read_u32(&params[4..8]) & FILE_PARAMETERS_HAS_PARENT != 0 {
+        return Err(invalid(common::differencing_unsupported("VHDX")));
+    }
+    let size = item(&VIRTUAL_DISK_SIZE).filter(|p| p.len() >= 8).map(LittleEndian::read_u64)
+        .ok_or_else(|| invalid(common::vhdx_missing_item("Virtual Disk Size")))?;
+    let sector_size = item(&LOGICAL_SECTOR_SIZE).filter(|p| p.len() >= 4).map(LittleEndian::read_u32)
+        .ok_or_else(|| invalid(common::vhdx_missing_item("Logical Sector Size")))? as u64;
+    if block_size == 0 || sector_size == 0 {
+        return Err(invalid(common::vhdx_bad_parameters(block_size, sector_size)));
+    }
+
+    // После каждых chunk_ratio записей блоков данных в BAT идет запись битовой карты секторов
+    let chunk_ratio = ((1u64 << 23) * sector_size / block_size).max(1);
+    let block_count = size.div_ceil(block_size);
+    let bat = read_at(&mut file, bat_offset, bat_len as usize)?;
+    let blocks = (0..block_count).map(|block| {
+        let index = (block + block / chunk_ratio) as usize;
+        let entry = bat.get(index * 8..index * 8 + 8).map(LittleEndian::read_u64).unwrap_or(0);
+        let state = entry & 0x7;
+        let offset = (entry >> 20) * MB;
+        matches!(state, PAYLOAD_BLOCK_FULLY_PRESENT | PAYLOAD_BLOCK_PARTIALLY_PRESENT).then_some((0, offset))
+    }).collect();
+
+    Ok(BlockMapReader::new(vec![file], block_size, size, blocks))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image::Fixture;
+
+    const METADATA_OFFSET: usize = 256 * KB as usize;
+    const BAT_OFFSET: usize = 320 * KB as usize;
+
+    /// Минимальный VHDX: заголовок, таблица регионов, метаданные и BAT; блоки данных - по `data`
+    /// (смещение в файле в МБ, содержимое начала блока).
+    fn vhdx(block_size: u32, sector_size: u32, size: u64, bat: &[u64], data: &[(u64, &[u8])]) -> Vec<u8> {
+        let file_len = data.iter().map(|(mb, bytes)| *mb as usize * MB as usize + bytes.len()).max().unwrap_or(0);
+        let mut image = vec![0u8; file_len.max(BAT_OFFSET + 64 * KB as usize)];
+        image[..8].copy_from_slice(&SIGNATURE);
+        for (offset, sequence) in HEADER_OFFSETS.into_iter().zip([1u64, 2]) {
+            let header = &mut image[offset as usize..];
+            header[..4].copy_from_slice(b"head");
+            LittleEndian::write_u64(&mut header[8..16], sequence);
+        }
+
+        let regions = &mut image[REGION_TABLE_OFFSET as usize..];
+        regions[..4].copy_from_slice(b"regi");
+        LittleEndian::write_u32(&mut regions[8..12], 2);
+        for (i, (guid, offset)) in [(BAT_REGION, BAT_OFFSET), (METADATA_REGION, METADATA_OFFSET)].into_iter().enumerate() {
+            let entry = &mut regions[16 + i * 32..];
+            entry[..16].copy_from_slice(&guid);
+            LittleEndian::write_u64(&mut entry[16..24], offset as u64);
+            LittleEndian::write_u32(&mut entry[24..28], 64 * KB as u32);
+        }
+
+        let metadata = &mut image[METADATA_OFFSET..];
+        metadata[..8].copy_from_slice(b"metadata");
+        LittleEndian::write_u16(&mut metadata[10..12], 3);
+        let mut values = [0u8; 20];
+        LittleEndian::write_u32(&mut values[0..4], block_size);
+        LittleEndian::write_u64(&mut values[8..16], size);
+        LittleEndian::write_u32(&mut values[16..20], sector_size);
+        for (i, (guid, at, len)) in [(FILE_PARAMETERS, 0, 8), (VIRTUAL_DISK_SIZE, 8, 8), (LOGICAL_SECTOR_SIZE, 16, 4)].into_iter().enumerate() {
+            let entry = &mut metadata[32 + i * 32..];
+            entry[..16].copy_from_slice(&guid);
+            LittleEndian::write_u32(&mut entry[16..20], 4096 + at as u32);
+            LittleEndian::write_u32(&mut entry[20..24], len as u32);
+        }
+        metadata[4096..4116].copy_from_slice(&values);
+
+        for (i, entry) in bat.iter().enumerate() {
+            LittleEndian::write_u64(&mut image[BAT_OFFSET + i * 8..], *entry);
+        }
+        for (mb, bytes) in data {
+            let at = *mb as usize * MB as usize;
+            image[at..at + bytes.len()].copy_from_slice(bytes);
+        }
+        image
+    }
+
+    fn present(mb: u64) -> u64 {
+        (mb << 20) | PAYLOAD_BLOCK_FULLY_PRESENT
+    }
+
+    #[test]
+    fn small_disk_maps_blocks() {
+        let block = MB as u32;
+        let image = vhdx(block, 512, 3 * MB, &[present(1), 0, (2 << 20) | PAYLOAD_BLOCK_PARTIALLY_PRESENT],
+            &[(1, &[0x11; 4096]), (2, &vec![0x22; MB as usize])]);
+        let fixture = Fixture::new();
+        let path = fixture.write("small.vhdx", &image);
+        let reader = open(&path).unwrap();
+        assert_eq!((reader.size(), reader.allocated_blocks()), (3 * MB, 2));
+
+        let data = Fixture::read_image(&path);
+        assert_eq!(data.len() as u64, 3 * MB);
+        assert!(data[..4096].iter().all(|b| *b == 0x11));
+        assert!(data[MB as usize..2 * MB as usize].iter().all(|b| *b == 0));
+        assert!(data[2 * MB as usize..].iter().all(|b| *b == 0x22));
+    }
+
+    #[test]
+    fn bat_skips_sector_bitmap_entries() {
+        // Блок 256 МБ, сектор 512: chunk_ratio = 2^23 * 512 / 2^28 = 16, запись 16 в BAT - битовая карта
+        let block = 256 * MB;
+        let mut bat = vec![0u64; 19];
+        bat[0] = present(1);
+        bat[15] = present(2);
+        bat[16] = present(3);
+        bat[17] = present(4);
+        let image = vhdx(block as u32, 512, 20 * block, &bat, &[(1, &[0x11; 16]), (2, &[0x22; 16]), (3, &[0x33; 16]), (4, &[0x44; 16])]);
+        let fixture = Fixture::new();
+        let path = fixture.write("large.vhdx", &image);
+        let mut reader = open(&path).unwrap();
+        assert_eq!(reader.allocated_blocks(), 3);
+
+        let mut read_block = |index: u64| {
+            let mut buf = [0u8; 16];
+            reader.seek(SeekFrom::Start(index * block)).unwrap();
+            reader.read_exact(&mut buf).unwrap();
+            buf[0]
+        };
+        assert_eq!([0, 15, 16, 17].map(&mut read_block), [0x11, 0x22, 0x44, 0]);
+    }
+
+    #[test]
+    fn differencing_disk_is_rejected() {
+        let mut image = vhdx(MB as u32, 512, MB, &[0], &[]);
+        LittleEndian::write_u32(&mut image[METADATA_OFFSET + 4100..], FILE_PARAMETERS_HAS_PARENT);
+        let fixture = Fixture::new();
+        assert_eq!(open(&fixture.write("child.vhdx", &image)).err().unwrap().kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,469 @@
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use byteorder::{ByteOrder, LittleEndian};
+use flate2::read::ZlibDecoder;
+
+use crate::i18n::common;
+
+/// Сигнатура sparse-экстента VMDK (монолитный диск Workstation, streamOptimized, части split-диска).
+pub const SPARSE_MAGIC: [u8; 4] = *b"KDMV";
+/// Начало текстового дескриптора VMDK.
+pub const DESCRIPTOR_PREFIX: [u8; 8] = *b"# Disk D";
+
+const SECTOR: u64 = 512;
+const SPARSE_HEADER_SIZE: usize = 512;
+const MAX_DESCRIPTOR_SIZE: u64 = 1024 * 1024;
+const FLAG_COMPRESSED_GRAINS: u32 = 1 << 16;
+const GD_AT_END: u64 = u64::MAX;
+/// Маркер сжатого грейна: LBA (8 байт) и длина данных (4 байта).
+const GRAIN_MARKER_SIZE: usize = 12;
+/// parentCID диска без родителя.
+const NO_PARENT: &str = "ffffffff";
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Таблицы грейнов sparse-экстента; сами таблицы читаются по требованию.
+struct SparseExtent {
+    file: usize,
+    grain_size: u64,
+    gtes_per_gt: u64,
+    directory: Vec<u32>,
+    compressed: bool,
+}
+
+enum ExtentKind {
+    Flat { file: usize, offset: u64 },
+    Sparse(SparseExtent),
+    Zero,
+}
+
+struct Extent {
+    start: u64,
+    size: u64,
+    kind: ExtentKind,
+}
+
+/// Чтение VMDK (monolithicSparse, streamOptimized, split sparse/flat, VMFS flat) как непрерывного диска.
+/// Невыделенные грейны читаются как нули; последняя таблица грейнов и последний сжатый грейн кешируются.
+pub struct VmdkReader {
+    files: Vec<File>,
+    extents: Vec<Extent>,
+    size: u64,
+    create_type: String,
+    position: u64,
+    cached_table: Option<((usize, usize), Vec<u32>)>,
+    cached_grain: Option<((usize, u64), Vec<u8>)>,
+}
+
+/// Заголовок sparse-экстента (поля, нужные для чтения).
+struct SparseHeader {
+    flags: u32,
+    capacity: u64,
+    grain_size: u64,
+    descriptor_offset: u64,
+    descriptor_size: u64,
+    gtes_per_gt: u64,
+    gd_offset: u64,
+}
+
+fn parse_sparse_header(buf: &[u8]) -> io::Result<SparseHeader> {
+    if buf[..4] != SPARSE_MAGIC { return Err(invalid(common::vmdk_no_sparse_header())); }
+    Ok(SparseHeader {
+        flags: LittleEndian::read_u32(&buf[8..12]),
+        capacity: LittleEndian::read_u64(&buf[12..20]),
+        grain_size: LittleEndian::read_u64(&buf[20..28]),
+        descriptor_offset: LittleEndian::read_u64(&buf[28..36]),
+        descriptor_size: LittleEndian::read_u64(&buf[36..44]),
+        gtes_per_gt: LittleEndian::read_u32(&buf[44..48]) as u64,
+        gd_offset: LittleEndian::read_u64(&buf[56..64]),
+    })
+}
+
+fn read_sparse_header(file: &mut File) -> io::Result<SparseHeader> {
+    let mut buf = [0u8; SPARSE_HEADER_SIZE];
+    file.seek(SeekFrom::Start(0))?;
+    file.read_exact(&mut buf)?;
+    let header = parse_sparse_header(&buf)?;
+    if header.gd_offset != GD_AT_END { return Ok(header); }
+
+    // streamOptimized: каталог грейнов пишется в конце, актуальная копия заголовка - в футере
+    // (перед маркером конца потока)
+    let len = file.seek(SeekFrom::End(0))?;
+    file.seek(SeekFrom::Start(len.saturating_sub(2 * SECTOR)))?;
+    file.read_exact(&mut buf)?;
+    let footer = parse_sparse_header(&buf)
+        .map_err(|_| invalid(common::vmdk_no_footer()))?;
+    if footer.gd_offset == GD_AT_END { return Err(invalid(common::vmdk_no_directory())); }
+    Ok(SparseHeader { descriptor_offset: header.descriptor_offset, descriptor_size: header.descriptor_size, ..footer })
+}
+
+fn read_embedded_descriptor(file: &mut File, header: &SparseHeader) -> io::Result<Option<String>> {
+    if header.descriptor_offset == 0 || header.descriptor_size == 0 { return Ok(None); }
+    let len = (header.descriptor_size * SECTOR).min(MAX_DESCRIPTOR_SIZE) as usize;
+    let mut buf = vec![0u8; len];
+    file.seek(SeekFrom::Start(header.descriptor_offset * SECTOR))?;
+    file.read_exact(&mut buf)?;
+    let end = buf.iter().position(|b| *b == 0).unwrap_or(buf.len());
+    Ok(Some(String::from_utf8_lossy(&buf[..end]).into_owned()))
+}
+
+/// Значение `key="value"` (или `key=value`) из дескриптора.
+fn descriptor_value<'a>(descriptor: &'a str, key: &str) -> Option<&'a str> {
+    descriptor.lines().find_map(|line| {
+        let (k, v) = line.split_once('=')?;
+        (k.trim() == key).then(|| v.trim().trim_matches('"'))
+    })
+}
+
+fn check_no_parent(descriptor: &str) -> io::Result<()> {
+    match descriptor_value(descriptor, "parentCID") {
+        Some(cid) if !cid.eq_ignore_ascii_case(NO_PARENT) => Err(invalid(common::vmdk_differencing())),
+        _ => Ok(()),
+    }
+}
+
+impl VmdkReader {
+    /// Открывает VMDK по дескриптору или по монолитному sparse-файлу.
+    pub fn open(path: &str) -> io::Result<Self> {
+        let mut file = File::open(path)?;
+        let mut magic = [0u8; 4];
+        file.read_exact(&mut magic)?;
+
+        let mut reader = Self {
+            files: Vec::new(),
+            extents: Vec::new(),
+            size: 0,
+            create_type: String::new(),
+            position: 0,
+            cached_table: None,
+            cached_grain: None,
+        };
+
+        if magic == SPARSE_MAGIC {
+            // Монолитный диск: единственный экстент - сам файл, дескриптор встроен
+            let header = read_sparse_header(&mut file)?;
+            if let Some(descriptor) = read_embedded_descriptor(&mut file, &header)? {
+                check_no_parent(&descriptor)?;
+                reader.create_type = descriptor_value(&descriptor, "createType").unwrap_or("monolithicSparse").to_string();
+            }
+            reader.files.push(file);
+            reader.add_sparse_extent(0, header)?;
+        } else {
+            let len = file.seek(SeekFrom::End(0))?;
+            if len > MAX_DESCRIPTOR_SIZE { return Err(invalid(common::vmdk_descriptor_too_large())); }
+            let mut descriptor = String::new();
+            file.seek(SeekFrom::Start(0))?;
+            file.read_to_string(&mut descriptor)
+                .map_err(|_| invalid(common::vmdk_descriptor_not_text()))?;
+            check_no_parent(&descriptor)?;
+            reader.create_type = descriptor_value(&descriptor, "createType").unwrap_or_default().to_string();
+            let base = Path::new(path).parent().unwrap_or(Path::new(""));
+            for line in descriptor.lines() {
+                reader.add_descriptor_extent(base, line.trim())?;
+            }
+        }
+
+        if reader.extents.is_empty() { return Err(invalid(common::vmdk_no_extents())); }
+        Ok(reader)
+    }
+
+    /// Размер виртуального диска, байт.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Значение createType из дескриптора (monolithicSparse, twoGbMaxExtentSparse, vmfs, ...).
+    pub fn create_type(&self) -> &str {
+        &self.create_type
+    }
+
+    pub fn extent_count(&self) -> usize {
+        self.extents.len()
+    }
+
+    /// Строка экстента: `RW 4192256 SPARSE "disk-s001.vmdk"`, `RW 2097152 FLAT "disk-flat.vmdk" 0`, `RW 100 ZERO`.
+    fn add_descriptor_extent(&mut self, base: &Path, line: &str) -> io::Result<()> {
+        let mut parts = line.splitn(3, ' ');
+        let (Some(access), Some(sectors), Some(rest)) = (parts.next(), parts.next(), parts.next()) else { return Ok(()) };
+        if !matches!(access, "RW" | "RDONLY" | "NOACCESS") { return Ok(()); }
+        let sectors: u64 = sectors.parse().map_err(|_| invalid(common::vmdk_bad_extent_line(line)))?;
+        let (kind, rest) = rest.split_once(' ').unwrap_or((rest, ""));
+        let start = self.size;
+
+        if kind == "ZERO" {
+            self.extents.push(Extent { start, size: sectors * SECTOR, kind: ExtentKind::Zero });
+            self.size += sectors * SECTOR;
+            return Ok(());
+        }
+
+        let rest = rest.trim();
+        let (name, offset) = match rest.strip_prefix('"').and_then(|r| r.split_once('"')) {
+            Some((name, tail)) => (name, tail.trim()),
+            None => return Err(invalid(common::vmdk_bad_extent_line(line))),
+        };
+        let extent_path = base.join(name);
+        let mut file = File::open(&extent_path)
+            .map_err(|e| io::Error::new(e.kind(), common::vmdk_extent(extent_path.display(), &e)))?;
+
+        match kind {
+            "FLAT" | "VMFS" => {
+                let offset: u64 = if offset.is_empty() { 0 } else {
+                    offset.parse().map_err(|_| invalid(common::vmdk_bad_extent_offset(line)))?
+                };
+                self.files.push(file);
+                self.extents.push(Extent { start, size: sectors * SECTOR, kind: ExtentKind::Flat { file: self.files.len() - 1, offset: offset * SECTOR } });
+                self.size += sectors * SECTOR;
+            }
+            "SPARSE" => {
+                let header = read_sparse_header(&mut file)?;
+                self.files.push(file);
+                self.add_sparse_extent(self.files.len() - 1, SparseHeader { capacity: sectors, ..header })?;
+            }
+            other => return Err(invalid(common::vmdk_extent_type(other))),
+        }
+        Ok(())
+    }
+
+    fn add_sparse_extent(&mut self, file_index: usize, header: SparseHeader) -> io::Result<()> {
+        if header.grain_size == 0 || header.gtes_per_gt == 0 {
+            return Err(invalid(common::vmdk_bad_sparse(header.grain_size, header.gtes_per_gt)));
+        }
+        let grain_size = header.grain_size * SECTOR;
+        let size = header.capacity * SECTOR;
+        let tables = size.div_ceil(grain_size).div_ceil(header.gtes_per_gt) as usize;
+
+        let file = &mut self.files[file_index];
+        let mut directory = vec![0u8; tables * 4];
+        file.seek(SeekFrom::Start(header.gd_offset * SECTOR))?;
+        file.read_exact(&mut directory)?;
+
+        self.extents.push(Extent {
+            start: self.size,
+            size,
+            kind: ExtentKind::Sparse(SparseExtent {
+                file: file_index,
+                grain_size,
+                gtes_per_gt: header.gtes_per_gt,
+                directory: directory.chunks_exact(4).map(LittleEndian::read_u32).collect(),
+                compressed: header.flags & FLAG_COMPRESSED_GRAINS != 0,
+            }),
+        });
+        self.size += size;
+        Ok(())
+    }
+
+    /// Сектор грейна в файле экстента; None - грейн не выделен (или помечен как нулевой).
+    fn grain_sector(&mut self, extent: usize, grain: u64) -> io::Result<Option<u64>> {
+        let ExtentKind::Sparse(sparse) = &self.extents[extent].kind else { return Ok(None) };
+        let table = (grain / sparse.gtes_per_gt) as usize;
+        let entry = (grain % sparse.gtes_per_gt) as usize;
+        let table_sector = sparse.directory.get(table).copied().unwrap_or(0) as u64;
+        if table_sector == 0 { return Ok(None); }
+
+        if self.cached_table.as_ref().map(|c| c.0) != Some((extent, table)) {
+            let mut buf = vec![0u8; sparse.gtes_per_gt as usize * 4];
+            let file = &mut self.files[sparse.file];
+            file.seek(SeekFrom::Start(table_sector * SECTOR))?;
+            file.read_exact(&mut buf)?;
+            self.cached_table = Some(((extent, table), buf.chunks_exact(4).map(LittleEndian::read_u32).collect()));
+        }
+        let sector = self.cached_table.as_ref().and_then(|c| c.1.get(entry)).copied().unwrap_or(0);
+        // 0 - грейн не выделен, 1 - явно обнуленный грейн
+        Ok((sector > 1).then_some(sector as u64))
+    }
+
+    fn read_compressed_grain(&mut self, file: usize, sector: u64, grain_size: u64) -> io::Result<&[u8]> {
+        if self.cached_grain.as_ref().map(|c| c.0) != Some((file, sector)) {
+            let file_handle = &mut self.files[file];
+            let mut marker = [0u8; GRAIN_MARKER_SIZE];
+            file_handle.seek(SeekFrom::Start(sector * SECTOR))?;
+            file_handle.read_exact(&mut marker)?;
+            let mut raw = vec![0u8; LittleEndian::read_u32(&marker[8..12]) as usize];
+            file_handle.read_exact(&mut raw)?;
+
+            let mut data = Vec::with_capacity(grain_size as usize);
+            ZlibDecoder::new(&raw[..]).take(grain_size).read_to_end(&mut data)
+                .map_err(|e| invalid(common::vmdk_grain_inflate(sector, e)))?;
+            data.resize(grain_size as usize, 0);
+            self.cached_grain = Some(((file, sector), data));
+        }
+        Ok(self.cached_grain.as_ref().map(|c| &c.1[..]).unwrap_or_default())
+    }
+}
+
+impl Read for VmdkReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.position >= self.size || buf.is_empty() { return Ok(0); }
+        let index = self.extents.partition_point(|e| e.start + e.size <= self.position);
+        let Some(extent) = self.extents.get(index) else { return Ok(0) };
+        let in_extent = self.position - extent.start;
+        let mut n = (buf.len() as u64).min(extent.size - in_extent) as usize;
+
+        match extent.kind {
+            ExtentKind::Zero => buf[..n].fill(0),
+            ExtentKind::Flat { file, offset } => {
+                let file = &mut self.files[file];
+                file.seek(SeekFrom::Start(offset + in_extent))?;
+                file.read_exact(&mut buf[..n])?;
+            }
+            ExtentKind::Sparse(SparseExtent { file, grain_size, compressed, .. }) => {
+                let grain = in_extent / grain_size;
+                let in_grain = in_extent % grain_size;
+                n = n.min((grain_size - in_grain) as usize);
+                match self.grain_sector(index, grain)? {
+                    None => buf[..n].fill(0),
+                    Some(sector) if compressed => {
+                        let data = self.read_compressed_grain(file, sector, grain_size)?;
+                        buf[..n].copy_from_slice(&data[in_grain as usize..in_grain as usize + n]);
+                    }
+                    Some(sector) => {
+                        let file = &mut self.files[file];
+                        file.seek(SeekFrom::Start(sector * SECTOR + in_grain))?;
+                        file.read_exact(&mut buf[..n])?;
+                    }
+                }
+            }
+        }
+        self.position += n as u64;
+        Ok(n)
+    }
+}
+
+impl Seek for VmdkReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(p) => Some(p),
+            SeekFrom::End(d) => self.size.checked_add_signed(d),
+            SeekFrom::Current(d) => self.position.checked_add_signed(d),
+        };
+        self.position = target.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, common::seek_before_start()))?;
+        Ok(self.position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use byteorder::WriteBytesExt;
+    use flate2::write::ZlibEncoder;
+
+    use super::*;
+    use crate::image::Fixture;
+
+    const GRAIN: usize = 8 * SECTOR as usize;
+
+    fn header(flags: u32, capacity: u64, descriptor: (u64, u64), gd_offset: u64) -> Vec<u8> {
+        let mut buf = vec![0u8; SPARSE_HEADER_SIZE];
+        buf[..4].copy_from_slice(&SPARSE_MAGIC);
+        LittleEndian::write_u32(&mut buf[4..8], 1);
+        LittleEndian::write_u32(&mut buf[8..12], flags);
+        LittleEndian::write_u64(&mut buf[12..20], capacity);
+        LittleEndian::write_u64(&mut buf[20..28], (GRAIN as u64) / SECTOR);
+        LittleEndian::write_u64(&mut buf[28..36], descriptor.0);
+        LittleEndian::write_u64(&mut buf[36..44], descriptor.1);
+        LittleEndian::write_u32(&mut buf[44..48], 4);
+        LittleEndian::write_u64(&mut buf[56..64], gd_offset);
+        buf
+    }
+
+    fn sector(words: &[u32]) -> Vec<u8> {
+        let mut buf = vec![0u8; SECTOR as usize];
+        for (i, word) in words.iter().enumerate() {
+            LittleEndian::write_u32(&mut buf[i * 4..], *word);
+        }
+        buf
+    }
+
+    /// Sparse-экстент на 8 грейнов по 4 КБ (две таблицы по 4 записи):
+    /// выделены грейны 0 (0xA1), 3 (0xA3) и 5 (0xA5), грейн 2 помечен нулевым.
+    fn sparse(descriptor: Option<&str>) -> Vec<u8> {
+        let mut image = header(0, 64, if descriptor.is_some() { (1, 1) } else { (0, 0) }, 2);
+        let mut text = descriptor.unwrap_or_default().as_bytes().to_vec();
+        text.resize(SECTOR as usize, 0);
+        image.extend(text);
+        image.extend(sector(&[3, 4]));
+        image.extend(sector(&[8, 0, 1, 16]));
+        image.extend(sector(&[0, 24, 0, 0]));
+        image.resize(8 * SECTOR as usize, 0);
+        for fill in [0xA1, 0xA3, 0xA5] {
+            image.extend([fill; GRAIN]);
+        }
+        image
+    }
+
+    fn sparse_expected() -> Vec<u8> {
+        [0xA1, 0, 0, 0xA3, 0, 0xA5, 0, 0].iter().flat_map(|b| [*b; GRAIN]).collect()
+    }
+
+    const DESCRIPTOR: &str = "# Disk DescriptorFile\nversion=1\nCID=12345678\nparentCID=ffffffff\ncreateType=\"monolithicSparse\"\n";
+
+    #[test]
+    fn monolithic_sparse_reads_grains() {
+        let fixture = Fixture::new();
+        let path = fixture.write("disk.vmdk", &sparse(Some(DESCRIPTOR)));
+        let reader = VmdkReader::open(&path).unwrap();
+        assert_eq!((reader.create_type(), reader.extent_count(), reader.size()), ("monolithicSparse", 1, 8 * GRAIN as u64));
+        assert_eq!(Fixture::read_image(&path), sparse_expected());
+    }
+
+    #[test]
+    fn snapshot_with_parent_is_rejected() {
+        let fixture = Fixture::new();
+        let path = fixture.write("snap.vmdk", &sparse(Some(&DESCRIPTOR.replace("parentCID=ffffffff", "parentCID=1a2b3c4d"))));
+        assert_eq!(VmdkReader::open(&path).err().unwrap().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn stream_optimized_inflates_grains() {
+        let grain: Vec<u8> = (0..GRAIN).map(|i| (i / 64) as u8).collect();
+        let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&grain).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        // Грейн после встроенного дескриптора: GTE 1 означало бы обнуленный грейн
+        let mut image = header(FLAG_COMPRESSED_GRAINS, 16, (1, 1), GD_AT_END);
+        let mut text = DESCRIPTOR.replace("monolithicSparse", "streamOptimized").into_bytes();
+        text.resize(SECTOR as usize, 0);
+        image.extend(text);
+        image.write_u64::<LittleEndian>(0).unwrap();
+        image.write_u32::<LittleEndian>(compressed.len() as u32).unwrap();
+        image.extend(&compressed);
+        image.resize(image.len().next_multiple_of(SECTOR as usize), 0);
+        let table = image.len() as u64 / SECTOR;
+        image.extend(sector(&[2, 0, 0, 0]));
+        image.extend(sector(&[table as u32]));
+        image.extend(header(FLAG_COMPRESSED_GRAINS, 16, (0, 0), table + 1));
+        image.extend(sector(&[]));
+
+        let fixture = Fixture::new();
+        let path = fixture.write("stream.vmdk", &image);
+        assert_eq!(VmdkReader::open(&path).unwrap().create_type(), "streamOptimized");
+        let mut expected = grain;
+        expected.resize(2 * GRAIN, 0);
+        assert_eq!(Fixture::read_image(&path), expected);
+    }
+
+    #[test]
+    fn descriptor_joins_flat_zero_and_sparse_extents() {
+        let fixture = Fixture::new();
+        let mut flat = vec![0xEE; SECTOR as usize];
+        flat.extend([0xF1; 4 * SECTOR as usize]);
+        fixture.write("disk-flat.vmdk", &flat);
+        fixture.write("disk-s002.vmdk", &sparse(None));
+        let path = fixture.write("disk.vmdk", concat!(
+            "# Disk DescriptorFile\nversion=1\nparentCID=ffffffff\ncreateType=\"twoGbMaxExtentSparse\"\n\n",
+            "RW 4 FLAT \"disk-flat.vmdk\" 1\nRW 2 ZERO\nRW 16 SPARSE \"disk-s002.vmdk\"\n").as_bytes());
+
+        let reader = VmdkReader::open(&path).unwrap();
+        assert_eq!((reader.extent_count(), reader.size()), (3, 6 * SECTOR + 2 * GRAIN as u64));
+        let mut expected = vec![0xF1; 4 * SECTOR as usize];
+        expected.extend([0; 2 * SECTOR as usize]);
+        expected.extend(&sparse_expected()[..2 * GRAIN]);
+        assert_eq!(Fixture::read_image(&path), expected);
+    }
+}
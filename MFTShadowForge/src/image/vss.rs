@@ -0,0 +1,45 @@
+use std::io;
+
+/// Теневая копия тома (Volume Shadow Copy).
+#[derive(Debug, Clone)]
+pub struct ShadowCopy {
+    /// Номер N из HarddiskVolumeShadowCopyN
+    pub index: u32,
+    pub device: String,
+}
+
+/// Перебирает устройства HarddiskVolumeShadowCopyN. Номера не переиспользуются, после
+/// удаления снимков остаются пропуски - перебор останавливается после MAX_GAP отсутствующих подряд.
+#[cfg(windows)]
+pub fn list() -> io::Result<Vec<ShadowCopy>> {
+    use std::fs::File;
+
+    /// Устройство теневой копии: к префиксу добавляется номер снимка
+    const SHADOW_DEVICE_PREFIX: &str = "\\\\?\\GLOBALROOT\\Device\\HarddiskVolumeShadowCopy";
+    const MAX_INDEX: u32 = 8192;
+    const MAX_GAP: u32 = 256;
+
+    let mut shadows = Vec::new();
+    let mut gap = 0;
+    for index in 1..=MAX_INDEX {
+        let shadow = ShadowCopy { index, device: format!("{}{}", SHADOW_DEVICE_PREFIX, index) };
+        match File::open(&shadow.device) {
+            Ok(_) => {
+                shadows.push(shadow);
+                gap = 0;
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                gap += 1;
+                if gap >= MAX_GAP { break; }
+            }
+            Err(e) => return Err(io::Error::new(e.kind(), crate::i18n::common::vss_access(&shadow.device, &e))),
+        }
+    }
+    Ok(shadows)
+}
+
+/// Теневые копии открываются только через диспетчер объектов Windows.
+#[cfg(not(windows))]
+pub fn list() -> io::Result<Vec<ShadowCopy>> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, crate::i18n::common::vss_unsupported()))
+}
\ No newline at end of file
@@ -0,0 +1,64 @@
+//! Извлечение и разбор NTFS $MFT для других инструментов на Rust. CLI `mft_shadow_forge`
+//! построен на этой же библиотеке; наружу открыты [`extract_mft`], [`parse_records`],
+//! модели строк отчета ([`models`]), разборщики VBR, заголовка записи и атрибутов и [`Error`].
+//! Ошибки возвращаются как [`Error`]; сообщение и код выхода по ним выбирает вызывающий.
+//!
+//! ```no_run
+//! use mftshadowforge::{extract_mft, parse_records, Error, ExtractOptions, ParseOptions};
+//!
+//! let meta = extract_mft(r"\\.\C:", "C_mft.raw", &ExtractOptions::default())?;
+//! println!("Запись MFT: {} байт", meta.mft_record_size);
+//! let dump = std::fs::File::open("C_mft.raw").map_err(Error::io("C_mft.raw"))?;
+//! for entry in parse_records(dump, &ParseOptions::default())? {
+//!     println!("{} {}", entry.entry_number, entry.full_path);
+//! }
+//! # Ok::<(), Error>(())
+//! ```
+
+mod app;
+mod audit;
+mod cli;
+mod commands;
+pub mod error;
+mod hashing;
+mod i18n;
+mod image;
+mod logging;
+mod mft;
+pub mod models;
+mod output;
+mod plugins;
+mod progress;
+mod report_index;
+mod rules;
+
+use std::io::{Read, Seek};
+
+pub use commands::extract::{ExtractOptions, PartitionSelect};
+pub use error::{Error, Outcome, Result};
+pub use i18n::{set_lang, Lang};
+pub use commands::parse::ParseOptions;
+pub use mft::attributes::{FileNameAttribute, StandardInformation};
+pub use mft::boot::NtfsBootSector;
+pub use mft::record::MftRecordHeader;
+pub use models::{MftEntry, MftMeta};
+
+/// Снимает $MFT тома или образа `image` (путь, `C:` или `\\.\C:`) в `out` рядом с
+/// `<out>.meta.json`, как `extract`. В audit.log пишет только с `opts.audit`.
+pub fn extract_mft(image: &str, out: &str, opts: &ExtractOptions) -> Result<MftMeta> {
+    commands::extract::run(image, out, opts, &models::CaseInfo::default(), &models::Tags::new())
+}
+
+/// Строки отчета parse по дампу $MFT из `reader`, в порядке номеров записей.
+/// Записи читаются по мере обхода итератора (дамп в память не загружается);
+/// правила, хешсет, $SDS и плагины из `opts` загружаются как у parse, опции вывода не применяются.
+pub fn parse_records<R: Read + Seek + 'static>(reader: R, opts: &ParseOptions) -> Result<impl Iterator<Item = MftEntry>> {
+    commands::parse::parse_records(reader, opts)
+}
+
+/// Точка входа CLI `mft_shadow_forge`: разбирает аргументы, выполняет команду и завершает
+/// процесс с кодом выхода. Не часть API библиотеки.
+#[doc(hidden)]
+pub fn cli_main() -> ! {
+    app::main()
+}
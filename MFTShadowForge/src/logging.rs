@@ -0,0 +1,91 @@
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use tracing::{Event, Level, Metadata, Subscriber};
+use tracing_subscriber::fmt::format::{FmtSpan, Writer};
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields, MakeWriter};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Отчет пишется в stdout (`-j -`, `query -o -`): журнал уходит в stderr, чтобы не смешаться с данными.
+static STDOUT_RESERVED: AtomicBool = AtomicBool::new(false);
+
+/// Уровень журнала по флагам: `--quiet` - только предупреждения и ошибки,
+/// `-v` - отладочные события, `-vv` - трассировка.
+pub fn level(verbose: u8, quiet: bool) -> Level {
+    match (quiet, verbose) {
+        (true, _) => Level::WARN,
+        (false, 0) => Level::INFO,
+        (false, 1) => Level::DEBUG,
+        _ => Level::TRACE,
+    }
+}
+
+/// Подключает журнал CLI. Текст: сообщения как есть, INFO и ниже - в stdout, WARN и ERROR - в stderr.
+/// JSON: одно событие на строку со всеми полями и текущим span команды, целиком в stderr;
+/// при закрытии span команды пишется ее длительность (`time.busy`).
+pub fn init(level: Level, json: bool) {
+    // Закрытый pipe (| head) - не повод писать в stderr о каждом следующем событии
+    let builder = tracing_subscriber::fmt().with_max_level(level).log_internal_errors(false);
+    if json {
+        builder.json()
+            .with_current_span(true)
+            .with_span_list(false)
+            .with_span_events(FmtSpan::CLOSE)
+            .with_writer(io::stderr)
+            .init();
+    } else {
+        builder.event_format(PlainFormat).with_writer(ConsoleWriter).init();
+    }
+}
+
+/// Переводит журнал в stderr до конца процесса: stdout занят отчетом.
+pub fn reserve_stdout() {
+    STDOUT_RESERVED.store(true, Ordering::Relaxed);
+}
+
+/// Текстовый вывод без времени и уровня: сообщения уже несут префиксы `[*]`, `[+]`, `[!]`.
+struct PlainFormat;
+
+impl<S, N> FormatEvent<S, N> for PlainFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(&self, _ctx: &FmtContext<'_, S, N>, mut writer: Writer<'_>, event: &Event<'_>) -> fmt::Result {
+        let mut visitor = MessageVisitor(&mut writer, Ok(()));
+        event.record(&mut visitor);
+        visitor.1?;
+        writeln!(writer)
+    }
+}
+
+/// Пишет только поле message; остальные поля (длительности этапов и т.п.) - для JSON.
+struct MessageVisitor<'a, 'w>(&'a mut Writer<'w>, fmt::Result);
+
+impl tracing::field::Visit for MessageVisitor<'_, '_> {
+    fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.1 = write!(self.0, "{:?}", value);
+        }
+    }
+}
+
+/// stdout или stderr по уровню события и занятости stdout.
+struct ConsoleWriter;
+
+impl<'a> MakeWriter<'a> for ConsoleWriter {
+    type Writer = Box<dyn Write + 'a>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        Box::new(io::stdout().lock())
+    }
+
+    fn make_writer_for(&'a self, meta: &Metadata<'_>) -> Self::Writer {
+        if *meta.level() <= Level::WARN || STDOUT_RESERVED.load(Ordering::Relaxed) {
+            Box::new(io::stderr().lock())
+        } else {
+            Box::new(io::stdout().lock())
+        }
+    }
+}
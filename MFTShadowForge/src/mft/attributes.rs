@@ -8,8 +8,12 @@ pub struct StandardInformation {
     pub modified_time: DateTime<Utc>,
     pub mft_modified_time: DateTime<Utc>,
     pub accessed_time: DateTime<Utc>,
+    /// Исходные FILETIME (C, M, E, A): в DateTime не переживают значения до 1970 года
+    pub raw_times: [u64; 4],
     pub file_attributes: u32, 
     pub security_id: u32,
+    /// USN последнего изменения в $UsnJrnl (NTFS 3.0+, 0 если журнал не велся)
+    pub usn: u64,
 }
 
 impl StandardInformation {
@@ -31,13 +35,22 @@ impl StandardInformation {
             0
         };
 
+        // USN со смещения 64, размер 8 байт (только в 72-байтовом варианте NTFS 3.0+)
+        let usn = if data.len() >= 72 {
+            LittleEndian::read_u64(&data[64..72])
+        } else {
+            0
+        };
+
         Some(Self {
             creation_time: filetime_to_datetime(LittleEndian::read_u64(&data[0..8])),
             modified_time: filetime_to_datetime(LittleEndian::read_u64(&data[8..16])),
             mft_modified_time: filetime_to_datetime(LittleEndian::read_u64(&data[16..24])),
             accessed_time: filetime_to_datetime(LittleEndian::read_u64(&data[24..32])),
+            raw_times: [0, 8, 16, 24].map(|o| LittleEndian::read_u64(&data[o..o + 8])),
             file_attributes,
             security_id,
+            usn,
         })
     }
 }
@@ -49,6 +62,8 @@ pub struct FileNameAttribute {
     pub modified_time: DateTime<Utc>,
     pub mft_modified_time: DateTime<Utc>,
     pub accessed_time: DateTime<Utc>,
+    /// Исходные FILETIME (C, M, E, A)
+    pub raw_times: [u64; 4],
     pub logical_size: u64,
     pub name_type: u8,
     pub name: String,
@@ -66,7 +81,7 @@ impl FileNameAttribute {
         
         let name_u16: Vec<u16> = data[name_offset..name_offset + name_bytes_len]
             .chunks_exact(2)
-            .map(|chunk| LittleEndian::read_u16(chunk))
+            .map(LittleEndian::read_u16)
             .collect();
             
         let name = String::from_utf16_lossy(&name_u16);
@@ -77,9 +92,198 @@ impl FileNameAttribute {
             modified_time: filetime_to_datetime(LittleEndian::read_u64(&data[16..24])),
             mft_modified_time: filetime_to_datetime(LittleEndian::read_u64(&data[24..32])),
             accessed_time: filetime_to_datetime(LittleEndian::read_u64(&data[32..40])),
+            raw_times: [8, 16, 24, 32].map(|o| LittleEndian::read_u64(&data[o..o + 8])),
             logical_size: LittleEndian::read_u64(&data[48..56]),
             name_type,
             name,
         })
     }
+}
+
+// Атрибуты файла в $STANDARD_INFORMATION (FILE_ATTRIBUTE_*)
+pub const FILE_ATTRIBUTE_TEMPORARY: u32 = 0x0100;
+pub const FILE_ATTRIBUTE_SPARSE_FILE: u32 = 0x0200;
+pub const FILE_ATTRIBUTE_REPARSE_POINT: u32 = 0x0400;
+pub const FILE_ATTRIBUTE_COMPRESSED: u32 = 0x0800;
+pub const FILE_ATTRIBUTE_OFFLINE: u32 = 0x1000;
+pub const FILE_ATTRIBUTE_ENCRYPTED: u32 = 0x4000;
+
+// Флаги заголовка атрибута (смещение 0x0C)
+pub const ATTR_FLAG_COMPRESSION_MASK: u16 = 0x00FF;
+pub const ATTR_FLAG_ENCRYPTED: u16 = 0x4000;
+pub const ATTR_FLAG_SPARSE: u16 = 0x8000;
+
+/// Флаги файла, собранные из $STANDARD_INFORMATION и заголовка основного $DATA:
+/// Windows выставляет их независимо, и при копировании/восстановлении они могут расходиться.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FileFlags {
+    pub is_sparse: bool,
+    pub is_compressed: bool,
+    pub is_encrypted: bool,
+    pub is_offline: bool,
+    pub is_reparse: bool,
+    pub is_temporary: bool,
+}
+
+impl FileFlags {
+    pub fn decode(si_flags: u32, data_flags: u16, has_reparse_attr: bool) -> Self {
+        Self {
+            is_sparse: si_flags & FILE_ATTRIBUTE_SPARSE_FILE != 0 || data_flags & ATTR_FLAG_SPARSE != 0,
+            is_compressed: si_flags & FILE_ATTRIBUTE_COMPRESSED != 0 || data_flags & ATTR_FLAG_COMPRESSION_MASK != 0,
+            is_encrypted: si_flags & FILE_ATTRIBUTE_ENCRYPTED != 0 || data_flags & ATTR_FLAG_ENCRYPTED != 0,
+            is_offline: si_flags & FILE_ATTRIBUTE_OFFLINE != 0,
+            is_reparse: si_flags & FILE_ATTRIBUTE_REPARSE_POINT != 0 || has_reparse_attr,
+            is_temporary: si_flags & FILE_ATTRIBUTE_TEMPORARY != 0,
+        }
+    }
+}
+
+pub const IO_REPARSE_TAG_MOUNT_POINT: u32 = 0xA000_0003;
+pub const IO_REPARSE_TAG_SYMLINK: u32 = 0xA000_000C;
+pub const IO_REPARSE_TAG_WOF: u32 = 0x8000_0017;
+pub const IO_REPARSE_TAG_APPEXECLINK: u32 = 0x8000_001B;
+pub const IO_REPARSE_TAG_LX_SYMLINK: u32 = 0xA000_001D;
+/// Облачные файлы (OneDrive и другие провайдеры Cloud Files API): CLOUD, CLOUD_1..CLOUD_F
+/// различаются битами 12..15.
+const IO_REPARSE_TAG_CLOUD_MASK: u32 = 0xFFFF_0FFF;
+const IO_REPARSE_TAG_CLOUD: u32 = 0x9000_001A;
+
+/// Известные теги reparse point (IO_REPARSE_TAG_*) без облачных.
+const REPARSE_TAG_NAMES: [(u32, &str); 20] = [
+    (IO_REPARSE_TAG_MOUNT_POINT, "MOUNT_POINT"), (IO_REPARSE_TAG_SYMLINK, "SYMLINK"),
+    (IO_REPARSE_TAG_WOF, "WOF"), (IO_REPARSE_TAG_APPEXECLINK, "APPEXECLINK"),
+    (IO_REPARSE_TAG_LX_SYMLINK, "LX_SYMLINK"), (0x8000_0021, "ONEDRIVE"),
+    (0xC000_0004, "HSM"), (0x8000_0006, "HSM2"), (0x8000_0007, "SIS"), (0x8000_0008, "WIM"),
+    (0x8000_0009, "CSV"), (0x8000_000A, "DFS"), (0x8000_0012, "DFSR"), (0x8000_0013, "DEDUP"),
+    (0x8000_0014, "NFS"), (0x8000_0018, "WCI"), (0x9000_001C, "PROJFS"),
+    (0x8000_001E, "STORAGE_SYNC"), (0x8000_0023, "AF_UNIX"), (0x8000_0024, "LX_FIFO"),
+];
+
+/// Имя тега reparse point для отчета; неизвестные - в hex.
+pub fn reparse_tag_name(tag: u32) -> String {
+    if tag & IO_REPARSE_TAG_CLOUD_MASK == IO_REPARSE_TAG_CLOUD {
+        return "CLOUD".to_string();
+    }
+    REPARSE_TAG_NAMES.iter().find(|(t, _)| *t == tag).map(|(_, n)| n.to_string()).unwrap_or_else(|| format!("0x{:08X}", tag))
+}
+
+/// Провайдеры Windows Overlay Filter
+pub const WOF_PROVIDER_WIM: u32 = 1;
+pub const WOF_PROVIDER_FILE: u32 = 2;
+
+/// Имя ADS, в котором WOF (CompactOS, `compact /exe`) хранит сжатое содержимое.
+pub const WOF_COMPRESSED_DATA_STREAM: &str = "WofCompressedData";
+
+/// Имена потоков $LOGGED_UTILITY_STREAM (0x100): состояние транзакции TxF и ключи EFS.
+pub const TXF_DATA_STREAM: &str = "$TXF_DATA";
+pub const EFS_STREAM: &str = "$EFS";
+
+/// Алгоритм сжатия провайдера FILE (FILE_PROVIDER_COMPRESSION_*).
+pub fn wof_algorithm_name(algorithm: u32) -> String {
+    match algorithm {
+        0 => "XPRESS4K".to_string(),
+        1 => "LZX".to_string(),
+        2 => "XPRESS8K".to_string(),
+        3 => "XPRESS16K".to_string(),
+        other => format!("UNKNOWN({})", other),
+    }
+}
+
+#[derive(Debug)]
+pub struct ReparsePoint {
+    pub tag: u32,
+    pub substitute_name: Option<String>,
+    pub print_name: Option<String>,
+    pub is_relative: bool,
+    /// Провайдер WOF (для тега IO_REPARSE_TAG_WOF)
+    pub wof_provider: Option<u32>,
+    /// Алгоритм сжатия провайдера FILE
+    pub wof_algorithm: Option<u32>,
+}
+
+fn read_utf16_at(data: &[u8], offset: usize, len: usize) -> Option<String> {
+    if len == 0 { return None; }
+    let bytes = data.get(offset..offset.checked_add(len)?)?;
+    let u16s: Vec<u16> = bytes.chunks_exact(2).map(LittleEndian::read_u16).collect();
+    Some(String::from_utf16_lossy(&u16s))
+}
+
+impl ReparsePoint {
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        if data.len() < 8 { return None; }
+        let tag = LittleEndian::read_u32(&data[0..4]);
+        let data_len = LittleEndian::read_u16(&data[4..6]) as usize;
+        let body = &data[8..std::cmp::min(data.len(), 8 + data_len)];
+
+        let mut rp = Self { tag, substitute_name: None, print_name: None, is_relative: false, wof_provider: None, wof_algorithm: None };
+
+        // WOF_EXTERNAL_INFO (Version, Provider), за ним у провайдера FILE - FILE_PROVIDER_EXTERNAL_INFO_V1 (Version, Algorithm)
+        if tag == IO_REPARSE_TAG_WOF {
+            if body.len() >= 8 {
+                let provider = LittleEndian::read_u32(&body[4..8]);
+                rp.wof_provider = Some(provider);
+                if provider == WOF_PROVIDER_FILE && body.len() >= 16 {
+                    rp.wof_algorithm = Some(LittleEndian::read_u32(&body[12..16]));
+                }
+            }
+            return Some(rp);
+        }
+
+        // Ярлык приложения Store (WindowsApps\*.exe): Version, затем строки UTF-16 через NUL -
+        // пакет, AppUserModelId, путь к исполняемому файлу, ...
+        if tag == IO_REPARSE_TAG_APPEXECLINK {
+            if body.len() > 4 {
+                let u16s: Vec<u16> = body[4..].chunks_exact(2).map(LittleEndian::read_u16).collect();
+                rp.substitute_name = u16s.split(|&c| c == 0).nth(2).filter(|s| !s.is_empty()).map(String::from_utf16_lossy);
+            }
+            return Some(rp);
+        }
+
+        // Symlink WSL: Version, затем цель в UTF-8 без завершающего нуля
+        if tag == IO_REPARSE_TAG_LX_SYMLINK {
+            if body.len() > 4 {
+                rp.substitute_name = Some(String::from_utf8_lossy(&body[4..]).into_owned());
+            }
+            return Some(rp);
+        }
+
+        // Junction и symlink хранят пары имен (substitute/print) в PathBuffer
+        let path_buffer = match tag {
+            IO_REPARSE_TAG_MOUNT_POINT => 8,
+            IO_REPARSE_TAG_SYMLINK => 12,
+            _ => return Some(rp),
+        };
+        if body.len() < path_buffer { return Some(rp); }
+
+        let sub_off = LittleEndian::read_u16(&body[0..2]) as usize;
+        let sub_len = LittleEndian::read_u16(&body[2..4]) as usize;
+        let print_off = LittleEndian::read_u16(&body[4..6]) as usize;
+        let print_len = LittleEndian::read_u16(&body[6..8]) as usize;
+        if tag == IO_REPARSE_TAG_SYMLINK {
+            rp.is_relative = LittleEndian::read_u32(&body[8..12]) & 0x1 != 0;
+        }
+
+        let names = &body[path_buffer..];
+        rp.substitute_name = read_utf16_at(names, sub_off, sub_len);
+        rp.print_name = read_utf16_at(names, print_off, print_len);
+        Some(rp)
+    }
+
+    /// Junction или symbolic link (у остальных тегов нет цели-пути)
+    pub fn is_link(&self) -> bool {
+        matches!(self.tag, IO_REPARSE_TAG_MOUNT_POINT | IO_REPARSE_TAG_SYMLINK)
+    }
+
+    /// Цель ссылки в "человеческом" виде: print name, иначе substitute name без `\??\`.
+    /// У AppExecLink - исполняемый файл, у symlink WSL - путь Linux.
+    pub fn target(&self) -> Option<String> {
+        let name = self.print_name.as_ref().filter(|s| !s.is_empty())
+            .or(self.substitute_name.as_ref())?;
+        let name = if let Some(unc) = name.strip_prefix("\\??\\UNC\\") {
+            format!("\\\\{}", unc)
+        } else {
+            name.strip_prefix("\\??\\").unwrap_or(name).to_string()
+        };
+        Some(name)
+    }
 }
\ No newline at end of file
@@ -4,6 +4,7 @@ use byteorder::{ByteOrder, LittleEndian};
 pub struct NtfsBootSector {
     pub bytes_per_sector: u16,
     pub sectors_per_cluster: u8,
+    pub total_sectors: u64,
     pub mft_lcn: u64,
     pub mft_mirror_lcn: u64,
     pub clusters_per_file_record_segment: i8,
@@ -25,6 +26,7 @@ impl NtfsBootSector {
         let bytes_per_sector = LittleEndian::read_u16(&vbr[11..13]);
         let sectors_per_cluster = vbr[13];
 
+        let total_sectors = LittleEndian::read_u64(&vbr[40..48]);
         let mft_lcn = LittleEndian::read_u64(&vbr[48..56]);
         let mft_mirror_lcn = LittleEndian::read_u64(&vbr[56..64]);
 
@@ -35,6 +37,7 @@ impl NtfsBootSector {
         Some(Self {
             bytes_per_sector,
             sectors_per_cluster,
+            total_sectors,
             mft_lcn,
             mft_mirror_lcn,
             clusters_per_file_record_segment,
@@ -0,0 +1,86 @@
+use byteorder::{ByteOrder, LittleEndian};
+
+use super::secure::format_sid;
+
+/// Заголовок $EFS до смещений массивов DDF/DRF.
+const EFS_HEADER_LEN: usize = 0x4C;
+/// Тип учетных данных: сертификат, заданный отпечатком (Windows 2000+).
+const CRED_CERT_THUMBPRINT: u32 = 3;
+
+/// Ключ из массива DDF (пользователи, которым доступен файл) или DRF (агенты восстановления).
+#[derive(Debug, Default)]
+pub struct EfsKeyEntry {
+    pub sid: Option<String>,
+    /// Отпечаток сертификата (SHA-1, hex)
+    pub thumbprint: Option<String>,
+    pub container: Option<String>,
+    pub provider: Option<String>,
+    pub user_name: Option<String>,
+}
+
+/// Содержимое потока $LOGGED_UTILITY_STREAM:$EFS (метаданные шифрования файла EFS).
+/// Раскладка - по ntfs-3g (layout.h: EFS_ATTR_HEADER, EFS_DF_HEADER, EFS_DF_CREDENTIAL_HEADER).
+#[derive(Debug)]
+pub struct EfsMetadata {
+    pub version: u32,
+    pub users: Vec<EfsKeyEntry>,
+    pub recovery_agents: Vec<EfsKeyEntry>,
+}
+
+impl EfsMetadata {
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        if data.len() < EFS_HEADER_LEN { return None; }
+        let length = LittleEndian::read_u32(&data[0..4]) as usize;
+        let data = data.get(..length.clamp(EFS_HEADER_LEN, data.len()))?;
+        let version = LittleEndian::read_u32(&data[8..12]);
+        let ddf = LittleEndian::read_u32(&data[0x40..0x44]) as usize;
+        let drf = LittleEndian::read_u32(&data[0x44..0x48]) as usize;
+        Some(EfsMetadata { version, users: parse_array(data, ddf), recovery_agents: parse_array(data, drf) })
+    }
+}
+
+/// Массив DDF/DRF: число элементов и элементы подряд, каждый начинается со своей длины.
+fn parse_array(data: &[u8], offset: usize) -> Vec<EfsKeyEntry> {
+    let mut keys = Vec::new();
+    if offset == 0 { return keys; }
+    let Some(count) = data.get(offset..offset + 4).map(LittleEndian::read_u32) else { return keys };
+    let mut pos = offset + 4;
+    for _ in 0..count {
+        let Some(len) = data.get(pos..pos + 4).map(|b| LittleEndian::read_u32(b) as usize) else { break };
+        let Some(df) = data.get(pos..pos.saturating_add(len)).filter(|_| len >= 20) else { break };
+        keys.push(parse_entry(df));
+        pos += len;
+    }
+    keys
+}
+
+fn parse_entry(df: &[u8]) -> EfsKeyEntry {
+    let mut key = EfsKeyEntry::default();
+    let cred_offset = LittleEndian::read_u32(&df[4..8]) as usize;
+    let Some(cred) = df.get(cred_offset..).filter(|c| c.len() >= 20) else { return key };
+    let sid_offset = LittleEndian::read_u32(&cred[4..8]) as usize;
+    if sid_offset != 0 {
+        key.sid = cred.get(sid_offset..).and_then(format_sid);
+    }
+    if LittleEndian::read_u32(&cred[8..12]) == CRED_CERT_THUMBPRINT {
+        let header_offset = LittleEndian::read_u32(&cred[16..20]) as usize;
+        if let Some(th) = cred.get(header_offset..).filter(|t| t.len() >= 20) {
+            let field = |i: usize| LittleEndian::read_u32(&th[i..i + 4]) as usize;
+            let (print_offset, print_size) = (field(0), field(4));
+            key.thumbprint = th.get(print_offset..print_offset.saturating_add(print_size))
+                .filter(|p| !p.is_empty())
+                .map(|p| p.iter().map(|b| format!("{:02x}", b)).collect());
+            key.container = utf16z(th, field(8));
+            key.provider = utf16z(th, field(12));
+            key.user_name = utf16z(th, field(16));
+        }
+    }
+    key
+}
+
+/// Строка UTF-16 до NUL со смещения `offset`; 0 - поля нет.
+fn utf16z(data: &[u8], offset: usize) -> Option<String> {
+    if offset == 0 { return None; }
+    let units: Vec<u16> = data.get(offset..)?.chunks_exact(2).map(LittleEndian::read_u16).take_while(|&c| c != 0).collect();
+    (!units.is_empty()).then(|| String::from_utf16_lossy(&units))
+}
\ No newline at end of file
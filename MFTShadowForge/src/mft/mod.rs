@@ -3,4 +3,9 @@ pub mod boot;
 pub mod record;
 pub mod utils;
 pub mod parser;
-pub mod path_builder;
\ No newline at end of file
+pub mod path_builder;
+pub mod recycle;
+pub mod secure;
+pub mod usn;
+pub mod efs;
+pub mod slack;
\ No newline at end of file
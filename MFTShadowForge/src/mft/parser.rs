@@ -1,8 +1,10 @@
+use std::collections::{HashMap, VecDeque};
 use std::fs::File;
-use std::io::{BufReader, Seek, SeekFrom, Read};
+use std::io::{BufReader, Cursor, Seek, SeekFrom, Read};
 
 use super::path_builder::PathBuilder;
 use super::record::MftRecordHeader;
+use crate::image::ImageReader;
 
 #[derive(Debug, PartialEq)]
 pub enum FixupResult {
@@ -12,10 +14,13 @@ pub enum FixupResult {
 }
 
 pub fn apply_fixups(data: &mut [u8], header: &MftRecordHeader, bytes_per_sector: u16) -> FixupResult {
+    apply_block_fixups(data, header.update_sequence_offset as usize, header.update_sequence_size as usize, bytes_per_sector)
+}
+
+/// Fixups любого блока с массивом USA (запись FILE, блок индекса INDX).
+pub fn apply_block_fixups(data: &mut [u8], usa_offset: usize, usa_count: usize, bytes_per_sector: u16) -> FixupResult {
     let bytes_per_sector = bytes_per_sector as usize;
-    if bytes_per_sector == 0 || data.len() % bytes_per_sector != 0 { return FixupResult::Failed; }
-    let usa_offset = header.update_sequence_offset as usize;
-    let usa_count = header.update_sequence_size as usize;
+    if bytes_per_sector == 0 || !data.len().is_multiple_of(bytes_per_sector) { return FixupResult::Failed; }
     if usa_count < 2 || usa_offset + usa_count * 2 > data.len() { return FixupResult::Failed; }
     
     let usn_0 = data[usa_offset];
@@ -40,23 +45,118 @@ pub fn apply_fixups(data: &mut [u8], header: &MftRecordHeader, bytes_per_sector:
     if torn_write { FixupResult::TornWrite } else { FixupResult::Ok }
 }
 
+/// Источник записей MFT: файл дампа или живой том.
+pub trait RecordSource {
+    fn record_size(&self) -> usize;
+    fn bytes_per_sector(&self) -> u16;
+    fn total_records(&self) -> u64;
+    /// Сырая запись по номеру (без fixups)
+    fn fetch_record(&mut self, entry_num: u64) -> Option<Vec<u8>>;
+    /// Значение нерезидентного атрибута записи `record` (заголовок - `attr_offset..attr_end`)
+    /// по его runlist; None - у источника нет доступа к кластерам тома.
+    fn read_nonresident(&mut self, _record: &[u8], _attr_offset: usize, _attr_end: usize) -> Option<Vec<u8>> { None }
+    /// Запись-расширение из $ATTRIBUTE_LIST (номер + sequence ссылки) после fixups;
+    /// None - не прочитана или fixups не сошлись.
+    fn fetch_extent(&mut self, entry_num: u64, _sequence: u16) -> Option<Vec<u8>> {
+        fetch_fixed(self, entry_num)
+    }
+}
+
+/// Запись источника с примененными fixups (разорванная запись тоже возвращается).
+pub fn fetch_fixed<S: RecordSource + ?Sized>(src: &mut S, entry_num: u64) -> Option<Vec<u8>> {
+    let mut buf = src.fetch_record(entry_num)?;
+    let header = MftRecordHeader::parse(&buf)?;
+    (apply_fixups(&mut buf, &header, src.bytes_per_sector()) != FixupResult::Failed).then_some(buf)
+}
+
+/// Число записей-расширений, которые держит кеш дампа.
+const EXTENT_CACHE_CAPACITY: usize = 1024;
+
+/// LRU-кеш записей-расширений после fixups: одно расширение нужно обоим проходам
+/// и нередко нескольким базовым записям.
+///
+/// Порядок использования - очередь `(номер, отметка)`: обращение дописывает номер в конец,
+/// а устаревшие элементы (отметка не совпадает с текущей у записи) пропускаются при вытеснении.
+#[derive(Default)]
+pub struct ExtentCache {
+    /// Номер записи -> (запись, отметка последнего обращения)
+    records: HashMap<u64, (Vec<u8>, u64)>,
+    order: VecDeque<(u64, u64)>,
+    clock: u64,
+}
+
+impl ExtentCache {
+    /// Запись из кеша, если ее sequence number совпадает со ссылкой (0 - без проверки).
+    fn get(&mut self, entry_num: u64, sequence: u16) -> Option<Vec<u8>> {
+        let (buf, used) = self.records.get_mut(&entry_num)?;
+        if !sequence_matches(buf, sequence) {
+            return None;
+        }
+        self.clock += 1;
+        *used = self.clock;
+        let buf = buf.clone();
+        self.touch(entry_num);
+        Some(buf)
+    }
+
+    fn insert(&mut self, entry_num: u64, buf: Vec<u8>) {
+        self.clock += 1;
+        if self.records.insert(entry_num, (buf, self.clock)).is_none() && self.records.len() > EXTENT_CACHE_CAPACITY {
+            while let Some((oldest, stamp)) = self.order.pop_front() {
+                if self.records.get(&oldest).is_some_and(|(_, used)| *used == stamp) {
+                    self.records.remove(&oldest);
+                    break;
+                }
+            }
+        }
+        self.touch(entry_num);
+    }
+
+    fn touch(&mut self, entry_num: u64) {
+        self.order.push_back((entry_num, self.clock));
+        // Частые обращения к одним и тем же записям копят устаревшие элементы - чистим их
+        if self.order.len() > EXTENT_CACHE_CAPACITY * 4 {
+            let records = &self.records;
+            self.order.retain(|(entry, stamp)| records.get(entry).is_some_and(|(_, used)| used == stamp));
+        }
+    }
+}
+
+/// Совпадает ли sequence number записи со ссылкой на нее (0 в ссылке - без проверки).
+fn sequence_matches(record: &[u8], sequence: u16) -> bool {
+    sequence == 0 || MftRecordHeader::parse(record).is_some_and(|h| h.sequence_number == sequence)
+}
+
 pub struct MftParser {
-    pub reader: BufReader<File>,
+    pub reader: BufReader<Box<dyn ImageReader>>,
     pub path_builder: PathBuilder,
     pub file_size: u64,
     pub record_size: usize,
     pub bytes_per_sector: u16,
+    pub extent_cache: ExtentCache,
 }
 
 impl MftParser {
     pub fn new(path: &str, record_size: usize, bytes_per_sector: u16) -> Result<Self, std::io::Error> {
         let file = File::open(path)?;
         let file_size = file.metadata()?.len();
-        Ok(Self {
-            reader: BufReader::new(file),
+        Ok(Self::with_reader(Box::new(file), file_size, record_size, bytes_per_sector))
+    }
+
+    /// Дамп, уже прочитанный в память (потоковый `play --stream`).
+    pub fn from_memory(data: Vec<u8>, record_size: usize, bytes_per_sector: u16) -> Self {
+        let file_size = data.len() as u64;
+        Self::with_reader(Box::new(Cursor::new(data)), file_size, record_size, bytes_per_sector)
+    }
+
+    /// Дамп из произвольного источника с произвольным доступом; `file_size` - его длина в байтах.
+    pub fn with_reader(reader: Box<dyn ImageReader>, file_size: u64, record_size: usize, bytes_per_sector: u16) -> Self {
+        Self {
+            reader: BufReader::new(reader),
             path_builder: PathBuilder::new(),
             file_size, record_size, bytes_per_sector,
-        })
+            extent_cache: ExtentCache::default(),
+        }
     }
 
     pub fn total_records(&self) -> u64 {
@@ -84,4 +184,79 @@ impl MftParser {
         
         Some(buf)
     }
+}
+
+impl RecordSource for MftParser {
+    fn record_size(&self) -> usize { self.record_size }
+    fn bytes_per_sector(&self) -> u16 { self.bytes_per_sector }
+    fn total_records(&self) -> u64 { MftParser::total_records(self) }
+    fn fetch_record(&mut self, entry_num: u64) -> Option<Vec<u8>> { MftParser::fetch_record(self, entry_num) }
+    /// Запись, уже занятая другим файлом (sequence не совпадает со ссылкой), не возвращается:
+    /// ее атрибуты смешались бы с атрибутами базовой записи.
+    fn fetch_extent(&mut self, entry_num: u64, sequence: u16) -> Option<Vec<u8>> {
+        if let Some(buf) = self.extent_cache.get(entry_num, sequence) {
+            return Some(buf);
+        }
+        let buf = fetch_fixed(self, entry_num)?;
+        self.extent_cache.insert(entry_num, buf.clone());
+        sequence_matches(&buf, sequence).then_some(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::bench::synthetic_mft;
+
+    #[test]
+    fn extent_cache_evicts_least_recently_used() {
+        let mut cache = ExtentCache::default();
+        for entry in 0..EXTENT_CACHE_CAPACITY as u64 {
+            cache.insert(entry, vec![entry as u8]);
+        }
+        // Частые обращения к одной записи не раздувают очередь и не сбивают порядок
+        for _ in 0..EXTENT_CACHE_CAPACITY * 5 {
+            assert_eq!(cache.get(0, 0), Some(vec![0]));
+        }
+        assert!(cache.order.len() <= EXTENT_CACHE_CAPACITY * 4 + 1);
+
+        cache.insert(EXTENT_CACHE_CAPACITY as u64, vec![1]);
+        assert_eq!(cache.records.len(), EXTENT_CACHE_CAPACITY);
+        assert_eq!(cache.get(1, 0), None);
+        assert_eq!(cache.get(0, 0), Some(vec![0]));
+        assert_eq!(cache.get(2, 0), Some(vec![2]));
+
+        // Повторная вставка той же записи ничего не вытесняет
+        cache.insert(2, vec![2]);
+        assert_eq!(cache.records.len(), EXTENT_CACHE_CAPACITY);
+        cache.insert(EXTENT_CACHE_CAPACITY as u64 + 1, vec![3]);
+        assert_eq!(cache.get(3, 0), None);
+        assert_eq!(cache.get(2, 0), Some(vec![2]));
+    }
+
+    #[test]
+    fn fetch_extent_checks_sequence_number() {
+        let mft = synthetic_mft(&[(5, "a.txt", false, true)]);
+        let mut parser = MftParser::from_memory(mft, 1024, 512);
+        // Сырая запись - с USN в хвосте сектора, расширение - после fixups
+        assert_eq!(&parser.fetch_record(16).unwrap()[510..512], [1, 0]);
+
+        for _ in 0..2 {
+            let extent = parser.fetch_extent(16, 1).unwrap();
+            assert_eq!(&extent[510..512], [0, 0]);
+            assert_eq!(parser.fetch_extent(16, 2), None);
+            assert!(parser.fetch_extent(16, 0).is_some());
+        }
+        assert_eq!(parser.extent_cache.records.len(), 1);
+        assert_eq!(parser.fetch_extent(99, 0), None);
+    }
+
+    #[test]
+    fn torn_sector_is_reported() {
+        let mut record = synthetic_mft(&[(5, "a.txt", false, true)])[16 * 1024..17 * 1024].to_vec();
+        record[1022] = 7;
+        let header = MftRecordHeader::parse(&record).unwrap();
+        assert_eq!(apply_fixups(&mut record, &header, 512), FixupResult::TornWrite);
+        assert_eq!(apply_fixups(&mut record[..1000], &header, 512), FixupResult::Failed);
+    }
 }
\ No newline at end of file
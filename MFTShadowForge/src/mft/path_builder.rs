@@ -1,44 +1,259 @@
+use std::borrow::Cow;
 use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use crate::mft::utils::create_temp_file;
+
+/// Насколько достоверен восстановленный путь. Порядок вариантов - от надежного к ненадежному:
+/// при нескольких запасных шагах в одной цепочке остается худший.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PathConfidence {
+    /// Вся цепочка родителей сошлась по sequence number
+    Full,
+    /// Основной $FILE_NAME ведет в никуда, путь взят по другой жесткой ссылке записи
+    HardLink,
+    /// Родитель удален, но его запись еще не занята: взяты старое имя и старый родитель из нее
+    StaleParent,
+    /// Имя переиспользованного родителя взято из элемента индекса $I30 (в т.ч. из slack)
+    Carved,
+    /// Цепочка оборвана: родитель переиспользован или отсутствует, либо цикл
+    Orphan,
+}
+
+impl PathConfidence {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PathConfidence::Full => "Full",
+            PathConfidence::HardLink => "HardLink",
+            PathConfidence::StaleParent => "StaleParent",
+            PathConfidence::Carved => "Carved",
+            PathConfidence::Orphan => "Orphan",
+        }
+    }
+}
+
+/// Запись дерева в режиме --low-memory: 16 байт вместо записи HashMap со String.
+#[derive(Debug, Clone, Copy)]
+struct PackedEntry {
+    /// Номер родителя (48 бит) и флаг `IN_USE_BIT` записи
+    parent_num: u64,
+    parent_seq: u16,
+    self_seq: u16,
+    /// Номер имени в `SpilledNames`; `NO_NAME` - записи нет
+    name: u32,
+}
+
+const NO_NAME: u32 = u32::MAX;
+/// Старший бит `PackedEntry::parent_num`: запись занята (номер записи NTFS - 48 бит).
+const IN_USE_BIT: u64 = 1 << 63;
+/// Сколько байт имен копится в памяти до дозаписи во временный файл.
+const SPILL_CHUNK: usize = 1024 * 1024;
+
+/// Имена записей во временном файле: в памяти - только смещения и хеши для интернирования
+/// (одинаковые имена вроде `desktop.ini` хранятся один раз). Файл удаляется при drop.
+#[derive(Debug)]
+struct SpilledNames {
+    path: PathBuf,
+    file: File,
+    /// Байт уже в файле; имена дальше этого смещения еще в `pending`
+    flushed: u64,
+    pending: Vec<u8>,
+    /// Номер имени -> (смещение, длина в байтах UTF-8)
+    spans: Vec<(u64, u32)>,
+    /// Хеш имени -> номер; при коллизии хешей имя просто не интернируется
+    by_hash: HashMap<u64, u32>,
+}
+
+impl SpilledNames {
+    fn create() -> io::Result<Self> {
+        let (path, file) = create_temp_file("mft_shadow_forge_names", "bin")?;
+        Ok(Self { path, file, flushed: 0, pending: Vec::new(), spans: Vec::new(), by_hash: HashMap::new() })
+    }
+
+    fn intern(&mut self, name: &str) -> io::Result<u32> {
+        let mut hasher = DefaultHasher::new();
+        name.hash(&mut hasher);
+        let hash = hasher.finish();
+        if let Some(&id) = self.by_hash.get(&hash) {
+            if self.get(id)? == name {
+                return Ok(id);
+            }
+        }
+
+        let id = self.spans.len() as u32;
+        self.spans.push((self.flushed + self.pending.len() as u64, name.len() as u32));
+        self.by_hash.entry(hash).or_insert(id);
+        self.pending.extend_from_slice(name.as_bytes());
+        if self.pending.len() >= SPILL_CHUNK {
+            self.file.write_all(&self.pending)?;
+            self.flushed += self.pending.len() as u64;
+            self.pending.clear();
+        }
+        Ok(id)
+    }
+
+    fn get(&self, id: u32) -> io::Result<Cow<'_, str>> {
+        let (offset, len) = self.spans[id as usize];
+        if offset >= self.flushed {
+            let start = (offset - self.flushed) as usize;
+            return Ok(String::from_utf8_lossy(&self.pending[start..start + len as usize]));
+        }
+        let mut buf = vec![0u8; len as usize];
+        read_exact_at(&self.file, &mut buf, offset)?;
+        Ok(Cow::Owned(String::from_utf8(buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?))
+    }
+}
+
+impl Drop for SpilledNames {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(unix)]
+fn read_exact_at(file: &File, buf: &mut [u8], offset: u64) -> io::Result<()> {
+    std::os::unix::fs::FileExt::read_exact_at(file, buf, offset)
+}
+
+#[cfg(windows)]
+fn read_exact_at(file: &File, mut buf: &mut [u8], mut offset: u64) -> io::Result<()> {
+    while !buf.is_empty() {
+        match std::os::windows::fs::FileExt::seek_read(file, buf, offset)? {
+            0 => return Err(io::ErrorKind::UnexpectedEof.into()),
+            n => {
+                buf = &mut buf[n..];
+                offset += n as u64;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Хранилище дерева: по умолчанию все в памяти, с --low-memory - плотный массив
+/// по номерам записей и имена во временном файле.
+#[derive(Debug)]
+enum Entries {
+    // entry_num -> (parent_entry_num, parent_sequence_number, self_sequence_number, in_use, name)
+    Memory(HashMap<u64, (u64, u16, u16, bool, String)>),
+    Spilled { entries: Vec<PackedEntry>, names: SpilledNames },
+}
+
+impl Default for Entries {
+    fn default() -> Self {
+        Entries::Memory(HashMap::new())
+    }
+}
 
 #[derive(Debug, Default)]
 pub struct PathBuilder {
-    // entry_num -> (parent_entry_num, parent_sequence_number, self_sequence_number, name)
-    entries: HashMap<u64, (u64, u16, u16, String)>,
+    entries: Entries,
+    // (entry_num, sequence_number) -> (каталог, имя) из элементов индексов $I30
+    carved: HashMap<(u64, u16), (u64, String)>,
 }
 
 impl PathBuilder {
     pub fn new() -> Self {
-        Self { entries: HashMap::new() }
+        Self::default()
+    }
+
+    /// Режим ограниченной памяти (parse --low-memory) для томов на десятки миллионов записей.
+    pub fn low_memory() -> io::Result<Self> {
+        Ok(Self { entries: Entries::Spilled { entries: Vec::new(), names: SpilledNames::create()? }, carved: HashMap::new() })
     }
 
     pub fn reserve(&mut self, additional: usize) {
-        self.entries.reserve(additional);
+        match &mut self.entries {
+            Entries::Memory(entries) => entries.reserve(additional),
+            Entries::Spilled { entries, .. } => entries.reserve(additional),
+        }
+    }
+
+    pub fn add_entry(&mut self, entry_num: u64, self_seq: u16, in_use: bool, parent_num: u64, parent_seq: u16, name: String) {
+        match &mut self.entries {
+            Entries::Memory(entries) => {
+                entries.insert(entry_num, (parent_num, parent_seq, self_seq, in_use, name));
+            }
+            Entries::Spilled { entries, names } => {
+                // Ошибка временного файла (диск заполнен) - запись остается без имени, как отсутствующая
+                let Ok(name) = names.intern(&name) else { return };
+                let slot = entry_num as usize;
+                if slot >= entries.len() {
+                    entries.resize(slot + 1, PackedEntry { parent_num: 0, parent_seq: 0, self_seq: 0, name: NO_NAME });
+                }
+                let parent_num = if in_use { parent_num | IN_USE_BIT } else { parent_num & !IN_USE_BIT };
+                entries[slot] = PackedEntry { parent_num, parent_seq, self_seq, name };
+            }
+        }
     }
 
-    pub fn add_entry(&mut self, entry_num: u64, self_seq: u16, parent_num: u64, parent_seq: u16, name: String) {
-        self.entries.insert(entry_num, (parent_num, parent_seq, self_seq, name));
+    /// Родитель, sequence родителя, собственный sequence, занятость и имя записи.
+    fn entry(&self, entry_num: u64) -> Option<(u64, u16, u16, bool, Cow<'_, str>)> {
+        match &self.entries {
+            Entries::Memory(entries) => entries.get(&entry_num)
+                .map(|(parent_num, parent_seq, self_seq, in_use, name)| (*parent_num, *parent_seq, *self_seq, *in_use, Cow::Borrowed(name.as_str()))),
+            Entries::Spilled { entries, names } => {
+                let entry = entries.get(usize::try_from(entry_num).ok()?).filter(|e| e.name != NO_NAME)?;
+                let name = names.get(entry.name).ok()?;
+                Some((entry.parent_num & !IN_USE_BIT, entry.parent_seq, entry.self_seq, entry.parent_num & IN_USE_BIT != 0, name))
+            }
+        }
+    }
+
+    /// Имя и каталог файла по элементу индекса $I30 (живому или из slack): запасной путь,
+    /// когда запись по этому номеру уже занята другим файлом. Первый добавленный элемент важнее.
+    pub fn add_carved(&mut self, entry_num: u64, seq: u16, dir: u64, name: String) {
+        self.carved.entry((entry_num, seq)).or_insert((dir, name));
     }
 
     pub fn get_full_path(&self, entry_num: u64, expected_seq: u16) -> String {
+        self.resolve(entry_num, expected_seq).0
+    }
+
+    /// Полный путь и его достоверность. Переиспользованный родитель, известный по $I30,
+    /// заменяется старым именем, и подъем продолжается от каталога, в чьем индексе оно найдено.
+    /// Удаленный, но еще не занятый родитель (sequence увеличен при удалении ровно на 1)
+    /// дает старое имя прямо из своей записи, подъем идет дальше по его старому родителю.
+    pub fn resolve(&self, entry_num: u64, expected_seq: u16) -> (String, PathConfidence) {
         let mut path_parts = Vec::new();
         let mut current_entry = entry_num;
         let mut current_expected_seq = expected_seq;
         let mut visited = HashSet::new();
+        let mut confidence = PathConfidence::Full;
 
-        while let Some(&(parent_num, parent_seq, self_seq, ref name)) = self.entries.get(&current_entry) {
+        loop {
+            let Some((parent_num, parent_seq, self_seq, in_use, name)) = self.entry(current_entry) else {
+                confidence = PathConfidence::Orphan;
+                break;
+            };
             if !visited.insert(current_entry) {
                 path_parts.push(String::from("<CORRUPTED_LOOP>"));
+                confidence = PathConfidence::Orphan;
                 break;
             }
 
             // ИЗМЕНЕНИЕ 2.2: Проверка Sequence Number (защита от Orphan путей для удаленных файлов)
             if current_expected_seq != 0 && self_seq != current_expected_seq {
-                path_parts.push(String::from("<ORPHAN_OR_REALLOCATED>"));
-                break;
+                if let Some((dir, old_name)) = self.carved.get(&(current_entry, current_expected_seq)) {
+                    path_parts.push(old_name.clone());
+                    confidence = confidence.max(PathConfidence::Carved);
+                    // Каталог из индекса живой: его sequence не проверяется
+                    current_entry = *dir;
+                    current_expected_seq = 0;
+                    continue;
+                }
+                if in_use || self_seq != next_sequence(current_expected_seq) {
+                    path_parts.push(String::from("<ORPHAN_OR_REALLOCATED>"));
+                    confidence = PathConfidence::Orphan;
+                    break;
+                }
+                confidence = confidence.max(PathConfidence::StaleParent);
             }
 
             if name != "." {
-                path_parts.push(name.clone());
+                path_parts.push(name.into_owned());
             }
 
             if current_entry == 5 || parent_num == current_entry {
@@ -50,18 +265,121 @@ impl PathBuilder {
         }
 
         path_parts.reverse();
-        if path_parts.is_empty() {
+        let path = if path_parts.is_empty() {
             String::from("\\")
         } else {
             format!("\\{}", path_parts.join("\\"))
+        };
+        (path, confidence)
+    }
+}
+
+/// Sequence number записи после ее освобождения: NTFS увеличивает его при удалении, пропуская 0.
+fn next_sequence(seq: u16) -> u16 {
+    match seq.wrapping_add(1) {
+        0 => 1,
+        next => next,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Дерево со всеми исходами подъема; записи заданы как (номер, sequence, занята, родитель, sequence родителя, имя).
+    fn build(mut builder: PathBuilder) -> PathBuilder {
+        let entries: &[(u64, u16, bool, u64, u16, &str)] = &[
+            (5, 5, true, 5, 5, "."),
+            (30, 1, true, 5, 5, "Users"),
+            (31, 1, true, 30, 1, "a.txt"),
+            // Удаленный каталог: sequence увеличен на 1, запись свободна
+            (40, 3, false, 30, 1, "Old"),
+            (41, 2, false, 40, 2, "b.txt"),
+            // Sequence после 0xFFFF - 1, а не 0
+            (42, 1, false, 30, 1, "Wrapped"),
+            (43, 1, false, 42, u16::MAX, "w.txt"),
+            // Каталог занят заново другим файлом
+            (50, 7, true, 5, 5, "New"),
+            (51, 1, true, 50, 5, "c.txt"),
+            // Переиспользованный каталог, старое имя которого есть в $I30
+            (60, 9, true, 5, 5, "Reused"),
+            (61, 1, false, 60, 8, "d.txt"),
+            // Удален, но sequence ушел дальше чем на 1
+            (70, 4, false, 30, 1, "Twice"),
+            (71, 1, false, 70, 2, "e.txt"),
+            (80, 1, true, 81, 1, "loop1"),
+            (81, 1, true, 80, 1, "loop2"),
+        ];
+        for &(entry, seq, in_use, parent, parent_seq, name) in entries {
+            builder.add_entry(entry, seq, in_use, parent, parent_seq, name.to_string());
         }
+        builder.add_carved(60, 8, 30, "Gone".to_string());
+        builder.add_carved(60, 8, 5, "Later".to_string());
+        builder
+    }
+
+    fn check(builder: &PathBuilder) {
+        let resolve = |entry, seq| {
+            let (path, confidence) = builder.resolve(entry, seq);
+            (path, confidence.as_str())
+        };
+        assert_eq!(resolve(5, 5), ("\\".to_string(), "Full"));
+        assert_eq!(resolve(31, 1), ("\\Users\\a.txt".to_string(), "Full"));
+        assert_eq!(resolve(41, 2), ("\\Users\\Old\\b.txt".to_string(), "StaleParent"));
+        assert_eq!(resolve(43, 1), ("\\Users\\Wrapped\\w.txt".to_string(), "StaleParent"));
+        assert_eq!(resolve(51, 1), ("\\<ORPHAN_OR_REALLOCATED>\\c.txt".to_string(), "Orphan"));
+        assert_eq!(resolve(61, 1), ("\\Users\\Gone\\d.txt".to_string(), "Carved"));
+        assert_eq!(resolve(71, 1), ("\\<ORPHAN_OR_REALLOCATED>\\e.txt".to_string(), "Orphan"));
+        assert_eq!(resolve(80, 1), ("\\<CORRUPTED_LOOP>\\loop2\\loop1".to_string(), "Orphan"));
+        assert_eq!(resolve(31, 2), ("\\<ORPHAN_OR_REALLOCATED>".to_string(), "Orphan"));
+        assert_eq!(resolve(999, 0), ("\\".to_string(), "Orphan"));
+        // Без ожидаемого sequence запись берется как есть
+        assert_eq!(resolve(51, 0), ("\\<ORPHAN_OR_REALLOCATED>\\c.txt".to_string(), "Orphan"));
+        assert_eq!(builder.get_full_path(50, 0), "\\New");
+    }
+
+    #[test]
+    fn resolve_reports_confidence() {
+        check(&build(PathBuilder::new()));
+    }
+
+    #[test]
+    fn low_memory_resolves_like_memory() {
+        let builder = build(PathBuilder::low_memory().unwrap());
+        check(&builder);
+        // Повторное добавление записи заменяет ее, а свободная запись не становится занятой
+        let mut builder = builder;
+        builder.add_entry(31, 1, true, 30, 1, "renamed.txt".to_string());
+        builder.add_entry(40, 3, false, 30, 1, "Old".to_string());
+        assert_eq!(builder.resolve(31, 1).0, "\\Users\\renamed.txt");
+        assert_eq!(builder.resolve(41, 2).1, PathConfidence::StaleParent);
     }
 
-    pub fn get_parent_path(&self, parent_num: u64, parent_seq: u16) -> String {
-        let mut parent = self.get_full_path(parent_num, parent_seq);
-        if parent.is_empty() {
-            parent = String::from("\\");
+    #[test]
+    fn low_memory_names_spill_to_file() {
+        let mut builder = PathBuilder::low_memory().unwrap();
+        builder.reserve(30_000);
+        builder.add_entry(5, 5, true, 5, 5, ".".to_string());
+        let name = |i: u64| format!("{:060}.txt", i);
+        for i in 16..30_000 {
+            builder.add_entry(i, 1, true, 5, 5, name(i));
         }
-        parent
+        // Одинаковые имена хранятся один раз
+        for i in 30_000..30_100 {
+            builder.add_entry(i, 1, true, 5, 5, "desktop.ini".to_string());
+        }
+        let Entries::Spilled { names, .. } = &builder.entries else { unreachable!() };
+        assert!(names.flushed > 0);
+        assert_eq!(names.spans.len(), 1 + (30_000 - 16) + 1);
+
+        for i in [16, 17, 15_000, 29_999] {
+            assert_eq!(builder.resolve(i, 1), (format!("\\{}", name(i)), PathConfidence::Full));
+        }
+        assert_eq!(builder.get_full_path(30_050, 1), "\\desktop.ini");
+        assert_eq!(builder.resolve(10, 0).1, PathConfidence::Orphan);
+
+        let path = names.path.clone();
+        drop(builder);
+        assert!(!path.exists());
     }
 }
\ No newline at end of file
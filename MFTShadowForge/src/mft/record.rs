@@ -46,4 +46,16 @@ impl MftRecordHeader {
     pub fn is_directory(&self) -> bool {
         self.flags & 0x02 != 0
     }
+
+    /// Размер записи и сектора по заголовку записи 0: allocated_size и длина массива USA
+    /// (по слову на сектор плюс USN).
+    pub fn geometry(&self) -> Option<(usize, u16)> {
+        if self.signature != "FILE" { return None; }
+        let record_size = self.allocated_size as usize;
+        let sectors = (self.update_sequence_size as usize).checked_sub(1).filter(|&s| s > 0)?;
+        if !record_size.is_power_of_two() || !(256..=65536).contains(&record_size) || !record_size.is_multiple_of(sectors) {
+            return None;
+        }
+        Some((record_size, (record_size / sectors) as u16))
+    }
 }
\ No newline at end of file
@@ -0,0 +1,54 @@
+use byteorder::{ByteOrder, LittleEndian};
+use chrono::{DateTime, Utc};
+use super::utils::filetime_to_datetime;
+
+/// Метаданные файла $I из $Recycle.Bin (Vista+).
+/// - v1 (Vista/7): путь фиксированной длины 260 символов со смещения 24
+/// - v2 (Win10+): длина пути (u32) по смещению 24, путь со смещения 28
+#[derive(Debug)]
+pub struct RecycleInfo {
+    pub original_size: u64,
+    pub deleted_at: DateTime<Utc>,
+    pub original_path: String,
+}
+
+impl RecycleInfo {
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        if data.len() < 24 { return None; }
+        let version = LittleEndian::read_u64(&data[0..8]);
+
+        let path_bytes = match version {
+            1 => data.get(24..std::cmp::min(data.len(), 24 + 520))?,
+            2 => {
+                let chars = LittleEndian::read_u32(data.get(24..28)?) as usize;
+                data.get(28..28usize.checked_add(chars.checked_mul(2)?)?)?
+            }
+            _ => return None,
+        };
+
+        let u16s: Vec<u16> = path_bytes.chunks_exact(2)
+            .map(LittleEndian::read_u16)
+            .take_while(|&c| c != 0)
+            .collect();
+        if u16s.is_empty() { return None; }
+
+        Some(Self {
+            original_size: LittleEndian::read_u64(&data[8..16]),
+            deleted_at: filetime_to_datetime(LittleEndian::read_u64(&data[16..24])),
+            original_path: String::from_utf16_lossy(&u16s),
+        })
+    }
+
+    /// Имя парного $R-файла с содержимым: `$IABC123.txt` -> `$RABC123.txt`.
+    pub fn data_file_name(i_name: &str) -> String {
+        format!("$R{}", &i_name[2..])
+    }
+
+    /// Подходит ли запись под соглашение об именах $I в корзине.
+    pub fn is_candidate(full_path_lc: &str, file_name: &str) -> bool {
+        full_path_lc.contains("\\$recycle.bin\\")
+            && file_name.len() > 2
+            && file_name.is_char_boundary(2)
+            && file_name[..2].eq_ignore_ascii_case("$i")
+    }
+}
\ No newline at end of file
@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+use std::io::{self, Read};
+
+use byteorder::{ByteOrder, LittleEndian};
+
+/// $SDS пишется блоками по 256 КБ, за каждым блоком - его зеркальная копия.
+const SDS_BLOCK: usize = 0x40000;
+/// Заголовок элемента $SDS: hash, security_id, offset, length.
+const SDS_HEADER: usize = 20;
+
+const SE_DACL_PRESENT: u16 = 0x0004;
+const SE_DACL_AUTO_INHERITED: u16 = 0x0400;
+const SE_DACL_PROTECTED: u16 = 0x1000;
+
+/// Типы ACE и их обозначения SDDL.
+const ACE_TYPES: [(u8, &str); 11] = [
+    (0x00, "A"), (0x01, "D"), (0x02, "AU"), (0x03, "AL"),
+    (0x05, "OA"), (0x06, "OD"), (0x07, "OU"), (0x08, "OL"),
+    (0x09, "XA"), (0x0A, "XD"), (0x11, "ML"),
+];
+
+/// Флаги наследования ACE (SDDL).
+const ACE_FLAGS: [(u8, &str); 7] = [
+    (0x01, "OI"), (0x02, "CI"), (0x04, "NP"), (0x08, "IO"), (0x10, "ID"), (0x40, "SA"), (0x80, "FA"),
+];
+
+/// Типичные маски прав на файлы (SDDL); остальные выводятся в hex.
+const FILE_RIGHTS: [(u32, &str); 8] = [
+    (0x001F_01FF, "FA"), (0x0012_0089, "FR"), (0x0012_0116, "FW"), (0x0012_00A0, "FX"),
+    (0x1000_0000, "GA"), (0x8000_0000, "GR"), (0x4000_0000, "GW"), (0x2000_0000, "GX"),
+];
+
+/// Дескриптор безопасности из $Secure:$SDS в виде, пригодном для строки отчета.
+#[derive(Debug, Clone, Default)]
+pub struct SecurityDescriptor {
+    pub owner: Option<String>,
+    pub group: Option<String>,
+    /// DACL в нотации SDDL: `D:PAI(A;OICI;FA;;;S-1-5-18)...`; None - DACL нет
+    pub dacl: Option<String>,
+}
+
+/// SID в строковой форме `S-1-5-21-...`.
+pub fn format_sid(data: &[u8]) -> Option<String> {
+    let count = *data.get(1)? as usize;
+    let sid = data.get(..8 + count * 4)?;
+    if sid[0] != 1 { return None; }
+    let authority = sid[2..8].iter().fold(0u64, |acc, &b| (acc << 8) | b as u64);
+    let mut text = if authority >> 32 == 0 { format!("S-1-{}", authority) } else { format!("S-1-0x{:012X}", authority) };
+    for sub in sid[8..].chunks_exact(4) {
+        text.push_str(&format!("-{}", LittleEndian::read_u32(sub)));
+    }
+    Some(text)
+}
+
+fn format_ace(ace: &[u8]) -> Option<String> {
+    let (kind, flags, mask) = (ace[0], ace[1], LittleEndian::read_u32(ace.get(4..8)?));
+    let kind_name = ACE_TYPES.iter().find(|(t, _)| *t == kind).map(|(_, n)| n.to_string()).unwrap_or_else(|| format!("0x{:02X}", kind));
+    let flag_names: String = ACE_FLAGS.iter().filter(|(f, _)| flags & f != 0).map(|(_, n)| *n).collect();
+    let rights = FILE_RIGHTS.iter().find(|(m, _)| *m == mask).map(|(_, n)| n.to_string()).unwrap_or_else(|| format!("0x{:X}", mask));
+    // Объектные ACE: после маски - флаги и до двух GUID
+    let sid_offset = match kind {
+        0x05..=0x08 => {
+            let object_flags = LittleEndian::read_u32(ace.get(8..12)?);
+            12 + 16 * (object_flags & 1) as usize + 16 * ((object_flags >> 1) & 1) as usize
+        }
+        _ => 8,
+    };
+    let sid = format_sid(ace.get(sid_offset..)?)?;
+    Some(format!("({};{};{};;;{})", kind_name, flag_names, rights, sid))
+}
+
+/// DACL в SDDL; ACE, которые не удалось разобрать, выводятся как `(?)`.
+fn format_dacl(acl: &[u8], control: u16) -> Option<String> {
+    let mut text = String::from("D:");
+    if control & SE_DACL_PROTECTED != 0 { text.push('P'); }
+    if control & SE_DACL_AUTO_INHERITED != 0 { text.push_str("AI"); }
+    let size = (LittleEndian::read_u16(acl.get(2..4)?) as usize).min(acl.len());
+    let count = LittleEndian::read_u16(&acl[4..6]);
+    let mut pos = 8;
+    for _ in 0..count {
+        let Some(header) = acl.get(pos..pos + 4) else { break };
+        let ace_size = LittleEndian::read_u16(&header[2..4]) as usize;
+        if ace_size < 8 || pos + ace_size > size { break; }
+        text.push_str(&format_ace(&acl[pos..pos + ace_size]).unwrap_or_else(|| "(?)".to_string()));
+        pos += ace_size;
+    }
+    Some(text)
+}
+
+impl SecurityDescriptor {
+    /// Разбирает самоотносительный SECURITY_DESCRIPTOR.
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        if data.len() < 20 || data[0] != 1 { return None; }
+        let control = LittleEndian::read_u16(&data[2..4]);
+        let at = |pos: usize| match LittleEndian::read_u32(&data[pos..pos + 4]) as usize {
+            0 => None,
+            offset => data.get(offset..),
+        };
+        let dacl = match (control & SE_DACL_PRESENT != 0, at(16)) {
+            (false, _) => None,
+            // Присутствующий, но пустой (NULL) DACL - полный доступ для всех
+            (true, None) => Some("D:NO_ACCESS_CONTROL".to_string()),
+            (true, Some(acl)) => format_dacl(acl, control),
+        };
+        Some(Self { owner: at(4).and_then(format_sid), group: at(8).and_then(format_sid), dacl })
+    }
+}
+
+/// Разбирает поток $Secure:$SDS: security_id -> дескриптор. Зеркальные блоки пропускаются,
+/// из повторов одного security_id берется первый; поток читается по блокам, целиком в память не грузится.
+pub fn scan_sds<R: Read>(mut reader: R) -> io::Result<HashMap<u32, SecurityDescriptor>> {
+    let mut descriptors = HashMap::new();
+    let mut block = vec![0u8; SDS_BLOCK];
+    let mut block_offset = 0u64;
+    loop {
+        let filled = read_full(&mut reader, &mut block)?;
+        if filled == 0 { break; }
+        let data = &block[..filled];
+        let mut pos = 0;
+        while pos + SDS_HEADER <= data.len() {
+            let security_id = LittleEndian::read_u32(&data[pos + 4..pos + 8]);
+            let offset = LittleEndian::read_u64(&data[pos + 8..pos + 16]);
+            let length = LittleEndian::read_u32(&data[pos + 16..pos + 20]) as usize;
+            // Конец заполненной части блока: дальше нули или остатки старых элементов
+            if offset != block_offset + pos as u64 || length < SDS_HEADER || pos + length > data.len() { break; }
+            if let Some(descriptor) = SecurityDescriptor::parse(&data[pos + SDS_HEADER..pos + length]) {
+                descriptors.entry(security_id).or_insert(descriptor);
+            }
+            pos += (length + 15) & !15;
+        }
+        // Зеркальная копия блока
+        let mirrored = read_full(&mut reader, &mut block)?;
+        block_offset += (filled + mirrored) as u64;
+        if filled < SDS_BLOCK || mirrored < SDS_BLOCK { break; }
+    }
+    Ok(descriptors)
+}
+
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
\ No newline at end of file
@@ -0,0 +1,115 @@
+use byteorder::{ByteOrder, LittleEndian};
+use chrono::{DateTime, Datelike, Utc};
+
+use super::attributes::{FileNameAttribute, StandardInformation};
+
+/// Минимальная длина строки, извлекаемой из slack.
+const MIN_STRING: usize = 5;
+/// Сколько строк одной записи попадает в отчет.
+const MAX_STRINGS: usize = 32;
+/// Длина строки в отчете (в символах).
+const MAX_STRING_LEN: usize = 256;
+
+/// Остаток прежнего атрибута в slack записи: $STANDARD_INFORMATION или $FILE_NAME.
+#[derive(Debug)]
+pub struct SlackAttribute {
+    pub attr_type: u32,
+    /// Смещение заголовка атрибута от начала записи
+    pub offset: usize,
+    /// Имя и родитель - только у $FILE_NAME
+    pub name: Option<String>,
+    pub parent_reference: Option<u64>,
+    pub times: [DateTime<Utc>; 4],
+}
+
+/// Slack записи MFT - байты от real_size до allocated_size: в нем остаются
+/// хвосты атрибутов, которые были в записи до ее сокращения или переиспользования.
+#[derive(Debug, Default)]
+pub struct RecordSlackScan {
+    pub non_zero: usize,
+    pub strings: Vec<String>,
+    pub attributes: Vec<SlackAttribute>,
+}
+
+/// Все метки правдоподобны: 1980..2100 (нули и мусор дают 1601 или далекое будущее).
+fn plausible(times: &[DateTime<Utc>; 4]) -> bool {
+    times.iter().all(|t| (1980..2100).contains(&t.year()))
+}
+
+/// Похожий на резидентный $SI/$FN заголовок атрибута по смещению `pos` записи.
+fn attribute_at(record: &[u8], pos: usize) -> Option<SlackAttribute> {
+    let header = record.get(pos..pos + 0x18)?;
+    let attr_type = LittleEndian::read_u32(&header[0..4]);
+    if attr_type != 0x10 && attr_type != 0x30 { return None; }
+    let attr_len = LittleEndian::read_u32(&header[4..8]) as usize;
+    // Резидентный, без имени атрибута
+    if header[8] != 0 || header[9] != 0 || attr_len < 0x18 || !attr_len.is_multiple_of(8) { return None; }
+    let value_len = LittleEndian::read_u32(&header[16..20]) as usize;
+    let value_off = LittleEndian::read_u16(&header[20..22]) as usize;
+    if value_off < 0x18 || value_off.checked_add(value_len)? > attr_len { return None; }
+    let value = record.get(pos + value_off..pos + value_off + value_len)?;
+    let found = if attr_type == 0x10 {
+        let si = StandardInformation::parse(value)?;
+        SlackAttribute { attr_type, offset: pos, name: None, parent_reference: None,
+            times: [si.creation_time, si.modified_time, si.mft_modified_time, si.accessed_time] }
+    } else {
+        let fn_attr = FileNameAttribute::parse(value)?;
+        if fn_attr.name.is_empty() || fn_attr.name.chars().any(char::is_control) { return None; }
+        SlackAttribute { attr_type, offset: pos, name: Some(fn_attr.name), parent_reference: Some(fn_attr.parent_directory_reference),
+            times: [fn_attr.creation_time, fn_attr.modified_time, fn_attr.mft_modified_time, fn_attr.accessed_time] }
+    };
+    plausible(&found.times).then_some(found)
+}
+
+/// Строки ASCII и UTF-16LE из печатаемых символов не короче MIN_STRING.
+fn printable_strings(data: &[u8], out: &mut Vec<String>) {
+    let printable = |b: u8| (0x20..0x7F).contains(&b);
+    let mut push = |s: String| if out.len() < MAX_STRINGS && !out.contains(&s) { out.push(s.chars().take(MAX_STRING_LEN).collect()) };
+
+    let mut start = None;
+    for (i, &b) in data.iter().chain(std::iter::once(&0)).enumerate() {
+        match (printable(b), start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                if i - s >= MIN_STRING { push(String::from_utf8_lossy(&data[s..i]).into_owned()); }
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    for parity in 0..2 {
+        let mut current = String::new();
+        for pair in data[parity..].chunks(2).chain(std::iter::once(&[0u8, 0][..])) {
+            if pair.len() == 2 && pair[1] == 0 && printable(pair[0]) {
+                current.push(pair[0] as char);
+                continue;
+            }
+            if current.len() >= MIN_STRING { push(std::mem::take(&mut current)); }
+            current.clear();
+        }
+    }
+}
+
+/// Разбор slack записи (после fixups): объем, ненулевые байты, строки и остатки $SI/$FN.
+/// None - slack пуст или заполнен нулями.
+pub fn scan_record_slack(record: &[u8], real_size: usize, allocated_size: usize) -> Option<RecordSlackScan> {
+    let end = allocated_size.min(record.len());
+    let slack = record.get(real_size..end)?;
+    let non_zero = slack.iter().filter(|&&b| b != 0).count();
+    if non_zero == 0 { return None; }
+
+    let mut scan = RecordSlackScan { non_zero, ..Default::default() };
+    // Атрибуты выровнены по 8 байт от начала записи
+    let mut pos = real_size.next_multiple_of(8);
+    while pos + 0x18 <= end {
+        match attribute_at(&record[..end], pos) {
+            Some(attr) => {
+                pos += LittleEndian::read_u32(&record[pos + 4..pos + 8]) as usize;
+                scan.attributes.push(attr);
+            }
+            None => pos += 8,
+        }
+    }
+    printable_strings(slack, &mut scan.strings);
+    Some(scan)
+}
\ No newline at end of file
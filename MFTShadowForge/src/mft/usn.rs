@@ -0,0 +1,153 @@
+use std::io::{self, Read};
+
+use byteorder::{ByteOrder, LittleEndian};
+use serde::{Deserialize, Serialize};
+use super::utils::filetime_to_datetime;
+
+/// Флаги USN_REASON_* и их имена.
+const REASONS: [(u32, &str); 23] = [
+    (0x0000_0001, "DATA_OVERWRITE"),
+    (0x0000_0002, "DATA_EXTEND"),
+    (0x0000_0004, "DATA_TRUNCATION"),
+    (0x0000_0010, "NAMED_DATA_OVERWRITE"),
+    (0x0000_0020, "NAMED_DATA_EXTEND"),
+    (0x0000_0040, "NAMED_DATA_TRUNCATION"),
+    (0x0000_0100, "FILE_CREATE"),
+    (0x0000_0200, "FILE_DELETE"),
+    (0x0000_0400, "EA_CHANGE"),
+    (0x0000_0800, "SECURITY_CHANGE"),
+    (0x0000_1000, "RENAME_OLD_NAME"),
+    (0x0000_2000, "RENAME_NEW_NAME"),
+    (0x0000_4000, "INDEXABLE_CHANGE"),
+    (0x0000_8000, "BASIC_INFO_CHANGE"),
+    (0x0001_0000, "HARD_LINK_CHANGE"),
+    (0x0002_0000, "COMPRESSION_CHANGE"),
+    (0x0004_0000, "ENCRYPTION_CHANGE"),
+    (0x0008_0000, "OBJECT_ID_CHANGE"),
+    (0x0010_0000, "REPARSE_POINT_CHANGE"),
+    (0x0020_0000, "STREAM_CHANGE"),
+    (0x0040_0000, "TRANSACTED_CHANGE"),
+    (0x0080_0000, "INTEGRITY_CHANGE"),
+    (0x8000_0000, "CLOSE"),
+];
+
+/// Запись USN-журнала (USN_RECORD_V2 / USN_RECORD_V3).
+/// В V3 ссылки на файлы 128-битные, для NTFS значимы младшие 64 бита.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct UsnRecord {
+    pub usn: i64,
+    pub entry: u64,
+    pub sequence: u16,
+    pub parent_entry: u64,
+    pub parent_sequence: u16,
+    pub timestamp: String,
+    pub reason: u32,
+    pub reason_names: String,
+    pub file_attributes: u32,
+    pub file_name: String,
+}
+
+impl UsnRecord {
+    /// Разбирает запись в начале буфера. Возвращает запись и ее длину (RecordLength).
+    pub fn parse(data: &[u8]) -> Option<(Self, usize)> {
+        if data.len() < 8 { return None; }
+        let record_len = LittleEndian::read_u32(&data[0..4]) as usize;
+        if record_len < 8 || record_len > data.len() { return None; }
+        let rec = &data[..record_len];
+
+        // (file_ref, parent_ref, usn, timestamp, reason, attributes, name_len, name_off)
+        let offsets: [usize; 8] = match LittleEndian::read_u16(&rec[4..6]) {
+            2 => [8, 16, 24, 32, 40, 52, 56, 58],
+            3 => [8, 24, 40, 48, 56, 68, 72, 74],
+            _ => return None,
+        };
+        if record_len < offsets[7] + 2 { return None; }
+
+        let file_ref = LittleEndian::read_u64(&rec[offsets[0]..offsets[0] + 8]);
+        let parent_ref = LittleEndian::read_u64(&rec[offsets[1]..offsets[1] + 8]);
+        let name_len = LittleEndian::read_u16(&rec[offsets[6]..offsets[6] + 2]) as usize;
+        let name_off = LittleEndian::read_u16(&rec[offsets[7]..offsets[7] + 2]) as usize;
+
+        let name_bytes = rec.get(name_off..name_off.checked_add(name_len)?)?;
+        let u16s: Vec<u16> = name_bytes.chunks_exact(2).map(LittleEndian::read_u16).collect();
+
+        let reason = LittleEndian::read_u32(&rec[offsets[4]..offsets[4] + 4]);
+        Some((Self {
+            usn: LittleEndian::read_i64(&rec[offsets[2]..offsets[2] + 8]),
+            entry: file_ref & 0xFFFFFFFFFFFF,
+            sequence: (file_ref >> 48) as u16,
+            parent_entry: parent_ref & 0xFFFFFFFFFFFF,
+            parent_sequence: (parent_ref >> 48) as u16,
+            timestamp: filetime_to_datetime(LittleEndian::read_u64(&rec[offsets[3]..offsets[3] + 8])).to_rfc3339(),
+            reason,
+            reason_names: reason_names(reason),
+            file_attributes: LittleEndian::read_u32(&rec[offsets[5]..offsets[5] + 4]),
+            file_name: String::from_utf16_lossy(&u16s),
+        }, record_len))
+    }
+}
+
+/// Разбирает подряд идущие записи (ответ FSCTL_READ_USN_JOURNAL без первых 8 байт).
+pub fn parse_records(data: &[u8]) -> Vec<UsnRecord> {
+    let mut records = Vec::new();
+    let mut off = 0;
+    while let Some((rec, len)) = UsnRecord::parse(&data[off..]) {
+        records.push(rec);
+        off += len;
+    }
+    records
+}
+
+/// Потоково разбирает выгруженный поток `$UsnJrnl:$J`.
+/// Начало потока обычно разреженное (нули), записи выровнены на 8 байт;
+/// нули и нераспознанные блоки пропускаются.
+pub fn scan_journal<R: Read, F: FnMut(UsnRecord)>(mut reader: R, mut f: F) -> io::Result<()> {
+    const CHUNK: usize = 1 << 20;
+    // Максимальная запись V3: 76 байт заголовка + 255 символов имени
+    const MAX_RECORD: usize = 4096;
+
+    let mut buf = vec![0u8; CHUNK];
+    let (mut off, mut filled, mut eof) = (0usize, 0usize, false);
+    loop {
+        if !eof && filled - off < MAX_RECORD {
+            buf.copy_within(off..filled, 0);
+            filled -= off;
+            off = 0;
+            while filled < CHUNK {
+                let n = reader.read(&mut buf[filled..])?;
+                if n == 0 { eof = true; break; }
+                filled += n;
+            }
+        }
+        if off + 8 > filled { break; }
+
+        if LittleEndian::read_u32(&buf[off..off + 4]) == 0 {
+            // Пропуск разреженной области до следующего ненулевого блока
+            off = match buf[off..filled].iter().position(|b| *b != 0) {
+                Some(pos) => off + (pos & !7).max(8),
+                None => filled & !7,
+            };
+            if off + 8 > filled && eof { break; }
+            continue;
+        }
+
+        match UsnRecord::parse(&buf[off..filled]) {
+            Some((rec, len)) => {
+                f(rec);
+                off += (len + 7) & !7;
+            }
+            None => off += 8,
+        }
+    }
+    Ok(())
+}
+
+/// Имена установленных флагов причины: `FILE_CREATE|CLOSE`.
+pub fn reason_names(reason: u32) -> String {
+    REASONS.iter()
+        .filter(|(flag, _)| reason & flag != 0)
+        .map(|(_, name)| *name)
+        .collect::<Vec<_>>()
+        .join("|")
+}
\ No newline at end of file
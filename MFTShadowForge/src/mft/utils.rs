@@ -1,4 +1,10 @@
+use std::fs::{File, OpenOptions};
+use std::hash::{BuildHasher, RandomState};
+use std::io;
+use std::path::{Path, PathBuf};
 use chrono::{DateTime, TimeZone, Utc};
+use clap::ValueEnum;
+use unicode_normalization::{is_nfc, is_nfd, UnicodeNormalization};
 
 /// Конвертирует Windows FILETIME (100-нс интервалы с 1601-01-01) в DateTime<Utc>
 pub fn filetime_to_datetime(filetime: u64) -> DateTime<Utc> {
@@ -11,4 +17,71 @@ pub fn filetime_to_datetime(filetime: u64) -> DateTime<Utc> {
     Utc.timestamp_opt(seconds, nanoseconds)
         .single()
         .unwrap_or_else(|| Utc.timestamp_opt(0, 0).single().unwrap())
+}
+
+/// Форма юникод-нормализации имен после декодирования UTF-16.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum NameNormalization {
+    Nfc,
+    Nfd,
+    #[default]
+    None,
+}
+
+impl NameNormalization {
+    /// Приводит имя к выбранной форме (для `None` возвращает как есть).
+    pub fn apply(self, name: String) -> String {
+        match self {
+            NameNormalization::Nfc if !is_nfc(&name) => name.nfc().collect(),
+            NameNormalization::Nfd if !is_nfd(&name) => name.nfd().collect(),
+            _ => name,
+        }
+    }
+
+    /// Меняется ли имя при нормализации (без выбранной формы сравниваем с NFC,
+    /// в которой Windows хранит подавляющее большинство имен).
+    pub fn changes(self, name: &str) -> bool {
+        match self {
+            NameNormalization::Nfd => !is_nfd(name),
+            _ => !is_nfc(name),
+        }
+    }
+}
+
+/// Создает новый временный файл `<prefix>_<pid>_<случайный суффикс>.<ext>` (чтение и запись).
+/// Файл открывается только если его еще нет (`create_new`): заранее подложенный по
+/// предсказуемому имени файл или символическая ссылка не будут открыты. На Unix - права 0600.
+/// Удаление файла - на вызывающем.
+pub fn create_temp_file(prefix: &str, ext: &str) -> io::Result<(PathBuf, File)> {
+    let mut options = OpenOptions::new();
+    options.read(true).write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    create_with_random_name(prefix, &format!(".{}", ext), |path| options.open(path))
+}
+
+/// Создает новый временный каталог `<prefix>_<pid>_<случайный суффикс>` (на Unix - права 0700).
+/// Как и у [`create_temp_file`], уже существующий путь не используется. Удаление - на вызывающем.
+#[cfg(feature = "agent")]
+pub fn create_temp_dir(prefix: &str) -> io::Result<PathBuf> {
+    let mut builder = std::fs::DirBuilder::new();
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    create_with_random_name(prefix, "", |path| builder.create(path)).map(|(path, ())| path)
+}
+
+/// Подбирает случайное имя `<prefix>_<pid>_<суффикс><ext>` во временном каталоге,
+/// пока `create` не создаст объект по еще не занятому пути.
+fn create_with_random_name<T>(prefix: &str, ext: &str, mut create: impl FnMut(&Path) -> io::Result<T>) -> io::Result<(PathBuf, T)> {
+    let random = RandomState::new();
+    for attempt in 0u32..16 {
+        let suffix = random.hash_one((attempt, std::time::SystemTime::now()));
+        let path = std::env::temp_dir().join(format!("{}_{}_{:016x}{}", prefix, std::process::id(), suffix, ext));
+        match create(&path) {
+            Ok(created) => return Ok((path, created)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Err(io::Error::new(io::ErrorKind::AlreadyExists, crate::i18n::common::temp_name_exhausted()))
 }
\ No newline at end of file
@@ -1,8 +1,61 @@
+use std::collections::BTreeMap;
+
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize)]
+use crate::error::Outcome;
+
+/// Версия формата строки отчета (поле SchemaVersion). Увеличивается при изменениях,
+/// ломающих разбор: удаление или переименование поля, смена типа или смысла.
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// Произвольные метки источника (`--tag host=SRV01`): дописываются в каждую
+/// строку отчета, meta.json и summary.json.
+pub type Tags = BTreeMap<String, String>;
+
+/// Сведения о деле для маркировки артефактов (meta.json, summary.json, audit.log).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CaseInfo {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub case_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub evidence_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub examiner: Option<String>,
+    /// Дописывать поля дела в каждую строку отчета (в файлы не сохраняется)
+    #[serde(skip)]
+    pub in_rows: bool,
+}
+
+impl CaseInfo {
+    pub fn is_empty(&self) -> bool {
+        self.case_id.is_none() && self.evidence_id.is_none() && self.examiner.is_none()
+    }
+
+    /// Заполняет незаданные поля из `other` (например, из meta.json дампа).
+    pub fn or(mut self, other: &CaseInfo) -> Self {
+        self.case_id = self.case_id.or_else(|| other.case_id.clone());
+        self.evidence_id = self.evidence_id.or_else(|| other.evidence_id.clone());
+        self.examiner = self.examiner.or_else(|| other.examiner.clone());
+        self
+    }
+
+    /// Поля дела в виде меток строки отчета: CaseId, EvidenceId, Examiner.
+    pub fn row_tags(&self) -> Tags {
+        [("CaseId", &self.case_id), ("EvidenceId", &self.evidence_id), ("Examiner", &self.examiner)]
+            .into_iter()
+            .filter_map(|(k, v)| v.as_ref().map(|v| (k.to_string(), v.clone())))
+            .collect()
+    }
+}
+
+/// Строка отчета parse (одна запись MFT или ADS).
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "PascalCase")]
 pub struct MftEntry {
+    /// Версия формата строки (SCHEMA_VERSION)
+    pub schema_version: u32,
+
     pub entry_number: u64,
     pub sequence_number: u16,
 
@@ -18,11 +71,20 @@ pub struct MftEntry {
 
     #[serde(rename = "Full_Path")]
     pub full_path: String,
+    /// Достоверность Full_Path: Full, HardLink (по другой жесткой ссылке), Carved (имя
+    /// переиспользованного каталога из $I30), Orphan (цепочка оборвана)
+    pub path_confidence: String,
 
     pub has_ads: bool,
     pub is_ads: bool,
+    /// Все именованные потоки $DATA: имя, размер, резидентный ли
+    pub ads_streams: Vec<AdsStream>,
 
     pub file_size: u64,
+    /// Выделенный и инициализированный размеры non-resident $DATA (null для резидентного):
+    /// байты между InitializedSize и FileSize читаются как нули, после FileSize - slack
+    pub allocated_size_data: Option<u64>,
+    pub initialized_size: Option<u64>,
 
     pub created0x10: Option<String>,
     pub created0x30: Option<String>,
@@ -32,31 +94,95 @@ pub struct MftEntry {
     pub last_record_change0x30: Option<String>,
     pub last_access0x10: Option<String>,
     pub last_access0x30: Option<String>,
+    /// Исходные значения FILETIME (100-нс интервалы с 1601 года) с --raw-timestamps
+    pub raw_timestamps: Option<RawTimestamps>,
 
     pub update_sequence_number: u64,
     pub logfile_sequence_number: u64,
+    pub journal_usn: u64,
 
     pub security_id: u32,
+    /// Владелец, группа и DACL (SDDL): из атрибута $SECURITY_DESCRIPTOR записи или
+    /// дескриптора SecurityId из $Secure:$SDS (с --sds)
+    pub owner_sid: Option<String>,
+    pub group_sid: Option<String>,
+    pub dacl: Option<String>,
+    /// OwnerSid/GroupSid/Dacl взяты из резидентного атрибута $SECURITY_DESCRIPTOR (0x50) записи
+    pub resident_security_descriptor: bool,
     pub si_flags: u32,
+    pub is_sparse: bool,
+    pub is_compressed: bool,
+    /// Файл зашифрован EFS: на диске шифротекст
+    pub is_encrypted: bool,
+    pub is_offline: bool,
+    pub is_reparse: bool,
+    pub is_temporary: bool,
+    /// Тег reparse point ($REPARSE_POINT, 0xC0): SYMLINK, MOUNT_POINT, CLOUD, WOF, APPEXECLINK, ...
+    pub reparse_tag: Option<String>,
+    /// Цель symlink/junction (как записана в атрибуте), исполняемый файл AppExecLink
+    pub reparse_target: Option<String>,
+    /// Файл сжат Windows Overlay Filter (CompactOS): FileSize - исходный размер,
+    /// содержимое лежит в ADS WofCompressedData или во внешнем WIM
+    pub wof_compressed: bool,
+    pub wof_algorithm: Option<String>,
+    /// Размер сжатых данных WOF (поток WofCompressedData); None - не WOF или данные во внешнем WIM
+    pub wof_compressed_size: Option<u64>,
+    /// Есть поток $LOGGED_UTILITY_STREAM:$TXF_DATA - файл изменялся в транзакции TxF
+    pub has_txf_data: bool,
+    /// Метаданные шифрования из $LOGGED_UTILITY_STREAM:$EFS; None - потока нет
+    pub efs: Option<EfsInfo>,
 
     pub reference_count: u16,
+    /// Все $FILE_NAME записи; расхождение с ReferenceCount - LinkCountMismatch
+    pub hard_links: Vec<HardLink>,
     pub name_type: u8,
 
     pub timestomped: bool,
     pub fits_rules: bool,
+    /// Совпавшие правила (встроенные, --rules, --sigma) от самого важного
+    pub rule_matches: Option<Vec<RuleMatch>>,
+    pub known_good: bool,
 
     pub zone_id_contents: Option<String>,
     pub content_data: Option<String>,
+    /// Хеши резидентного $DATA (с --hash)
+    pub md5: Option<String>,
+    pub sha1: Option<String>,
+    pub sha256: Option<String>,
+    /// VCN -> LCN non-resident $DATA (с --runs)
+    pub data_runs: Option<Vec<DataRunEntry>>,
+
+    pub recycle_original_path: Option<String>,
+    pub recycle_deleted_at: Option<String>,
+    pub recycle_original_size: Option<u64>,
+    pub recycle_data_path: Option<String>,
 
     #[serde(rename = "uSecZeros")]
     pub u_sec_zeros: bool,
     pub copied: bool,
     
     pub torn_write: bool,
+
+    pub path_too_deep: bool,
+    pub path_too_long: bool,
+    pub repeated_nesting: bool,
+    pub dot_like_name: bool,
+    pub normalization_changed: bool,
+    pub case_collision: bool,
+    pub suspicious_reparse: bool,
+    pub posix_name_anomaly: bool,
+    pub compressed_exec_in_temp: bool,
+    pub link_count_mismatch: bool,
+    /// DOS-имя 8.3 записи (отдельный $FILE_NAME с name_type 2)
+    pub short_name: Option<String>,
+    /// Метки $FILE_NAME DOS-имени расходятся с метками Win32-имени
+    pub short_name_mismatch: bool,
     
     // ИЗМЕНЕНИЕ 3: Флаг для non-resident $ATTRIBUTE_LIST
     pub complex_extents: bool,
 
+    /// Идентификаторы экземпляра атрибута (смещение 0x0E заголовка) основного $FILE_NAME
+    /// и $STANDARD_INFORMATION - для сопоставления с операциями $LogFile
     pub fn_attribute_id: u16,
     pub other_attribute_id: u16,
 
@@ -66,6 +192,28 @@ pub struct MftEntry {
     pub base_record_reference: u64,
     pub real_size: u32,
     pub allocated_size: u32,
+
+    /// Сырая запись MFT до fixups в hex (с --preserve-flagged, только для записей с детектами)
+    pub raw_record: Option<String>,
+    /// Ненулевой slack записи (с --record-slack): строки и остатки прежних $SI/$FN
+    pub record_slack: Option<RecordSlack>,
+
+    /// Поля от плагинов (--plugin)
+    #[serde(flatten)]
+    pub plugin_fields: serde_json::Map<String, serde_json::Value>,
+
+    #[serde(flatten)]
+    pub tags: Tags,
+}
+
+impl MftEntry {
+    /// Выставлен хотя бы один признак-детект (тот же набор, что считает merge).
+    pub fn has_detections(&self) -> bool {
+        self.timestomped || self.fits_rules || self.copied || self.u_sec_zeros || self.torn_write || self.has_ads
+            || self.path_too_deep || self.path_too_long || self.repeated_nesting || self.dot_like_name
+            || self.case_collision || self.suspicious_reparse || self.posix_name_anomaly
+            || self.compressed_exec_in_temp || self.link_count_mismatch || self.short_name_mismatch
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -78,5 +226,247 @@ pub struct MftMeta {
     pub clusters_per_index_buffer: i8,     
     pub mft_record_size: u32,
     pub volume_serial_number: u64,
+    /// Смещение NTFS-партиции от начала образа/устройства
+    #[serde(default)]
+    pub partition_offset: u64,
+    pub source: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout: Option<MftLayoutReport>,
+    /// Метка, версия NTFS и флаги тома из записи $Volume
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub volume: Option<VolumeInfo>,
+    /// Номер теневой копии (HarddiskVolumeShadowCopyN), если $MFT снят со снимка VSS
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shadow_copy: Option<u32>,
+    #[serde(default, skip_serializing_if = "CaseInfo::is_empty")]
+    pub case: CaseInfo,
+    #[serde(default, skip_serializing_if = "Tags::is_empty")]
+    pub tags: Tags,
+}
+
+/// Сведения о томе из записи 3 ($Volume): $VOLUME_NAME и $VOLUME_INFORMATION.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VolumeInfo {
+    pub label: Option<String>,
+    /// Версия NTFS: "3.1" (Windows XP и новее), "3.0", "1.2"
+    pub ntfs_version: Option<String>,
+    pub volume_flags: u16,
+    /// VOLUME_IS_DIRTY: том не был корректно размонтирован (снят с живой системы, сбой
+    /// питания, chkdsk назначен) - недописанные записи (TornWrite) ожидаемы
+    pub dirty: bool,
+}
+
+impl VolumeInfo {
+    /// Строка для заголовка вывода: `label "DATA", NTFS 3.1, flags 0x0001 (dirty)` (на языке сообщений).
+    pub fn describe(&self) -> String {
+        crate::i18n::common::volume_describe(
+            self.label.as_deref().unwrap_or(""), self.ntfs_version.as_deref().unwrap_or("?"),
+            self.volume_flags, if self.dirty { " (dirty)" } else { "" })
+    }
+}
+
+/// Заполненность $MFT: сколько записей занято, свободно, повреждено и сколько
+/// уходит под расширения и резидентные данные.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MftUtilization {
+    pub records_total: u64,
+    pub in_use: u64,
+    /// Записи FILE без флага InUse (удаленные, готовые к переиспользованию)
+    pub free: u64,
+    /// Слоты без сигнатуры FILE/BAAD (никогда не использовались или затерты)
+    pub empty: u64,
+    pub baad: u64,
+    /// Записи с ошибкой fixup (повреждены, не разбираются)
+    pub fixup_failed: u64,
+    /// Записи-расширения (base_record_reference != 0), в том числе среди in_use/free
+    pub extension_records: u64,
+    pub avg_attributes_per_record: f64,
+    /// Суммарный объем резидентного $DATA (в том числе ADS) в занятых записях, байт
+    pub resident_data_bytes: u64,
+    #[serde(skip)]
+    pub attributes_total: u64,
+}
+
+impl MftUtilization {
+    pub fn in_use_percent(&self) -> f64 {
+        if self.records_total == 0 { 0.0 } else { self.in_use as f64 * 100.0 / self.records_total as f64 }
+    }
+}
+
+/// Важность правила - уровни как `level` в Sigma.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Informational,
+    Low,
+    #[default]
+    Medium,
+    High,
+    Critical,
+}
+
+/// Совпавшее правило: имя, важность и метки (например, attack.t1105).
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct RuleMatch {
+    pub name: String,
+    pub severity: Severity,
+    pub tags: Vec<String>,
+}
+
+/// Метки $STANDARD_INFORMATION и $FILE_NAME как u64 FILETIME: без округления
+/// и без потери значений вне диапазона DateTime (0, 1601 год и прочие следы timestomp).
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct RawTimestamps {
+    pub created0x10: Option<u64>,
+    pub created0x30: Option<u64>,
+    pub last_modified0x10: Option<u64>,
+    pub last_modified0x30: Option<u64>,
+    pub last_record_change0x10: Option<u64>,
+    pub last_record_change0x30: Option<u64>,
+    pub last_access0x10: Option<u64>,
+    pub last_access0x30: Option<u64>,
+}
+
+/// Остаток прежнего атрибута $STANDARD_INFORMATION или $FILE_NAME в slack записи.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct SlackRemnant {
+    /// `$STANDARD_INFORMATION` или `$FILE_NAME`
+    pub attribute: String,
+    /// Смещение заголовка атрибута от начала записи (для записей-расширений - своей)
+    pub offset: usize,
+    pub name: Option<String>,
+    pub parent_entry_number: Option<u64>,
+    pub parent_sequence_number: Option<u16>,
+    pub created: String,
+    pub modified: String,
+    pub mft_modified: String,
+    pub accessed: String,
+}
+
+/// Slack записи MFT: байты от RealSize до AllocatedSize базовой записи и ее расширений.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct RecordSlack {
+    pub size: usize,
+    pub non_zero_bytes: usize,
+    /// Строки ASCII и UTF-16LE (не короче 5 символов, не больше 32)
+    pub strings: Vec<String>,
+    pub remnants: Vec<SlackRemnant>,
+}
+
+/// Ключ файла EFS: пользователь (DDF) или агент восстановления (DRF).
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct EfsKey {
+    pub sid: Option<String>,
+    /// Отпечаток сертификата (SHA-1, hex)
+    pub thumbprint: Option<String>,
+    pub container: Option<String>,
+    pub provider: Option<String>,
+    pub user_name: Option<String>,
+}
+
+/// Поток $EFS записи. Ключи разбираются только у резидентного потока - содержимое
+/// нерезидентного в дампе MFT отсутствует.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct EfsInfo {
+    pub resident: bool,
+    pub version: Option<u32>,
+    pub users: Vec<EfsKey>,
+    pub recovery_agents: Vec<EfsKey>,
+}
+
+/// Именованный поток $DATA (ADS) записи.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct AdsStream {
+    pub name: String,
+    pub size: u64,
+    pub resident: bool,
+}
+
+/// Имя записи из одного атрибута $FILE_NAME: жесткая ссылка или DOS-имя 8.3.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct HardLink {
+    pub parent_entry_number: u64,
+    pub parent_sequence_number: u16,
+    pub name: String,
+    /// Пространство имен: 0 - POSIX, 1 - Win32, 2 - DOS, 3 - Win32&DOS
+    pub name_type: u8,
+    #[serde(rename = "Full_Path")]
+    pub full_path: String,
+}
+
+/// Участок non-resident потока $DATA: VCN -> LCN (Lcn = null для разреженного).
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct DataRunEntry {
+    /// Имя потока: пусто - основной $DATA, иначе ADS
+    pub stream: String,
+    pub vcn: u64,
+    pub lcn: Option<u64>,
+    pub clusters: u64,
+    /// Разреженный участок: кластеров на диске нет, читаются нули
+    pub sparse: bool,
+}
+
+/// Непрерывный участок $MFT на томе (после склейки смежных runs).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MftFragment {
+    pub vcn: u64,
+    /// None - разреженный участок
+    pub lcn: Option<u64>,
+    pub clusters: u64,
+}
+
+/// Раскладка $MFT по тому: фрагментация, разреженность, положение.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MftLayoutReport {
+    pub fragments: usize,
+    pub total_clusters: u64,
+    pub sparse_clusters: u64,
+    pub sparse_percent: f64,
+    pub volume_clusters: u64,
+    /// Начало $MFT от начала тома, %
+    pub start_percent: f64,
+    pub ranges: Vec<MftFragment>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+/// Итоги прогона parse (`<report>.summary.json`), база для `--since`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunSummary {
     pub source: String,
+    pub volume_serial_number: Option<u64>,
+    pub records_total: u64,
+    pub entries_written: u64,
+    pub max_lsn: u64,
+    pub max_usn: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub utilization: Option<MftUtilization>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub volume: Option<VolumeInfo>,
+    #[serde(default, skip_serializing_if = "CaseInfo::is_empty")]
+    pub case: CaseInfo,
+    #[serde(default, skip_serializing_if = "Tags::is_empty")]
+    pub tags: Tags,
+    /// Записи, пропущенные при разборе (BAAD, ошибка fixup)
+    #[serde(default)]
+    pub skipped_records: u64,
+    /// Приемники отчета (JSONL, --bodyfile, SIEM...), отключенные из-за ошибки записи
+    #[serde(default)]
+    pub failed_outputs: u64,
+}
+
+impl RunSummary {
+    /// Пропуски или потерянный вывод - частичный успех (код выхода 2).
+    pub fn outcome(&self) -> Outcome {
+        if self.skipped_records > 0 || self.failed_outputs > 0 { Outcome::Partial } else { Outcome::Complete }
+    }
 }
\ No newline at end of file
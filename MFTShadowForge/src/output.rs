@@ -1,5 +1,10 @@
+use clap::ValueEnum;
 use serde::Serialize;
-use std::io::{self, Write};
+use std::io::{self, BufRead, Read, Write};
+
+#[cfg(not(feature = "zstd"))]
+use crate::i18n::parse as msg;
+use crate::models::MftEntry;
 
 /// Потоковая запись в формате JSONL (JSON Lines).
 /// - Одна запись - один JSON-объект
@@ -7,21 +12,1096 @@ use std::io::{self, Write};
 /// - Нет массива, запятых и закрывающих скобок
 pub struct JsonlWriter<W: Write> {
     inner: W,
+    line: Vec<u8>,
+    position: u64,
 }
 
 impl<W: Write> JsonlWriter<W> {
     pub fn new(inner: W) -> Self {
-        Self { inner }
+        Self { inner, line: Vec::new(), position: 0 }
     }
 
     pub fn write<T: Serialize>(&mut self, value: &T) -> io::Result<()> {
-        serde_json::to_writer(&mut self.inner, value)
+        self.line.clear();
+        serde_json::to_writer(&mut self.line, value)
             .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
-        self.inner.write_all(b"\n")?;
+        self.line.push(b'\n');
+        self.inner.write_all(&self.line)?;
+        self.position += self.line.len() as u64;
         Ok(())
     }
 
+    /// Записано байт JSONL (до сжатия): смещение следующей строки.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
     pub fn flush(&mut self) -> io::Result<()> {
         self.inner.flush()
     }
-}
\ No newline at end of file
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+/// Записывает отчет команды целиком в новый файл JSONL; ошибка записи (в том числе
+/// при сбросе буфера) не теряется.
+pub fn write_jsonl_file<T: Serialize>(path: &str, rows: &[T]) -> io::Result<()> {
+    let mut writer = JsonlWriter::new(io::BufWriter::new(std::fs::File::create(path)?));
+    rows.iter().try_for_each(|row| writer.write(row))?;
+    writer.flush()
+}
+
+/// Записывает итоги или отчет в файл как JSON с отступами и переводом строки в конце.
+pub fn write_json_file<T: Serialize>(path: &str, value: &T) -> io::Result<()> {
+    let mut writer = io::BufWriter::new(std::fs::File::create(path)?);
+    serde_json::to_writer_pretty(&mut writer, value)?;
+    writer.write_all(b"\n")?;
+    writer.flush()
+}
+
+/// Сжатие потока JSONL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// Расширение, которое дописывается к имени отчета
+    pub fn extension(self) -> &'static str {
+        match self {
+            Compression::Gzip => ".gz",
+            Compression::Zstd => ".zst",
+        }
+    }
+}
+
+/// Поток отчета, при необходимости сжимаемый на лету (без второго прохода по файлу).
+/// `finish` дописывает завершающий блок архива - без него файл обрезан.
+pub enum CompressedWriter {
+    Plain(Box<dyn Write>),
+    Gzip(flate2::write::GzEncoder<Box<dyn Write>>),
+    #[cfg(feature = "zstd")]
+    Zstd(zstd::Encoder<'static, Box<dyn Write>>),
+}
+
+impl CompressedWriter {
+    pub fn new(inner: Box<dyn Write>, compression: Option<Compression>) -> io::Result<Self> {
+        Ok(match compression {
+            None => CompressedWriter::Plain(inner),
+            Some(Compression::Gzip) => CompressedWriter::Gzip(flate2::write::GzEncoder::new(inner, flate2::Compression::default())),
+            #[cfg(feature = "zstd")]
+            Some(Compression::Zstd) => CompressedWriter::Zstd(zstd::Encoder::new(inner, zstd::DEFAULT_COMPRESSION_LEVEL)?),
+            #[cfg(not(feature = "zstd"))]
+            Some(Compression::Zstd) => return Err(io::Error::new(io::ErrorKind::Unsupported, msg::zstd_unsupported())),
+        })
+    }
+
+    pub fn finish(self) -> io::Result<()> {
+        let mut inner = match self {
+            CompressedWriter::Plain(inner) => inner,
+            CompressedWriter::Gzip(encoder) => encoder.finish()?,
+            #[cfg(feature = "zstd")]
+            CompressedWriter::Zstd(encoder) => encoder.finish()?,
+        };
+        inner.flush()
+    }
+}
+
+impl Write for CompressedWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            CompressedWriter::Plain(w) => w.write(buf),
+            CompressedWriter::Gzip(w) => w.write(buf),
+            #[cfg(feature = "zstd")]
+            CompressedWriter::Zstd(w) => w.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            CompressedWriter::Plain(w) => w.flush(),
+            CompressedWriter::Gzip(w) => w.flush(),
+            #[cfg(feature = "zstd")]
+            CompressedWriter::Zstd(w) => w.flush(),
+        }
+    }
+}
+
+/// Чтение отчета, сжатого parse --compress или нет: формат определяется по сигнатуре
+/// (gzip 1F 8B, zstd 28 B5 2F FD), а не по расширению. `-` - stdin.
+pub fn open_report(path: &str) -> io::Result<Box<dyn BufRead>> {
+    let inner: Box<dyn Read> = if path == "-" { Box::new(io::stdin().lock()) } else { Box::new(std::fs::File::open(path)?) };
+    let mut reader = io::BufReader::new(inner);
+    let magic = reader.fill_buf()?;
+    if magic.starts_with(&[0x1F, 0x8B]) {
+        return Ok(Box::new(io::BufReader::new(flate2::bufread::MultiGzDecoder::new(reader))));
+    }
+    if magic.starts_with(&[0x28, 0xB5, 0x2F, 0xFD]) {
+        #[cfg(feature = "zstd")]
+        return Ok(Box::new(io::BufReader::new(zstd::Decoder::with_buffer(reader)?)));
+        #[cfg(not(feature = "zstd"))]
+        return Err(io::Error::new(io::ErrorKind::Unsupported, msg::zstd_unsupported()));
+    }
+    Ok(Box::new(reader))
+}
+
+/// Дополнительный приемник строк отчета parse (помимо основного JSONL).
+pub trait EntrySink {
+    fn write(&mut self, entry: &MftEntry) -> io::Result<()>;
+    /// Дописывает буферы и завершает вывод; после вызова приемник не используется
+    fn finish(&mut self) -> io::Result<()>;
+}
+
+/// Короткий JSONL только со строками, где есть детекты (`MftEntry::has_detections`):
+/// совпадения правил, timestomp, torn write, ADS и прочие аномалии.
+pub struct AlertsWriter<W: Write> {
+    inner: JsonlWriter<W>,
+}
+
+impl<W: Write> AlertsWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self { inner: JsonlWriter::new(inner) }
+    }
+}
+
+impl<W: Write> EntrySink for AlertsWriter<W> {
+    fn write(&mut self, entry: &MftEntry) -> io::Result<()> {
+        if !entry.has_detections() {
+            return Ok(());
+        }
+        self.inner.write(entry)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// Карта размещения потоков в CSV для карверов и точечного снятия образа:
+/// строка на участок runlist (`DataRuns`) с номером записи и путем; у разреженных `Lcn` пуст.
+pub struct RunsWriter<W: Write> {
+    inner: W,
+}
+
+impl<W: Write> RunsWriter<W> {
+    pub fn new(mut inner: W) -> io::Result<Self> {
+        writeln!(inner, "EntryNumber,SequenceNumber,InUse,Stream,Vcn,Lcn,Clusters,Sparse,FullPath")?;
+        Ok(Self { inner })
+    }
+}
+
+impl<W: Write> EntrySink for RunsWriter<W> {
+    fn write(&mut self, entry: &MftEntry) -> io::Result<()> {
+        for run in entry.data_runs.iter().flatten() {
+            writeln!(self.inner, "{},{},{},{},{},{},{},{},{}",
+                entry.entry_number, entry.sequence_number, entry.in_use, csv_field(&run.stream), run.vcn,
+                run.lcn.map(|l| l.to_string()).unwrap_or_default(), run.clusters, run.sparse, csv_field(&entry.full_path))?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Секунды Unix для метки времени отчета; отсутствующая - 0, как принято в bodyfile.
+fn epoch_seconds(ts: Option<&String>) -> i64 {
+    ts.and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok()).map(|t| t.timestamp()).unwrap_or(0)
+}
+
+/// Bodyfile Sleuth Kit 3.x для `mactime -b` и log2timeline:
+/// `MD5|name|inode|mode_as_string|UID|GID|size|atime|mtime|ctime|crtime`.
+/// На запись - строка по $STANDARD_INFORMATION и строка по $FILE_NAME с суффиксом
+/// ` ($FILE_NAME)`, как у `fls -m`; у удаленных записей добавляется ` (deleted)`.
+pub struct BodyfileWriter<W: Write> {
+    inner: W,
+}
+
+impl<W: Write> BodyfileWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self { inner }
+    }
+}
+
+impl<W: Write> EntrySink for BodyfileWriter<W> {
+    fn write(&mut self, entry: &MftEntry) -> io::Result<()> {
+        let mode = if entry.is_directory { "d/drwxrwxrwx" } else { "r/rrwxrwxrwx" };
+        let deleted = if entry.in_use { "" } else { " (deleted)" };
+        // Порядок bodyfile: atime, mtime, ctime (изменение записи MFT), crtime
+        let sources = [
+            ("", [&entry.last_access0x10, &entry.last_modified0x10, &entry.last_record_change0x10, &entry.created0x10]),
+            (" ($FILE_NAME)", [&entry.last_access0x30, &entry.last_modified0x30, &entry.last_record_change0x30, &entry.created0x30]),
+        ];
+        for (suffix, times) in sources {
+            if times.iter().all(|t| t.is_none()) { continue; }
+            let [atime, mtime, ctime, crtime] = times.map(|t| epoch_seconds(t.as_ref()));
+            writeln!(self.inner, "0|{}{}{}|{}-{}|{}|0|0|{}|{}|{}|{}|{}",
+                entry.full_path, suffix, deleted, entry.entry_number, entry.sequence_number, mode,
+                entry.file_size, atime, mtime, ctime, crtime)?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Временная шкала TLN (методика Х. Карви): `time|source|host|user|desc`, время - секунды Unix.
+/// Одинаковые метки атрибута сводятся в одну строку с маской MACB (`M.C.`),
+/// атрибут указывается в описании: `MACB [$SI] \path`.
+pub struct TlnWriter<W: Write> {
+    inner: W,
+    host: String,
+    user: String,
+}
+
+impl<W: Write> TlnWriter<W> {
+    pub fn new(inner: W, host: &str, user: &str) -> Self {
+        Self { inner, host: host.to_string(), user: user.to_string() }
+    }
+}
+
+impl<W: Write> EntrySink for TlnWriter<W> {
+    fn write(&mut self, entry: &MftEntry) -> io::Result<()> {
+        let deleted = if entry.in_use { "" } else { " (deleted)" };
+        let sources = [
+            ("$SI", [&entry.last_modified0x10, &entry.last_access0x10, &entry.last_record_change0x10, &entry.created0x10]),
+            ("$FN", [&entry.last_modified0x30, &entry.last_access0x30, &entry.last_record_change0x30, &entry.created0x30]),
+        ];
+        for (attribute, times) in sources {
+            let times = times.map(|t| t.as_ref().map(|_| epoch_seconds(t.as_ref())));
+            let mut distinct: Vec<i64> = times.iter().flatten().copied().collect();
+            distinct.sort_unstable();
+            distinct.dedup();
+            for time in distinct {
+                let macb: String = times.iter().zip("MACB".chars())
+                    .map(|(t, letter)| if *t == Some(time) { letter } else { '.' })
+                    .collect();
+                writeln!(self.inner, "{}|FILE|{}|{}|{} [{}] {}{}", time, self.host, self.user, macb, attribute, entry.full_path, deleted)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Формат основного отчета parse.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ReportFormat {
+    /// Строка на запись MFT (MftEntry)
+    #[default]
+    Jsonl,
+    /// Событие на метку времени с полями message/datetime/timestamp_desc для импорта в Timesketch
+    Timesketch,
+}
+
+/// Описание меток M, A, C, B в терминах Timesketch/plaso.
+const TIMESKETCH_DESC: [&str; 4] = ["Content Modification Time", "Last Access Time", "Metadata Modification Time", "Creation Time"];
+
+/// События Timesketch для строки отчета: одинаковые метки атрибута сводятся в одно событие
+/// (как в TLN), timestamp_desc перечисляет их. Поля записи копируются в каждое событие,
+/// чтобы по ним можно было фильтровать. Запись без меток времени событий не дает.
+pub fn timesketch_events(entry: &MftEntry) -> io::Result<Vec<serde_json::Value>> {
+    match serde_json::to_value(entry).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))? {
+        serde_json::Value::Object(fields) => Ok(timesketch_row_events(&fields)),
+        _ => unreachable!("MftEntry сериализуется в объект"),
+    }
+}
+
+/// То же по уже сериализованной строке отчета (query читает JSONL, а не MftEntry).
+pub fn timesketch_row_events(fields: &serde_json::Map<String, serde_json::Value>) -> Vec<serde_json::Value> {
+    use serde_json::Value;
+
+    let time = |name: &str| fields.get(name).and_then(Value::as_str);
+    let deleted = if fields.get("InUse").and_then(Value::as_bool).unwrap_or(true) { "" } else { " (deleted)" };
+    let full_path = fields.get("Full_Path").and_then(Value::as_str).unwrap_or_default();
+    let sources = [
+        ("$SI", [time("LastModified0x10"), time("LastAccess0x10"), time("LastRecordChange0x10"), time("Created0x10")]),
+        ("$FN", [time("LastModified0x30"), time("LastAccess0x30"), time("LastRecordChange0x30"), time("Created0x30")]),
+    ];
+    let mut events = Vec::new();
+    for (attribute, times) in sources {
+        let mut distinct: Vec<&str> = times.iter().flatten().copied().collect();
+        distinct.sort_unstable();
+        distinct.dedup();
+        for time in distinct {
+            let Ok(parsed) = chrono::DateTime::parse_from_rfc3339(time) else { continue };
+            let macb: String = times.iter().zip("MACB".chars())
+                .map(|(t, letter)| if *t == Some(time) { letter } else { '.' })
+                .collect();
+            let desc: Vec<&str> = times.iter().zip(TIMESKETCH_DESC)
+                .filter(|(t, _)| **t == Some(time))
+                .map(|(_, desc)| desc)
+                .collect();
+
+            let mut event = fields.clone();
+            event.insert("message".into(), Value::from(format!("{} [{}] {}{}", macb, attribute, full_path, deleted)));
+            event.insert("datetime".into(), Value::from(time));
+            event.insert("timestamp".into(), Value::from(parsed.timestamp_micros()));
+            event.insert("timestamp_desc".into(), Value::from(format!("{} ({})", desc.join(", "), attribute)));
+            events.push(Value::Object(event));
+        }
+    }
+    events
+}
+
+#[cfg(feature = "parquet")]
+pub use columnar::ParquetWriter;
+
+#[cfg(feature = "parquet")]
+mod columnar {
+    use std::fs::File;
+    use std::io;
+    use std::sync::Arc;
+
+    use parquet::basic::{Compression, LogicalType, Repetition, TimeUnit, Type as PhysicalType};
+    use parquet::data_type::{BoolType, ByteArray, ByteArrayType, Int64Type};
+    use parquet::errors::ParquetError;
+    use parquet::file::properties::WriterProperties;
+    use parquet::file::writer::SerializedFileWriter;
+    use parquet::schema::types::Type;
+    use serde_json::Value;
+
+    use super::EntrySink;
+    use crate::i18n::parse as msg;
+    use crate::models::MftEntry;
+
+    /// Строк в одной группе: больше - лучше сжатие, меньше - меньше памяти на буферы колонок.
+    const ROW_GROUP_SIZE: usize = 65536;
+    /// Поля плагинов (набор заранее неизвестен) - одной колонкой с JSON-объектом.
+    const PLUGIN_FIELDS_COLUMN: &str = "PluginFields";
+
+    #[derive(Clone, Copy)]
+    enum Kind {
+        Bool,
+        Int,
+        Str,
+        /// RFC 3339 -> TIMESTAMP(NANOS, UTC)
+        Time,
+        /// Вложенное значение целиком как строка JSON
+        Json,
+    }
+
+    /// Колонки MftEntry в порядке JSONL (имена - как ключи JSON).
+    const MFT_COLUMNS: &[(&str, Kind)] = &[
+        ("SchemaVersion", Kind::Int),
+        ("EntryNumber", Kind::Int), ("SequenceNumber", Kind::Int),
+        ("ParentEntryNumber", Kind::Int), ("ParentSequenceNumber", Kind::Int),
+        ("InUse", Kind::Bool), ("IsDirectory", Kind::Bool),
+        ("ParentPath", Kind::Str), ("FileName", Kind::Str), ("Extension", Kind::Str), ("Full_Path", Kind::Str), ("PathConfidence", Kind::Str),
+        ("HasAds", Kind::Bool), ("IsAds", Kind::Bool), ("AdsStreams", Kind::Json),
+        ("FileSize", Kind::Int), ("AllocatedSizeData", Kind::Int), ("InitializedSize", Kind::Int),
+        ("Created0x10", Kind::Time), ("Created0x30", Kind::Time),
+        ("LastModified0x10", Kind::Time), ("LastModified0x30", Kind::Time),
+        ("LastRecordChange0x10", Kind::Time), ("LastRecordChange0x30", Kind::Time),
+        ("LastAccess0x10", Kind::Time), ("LastAccess0x30", Kind::Time), ("RawTimestamps", Kind::Json),
+        ("UpdateSequenceNumber", Kind::Int), ("LogfileSequenceNumber", Kind::Int), ("JournalUsn", Kind::Int),
+        ("SecurityId", Kind::Int), ("OwnerSid", Kind::Str), ("GroupSid", Kind::Str), ("Dacl", Kind::Str), ("ResidentSecurityDescriptor", Kind::Bool), ("SiFlags", Kind::Int),
+        ("IsSparse", Kind::Bool), ("IsCompressed", Kind::Bool), ("IsEncrypted", Kind::Bool), ("IsOffline", Kind::Bool),
+        ("IsReparse", Kind::Bool), ("IsTemporary", Kind::Bool), ("ReparseTag", Kind::Str), ("ReparseTarget", Kind::Str),
+        ("WofCompressed", Kind::Bool), ("WofAlgorithm", Kind::Str), ("WofCompressedSize", Kind::Int), ("HasTxfData", Kind::Bool), ("Efs", Kind::Json),
+        ("ReferenceCount", Kind::Int), ("HardLinks", Kind::Json), ("NameType", Kind::Int),
+        ("Timestomped", Kind::Bool), ("FitsRules", Kind::Bool), ("RuleMatches", Kind::Json), ("KnownGood", Kind::Bool),
+        ("ZoneIdContents", Kind::Str), ("ContentData", Kind::Str),
+        ("Md5", Kind::Str), ("Sha1", Kind::Str), ("Sha256", Kind::Str), ("DataRuns", Kind::Json),
+        ("RecycleOriginalPath", Kind::Str), ("RecycleDeletedAt", Kind::Time),
+        ("RecycleOriginalSize", Kind::Int), ("RecycleDataPath", Kind::Str),
+        ("uSecZeros", Kind::Bool), ("Copied", Kind::Bool), ("TornWrite", Kind::Bool),
+        ("PathTooDeep", Kind::Bool), ("PathTooLong", Kind::Bool), ("RepeatedNesting", Kind::Bool),
+        ("DotLikeName", Kind::Bool), ("NormalizationChanged", Kind::Bool), ("CaseCollision", Kind::Bool),
+        ("SuspiciousReparse", Kind::Bool), ("PosixNameAnomaly", Kind::Bool),
+        ("CompressedExecInTemp", Kind::Bool), ("LinkCountMismatch", Kind::Bool), ("ShortName", Kind::Str), ("ShortNameMismatch", Kind::Bool),
+        ("ComplexExtents", Kind::Bool),
+        ("FnAttributeId", Kind::Int), ("OtherAttributeId", Kind::Int),
+        ("SourceFile", Kind::Str), ("Signature", Kind::Str),
+        ("BaseRecordReference", Kind::Int), ("RealSize", Kind::Int), ("AllocatedSize", Kind::Int),
+        ("RawRecord", Kind::Str), ("RecordSlack", Kind::Json),
+    ];
+
+    /// Значения одной колонки текущей группы строк; null отмечается уровнем определения 0.
+    enum Values {
+        Bool(Vec<bool>),
+        Int(Vec<i64>),
+        Bytes(Vec<ByteArray>),
+    }
+
+    struct Column {
+        name: String,
+        kind: Kind,
+        values: Values,
+        def_levels: Vec<i16>,
+    }
+
+    impl Column {
+        fn new(name: &str, kind: Kind) -> Self {
+            let values = match kind {
+                Kind::Bool => Values::Bool(Vec::new()),
+                Kind::Int | Kind::Time => Values::Int(Vec::new()),
+                Kind::Str | Kind::Json => Values::Bytes(Vec::new()),
+            };
+            Self { name: name.to_string(), kind, values, def_levels: Vec::new() }
+        }
+
+        fn schema(&self) -> Result<Type, ParquetError> {
+            let (physical, logical) = match self.kind {
+                Kind::Bool => (PhysicalType::BOOLEAN, None),
+                Kind::Int => (PhysicalType::INT64, None),
+                Kind::Time => (PhysicalType::INT64, Some(LogicalType::Timestamp { is_adjusted_to_u_t_c: true, unit: TimeUnit::NANOS(Default::default()) })),
+                Kind::Str | Kind::Json => (PhysicalType::BYTE_ARRAY, Some(LogicalType::String)),
+            };
+            Type::primitive_type_builder(&self.name, physical)
+                .with_repetition(Repetition::OPTIONAL)
+                .with_logical_type(logical)
+                .build()
+        }
+
+        fn push(&mut self, value: Option<Value>) {
+            let present = match (&mut self.values, self.kind, value) {
+                (Values::Bool(v), _, Some(Value::Bool(b))) => { v.push(b); true }
+                (Values::Int(v), Kind::Int, Some(Value::Number(n))) => {
+                    // u64 пишется как есть: значения выше i64::MAX на практике не встречаются
+                    n.as_i64().or_else(|| n.as_u64().map(|u| u as i64)).map(|i| v.push(i)).is_some()
+                }
+                // Вне диапазона наносекунд i64 (1677-2262) - null, точное значение остается в JSONL
+                (Values::Int(v), Kind::Time, Some(Value::String(s))) => chrono::DateTime::parse_from_rfc3339(&s).ok()
+                    .and_then(|t| t.timestamp_nanos_opt())
+                    .map(|ns| v.push(ns)).is_some(),
+                (Values::Bytes(v), Kind::Str, Some(Value::String(s))) => { v.push(ByteArray::from(s.into_bytes())); true }
+                (Values::Bytes(v), Kind::Json, Some(value)) if !value.is_null() => {
+                    v.push(ByteArray::from(value.to_string().into_bytes()));
+                    true
+                }
+                _ => false,
+            };
+            self.def_levels.push(present as i16);
+        }
+
+        fn clear(&mut self) {
+            match &mut self.values {
+                Values::Bool(v) => v.clear(),
+                Values::Int(v) => v.clear(),
+                Values::Bytes(v) => v.clear(),
+            }
+            self.def_levels.clear();
+        }
+    }
+
+    fn to_io(e: ParquetError) -> io::Error {
+        io::Error::other(e.to_string())
+    }
+
+    /// Запись строк отчета (MftEntry) в Apache Parquet с типизированной схемой:
+    /// флаги - BOOLEAN, числа - INT64, метки времени - TIMESTAMP(NANOS), строки - UTF8.
+    /// Метки источника (--tag) - отдельные строковые колонки, поля плагинов - JSON в `PluginFields`.
+    pub struct ParquetWriter {
+        writer: SerializedFileWriter<File>,
+        columns: Vec<Column>,
+        rows: usize,
+    }
+
+    impl ParquetWriter {
+        /// `tag_columns` - ключи меток, одинаковых для всех строк прогона.
+        pub fn create(path: &str, tag_columns: &[String]) -> io::Result<Self> {
+            let mut columns: Vec<Column> = MFT_COLUMNS.iter().map(|(name, kind)| Column::new(name, *kind)).collect();
+            for tag in tag_columns {
+                if !columns.iter().any(|c| c.name == *tag) && tag != PLUGIN_FIELDS_COLUMN {
+                    columns.push(Column::new(tag, Kind::Str));
+                }
+            }
+            columns.push(Column::new(PLUGIN_FIELDS_COLUMN, Kind::Json));
+
+            let fields = columns.iter().map(|c| c.schema().map(Arc::new)).collect::<Result<Vec<_>, _>>().map_err(to_io)?;
+            let schema = Type::group_type_builder("MftEntry").with_fields(fields).build().map_err(to_io)?;
+            let props = WriterProperties::builder().set_compression(Compression::SNAPPY).build();
+            let writer = SerializedFileWriter::new(File::create(path)?, Arc::new(schema), Arc::new(props)).map_err(to_io)?;
+            Ok(Self { writer, columns, rows: 0 })
+        }
+
+        fn flush_row_group(&mut self) -> io::Result<()> {
+            if self.rows == 0 { return Ok(()); }
+            let mut group = self.writer.next_row_group().map_err(to_io)?;
+            for column in &mut self.columns {
+                let mut writer = group.next_column().map_err(to_io)?
+                    .ok_or_else(|| io::Error::other(msg::parquet_schema_short()))?;
+                let levels = Some(column.def_levels.as_slice());
+                match &column.values {
+                    Values::Bool(v) => writer.typed::<BoolType>().write_batch(v, levels, None),
+                    Values::Int(v) => writer.typed::<Int64Type>().write_batch(v, levels, None),
+                    Values::Bytes(v) => writer.typed::<ByteArrayType>().write_batch(v, levels, None),
+                }.map_err(to_io)?;
+                writer.close().map_err(to_io)?;
+                column.clear();
+            }
+            group.close().map_err(to_io)?;
+            self.rows = 0;
+            Ok(())
+        }
+    }
+
+    impl EntrySink for ParquetWriter {
+        fn write(&mut self, entry: &MftEntry) -> io::Result<()> {
+            let mut map = match serde_json::to_value(entry).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))? {
+                Value::Object(map) => map,
+                _ => return Err(io::Error::new(io::ErrorKind::InvalidData, msg::row_not_object())),
+            };
+            // Последняя колонка - PluginFields: в нее уходят ключи, не попавшие в остальные
+            if let Some((plugin, fixed)) = self.columns.split_last_mut() {
+                for column in fixed {
+                    column.push(map.remove(&column.name));
+                }
+                plugin.push((!map.is_empty()).then_some(Value::Object(map)));
+            }
+
+            self.rows += 1;
+            if self.rows >= ROW_GROUP_SIZE { self.flush_row_group()?; }
+            Ok(())
+        }
+
+        /// Дописывает последнюю группу строк и футер; без вызова файл не читается.
+        fn finish(&mut self) -> io::Result<()> {
+            self.flush_row_group()?;
+            self.writer.finish().map_err(to_io)?;
+            Ok(())
+        }
+    }
+}
+
+
+#[cfg(feature = "siem")]
+pub use http::{redact_url, ElasticSink, SplunkSink};
+
+#[cfg(feature = "siem")]
+mod http {
+    use std::io;
+    use std::time::Duration;
+
+    use serde_json::Value;
+    use tracing::warn;
+
+    use super::EntrySink;
+    use crate::i18n::parse as msg;
+    use crate::models::MftEntry;
+
+    /// Попыток отправки пакета; пауза между ними удваивается с 1 секунды.
+    const HTTP_RETRIES: u32 = 5;
+    const ES_BATCH_DOCS: usize = 5000;
+    const ES_BATCH_BYTES: usize = 8 * 1024 * 1024;
+    /// Пакет HEC держится под лимитом max_content_length старых версий Splunk (1 МБ)
+    const HEC_BATCH_EVENTS: usize = 1000;
+    const HEC_BATCH_BYTES: usize = 900 * 1024;
+    const HEC_SOURCETYPE: &str = "mft:entry";
+
+    /// URL без логина и пароля - для сообщений и журнала.
+    pub fn redact_url(url: &str) -> String {
+        if let Some((scheme, rest)) = url.split_once("://") {
+            let host_end = rest.find('/').unwrap_or(rest.len());
+            if let Some(at) = rest[..host_end].rfind('@') {
+                return format!("{}://***@{}", scheme, &rest[at + 1..]);
+            }
+        }
+        url.to_string()
+    }
+
+    pub(super) fn agent() -> ureq::Agent {
+        ureq::AgentBuilder::new()
+            .timeout_connect(Duration::from_secs(10))
+            .timeout(Duration::from_secs(120))
+            .build()
+    }
+
+    /// POST с повторами: сетевые ошибки, 429 и 5xx повторяются с экспоненциальной паузой,
+    /// прочие коды ответа сразу возвращаются как ошибка.
+    pub(super) fn post_with_retry(agent: &ureq::Agent, url: &str, headers: &[(&str, &str)], body: &[u8]) -> io::Result<String> {
+        let mut delay = Duration::from_secs(1);
+        let mut last_error = String::new();
+        for attempt in 1..=HTTP_RETRIES {
+            let mut request = agent.post(url);
+            for (name, value) in headers {
+                request = request.set(name, value);
+            }
+            match request.send_bytes(body) {
+                Ok(response) => return response.into_string(),
+                Err(ureq::Error::Status(code, _)) if code == 429 || code >= 500 => last_error = format!("HTTP {}", code),
+                Err(ureq::Error::Status(code, response)) => {
+                    let text: String = response.into_string().unwrap_or_default().chars().take(300).collect();
+                    return Err(io::Error::other(format!("HTTP {}: {}", code, text)));
+                }
+                Err(ureq::Error::Transport(e)) => last_error = e.to_string(),
+            }
+            if attempt < HTTP_RETRIES {
+                warn!("{}", msg::http_retry(redact_url(url), &last_error, delay.as_secs()));
+                std::thread::sleep(delay);
+                delay *= 2;
+            }
+        }
+        Err(io::Error::other(msg::http_no_response(HTTP_RETRIES, last_error)))
+    }
+
+    /// Индексация строк отчета в Elasticsearch через _bulk пакетами.
+    /// Документы, отклоненные с 429 (очередь узла переполнена), отправляются повторно;
+    /// остальные отказы считаются и возвращаются ошибкой из `finish`.
+    pub struct ElasticSink {
+        agent: ureq::Agent,
+        bulk_url: String,
+        /// Строка действия `index` - одинакова для всех документов
+        action: String,
+        /// Пары "действие + документ" текущего пакета
+        batch: Vec<String>,
+        batch_bytes: usize,
+        indexed: u64,
+        failed: u64,
+        first_failure: Option<String>,
+    }
+
+    impl ElasticSink {
+        pub fn new(url: &str, index: &str) -> Self {
+            let action = serde_json::json!({ "index": { "_index": index } }).to_string();
+            Self {
+                agent: agent(),
+                bulk_url: format!("{}/_bulk", url.trim_end_matches('/')),
+                action,
+                batch: Vec::new(),
+                batch_bytes: 0,
+                indexed: 0,
+                failed: 0,
+                first_failure: None,
+            }
+        }
+
+        fn send_batch(&mut self) -> io::Result<()> {
+            let mut pending = std::mem::take(&mut self.batch);
+            self.batch_bytes = 0;
+            let mut delay = Duration::from_secs(1);
+            for attempt in 1..=HTTP_RETRIES {
+                if pending.is_empty() { return Ok(()); }
+                let body = pending.concat();
+                let response = post_with_retry(&self.agent, &self.bulk_url, &[("Content-Type", "application/x-ndjson")], body.as_bytes())?;
+                let response: Value = serde_json::from_str(&response).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                if response["errors"].as_bool() != Some(true) {
+                    self.indexed += pending.len() as u64;
+                    return Ok(());
+                }
+
+                let items = response["items"].as_array().cloned().unwrap_or_default();
+                let mut retry = Vec::new();
+                for (doc, item) in pending.into_iter().zip(items) {
+                    let result = &item["index"];
+                    match result["status"].as_u64().unwrap_or(0) {
+                        200..=299 => self.indexed += 1,
+                        429 if attempt < HTTP_RETRIES => retry.push(doc),
+                        _ => {
+                            self.failed += 1;
+                            if self.first_failure.is_none() {
+                                self.first_failure = Some(result["error"].to_string());
+                            }
+                        }
+                    }
+                }
+                pending = retry;
+                if !pending.is_empty() && attempt < HTTP_RETRIES {
+                    std::thread::sleep(delay);
+                    delay *= 2;
+                }
+            }
+            Ok(())
+        }
+    }
+
+    impl EntrySink for ElasticSink {
+        fn write(&mut self, entry: &MftEntry) -> io::Result<()> {
+            let doc = serde_json::to_string(entry).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            let pair = format!("{}\n{}\n", self.action, doc);
+            self.batch_bytes += pair.len();
+            self.batch.push(pair);
+            if self.batch.len() >= ES_BATCH_DOCS || self.batch_bytes >= ES_BATCH_BYTES {
+                self.send_batch()?;
+            }
+            Ok(())
+        }
+
+        fn finish(&mut self) -> io::Result<()> {
+            self.send_batch()?;
+            match &self.first_failure {
+                Some(reason) => Err(io::Error::other(msg::es_not_indexed(self.failed, self.indexed, reason))),
+                None => Ok(()),
+            }
+        }
+    }
+
+    /// Отправка строк отчета в Splunk HTTP Event Collector пакетами событий.
+    /// Время события - $SI LastModified записи (без него Splunk ставит время индексации),
+    /// source - исходный файл MFT, sourcetype - `mft:entry`.
+    pub struct SplunkSink {
+        agent: ureq::Agent,
+        event_url: String,
+        authorization: String,
+        batch: String,
+        batch_events: usize,
+        sent: u64,
+    }
+
+    impl SplunkSink {
+        /// `url` - адрес HEC (`https://splunk:8088`); путь `/services/collector/event`
+        /// добавляется, если не указан.
+        pub fn new(url: &str, token: &str) -> Self {
+            let url = url.trim_end_matches('/');
+            let event_url = if url.contains("/services/collector") { url.to_string() } else { format!("{}/services/collector/event", url) };
+            Self {
+                agent: agent(),
+                event_url,
+                authorization: format!("Splunk {}", token),
+                batch: String::new(),
+                batch_events: 0,
+                sent: 0,
+            }
+        }
+
+        /// Адрес приема событий (для сообщений и журнала)
+        pub fn event_url(&self) -> String { redact_url(&self.event_url) }
+
+        fn send_batch(&mut self) -> io::Result<()> {
+            if self.batch_events == 0 { return Ok(()); }
+            let headers = [("Authorization", self.authorization.as_str()), ("Content-Type", "application/json")];
+            let response = post_with_retry(&self.agent, &self.event_url, &headers, self.batch.as_bytes())?;
+            // HEC подтверждает пакет целиком: {"text":"Success","code":0}
+            let response: Value = serde_json::from_str(&response).unwrap_or(Value::Null);
+            if response["code"].as_i64().is_some_and(|code| code != 0) {
+                return Err(io::Error::other(format!("HEC: {}", response["text"])));
+            }
+            self.sent += self.batch_events as u64;
+            self.batch.clear();
+            self.batch_events = 0;
+            Ok(())
+        }
+    }
+
+    impl EntrySink for SplunkSink {
+        fn write(&mut self, entry: &MftEntry) -> io::Result<()> {
+            let event = serde_json::json!({
+                "sourcetype": HEC_SOURCETYPE,
+                "source": entry.source_file,
+                "event": entry,
+            });
+            let event = serde_json::to_string(&event).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            // time - число с микросекундами; через f64 дробная часть искажается
+            let time = entry.last_modified0x10.as_deref().and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok());
+            match time {
+                Some(time) => {
+                    self.batch.push_str(&format!("{{\"time\":{}.{:06},", time.timestamp(), time.timestamp_subsec_micros()));
+                    self.batch.push_str(&event[1..]);
+                }
+                None => self.batch.push_str(&event),
+            }
+            self.batch.push('\n');
+            self.batch_events += 1;
+            if self.batch_events >= HEC_BATCH_EVENTS || self.batch.len() >= HEC_BATCH_BYTES {
+                self.send_batch()?;
+            }
+            Ok(())
+        }
+
+        fn finish(&mut self) -> io::Result<()> {
+            self.send_batch()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::bench::synthetic_mft;
+    use crate::commands::parse::{parse_records, ParseOptions};
+    use crate::image::Fixture;
+
+    /// Строки отчета синтетического MFT: корень, каталог, файл с запятой в имени и удаленный файл.
+    /// Метки записи N - 2024-01-01T00:00:00Z плюс N секунд, у $SI и $FN одинаковые.
+    fn entries() -> Vec<MftEntry> {
+        let mft = synthetic_mft(&[(5, "docs", true, true), (16, "a,b.txt", false, true), (16, "gone.txt", false, false)]);
+        parse_records(io::Cursor::new(mft), &ParseOptions::default()).unwrap().collect()
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn parquet_round_trip() {
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+        use parquet::record::Field;
+
+        let entries = entries();
+        let fixture = Fixture::new();
+        let path = fixture.write("report.parquet", b"");
+        let mut writer = ParquetWriter::create(&path, &["Host".to_string()]).unwrap();
+        for entry in &entries {
+            writer.write(entry).unwrap();
+        }
+        writer.finish().unwrap();
+
+        let reader = SerializedFileReader::new(std::fs::File::open(&path).unwrap()).unwrap();
+        let metadata = reader.metadata().file_metadata();
+        assert_eq!(metadata.num_rows(), entries.len() as i64);
+        let columns: Vec<String> = metadata.schema_descr().columns().iter().map(|c| c.name().to_string()).collect();
+        assert_eq!(columns.first().map(String::as_str), Some("SchemaVersion"));
+        assert_eq!(&columns[columns.len() - 2..], ["Host", "PluginFields"]);
+
+        let rows: Vec<_> = reader.get_row_iter(None).unwrap().map(Result::unwrap).collect();
+        assert_eq!(rows.len(), 4);
+        for (entry, row) in entries.iter().zip(&rows) {
+            let field = |name: &str| row.get_column_iter().find(|(n, _)| *n == name).map(|(_, f)| f.clone()).unwrap();
+            assert_eq!(field("EntryNumber"), Field::Long(entry.entry_number as i64));
+            assert_eq!(field("FileName"), Field::Str(entry.file_name.clone()));
+            assert_eq!(field("Full_Path"), Field::Str(entry.full_path.clone()));
+            assert_eq!(field("InUse"), Field::Bool(entry.in_use));
+            assert_eq!(field("Host"), Field::Null);
+            assert_eq!(field("PluginFields"), Field::Null);
+            let created = chrono::DateTime::parse_from_rfc3339(entry.created0x10.as_deref().unwrap()).unwrap();
+            assert_eq!(field("Created0x10"), Field::Long(created.timestamp_nanos_opt().unwrap()));
+            let runs = serde_json::to_string(&entry.data_runs).unwrap();
+            assert_eq!(field("DataRuns"), if runs == "null" { Field::Null } else { Field::Str(runs) });
+        }
+    }
+
+    /// Пропускает `entries()` через приемник.
+    fn write_entries(sink: &mut dyn EntrySink) {
+        for entry in entries() {
+            sink.write(&entry).unwrap();
+        }
+        sink.finish().unwrap();
+    }
+
+    #[test]
+    fn bodyfile_has_si_and_fn_lines() {
+        let mut out = Vec::new();
+        write_entries(&mut BodyfileWriter::new(&mut out));
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 8);
+        assert_eq!(lines[2..], [
+            "0|\\docs|16-1|d/drwxrwxrwx|0|0|0|1704067216|1704067216|1704067216|1704067216",
+            "0|\\docs ($FILE_NAME)|16-1|d/drwxrwxrwx|0|0|0|1704067216|1704067216|1704067216|1704067216",
+            "0|\\docs\\a,b.txt|17-1|r/rrwxrwxrwx|0|0|30|1704067217|1704067217|1704067217|1704067217",
+            "0|\\docs\\a,b.txt ($FILE_NAME)|17-1|r/rrwxrwxrwx|0|0|30|1704067217|1704067217|1704067217|1704067217",
+            "0|\\docs\\gone.txt (deleted)|18-1|r/rrwxrwxrwx|0|0|30|1704067218|1704067218|1704067218|1704067218",
+            "0|\\docs\\gone.txt ($FILE_NAME) (deleted)|18-1|r/rrwxrwxrwx|0|0|30|1704067218|1704067218|1704067218|1704067218",
+        ]);
+    }
+
+    #[test]
+    fn tln_groups_equal_timestamps() {
+        let mut out = Vec::new();
+        write_entries(&mut TlnWriter::new(&mut out, "WS01", "-"));
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 8);
+        assert_eq!(lines[4..], [
+            "1704067217|FILE|WS01|-|MACB [$SI] \\docs\\a,b.txt",
+            "1704067217|FILE|WS01|-|MACB [$FN] \\docs\\a,b.txt",
+            "1704067218|FILE|WS01|-|MACB [$SI] \\docs\\gone.txt (deleted)",
+            "1704067218|FILE|WS01|-|MACB [$FN] \\docs\\gone.txt (deleted)",
+        ]);
+
+        // Разные метки одного атрибута - отдельные строки по возрастанию времени
+        let mut entry = entries().remove(2);
+        entry.last_access0x10 = Some("2024-01-01T01:00:00Z".to_string());
+        entry.created0x10 = Some("2023-12-31T23:00:00Z".to_string());
+        entry.created0x30 = None;
+        let mut out = Vec::new();
+        let mut sink = TlnWriter::new(&mut out, "WS01", "alice");
+        sink.write(&entry).unwrap();
+        sink.finish().unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "\
+            1704063600|FILE|WS01|alice|...B [$SI] \\docs\\a,b.txt\n\
+            1704067217|FILE|WS01|alice|M.C. [$SI] \\docs\\a,b.txt\n\
+            1704070800|FILE|WS01|alice|.A.. [$SI] \\docs\\a,b.txt\n\
+            1704067217|FILE|WS01|alice|MAC. [$FN] \\docs\\a,b.txt\n");
+    }
+
+    /// HTTP-сервер для приемников SIEM: отвечает на запросы по очереди ответами `responses`
+    /// (код, тело) и возвращает принятые запросы (заголовок, тело).
+    #[cfg(feature = "siem")]
+    fn http_server(responses: Vec<(u16, String)>) -> (String, std::thread::JoinHandle<Vec<(String, String)>>) {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let mut requests = Vec::new();
+            for (status, body) in responses {
+                let mut reader = io::BufReader::new(listener.accept().unwrap().0);
+                let mut head = String::new();
+                while reader.read_line(&mut head).unwrap() > 2 {}
+                let length = head.lines()
+                    .find_map(|l| l.to_ascii_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse().unwrap()))
+                    .unwrap_or(0);
+                let mut request = vec![0; length];
+                reader.read_exact(&mut request).unwrap();
+                write!(reader.get_mut(), "HTTP/1.1 {} X\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status, body.len(), body).unwrap();
+                requests.push((head, String::from_utf8(request).unwrap()));
+            }
+            requests
+        });
+        (url, server)
+    }
+
+    #[cfg(feature = "siem")]
+    #[test]
+    fn elastic_retries_rejected_documents() {
+        let bulk = |statuses: &[u16]| {
+            let items: Vec<_> = statuses.iter().map(|&status| match status {
+                400 => serde_json::json!({"index": {"status": 400, "error": {"type": "mapper_parsing_exception"}}}),
+                status => serde_json::json!({"index": {"status": status}}),
+            }).collect();
+            serde_json::json!({"errors": statuses.iter().any(|&s| s >= 300), "items": items}).to_string()
+        };
+        // 503 повторяется целиком, из пакета повторяется только документ с 429
+        let (url, server) = http_server(vec![(503, String::new()), (200, bulk(&[201, 429, 400, 201])), (200, bulk(&[201]))]);
+        let mut sink = ElasticSink::new(&format!("{}/", url), "mft");
+        for entry in entries() {
+            sink.write(&entry).unwrap();
+        }
+        let err = sink.finish().unwrap_err();
+        assert_eq!(err.to_string(), crate::i18n::parse::es_not_indexed(1, 3, "{\"type\":\"mapper_parsing_exception\"}"));
+
+        let requests = server.join().unwrap();
+        assert_eq!(requests.len(), 3);
+        assert!(requests.iter().all(|(head, _)| head.starts_with("POST /_bulk HTTP/1.1")));
+        assert_eq!(requests[0].1, requests[1].1);
+        let lines: Vec<&str> = requests[1].1.lines().collect();
+        assert_eq!(lines.len(), 8);
+        let names: Vec<String> = lines.chunks(2).map(|pair| {
+            assert_eq!(pair[0], "{\"index\":{\"_index\":\"mft\"}}");
+            serde_json::from_str::<serde_json::Value>(pair[1]).unwrap()["FileName"].as_str().unwrap().to_string()
+        }).collect();
+        assert_eq!(names, [".", "docs", "a,b.txt", "gone.txt"]);
+        assert_eq!(requests[2].1, format!("{}\n{}\n", lines[2], lines[3]));
+    }
+
+    #[cfg(feature = "siem")]
+    #[test]
+    fn splunk_events_carry_record_time() {
+        let (url, server) = http_server(vec![(200, "{\"text\":\"Success\",\"code\":0}".to_string())]);
+        let (scheme, host) = url.split_once("://").unwrap();
+        let mut sink = SplunkSink::new(&format!("{}://user:secret@{}", scheme, host), "token-1");
+        assert_eq!(sink.event_url(), format!("{}://***@{}/services/collector/event", scheme, host));
+        let entries = entries();
+        for entry in &entries {
+            sink.write(entry).unwrap();
+        }
+        sink.finish().unwrap();
+
+        let requests = server.join().unwrap();
+        assert_eq!(requests.len(), 1);
+        let (head, body) = &requests[0];
+        assert!(head.starts_with("POST /services/collector/event HTTP/1.1"), "{}", head);
+        assert!(head.lines().any(|l| l.eq_ignore_ascii_case("authorization: Splunk token-1")), "{}", head);
+        let lines: Vec<&str> = body.lines().collect();
+        assert_eq!(lines.len(), entries.len());
+        assert!(lines[2].starts_with("{\"time\":1704067217.000000,"), "{}", lines[2]);
+        let event: serde_json::Value = serde_json::from_str(lines[2]).unwrap();
+        assert_eq!(event["sourcetype"], "mft:entry");
+        assert_eq!(event["source"], serde_json::json!(entries[2].source_file));
+        assert_eq!(event["event"]["Full_Path"], "\\docs\\a,b.txt");
+
+        // Отказ HEC (неверный токен) не повторяется и возвращается из finish
+        let (url, server) = http_server(vec![(403, "{\"text\":\"Invalid token\",\"code\":4}".to_string())]);
+        let mut sink = SplunkSink::new(&format!("{}/services/collector/event", url), "bad");
+        sink.write(&entries[0]).unwrap();
+        let err = sink.finish().unwrap_err();
+        assert!(err.to_string().starts_with("HTTP 403: "), "{}", err);
+        assert_eq!(server.join().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn compressed_report_round_trip() {
+        let fixture = Fixture::new();
+        let mut expected = String::new();
+        for compression in [None, Some(Compression::Gzip), Some(Compression::Zstd)] {
+            let name = format!("report.jsonl{}", compression.map(Compression::extension).unwrap_or_default());
+            let path = fixture.write(&name, b"");
+            let writer = CompressedWriter::new(Box::new(std::fs::File::create(&path).unwrap()), compression);
+            #[cfg(not(feature = "zstd"))]
+            if compression == Some(Compression::Zstd) {
+                assert_eq!(writer.err().map(|e| e.kind()), Some(io::ErrorKind::Unsupported));
+                continue;
+            }
+            let mut jsonl = JsonlWriter::new(writer.unwrap());
+            for entry in entries() {
+                jsonl.write(&entry).unwrap();
+            }
+            let position = jsonl.position();
+            jsonl.into_inner().finish().unwrap();
+
+            let mut text = String::new();
+            open_report(&path).unwrap().read_to_string(&mut text).unwrap();
+            assert_eq!(text.len() as u64, position);
+            assert_eq!(text.lines().count(), 4);
+            if compression.is_none() {
+                expected = text;
+            } else {
+                assert_ne!(std::fs::read(&path).unwrap(), expected.as_bytes());
+                assert_eq!(text, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn timesketch_event_per_distinct_time() {
+        let mut entry = entries().remove(3);
+        entry.last_access0x10 = Some("2024-01-01T01:00:00.5Z".to_string());
+        for attribute in [&mut entry.created0x30, &mut entry.last_modified0x30, &mut entry.last_record_change0x30, &mut entry.last_access0x30] {
+            *attribute = None;
+        }
+        let events = timesketch_events(&entry).unwrap();
+        let summary: Vec<(&str, &str, i64, &str)> = events.iter().map(|e| (
+            e["message"].as_str().unwrap(), e["datetime"].as_str().unwrap(),
+            e["timestamp"].as_i64().unwrap(), e["timestamp_desc"].as_str().unwrap(),
+        )).collect();
+        let created = entry.created0x10.as_deref().unwrap();
+        assert_eq!(summary, [
+            ("M.CB [$SI] \\docs\\gone.txt (deleted)", created, 1_704_067_218_000_000,
+                "Content Modification Time, Metadata Modification Time, Creation Time ($SI)"),
+            (".A.. [$SI] \\docs\\gone.txt (deleted)", "2024-01-01T01:00:00.5Z", 1_704_070_800_500_000, "Last Access Time ($SI)"),
+        ]);
+        // Поля записи копируются в событие; из уже сериализованной строки (query) - те же события
+        assert_eq!(events[0]["EntryNumber"], 18);
+        let serde_json::Value::Object(row) = serde_json::to_value(&entry).unwrap() else { unreachable!() };
+        assert_eq!(timesketch_row_events(&row), events);
+
+        entry.created0x10 = None;
+        entry.last_modified0x10 = None;
+        entry.last_record_change0x10 = None;
+        entry.last_access0x10 = None;
+        assert!(timesketch_events(&entry).unwrap().is_empty());
+    }
+
+    #[test]
+    fn report_files_round_trip() {
+        let (path, _) = crate::mft::utils::create_temp_file("mft_shadow_forge_test", "jsonl").unwrap();
+        let path = path.to_string_lossy().into_owned();
+        write_jsonl_file(&path, &[serde_json::json!({"a": 1}), serde_json::json!({"b": 2})]).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "{\"a\":1}\n{\"b\":2}\n");
+        write_json_file(&path, &serde_json::json!({"a": 1})).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "{\n  \"a\": 1\n}\n");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn full_disk_is_an_error() {
+        assert!(write_jsonl_file("/dev/full", &[serde_json::json!({"a": 1})]).is_err());
+        assert!(write_json_file("/dev/full", &serde_json::json!({"a": 1})).is_err());
+    }
+}
@@ -0,0 +1,147 @@
+use std::borrow::Cow;
+use std::ffi::{c_char, CStr, CString};
+
+use libloading::{Library, Symbol};
+use serde_json::{Map, Value};
+use tracing::info;
+
+use crate::i18n::parse as msg;
+
+/// Версия C-интерфейса плагинов (`mftsf_plugin_abi_version`).
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+
+/// Внешний детектор: получает строку отчета (и при необходимости сырые записи MFT)
+/// и возвращает дополнительные поля для этой строки.
+pub trait EntryAnalyzer {
+    fn name(&self) -> &str;
+
+    /// Нужны ли анализатору сырые записи MFT (базовая + расширения, после fixups).
+    fn wants_raw(&self) -> bool {
+        false
+    }
+
+    /// Дополнительные поля или None, если добавлять нечего. `raw` пуст, если `wants_raw` = false.
+    fn analyze(&self, entry: &Value, raw: &[Cow<[u8]>]) -> Option<Map<String, Value>>;
+}
+
+type AbiVersionFn = unsafe extern "C" fn() -> u32;
+type NameFn = unsafe extern "C" fn() -> *const c_char;
+type WantsRawFn = unsafe extern "C" fn() -> i32;
+type AnalyzeFn = unsafe extern "C" fn(*const c_char) -> *mut c_char;
+type FreeFn = unsafe extern "C" fn(*mut c_char);
+
+/// Плагин в динамической библиотеке (.dll/.so) с C-интерфейсом:
+/// - `uint32_t mftsf_plugin_abi_version(void)` - должен вернуть `PLUGIN_ABI_VERSION`;
+/// - `const char* mftsf_plugin_name(void)`;
+/// - `int mftsf_plugin_wants_raw(void)` - необязательно, по умолчанию 0;
+/// - `char* mftsf_analyze(const char* input_json)` - JSON-объект с новыми полями или NULL;
+/// - `void mftsf_free(char*)` - освобождение строки, возвращенной `mftsf_analyze`.
+///
+/// На вход `mftsf_analyze` подается строка отчета; при `wants_raw` в нее добавляется
+/// поле `RawRecords` - записи MFT в hex.
+pub struct DylibAnalyzer {
+    name: String,
+    wants_raw: bool,
+    analyze: AnalyzeFn,
+    free: FreeFn,
+    // Библиотека должна жить дольше указателей на ее функции
+    _lib: Library,
+}
+
+impl DylibAnalyzer {
+    pub fn load(path: &str) -> Result<Self, String> {
+        // Загрузка библиотеки выполняет ее код инициализации: плагины - только из доверенных источников
+        let lib = unsafe { Library::new(path) }.map_err(|e| e.to_string())?;
+        unsafe {
+            let abi: Symbol<AbiVersionFn> = lib.get(b"mftsf_plugin_abi_version\0").map_err(|e| e.to_string())?;
+            let version = abi();
+            if version != PLUGIN_ABI_VERSION {
+                return Err(msg::plugin_abi(version, PLUGIN_ABI_VERSION));
+            }
+            let name_fn: Symbol<NameFn> = lib.get(b"mftsf_plugin_name\0").map_err(|e| e.to_string())?;
+            let name_ptr = name_fn();
+            let name = if name_ptr.is_null() {
+                path.to_string()
+            } else {
+                CStr::from_ptr(name_ptr).to_string_lossy().into_owned()
+            };
+            let wants_raw = lib.get::<WantsRawFn>(b"mftsf_plugin_wants_raw\0").map(|f| f() != 0).unwrap_or(false);
+            let analyze = *lib.get::<AnalyzeFn>(b"mftsf_analyze\0").map_err(|e| e.to_string())?;
+            let free = *lib.get::<FreeFn>(b"mftsf_free\0").map_err(|e| e.to_string())?;
+            Ok(Self { name, wants_raw, analyze, free, _lib: lib })
+        }
+    }
+}
+
+impl EntryAnalyzer for DylibAnalyzer {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn wants_raw(&self) -> bool {
+        self.wants_raw
+    }
+
+    fn analyze(&self, entry: &Value, raw: &[Cow<[u8]>]) -> Option<Map<String, Value>> {
+        let input = if !raw.is_empty() {
+            let mut with_raw = entry.clone();
+            if let Value::Object(obj) = &mut with_raw {
+                obj.insert("RawRecords".to_string(), raw.iter().map(|r| Value::from(to_hex(r))).collect());
+            }
+            with_raw
+        } else {
+            entry.clone()
+        };
+        let input = CString::new(input.to_string()).ok()?;
+
+        let out = unsafe { (self.analyze)(input.as_ptr()) };
+        if out.is_null() {
+            return None;
+        }
+        let parsed = unsafe { CStr::from_ptr(out) }.to_str().ok()
+            .and_then(|s| serde_json::from_str::<Map<String, Value>>(s).ok());
+        unsafe { (self.free)(out) };
+        parsed
+    }
+}
+
+fn to_hex(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Набор загруженных плагинов, применяемый к каждой записи.
+#[derive(Default)]
+pub struct PluginSet {
+    analyzers: Vec<Box<dyn EntryAnalyzer>>,
+}
+
+impl PluginSet {
+    pub fn load(paths: &[String]) -> Result<Self, String> {
+        let mut analyzers: Vec<Box<dyn EntryAnalyzer>> = Vec::new();
+        for path in paths {
+            let plugin = DylibAnalyzer::load(path).map_err(|e| format!("{}: {}", path, e))?;
+            info!("{}", msg::plugin_loaded(plugin.name(), path));
+            analyzers.push(Box::new(plugin));
+        }
+        Ok(Self { analyzers })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.analyzers.is_empty()
+    }
+
+    /// Поля от всех плагинов. Поля, уже имеющиеся в строке отчета, не переопределяются.
+    pub fn analyze(&self, entry: &Value, raw: &[Cow<[u8]>]) -> Map<String, Value> {
+        let mut fields = Map::new();
+        for analyzer in &self.analyzers {
+            let raw = if analyzer.wants_raw() { raw } else { &[] };
+            let Some(extra) = analyzer.analyze(entry, raw) else { continue };
+            for (key, value) in extra {
+                if entry.get(&key).is_none() && !fields.contains_key(&key) {
+                    fields.insert(key, value);
+                }
+            }
+        }
+        fields
+    }
+}
\ No newline at end of file
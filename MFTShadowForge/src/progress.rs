@@ -0,0 +1,102 @@
+use std::io::{self, IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+use tracing::{info, Level};
+
+use crate::i18n::common as msg;
+
+/// Минимальный интервал между перерисовками строки хода.
+const REDRAW_EVERY: Duration = Duration::from_millis(250);
+
+/// В чем считается объем работы.
+#[derive(Debug, Clone, Copy)]
+pub enum ProgressUnit {
+    Records,
+    Bytes,
+}
+
+/// Строка хода длинной операции в stderr: сделано/всего, скорость и оставшееся время.
+/// Перерисовывается на месте через `\r`, поэтому выводится только в терминал:
+/// в перенаправленный stderr (скрипты, журналы) ничего не пишется. По завершении
+/// длительность этапа всегда уходит в журнал (поля `stage`, `elapsed_ms`, `total`).
+pub struct Progress {
+    /// Имя этапа для журнала, не зависит от языка (`extract`, `pass1`)
+    stage: &'static str,
+    label: String,
+    total: u64,
+    unit: ProgressUnit,
+    started: Instant,
+    last_draw: Option<Instant>,
+    /// Длина последней строки в символах: короткая строка дополняется пробелами
+    /// (ANSI-очистка строки есть не во всех консолях Windows)
+    width: usize,
+    enabled: bool,
+    /// Длительность этапа уже записана в журнал
+    logged: bool,
+}
+
+impl Progress {
+    pub fn new(stage: &'static str, label: String, total: u64, unit: ProgressUnit, enabled: bool) -> Self {
+        Progress {
+            stage, label, total, unit,
+            started: Instant::now(),
+            last_draw: None,
+            width: 0,
+            // --quiet отключает и строку хода
+            enabled: enabled && total > 0 && io::stderr().is_terminal() && tracing::enabled!(Level::INFO),
+            logged: false,
+        }
+    }
+
+    pub fn set(&mut self, done: u64) {
+        if !self.enabled { return; }
+        let now = Instant::now();
+        if self.last_draw.is_some_and(|t| now.duration_since(t) < REDRAW_EVERY) { return; }
+        self.last_draw = Some(now);
+
+        let elapsed = now.duration_since(self.started).as_secs_f64();
+        let rate = if elapsed > 0.0 { done as f64 / elapsed } else { 0.0 };
+        let eta = if rate > 0.0 { format_duration(self.total.saturating_sub(done) as f64 / rate) } else { "--:--:--".to_string() };
+        let percent = done.min(self.total) as f64 * 100.0 / self.total as f64;
+        let (done, total, rate) = match self.unit {
+            ProgressUnit::Records => (done.to_string(), msg::progress_records(self.total), msg::progress_records_rate(rate)),
+            ProgressUnit::Bytes => (format_mib(done as f64), msg::progress_mib(&format_mib(self.total as f64)), msg::progress_mib_rate(&format_mib(rate))),
+        };
+        let line = msg::progress_line(&self.label, percent, &done, &total, &rate, &eta);
+        let width = line.chars().count();
+        let mut err = io::stderr().lock();
+        let _ = write!(err, "\r{}{}", line, " ".repeat(self.width.saturating_sub(width)));
+        let _ = err.flush();
+        self.width = width;
+    }
+
+    /// Стирает строку хода, чтобы за ней шел обычный вывод, и пишет длительность этапа.
+    pub fn finish(&mut self) {
+        if self.enabled && self.last_draw.take().is_some() {
+            let mut err = io::stderr().lock();
+            let _ = write!(err, "\r{}\r", " ".repeat(self.width));
+            let _ = err.flush();
+        }
+        if !self.logged {
+            self.logged = true;
+            let elapsed = self.started.elapsed();
+            info!(stage = self.stage, elapsed_ms = elapsed.as_millis() as u64, total = self.total,
+                "{}", msg::stage_finished(&self.label, elapsed.as_secs_f64()));
+        }
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+fn format_mib(bytes: f64) -> String {
+    format!("{:.1}", bytes / (1024.0 * 1024.0))
+}
+
+fn format_duration(secs: f64) -> String {
+    let secs = secs.round() as u64;
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
\ No newline at end of file
@@ -0,0 +1,98 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::MftEntry;
+
+/// Версия формата индекса: индекс другой версии не используется.
+const INDEX_VERSION: u32 = 1;
+
+/// Индекс отчета parse рядом с ним (`<report>.idx`, JSON в gzip): по нему query и recover
+/// читают только нужные строки, а не весь отчет. Колонки идут по порядку строк отчета
+/// (он же порядок номеров записей).
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ReportIndex {
+    version: u32,
+    /// Размер отчета при построении: к дописанному или перезаписанному отчету индекс не применяется
+    report_size: u64,
+    entries: Vec<u64>,
+    sequences: Vec<u16>,
+    /// Смещение строки в отчете
+    offsets: Vec<u64>,
+    /// Full_Path в нижнем регистре
+    paths: Vec<String>,
+    /// Расширение в нижнем регистре -> номера строк
+    extensions: BTreeMap<String, Vec<u32>>,
+}
+
+pub fn index_path_for_report(report: &str) -> String { format!("{}.idx", report) }
+
+/// Путь без буквы диска: `C:\Windows` и `\Windows` - один и тот же путь тома.
+fn volume_path(path: &str) -> &str {
+    match path.as_bytes() {
+        [drive, b':', ..] if drive.is_ascii_alphabetic() => &path[2..],
+        _ => path,
+    }
+}
+
+impl ReportIndex {
+    pub fn new() -> Self {
+        Self { version: INDEX_VERSION, ..Default::default() }
+    }
+
+    /// Строка `entry`, записанная в отчет со смещения `offset`.
+    pub fn push(&mut self, offset: u64, entry: &MftEntry) {
+        let row = self.entries.len() as u32;
+        self.entries.push(entry.entry_number);
+        self.sequences.push(entry.sequence_number);
+        self.offsets.push(offset);
+        self.paths.push(entry.full_path.to_lowercase());
+        if let Some(ext) = entry.extension.as_deref().filter(|e| !e.is_empty()) {
+            self.extensions.entry(ext.to_lowercase()).or_default().push(row);
+        }
+    }
+
+    pub fn rows(&self) -> usize { self.entries.len() }
+
+    pub fn save(&mut self, report: &str, report_size: u64) -> io::Result<()> {
+        self.report_size = report_size;
+        let file = File::create(index_path_for_report(report))?;
+        let mut writer = flate2::write::GzEncoder::new(BufWriter::new(file), flate2::Compression::default());
+        serde_json::to_writer(&mut writer, self)?;
+        writer.finish()?.flush()
+    }
+
+    /// Индекс отчета `report`, если он есть и построен по текущему содержимому отчета.
+    pub fn load(report: &str) -> Option<Self> {
+        let report_size = std::fs::metadata(report).ok()?.len();
+        let file = File::open(index_path_for_report(report)).ok()?;
+        let index: Self = serde_json::from_reader(flate2::read::GzDecoder::new(BufReader::new(file))).ok()?;
+        (index.version == INDEX_VERSION && index.report_size == report_size).then_some(index)
+    }
+
+    pub fn offset(&self, row: usize) -> u64 { self.offsets[row] }
+
+    /// Строка записи `entry` (строки упорядочены по номерам записей).
+    pub fn row_by_entry(&self, entry: u64) -> Option<usize> {
+        self.entries.binary_search(&entry).ok()
+    }
+
+    /// Строки с расширением `ext` (без учета регистра), по возрастанию.
+    pub fn rows_by_extension(&self, ext: &str) -> Vec<usize> {
+        self.extensions.get(&ext.to_lowercase()).map(|rows| rows.iter().map(|&r| r as usize).collect()).unwrap_or_default()
+    }
+
+    /// Строки, путь которых (в нижнем регистре) удовлетворяет `matches`, по возрастанию.
+    pub fn rows_by_path(&self, matches: impl Fn(&str) -> bool) -> Vec<usize> {
+        self.paths.iter().enumerate().filter(|(_, p)| matches(p)).map(|(row, _)| row).collect()
+    }
+
+    /// Номер и sequence записи по пути тома, с буквой диска или без нее.
+    pub fn entry_by_path(&self, path: &str) -> Option<(u64, u16)> {
+        let wanted = volume_path(path).trim_end_matches('\\').to_lowercase();
+        let row = self.paths.iter().position(|p| volume_path(p) == wanted)?;
+        Some((self.entries[row], self.sequences[row]))
+    }
+}
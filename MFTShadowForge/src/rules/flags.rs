@@ -0,0 +1,24 @@
+use crate::mft::attributes::FileFlags;
+
+/// Расширения исполняемых файлов и скриптов.
+const EXECUTABLE_EXTENSIONS: [&str; 12] = [
+    "exe", "dll", "sys", "scr", "com", "cpl", "ocx", "ps1", "bat", "cmd", "vbs", "js",
+];
+
+/// Временные каталоги, доступные пользователю на запись.
+const TEMP_DIRS: [&str; 3] = ["\\appdata\\local\\temp\\", "\\windows\\temp\\", "\\temp\\"];
+
+pub fn is_executable_extension(extension: Option<&str>) -> bool {
+    extension.is_some_and(|ext| EXECUTABLE_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+pub fn is_temp_path(full_path: &str) -> bool {
+    let path_lc = full_path.to_lowercase();
+    TEMP_DIRS.iter().any(|d| path_lc.contains(d))
+}
+
+/// Сжатый NTFS-сжатием исполняемый файл во временном каталоге: легитимные установщики
+/// так не делают, а сжатие меняет размер на диске и мешает сигнатурному поиску по кластерам.
+pub fn is_compressed_exec_in_temp(full_path: &str, extension: Option<&str>, flags: &FileFlags) -> bool {
+    flags.is_compressed && is_executable_extension(extension) && is_temp_path(full_path)
+}
\ No newline at end of file
@@ -0,0 +1,132 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+
+use crate::hashing::{to_hex, HashAlgo};
+use crate::rules::sqlite::{self, SqliteReader, Value};
+
+/// Набор известных хешей (NSRL RDS или пользовательский список).
+///
+/// Формат файла - текст, по одному хешу на строку. Также принимаются CSV-строки
+/// NSRL (`"SHA-1","MD5","CRC32",...`): из строки берутся все поля, похожие
+/// на MD5/SHA1/SHA256. Строки, начинающиеся с `#`, пропускаются.
+/// База SQLite (NSRL RDS v3) узнается по сигнатуре: читаются все таблицы, хешами
+/// считаются текстовые значения того же вида и BLOB длиной 16/20/32 байта.
+#[derive(Debug, Default)]
+pub struct KnownHashSet {
+    hashes: HashSet<String>,
+    algos: Vec<HashAlgo>,
+}
+
+impl KnownHashSet {
+    pub fn load(path: &str) -> io::Result<Self> {
+        let mut file = File::open(path)?;
+        let mut magic = [0u8; 16];
+        let is_sqlite = file.read_exact(&mut magic).is_ok() && &magic == sqlite::MAGIC;
+        if is_sqlite {
+            return Self::load_sqlite(file);
+        }
+
+        let reader = BufReader::new(File::open(path)?);
+        let mut set = Self::default();
+        for line in reader.lines() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') { continue; }
+
+            for field in line.split(|c: char| c == ',' || c.is_whitespace()) {
+                set.add_field(field.trim_matches('"'));
+            }
+        }
+        Ok(set)
+    }
+
+    fn load_sqlite(file: File) -> io::Result<Self> {
+        let mut db = SqliteReader::open(file)?;
+        let mut set = Self::default();
+        for (_, root) in db.tables()? {
+            db.scan_table(root, &mut |row| {
+                for value in row {
+                    match value {
+                        Value::Text(text) => set.add_field(text.trim()),
+                        Value::Blob(bytes) => set.add_field(&to_hex(bytes)),
+                        _ => {}
+                    }
+                }
+            })?;
+        }
+        Ok(set)
+    }
+
+    /// Добавляет значение, если оно похоже на MD5/SHA1/SHA256 в hex.
+    fn add_field(&mut self, field: &str) {
+        let algo = match HashAlgo::from_hex_len(field.len()) {
+            Some(a) if field.chars().all(|c| c.is_ascii_hexdigit()) => a,
+            _ => return,
+        };
+        if !self.algos.contains(&algo) { self.algos.push(algo); }
+        self.hashes.insert(field.to_ascii_lowercase());
+    }
+
+    pub fn len(&self) -> usize {
+        self.hashes.len()
+    }
+
+    /// Считает только те алгоритмы, что реально встречаются в наборе.
+    pub fn contains_data(&self, data: &[u8]) -> bool {
+        self.algos.iter().any(|algo| self.hashes.contains(&algo.digest_hex(data)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image::Fixture;
+    use crate::rules::sqlite::TestDb;
+
+    #[test]
+    fn text_list_accepts_plain_hashes_and_nsrl_csv() {
+        let text = "# комментарий\n\
+            900150983CD24FB0D6963F7D28E17F72\n\
+            \"AAF4C61DDCC5E8A2DABEDE0F3B482CD9AEA9434D\",\"00000000000000000000000000000000\",\"1234ABCD\",\"hello.txt\"\n\
+            not-a-hash\n";
+        let fixture = Fixture::new();
+        let set = KnownHashSet::load(&fixture.write("known.txt", text.as_bytes())).unwrap();
+        assert_eq!(set.len(), 3);
+        assert!(set.contains_data(b"abc"));
+        assert!(set.contains_data(b"hello"));
+        assert!(!set.contains_data(b"other"));
+    }
+
+    #[test]
+    fn sqlite_hashes_come_from_text_and_blob_columns() {
+        let sha1_hello = [
+            0xaa, 0xf4, 0xc6, 0x1d, 0xdc, 0xc5, 0xe8, 0xa2, 0xda, 0xbe,
+            0xde, 0x0f, 0x3b, 0x48, 0x2c, 0xd9, 0xae, 0xa9, 0x43, 0x4d,
+        ];
+        let mut db = TestDb::new(1);
+        let cells = [
+            db.row(1, &[Value::Text("900150983CD24FB0D6963F7D28E17F72".into()), Value::Int(3)]),
+            db.row(2, &[Value::Blob(sha1_hello.to_vec()), Value::Text("hello.txt".into())]),
+            db.row(3, &[Value::Blob(vec![1, 2, 3]), Value::Null]),
+        ];
+        let root = db.leaf(&cells);
+        let fixture = Fixture::new();
+        let path = fixture.write("rds.db", &db.finish(&[("FILE", root)]));
+
+        let set = KnownHashSet::load(&path).unwrap();
+        assert_eq!(set.len(), 2);
+        assert!(set.contains_data(b"abc"));
+        assert!(set.contains_data(b"hello"));
+        assert!(!set.contains_data(b"other"));
+    }
+
+    #[test]
+    fn damaged_sqlite_is_invalid_data() {
+        let mut bytes = sqlite::MAGIC.to_vec();
+        bytes.extend([0u8; 40]);
+        let fixture = Fixture::new();
+        let err = KnownHashSet::load(&fixture.write("short.db", &bytes)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -1,2 +1,13 @@
+#[allow(clippy::module_inception)]
 pub mod rules;
+pub mod flags;
+pub mod hashset;
+pub mod path;
+pub mod profiles;
+pub mod query;
+pub mod reparse;
+pub mod ruleset;
+pub mod sigma;
+pub mod siblings;
+pub mod sqlite;
 pub mod timestamp;
\ No newline at end of file
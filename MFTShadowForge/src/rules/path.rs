@@ -0,0 +1,73 @@
+/// Символы, визуально похожие на точку (для имен вида `..`, `‥`, `．．`).
+const DOT_LOOKALIKES: [char; 6] = ['\u{2024}', '\u{2025}', '\u{2026}', '\u{FF0E}', '\u{FE52}', '\u{2E33}'];
+
+/// Зарезервированные имена устройств Win32 (запрещены и с любым расширением: `con.exe`).
+const RESERVED_DEVICE_NAMES: [&str; 22] = [
+    "con", "prn", "aux", "nul",
+    "com1", "com2", "com3", "com4", "com5", "com6", "com7", "com8", "com9",
+    "lpt1", "lpt2", "lpt3", "lpt4", "lpt5", "lpt6", "lpt7", "lpt8", "lpt9",
+];
+
+/// Имя, которое нельзя создать через Win32 API: завершающие точки/пробелы,
+/// имена устройств, запрещенные и управляющие символы. Такие имена создаются
+/// только через POSIX-пространство или native API и прячутся от Explorer.
+pub fn is_win32_invalid_name(name: &str) -> bool {
+    if name.is_empty() || name == "." || name == ".." { return false; }
+    if name.ends_with('.') || name.ends_with(' ') { return true; }
+    if name.chars().any(|c| (c as u32) < 0x20 || matches!(c, '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*')) {
+        return true;
+    }
+    let stem = name.split('.').next().unwrap_or(name).trim_end().to_ascii_lowercase();
+    RESERVED_DEVICE_NAMES.contains(&stem.as_str())
+}
+
+/// Разобранный путь для эвристик формы пути.
+pub struct PathShape<'a> {
+    full_path: &'a str,
+    components: Vec<&'a str>,
+}
+
+impl<'a> PathShape<'a> {
+    pub fn new(full_path: &'a str) -> Self {
+        let components = full_path
+            .split('\\')
+            .filter(|c| !c.is_empty() && !c.ends_with(':'))
+            .collect();
+        Self { full_path, components }
+    }
+
+    /// Глубина пути больше порога
+    pub fn is_too_deep(&self, max_depth: usize) -> bool {
+        max_depth > 0 && self.components.len() > max_depth
+    }
+
+    /// Длина пути (в UTF-16 единицах, как считает Windows) больше порога
+    pub fn is_too_long(&self, max_len: usize) -> bool {
+        max_len > 0 && self.full_path.encode_utf16().count() > max_len
+    }
+
+    /// Повторяющееся вложение: последовательность из 1-3 каталогов подряд
+    /// встречается 3+ раза (a\a\a, a\b\a\b\a\b) - типично для распаковки "архивных бомб".
+    pub fn has_repeated_nesting(&self) -> bool {
+        let parts: Vec<String> = self.components.iter().map(|c| c.to_lowercase()).collect();
+        for period in 1..=3 {
+            let needed = period * 3;
+            if parts.len() < needed { break; }
+            for start in 0..=parts.len() - needed {
+                if (start + period..start + needed).all(|i| parts[i] == parts[i - period]) {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    /// Компонент, состоящий только из точек/пробелов (кроме корня "."),
+    /// либо содержащий юникодные двойники точки.
+    pub fn has_dot_like_name(&self) -> bool {
+        self.components.iter().any(|name| {
+            if name.chars().any(|c| DOT_LOOKALIKES.contains(&c)) { return true; }
+            *name != "." && name.chars().all(|c| c == '.' || c == ' ')
+        })
+    }
+}
@@ -0,0 +1,41 @@
+use super::rules::Rule;
+use super::ruleset::{self, NamedRule};
+use crate::models::Severity;
+
+/// Наборы правил, вшитые в бинарник (`--profile`): имя, описание, TOML.
+const PROFILES: &[(&str, &str, &str)] = &[
+    ("persistence", "места автозапуска: Startup, задания, GPO-скрипты, профили PowerShell, Office", include_str!("profiles/persistence.toml")),
+    ("webshell", "скрипты в каталогах IIS, Exchange, Java и PHP", include_str!("profiles/webshell.toml")),
+    ("ransomware", "записки о выкупе и расширения зашифрованных файлов", include_str!("profiles/ransomware.toml")),
+    ("lolbin", "исполняемые файлы и скрипты в каталогах подготовки атаки", include_str!("profiles/lolbin.toml")),
+];
+
+/// Имена профилей для --profile; `default` - встроенные правила.
+pub const PROFILE_NAMES: [&str; 5] = ["default", "persistence", "webshell", "ransomware", "lolbin"];
+
+/// Встроенные правила - используются, если не заданы --profile и --rules.
+pub fn default_rules() -> Vec<NamedRule> {
+    vec![
+        NamedRule::new("applocker-txt", Severity::Medium, Rule::glob(r"*\Windows\System32\AppLocker\*.txt").unwrap().and(Rule::ends_with("123.txt").not())),
+        NamedRule::new("ime-ps1", Severity::High, Rule::glob(r"*\Windows\IME\*.ps1").unwrap()),
+        NamedRule::new("recycle-bin-exe", Severity::High, Rule::glob(r"*\$Recycle.Bin\*.exe").unwrap()),
+        NamedRule::new("public-exe", Severity::Medium, Rule::starts_with("C:\\Users\\Public\\").and(Rule::ends_with(".exe"))),
+        NamedRule::new("system32-dll", Severity::Low, Rule::contains("\\system32\\").and(Rule::ends_with(".dll"))),
+    ]
+}
+
+/// Правила профилей по порядку; одноименные правила последующих профилей заменяют предыдущие.
+pub fn load(names: &[String]) -> Result<Vec<NamedRule>, String> {
+    let mut rules = Vec::new();
+    for name in names {
+        let profile_rules = if name == "default" {
+            default_rules()
+        } else {
+            let (_, _, text) = PROFILES.iter().find(|(n, _, _)| n == name)
+                .ok_or_else(|| format!("неизвестный профиль {} (есть: {})", name, PROFILE_NAMES.join(", ")))?;
+            ruleset::parse_toml(text).map_err(|e| format!("профиль {}: {}", name, e))?
+        };
+        ruleset::merge(&mut rules, profile_rules);
+    }
+    Ok(rules)
+}
\ No newline at end of file
@@ -0,0 +1,511 @@
+use std::fmt;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde_json::Value;
+
+use super::rules::GlobRule;
+
+/// Выражение фильтра `query --where`:
+///
+/// ```text
+/// expr    := and ("or" and)*
+/// and     := unary ("and" unary)*
+/// unary   := "not" unary | "(" expr ")" | field [op literal]
+/// op      := = | != | < | <= | > | >= | glob | contains
+/// literal := 'строка' | "строка" | число | true | false | null
+/// ```
+///
+/// Имена полей и ключевые слова - без учета регистра (`extension` = `Extension`).
+/// Поле без оператора - проверка на истинность (true, не 0, не пустая строка).
+/// Строки сравниваются без учета регистра; если поле - метка RFC 3339, а литерал -
+/// дата (`2024-03-01`) или метка, сравниваются моменты времени.
+#[derive(Debug, Clone)]
+pub struct Query {
+    root: Node,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Glob,
+    Contains,
+}
+
+#[derive(Debug, Clone)]
+enum Literal {
+    Str(String),
+    Num(f64),
+    Bool(bool),
+    Null,
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Or(Box<Node>, Box<Node>),
+    And(Box<Node>, Box<Node>),
+    Not(Box<Node>),
+    Truthy(String),
+    Compare { field: String, op: Op, literal: Literal, glob: Option<GlobRule>, time: Option<DateTime<Utc>> },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Num(f64),
+    Op(&'static str),
+    LParen,
+    RParen,
+}
+
+fn tokenize(input: &str) -> Result<Vec<(usize, Token)>, String> {
+    let chars: Vec<(usize, char)> = input.char_indices().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let (pos, c) = chars[i];
+        if c.is_whitespace() { i += 1; continue; }
+        match c {
+            '(' => { tokens.push((pos, Token::LParen)); i += 1; }
+            ')' => { tokens.push((pos, Token::RParen)); i += 1; }
+            '\'' | '"' => {
+                let mut s = String::new();
+                i += 1;
+                loop {
+                    let Some(&(_, ch)) = chars.get(i) else { return Err(format!("незакрытая строка в позиции {}", pos)) };
+                    i += 1;
+                    if ch == c {
+                        // Кавычка внутри строки удваивается: 'O''Brien'
+                        if chars.get(i).is_some_and(|&(_, next)| next == c) { s.push(c); i += 1; continue; }
+                        break;
+                    }
+                    s.push(ch);
+                }
+                tokens.push((pos, Token::Str(s)));
+            }
+            '=' | '!' | '<' | '>' => {
+                let next = chars.get(i + 1).map(|&(_, ch)| ch);
+                let (op, len) = match (c, next) {
+                    ('!', Some('=')) => ("!=", 2),
+                    ('<', Some('=')) => ("<=", 2),
+                    ('>', Some('=')) => (">=", 2),
+                    ('<', Some('>')) => ("!=", 2),
+                    ('=', Some('=')) => ("=", 2),
+                    ('=', _) => ("=", 1),
+                    ('<', _) => ("<", 1),
+                    ('>', _) => (">", 1),
+                    _ => return Err(format!("неизвестный оператор в позиции {}", pos)),
+                };
+                tokens.push((pos, Token::Op(op)));
+                i += len;
+            }
+            c if c.is_ascii_digit() || c == '-' => {
+                let start = i;
+                i += 1;
+                while chars.get(i).is_some_and(|&(_, ch)| ch.is_ascii_digit() || ch == '.') { i += 1; }
+                let text: String = chars[start..i].iter().map(|&(_, ch)| ch).collect();
+                let num = text.parse().map_err(|_| format!("некорректное число {} в позиции {}", text, pos))?;
+                tokens.push((pos, Token::Num(num)));
+            }
+            c if c.is_alphanumeric() || c == '_' || c == '$' => {
+                let start = i;
+                while chars.get(i).is_some_and(|&(_, ch)| ch.is_alphanumeric() || ch == '_' || ch == '$') { i += 1; }
+                tokens.push((pos, Token::Ident(chars[start..i].iter().map(|&(_, ch)| ch).collect())));
+            }
+            _ => return Err(format!("неожиданный символ '{}' в позиции {}", c, pos)),
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> { self.tokens.get(self.pos).map(|(_, t)| t) }
+
+    fn position(&self) -> String {
+        self.tokens.get(self.pos).map(|(p, _)| format!("позиции {}", p)).unwrap_or_else(|| "конце выражения".to_string())
+    }
+
+    fn keyword(&mut self, word: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Ident(w)) if w.eq_ignore_ascii_case(word)) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn or(&mut self) -> Result<Node, String> {
+        let mut node = self.and()?;
+        while self.keyword("or") {
+            node = Node::Or(Box::new(node), Box::new(self.and()?));
+        }
+        Ok(node)
+    }
+
+    fn and(&mut self) -> Result<Node, String> {
+        let mut node = self.unary()?;
+        while self.keyword("and") {
+            node = Node::And(Box::new(node), Box::new(self.unary()?));
+        }
+        Ok(node)
+    }
+
+    fn unary(&mut self) -> Result<Node, String> {
+        if self.keyword("not") {
+            return Ok(Node::Not(Box::new(self.unary()?)));
+        }
+        match self.peek().cloned() {
+            Some(Token::LParen) => {
+                self.pos += 1;
+                let node = self.or()?;
+                if self.peek() != Some(&Token::RParen) {
+                    return Err(format!("ожидалась ')' в {}", self.position()));
+                }
+                self.pos += 1;
+                Ok(node)
+            }
+            Some(Token::Ident(field)) => {
+                self.pos += 1;
+                let op = match self.peek() {
+                    Some(Token::Op(op)) => match *op {
+                        "=" => Op::Eq, "!=" => Op::Ne, "<" => Op::Lt, "<=" => Op::Le, ">" => Op::Gt, _ => Op::Ge,
+                    },
+                    Some(Token::Ident(w)) if w.eq_ignore_ascii_case("glob") => Op::Glob,
+                    Some(Token::Ident(w)) if w.eq_ignore_ascii_case("contains") => Op::Contains,
+                    _ => return Ok(Node::Truthy(field)),
+                };
+                self.pos += 1;
+                let literal = match self.peek().cloned() {
+                    Some(Token::Str(s)) => Literal::Str(s),
+                    Some(Token::Num(n)) => Literal::Num(n),
+                    Some(Token::Ident(w)) if w.eq_ignore_ascii_case("true") => Literal::Bool(true),
+                    Some(Token::Ident(w)) if w.eq_ignore_ascii_case("false") => Literal::Bool(false),
+                    Some(Token::Ident(w)) if w.eq_ignore_ascii_case("null") => Literal::Null,
+                    _ => return Err(format!("ожидалось значение в {}", self.position())),
+                };
+                self.pos += 1;
+                let glob = match (op, &literal) {
+                    (Op::Glob, Literal::Str(pattern)) => Some(GlobRule::new(pattern).map_err(|e| format!("glob {}: {}", pattern, e))?),
+                    (Op::Glob, _) => return Err("glob требует строку".to_string()),
+                    _ => None,
+                };
+                let time = match &literal { Literal::Str(s) => parse_time(s), _ => None };
+                Ok(Node::Compare { field, op, literal, glob, time })
+            }
+            _ => Err(format!("ожидалось поле, not или '(' в {}", self.position())),
+        }
+    }
+}
+
+impl Op {
+    fn as_str(self) -> &'static str {
+        match self {
+            Op::Eq => "=",
+            Op::Ne => "!=",
+            Op::Lt => "<",
+            Op::Le => "<=",
+            Op::Gt => ">",
+            Op::Ge => ">=",
+            Op::Glob => "glob",
+            Op::Contains => "contains",
+        }
+    }
+}
+
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Literal::Str(s) => write!(f, "'{}'", s.replace('\'', "''")),
+            Literal::Num(n) => write!(f, "{}", n),
+            Literal::Bool(b) => write!(f, "{}", b),
+            Literal::Null => f.write_str("null"),
+        }
+    }
+}
+
+impl Node {
+    /// Приоритет связки: скобки ставятся только там, где без них дерево разобралось бы иначе.
+    fn precedence(&self) -> u8 {
+        match self {
+            Node::Or(..) => 1,
+            Node::And(..) => 2,
+            _ => 3,
+        }
+    }
+
+    fn fmt_in(&self, f: &mut fmt::Formatter<'_>, min_precedence: u8) -> fmt::Result {
+        if self.precedence() < min_precedence {
+            f.write_str("(")?;
+            self.fmt_in(f, 0)?;
+            return f.write_str(")");
+        }
+        match self {
+            // Связки левоассоциативны: правый операнд того же уровня - в скобках
+            Node::Or(l, r) => { l.fmt_in(f, 1)?; f.write_str(" or ")?; r.fmt_in(f, 2) }
+            Node::And(l, r) => { l.fmt_in(f, 2)?; f.write_str(" and ")?; r.fmt_in(f, 3) }
+            Node::Not(inner) => { f.write_str("not ")?; inner.fmt_in(f, 3) }
+            Node::Truthy(name) => f.write_str(name),
+            Node::Compare { field, op, literal, .. } => write!(f, "{} {} {}", field, op.as_str(), literal),
+        }
+    }
+}
+
+/// Выражение в каноническом виде: `Query::parse` от него дает то же условие.
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.root.fmt_in(f, 0)
+    }
+}
+
+/// Метка RFC 3339 или дата `YYYY-MM-DD` (полночь UTC).
+fn parse_time(s: &str) -> Option<DateTime<Utc>> {
+    if let Ok(t) = DateTime::parse_from_rfc3339(s) {
+        return Some(t.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .map(|t| t.and_utc())
+}
+
+/// Значение поля без учета регистра имени.
+fn field<'a>(row: &'a Value, name: &str) -> Option<&'a Value> {
+    let obj = row.as_object()?;
+    obj.get(name).or_else(|| obj.iter().find(|(k, _)| k.eq_ignore_ascii_case(name)).map(|(_, v)| v))
+}
+
+fn value_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn compare(value: &Value, op: Op, literal: &Literal, glob: Option<&GlobRule>, time: Option<DateTime<Utc>>) -> bool {
+    use std::cmp::Ordering;
+    if matches!(literal, Literal::Null) {
+        return match op { Op::Eq => value.is_null(), Op::Ne => !value.is_null(), _ => false };
+    }
+    if value.is_null() {
+        return false;
+    }
+    match op {
+        Op::Glob => return glob.is_some_and(|g| g.regex.is_match(&value_text(value).to_lowercase())),
+        Op::Contains => {
+            let Literal::Str(needle) = literal else { return false };
+            return value_text(value).to_lowercase().contains(&needle.to_lowercase());
+        }
+        _ => {}
+    }
+    let ordering = match (value, literal) {
+        (Value::Bool(b), Literal::Bool(l)) => b.cmp(l),
+        (Value::Number(n), Literal::Num(l)) => match n.as_f64().and_then(|n| n.partial_cmp(l)) {
+            Some(o) => o,
+            None => return false,
+        },
+        (Value::String(s), Literal::Str(l)) => match (time, DateTime::parse_from_rfc3339(s)) {
+            (Some(t), Ok(v)) => v.with_timezone(&Utc).cmp(&t),
+            _ => s.to_lowercase().cmp(&l.to_lowercase()),
+        },
+        // Число в кавычках или строка-число: сравнение как текст
+        (v, Literal::Str(l)) => value_text(v).to_lowercase().cmp(&l.to_lowercase()),
+        (Value::String(s), Literal::Num(l)) => match s.parse::<f64>().ok().and_then(|n| n.partial_cmp(l)) {
+            Some(o) => o,
+            None => return false,
+        },
+        _ => return false,
+    };
+    match op {
+        Op::Eq => ordering == Ordering::Equal,
+        Op::Ne => ordering != Ordering::Equal,
+        Op::Lt => ordering == Ordering::Less,
+        Op::Le => ordering != Ordering::Greater,
+        Op::Gt => ordering == Ordering::Greater,
+        Op::Ge => ordering != Ordering::Less,
+        Op::Glob | Op::Contains => false,
+    }
+}
+
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64() != Some(0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(a) => !a.is_empty(),
+        Value::Object(o) => !o.is_empty(),
+    }
+}
+
+/// Условие, обязательное для любого совпадения (звено цепочки and верхнего уровня),
+/// по которому строки отбираются индексом отчета, а не чтением всего отчета.
+pub enum IndexKey<'a> {
+    Entry(u64),
+    Extension(&'a str),
+    PathEq(&'a str),
+    PathGlob(&'a GlobRule),
+}
+
+impl Node {
+    fn eval(&self, row: &Value) -> bool {
+        match self {
+            Node::Or(l, r) => l.eval(row) || r.eval(row),
+            Node::And(l, r) => l.eval(row) && r.eval(row),
+            Node::Not(inner) => !inner.eval(row),
+            Node::Truthy(name) => field(row, name).is_some_and(truthy),
+            Node::Compare { field: name, op, literal, glob, time } => match field(row, name) {
+                Some(value) => compare(value, *op, literal, glob.as_ref(), *time),
+                // Поля нет в строке - как null
+                None => matches!(literal, Literal::Null) && *op == Op::Eq,
+            },
+        }
+    }
+
+    fn index_keys<'a>(&'a self, keys: &mut Vec<IndexKey<'a>>) {
+        match self {
+            Node::And(l, r) => {
+                l.index_keys(keys);
+                r.index_keys(keys);
+            }
+            Node::Compare { field, op, literal, glob, .. } => {
+                let key = match (op, literal) {
+                    (Op::Eq, Literal::Num(n)) if field.eq_ignore_ascii_case("EntryNumber") && *n >= 0.0 && n.fract() == 0.0 => IndexKey::Entry(*n as u64),
+                    (Op::Eq, Literal::Str(s)) if field.eq_ignore_ascii_case("Extension") && !s.is_empty() => IndexKey::Extension(s),
+                    (Op::Eq, Literal::Str(s)) if field.eq_ignore_ascii_case("Full_Path") => IndexKey::PathEq(s),
+                    (Op::Glob, _) if field.eq_ignore_ascii_case("Full_Path") => match glob {
+                        Some(glob) => IndexKey::PathGlob(glob),
+                        None => return,
+                    },
+                    _ => return,
+                };
+                keys.push(key);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Query {
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let mut parser = Parser { tokens: tokenize(input)?, pos: 0 };
+        let root = parser.or()?;
+        if parser.pos < parser.tokens.len() {
+            return Err(format!("лишний текст в {}", parser.position()));
+        }
+        Ok(Query { root })
+    }
+
+    /// Оба условия сразу (флаги `--path`/`--after` к `--where`).
+    pub fn and(self, other: Query) -> Query {
+        Query { root: Node::And(Box::new(self.root), Box::new(other.root)) }
+    }
+
+    pub fn matches(&self, row: &Value) -> bool {
+        self.root.eval(row)
+    }
+
+    /// Условия выражения, пригодные для выборки по индексу отчета; пусто - нужен полный проход.
+    pub fn index_keys(&self) -> Vec<IndexKey<'_>> {
+        let mut keys = Vec::new();
+        self.root.index_keys(&mut keys);
+        keys
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn row() -> Value {
+        json!({
+            "EntryNumber": 42,
+            "Extension": "exe",
+            "Full_Path": "C:\\Users\\Public\\O'Brien.exe",
+            "FileSize": 53248,
+            "InUse": true,
+            "Timestomped": false,
+            "Created0x10": "2024-03-05T10:00:00Z",
+            "ZoneIdContents": null,
+        })
+    }
+
+    fn matches(expr: &str) -> bool {
+        Query::parse(expr).unwrap().matches(&row())
+    }
+
+    #[test]
+    fn round_trip_keeps_meaning() {
+        let exprs = [
+            "extension = 'exe' and timestomped",
+            "a or b and c",
+            "(a or b) and c",
+            "a or (b or c)",
+            "not (InUse and FileSize > 1000)",
+            "not not InUse",
+            "Full_Path glob '*\\public\\*' or Full_Path contains 'o''brien'",
+            "FileSize >= 53248 and FileSize <= 53248.5 and EntryNumber != -1",
+            "Created0x10 >= '2024-03-01' and Created0x10 < '2024-04-01T00:00:00Z'",
+            "ZoneIdContents = null and InUse = true and Timestomped == false",
+            "EntryNumber <> 41",
+        ];
+        for expr in exprs {
+            let query = Query::parse(expr).unwrap();
+            let text = query.to_string();
+            let again = Query::parse(&text).unwrap_or_else(|e| panic!("{} -> {}: {}", expr, text, e));
+            assert_eq!(again.to_string(), text, "{}", expr);
+            assert_eq!(again.matches(&row()), query.matches(&row()), "{}", expr);
+        }
+    }
+
+    #[test]
+    fn canonical_form() {
+        let canonical = |expr: &str| Query::parse(expr).unwrap().to_string();
+        assert_eq!(canonical("A OR B AND C"), "A or B and C");
+        assert_eq!(canonical("(a or b) and c"), "(a or b) and c");
+        assert_eq!(canonical("((a and b))"), "a and b");
+        assert_eq!(canonical("a or (b or c)"), "a or (b or c)");
+        assert_eq!(canonical("not (a and b)"), "not (a and b)");
+        assert_eq!(canonical("x <> \"it's\""), "x != 'it''s'");
+        assert_eq!(canonical("n == 5"), "n = 5");
+    }
+
+    #[test]
+    fn evaluation() {
+        assert!(matches("extension = 'EXE' and not timestomped"));
+        assert!(matches("Full_Path contains 'o''brien'"));
+        assert!(matches("full_path glob '*\\users\\*.exe'"));
+        assert!(matches("FileSize > 50000 and FileSize < 60000"));
+        assert!(matches("Created0x10 >= '2024-03-05' and Created0x10 < '2024-03-06'"));
+        assert!(matches("ZoneIdContents = null and Missing = null"));
+        assert!(!matches("ZoneIdContents != null"));
+        assert!(!matches("Missing"));
+        assert!(matches("InUse or Missing > 1"));
+    }
+
+    #[test]
+    fn errors_point_at_position() {
+        let err = |expr: &str| Query::parse(expr).unwrap_err();
+        assert_eq!(err("a = 'x"), "незакрытая строка в позиции 4");
+        assert_eq!(err("a = "), "ожидалось значение в конце выражения");
+        assert_eq!(err("(a and b"), "ожидалась ')' в конце выражения");
+        assert_eq!(err("a b"), "лишний текст в позиции 2");
+        assert_eq!(err("a ! b"), "неизвестный оператор в позиции 2");
+        assert_eq!(err("a glob 5"), "glob требует строку");
+    }
+
+    #[test]
+    fn index_keys_from_top_level_and_only() {
+        let query = Query::parse("EntryNumber = 42 and Extension = 'exe' and Full_Path glob '*.exe'").unwrap();
+        let keys = query.index_keys();
+        assert!(matches!(keys[..], [IndexKey::Entry(42), IndexKey::Extension("exe"), IndexKey::PathGlob(_)]));
+        assert!(Query::parse("EntryNumber = 42 or Extension = 'exe'").unwrap().index_keys().is_empty());
+    }
+}
@@ -0,0 +1,71 @@
+/// Каталоги, куда обычный пользователь может писать без повышения прав.
+const USER_WRITABLE: [&str; 7] = [
+    "\\users\\",
+    "\\programdata\\",
+    "\\windows\\temp\\",
+    "\\windows\\tasks\\",
+    "\\windows\\tracing\\",
+    "\\$recycle.bin\\",
+    "\\temp\\",
+];
+
+/// Системные каталоги, ссылка в которые из пользовательского места подозрительна.
+const SYSTEM_DIRS: [&str; 3] = ["\\windows", "\\program files", "\\program files (x86)"];
+
+/// Буква диска (`c:`) в начале пути, если есть.
+fn drive_of(path: &str) -> Option<&str> {
+    let bytes = path.as_bytes();
+    if bytes.len() >= 2 && bytes[1] == b':' && bytes[0].is_ascii_alphabetic() {
+        Some(&path[..2])
+    } else {
+        None
+    }
+}
+
+/// Путь без буквы диска.
+fn strip_drive(path: &str) -> &str {
+    match drive_of(path) {
+        Some(_) => &path[2..],
+        None => path,
+    }
+}
+
+/// Путь внутри системного каталога (`\Windows`, `\Program Files`, ...), с буквой диска или без.
+pub fn is_system_path(path: &str) -> bool {
+    let path_lc = path.to_lowercase();
+    let rel = strip_drive(&path_lc);
+    SYSTEM_DIRS.iter().any(|d| rel == *d || rel.starts_with(&format!("{}\\", d)))
+}
+
+/// Склеивает относительную цель symlink с каталогом ссылки и схлопывает `.`/`..`.
+pub fn resolve_relative(parent_path: &str, target: &str) -> String {
+    let mut parts: Vec<&str> = Vec::new();
+    for comp in parent_path.split('\\').chain(target.split('\\')) {
+        match comp {
+            "" | "." => {}
+            ".." => { if parts.last().is_some_and(|p| !p.ends_with(':')) { parts.pop(); } }
+            c => parts.push(c),
+        }
+    }
+    let joined = parts.join("\\");
+    if drive_of(&joined).is_some() { joined } else { format!("\\{}", joined) }
+}
+
+/// Junction/symlink в пользовательском каталоге, указывающий в системный каталог,
+/// на UNC-ресурс, GUID-том или на другой диск (съемный носитель).
+pub fn is_suspicious_reparse(link_path: &str, target: &str) -> bool {
+    let link_lc = link_path.to_lowercase();
+    let target_lc = target.to_lowercase();
+
+    let link_rel = strip_drive(&link_lc);
+    let from_user_dir = USER_WRITABLE.iter().any(|d| link_rel.contains(d));
+    if !from_user_dir { return false; }
+
+    if target_lc.starts_with("\\\\") || target_lc.starts_with("volume{") { return true; }
+
+    if let (Some(src), Some(dst)) = (drive_of(&link_lc), drive_of(&target_lc)) {
+        if src != dst { return true; }
+    }
+
+    is_system_path(&target_lc)
+}
@@ -1,30 +1,111 @@
+use chrono::{DateTime, NaiveDate, Utc};
 use regex::Regex;
 
 /// Предкомпилированное glob-правило.
 #[derive(Debug, Clone)]
 pub struct GlobRule {
+    pub pattern: String,
     pub regex: Regex,
+    pub case_sensitive: bool,
 }
 
 impl GlobRule {
     pub fn new(pattern: impl AsRef<str>) -> Result<Self, regex::Error> {
-        let pattern_lc = pattern.as_ref().to_ascii_lowercase();
-        let escaped = regex::escape(&pattern_lc);
+        Self::with_case(pattern, false)
+    }
+
+    /// Без `case_sensitive` шаблон приводится к нижнему регистру и сравнивается с `path_lc`.
+    pub fn with_case(pattern: impl AsRef<str>, case_sensitive: bool) -> Result<Self, regex::Error> {
+        let pattern = pattern.as_ref();
+        let pattern_cased = if case_sensitive { pattern.to_string() } else { pattern.to_ascii_lowercase() };
+        let escaped = regex::escape(&pattern_cased);
         let regex_str = escaped.replace("\\*", ".*").replace("\\?", ".");
         let final_pattern = format!("^{}$", regex_str);
         Ok(Self {
+            pattern: pattern.to_string(),
             regex: Regex::new(&final_pattern)?,
+            case_sensitive,
         })
     }
 }
 
+/// Строка условия пути: исходная и в нижнем регистре. По умолчанию сравнивается
+/// с `path_lc`, с `case_sensitive` - исходная с путем как есть.
+#[derive(Debug, Clone)]
+pub struct Text {
+    pub value: String,
+    pub value_lc: String,
+    pub case_sensitive: bool,
+}
+
+impl Text {
+    fn new(value: String) -> Self {
+        Text { value_lc: value.to_ascii_lowercase(), value, case_sensitive: false }
+    }
+
+    /// Пара (образец, путь) для сравнения с учетом регистра.
+    fn pick<'a>(&'a self, subject: &Subject<'a>) -> (&'a str, &'a str) {
+        if self.case_sensitive { (&self.value, subject.path) } else { (&self.value_lc, subject.path_lc) }
+    }
+}
+
+/// Сравнение размера в правиле `size`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SizeOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// Метка $STANDARD_INFORMATION для правила по времени.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeField {
+    Created,
+    Modified,
+    MftModified,
+    Accessed,
+}
+
+/// Признак записи для правила-флага.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryFlag {
+    HasAds,
+    Deleted,
+    Directory,
+    Timestomped,
+}
+
+/// То, что проверяют правила: путь (как есть и в нижнем регистре) и метаданные записи.
+#[derive(Debug, Clone, Default)]
+pub struct Subject<'a> {
+    pub path: &'a str,
+    pub path_lc: &'a str,
+    pub size: u64,
+    pub created: Option<DateTime<Utc>>,
+    pub modified: Option<DateTime<Utc>>,
+    pub mft_modified: Option<DateTime<Utc>>,
+    pub accessed: Option<DateTime<Utc>>,
+    pub has_ads: bool,
+    pub deleted: bool,
+    pub directory: bool,
+    pub timestomped: bool,
+}
+
 #[derive(Debug, Clone)]
 pub enum Rule {
     Matches(GlobRule),
-    StartsWith(String),
-    EndsWith(String),
-    Contains(String),
+    StartsWith(Text),
+    EndsWith(Text),
+    Contains(Text),
+    Size(SizeOp, u64),
+    /// Метка в полуинтервале [from, to); границы необязательны
+    Time(TimeField, Option<DateTime<Utc>>, Option<DateTime<Utc>>),
+    Flag(EntryFlag),
     And(Box<Rule>, Box<Rule>),
+    Or(Box<Rule>, Box<Rule>),
     Not(Box<Rule>),
 }
 
@@ -34,39 +115,159 @@ impl Rule {
     }
 
     pub fn starts_with(s: impl Into<String>) -> Self {
-        Rule::StartsWith(s.into().to_ascii_lowercase())
+        Rule::StartsWith(Text::new(s.into()))
     }
 
     pub fn ends_with(s: impl Into<String>) -> Self {
-        Rule::EndsWith(s.into().to_ascii_lowercase())
+        Rule::EndsWith(Text::new(s.into()))
     }
 
     pub fn contains(s: impl Into<String>) -> Self {
-        Rule::Contains(s.into().to_ascii_lowercase())
+        Rule::Contains(Text::new(s.into()))
+    }
+
+    /// `"> 50MB"`, `"<= 4096"`, `"0"`: оператор (по умолчанию `=`) и размер с
+    /// необязательной единицей B, KB, MB, GB, TB (по 1024).
+    pub fn size(expr: &str) -> Result<Self, String> {
+        let expr = expr.trim();
+        let (op, rest) = [(">=", SizeOp::Ge), ("<=", SizeOp::Le), ("!=", SizeOp::Ne), (">", SizeOp::Gt), ("<", SizeOp::Lt), ("=", SizeOp::Eq)]
+            .into_iter()
+            .find_map(|(token, op)| expr.strip_prefix(token).map(|rest| (op, rest)))
+            .unwrap_or((SizeOp::Eq, expr));
+        let rest = rest.trim();
+        let split = rest.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(rest.len());
+        let (number, unit) = rest.split_at(split);
+        let number: f64 = number.parse().map_err(|_| format!("размер {}: ожидается число", expr))?;
+        let multiplier: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+            "" | "B" => 1,
+            "KB" | "K" | "KIB" => 1 << 10,
+            "MB" | "M" | "MIB" => 1 << 20,
+            "GB" | "G" | "GIB" => 1 << 30,
+            "TB" | "T" | "TIB" => 1 << 40,
+            other => return Err(format!("размер {}: неизвестная единица {}", expr, other)),
+        };
+        Ok(Rule::Size(op, (number * multiplier as f64) as u64))
+    }
+
+    /// `"2024-01-01..2024-02-01"`: метка в [начало, конец); любая граница может быть
+    /// опущена (`"2024-01-01.."`), границы - даты (полночь UTC) или метки RFC 3339.
+    pub fn time(field: TimeField, range: &str) -> Result<Self, String> {
+        let (from, to) = range.split_once("..").ok_or_else(|| format!("диапазон {}: ожидается НАЧАЛО..КОНЕЦ", range))?;
+        let bound = |s: &str| -> Result<Option<DateTime<Utc>>, String> {
+            let s = s.trim();
+            if s.is_empty() { return Ok(None); }
+            if let Ok(t) = DateTime::parse_from_rfc3339(s) { return Ok(Some(t.with_timezone(&Utc))); }
+            NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                .map(|d| Some(d.and_hms_opt(0, 0, 0).expect("полночь").and_utc()))
+                .map_err(|_| format!("диапазон {}: {} - не дата и не RFC 3339", range, s))
+        };
+        let (from, to) = (bound(from)?, bound(to)?);
+        if from.is_none() && to.is_none() {
+            return Err(format!("диапазон {}: нет ни одной границы", range));
+        }
+        if let (Some(f), Some(t)) = (from, to) {
+            if f >= t { return Err(format!("диапазон {}: начало не раньше конца", range)); }
+        }
+        Ok(Rule::Time(field, from, to))
+    }
+
+    pub fn flag(flag: EntryFlag) -> Self {
+        Rule::Flag(flag)
     }
 
     pub fn and(self, other: Rule) -> Self {
         Rule::And(Box::new(self), Box::new(other))
     }
 
+    pub fn or(self, other: Rule) -> Self {
+        Rule::Or(Box::new(self), Box::new(other))
+    }
+
+    #[allow(clippy::should_implement_trait)]
     pub fn not(self) -> Self {
         Rule::Not(Box::new(self))
     }
 
-    /// Быстрая проверка - вход уже в нижнем регистре.
-    pub fn check_lowered(&self, input_lc: &str) -> bool {
+    /// Условия пути во всем дереве сравниваются с учетом регистра (например,
+    /// имена записок вымогателей в смешанном регистре); остальные не меняются.
+    pub fn case_sensitive(self) -> Self {
+        match self {
+            Rule::Matches(g) if !g.case_sensitive => {
+                Rule::Matches(GlobRule::with_case(&g.pattern, true).expect("шаблон уже компилировался"))
+            }
+            Rule::StartsWith(t) => Rule::StartsWith(Text { case_sensitive: true, ..t }),
+            Rule::EndsWith(t) => Rule::EndsWith(Text { case_sensitive: true, ..t }),
+            Rule::Contains(t) => Rule::Contains(Text { case_sensitive: true, ..t }),
+            Rule::And(l, r) => l.case_sensitive().and(r.case_sensitive()),
+            Rule::Or(l, r) => l.case_sensitive().or(r.case_sensitive()),
+            Rule::Not(inner) => inner.case_sensitive().not(),
+            other => other,
+        }
+    }
+
+    /// Подстроки `path_lc`, хотя бы одна из которых обязана встретиться, если правило
+    /// совпало; None - такого набора нет (отрицание, метаданные, glob без литералов).
+    pub fn required_literals(&self) -> Option<Vec<String>> {
+        let nonempty = |s: &str| (!s.is_empty()).then(|| vec![s.to_string()]);
+        match self {
+            Rule::StartsWith(t) | Rule::EndsWith(t) | Rule::Contains(t) => nonempty(&t.value_lc),
+            Rule::Matches(g) => g.pattern.split(['*', '?']).max_by_key(|s| s.len()).and_then(|s| nonempty(&s.to_ascii_lowercase())),
+            // Для И достаточно одной стороны - берем ту, где самый короткий литерал длиннее
+            Rule::And(l, r) => match (l.required_literals(), r.required_literals()) {
+                (Some(a), Some(b)) => {
+                    let shortest = |v: &Vec<String>| v.iter().map(String::len).min().unwrap_or(0);
+                    Some(if shortest(&b) > shortest(&a) { b } else { a })
+                }
+                (a, b) => a.or(b),
+            },
+            Rule::Or(l, r) => {
+                let mut literals = l.required_literals()?;
+                literals.extend(r.required_literals()?);
+                Some(literals)
+            }
+            Rule::Size(..) | Rule::Time(..) | Rule::Flag(_) | Rule::Not(_) => None,
+        }
+    }
+
+    pub fn matches(&self, subject: &Subject) -> bool {
         match self {
-            Rule::StartsWith(s) => input_lc.starts_with(s),
-            Rule::EndsWith(s) => input_lc.ends_with(s),
-            Rule::Contains(s) => input_lc.contains(s),
-            Rule::Matches(g) => g.regex.is_match(input_lc),
-            Rule::And(l, r) => l.check_lowered(input_lc) && r.check_lowered(input_lc),
-            Rule::Not(inner) => !inner.check_lowered(input_lc),
+            Rule::StartsWith(t) => { let (s, path) = t.pick(subject); path.starts_with(s) }
+            Rule::EndsWith(t) => { let (s, path) = t.pick(subject); path.ends_with(s) }
+            Rule::Contains(t) => { let (s, path) = t.pick(subject); path.contains(s) }
+            Rule::Matches(g) => g.regex.is_match(if g.case_sensitive { subject.path } else { subject.path_lc }),
+            Rule::Size(op, value) => match op {
+                SizeOp::Eq => subject.size == *value,
+                SizeOp::Ne => subject.size != *value,
+                SizeOp::Lt => subject.size < *value,
+                SizeOp::Le => subject.size <= *value,
+                SizeOp::Gt => subject.size > *value,
+                SizeOp::Ge => subject.size >= *value,
+            },
+            Rule::Time(field, from, to) => {
+                let time = match field {
+                    TimeField::Created => subject.created,
+                    TimeField::Modified => subject.modified,
+                    TimeField::MftModified => subject.mft_modified,
+                    TimeField::Accessed => subject.accessed,
+                };
+                time.is_some_and(|t| from.is_none_or(|f| t >= f) && to.is_none_or(|e| t < e))
+            }
+            Rule::Flag(flag) => match flag {
+                EntryFlag::HasAds => subject.has_ads,
+                EntryFlag::Deleted => subject.deleted,
+                EntryFlag::Directory => subject.directory,
+                EntryFlag::Timestomped => subject.timestomped,
+            },
+            Rule::And(l, r) => l.matches(subject) && r.matches(subject),
+            Rule::Or(l, r) => l.matches(subject) || r.matches(subject),
+            Rule::Not(inner) => !inner.matches(subject),
         }
     }
 
+    /// Проверка только по пути (метаданные пустые).
     #[allow(dead_code)]
     pub fn check(&self, input: &str) -> bool {
-        self.check_lowered(&input.to_ascii_lowercase())
+        let path_lc = input.to_ascii_lowercase();
+        self.matches(&Subject { path: input, path_lc: &path_lc, ..Default::default() })
     }
 }
\ No newline at end of file
@@ -0,0 +1,539 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use aho_corasick::AhoCorasick;
+use serde::Deserialize;
+
+use super::rules::{EntryFlag, Rule, Subject, TimeField};
+use super::{profiles, sigma};
+use crate::i18n::rules as msg;
+use crate::models::{RuleMatch, Severity};
+
+/// Правило с именем, важностью и метками - совпавшие попадают в RuleMatches.
+#[derive(Debug, Clone)]
+pub struct NamedRule {
+    pub name: String,
+    pub description: Option<String>,
+    pub severity: Severity,
+    pub tags: Vec<String>,
+    pub rule: Rule,
+}
+
+impl NamedRule {
+    pub fn new(name: &str, severity: Severity, rule: Rule) -> Self {
+        NamedRule { name: name.to_string(), description: None, severity, tags: Vec::new(), rule }
+    }
+
+    /// Условия пути правила - с учетом регистра.
+    #[allow(dead_code)]
+    pub fn case_sensitive(mut self) -> Self {
+        self.rule = self.rule.case_sensitive();
+        self
+    }
+
+    pub fn to_match(&self) -> RuleMatch {
+        RuleMatch { name: self.name.clone(), severity: self.severity, tags: self.tags.clone() }
+    }
+}
+
+/// Набор правил с предфильтром: общий автомат Aho-Corasick по обязательным подстрокам
+/// правил отсекает записи, которые не могут совпасть, и правила проверяются только
+/// для кандидатов. Правила без обязательных подстрок проверяются всегда.
+#[derive(Debug, Clone, Default)]
+pub struct RuleSet {
+    rules: Vec<NamedRule>,
+    prefilter: Option<AhoCorasick>,
+    /// Номер литерала автомата -> правила, которым он нужен
+    literal_rules: Vec<Vec<usize>>,
+    always: Vec<usize>,
+}
+
+impl RuleSet {
+    pub fn new(rules: Vec<NamedRule>) -> Self {
+        let mut literals: Vec<String> = Vec::new();
+        let mut literal_index: HashMap<String, usize> = HashMap::new();
+        let mut literal_rules: Vec<Vec<usize>> = Vec::new();
+        let mut always = Vec::new();
+        for (idx, rule) in rules.iter().enumerate() {
+            let Some(required) = rule.rule.required_literals() else {
+                always.push(idx);
+                continue;
+            };
+            for literal in required {
+                let pos = *literal_index.entry(literal.clone()).or_insert_with(|| {
+                    literals.push(literal);
+                    literal_rules.push(Vec::new());
+                    literals.len() - 1
+                });
+                if !literal_rules[pos].contains(&idx) {
+                    literal_rules[pos].push(idx);
+                }
+            }
+        }
+        let prefilter = match AhoCorasick::new(&literals) {
+            Ok(ac) if !literals.is_empty() => Some(ac),
+            Ok(_) => None,
+            // Автомат не собрался - проверяем все правила без предфильтра
+            Err(_) => {
+                always = (0..rules.len()).collect();
+                literal_rules.clear();
+                None
+            }
+        };
+        RuleSet { rules, prefilter, literal_rules, always }
+    }
+
+    /// Совпавшие правила в порядке набора.
+    pub fn matching<'a>(&'a self, subject: &Subject) -> Vec<&'a NamedRule> {
+        let mut candidates = vec![false; self.rules.len()];
+        for &idx in &self.always {
+            candidates[idx] = true;
+        }
+        if let Some(prefilter) = &self.prefilter {
+            for found in prefilter.find_overlapping_iter(subject.path_lc) {
+                for &idx in &self.literal_rules[found.pattern().as_usize()] {
+                    candidates[idx] = true;
+                }
+            }
+        }
+        self.rules.iter().zip(candidates).filter(|(rule, candidate)| *candidate && rule.rule.matches(subject)).map(|(rule, _)| rule).collect()
+    }
+
+    pub fn any_match(&self, subject: &Subject) -> bool {
+        !self.matching(subject).is_empty()
+    }
+}
+
+/// Файл правил (--rules): список `rules`, у каждого имя, необязательные описание,
+/// важность (medium) и метки, и условия; условия одного уровня объединяются через И.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleFile {
+    rules: Vec<RuleSpec>,
+}
+
+#[derive(Deserialize)]
+#[serde(try_from = "Spec")]
+struct RuleSpec(NamedRule);
+
+/// Вложенное условие (`not`, элементы `all`) - те же ключи, но без имени, описания,
+/// важности и меток.
+#[derive(Deserialize)]
+#[serde(try_from = "Spec")]
+struct Condition(Rule);
+
+/// Ключи правила и условия. Ошибки проверки возвращаются из десериализации,
+/// поэтому парсер файла дописывает к ним строку и столбец.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Spec {
+    name: Option<String>,
+    description: Option<String>,
+    severity: Option<Severity>,
+    tags: Option<Vec<String>>,
+    /// Условия пути этого правила (и вложенных) - с учетом регистра
+    case_sensitive: Option<bool>,
+    glob: Option<String>,
+    starts_with: Option<String>,
+    ends_with: Option<String>,
+    contains: Option<String>,
+    size: Option<String>,
+    created: Option<String>,
+    modified: Option<String>,
+    mft_modified: Option<String>,
+    accessed: Option<String>,
+    has_ads: Option<bool>,
+    is_deleted: Option<bool>,
+    is_directory: Option<bool>,
+    timestomped: Option<bool>,
+    not: Option<Box<Condition>>,
+    all: Option<Vec<Condition>>,
+    any: Option<Vec<Condition>>,
+}
+
+impl Spec {
+    fn condition(self) -> Result<Rule, String> {
+        let case_sensitive = self.case_sensitive.unwrap_or(false);
+        let mut parts = Vec::new();
+        if let Some(pattern) = self.glob {
+            if pattern.is_empty() { return Err("пустой glob".into()); }
+            parts.push(Rule::glob(&pattern).map_err(|e| format!("glob {}: {}", pattern, e))?);
+        }
+        for (key, value, make) in [
+            ("starts_with", self.starts_with, Rule::starts_with as fn(String) -> Rule),
+            ("ends_with", self.ends_with, Rule::ends_with),
+            ("contains", self.contains, Rule::contains),
+        ] {
+            if let Some(value) = value {
+                if value.is_empty() { return Err(format!("пустое значение {}", key)); }
+                parts.push(make(value));
+            }
+        }
+        if let Some(size) = self.size {
+            parts.push(Rule::size(&size)?);
+        }
+        for (field, range) in [
+            (TimeField::Created, self.created),
+            (TimeField::Modified, self.modified),
+            (TimeField::MftModified, self.mft_modified),
+            (TimeField::Accessed, self.accessed),
+        ] {
+            if let Some(range) = range {
+                parts.push(Rule::time(field, &range)?);
+            }
+        }
+        for (flag, value) in [
+            (EntryFlag::HasAds, self.has_ads),
+            (EntryFlag::Deleted, self.is_deleted),
+            (EntryFlag::Directory, self.is_directory),
+            (EntryFlag::Timestomped, self.timestomped),
+        ] {
+            match value {
+                Some(true) => parts.push(Rule::flag(flag)),
+                Some(false) => parts.push(Rule::flag(flag).not()),
+                None => {}
+            }
+        }
+        if let Some(inner) = self.not {
+            parts.push(inner.0.not());
+        }
+        if let Some(all) = self.all {
+            if all.is_empty() { return Err("пустой список all".into()); }
+            parts.extend(all.into_iter().map(|c| c.0));
+        }
+        if let Some(any) = self.any {
+            let any = any.into_iter().map(|c| c.0).reduce(Rule::or).ok_or("пустой список any")?;
+            parts.push(any);
+        }
+        let rule = parts.into_iter().reduce(Rule::and).ok_or_else(|| {
+            "нет ни одного условия (glob, starts_with, ends_with, contains, size, created, modified, \
+             mft_modified, accessed, has_ads, is_deleted, is_directory, timestomped, not, all, any)".to_string()
+        })?;
+        Ok(if case_sensitive { rule.case_sensitive() } else { rule })
+    }
+}
+
+impl TryFrom<Spec> for Condition {
+    type Error = String;
+
+    fn try_from(spec: Spec) -> Result<Self, String> {
+        if spec.name.is_some() || spec.description.is_some() || spec.severity.is_some() || spec.tags.is_some() {
+            return Err("name, description, severity и tags допустимы только у правила, не у вложенного условия".into());
+        }
+        spec.condition().map(Condition)
+    }
+}
+
+impl TryFrom<Spec> for RuleSpec {
+    type Error = String;
+
+    fn try_from(mut spec: Spec) -> Result<Self, String> {
+        let name = spec.name.take().map(|n| n.trim().to_string()).unwrap_or_default();
+        if name.is_empty() { return Err("у правила нет имени (name)".into()); }
+        let description = spec.description.take();
+        let severity = spec.severity.take().unwrap_or_default();
+        let tags = spec.tags.take().unwrap_or_default();
+        let rule = spec.condition().map_err(|e| format!("правило {}: {}", name, e))?;
+        Ok(RuleSpec(NamedRule { name, description, severity, tags, rule }))
+    }
+}
+
+/// Загружает правила из TOML (`.toml`) или YAML (`.yaml`, `.yml`). Ошибки разбора
+/// и проверки содержат строку и столбец в файле.
+pub fn load(path: &str) -> Result<Vec<NamedRule>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let ext = Path::new(path).extension().and_then(|e| e.to_str()).unwrap_or_default().to_ascii_lowercase();
+    match ext.as_str() {
+        "toml" => parse_toml(&text),
+        "yaml" | "yml" => parse_yaml(&text),
+        _ => Err("неизвестный формат файла правил: ожидается .toml, .yaml или .yml".into()),
+    }
+}
+
+pub fn parse_toml(text: &str) -> Result<Vec<NamedRule>, String> {
+    let file: RuleFile = toml::from_str(text)
+        .map_err(|e| locate_toml_error(text, &e).unwrap_or_else(|| e.to_string().trim_end().to_string()))?;
+    validate(file)
+}
+
+/// Позиции правил TOML-файла: массив и каждый его элемент.
+#[derive(Deserialize)]
+struct TomlSpans {
+    rules: toml::Spanned<Vec<toml::Spanned<toml::Value>>>,
+}
+
+/// Ошибку проверки правила (`TryFrom<Spec>`) toml относит ко всему массиву `rules` -
+/// к первому `[[rules]]`. Тогда правило с ошибкой ищется по одному и строка берется
+/// от его начала. Синтаксические ошибки и неизвестные ключи уже указывают точное место.
+fn locate_toml_error(text: &str, error: &toml::de::Error) -> Option<String> {
+    let spans: TomlSpans = toml::from_str(text).ok()?;
+    if error.span()?.start != spans.rules.span().start {
+        return None;
+    }
+    spans.rules.into_inner().into_iter().find_map(|rule| {
+        let start = rule.span().start;
+        let line = text[..start].matches('\n').count() + 1;
+        let column = text[..start].rsplit('\n').next().unwrap_or_default().chars().count() + 1;
+        let error = rule.into_inner().try_into::<RuleSpec>().err()?;
+        Some(format!("TOML parse error at line {}, column {}\n{}", line, column, error.message()))
+    })
+}
+
+pub fn parse_yaml(text: &str) -> Result<Vec<NamedRule>, String> {
+    let file: RuleFile = serde_yaml::from_str(text).map_err(|e| e.to_string())?;
+    validate(file)
+}
+
+fn validate(file: RuleFile) -> Result<Vec<NamedRule>, String> {
+    let rules: Vec<NamedRule> = file.rules.into_iter().map(|r| r.0).collect();
+    if rules.is_empty() {
+        return Err("в файле нет ни одного правила".into());
+    }
+    for (i, rule) in rules.iter().enumerate() {
+        if rules[..i].iter().any(|r| r.name.eq_ignore_ascii_case(&rule.name)) {
+            return Err(format!("повторяется имя правила {}", rule.name));
+        }
+    }
+    Ok(rules)
+}
+
+/// Добавляет `overrides` к `rules`: правило с тем же именем (без учета регистра) заменяется.
+pub fn merge(rules: &mut Vec<NamedRule>, overrides: Vec<NamedRule>) {
+    for rule in overrides {
+        match rules.iter_mut().find(|r| r.name.eq_ignore_ascii_case(&rule.name)) {
+            Some(existing) => *existing = rule,
+            None => rules.push(rule),
+        }
+    }
+}
+
+/// Источники правил parse: вшитые профили, файл правил, Sigma и исключения.
+#[derive(Debug, Clone, Default)]
+pub struct RuleSources {
+    pub profiles: Vec<String>,
+    pub rules: Option<String>,
+    pub sigma: Vec<String>,
+    pub exclude: Option<String>,
+}
+
+/// Собранный набор: правила, исключения и строки журнала загрузки.
+pub struct LoadedRules {
+    pub rules: Vec<NamedRule>,
+    pub excludes: Vec<NamedRule>,
+    /// Ход загрузки для вывода (`[*] ...`)
+    pub log: Vec<String>,
+    /// Пропущенные Sigma-правила (`[!] ...`)
+    pub warnings: Vec<String>,
+}
+
+impl RuleSources {
+    /// Без профилей и файла правил - встроенные; файл с профилями дополняет их
+    /// и заменяет одноименные, Sigma добавляется к итогу.
+    pub fn load(&self) -> Result<LoadedRules, String> {
+        let mut log = Vec::new();
+        let mut warnings = Vec::new();
+        let mut rules = if !self.profiles.is_empty() {
+            let rules = profiles::load(&self.profiles)?;
+            log.push(msg::profiles(self.profiles.join(", "), rules.len()));
+            rules
+        } else if self.rules.is_some() {
+            Vec::new()
+        } else {
+            profiles::default_rules()
+        };
+        if let Some(rules_path) = &self.rules {
+            let file_rules = load(rules_path).map_err(|e| msg::rules_file_error(rules_path, e))?;
+            log.push(msg::rules_from(rules_path, file_rules.len()));
+            for rule in &file_rules {
+                log.push(format!("    {} [{:?}]{}", rule.name, rule.severity, rule.description.as_deref().map(|d| format!(" - {}", d)).unwrap_or_default()));
+            }
+            merge(&mut rules, file_rules);
+        }
+        let excludes = match &self.exclude {
+            Some(exclude_path) => {
+                let excludes = load(exclude_path).map_err(|e| msg::excludes_file_error(exclude_path, e))?;
+                log.push(msg::excludes_from(exclude_path, excludes.len()));
+                excludes
+            }
+            None => Vec::new(),
+        };
+        for sigma_path in &self.sigma {
+            let import = sigma::load(sigma_path).map_err(msg::sigma_failed)?;
+            warnings.extend(import.skipped.iter().map(msg::sigma_skipped));
+            log.push(msg::sigma_from(sigma_path, import.rules.len(), import.skipped.len(), import.other_category));
+            rules.extend(import.rules);
+        }
+        Ok(LoadedRules { rules, excludes, log, warnings })
+    }
+
+    /// Отпечаток файлов правил (путь, время изменения, размер): изменился -
+    /// набор пора перезагрузить. Каталоги Sigma обходятся рекурсивно.
+    pub fn fingerprint(&self) -> Vec<(PathBuf, Option<SystemTime>, u64)> {
+        fn walk(path: &Path, out: &mut Vec<(PathBuf, Option<SystemTime>, u64)>) {
+            let Ok(meta) = std::fs::metadata(path) else {
+                out.push((path.to_path_buf(), None, 0));
+                return;
+            };
+            if meta.is_dir() {
+                let mut children: Vec<PathBuf> = std::fs::read_dir(path).into_iter().flatten().flatten().map(|e| e.path()).collect();
+                children.sort();
+                children.iter().for_each(|child| walk(child, out));
+            } else {
+                out.push((path.to_path_buf(), meta.modified().ok(), meta.len()));
+            }
+        }
+        let mut out = Vec::new();
+        for path in self.rules.iter().chain(&self.sigma).chain(&self.exclude) {
+            walk(Path::new(path), &mut out);
+        }
+        out
+    }
+
+    /// Есть ли что перезагружать: профили вшиты, встроенные правила не меняются.
+    pub fn has_files(&self) -> bool {
+        self.rules.is_some() || self.exclude.is_some() || !self.sigma.is_empty()
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn subject(path: &str) -> (String, String) {
+        (path.to_string(), path.to_lowercase())
+    }
+
+    fn names<'a>(set: &'a RuleSet, path: &(String, String), size: u64, deleted: bool) -> Vec<&'a str> {
+        let subject = Subject {
+            path: &path.0, path_lc: &path.1, size,
+            created: None, modified: None, mft_modified: None, accessed: None,
+            has_ads: false, deleted, directory: false, timestomped: false,
+        };
+        set.matching(&subject).into_iter().map(|r| r.name.as_str()).collect()
+    }
+
+    const TOML_RULES: &str = r#"
+[[rules]]
+name = "exe in temp"
+severity = "high"
+tags = ["attack.t1105"]
+glob = "*\\temp\\*.exe"
+size = "> 1 KB"
+
+[[rules]]
+name = "deleted script"
+is_deleted = true
+any = [{ ends_with = ".ps1" }, { ends_with = ".vbs" }]
+"#;
+
+    #[test]
+    fn toml_rules_match() {
+        let rules = parse_toml(TOML_RULES).unwrap();
+        assert_eq!(rules[0].severity, Severity::High);
+        assert_eq!(rules[0].tags, ["attack.t1105"]);
+        assert_eq!(rules[1].severity, Severity::Medium);
+        let set = RuleSet::new(rules);
+        let exe = subject("C:\\Users\\x\\AppData\\Local\\Temp\\Drop.EXE");
+        assert_eq!(names(&set, &exe, 4096, false), ["exe in temp"]);
+        assert!(names(&set, &exe, 100, false).is_empty());
+        let script = subject("C:\\Users\\x\\run.vbs");
+        assert_eq!(names(&set, &script, 10, true), ["deleted script"]);
+        assert!(names(&set, &script, 10, false).is_empty());
+    }
+
+    #[test]
+    fn yaml_rules_match() {
+        let yaml = "rules:\n  - name: Case\n    case_sensitive: true\n    contains: '\\Windows\\'\n    not:\n      ends_with: .dll\n";
+        let set = RuleSet::new(parse_yaml(yaml).unwrap());
+        assert_eq!(names(&set, &subject("C:\\Windows\\notepad.exe"), 0, false), ["Case"]);
+        assert!(names(&set, &subject("C:\\WINDOWS\\notepad.exe"), 0, false).is_empty());
+        assert!(names(&set, &subject("C:\\Windows\\k.dll"), 0, false).is_empty());
+    }
+
+    #[test]
+    fn toml_errors_point_at_the_rule() {
+        // Ошибка проверки второго правила - строка его [[rules]], а не первого
+        let text = "[[rules]]\nname = \"a\"\nglob = \"*.exe\"\n\n[[rules]]\nname = \"b\"\nsize = \"huge\"\n";
+        assert_eq!(parse_toml(text).unwrap_err(), "TOML parse error at line 5, column 1\nправило b: размер huge: ожидается число");
+
+        let text = "rules = [\n  { name = \"a\", glob = \"x\" },\n  { name = \"b\", created = \"..\" },\n]\n";
+        assert_eq!(parse_toml(text).unwrap_err(), "TOML parse error at line 3, column 3\nправило b: диапазон ..: нет ни одной границы");
+
+        let text = "[[rules]]\nname = \"a\"\nglob = \"*.exe\"\ncolour = \"red\"\n";
+        assert!(parse_toml(text).unwrap_err().starts_with("TOML parse error at line 4, column 1\n"));
+
+        let text = "[[rules]]\nname = \"a\"\nglob = \"*.exe\n";
+        assert!(parse_toml(text).unwrap_err().starts_with("TOML parse error at line 3, column "));
+    }
+
+    #[test]
+    fn yaml_errors_point_at_the_rule() {
+        let yaml = "rules:\n  - name: a\n    glob: '*.exe'\n  - name: b\n    not:\n      name: c\n      glob: x\n";
+        let err = parse_yaml(yaml).unwrap_err();
+        assert!(err.contains("допустимы только у правила") && err.ends_with("at line 4 column 5"), "{}", err);
+
+        let yaml = "rules:\n  - name: a\n    created: 'yesterday..'\n";
+        let err = parse_yaml(yaml).unwrap_err();
+        assert!(err.contains("правило a: диапазон yesterday..") && err.ends_with("at line 2 column 3"), "{}", err);
+    }
+
+    #[test]
+    fn file_level_errors() {
+        assert_eq!(parse_yaml("rules: []\n").unwrap_err(), "в файле нет ни одного правила");
+        let text = "[[rules]]\nname = \"a\"\nglob = \"x\"\n\n[[rules]]\nname = \"A\"\nglob = \"y\"\n";
+        assert_eq!(parse_toml(text).unwrap_err(), "повторяется имя правила A");
+        let text = "[[rules]]\nname = \" \"\nglob = \"x\"\n";
+        assert!(parse_toml(text).unwrap_err().ends_with("у правила нет имени (name)"));
+    }
+
+    #[test]
+    fn merge_replaces_by_name() {
+        let mut rules = parse_toml(TOML_RULES).unwrap();
+        let overrides = parse_yaml("rules:\n  - name: EXE IN TEMP\n    severity: low\n    glob: '*.exe'\n  - name: new\n    is_directory: true\n").unwrap();
+        merge(&mut rules, overrides);
+        let summary: Vec<_> = rules.iter().map(|r| (r.name.as_str(), r.severity)).collect();
+        assert_eq!(summary, [("EXE IN TEMP", Severity::Low), ("deleted script", Severity::Medium), ("new", Severity::Medium)]);
+    }
+
+    #[test]
+    fn prefilter_splits_rules_by_required_literals() {
+        let glob = |p: &str| Rule::glob(p).unwrap();
+        let rules = vec![
+            NamedRule::new("glob", Severity::Low, glob("*\\temp\\*.exe")),
+            NamedRule::new("either", Severity::Low, Rule::ends_with(".ps1").or(Rule::contains("\\startup\\"))),
+            NamedRule::new("both", Severity::Low, Rule::contains("\\x").and(Rule::ends_with(".dll"))),
+            NamedRule::new("size", Severity::Low, Rule::size("> 1 MB").unwrap()),
+            NamedRule::new("or any", Severity::Low, Rule::contains("evil").or(Rule::flag(EntryFlag::Deleted))),
+            NamedRule::new("not", Severity::Low, Rule::contains("windows").not()),
+            NamedRule::new("case", Severity::Low, Rule::contains("\\Users\\")).case_sensitive(),
+        ];
+        let set = RuleSet::new(rules.clone());
+        // Без обязательных подстрок: размер, ИЛИ с флагом, отрицание
+        assert_eq!(set.always, [3, 4, 5]);
+        // Самый длинный литерал шаблона, обе стороны ИЛИ, у И - сторона с более длинным литералом
+        let required = |i: usize| rules[i].rule.required_literals().unwrap();
+        assert_eq!(required(0), ["\\temp\\"]);
+        assert_eq!(required(1), [".ps1", "\\startup\\"]);
+        assert_eq!(required(2), [".dll"]);
+        assert_eq!(required(6), ["\\users\\"]);
+        assert_eq!(set.literal_rules, [vec![0], vec![1], vec![1], vec![2], vec![6]]);
+
+        // Кандидаты предфильтра не теряют совпадений полной проверки
+        let paths = [
+            "C:\\Temp\\a.exe", "C:\\Users\\x\\b.ps1", "C:\\ProgramData\\Startup\\c.lnk", "C:\\x\\y.dll",
+            "C:\\Windows\\evil.txt", "C:\\users\\z", "C:\\Users\\z", "D:\\plain.txt",
+        ];
+        for path in paths {
+            for (size, deleted) in [(10, false), (2 << 20, true)] {
+                let path = subject(path);
+                let expected: Vec<&str> = rules.iter()
+                    .filter(|r| r.rule.matches(&Subject { path: &path.0, path_lc: &path.1, size, deleted, ..Default::default() }))
+                    .map(|r| r.name.as_str())
+                    .collect();
+                assert_eq!(names(&set, &path, size, deleted), expected, "{} {} {}", path.0, size, deleted);
+            }
+        }
+        assert_eq!(names(&set, &subject("C:\\Temp\\a.exe"), 10, false), ["glob", "not"]);
+        assert_eq!(names(&set, &subject("C:\\Users\\x\\b.ps1"), 10, false), ["either", "not", "case"]);
+    }
+}
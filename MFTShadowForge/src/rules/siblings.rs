@@ -0,0 +1,37 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+fn hash_str(s: &str) -> u64 {
+    let mut h = DefaultHasher::new();
+    s.hash(&mut h);
+    h.finish()
+}
+
+/// Индекс имен в каталогах для поиска соседей, различающихся только регистром.
+/// Хранит хеши вместо строк, чтобы не удваивать расход памяти на больших томах.
+#[derive(Debug, Default)]
+pub struct CaseCollisionIndex {
+    // (parent_entry, hash(lowercase name)) -> (hash(точное имя), есть коллизия)
+    names: HashMap<(u64, u64), (u64, bool)>,
+}
+
+impl CaseCollisionIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, parent_entry: u64, name: &str) {
+        let exact = hash_str(name);
+        let key = (parent_entry, hash_str(&name.to_lowercase()));
+        let slot = self.names.entry(key).or_insert((exact, false));
+        if slot.0 != exact { slot.1 = true; }
+    }
+
+    pub fn collides(&self, parent_entry: u64, name: &str) -> bool {
+        self.names
+            .get(&(parent_entry, hash_str(&name.to_lowercase())))
+            .map(|slot| slot.1)
+            .unwrap_or(false)
+    }
+}
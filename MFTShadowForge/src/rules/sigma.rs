@@ -0,0 +1,422 @@
+use std::path::Path;
+
+use serde::Deserialize;
+use serde_yaml::{Mapping, Value};
+
+use super::rules::Rule;
+use super::ruleset::NamedRule;
+use crate::models::Severity;
+
+/// Категории logsource, события которых - создание файлов (проверяемы по MFT).
+const FILE_CATEGORIES: &[&str] = &["file_event", "file_create"];
+/// Поле пути создаваемого файла в Sigma (Sysmon EventID 11).
+const PATH_FIELD: &str = "targetfilename";
+
+/// Итог загрузки Sigma: скомпилированные правила и пропущенные с причиной.
+#[derive(Debug, Default)]
+pub struct SigmaImport {
+    pub rules: Vec<NamedRule>,
+    /// Правила file_event, которые не удалось перевести (файл, заголовок, причина)
+    pub skipped: Vec<String>,
+    /// Правила других категорий logsource
+    pub other_category: usize,
+}
+
+/// Загружает Sigma-правила из файла или каталога (рекурсивно, `.yml`/`.yaml`).
+/// Берутся только категории file_event/file_create; условия по `TargetFilename`
+/// переводятся в дерево `Rule`, правило с любым другим полем пропускается целиком -
+/// отброшенный фильтр расширил бы совпадения.
+pub fn load(path: &str) -> Result<SigmaImport, String> {
+    let mut files = Vec::new();
+    collect_files(Path::new(path), &mut files).map_err(|e| format!("{}: {}", path, e))?;
+    files.sort();
+    let mut import = SigmaImport::default();
+    for file in files {
+        let label = file.display().to_string();
+        let text = match std::fs::read_to_string(&file) {
+            Ok(text) => text,
+            Err(e) => { import.skipped.push(format!("{}: {}", label, e)); continue; }
+        };
+        // Файл может содержать несколько документов через ---
+        for document in serde_yaml::Deserializer::from_str(&text) {
+            let doc = match Value::deserialize(document) {
+                Ok(doc) => doc,
+                Err(e) => { import.skipped.push(format!("{}: {}", label, e)); break; }
+            };
+            let Some(doc) = doc.as_mapping() else { continue };
+            let category = doc.get("logsource").and_then(|l| l.get("category")).and_then(Value::as_str).unwrap_or_default();
+            if !FILE_CATEGORIES.contains(&category) {
+                import.other_category += 1;
+                continue;
+            }
+            let title = doc.get("title").and_then(Value::as_str).unwrap_or("без заголовка").to_string();
+            match compile(doc) {
+                Ok(rule) => import.rules.push(NamedRule {
+                    name: title, description: describe(doc), severity: severity(doc), tags: tags(doc), rule,
+                }),
+                Err(e) => import.skipped.push(format!("{}: {}: {}", label, title, e)),
+            }
+        }
+    }
+    Ok(import)
+}
+
+fn collect_files(path: &Path, out: &mut Vec<std::path::PathBuf>) -> std::io::Result<()> {
+    if !path.is_dir() {
+        std::fs::metadata(path)?;
+        out.push(path.to_path_buf());
+        return Ok(());
+    }
+    for entry in std::fs::read_dir(path)? {
+        let entry = entry?.path();
+        if entry.is_dir() {
+            collect_files(&entry, out)?;
+        } else if entry.extension().and_then(|e| e.to_str()).is_some_and(|e| e.eq_ignore_ascii_case("yml") || e.eq_ignore_ascii_case("yaml")) {
+            out.push(entry);
+        }
+    }
+    Ok(())
+}
+
+/// Описание правила: id и level Sigma.
+fn describe(doc: &Mapping) -> Option<String> {
+    let field = |key: &str| doc.get(key).and_then(Value::as_str);
+    match (field("id"), field("level")) {
+        (None, None) => None,
+        (id, level) => Some(format!("sigma {}{}", id.unwrap_or("-"), level.map(|l| format!(" ({})", l)).unwrap_or_default())),
+    }
+}
+
+/// `level` правила; неизвестный или отсутствующий - medium.
+fn severity(doc: &Mapping) -> Severity {
+    doc.get("level").cloned().and_then(|l| serde_yaml::from_value(l).ok()).unwrap_or_default()
+}
+
+/// `tags` правила (attack.*, car.*, ...).
+fn tags(doc: &Mapping) -> Vec<String> {
+    doc.get("tags").and_then(Value::as_sequence)
+        .map(|tags| tags.iter().filter_map(Value::as_str).map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// `detection` правила -> Rule: именованные выборки и выражение `condition`.
+fn compile(doc: &Mapping) -> Result<Rule, String> {
+    let detection = doc.get("detection").and_then(Value::as_mapping).ok_or("нет detection")?;
+    let mut selections = Vec::new();
+    let mut conditions = Vec::new();
+    for (key, value) in detection {
+        let key = key.as_str().ok_or("нестроковый ключ в detection")?;
+        match key {
+            "condition" => match value {
+                Value::String(s) => conditions.push(s.clone()),
+                // Список условий - любое из них
+                Value::Sequence(list) => conditions.extend(list.iter().filter_map(Value::as_str).map(str::to_string)),
+                _ => return Err("condition - не строка".into()),
+            },
+            "timeframe" => return Err("timeframe не поддерживается".into()),
+            name => selections.push((name.to_string(), selection(value).map_err(|e| format!("{}: {}", name, e))?)),
+        }
+    }
+    if conditions.is_empty() {
+        return Err("нет condition".into());
+    }
+    let rules = conditions.iter().map(|c| Condition::parse(c, &selections)).collect::<Result<Vec<_>, _>>()?;
+    Ok(rules.into_iter().reduce(Rule::or).expect("условие есть"))
+}
+
+/// Выборка: map полей (И) или список map (ИЛИ).
+fn selection(value: &Value) -> Result<Rule, String> {
+    match value {
+        Value::Mapping(map) => {
+            let mut parts = Vec::new();
+            for (key, values) in map {
+                parts.push(field_rule(key.as_str().ok_or("нестроковое поле")?, values)?);
+            }
+            parts.into_iter().reduce(Rule::and).ok_or_else(|| "пустая выборка".into())
+        }
+        Value::Sequence(list) if list.iter().all(Value::is_mapping) && !list.is_empty() => {
+            let parts = list.iter().map(selection).collect::<Result<Vec<_>, _>>()?;
+            Ok(parts.into_iter().reduce(Rule::or).expect("список не пуст"))
+        }
+        _ => Err("поиск по ключевым словам не поддерживается".into()),
+    }
+}
+
+/// `TargetFilename|mod|mod: значение(я)`. Список значений - ИЛИ, с модификатором `all` - И.
+fn field_rule(key: &str, values: &Value) -> Result<Rule, String> {
+    let mut parts = key.split('|');
+    let field = parts.next().unwrap_or_default();
+    if !field.eq_ignore_ascii_case(PATH_FIELD) {
+        return Err(format!("поле {} не извлекается из MFT", field));
+    }
+    let mut mode = Match::Exact;
+    let mut all = false;
+    for modifier in parts {
+        match modifier {
+            "contains" => mode = Match::Contains,
+            "startswith" => mode = Match::StartsWith,
+            "endswith" => mode = Match::EndsWith,
+            "all" => all = true,
+            other => return Err(format!("модификатор {} не поддерживается", other)),
+        }
+    }
+    let values: Vec<&Value> = match values {
+        Value::Sequence(list) => list.iter().collect(),
+        value => vec![value],
+    };
+    let mut rules = Vec::new();
+    for value in values {
+        let value = match value {
+            Value::String(s) => s.clone(),
+            Value::Number(n) => n.to_string(),
+            _ => return Err(format!("значение {} не поддерживается", field)),
+        };
+        rules.push(value_rule(&value, mode)?);
+    }
+    let combine = if all { Rule::and } else { Rule::or };
+    rules.into_iter().reduce(combine).ok_or_else(|| format!("пустой список значений {}", field))
+}
+
+#[derive(Clone, Copy)]
+enum Match {
+    Exact,
+    Contains,
+    StartsWith,
+    EndsWith,
+}
+
+/// Значение Sigma -> Rule. `*` и `?` - подстановки, `\\` - один обратный слеш.
+/// Путь с буквой диска (`C:\Users\`) совпадает и с путем отчета без префикса диска.
+fn value_rule(value: &str, mode: Match) -> Result<Rule, String> {
+    let value = value.replace("\\\\", "\\");
+    if value.is_empty() {
+        return Err("пустое значение".into());
+    }
+    let rule = |value: &str| -> Result<Rule, String> {
+        let glob = |pattern: String| Rule::glob(pattern).map_err(|e| e.to_string());
+        let wildcard = value.contains(['*', '?']);
+        Ok(match mode {
+            Match::Exact => glob(value.to_string())?,
+            Match::Contains if wildcard => glob(format!("*{}*", value))?,
+            Match::Contains => Rule::contains(value),
+            Match::StartsWith if wildcard => glob(format!("{}*", value))?,
+            Match::StartsWith => Rule::starts_with(value),
+            Match::EndsWith if wildcard => glob(format!("*{}", value))?,
+            Match::EndsWith => Rule::ends_with(value),
+        })
+    };
+    let bytes = value.as_bytes();
+    let anchored = matches!(mode, Match::Exact | Match::StartsWith);
+    if anchored && bytes.len() >= 3 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' && bytes[2] == b'\\' {
+        return Ok(rule(&value)?.or(rule(&value[2..])?));
+    }
+    rule(&value)
+}
+
+/// Выражение `condition`:
+///
+/// ```text
+/// expr  := and ("or" and)*
+/// and   := unary ("and" unary)*
+/// unary := "not" unary | "(" expr ")" | ("1" | "any" | "all") "of" (имя* | "them") | имя
+/// ```
+struct Condition<'a> {
+    tokens: Vec<String>,
+    pos: usize,
+    selections: &'a [(String, Rule)],
+}
+
+impl<'a> Condition<'a> {
+    fn parse(input: &str, selections: &'a [(String, Rule)]) -> Result<Rule, String> {
+        if input.contains('|') {
+            return Err("агрегации в condition не поддерживаются".into());
+        }
+        let tokens = input.replace('(', " ( ").replace(')', " ) ").split_whitespace().map(str::to_string).collect();
+        let mut cond = Condition { tokens, pos: 0, selections };
+        let rule = cond.expr()?;
+        match cond.tokens.get(cond.pos) {
+            None => Ok(rule),
+            Some(token) => Err(format!("condition: лишнее {}", token)),
+        }
+    }
+
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.pos).map(String::as_str)
+    }
+
+    fn next(&mut self) -> Result<String, String> {
+        let token = self.tokens.get(self.pos).cloned().ok_or("condition обрывается")?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn keyword(&self, word: &str) -> bool {
+        self.peek().is_some_and(|t| t.eq_ignore_ascii_case(word))
+    }
+
+    fn expr(&mut self) -> Result<Rule, String> {
+        let mut rule = self.and()?;
+        while self.keyword("or") {
+            self.pos += 1;
+            rule = rule.or(self.and()?);
+        }
+        Ok(rule)
+    }
+
+    fn and(&mut self) -> Result<Rule, String> {
+        let mut rule = self.unary()?;
+        while self.keyword("and") {
+            self.pos += 1;
+            rule = rule.and(self.unary()?);
+        }
+        Ok(rule)
+    }
+
+    fn unary(&mut self) -> Result<Rule, String> {
+        let token = self.next()?;
+        match token.to_ascii_lowercase().as_str() {
+            "not" => Ok(self.unary()?.not()),
+            "(" => {
+                let rule = self.expr()?;
+                match self.next()?.as_str() {
+                    ")" => Ok(rule),
+                    other => Err(format!("condition: ожидалась ), найдено {}", other)),
+                }
+            }
+            quantifier @ ("1" | "any" | "all") if self.keyword("of") => {
+                self.pos += 1;
+                let pattern = self.next()?;
+                let matched: Vec<Rule> = self.selections.iter()
+                    .filter(|(name, _)| pattern == "them" || glob_name(&pattern, name))
+                    .map(|(_, rule)| rule.clone())
+                    .collect();
+                let combine = if quantifier == "all" { Rule::and } else { Rule::or };
+                matched.into_iter().reduce(combine).ok_or_else(|| format!("condition: нет выборок {}", pattern))
+            }
+            _ => self.selections.iter().find(|(name, _)| *name == token)
+                .map(|(_, rule)| rule.clone())
+                .ok_or_else(|| format!("condition: нет выборки {}", token)),
+        }
+    }
+}
+
+/// Имя выборки под шаблоном `selection_*` из `1 of`/`all of`.
+fn glob_name(pattern: &str, name: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => name.starts_with(prefix),
+        None => name == pattern,
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image::Fixture;
+    use crate::rules::ruleset::RuleSet;
+    use crate::rules::rules::Subject;
+
+    fn names<'a>(set: &'a RuleSet, path: &str) -> Vec<&'a str> {
+        let path_lc = path.to_lowercase();
+        let subject = Subject {
+            path, path_lc: &path_lc, size: 0,
+            created: None, modified: None, mft_modified: None, accessed: None,
+            has_ads: false, deleted: false, directory: false, timestomped: false,
+        };
+        set.matching(&subject).into_iter().map(|r| r.name.as_str()).collect()
+    }
+
+    fn compile_yaml(yaml: &str) -> Result<Rule, String> {
+        let doc: Value = serde_yaml::from_str(yaml).unwrap();
+        compile(doc.as_mapping().unwrap())
+    }
+
+    const DROP: &str = r"title: Drop in temp
+id: 5f1e7b52-0a3c-4c8e-9a4b-7d2f8e6c1a90
+level: high
+tags: [attack.t1105]
+logsource: {category: file_event, product: windows}
+detection:
+  selection:
+    TargetFilename|contains: '\Temp\'
+    TargetFilename|endswith: ['.exe', '.dll']
+  filter:
+    TargetFilename|startswith: 'C:\Windows\'
+  condition: selection and not filter
+---
+title: Process
+logsource: {category: process_creation}
+detection: {selection: {Image: x}, condition: selection}
+";
+
+    const NOTES: &str = r"title: Ransom notes
+logsource: {category: file_event}
+detection:
+  note_1: {TargetFilename|endswith: 'README.txt'}
+  note_2: {TargetFilename: '*\DECRYPT_*.html'}
+  condition: 1 of note_*
+";
+
+    const HASHES: &str = "title: Hash rule\nlogsource: {category: file_create}\ndetection:\n  selection: {Hashes: abc}\n  condition: selection\n";
+
+    #[test]
+    fn loads_file_event_rules_from_directory() {
+        let fixture = Fixture::new();
+        let first = fixture.write("a.yml", DROP.as_bytes());
+        fixture.write("b.yaml", HASHES.as_bytes());
+        fixture.write("notes.txt", b"not a rule");
+        let dir = Path::new(&first).parent().unwrap().to_path_buf();
+        std::fs::create_dir(dir.join("sub")).unwrap();
+        std::fs::write(dir.join("sub").join("c.yml"), NOTES).unwrap();
+
+        let import = load(&dir.to_string_lossy()).unwrap();
+        let loaded: Vec<_> = import.rules.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(loaded, ["Drop in temp", "Ransom notes"]);
+        assert_eq!(import.other_category, 1);
+        assert_eq!(import.skipped.len(), 1);
+        assert!(import.skipped[0].contains("b.yaml: Hash rule: selection: "), "{}", import.skipped[0]);
+
+        let drop = &import.rules[0];
+        assert_eq!(drop.severity, Severity::High);
+        assert_eq!(drop.tags, ["attack.t1105"]);
+        assert_eq!(drop.description.as_deref(), Some("sigma 5f1e7b52-0a3c-4c8e-9a4b-7d2f8e6c1a90 (high)"));
+        assert_eq!(import.rules[1].severity, Severity::Medium);
+
+        let set = RuleSet::new(import.rules);
+        assert_eq!(names(&set, "C:\\Users\\x\\AppData\\Local\\Temp\\drop.EXE"), ["Drop in temp"]);
+        assert!(names(&set, "C:\\Users\\x\\AppData\\Local\\Temp\\drop.txt").is_empty());
+        // Фильтр с буквой диска исключает и путь отчета без префикса
+        assert!(names(&set, "C:\\Windows\\Temp\\a.exe").is_empty());
+        assert!(names(&set, "\\Windows\\Temp\\a.exe").is_empty());
+        assert_eq!(names(&set, "C:\\data\\readme.txt"), ["Ransom notes"]);
+        assert_eq!(names(&set, "C:\\data\\DECRYPT_files.html"), ["Ransom notes"]);
+    }
+
+    #[test]
+    fn quantifiers_and_modifiers() {
+        let all = "detection:\n  a: {TargetFilename|contains|all: ['\\\\x\\\\', '.ps1']}\n  b: {TargetFilename|endswith: .bat}\n  condition: all of them\n";
+        let set = RuleSet::new(vec![NamedRule::new("r", Severity::Low, compile_yaml(all).unwrap())]);
+        assert!(names(&set, "C:\\x\\run.ps1").is_empty());
+
+        let any = all.replace("all of them", "any of them");
+        let set = RuleSet::new(vec![NamedRule::new("r", Severity::Low, compile_yaml(&any).unwrap())]);
+        assert_eq!(names(&set, "C:\\x\\run.ps1"), ["r"]);
+        assert_eq!(names(&set, "C:\\y\\run.bat"), ["r"]);
+        assert!(names(&set, "C:\\y\\run.ps1").is_empty());
+    }
+
+    #[test]
+    fn unsupported_detections_are_rejected() {
+        let cases = [
+            "detection:\n  s: {TargetFilename: a}\n",
+            "detection:\n  s: {TargetFilename: a}\n  timeframe: 5m\n  condition: s\n",
+            "detection:\n  s: {TargetFilename: a}\n  condition: s | count() > 5\n",
+            "detection:\n  s: [evil, bad]\n  condition: s\n",
+            "detection:\n  s: {TargetFilename|re: a}\n  condition: s\n",
+            "detection:\n  s: {TargetFilename: a}\n  condition: s and missing\n",
+            "detection:\n  s: {TargetFilename: a}\n  condition: (s\n",
+            "detection:\n  s: {TargetFilename: ''}\n  condition: s\n",
+        ];
+        for yaml in cases {
+            assert!(compile_yaml(yaml).is_err(), "{}", yaml);
+        }
+    }
+}
@@ -0,0 +1,464 @@
+//! Минимальное чтение базы SQLite 3 без внешних зависимостей: обход b-деревьев таблиц
+//! и разбор строк. Нужно только для загрузки хешсетов (NSRL RDS v3 распространяется в SQLite).
+//! Поддерживаются обычные (rowid) таблицы; журнал WAL не читается.
+
+use std::collections::HashSet;
+use std::io::{self, Read, Seek, SeekFrom};
+
+pub const MAGIC: &[u8; 16] = b"SQLite format 3\0";
+
+/// Размер заголовка базы в начале первой страницы.
+const DB_HEADER_LEN: usize = 100;
+const PAGE_TABLE_INTERIOR: u8 = 0x05;
+const PAGE_TABLE_LEAF: u8 = 0x0D;
+
+/// Значение столбца (целые и вещественные не нужны - только их наличие).
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Int(i64),
+    Real,
+    Text(String),
+    Blob(Vec<u8>),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum TextEncoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+}
+
+pub struct SqliteReader<R> {
+    reader: R,
+    page_size: usize,
+    /// Полезная часть страницы (без зарезервированного хвоста)
+    usable_size: usize,
+    page_count: u32,
+    encoding: TextEncoding,
+}
+
+fn invalid(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("SQLite: {}", msg.into()))
+}
+
+/// Varint SQLite: до 9 байт, старший бит - продолжение, девятый байт - все 8 бит.
+fn read_varint(buf: &[u8], pos: &mut usize) -> io::Result<u64> {
+    let mut value = 0u64;
+    for i in 0..9 {
+        let byte = *buf.get(*pos).ok_or_else(|| invalid("varint за пределами страницы"))?;
+        *pos += 1;
+        if i == 8 {
+            return Ok((value << 8) | byte as u64);
+        }
+        value = (value << 7) | (byte & 0x7F) as u64;
+        if byte & 0x80 == 0 {
+            break;
+        }
+    }
+    Ok(value)
+}
+
+fn be_int(bytes: &[u8]) -> i64 {
+    let unsigned = bytes.iter().fold(0u64, |acc, &b| (acc << 8) | b as u64);
+    let shift = 64 - 8 * bytes.len() as u32;
+    ((unsigned << shift) as i64) >> shift
+}
+
+impl<R: Read + Seek> SqliteReader<R> {
+    pub fn open(mut reader: R) -> io::Result<Self> {
+        let mut header = [0u8; DB_HEADER_LEN];
+        reader.seek(SeekFrom::Start(0))?;
+        reader.read_exact(&mut header).map_err(|e| match e.kind() {
+            io::ErrorKind::UnexpectedEof => invalid("обрезан заголовок базы"),
+            _ => e,
+        })?;
+        if &header[..16] != MAGIC {
+            return Err(invalid("нет сигнатуры базы"));
+        }
+        let page_size = match u16::from_be_bytes([header[16], header[17]]) {
+            1 => 65536,
+            n if n >= 512 && n.is_power_of_two() => n as usize,
+            n => return Err(invalid(format!("неверный размер страницы {}", n))),
+        };
+        let usable_size = page_size - header[20] as usize;
+        if usable_size < 480 {
+            return Err(invalid("слишком большой зарезервированный хвост страницы"));
+        }
+        let file_pages = reader.seek(SeekFrom::End(0))? / page_size as u64;
+        let page_count = u32::try_from(file_pages).map_err(|_| invalid("слишком большая база"))?;
+        let encoding = match u32::from_be_bytes([header[56], header[57], header[58], header[59]]) {
+            0 | 1 => TextEncoding::Utf8,
+            2 => TextEncoding::Utf16Le,
+            3 => TextEncoding::Utf16Be,
+            n => return Err(invalid(format!("неизвестная кодировка текста {}", n))),
+        };
+        Ok(Self { reader, page_size, usable_size, page_count, encoding })
+    }
+
+    fn read_page(&mut self, page: u32) -> io::Result<Vec<u8>> {
+        if page == 0 || page > self.page_count {
+            return Err(invalid(format!("ссылка на страницу {} вне файла", page)));
+        }
+        let mut buf = vec![0u8; self.page_size];
+        self.reader.seek(SeekFrom::Start((page as u64 - 1) * self.page_size as u64))?;
+        self.reader.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+
+    /// Корневые страницы всех таблиц схемы (`sqlite_schema`, страница 1): имя и номер.
+    pub fn tables(&mut self) -> io::Result<Vec<(String, u32)>> {
+        let mut tables = Vec::new();
+        self.scan_table(1, &mut |row| {
+            if let [Value::Text(kind), Value::Text(name), _, Value::Int(root), ..] = row {
+                if kind == "table" && *root > 0 {
+                    tables.push((name.clone(), *root as u32));
+                }
+            }
+        })?;
+        Ok(tables)
+    }
+
+    /// Обходит b-дерево таблицы с корнем `root` и отдает каждую строку.
+    pub fn scan_table(&mut self, root: u32, on_row: &mut dyn FnMut(&[Value])) -> io::Result<()> {
+        let mut stack = vec![root];
+        let mut visited = HashSet::new();
+        while let Some(page_no) = stack.pop() {
+            // Цикл ссылок в поврежденной базе
+            if !visited.insert(page_no) {
+                return Err(invalid(format!("цикл в b-дереве на странице {}", page_no)));
+            }
+            let page = self.read_page(page_no)?;
+            let start = if page_no == 1 { DB_HEADER_LEN } else { 0 };
+            let kind = page[start];
+            let cell_count = u16::from_be_bytes([page[start + 3], page[start + 4]]) as usize;
+            let header_len = match kind {
+                PAGE_TABLE_INTERIOR => 12,
+                PAGE_TABLE_LEAF => 8,
+                other => return Err(invalid(format!("страница {}: тип {:#04x} не таблица", page_no, other))),
+            };
+            let pointers = page.get(start + header_len..start + header_len + cell_count * 2)
+                .ok_or_else(|| invalid(format!("страница {}: массив ячеек за пределами", page_no)))?;
+            let cells: Vec<usize> = pointers.chunks_exact(2).map(|p| u16::from_be_bytes([p[0], p[1]]) as usize).collect();
+
+            if kind == PAGE_TABLE_INTERIOR {
+                // Правый потомок - последним, чтобы строки шли по возрастанию rowid
+                stack.push(u32::from_be_bytes([page[start + 8], page[start + 9], page[start + 10], page[start + 11]]));
+                for &cell in cells.iter().rev() {
+                    let child = page.get(cell..cell + 4).ok_or_else(|| invalid(format!("страница {}: ячейка за пределами", page_no)))?;
+                    stack.push(u32::from_be_bytes([child[0], child[1], child[2], child[3]]));
+                }
+                continue;
+            }
+            for cell in cells {
+                let payload = self.cell_payload(&page, cell)?;
+                on_row(&self.decode_record(&payload)?);
+            }
+        }
+        Ok(())
+    }
+
+    /// Содержимое ячейки листа таблицы с дочитыванием страниц переполнения.
+    fn cell_payload(&mut self, page: &[u8], cell: usize) -> io::Result<Vec<u8>> {
+        let mut pos = cell;
+        let total = read_varint(page, &mut pos)? as usize;
+        read_varint(page, &mut pos)?; // rowid
+        let max_local = self.usable_size - 35;
+        let local = if total <= max_local {
+            total
+        } else {
+            let min_local = (self.usable_size - 12) * 32 / 255 - 23;
+            let k = min_local + (total - min_local) % (self.usable_size - 4);
+            if k <= max_local { k } else { min_local }
+        };
+        let mut payload = page.get(pos..pos + local).ok_or_else(|| invalid("ячейка за пределами страницы"))?.to_vec();
+        if local == total {
+            return Ok(payload);
+        }
+        let next = page.get(pos + local..pos + local + 4).ok_or_else(|| invalid("ячейка за пределами страницы"))?;
+        let mut overflow = u32::from_be_bytes([next[0], next[1], next[2], next[3]]);
+        let mut visited = HashSet::new();
+        while payload.len() < total {
+            if overflow == 0 || !visited.insert(overflow) {
+                return Err(invalid("оборвана цепочка страниц переполнения"));
+            }
+            let page = self.read_page(overflow)?;
+            let take = (total - payload.len()).min(self.usable_size - 4);
+            payload.extend_from_slice(&page[4..4 + take]);
+            overflow = u32::from_be_bytes([page[0], page[1], page[2], page[3]]);
+        }
+        Ok(payload)
+    }
+
+    fn decode_record(&self, payload: &[u8]) -> io::Result<Vec<Value>> {
+        let mut pos = 0;
+        let header_len = read_varint(payload, &mut pos)? as usize;
+        let mut body = header_len;
+        let mut values = Vec::new();
+        while pos < header_len {
+            let serial = read_varint(payload, &mut pos)?;
+            let len = match serial {
+                0 | 8 | 9 => 0,
+                1..=4 => serial as usize,
+                5 => 6,
+                6 | 7 => 8,
+                10 | 11 => return Err(invalid(format!("зарезервированный тип столбца {}", serial))),
+                n => ((n - 12) / 2) as usize,
+            };
+            let data = payload.get(body..body + len).ok_or_else(|| invalid("строка короче заголовка"))?;
+            body += len;
+            values.push(match serial {
+                0 => Value::Null,
+                1..=6 => Value::Int(be_int(data)),
+                7 => Value::Real,
+                8 => Value::Int(0),
+                9 => Value::Int(1),
+                n if n % 2 == 0 => Value::Blob(data.to_vec()),
+                _ => Value::Text(self.decode_text(data)),
+            });
+        }
+        Ok(values)
+    }
+
+    fn decode_text(&self, data: &[u8]) -> String {
+        let units = |from: fn([u8; 2]) -> u16| data.chunks_exact(2).map(|c| from([c[0], c[1]])).collect::<Vec<_>>();
+        match self.encoding {
+            TextEncoding::Utf8 => String::from_utf8_lossy(data).into_owned(),
+            TextEncoding::Utf16Le => String::from_utf16_lossy(&units(u16::from_le_bytes)),
+            TextEncoding::Utf16Be => String::from_utf16_lossy(&units(u16::from_be_bytes)),
+        }
+    }
+}
+
+/// Сборщик маленьких баз SQLite для тестов: страницы по 512 байт, строки длиннее
+/// страницы уходят в цепочку переполнения так же, как их раскладывает SQLite.
+#[cfg(test)]
+pub(crate) struct TestDb {
+    encoding: u32,
+    /// Страница 1 (схема) собирается в `finish`, здесь - ее место
+    pages: Vec<Vec<u8>>,
+}
+
+#[cfg(test)]
+impl TestDb {
+    const PAGE: usize = 512;
+
+    pub fn new(encoding: u32) -> Self {
+        Self { encoding, pages: vec![Vec::new()] }
+    }
+
+    /// Номер страницы, которую получит следующий `leaf`/`interior`.
+    pub fn next_page(&self) -> u32 {
+        self.pages.len() as u32 + 1
+    }
+
+    fn varint(mut value: u64) -> Vec<u8> {
+        let mut out = vec![(value & 0x7F) as u8];
+        value >>= 7;
+        while value > 0 {
+            out.insert(0, (value & 0x7F) as u8 | 0x80);
+            value >>= 7;
+        }
+        out
+    }
+
+    fn record(&self, values: &[Value]) -> Vec<u8> {
+        let mut header = Vec::new();
+        let mut body = Vec::new();
+        for value in values {
+            let serial = match value {
+                Value::Null => 0,
+                Value::Int(n) if i8::try_from(*n).is_ok() => {
+                    body.push(*n as u8);
+                    1
+                }
+                Value::Int(n) => {
+                    body.extend(n.to_be_bytes());
+                    6
+                }
+                Value::Real => {
+                    body.extend(0f64.to_be_bytes());
+                    7
+                }
+                Value::Text(text) => {
+                    let bytes: Vec<u8> = match self.encoding {
+                        2 => text.encode_utf16().flat_map(u16::to_le_bytes).collect(),
+                        3 => text.encode_utf16().flat_map(u16::to_be_bytes).collect(),
+                        _ => text.as_bytes().to_vec(),
+                    };
+                    body.extend(&bytes);
+                    13 + 2 * bytes.len() as u64
+                }
+                Value::Blob(bytes) => {
+                    body.extend(bytes);
+                    12 + 2 * bytes.len() as u64
+                }
+            };
+            header.extend(Self::varint(serial));
+        }
+        let mut record = Self::varint(header.len() as u64 + 1);
+        record.extend(header);
+        record.extend(body);
+        record
+    }
+
+    /// Ячейка листа; хвост, не поместившийся в страницу, сразу пишется в страницы переполнения.
+    pub fn row(&mut self, rowid: i64, values: &[Value]) -> Vec<u8> {
+        let payload = self.record(values);
+        let usable = Self::PAGE;
+        let total = payload.len();
+        let max_local = usable - 35;
+        let local = if total <= max_local {
+            total
+        } else {
+            let min_local = (usable - 12) * 32 / 255 - 23;
+            let k = min_local + (total - min_local) % (usable - 4);
+            if k <= max_local { k } else { min_local }
+        };
+        let mut cell = Self::varint(total as u64);
+        cell.extend(Self::varint(rowid as u64));
+        cell.extend(&payload[..local]);
+        if local < total {
+            cell.extend(self.next_page().to_be_bytes());
+            let chunks: Vec<&[u8]> = payload[local..].chunks(usable - 4).collect();
+            for (i, chunk) in chunks.iter().enumerate() {
+                let next = if i + 1 < chunks.len() { self.next_page() + 1 } else { 0 };
+                let mut page = next.to_be_bytes().to_vec();
+                page.extend(*chunk);
+                page.resize(Self::PAGE, 0);
+                self.pages.push(page);
+            }
+        }
+        cell
+    }
+
+    fn page(page_no: u32, kind: u8, cells: &[Vec<u8>], right: Option<u32>) -> Vec<u8> {
+        let mut page = vec![0u8; Self::PAGE];
+        let start = if page_no == 1 { DB_HEADER_LEN } else { 0 };
+        let header_len = if right.is_some() { 12 } else { 8 };
+        page[start] = kind;
+        page[start + 3..start + 5].copy_from_slice(&(cells.len() as u16).to_be_bytes());
+        if let Some(right) = right {
+            page[start + 8..start + 12].copy_from_slice(&right.to_be_bytes());
+        }
+        let mut end = Self::PAGE;
+        for (i, cell) in cells.iter().enumerate() {
+            end -= cell.len();
+            page[end..end + cell.len()].copy_from_slice(cell);
+            let pointer = start + header_len + i * 2;
+            page[pointer..pointer + 2].copy_from_slice(&(end as u16).to_be_bytes());
+        }
+        page[start + 5..start + 7].copy_from_slice(&(end as u16).to_be_bytes());
+        page
+    }
+
+    pub fn leaf(&mut self, cells: &[Vec<u8>]) -> u32 {
+        let page_no = self.next_page();
+        self.pages.push(Self::page(page_no, PAGE_TABLE_LEAF, cells, None));
+        page_no
+    }
+
+    /// Внутренний узел: потомки с наибольшим rowid в каждом и правый потомок.
+    pub fn interior(&mut self, children: &[(u32, i64)], right: u32) -> u32 {
+        let cells: Vec<Vec<u8>> = children.iter().map(|&(child, key)| {
+            let mut cell = child.to_be_bytes().to_vec();
+            cell.extend(Self::varint(key as u64));
+            cell
+        }).collect();
+        let page_no = self.next_page();
+        self.pages.push(Self::page(page_no, PAGE_TABLE_INTERIOR, &cells, Some(right)));
+        page_no
+    }
+
+    /// Записывает схему (имя таблицы и корневая страница) и возвращает файл базы.
+    pub fn finish(mut self, tables: &[(&str, u32)]) -> Vec<u8> {
+        let schema: Vec<Vec<u8>> = tables.iter().enumerate().map(|(i, &(name, root))| {
+            let sql = format!("CREATE TABLE {}(value)", name);
+            self.row(i as i64 + 1, &[
+                Value::Text("table".into()), Value::Text(name.into()), Value::Text(name.into()),
+                Value::Int(root as i64), Value::Text(sql),
+            ])
+        }).collect();
+        let mut first = Self::page(1, PAGE_TABLE_LEAF, &schema, None);
+        first[..16].copy_from_slice(MAGIC);
+        first[16..18].copy_from_slice(&(Self::PAGE as u16).to_be_bytes());
+        // Версии формата, доли полезной нагрузки, счетчик изменений и формат схемы - как у sqlite3
+        first[18..24].copy_from_slice(&[1, 1, 0, 64, 32, 32]);
+        first[24..28].copy_from_slice(&1u32.to_be_bytes());
+        first[28..32].copy_from_slice(&(self.pages.len() as u32).to_be_bytes());
+        first[44..48].copy_from_slice(&4u32.to_be_bytes());
+        first[56..60].copy_from_slice(&self.encoding.to_be_bytes());
+        first[92..96].copy_from_slice(&1u32.to_be_bytes());
+        self.pages[0] = first;
+        self.pages.concat()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn rows(bytes: Vec<u8>, root: u32) -> io::Result<Vec<Vec<Value>>> {
+        let mut reader = SqliteReader::open(Cursor::new(bytes))?;
+        let mut rows = Vec::new();
+        reader.scan_table(root, &mut |row| rows.push(row.to_vec()))?;
+        Ok(rows)
+    }
+
+    #[test]
+    fn reads_rows_through_interior_and_overflow_pages() {
+        let long = Value::Blob((0..1500u32).map(|i| i as u8).collect());
+        let mut db = TestDb::new(1);
+        let first = [
+            db.row(1, &[Value::Null, Value::Int(-2), Value::Text("a".into())]),
+            db.row(2, &[long.clone(), Value::Real]),
+        ];
+        let left = db.leaf(&first);
+        let second = [db.row(3, &[Value::Int(300), Value::Blob(vec![1, 2])])];
+        let right = db.leaf(&second);
+        let root = db.interior(&[(left, 2)], right);
+        let bytes = db.finish(&[("hashes", root)]);
+
+        let mut reader = SqliteReader::open(Cursor::new(bytes.clone())).unwrap();
+        assert_eq!(reader.tables().unwrap(), vec![("hashes".to_string(), root)]);
+        assert_eq!(rows(bytes, root).unwrap(), vec![
+            vec![Value::Null, Value::Int(-2), Value::Text("a".into())],
+            vec![long, Value::Real],
+            vec![Value::Int(300), Value::Blob(vec![1, 2])],
+        ]);
+    }
+
+    #[test]
+    fn decodes_utf16_text() {
+        for encoding in [2, 3] {
+            let mut db = TestDb::new(encoding);
+            let cells = [db.row(1, &[Value::Text("Привет".into())])];
+            let root = db.leaf(&cells);
+            let bytes = db.finish(&[("t", root)]);
+
+            let mut reader = SqliteReader::open(Cursor::new(bytes.clone())).unwrap();
+            assert_eq!(reader.tables().unwrap(), vec![("t".to_string(), root)]);
+            assert_eq!(rows(bytes, root).unwrap(), vec![vec![Value::Text("Привет".into())]]);
+        }
+    }
+
+    #[test]
+    fn rejects_damaged_databases() {
+        let kind = |result: io::Result<Vec<Vec<Value>>>| result.err().map(|e| e.kind());
+        assert_eq!(kind(rows(vec![0; 1024], 1)), Some(io::ErrorKind::InvalidData));
+        assert_eq!(kind(rows(MAGIC.to_vec(), 1)), Some(io::ErrorKind::InvalidData));
+
+        let mut db = TestDb::new(1);
+        let looped = db.next_page();
+        db.interior(&[], looped);
+        let bytes = db.finish(&[("t", looped)]);
+        assert_eq!(kind(rows(bytes.clone(), looped)), Some(io::ErrorKind::InvalidData));
+        assert_eq!(kind(rows(bytes.clone(), 99)), Some(io::ErrorKind::InvalidData));
+
+        let mut bad_page_size = bytes;
+        bad_page_size[16..18].copy_from_slice(&1000u16.to_be_bytes());
+        assert_eq!(kind(rows(bad_page_size, 1)), Some(io::ErrorKind::InvalidData));
+    }
+}
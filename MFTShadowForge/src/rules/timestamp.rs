@@ -52,4 +52,14 @@ impl TimestampData {
             false
         }
     }
+}
+
+/// Допуск расхождения меток DOS- и Win32-имени одной записи, секунд.
+const SHORT_NAME_TOLERANCE_SECS: i64 = 2;
+
+/// Метки $FILE_NAME DOS-имени 8.3 (C, M, E, A) расходятся с метками Win32-имени той же записи
+/// больше допуска: NTFS обновляет оба атрибута вместе, а разные метки оставляют
+/// туннелирование имен и часть timestomp-утилит, правящих только одно имя.
+pub fn short_name_diverges(dos: &[DateTime<Utc>; 4], win32: &[DateTime<Utc>; 4]) -> bool {
+    dos.iter().zip(win32).any(|(d, w)| (*d - *w).num_seconds().abs() > SHORT_NAME_TOLERANCE_SECS)
 }
\ No newline at end of file